@@ -0,0 +1,123 @@
+#![cfg(feature = "std")]
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use lencode::prelude::*;
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use std::hint::black_box;
+
+/// Number of values in each benchmarked list.
+const LEN: usize = 4096;
+
+/// Means of the geometric distributions the lists are drawn from; Rice parameters 1, 4 and
+/// 7 roughly match each in turn.
+const MEANS: [(&str, f64); 3] = [("mean_2", 2.0), ("mean_16", 16.0), ("mean_200", 200.0)];
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Draws `LEN` values from a geometric distribution with the given mean, the shape of run
+/// lengths and of gaps between sorted IDs.
+fn geometric(rng: &mut StdRng, mean: f64) -> Vec<u64> {
+    (0..LEN)
+        .map(|_| (-(1.0 - rng.random::<f64>()).ln() * mean) as u64)
+        .collect()
+}
+
+fn encoded<T: Encode>(value: &T) -> Vec<u8> {
+    let mut buf = Vec::new();
+    value.encode(&mut buf).unwrap();
+    buf
+}
+
+/// Runs `$body` once per list layout, with `$value` bound to the wrapped list and `$ty` to
+/// its type.
+macro_rules! for_each_layout {
+    ($values:expr, |$name:ident, $value:ident: $ty:ident| $body:block) => {{
+        {
+            type $ty = Vec<u64>;
+            let ($name, $value) = ("varint", $values.clone());
+            $body
+        }
+        {
+            type $ty = RiceVec<1>;
+            let ($name, $value) = ("rice_k1", RiceVec::<1>($values.clone()));
+            $body
+        }
+        {
+            type $ty = RiceVec<4>;
+            let ($name, $value) = ("rice_k4", RiceVec::<4>($values.clone()));
+            $body
+        }
+        {
+            type $ty = RiceVec<7>;
+            let ($name, $value) = ("rice_k7", RiceVec::<7>($values.clone()));
+            $body
+        }
+        {
+            type $ty = GammaVec;
+            let ($name, $value) = ("gamma", GammaVec($values.clone()));
+            $body
+        }
+    }};
+}
+
+// ---------------------------------------------------------------------------
+// Size report — varints vs Golomb-Rice vs Elias-gamma
+// ---------------------------------------------------------------------------
+
+fn report_sizes(_c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0xB175);
+
+    println!();
+    println!("=== Encoded size of {LEN} geometric values (bytes) ===");
+    println!(
+        "{:<10} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "dist", "varint", "rice_k1", "rice_k4", "rice_k7", "gamma"
+    );
+    for (dist, mean) in MEANS {
+        let values = geometric(&mut rng, mean);
+        let mut row = format!("{dist:<10}");
+        for_each_layout!(values, |_name, value: T| {
+            row += &format!(" {:>8}", encoded::<T>(&value).len());
+        });
+        println!("{row}");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Throughput benchmarks
+// ---------------------------------------------------------------------------
+
+fn bench_encode(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0xB175);
+    let mut group = c.benchmark_group("bits_encode");
+    for (dist, mean) in MEANS {
+        let values = geometric(&mut rng, mean);
+        for_each_layout!(values, |name, value: T| {
+            group.bench_function(BenchmarkId::new(dist, name), |b| {
+                b.iter(|| black_box(encoded(black_box(&value))))
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0xB175);
+    let mut group = c.benchmark_group("bits_decode");
+    for (dist, mean) in MEANS {
+        let values = geometric(&mut rng, mean);
+        for_each_layout!(values, |name, value: T| {
+            let buf = encoded(&value);
+            group.bench_function(BenchmarkId::new(dist, name), |b| {
+                b.iter(|| black_box(decode::<T>(&mut Cursor::new(black_box(&buf))).unwrap()))
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, report_sizes, bench_encode, bench_decode);
+criterion_main!(benches);
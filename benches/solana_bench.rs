@@ -579,6 +579,7 @@ fn bench_pubkey_vec_dupes(c: &mut Criterion) {
                     EncoderContext {
                         dedupe: Some(DedupeEncoder::with_capacity(capacity, 1)),
                         diff: None,
+                        ..EncoderContext::new()
                     },
                 )
             },
@@ -626,6 +627,7 @@ fn bench_pubkey_vec_dupes(c: &mut Criterion) {
         let mut encoder = EncoderContext {
             dedupe: Some(DedupeEncoder::with_capacity(capacity, 1)),
             diff: None,
+            ..EncoderContext::new()
         };
         encode_lencode_dedupe(&pubkeys, &mut encoder)
     };
@@ -647,6 +649,7 @@ fn bench_pubkey_vec_dupes(c: &mut Criterion) {
             1,
         )),
         diff: None,
+        ..EncoderContext::new()
     };
     for _ in 0..size_batch_count {
         let batch = make_pubkeys_with_hotset_from(&mut size_rng, count, &size_hotset, hotset_pct);
@@ -676,6 +679,7 @@ fn bench_pubkey_vec_dupes(c: &mut Criterion) {
             || DecoderContext {
                 dedupe: Some(DedupeDecoder::with_capacity(capacity)),
                 diff: None,
+                ..DecoderContext::new()
             },
             |mut decoder| {
                 black_box(decode_lencode_dedupe::<Vec<BenchPubkey>>(
@@ -0,0 +1,28 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "ffi")]
+    ffi_header();
+}
+
+/// Writes the C header for the `ffi` module to `$LENCODE_FFI_HEADER`, or to
+/// `$OUT_DIR/lencode.h` when that is unset.
+#[cfg(feature = "ffi")]
+fn ffi_header() {
+    use std::env;
+    use std::path::PathBuf;
+
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=LENCODE_FFI_HEADER");
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let header = env::var_os("LENCODE_FFI_HEADER")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env::var("OUT_DIR").unwrap()).join("lencode.h"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("failed to generate the lencode C header")
+        .write_to_file(header);
+}
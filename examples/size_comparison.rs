@@ -37,6 +37,7 @@ fn main() {
     let mut ctx = EncoderContext {
         dedupe: Some(DedupeEncoder::with_capacity(1000, 1)),
         diff: None,
+        ..EncoderContext::new()
     };
     let mut cursor = Cursor::new(Vec::new());
     all_pubkeys.encode_ext(&mut cursor, Some(&mut ctx)).unwrap();
@@ -59,6 +60,7 @@ fn main() {
     let mut dec_ctx = DecoderContext {
         dedupe: Some(DedupeDecoder::with_capacity(1000)),
         diff: None,
+        ..DecoderContext::new()
     };
     let mut cursor = Cursor::new(&lencode_data);
     let decoded: Vec<Pubkey> = Vec::decode_ext(&mut cursor, Some(&mut dec_ctx)).unwrap();
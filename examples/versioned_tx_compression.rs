@@ -125,6 +125,7 @@ fn main() {
     let mut enc = EncoderContext {
         dedupe: Some(DedupeEncoder::with_capacity(4096, 8)),
         diff: None,
+        ..EncoderContext::new()
     };
     let t1 = Instant::now();
     vtxs.encode_ext(&mut lencode_buf, Some(&mut enc)).unwrap();
@@ -155,6 +156,7 @@ fn main() {
     let mut dec = DecoderContext {
         dedupe: Some(DedupeDecoder::with_capacity(4096)),
         diff: None,
+        ..DecoderContext::new()
     };
     let decoded: Vec<VersionedTransaction> =
         Vec::decode_ext(&mut Cursor::new(&lencode_buf), Some(&mut dec)).unwrap();
@@ -7,6 +7,14 @@
 //!   declaration order. For `#[repr(transparent)]` single‑field structs, it additionally
 //!   generates bulk `pack_slice`/`unpack_vec` overrides that transmute to/from the inner
 //!   type's slice/vec, enabling zero‑copy bulk I/O for newtypes over byte arrays.
+//! - `#[derive(Delta)]` implements `lencode::delta::Delta`, encoding only the fields that
+//!   changed between two values.
+//! - `#[derive(Columns)]` implements `lencode::columnar::Columns`, so `Columnar<Vec<T>>`
+//!   encodes each field as its own column.
+//! - `#[derive(Project)]` implements `lencode::projection::Project`, decoding only selected
+//!   fields into an `Option`‑wrapped projection struct.
+//! - `#[derive(DescribeLayout)]` implements `lencode::layout::DescribeLayout`, returning a
+//!   runtime descriptor of the layout produced by `#[derive(Encode)]`.
 //!
 //! For C‑like enums with an explicit `#[repr(uN/iN)]`, the numeric value of the discriminant
 //! is preserved; otherwise, the variant index is used.
@@ -57,6 +65,41 @@ fn enum_repr_ty(attrs: &[Attribute]) -> Option<Type> {
     out
 }
 
+/// Rejects C‑like enums with a 128‑bit `#[repr]`: their numeric discriminants are encoded as
+/// 64‑bit varints, so wider values could not roundtrip.
+fn check_repr_width(derive_input: &DeriveInput) -> Result<()> {
+    let syn::Data::Enum(data_enum) = &derive_input.data else {
+        return Ok(());
+    };
+    if !data_enum
+        .variants
+        .iter()
+        .all(|v| matches!(v.fields, syn::Fields::Unit))
+    {
+        return Ok(());
+    }
+    for attr in &derive_input.attrs {
+        if !attr.path().is_ident("repr") {
+            continue;
+        }
+        let mut wide = None;
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("u128") || meta.path.is_ident("i128") {
+                wide = meta.path.get_ident().cloned();
+            }
+            Ok(())
+        });
+        if let Some(wide) = wide {
+            return Err(syn::Error::new_spanned(
+                wide,
+                "lencode encodes enum discriminants as 64-bit varints; \
+                 #[repr(u128)] and #[repr(i128)] enums are not supported",
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn crate_path() -> TokenStream2 {
     // Resolve the path to the main `lencode` crate from the macro crate, honoring any
     // potential crate renames by the downstream user. In ambiguous contexts like doctests,
@@ -72,12 +115,521 @@ fn crate_path() -> TokenStream2 {
     }
 }
 
+/// Parses `#[lencode(len = "...")]` on a field into the path of its `LenStrategy`.
+///
+/// Built‑in names map to the strategies in `lencode::length`; any other value is parsed as a
+/// type path.
+fn field_len_strategy(attrs: &[Attribute], krate: &TokenStream2) -> Result<Option<TokenStream2>> {
+    let mut strategy = None;
+    for attr in attrs {
+        if !attr.path().is_ident("lencode") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("len") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                strategy = Some(match lit.value().as_str() {
+                    "varint" => quote!(#krate::length::VarintLen),
+                    "u8_fixed" => quote!(#krate::length::U8FixedLen),
+                    "u16_fixed" => quote!(#krate::length::U16FixedLen),
+                    "u32_fixed" => quote!(#krate::length::U32FixedLen),
+                    "u64_fixed" => quote!(#krate::length::U64FixedLen),
+                    "short_vec" => quote!(#krate::length::ShortVecLen),
+                    _ => {
+                        let path: syn::Path = lit.parse()?;
+                        quote!(#path)
+                    }
+                });
+                Ok(())
+            } else if meta.path.is_ident("codec") || meta.path.is_ident("with") {
+                let _: syn::LitStr = meta.value()?.parse()?;
+                Ok(())
+            } else if meta.path.is_ident("length_prefixed") {
+                Ok(())
+            } else if meta.path.is_ident("rename") {
+                let _: syn::LitStr = meta.value()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported lencode attribute"))
+            }
+        })?;
+    }
+    Ok(strategy)
+}
+
+/// Parses `#[lencode(codec = "name")]` on a field: the name of the `Codec` to look up in the
+/// context's `CodecRegistry`.
+fn field_codec(attrs: &[Attribute]) -> Result<Option<syn::LitStr>> {
+    let mut codec = None;
+    let mut len = None;
+    for attr in attrs {
+        if !attr.path().is_ident("lencode") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("codec") {
+                codec = Some(meta.value()?.parse::<syn::LitStr>()?);
+            } else if !meta.path.is_ident("length_prefixed") {
+                if meta.path.is_ident("len") {
+                    len = Some(meta.path.clone());
+                }
+                let _: syn::LitStr = meta.value()?.parse()?;
+            }
+            Ok(())
+        })?;
+    }
+    match (&codec, len) {
+        (Some(_), Some(len)) => Err(syn::Error::new_spanned(
+            len,
+            "`len` and `codec` cannot be combined; the codec decides the layout",
+        )),
+        _ => Ok(codec),
+    }
+}
+
+/// Parses `#[lencode(with = "module")]` on a field: the path of a module providing
+/// `encode_ext`/`decode_ext` functions used in place of the field type's own impls.
+fn field_with(attrs: &[Attribute]) -> Result<Option<syn::Path>> {
+    let mut with = None;
+    let mut conflict = None;
+    for attr in attrs {
+        if !attr.path().is_ident("lencode") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("with") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                with = Some(lit.parse::<syn::Path>()?);
+            } else if !meta.path.is_ident("length_prefixed") {
+                if meta.path.is_ident("len") || meta.path.is_ident("codec") {
+                    conflict = Some(meta.path.clone());
+                }
+                let _: syn::LitStr = meta.value()?.parse()?;
+            }
+            Ok(())
+        })?;
+    }
+    match (&with, conflict) {
+        (Some(_), Some(conflict)) => Err(syn::Error::new_spanned(
+            &conflict,
+            format!(
+                "`{}` and `with` cannot be combined; the `with` module decides the layout",
+                conflict.get_ident().unwrap()
+            ),
+        )),
+        _ => Ok(with),
+    }
+}
+
+/// Returns `true` if the field is marked `#[lencode(length_prefixed)]`: its encoding is
+/// written as a size‑prefixed section, like `lencode::length::LengthPrefixed`.
+fn field_length_prefixed(attrs: &[Attribute]) -> Result<bool> {
+    let mut prefixed = false;
+    for attr in attrs {
+        if !attr.path().is_ident("lencode") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("length_prefixed") {
+                prefixed = true;
+            } else {
+                let _: syn::LitStr = meta.value()?.parse()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(prefixed)
+}
+
+/// Parses `#[lencode(rename = "Name")]` on a container, variant or field: the name recorded
+/// in its `DescribeLayout` layout instead of the Rust name. The wire format is unaffected.
+fn lencode_rename(attrs: &[Attribute]) -> Result<Option<syn::LitStr>> {
+    let mut rename = None;
+    for attr in attrs {
+        if !attr.path().is_ident("lencode") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                rename = Some(meta.value()?.parse()?);
+            } else if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(rename)
+}
+
+/// Checks the container attribute `#[lencode(index_stable = "A, B, ...")]`.
+///
+/// Enums without a numeric `repr` are encoded by variant position, so the listed names pin
+/// the order already in use: compilation fails unless they are a prefix of the declared
+/// variants. Appending new variants is still allowed.
+fn check_index_stable(derive_input: &DeriveInput) -> Result<()> {
+    let mut frozen: Option<syn::LitStr> = None;
+    for attr in &derive_input.attrs {
+        if !attr.path().is_ident("lencode") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("index_stable") {
+                frozen = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("fuzz") {
+                Ok(())
+            } else if meta.path.is_ident("rename") {
+                let _: syn::LitStr = meta.value()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported lencode attribute"))
+            }
+        })?;
+    }
+    let Some(frozen) = frozen else {
+        return Ok(());
+    };
+    let syn::Data::Enum(data_enum) = &derive_input.data else {
+        return Err(syn::Error::new_spanned(
+            frozen,
+            "index_stable only applies to enums",
+        ));
+    };
+    let is_c_like = data_enum
+        .variants
+        .iter()
+        .all(|v| matches!(v.fields, syn::Fields::Unit));
+    if is_c_like && enum_repr_ty(&derive_input.attrs).is_some() {
+        return Err(syn::Error::new_spanned(
+            frozen,
+            "index_stable has no effect: this enum encodes its numeric repr discriminants",
+        ));
+    }
+    let expected: Vec<String> = frozen
+        .value()
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    let actual: Vec<String> = data_enum
+        .variants
+        .iter()
+        .map(|v| v.ident.to_string())
+        .collect();
+    for (idx, name) in expected.iter().enumerate() {
+        match actual.get(idx) {
+            Some(found) if found == name => {}
+            Some(found) => {
+                return Err(syn::Error::new_spanned(
+                    &frozen,
+                    format!(
+                        "variant {idx} must be `{name}` to keep encoded indices stable, found `{found}`; add new variants at the end"
+                    ),
+                ));
+            }
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &frozen,
+                    format!("variant `{name}` is listed by index_stable but missing"),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses `#[lencode(discriminant = N)]` on an enum variant.
+fn variant_discriminant_attr(attrs: &[Attribute]) -> Result<Option<syn::LitInt>> {
+    let mut disc = None;
+    for attr in attrs {
+        if !attr.path().is_ident("lencode") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("discriminant") {
+                disc = Some(meta.value()?.parse()?);
+            } else if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(disc)
+}
+
+/// Returns the wire discriminant of each variant of an enum encoded by variant index, or an
+/// empty list for other items.
+///
+/// A variant marked `#[lencode(discriminant = N)]` is written as `N`; any other variant takes
+/// the previous variant's discriminant plus one, starting from zero, so without the attribute
+/// this is the declaration index. Discriminants must be unique.
+fn variant_discriminants(derive_input: &DeriveInput) -> Result<Vec<u64>> {
+    let syn::Data::Enum(data_enum) = &derive_input.data else {
+        return Ok(Vec::new());
+    };
+    let is_c_like = data_enum
+        .variants
+        .iter()
+        .all(|v| matches!(v.fields, syn::Fields::Unit));
+    let numeric_repr = is_c_like && enum_repr_ty(&derive_input.attrs).is_some();
+    let mut discs: Vec<u64> = Vec::new();
+    let mut next = Some(0u64);
+    for v in &data_enum.variants {
+        let disc = match variant_discriminant_attr(&v.attrs)? {
+            Some(lit) if numeric_repr => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "discriminant has no effect: this enum encodes its numeric repr discriminants",
+                ));
+            }
+            Some(lit) => lit
+                .base10_parse::<u64>()
+                .map_err(|_| syn::Error::new_spanned(&lit, "discriminant must fit in a u64"))?,
+            None => next.ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &v.ident,
+                    "discriminant overflows u64; set `#[lencode(discriminant = N)]` explicitly",
+                )
+            })?,
+        };
+        if let Some(other) = discs.iter().position(|&d| d == disc) {
+            return Err(syn::Error::new_spanned(
+                &v.ident,
+                format!(
+                    "discriminant {disc} of `{}` is already used by `{}`",
+                    v.ident, data_enum.variants[other].ident
+                ),
+            ));
+        }
+        discs.push(disc);
+        next = disc.checked_add(1);
+    }
+    Ok(discs)
+}
+
+/// Emits the fuzz harness and roundtrip test for the container attribute
+/// `#[lencode(fuzz)]`, or nothing if it is absent.
+fn fuzz_harness(derive_input: &DeriveInput, krate: &TokenStream2) -> Result<TokenStream2> {
+    let mut fuzz: Option<syn::Path> = None;
+    for attr in &derive_input.attrs {
+        if !attr.path().is_ident("lencode") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("fuzz") {
+                fuzz = Some(meta.path.clone());
+            } else if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        })?;
+    }
+    let Some(fuzz) = fuzz else {
+        return Ok(quote! {});
+    };
+    if !derive_input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            fuzz,
+            "`fuzz` needs a concrete type; annotate a type without generic parameters",
+        ));
+    }
+    let name = &derive_input.ident;
+    // The type name is kept as written so names differing only in case get distinct tests.
+    let test_name = Ident::new(
+        &format!("lencode_fuzz_{}", syn::ext::IdentExt::unraw(name)),
+        name.span(),
+    );
+    Ok(quote! {
+        #[cfg(fuzzing)]
+        impl #name {
+            /// Fuzz target generated by `#[lencode(fuzz)]`; see `lencode::fuzz::fuzz_decode`.
+            pub fn lencode_fuzz(data: &[u8]) {
+                #krate::fuzz::fuzz_decode::<Self>(data);
+            }
+        }
+
+        #[cfg(test)]
+        #[test]
+        #[allow(non_snake_case)]
+        fn #test_name() {
+            #krate::fuzz::fuzz_random_inputs::<#name>(#krate::fuzz::DEFAULT_FUZZ_CASES);
+        }
+    })
+}
+
+/// Returns the element type of `ty` if it is an array that should be expanded inline.
+///
+/// Byte arrays keep the `[u8; N]` impl so they stay on its bulk and diff paths.
+fn inline_array_elem(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Array(array) => match &*array.elem {
+            Type::Path(path) if path.qself.is_none() && path.path.is_ident("u8") => None,
+            elem => Some(elem),
+        },
+        Type::Paren(paren) => inline_array_elem(&paren.elem),
+        Type::Group(group) => inline_array_elem(&group.elem),
+        _ => None,
+    }
+}
+
+/// Returns the expression encoding `value` (a reference to a `ty`).
+///
+/// Arrays are expanded into a loop over their elements, producing the same bytes as the
+/// `[T; N]` impl without requiring `T: 'static`.
+fn encode_value(ty: &Type, value: TokenStream2, krate: &TokenStream2) -> TokenStream2 {
+    if let Some(elem) = inline_array_elem(ty) {
+        let encode_item = encode_value(elem, quote!(item), krate);
+        return quote! {
+            {
+                let mut array_bytes = 0usize;
+                for item in (#value).iter() {
+                    array_bytes += #encode_item;
+                }
+                array_bytes
+            }
+        };
+    }
+    quote! {
+        <#ty as #krate::prelude::Encode>::encode_ext(#value, writer, ctx.as_deref_mut())?
+    }
+}
+
+/// Returns the expression decoding a `ty` as a `Result`; arrays are decoded element by
+/// element.
+fn decode_value(ty: &Type, krate: &TokenStream2) -> TokenStream2 {
+    if let Some(elem) = inline_array_elem(ty) {
+        let decode_item = decode_value(elem, krate);
+        return quote! {
+            #krate::decode_array_with(|| #decode_item)
+        };
+    }
+    quote! {
+        <#ty as #krate::prelude::Decode>::decode_ext(reader, ctx.as_deref_mut())
+    }
+}
+
+/// Returns the expression encoding one field, honoring `#[lencode(...)]` field attributes.
+///
+/// The bytes written are reported to `EncoderContext::record_field` under `label`.
+fn encode_field(
+    field: &syn::Field,
+    value: TokenStream2,
+    label: &str,
+    krate: &TokenStream2,
+) -> TokenStream2 {
+    let ftype = &field.ty;
+    let encode = match (
+        field_with(&field.attrs),
+        field_codec(&field.attrs),
+        field_len_strategy(&field.attrs, krate),
+    ) {
+        (Err(err), _, _) | (_, Err(err), _) => return err.to_compile_error(),
+        (Ok(Some(with)), _, _) => quote! {
+            #with::encode_ext(#value, writer, ctx.as_deref_mut())?
+        },
+        (_, Ok(Some(codec)), _) => quote! {
+            #krate::codec::encode_with_codec::<#ftype>(#codec, #value, writer, ctx.as_deref_mut())?
+        },
+        (_, _, Ok(None)) => encode_value(ftype, value, krate),
+        (_, _, Ok(Some(strategy))) => quote! {
+            <#ftype as #krate::length::EncodeWithLen>::encode_with_len::<#strategy>(#value, writer, ctx.as_deref_mut())?
+        },
+        (_, _, Err(err)) => return err.to_compile_error(),
+    };
+    let encode = match field_length_prefixed(&field.attrs) {
+        Ok(false) => encode,
+        Ok(true) => quote! {
+            #krate::length::encode_length_prefixed(writer, ctx.as_deref_mut(), |writer, mut ctx| {
+                let body_bytes = #encode;
+                Ok(body_bytes)
+            })?
+        },
+        Err(err) => return err.to_compile_error(),
+    };
+    quote! {
+        {
+            let field_bytes = #encode;
+            #krate::context::EncoderContext::record_field(
+                ctx.as_deref_mut(),
+                ::core::any::type_name::<Self>(),
+                #label,
+                field_bytes,
+            );
+            field_bytes
+        }
+    }
+}
+
+/// Returns the expression decoding one field, honoring `#[lencode(...)]` field attributes.
+fn decode_field(field: &syn::Field, krate: &TokenStream2) -> TokenStream2 {
+    let ftype = &field.ty;
+    let decode = decode_field_body(field, krate);
+    match field_length_prefixed(&field.attrs) {
+        Ok(false) => decode,
+        Ok(true) => quote! {
+            #krate::length::decode_length_prefixed(reader, ctx.as_deref_mut(), |reader, mut ctx| {
+                let value: #ftype = #decode;
+                Ok(value)
+            })?
+        },
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+/// Returns the expression decoding one field's own encoding, without a size prefix.
+fn decode_field_body(field: &syn::Field, krate: &TokenStream2) -> TokenStream2 {
+    let ftype = &field.ty;
+    match field_with(&field.attrs) {
+        Ok(Some(with)) => {
+            return quote! {
+                #with::decode_ext(reader, ctx.as_deref_mut())?
+            };
+        }
+        Ok(None) => {}
+        Err(err) => return err.to_compile_error(),
+    }
+    match field_codec(&field.attrs) {
+        Ok(Some(codec)) => {
+            return quote! {
+                #krate::codec::decode_with_codec::<#ftype>(#codec, reader, ctx.as_deref_mut())?
+            };
+        }
+        Ok(None) => {}
+        Err(err) => return err.to_compile_error(),
+    }
+    match field_len_strategy(&field.attrs, krate) {
+        Ok(None) => {
+            let decode = decode_value(ftype, krate);
+            quote!(#decode?)
+        }
+        Ok(Some(strategy)) => quote! {
+            <#ftype as #krate::length::DecodeWithLen>::decode_with_len::<#strategy>(reader, ctx.as_deref_mut())?
+        },
+        Err(err) => err.to_compile_error(),
+    }
+}
+
 /// Derives `lencode::Encode` for structs and enums.
 ///
 /// - Structs: fields are encoded in declaration order.
 /// - Enums: a compact discriminant is written, then any fields as for structs. C‑like enums
 ///   with `#[repr(uN/iN)]` preserve the numeric discriminant.
-#[proc_macro_derive(Encode)]
+/// - `#[lencode(index_stable = "A, B")]` on an enum fails compilation unless the listed
+///   variants come first, in that order, so positional discriminants cannot shift.
+/// - `#[lencode(discriminant = N)]` on a variant writes `N` as its discriminant instead of its
+///   position, so variants can be reordered or inserted without changing the wire format.
+///   Variants without it take the previous variant's discriminant plus one.
+/// - `#[lencode(codec = "name")]` on a field encodes it with the `Codec` registered under
+///   `name` in the context's `CodecRegistry`, if there is one.
+/// - `#[lencode(with = "module")]` on a field encodes it with `module::encode_ext` instead of
+///   the field type's `Encode` impl, e.g. for foreign types the orphan rule keeps from
+///   implementing it. The module provides
+///   `fn encode_ext(value: &T, writer: &mut impl Write, ctx: Option<&mut EncoderContext>) -> Result<usize>`
+///   and, for `#[derive(Decode)]`,
+///   `fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<T>`.
+/// - `#[lencode(length_prefixed)]` on a field writes the byte size of its encoding first, as
+///   `LengthPrefixed` does, so decoders can skip it.
+#[proc_macro_derive(Encode, attributes(lencode))]
 pub fn derive_encode(input: TokenStream) -> TokenStream {
     match derive_encode_impl(input) {
         Ok(ts) => ts.into(),
@@ -88,7 +640,12 @@ pub fn derive_encode(input: TokenStream) -> TokenStream {
 /// Derives `lencode::Decode` for structs and enums.
 ///
 /// The layout matches what `#[derive(Encode)]` produces.
-#[proc_macro_derive(Decode)]
+///
+/// `#[lencode(fuzz)]` on a type without generic parameters also emits a
+/// `#[cfg(fuzzing)]` fuzz target `Type::lencode_fuzz(data: &[u8])` and a `#[cfg(test)]`
+/// test that decodes pseudo‑random inputs and checks every value that decodes roundtrips.
+/// The type must implement `Encode` too; see `lencode::fuzz`.
+#[proc_macro_derive(Decode, attributes(lencode))]
 pub fn derive_decode(input: TokenStream) -> TokenStream {
     match derive_decode_impl(input) {
         Ok(ts) => ts.into(),
@@ -118,9 +675,77 @@ pub fn derive_pack(input: TokenStream) -> TokenStream {
     }
 }
 
+/// Derives `lencode::delta::Delta` for structs.
+///
+/// The delta starts with a bitmap of changed fields, followed by the changed fields encoded
+/// as `#[derive(Encode)]` would (honoring `#[lencode(len = "...")]`). Field types must
+/// implement `Encode`, `Decode` and `PartialEq`.
+#[proc_macro_derive(Delta, attributes(lencode))]
+pub fn derive_delta(input: TokenStream) -> TokenStream {
+    match derive_delta_impl(input) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives `lencode::columnar::Columns` for structs.
+///
+/// Each field becomes one column of a `Columnar<Vec<Self>>`, encoded with the same
+/// per‑field attributes as `#[derive(Encode)]`.
+#[proc_macro_derive(Columns, attributes(lencode))]
+pub fn derive_columns(input: TokenStream) -> TokenStream {
+    match derive_columns_impl(input) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives `lencode::projection::Project` for structs.
+///
+/// Generates `<Name>Projection`, a struct with every field wrapped in `Option`, and a
+/// `decode_projection_ext` that decodes the fields selected by a `FieldMask` and skips the
+/// rest. The wire layout is the one `#[derive(Encode)]` writes.
+#[proc_macro_derive(Project, attributes(lencode))]
+pub fn derive_project(input: TokenStream) -> TokenStream {
+    match derive_project_impl(input) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives `lencode::layout::DescribeLayout` for structs and enums.
+///
+/// The generated descriptor lists fields (and enum variants with their wire discriminants)
+/// in the order `#[derive(Encode)]` writes them. `#[lencode(rename = "Name")]` on the type,
+/// a variant or a field replaces the name recorded in the layout, so schemas and generated
+/// bindings keep stable names across refactors; the wire format is unaffected.
+#[proc_macro_derive(DescribeLayout, attributes(lencode))]
+pub fn derive_describe_layout(input: TokenStream) -> TokenStream {
+    match derive_describe_layout_impl(input) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives `lencode::wrappers::NamedVariants` for enums whose variants have no fields.
+///
+/// Each variant is named by its identifier, or by `#[lencode(rename = "name")]`, which lets a
+/// variant be renamed in code while [`StringEnum`](../lencode/wrappers/struct.StringEnum.html)
+/// data already written under the old name still decodes.
+#[proc_macro_derive(NamedVariants, attributes(lencode))]
+pub fn derive_named_variants(input: TokenStream) -> TokenStream {
+    match derive_named_variants_impl(input) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
 #[inline(always)]
 fn derive_encode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
     let derive_input = parse2::<DeriveInput>(input.into())?;
+    check_index_stable(&derive_input)?;
+    check_repr_width(&derive_input)?;
+    let discs = variant_discriminants(&derive_input)?;
     let krate = crate_path();
     let name = derive_input.ident.clone();
     // Prepare generics and add Encode bounds for all type parameters
@@ -144,9 +769,10 @@ fn derive_encode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
                 syn::Fields::Named(ref named_fields) => {
                     let field_encodes = named_fields.named.iter().map(|f| {
                         let fname = &f.ident;
-                        let ftype = &f.ty;
+                        let label = fname.as_ref().unwrap().to_string();
+                        let encode = encode_field(f, quote!(&self.#fname), &label, &krate);
                         quote! {
-                            total_bytes += <#ftype as #krate::prelude::Encode>::encode_ext(&self.#fname, writer, ctx.as_deref_mut())?;
+                            total_bytes += #encode;
                         }
                     });
                     quote! {
@@ -156,9 +782,9 @@ fn derive_encode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
                 syn::Fields::Unnamed(ref unnamed_fields) => {
                     let field_encodes = unnamed_fields.unnamed.iter().enumerate().map(|(i, f)| {
                         let index = syn::Index::from(i);
-                        let ftype = &f.ty;
+                        let encode = encode_field(f, quote!(&self.#index), &i.to_string(), &krate);
                         quote! {
-                            total_bytes += <#ftype as #krate::prelude::Encode>::encode_ext(&self.#index, writer, ctx.as_deref_mut())?;
+                            total_bytes += #encode;
                         }
                     });
                     quote! {
@@ -169,15 +795,16 @@ fn derive_encode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
             };
             Ok(quote! {
                 impl #impl_generics #krate::prelude::Encode for #name #ty_generics #where_clause {
-                    #[inline(always)]
-                    fn encode_ext(
-                        &self,
-                        writer: &mut impl #krate::io::Write,
-                        mut ctx: Option<&mut #krate::context::EncoderContext>,
-                    ) -> #krate::Result<usize> {
-                        let mut total_bytes = 0;
-                        #encode_body
-                        Ok(total_bytes)
+                    #krate::__derived_codec_fn! {
+                        fn encode_ext(
+                            &self,
+                            writer: &mut impl #krate::io::Write,
+                            mut ctx: Option<&mut #krate::context::EncoderContext>,
+                        ) -> #krate::Result<usize> {
+                            let mut total_bytes = 0;
+                            #encode_body
+                            Ok(total_bytes)
+                        }
                     }
                 }
             })
@@ -190,26 +817,28 @@ fn derive_encode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
             let repr_ty = enum_repr_ty(&derive_input.attrs);
             let use_numeric_disc = is_c_like && repr_ty.is_some();
             let repr_ty_ts = repr_ty.unwrap_or(parse_quote!(usize));
-            let variant_matches = data_enum.variants.iter().enumerate().map(|(idx, v)| {
+            let variant_matches = data_enum.variants.iter().zip(&discs).map(|(v, &disc)| {
 				let vname = &v.ident;
-				let idx_lit = syn::Index::from(idx);
+				let disc_lit = proc_macro2::Literal::u64_suffixed(disc);
 				match &v.fields {
 					syn::Fields::Named(named_fields) => {
 						let fields: Vec<_> = named_fields
 							.named
 							.iter()
-							.map(|f| (f.ident.as_ref().unwrap().clone(), f.ty.clone()))
+							.map(|f| (f.ident.as_ref().unwrap().clone(), f))
 							.collect();
 
 						let field_names: Vec<_> = fields.iter().map(|(ident, _)| ident).collect();
-						let field_encodes = fields.iter().map(|(fname, ftype)| {
+						let field_encodes = fields.iter().map(|(fname, f)| {
+							let label = format!("{vname}.{fname}");
+							let encode = encode_field(f, quote!(#fname), &label, &krate);
 							quote! {
-								total_bytes += <#ftype as #krate::prelude::Encode>::encode_ext(#fname, writer, ctx.as_deref_mut())?;
+								total_bytes += #encode;
 							}
 						});
 						quote! {
 							#name::#vname { #(#field_names),* } => {
-								total_bytes += <usize as #krate::prelude::Encode>::encode_discriminant(#idx_lit as usize, writer)?;
+								total_bytes += #krate::prelude::Lencode::encode_varint_u64(#disc_lit, writer)?;
 								#(#field_encodes)*
 							}
 						}
@@ -219,18 +848,20 @@ fn derive_encode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
 							.unnamed
 							.iter()
 							.enumerate()
-							.map(|(i, f)| (Ident::new(&format!("field{}", i), Span::call_site()), f.ty.clone()))
+							.map(|(i, f)| (Ident::new(&format!("field{}", i), Span::call_site()), f))
 							.collect();
 
 						let field_indices: Vec<_> = fields.iter().map(|(ident, _)| ident).collect();
-						let field_encodes = fields.iter().map(|(fname, ftype)| {
+						let field_encodes = fields.iter().enumerate().map(|(i, (fname, f))| {
+							let label = format!("{vname}.{i}");
+							let encode = encode_field(f, quote!(#fname), &label, &krate);
 							quote! {
-								total_bytes += <#ftype as #krate::prelude::Encode>::encode_ext(#fname, writer, ctx.as_deref_mut())?;
+								total_bytes += #encode;
 							}
 						});
 						quote! {
 							#name::#vname( #(#field_indices),* ) => {
-								total_bytes += <usize as #krate::prelude::Encode>::encode_discriminant(#idx_lit as usize, writer)?;
+								total_bytes += #krate::prelude::Lencode::encode_varint_u64(#disc_lit, writer)?;
 								#(#field_encodes)*
 							}
 						}
@@ -239,14 +870,14 @@ fn derive_encode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
                         if use_numeric_disc {
                             quote! {
                                 #name::#vname => {
-                                    let disc = (#name::#vname as #repr_ty_ts) as usize;
-                                    total_bytes += <usize as #krate::prelude::Encode>::encode_discriminant(disc, writer)?;
+                                    let disc = (#name::#vname as #repr_ty_ts) as u64;
+                                    total_bytes += #krate::prelude::Lencode::encode_varint_u64(disc, writer)?;
                                 }
                             }
                         } else {
                             quote! {
                                 #name::#vname => {
-                                    total_bytes += <usize as #krate::prelude::Encode>::encode_discriminant(#idx_lit as usize, writer)?;
+                                    total_bytes += #krate::prelude::Lencode::encode_varint_u64(#disc_lit, writer)?;
                                 }
                             }
                         }
@@ -255,17 +886,18 @@ fn derive_encode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
 			});
             Ok(quote! {
                 impl #impl_generics #krate::prelude::Encode for #name #ty_generics #where_clause {
-                    #[inline(always)]
-                    fn encode_ext(
-                        &self,
-                        writer: &mut impl #krate::io::Write,
-                        mut ctx: Option<&mut #krate::context::EncoderContext>,
-                    ) -> #krate::Result<usize> {
-                        let mut total_bytes = 0;
-                        match self {
-                            #(#variant_matches)*
+                    #krate::__derived_codec_fn! {
+                        fn encode_ext(
+                            &self,
+                            writer: &mut impl #krate::io::Write,
+                            mut ctx: Option<&mut #krate::context::EncoderContext>,
+                        ) -> #krate::Result<usize> {
+                            let mut total_bytes = 0;
+                            match self {
+                                #(#variant_matches)*
+                            }
+                            Ok(total_bytes)
                         }
-                        Ok(total_bytes)
                     }
                 }
             })
@@ -283,7 +915,11 @@ fn derive_encode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
 #[inline(always)]
 fn derive_decode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
     let derive_input = parse2::<DeriveInput>(input.into())?;
+    check_index_stable(&derive_input)?;
+    check_repr_width(&derive_input)?;
+    let discs = variant_discriminants(&derive_input)?;
     let krate = crate_path();
+    let fuzz = fuzz_harness(&derive_input, &krate)?;
     let name = derive_input.ident.clone();
     // Prepare generics and add Decode bounds for all type parameters
     let mut generics = derive_input.generics.clone();
@@ -306,9 +942,9 @@ fn derive_decode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
                 syn::Fields::Named(ref named_fields) => {
                     let field_decodes = named_fields.named.iter().map(|f| {
                         let fname = &f.ident;
-                        let ftype = &f.ty;
+                        let decode = decode_field(f, &krate);
                         quote! {
-                            #fname: <#ftype as #krate::prelude::Decode>::decode_ext(reader, ctx.as_deref_mut())?,
+                            #fname: #decode,
                         }
                     });
                     quote! {
@@ -319,9 +955,9 @@ fn derive_decode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
                 }
                 syn::Fields::Unnamed(ref unnamed_fields) => {
                     let field_decodes = unnamed_fields.unnamed.iter().map(|f| {
-                        let ftype = &f.ty;
+                        let decode = decode_field(f, &krate);
                         quote! {
-                            <#ftype as #krate::prelude::Decode>::decode_ext(reader, ctx.as_deref_mut())?,
+                            #decode,
                         }
                     });
                     quote! {
@@ -334,14 +970,17 @@ fn derive_decode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
             };
             Ok(quote! {
                 impl #impl_generics #krate::prelude::Decode for #name #ty_generics #where_clause {
-                    #[inline(always)]
-                    fn decode_ext(
-                        reader: &mut impl #krate::io::Read,
-                        mut ctx: Option<&mut #krate::context::DecoderContext>,
-                    ) -> #krate::Result<Self> {
-                        #decode_body
+                    #krate::__derived_codec_fn! {
+                        fn decode_ext(
+                            reader: &mut impl #krate::io::Read,
+                            mut ctx: Option<&mut #krate::context::DecoderContext>,
+                        ) -> #krate::Result<Self> {
+                            #decode_body
+                        }
                     }
                 }
+
+                #fuzz
             })
         }
         syn::Data::Enum(data_enum) => {
@@ -352,41 +991,41 @@ fn derive_decode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
             let repr_ty = enum_repr_ty(&derive_input.attrs);
             let use_numeric_disc = is_c_like && repr_ty.is_some();
             let repr_ty_ts = repr_ty.unwrap_or(parse_quote!(usize));
-            let variant_matches = data_enum.variants.iter().enumerate().map(|(idx, v)| {
+            let variant_matches = data_enum.variants.iter().zip(&discs).map(|(v, &disc)| {
                 let vname = &v.ident;
-                let idx_lit = syn::Index::from(idx);
+                let disc_lit = proc_macro2::Literal::u64_suffixed(disc);
                 match &v.fields {
                     syn::Fields::Named(named_fields) => {
                         let field_decodes = named_fields.named.iter().map(|f| {
                             let fname = &f.ident;
-                            let ftype = &f.ty;
-							quote! {
-								#fname: <#ftype as #krate::prelude::Decode>::decode_ext(reader, ctx.as_deref_mut())?,
-							}
+                            let decode = decode_field(f, &krate);
+                            quote! {
+                                #fname: #decode,
+                            }
 						});
                         quote! {
-                            #idx_lit => Ok(#name::#vname { #(#field_decodes)* }),
+                            #disc_lit => Ok(#name::#vname { #(#field_decodes)* }),
                         }
                     }
                     syn::Fields::Unnamed(unnamed_fields) => {
                         let field_decodes = unnamed_fields.unnamed.iter().map(|f| {
-                            let ftype = &f.ty;
+                            let decode = decode_field(f, &krate);
                             quote! {
-                                <#ftype as #krate::prelude::Decode>::decode_ext(reader, ctx.as_deref_mut())?,
+                                #decode,
                             }
                         });
                         quote! {
-                            #idx_lit => Ok(#name::#vname( #(#field_decodes)* )),
+                            #disc_lit => Ok(#name::#vname( #(#field_decodes)* )),
                         }
                     }
                     syn::Fields::Unit => {
                         if use_numeric_disc {
                             quote! {
-                                disc if disc == ((#name::#vname as #repr_ty_ts) as usize) => Ok(#name::#vname),
+                                disc if disc == ((#name::#vname as #repr_ty_ts) as u64) => Ok(#name::#vname),
                             }
                         } else {
                             quote! {
-                                #idx_lit => Ok(#name::#vname),
+                                #disc_lit => Ok(#name::#vname),
                             }
                         }
                     }
@@ -394,18 +1033,21 @@ fn derive_decode_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
             });
             Ok(quote! {
                 impl #impl_generics #krate::prelude::Decode for #name #ty_generics #where_clause {
-                    #[inline(always)]
-                    fn decode_ext(
-                        reader: &mut impl #krate::io::Read,
-                        mut ctx: Option<&mut #krate::context::DecoderContext>,
-                    ) -> #krate::Result<Self> {
-                        let variant_idx = <usize as #krate::prelude::Decode>::decode_discriminant(reader)?;
-                        match variant_idx {
-                            #(#variant_matches)*
-                            _ => Err(#krate::io::Error::InvalidData),
+                    #krate::__derived_codec_fn! {
+                        fn decode_ext(
+                            reader: &mut impl #krate::io::Read,
+                            mut ctx: Option<&mut #krate::context::DecoderContext>,
+                        ) -> #krate::Result<Self> {
+                            let variant_idx = #krate::prelude::Lencode::decode_varint_u64(reader)?;
+                            match variant_idx {
+                                #(#variant_matches)*
+                                disc => Err(#krate::io::Error::UnknownDiscriminant(disc)),
+                            }
                         }
                     }
                 }
+
+                #fuzz
             })
         }
         syn::Data::Union(_data_union) => {
@@ -547,6 +1189,474 @@ fn derive_pack_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
     })
 }
 
+/// Builds `Field` descriptors for a struct or variant's fields.
+fn layout_fields(fields: &syn::Fields, krate: &TokenStream2) -> Result<Vec<TokenStream2>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let fname = match (lencode_rename(&f.attrs)?, &f.ident) {
+                (Some(rename), _) => rename.value(),
+                (None, Some(ident)) => ident.to_string(),
+                (None, None) => i.to_string(),
+            };
+            let ftype = &f.ty;
+            Ok(quote! {
+                #krate::layout::Field::new(
+                    #fname,
+                    <#ftype as #krate::layout::DescribeLayout>::layout(),
+                )
+            })
+        })
+        .collect()
+}
+
+#[inline(always)]
+fn derive_delta_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
+    let derive_input = parse2::<DeriveInput>(input.into())?;
+    let krate = crate_path();
+    let name = derive_input.ident.clone();
+    let mut generics = derive_input.generics.clone();
+    {
+        let type_idents: Vec<Ident> = generics.type_params().map(|tp| tp.ident.clone()).collect();
+        let where_clause = generics.make_where_clause();
+        for ident in type_idents {
+            where_clause.predicates.push(parse_quote!(
+                #ident: #krate::prelude::Encode + #krate::prelude::Decode + ::core::cmp::PartialEq
+            ));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let syn::Data::Struct(data_struct) = &derive_input.data else {
+        return Err(syn::Error::new_spanned(
+            derive_input.ident,
+            "Delta can only be derived for structs",
+        ));
+    };
+    let fields: Vec<(TokenStream2, String, &syn::Field)> = data_struct
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => (quote!(#ident), ident.to_string(), f),
+            None => {
+                let index = syn::Index::from(i);
+                (quote!(#index), i.to_string(), f)
+            }
+        })
+        .collect();
+    let field_count = fields.len();
+    let mask_len = field_count.div_ceil(8);
+    let mut compares = Vec::new();
+    let mut encodes = Vec::new();
+    let mut applies = Vec::new();
+    for (i, (member, label, field)) in fields.iter().enumerate() {
+        let byte = i / 8;
+        let bit = 1u8 << (i % 8);
+        let encode = encode_field(field, quote!(&self.#member), label, &krate);
+        let decode = decode_field(field, &krate);
+        compares.push(quote! {
+            if self.#member != old.#member {
+                mask[#byte] |= #bit;
+            }
+        });
+        encodes.push(quote! {
+            if mask[#byte] & #bit != 0 {
+                total_bytes += #encode;
+            }
+        });
+        applies.push(quote! {
+            if mask[#byte] & #bit != 0 {
+                self.#member = #decode;
+            }
+        });
+    }
+    Ok(quote! {
+        impl #impl_generics #krate::delta::Delta for #name #ty_generics #where_clause {
+            #[inline]
+            fn encode_delta_ext(
+                &self,
+                old: &Self,
+                writer: &mut impl #krate::io::Write,
+                mut ctx: Option<&mut #krate::context::EncoderContext>,
+            ) -> #krate::Result<usize> {
+                let mut mask = [0u8; #mask_len];
+                #(#compares)*
+                let mut total_bytes = #krate::io::Write::write(writer, &mask)?;
+                #(#encodes)*
+                Ok(total_bytes)
+            }
+
+            #[inline]
+            fn apply_delta_ext(
+                &mut self,
+                reader: &mut impl #krate::io::Read,
+                mut ctx: Option<&mut #krate::context::DecoderContext>,
+            ) -> #krate::Result<()> {
+                let mask = #krate::delta::read_delta_mask::<#mask_len>(reader, #field_count)?;
+                #(#applies)*
+                Ok(())
+            }
+        }
+    })
+}
+
+#[inline(always)]
+fn derive_columns_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
+    let derive_input = parse2::<DeriveInput>(input.into())?;
+    let krate = crate_path();
+    let name = derive_input.ident.clone();
+    let mut generics = derive_input.generics.clone();
+    {
+        let type_idents: Vec<Ident> = generics.type_params().map(|tp| tp.ident.clone()).collect();
+        let where_clause = generics.make_where_clause();
+        for ident in type_idents {
+            where_clause.predicates.push(parse_quote!(
+                #ident: #krate::prelude::Encode + #krate::prelude::Decode
+            ));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let syn::Data::Struct(data_struct) = &derive_input.data else {
+        return Err(syn::Error::new_spanned(
+            derive_input.ident,
+            "Columns can only be derived for structs",
+        ));
+    };
+    if data_struct.fields.is_empty() {
+        return Err(syn::Error::new_spanned(
+            derive_input.ident,
+            "Columns requires at least one field",
+        ));
+    }
+    let mut encodes = Vec::new();
+    let mut decodes = Vec::new();
+    let mut inits = Vec::new();
+    for (i, field) in data_struct.fields.iter().enumerate() {
+        let (member, label) = match &field.ident {
+            Some(ident) => (quote!(#ident), ident.to_string()),
+            None => {
+                let index = syn::Index::from(i);
+                (quote!(#index), i.to_string())
+            }
+        };
+        let ftype = &field.ty;
+        let column = Ident::new(&format!("column_{i}"), Span::call_site());
+        let encode = encode_field(field, quote!(&row.#member), &label, &krate);
+        let decode = decode_field(field, &krate);
+        encodes.push(quote! {
+            total_bytes += #krate::columnar::encode_column(
+                rows,
+                writer,
+                ctx.as_deref_mut(),
+                |row, writer, mut ctx| Ok(#encode),
+            )?;
+        });
+        decodes.push(quote! {
+            let mut #column = #krate::columnar::decode_column(
+                reader,
+                rows,
+                ctx.as_deref_mut(),
+                |reader, mut ctx| {
+                    let value: #ftype = #decode;
+                    Ok(value)
+                },
+            )?
+            .into_iter();
+        });
+        inits.push(quote! {
+            #member: #column.next().ok_or(#krate::io::Error::InvalidData)?
+        });
+    }
+    Ok(quote! {
+        impl #impl_generics #krate::columnar::Columns for #name #ty_generics #where_clause {
+            #[inline]
+            fn encode_columns(
+                rows: &[Self],
+                writer: &mut impl #krate::io::Write,
+                mut ctx: Option<&mut #krate::context::EncoderContext>,
+            ) -> #krate::Result<usize> {
+                let mut total_bytes = 0usize;
+                #(#encodes)*
+                Ok(total_bytes)
+            }
+
+            #[inline]
+            fn decode_columns(
+                reader: &mut impl #krate::io::Read,
+                rows: usize,
+                mut ctx: Option<&mut #krate::context::DecoderContext>,
+            ) -> #krate::Result<Vec<Self>> {
+                #(#decodes)*
+                let mut out = Vec::with_capacity(rows);
+                for _ in 0..rows {
+                    out.push(Self { #(#inits,)* });
+                }
+                Ok(out)
+            }
+        }
+    })
+}
+
+/// Returns the statement stepping over one field without keeping it.
+///
+/// Fields with a length strategy, a codec, a `with` module or an inline array layout are
+/// decoded and dropped, since their wire form differs from the type's own `Decode` impl.
+fn skip_field(field: &syn::Field, krate: &TokenStream2) -> TokenStream2 {
+    let ftype = &field.ty;
+    if matches!(field_length_prefixed(&field.attrs), Ok(true)) {
+        return quote!(#krate::length::skip_length_prefixed(reader)?;);
+    }
+    if !matches!(field_len_strategy(&field.attrs, krate), Ok(None))
+        || !matches!(field_codec(&field.attrs), Ok(None))
+        || !matches!(field_with(&field.attrs), Ok(None))
+        || inline_array_elem(ftype).is_some()
+    {
+        let decode = decode_field(field, krate);
+        return quote!(let _: #ftype = #decode;);
+    }
+    quote! {
+        <#ftype as #krate::prelude::Decode>::skip_ext(reader, ctx.as_deref_mut())?;
+    }
+}
+
+#[inline(always)]
+fn derive_project_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
+    let derive_input = parse2::<DeriveInput>(input.into())?;
+    let krate = crate_path();
+    let name = derive_input.ident.clone();
+    let vis = &derive_input.vis;
+    let projection = Ident::new(&format!("{name}Projection"), name.span());
+    let mut generics = derive_input.generics.clone();
+    {
+        let type_idents: Vec<Ident> = generics.type_params().map(|tp| tp.ident.clone()).collect();
+        let where_clause = generics.make_where_clause();
+        for ident in type_idents {
+            where_clause
+                .predicates
+                .push(parse_quote!(#ident: #krate::prelude::Decode));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let syn::Data::Struct(data_struct) = &derive_input.data else {
+        return Err(syn::Error::new_spanned(
+            derive_input.ident,
+            "Project can only be derived for structs",
+        ));
+    };
+    let mut names = Vec::new();
+    let mut decls = Vec::new();
+    let mut inits = Vec::new();
+    for (i, field) in data_struct.fields.iter().enumerate() {
+        let fvis = &field.vis;
+        let ftype = &field.ty;
+        let decode = decode_field(field, &krate);
+        let skip = skip_field(field, &krate);
+        let value = quote! {
+            if mask.contains(#i) {
+                Some(#decode)
+            } else {
+                #skip
+                None
+            }
+        };
+        match &field.ident {
+            Some(ident) => {
+                names.push(ident.to_string());
+                decls.push(quote!(#fvis #ident: ::core::option::Option<#ftype>));
+                inits.push(quote!(#ident: #value));
+            }
+            None => {
+                names.push(i.to_string());
+                decls.push(quote!(#fvis ::core::option::Option<#ftype>));
+                inits.push(value);
+            }
+        }
+    }
+    let doc = format!("Fields of [`{name}`] selected by a `FieldMask`; see `lencode::projection`.");
+    let (definition, construct) = match &data_struct.fields {
+        syn::Fields::Named(_) => (
+            quote! {
+                #[doc = #doc]
+                #vis struct #projection #impl_generics #where_clause {
+                    #(#decls,)*
+                }
+            },
+            quote!(#projection { #(#inits,)* }),
+        ),
+        _ => (
+            quote! {
+                #[doc = #doc]
+                #vis struct #projection #impl_generics (#(#decls,)*) #where_clause;
+            },
+            quote!(#projection(#(#inits,)*)),
+        ),
+    };
+    Ok(quote! {
+        #definition
+
+        impl #impl_generics #krate::projection::Project for #name #ty_generics #where_clause {
+            type Projection = #projection #ty_generics;
+
+            const FIELDS: &'static [&'static str] = &[#(#names),*];
+
+            #[inline]
+            fn decode_projection_ext(
+                reader: &mut impl #krate::io::Read,
+                mask: &#krate::projection::FieldMask,
+                mut ctx: Option<&mut #krate::context::DecoderContext>,
+            ) -> #krate::Result<Self::Projection> {
+                Ok(#construct)
+            }
+        }
+    })
+}
+
+#[inline(always)]
+fn derive_describe_layout_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
+    let derive_input = parse2::<DeriveInput>(input.into())?;
+    check_repr_width(&derive_input)?;
+    let discs = variant_discriminants(&derive_input)?;
+    let krate = crate_path();
+    let name = derive_input.ident.clone();
+    // Prepare generics and add DescribeLayout bounds for all type parameters
+    let mut generics = derive_input.generics.clone();
+    {
+        let type_idents: Vec<Ident> = generics.type_params().map(|tp| tp.ident.clone()).collect();
+        let where_clause = generics.make_where_clause();
+        for ident in type_idents {
+            where_clause
+                .predicates
+                .push(parse_quote!(#ident: #krate::layout::DescribeLayout));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let type_name = match lencode_rename(&derive_input.attrs)? {
+        Some(rename) => quote!(#rename),
+        None => quote!(::core::any::type_name::<Self>()),
+    };
+    let body = match derive_input.data {
+        syn::Data::Struct(data_struct) => {
+            let fields = layout_fields(&data_struct.fields, &krate)?;
+            quote! {
+                #krate::layout::Layout::structure(
+                    #type_name,
+                    [#(#fields),*],
+                )
+            }
+        }
+        syn::Data::Enum(data_enum) => {
+            let is_c_like = data_enum
+                .variants
+                .iter()
+                .all(|v| matches!(v.fields, syn::Fields::Unit));
+            let repr_ty = enum_repr_ty(&derive_input.attrs);
+            let variants = data_enum
+                .variants
+                .iter()
+                .zip(&discs)
+                .map(|(v, &disc)| {
+                    let vname = &v.ident;
+                    let vname_str = match lencode_rename(&v.attrs)? {
+                        Some(rename) => rename.value(),
+                        None => vname.to_string(),
+                    };
+                    let disc = match (&repr_ty, is_c_like) {
+                        (Some(repr_ty), true) => quote!((#name::#vname as #repr_ty) as u64),
+                        _ => {
+                            let disc = proc_macro2::Literal::u64_suffixed(disc);
+                            quote!(#disc)
+                        }
+                    };
+                    let fields = layout_fields(&v.fields, &krate)?;
+                    Ok(quote! {
+                        #krate::layout::Variant::new(
+                            #vname_str,
+                            #disc,
+                            [#(#fields),*],
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            quote! {
+                #krate::layout::Layout::enumeration(
+                    #type_name,
+                    [#(#variants),*],
+                )
+            }
+        }
+        syn::Data::Union(_data_union) => {
+            return Err(syn::Error::new_spanned(
+                derive_input.ident,
+                "DescribeLayout cannot be derived for unions",
+            ));
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics #krate::layout::DescribeLayout for #name #ty_generics #where_clause {
+            fn layout() -> #krate::layout::Layout {
+                #body
+            }
+        }
+    })
+}
+
+fn derive_named_variants_impl(input: impl Into<TokenStream2>) -> Result<TokenStream2> {
+    let derive_input = parse2::<DeriveInput>(input.into())?;
+    let krate = crate_path();
+    let name = &derive_input.ident;
+    let (impl_generics, ty_generics, where_clause) = derive_input.generics.split_for_impl();
+    let syn::Data::Enum(data_enum) = &derive_input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "NamedVariants can only be derived for enums",
+        ));
+    };
+    let mut names: Vec<String> = Vec::new();
+    let mut to_name = Vec::new();
+    let mut from_name = Vec::new();
+    for variant in &data_enum.variants {
+        if !matches!(variant.fields, syn::Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "NamedVariants requires variants without fields",
+            ));
+        }
+        let vname = &variant.ident;
+        let label = match lencode_rename(&variant.attrs)? {
+            Some(lit) => lit.value(),
+            None => vname.to_string(),
+        };
+        if names.contains(&label) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!("duplicate variant name `{label}`"),
+            ));
+        }
+        to_name.push(quote!(Self::#vname => #label,));
+        from_name.push(quote!(#label => Some(Self::#vname),));
+        names.push(label);
+    }
+    Ok(quote! {
+        impl #impl_generics #krate::wrappers::NamedVariants for #name #ty_generics #where_clause {
+            #[inline]
+            fn variant_name(&self) -> &'static str {
+                match self {
+                    #(#to_name)*
+                }
+            }
+
+            #[inline]
+            fn from_variant_name(name: &str) -> Option<Self> {
+                match name {
+                    #(#from_name)*
+                    _ => None,
+                }
+            }
+        }
+    })
+}
+
 #[test]
 fn test_derive_encode_struct_basic() {
     let tokens = quote! {
@@ -558,24 +1668,43 @@ fn test_derive_encode_struct_basic() {
     let derived = derive_encode_impl(tokens).unwrap();
     let expected = quote! {
         impl ::lencode::prelude::Encode for TestStruct {
-            #[inline(always)]
-            fn encode_ext(
-                &self,
-                writer: &mut impl ::lencode::io::Write,
-                mut ctx: Option<&mut ::lencode::context::EncoderContext>,
-            ) -> ::lencode::Result<usize> {
-                let mut total_bytes = 0;
-                total_bytes += <u32 as ::lencode::prelude::Encode>::encode_ext(
-                    &self.a,
-                    writer,
-                    ctx.as_deref_mut()
-                )?;
-                total_bytes += <String as ::lencode::prelude::Encode>::encode_ext(
-                    &self.b,
-                    writer,
-                    ctx.as_deref_mut()
-                )?;
-                Ok(total_bytes)
+            ::lencode::__derived_codec_fn! {
+                fn encode_ext(
+                    &self,
+                    writer: &mut impl ::lencode::io::Write,
+                    mut ctx: Option<&mut ::lencode::context::EncoderContext>,
+                ) -> ::lencode::Result<usize> {
+                    let mut total_bytes = 0;
+                    total_bytes += {
+                        let field_bytes = <u32 as ::lencode::prelude::Encode>::encode_ext(
+                            &self.a,
+                            writer,
+                            ctx.as_deref_mut()
+                        )?;
+                        ::lencode::context::EncoderContext::record_field(
+                            ctx.as_deref_mut(),
+                            ::core::any::type_name::<Self>(),
+                            "a",
+                            field_bytes,
+                        );
+                        field_bytes
+                    };
+                    total_bytes += {
+                        let field_bytes = <String as ::lencode::prelude::Encode>::encode_ext(
+                            &self.b,
+                            writer,
+                            ctx.as_deref_mut()
+                        )?;
+                        ::lencode::context::EncoderContext::record_field(
+                            ctx.as_deref_mut(),
+                            ::core::any::type_name::<Self>(),
+                            "b",
+                            field_bytes,
+                        );
+                        field_bytes
+                    };
+                    Ok(total_bytes)
+                }
             }
         }
     };
@@ -593,15 +1722,16 @@ fn test_derive_decode_struct_basic() {
     let derived = derive_decode_impl(tokens).unwrap();
     let expected = quote! {
         impl ::lencode::prelude::Decode for TestStruct {
-            #[inline(always)]
-            fn decode_ext(
-                reader: &mut impl ::lencode::io::Read,
-                mut ctx: Option<&mut ::lencode::context::DecoderContext>,
-            ) -> ::lencode::Result<Self> {
-                Ok(TestStruct {
-                    a: <u32 as ::lencode::prelude::Decode>::decode_ext(reader, ctx.as_deref_mut())?,
-                    b: <String as ::lencode::prelude::Decode>::decode_ext(reader, ctx.as_deref_mut())?,
-                })
+            ::lencode::__derived_codec_fn! {
+                fn decode_ext(
+                    reader: &mut impl ::lencode::io::Read,
+                    mut ctx: Option<&mut ::lencode::context::DecoderContext>,
+                ) -> ::lencode::Result<Self> {
+                    Ok(TestStruct {
+                        a: <u32 as ::lencode::prelude::Decode>::decode_ext(reader, ctx.as_deref_mut())?,
+                        b: <String as ::lencode::prelude::Decode>::decode_ext(reader, ctx.as_deref_mut())?,
+                    })
+                }
             }
         }
     };
@@ -665,3 +1795,230 @@ fn test_derive_pack_transparent_tuple_struct() {
         "should contain from_raw_parts for bulk encode"
     );
 }
+
+#[test]
+fn test_derive_describe_layout_struct() {
+    let tokens = quote! {
+        struct TestStruct {
+            a: u32,
+            b: String,
+        }
+    };
+    let derived = derive_describe_layout_impl(tokens).unwrap();
+    let expected = quote! {
+        impl ::lencode::layout::DescribeLayout for TestStruct {
+            fn layout() -> ::lencode::layout::Layout {
+                ::lencode::layout::Layout::structure(
+                    ::core::any::type_name::<Self>(),
+                    [
+                        ::lencode::layout::Field::new(
+                            "a",
+                            <u32 as ::lencode::layout::DescribeLayout>::layout(),
+                        ),
+                        ::lencode::layout::Field::new(
+                            "b",
+                            <String as ::lencode::layout::DescribeLayout>::layout(),
+                        )
+                    ],
+                )
+            }
+        }
+    };
+    assert_eq!(derived.to_string(), expected.to_string());
+}
+
+#[test]
+fn test_derive_encode_len_strategy_attr() {
+    let tokens = quote! {
+        struct Legacy {
+            #[lencode(len = "u16_fixed")]
+            name: String,
+        }
+    };
+    let derived = derive_encode_impl(tokens).unwrap().to_string();
+    let expected = quote! {
+        <String as ::lencode::length::EncodeWithLen>::encode_with_len::<::lencode::length::U16FixedLen>(&self.name, writer, ctx.as_deref_mut())?
+    };
+    assert!(derived.contains(&expected.to_string()));
+
+    let bad = quote! {
+        struct Legacy {
+            #[lencode(length = "u16_fixed")]
+            name: String,
+        }
+    };
+    let derived = derive_decode_impl(bad).unwrap().to_string();
+    assert!(derived.contains("compile_error"));
+}
+
+#[test]
+fn test_derive_with_module_attr() {
+    let tokens = quote! {
+        struct Peer {
+            #[lencode(with = "codecs::ipv4")]
+            addr: Ipv4Addr,
+        }
+    };
+    let encoded = derive_encode_impl(tokens.clone()).unwrap().to_string();
+    let expected = quote!(codecs::ipv4::encode_ext(
+        &self.addr,
+        writer,
+        ctx.as_deref_mut()
+    )?);
+    assert!(encoded.contains(&expected.to_string()));
+    let decoded = derive_decode_impl(tokens).unwrap().to_string();
+    let expected = quote!(codecs::ipv4::decode_ext(reader, ctx.as_deref_mut())?);
+    assert!(decoded.contains(&expected.to_string()));
+
+    let combined = quote! {
+        struct Peer {
+            #[lencode(with = "codecs::ipv4", len = "u8_fixed")]
+            addr: Ipv4Addr,
+        }
+    };
+    let derived = derive_encode_impl(combined).unwrap().to_string();
+    assert!(derived.contains("`len` and `with` cannot be combined"));
+}
+
+#[test]
+fn test_index_stable_rejects_reordered_variants() {
+    let appended = quote! {
+        #[lencode(index_stable = "Idle, Busy")]
+        enum State { Idle, Busy, Done }
+    };
+    assert!(derive_encode_impl(appended).is_ok());
+
+    let inserted = quote! {
+        #[lencode(index_stable = "Idle, Busy")]
+        enum State { Idle, Done, Busy }
+    };
+    let err = derive_decode_impl(inserted).unwrap_err();
+    assert!(err.to_string().contains("variant 1 must be `Busy`"));
+
+    let numeric = quote! {
+        #[repr(u8)]
+        #[lencode(index_stable = "Idle")]
+        enum State { Idle = 3 }
+    };
+    assert!(derive_encode_impl(numeric).is_err());
+}
+
+#[test]
+fn test_variant_discriminant_attr() {
+    let tokens = quote! {
+        enum Event {
+            #[lencode(discriminant = 10)]
+            Login { user: u32 },
+            Logout(u32),
+            #[lencode(discriminant = 2)]
+            Ping,
+        }
+    };
+    let encoded = derive_encode_impl(tokens.clone()).unwrap().to_string();
+    for disc in [10, 11, 2] {
+        let disc = proc_macro2::Literal::u64_suffixed(disc);
+        let expected = quote!(encode_varint_u64(#disc, writer));
+        assert!(encoded.contains(&expected.to_string()));
+    }
+    let decoded = derive_decode_impl(tokens).unwrap().to_string();
+    assert!(decoded.contains("11u64 => Ok (Event :: Logout ("));
+
+    // Discriminants are `u64` literals, so values past `u32::MAX` build on 32-bit targets.
+    let wide = quote! {
+        enum Event {
+            #[lencode(discriminant = 5000000000)]
+            Login,
+        }
+    };
+    let decoded = derive_decode_impl(wide).unwrap().to_string();
+    assert!(decoded.contains("5000000000u64 => Ok (Event :: Login)"));
+    let too_wide = quote! {
+        enum Event {
+            #[lencode(discriminant = 18446744073709551616)]
+            Login,
+        }
+    };
+    assert_eq!(
+        derive_encode_impl(too_wide).unwrap_err().to_string(),
+        "discriminant must fit in a u64"
+    );
+
+    let duplicate = quote! {
+        enum Event {
+            #[lencode(discriminant = 1)]
+            Login,
+            Logout,
+            #[lencode(discriminant = 2)]
+            Ping(u8),
+        }
+    };
+    assert_eq!(
+        derive_encode_impl(duplicate).unwrap_err().to_string(),
+        "discriminant 2 of `Ping` is already used by `Logout`"
+    );
+
+    let numeric = quote! {
+        #[repr(u8)]
+        enum Event {
+            #[lencode(discriminant = 1)]
+            Login = 3,
+        }
+    };
+    assert!(derive_decode_impl(numeric).is_err());
+}
+
+#[test]
+fn test_128_bit_repr_enums_are_rejected() {
+    let wide = quote! {
+        #[repr(u128)]
+        enum Id { A = 1 << 100 }
+    };
+    let err = derive_encode_impl(wide.clone()).unwrap_err();
+    assert!(err.to_string().contains("64-bit varints"));
+    assert!(derive_decode_impl(wide.clone()).is_err());
+    assert!(derive_describe_layout_impl(wide).is_err());
+
+    // Data‑carrying variants encode their index, whatever the repr.
+    let tagged = quote! {
+        #[repr(i128)]
+        enum Id { A(u8) }
+    };
+    assert!(derive_encode_impl(tagged).is_ok());
+}
+
+#[test]
+fn test_named_variants_rejects_fields_and_duplicate_names() {
+    let with_fields = derive_named_variants_impl(quote! {
+        enum Mode { Fast, Custom(u8) }
+    });
+    assert!(with_fields.is_err());
+    let duplicate = derive_named_variants_impl(quote! {
+        enum Mode {
+            Fast,
+            #[lencode(rename = "Fast")]
+            Quick,
+        }
+    });
+    assert_eq!(
+        duplicate.unwrap_err().to_string(),
+        "duplicate variant name `Fast`"
+    );
+}
+
+#[test]
+fn test_fuzz_test_names_keep_type_case() {
+    let lower = derive_decode_impl(quote! {
+        #[lencode(fuzz)]
+        struct Foo(u8);
+    })
+    .unwrap()
+    .to_string();
+    let upper = derive_decode_impl(quote! {
+        #[lencode(fuzz)]
+        struct FOO(u8);
+    })
+    .unwrap()
+    .to_string();
+    assert!(lower.contains("fn lencode_fuzz_Foo ()"));
+    assert!(upper.contains("fn lencode_fuzz_FOO ()"));
+}
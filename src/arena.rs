@@ -0,0 +1,309 @@
+//! Arena‑backed decoding via [`bumpalo`].
+//!
+//! [`DecodeIn`] mirrors [`Decode`] but materializes strings, byte slices, and vectors inside
+//! a caller‑provided [`Bump`] arena. A consumer decoding many short‑lived messages can
+//! decode each one into an arena and [`Bump::reset`] it afterwards, freeing every allocation
+//! at once instead of dropping them one by one.
+//!
+//! The wire format is exactly the one produced by [`Encode`]: a `&'a str` or
+//! `bumpalo::collections::String<'a>` decodes anything encoded from a `String`, and a
+//! `bumpalo::collections::Vec<'a, T>` decodes anything encoded from a `Vec<T>` (including
+//! the byte‑string layout used for `Vec<u8>`).
+//!
+//! ```rust
+//! use bumpalo::Bump;
+//! use lencode::prelude::*;
+//!
+//! let names = vec![String::from("alice"), String::from("bob")];
+//! let mut buf = Vec::new();
+//! encode(&names, &mut buf).unwrap();
+//!
+//! let mut bump = Bump::new();
+//! {
+//!     let decoded: bumpalo::collections::Vec<&str> =
+//!         decode_in(&mut Cursor::new(&buf), &bump).unwrap();
+//!     assert_eq!(decoded, ["alice", "bob"]);
+//! }
+//! bump.reset();
+//! ```
+use bumpalo::Bump;
+use bumpalo::collections::{String as BumpString, Vec as BumpVec};
+
+use crate::bytes;
+use crate::io::read_vec;
+use crate::prelude::*;
+
+/// Trait for types that can be decoded into a [`Bump`] arena.
+///
+/// Implementations allocate any variable‑length data in `bump`; the decoded value borrows
+/// from the arena for `'a`.
+pub trait DecodeIn<'a>: Sized {
+    /// Decodes `Self` from `reader`, allocating in `bump` and optionally using a
+    /// [`DecoderContext`].
+    fn decode_in(
+        reader: &mut impl Read,
+        bump: &'a Bump,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self>;
+
+    /// Decodes a `Vec<Self>` encoding into an arena vector.
+    ///
+    /// The default reads an element count followed by each element. `u8` overrides this to
+    /// read the flagged byte‑string layout used by `Vec<u8>`.
+    #[inline(always)]
+    fn decode_vec_in(
+        reader: &mut impl Read,
+        bump: &'a Bump,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<BumpVec<'a, Self>> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+            let mut out = BumpVec::new_in(bump);
+            for _ in 0..len {
+                out.push(Self::decode_in(reader, bump, ctx.as_deref_mut())?);
+            }
+            Ok(out)
+        })
+    }
+}
+
+/// Decodes a value of type `T` from `reader`, allocating in `bump`.
+#[inline(always)]
+pub fn decode_in<'a, T: DecodeIn<'a>>(reader: &mut impl Read, bump: &'a Bump) -> Result<T> {
+    T::decode_in(reader, bump, None)
+}
+
+/// Decodes a value of type `T` from `reader` with an optional [`DecoderContext`],
+/// allocating in `bump`.
+#[inline(always)]
+pub fn decode_in_ext<'a, T: DecodeIn<'a>>(
+    reader: &mut impl Read,
+    bump: &'a Bump,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<T> {
+    T::decode_in(reader, bump, ctx)
+}
+
+/// Reads a flagged byte‑string (`varint((len << 1) | compressed) + payload`) into `bump`.
+fn decode_bytes_in<'a>(reader: &mut impl Read, bump: &'a Bump) -> Result<BumpVec<'a, u8>> {
+    let flagged = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    let is_compressed = (flagged & 1) == 1;
+    let payload_len = flagged >> 1;
+    if is_compressed {
+        let decompress = |comp: &[u8]| -> Result<BumpVec<'a, u8>> {
+            let orig_len = bytes::zstd_content_size(comp)?;
+            let mut out = BumpVec::with_capacity_in(orig_len, bump);
+            out.resize(orig_len, 0u8);
+            bytes::zstd_decompress_into(comp, &mut out)?;
+            Ok(out)
+        };
+        // Zero-copy fast path for compressed data
+        if let Some(slice) = reader.buf()
+            && slice.len() >= payload_len
+        {
+            let out = decompress(&slice[..payload_len])?;
+            reader.advance(payload_len);
+            return Ok(out);
+        }
+        // `payload_len` is untrusted: read it in bounded steps rather than allocating it up
+        // front.
+        let comp = read_vec(reader, payload_len)?;
+        return decompress(&comp);
+    }
+    // Zero-copy fast path for raw data
+    if let Some(slice) = reader.buf()
+        && slice.len() >= payload_len
+    {
+        let mut out = BumpVec::with_capacity_in(payload_len, bump);
+        out.extend_from_slice_copy(&slice[..payload_len]);
+        reader.advance(payload_len);
+        return Ok(out);
+    }
+    let data = read_vec(reader, payload_len)?;
+    let mut out = BumpVec::with_capacity_in(data.len(), bump);
+    out.extend_from_slice_copy(&data);
+    Ok(out)
+}
+
+impl<'a> DecodeIn<'a> for u8 {
+    #[inline(always)]
+    fn decode_in(
+        reader: &mut impl Read,
+        _bump: &'a Bump,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        u8::decode_ext(reader, ctx)
+    }
+
+    #[inline(always)]
+    fn decode_vec_in(
+        reader: &mut impl Read,
+        bump: &'a Bump,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<BumpVec<'a, Self>> {
+        // Diff-encoded blobs are reconstructed by the diff decoder, then copied in.
+        if let Some(c) = ctx
+            && let Some(ref mut diff) = c.diff
+            && diff.current_key.is_some()
+        {
+            let out = diff.decode_blob(reader)?;
+            let mut vec = BumpVec::with_capacity_in(out.len(), bump);
+            vec.extend_from_slice_copy(&out);
+            return Ok(vec);
+        }
+        decode_bytes_in(reader, bump)
+    }
+}
+
+macro_rules! impl_decode_in_via_decode {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> DecodeIn<'a> for $t {
+                #[inline(always)]
+                fn decode_in(
+                    reader: &mut impl Read,
+                    _bump: &'a Bump,
+                    ctx: Option<&mut DecoderContext>,
+                ) -> Result<Self> {
+                    <$t>::decode_ext(reader, ctx)
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_in_via_decode!(
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    bool,
+    f32,
+    f64,
+    ()
+);
+
+impl<'a> DecodeIn<'a> for &'a str {
+    #[inline(always)]
+    fn decode_in(
+        reader: &mut impl Read,
+        bump: &'a Bump,
+        _ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        let bytes = decode_bytes_in(reader, bump)?.into_bump_slice();
+        core::str::from_utf8(bytes).map_err(|_| Error::InvalidUtf8)
+    }
+}
+
+impl<'a> DecodeIn<'a> for BumpString<'a> {
+    #[inline(always)]
+    fn decode_in(
+        reader: &mut impl Read,
+        bump: &'a Bump,
+        _ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        BumpString::from_utf8(decode_bytes_in(reader, bump)?).map_err(|_| Error::InvalidUtf8)
+    }
+}
+
+impl<'a, T: DecodeIn<'a>> DecodeIn<'a> for BumpVec<'a, T> {
+    #[inline(always)]
+    fn decode_in(
+        reader: &mut impl Read,
+        bump: &'a Bump,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        T::decode_vec_in(reader, bump, ctx)
+    }
+}
+
+impl<'a, T: DecodeIn<'a>> DecodeIn<'a> for &'a [T] {
+    #[inline(always)]
+    fn decode_in(
+        reader: &mut impl Read,
+        bump: &'a Bump,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        Ok(T::decode_vec_in(reader, bump, ctx)?.into_bump_slice())
+    }
+}
+
+impl<'a, T: DecodeIn<'a>> DecodeIn<'a> for Option<T> {
+    #[inline(always)]
+    fn decode_in(
+        reader: &mut impl Read,
+        bump: &'a Bump,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        DecoderContext::nested(ctx, |ctx| {
+            if Lencode::decode_bool(reader)? {
+                Ok(Some(T::decode_in(reader, bump, ctx)?))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+}
+
+#[test]
+fn test_decode_in_strings_and_bytes() {
+    let bump = Bump::new();
+    let text = String::from("hello arena");
+    let blob: Vec<u8> = (0..=255u8).collect();
+    let repetitive = "ab".repeat(500);
+    let mut buf = Vec::new();
+    text.encode(&mut buf).unwrap();
+    blob.encode(&mut buf).unwrap();
+    repetitive.encode(&mut buf).unwrap();
+
+    let mut cursor = Cursor::new(&buf);
+    let s: &str = decode_in(&mut cursor, &bump).unwrap();
+    let b: &[u8] = decode_in(&mut cursor, &bump).unwrap();
+    let r: BumpString = decode_in(&mut cursor, &bump).unwrap();
+    assert_eq!(s, text);
+    assert_eq!(b, &blob[..]);
+    assert_eq!(r.as_str(), repetitive);
+}
+
+#[test]
+fn test_decode_in_nested() {
+    let bump = Bump::new();
+    let value: Vec<Option<Vec<u32>>> = vec![Some(vec![1, 2, 3]), None, Some(vec![])];
+    let mut buf = Vec::new();
+    value.encode(&mut buf).unwrap();
+
+    let decoded: BumpVec<Option<&[u32]>> = decode_in(&mut Cursor::new(&buf), &bump).unwrap();
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded[0], Some(&[1u32, 2, 3][..]));
+    assert_eq!(decoded[1], None);
+    assert_eq!(decoded[2], Some(&[][..]));
+}
+
+#[test]
+fn test_decode_in_invalid_utf8() {
+    let bump = Bump::new();
+    let mut buf = Vec::new();
+    vec![0xffu8, 0xfe].encode(&mut buf).unwrap();
+    let res: Result<&str> = decode_in(&mut Cursor::new(&buf), &bump);
+    assert!(matches!(res, Err(Error::InvalidUtf8)));
+}
+
+#[test]
+fn test_decode_in_forged_lengths_allocate_nothing_up_front() {
+    let bump = Bump::new();
+    for compressed in [0u64, 1] {
+        let mut buf = Vec::new();
+        Lencode::encode_varint_u64((1 << 40) << 1 | compressed, &mut buf).unwrap();
+        buf.extend([0; 16]);
+        let res: Result<&[u8]> = decode_in(&mut Cursor::new(&buf), &bump);
+        assert!(res.is_err());
+    }
+    assert!(bump.allocated_bytes() < 1 << 20);
+}
@@ -0,0 +1,447 @@
+//! Text armor: base64 and hex adapters for embedding payloads in text.
+//!
+//! JSON strings, log lines and environment variables cannot hold arbitrary bytes.
+//! [`encode_base64`] and [`encode_hex`] wrap a [`Write`] so that everything encoded into them
+//! comes out as printable ASCII with no whitespace, quotes, backslashes or separators, and
+//! [`decode_base64`] and [`decode_hex`] wrap a [`Read`] to turn such text back into bytes.
+//! Both directions convert as data flows through, a few bytes at a time, so a large payload
+//! never has to be held in memory as bytes and as text at once.
+//!
+//! Base64 uses the standard RFC 4648 alphabet with `=` padding. The decoder also accepts
+//! unpadded input, and stops at the padding so text following a padded payload is left
+//! unread. Hex is written in lowercase and read in either case. Any other character fails
+//! with [`Error::InvalidData`].
+//!
+//! Base64 encodes three bytes at a time, so call [`Base64Writer::finish`] to write out the
+//! last one or two bytes and the padding; [`HexWriter::finish`] is provided for symmetry.
+//!
+//! ```
+//! use lencode::armor;
+//! use lencode::prelude::*;
+//!
+//! let mut text = armor::encode_base64(Vec::new());
+//! encode(&(42u32, String::from("ok")), &mut text).unwrap();
+//! let text = text.finish().unwrap();
+//! assert_eq!(text, b"KgRvaw==");
+//!
+//! let mut bytes = armor::decode_base64(Cursor::new(&text));
+//! let value: (u32, String) = decode(&mut bytes).unwrap();
+//! assert_eq!(value, (42, String::from("ok")));
+//! ```
+use crate::io::read_exact;
+use crate::prelude::*;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Input bytes converted per write to the underlying writer.
+const CHUNK: usize = 48;
+
+/// Wraps `writer` so bytes written to it are stored as base64 text.
+#[inline(always)]
+pub const fn encode_base64<W: Write>(writer: W) -> Base64Writer<W> {
+    Base64Writer::new(writer)
+}
+
+/// Wraps `reader`, which holds base64 text, so reading from it yields the decoded bytes.
+#[inline(always)]
+pub const fn decode_base64<R: Read>(reader: R) -> Base64Reader<R> {
+    Base64Reader::new(reader)
+}
+
+/// Wraps `writer` so bytes written to it are stored as lowercase hex text.
+#[inline(always)]
+pub const fn encode_hex<W: Write>(writer: W) -> HexWriter<W> {
+    HexWriter::new(writer)
+}
+
+/// Wraps `reader`, which holds hex text, so reading from it yields the decoded bytes.
+#[inline(always)]
+pub const fn decode_hex<R: Read>(reader: R) -> HexReader<R> {
+    HexReader::new(reader)
+}
+
+/// Encodes one to three bytes as a padded group of four base64 characters.
+#[inline(always)]
+fn base64_group(bytes: &[u8]) -> [u8; 4] {
+    let mut triple = [0u8; 3];
+    triple[..bytes.len()].copy_from_slice(bytes);
+    let bits = u32::from_be_bytes([0, triple[0], triple[1], triple[2]]);
+    let mut group = [b'='; 4];
+    for (i, c) in group.iter_mut().enumerate().take(bytes.len() + 1) {
+        *c = BASE64_ALPHABET[(bits >> (18 - 6 * i)) as usize & 0x3f];
+    }
+    group
+}
+
+/// Returns the 6‑bit value of a base64 character.
+#[inline(always)]
+const fn base64_value(c: u8) -> Result<u32> {
+    Ok(match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return Err(Error::InvalidData),
+    } as u32)
+}
+
+/// Returns the 4‑bit value of a hex digit.
+#[inline(always)]
+const fn hex_value(c: u8) -> Result<u8> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(Error::InvalidData),
+    }
+}
+
+/// A [`Write`] adapter that stores everything written to it as base64 text.
+///
+/// Up to two bytes are held back until a group of three is complete; call
+/// [`Base64Writer::finish`] to write them. [`Write::flush`] flushes the underlying writer
+/// but cannot emit a partial group without ending the text.
+pub struct Base64Writer<W: Write> {
+    inner: W,
+    pending: [u8; 3],
+    pending_len: usize,
+}
+
+impl<W: Write> Base64Writer<W> {
+    /// Creates a base64 writer over `inner`.
+    #[inline(always)]
+    pub const fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: [0; 3],
+            pending_len: 0,
+        }
+    }
+
+    /// Returns a reference to the underlying writer. Held‑back bytes are not included.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Writes the held‑back bytes and the padding, flushes, and returns the underlying
+    /// writer.
+    pub fn finish(mut self) -> Result<W> {
+        if self.pending_len > 0 {
+            let group = base64_group(&self.pending[..self.pending_len]);
+            write_whole(&mut self.inner, &group)?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Writes whole groups; `bytes.len()` must be a multiple of three.
+    fn write_groups(&mut self, bytes: &[u8]) -> Result<()> {
+        let mut text = [0u8; CHUNK / 3 * 4];
+        for chunk in bytes.chunks(CHUNK) {
+            let len = chunk.len() / 3 * 4;
+            for (group, triple) in text[..len].chunks_exact_mut(4).zip(chunk.chunks_exact(3)) {
+                group.copy_from_slice(&base64_group(triple));
+            }
+            write_whole(&mut self.inner, &text[..len])?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Base64Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let mut input = buf;
+        if self.pending_len > 0 {
+            let take = (3 - self.pending_len).min(input.len());
+            self.pending[self.pending_len..self.pending_len + take].copy_from_slice(&input[..take]);
+            self.pending_len += take;
+            input = &input[take..];
+            if self.pending_len < 3 {
+                return Ok(buf.len());
+            }
+            let group = base64_group(&self.pending);
+            write_whole(&mut self.inner, &group)?;
+            self.pending_len = 0;
+        }
+        let whole = input.len() / 3 * 3;
+        self.write_groups(&input[..whole])?;
+        let rest = &input[whole..];
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+        Ok(buf.len())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A [`Read`] adapter that decodes base64 text from the underlying reader.
+///
+/// Like [`Cursor`], it fails with [`Error::ReaderOutOfData`] once the text is used up.
+pub struct Base64Reader<R: Read> {
+    inner: R,
+    decoded: [u8; 3],
+    start: usize,
+    end: usize,
+    /// Set once the last group (end of input or padding) has been decoded.
+    done: bool,
+}
+
+impl<R: Read> Base64Reader<R> {
+    /// Creates a base64 reader over `inner`.
+    #[inline(always)]
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            decoded: [0; 3],
+            start: 0,
+            end: 0,
+            done: false,
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the underlying reader. Decoded bytes not yet read are discarded.
+    #[inline(always)]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decodes the next group of up to four characters into `decoded`.
+    fn fill(&mut self) -> Result<()> {
+        let mut chars = [0u8; 4];
+        let mut read = 0;
+        while read < 4 {
+            // Readers report the end of input either way.
+            match self.inner.read(&mut chars[read..]) {
+                Ok(0) | Err(Error::ReaderOutOfData) => break,
+                Ok(n) => read += n,
+                Err(err) => return Err(err),
+            }
+        }
+        let len = chars[..read]
+            .iter()
+            .position(|&c| c == b'=')
+            .unwrap_or(read);
+        if len < 4 {
+            self.done = true;
+        }
+        if read == 0 {
+            return Ok(());
+        }
+        // A group carries at least one byte, and padding must complete it.
+        if len < 2 || (len < read && (read < 4 || chars[len..].iter().any(|&c| c != b'='))) {
+            return Err(Error::InvalidData);
+        }
+        let mut bits = 0u32;
+        for &c in &chars[..len] {
+            bits = (bits << 6) | base64_value(c)?;
+        }
+        bits <<= 6 * (4 - len);
+        let [_, a, b, c] = bits.to_be_bytes();
+        self.decoded = [a, b, c];
+        self.start = 0;
+        self.end = len - 1;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Base64Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            if self.start == self.end {
+                if self.done {
+                    break;
+                }
+                self.fill()?;
+                continue;
+            }
+            let take = (self.end - self.start).min(buf.len() - written);
+            buf[written..written + take]
+                .copy_from_slice(&self.decoded[self.start..self.start + take]);
+            self.start += take;
+            written += take;
+        }
+        if written == 0 && !buf.is_empty() {
+            return Err(Error::ReaderOutOfData);
+        }
+        Ok(written)
+    }
+}
+
+/// A [`Write`] adapter that stores everything written to it as lowercase hex text.
+pub struct HexWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> HexWriter<W> {
+    /// Creates a hex writer over `inner`.
+    #[inline(always)]
+    pub const fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Returns a reference to the underlying writer.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Flushes and returns the underlying writer.
+    #[inline]
+    pub fn finish(mut self) -> Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for HexWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let mut text = [0u8; CHUNK * 2];
+        for chunk in buf.chunks(CHUNK) {
+            for (pair, &byte) in text.chunks_exact_mut(2).zip(chunk) {
+                pair[0] = HEX_DIGITS[(byte >> 4) as usize];
+                pair[1] = HEX_DIGITS[(byte & 0x0f) as usize];
+            }
+            write_whole(&mut self.inner, &text[..chunk.len() * 2])?;
+        }
+        Ok(buf.len())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A [`Read`] adapter that decodes hex text from the underlying reader.
+///
+/// Input ending in half a byte fails with [`Error::ReaderOutOfData`].
+pub struct HexReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> HexReader<R> {
+    /// Creates a hex reader over `inner`.
+    #[inline(always)]
+    pub const fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    /// Returns a reference to the underlying reader.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the underlying reader.
+    #[inline(always)]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for HexReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut text = [0u8; CHUNK * 2];
+        let want = (buf.len() * 2).min(text.len());
+        let mut read = self.inner.read(&mut text[..want])?;
+        if read % 2 == 1 {
+            read_exact(&mut self.inner, &mut text[read..read + 1])?;
+            read += 1;
+        }
+        for (byte, pair) in buf.iter_mut().zip(text[..read].chunks_exact(2)) {
+            *byte = (hex_value(pair[0])? << 4) | hex_value(pair[1])?;
+        }
+        Ok(read / 2)
+    }
+}
+
+#[test]
+fn test_base64_matches_rfc4648_when_streamed_bytewise() {
+    let vectors: [(&[u8], &[u8]); 7] = [
+        (b"", b""),
+        (b"f", b"Zg=="),
+        (b"fo", b"Zm8="),
+        (b"foo", b"Zm9v"),
+        (b"foob", b"Zm9vYg=="),
+        (b"fooba", b"Zm9vYmE="),
+        (b"foobar", b"Zm9vYmFy"),
+    ];
+    for (bytes, text) in vectors {
+        let mut writer = encode_base64(Vec::new());
+        for byte in bytes {
+            writer.write(&[*byte]).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), text);
+
+        let mut reader = decode_base64(Cursor::new(text));
+        let mut out = [0u8; 8];
+        let mut len = 0;
+        while let Ok(n) = reader.read(&mut out[len..len + 1]) {
+            len += n;
+        }
+        assert_eq!(&out[..len], bytes);
+    }
+
+    // Unpadded input, and text after the padding is left unread.
+    let mut out = [0u8; 4];
+    assert_eq!(
+        decode_base64(Cursor::new(b"Zm8")).read(&mut out).unwrap(),
+        2
+    );
+    assert_eq!(&out[..2], b"fo");
+    let mut reader = decode_base64(Cursor::new(b"Zg==,rest"));
+    assert_eq!(reader.read(&mut out).unwrap(), 1);
+    assert_eq!(reader.into_inner().position(), 4);
+
+    for bad in [&b"Zm9v\n"[..], b"Zm=v", b"Z", b"Zm="] {
+        let mut reader = decode_base64(Cursor::new(bad));
+        assert!(matches!(reader.read(&mut out), Err(Error::InvalidData)));
+    }
+}
+
+#[test]
+fn test_armored_payloads_roundtrip_past_chunk_boundaries() {
+    let value: Vec<u64> = (0..200).map(|i| i * 0x0101_0101).collect();
+    let mut raw = Vec::new();
+    encode(&value, &mut raw).unwrap();
+
+    let mut base64 = encode_base64(Vec::new());
+    encode(&value, &mut base64).unwrap();
+    let base64 = base64.finish().unwrap();
+    assert_eq!(base64.len(), raw.len().div_ceil(3) * 4);
+    let decoded: Vec<u64> = decode(&mut decode_base64(Cursor::new(&base64))).unwrap();
+    assert_eq!(decoded, value);
+
+    let mut hex = encode_hex(Vec::new());
+    encode(&value, &mut hex).unwrap();
+    let hex = hex.finish().unwrap();
+    assert_eq!(hex.len(), raw.len() * 2);
+    assert!(hex.iter().all(|c| HEX_DIGITS.contains(c)));
+    let upper = hex.to_ascii_uppercase();
+    let decoded: Vec<u64> = decode(&mut decode_hex(Cursor::new(&upper))).unwrap();
+    assert_eq!(decoded, value);
+
+    let mut out = [0u8; 2];
+    assert!(matches!(
+        decode_hex(Cursor::new(b"abc")).read(&mut out),
+        Err(Error::ReaderOutOfData)
+    ));
+    assert!(matches!(
+        decode_hex(Cursor::new(b"zz")).read(&mut out),
+        Err(Error::InvalidData)
+    ));
+}
@@ -0,0 +1,324 @@
+//! Encoding onto and decoding from `tokio` byte streams (`async` feature).
+//!
+//! [`AsyncEncoder`] and [`AsyncDecoder`] wrap a [`tokio::io::AsyncWrite`] or
+//! [`tokio::io::AsyncRead`], such as a `TcpStream`, and move lencode values over it without a
+//! framing layer: the bytes on the wire are exactly what [`encode`] writes, value after
+//! value.
+//!
+//! [`Encode`] and [`Decode`] impls are synchronous, so a single value is staged in memory:
+//! the encoder writes it to a scratch buffer and then to the stream, and the decoder reads
+//! until the buffered bytes hold a whole value. Sequences are what get large, and those are
+//! streamed: [`AsyncEncoder::encode_seq`] writes the `Vec<T>` layout in chunks as elements
+//! are encoded, and [`AsyncDecoder::decode_seq`] hands each element to a callback as soon as
+//! it arrives, so neither end ever holds the whole sequence in encoded form.
+//!
+//! Values are encoded without an [`EncoderContext`], so each decodes on its own.
+//!
+//! ```
+//! use lencode::prelude::*;
+//! use lencode::async_io::{AsyncDecoder, AsyncEncoder};
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let (client, server) = tokio::io::duplex(64 << 10);
+//! let mut encoder = AsyncEncoder::new(client);
+//! encoder.encode(&String::from("slots")).await.unwrap();
+//! encoder.encode_seq(&(0..10_000u64).collect::<Vec<_>>()).await.unwrap();
+//! drop(encoder);
+//!
+//! let mut decoder = AsyncDecoder::new(server);
+//! assert_eq!(decoder.decode::<String>().await.unwrap().unwrap(), "slots");
+//! let mut sum = 0;
+//! let n = decoder
+//!     .decode_seq(|slot: u64| {
+//!         sum += slot;
+//!         Ok(())
+//!     })
+//!     .await
+//!     .unwrap();
+//! assert_eq!((n, sum), (10_000, 49_995_000));
+//! assert!(decoder.decode::<u64>().await.unwrap().is_none());
+//! # });
+//! ```
+use core::any::TypeId;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::prelude::*;
+
+/// Bytes an [`AsyncEncoder`] stages before writing them out during
+/// [`encode_seq`](AsyncEncoder::encode_seq).
+const DEFAULT_CHUNK_LEN: usize = 64 << 10;
+
+/// Bytes an [`AsyncDecoder`] asks for when its buffer runs dry.
+const MIN_READ_LEN: usize = 8 << 10;
+
+/// Writes lencode values to an [`AsyncWrite`]. See the [module docs](self).
+pub struct AsyncEncoder<W> {
+    inner: W,
+    scratch: Vec<u8>,
+    chunk_len: usize,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncEncoder<W> {
+    /// Creates an encoder writing to `inner`.
+    #[inline]
+    pub const fn new(inner: W) -> Self {
+        Self {
+            inner,
+            scratch: Vec::new(),
+            chunk_len: DEFAULT_CHUNK_LEN,
+        }
+    }
+
+    /// Sets how many bytes [`encode_seq`](Self::encode_seq) stages before writing them to
+    /// the stream, 64 KiB by default.
+    #[inline(always)]
+    pub const fn with_chunk_len(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = chunk_len;
+        self
+    }
+
+    /// Encodes `value` and writes it to the stream. Returns the number of bytes written.
+    pub async fn encode<T: Encode + ?Sized>(&mut self, value: &T) -> Result<usize> {
+        self.scratch.clear();
+        value.encode(&mut self.scratch)?;
+        self.inner.write_all(&self.scratch).await?;
+        Ok(self.scratch.len())
+    }
+
+    /// Writes `items` in the layout of a `Vec<T>`, a chunk at a time, and returns the number
+    /// of bytes written.
+    ///
+    /// Only about [`with_chunk_len`](Self::with_chunk_len) bytes plus one element are staged
+    /// at once. `u8` slices use the byte‑string layout of `Vec<u8>`, which is written whole.
+    pub async fn encode_seq<T: Encode + 'static>(&mut self, items: &[T]) -> Result<usize> {
+        if TypeId::of::<T>() == TypeId::of::<u8>() {
+            // SAFETY: `T` is `u8`.
+            let bytes: &[u8] =
+                unsafe { core::slice::from_raw_parts(items.as_ptr() as *const u8, items.len()) };
+            return self.encode(&bytes).await;
+        }
+        self.scratch.clear();
+        let mut total = Lencode::encode_varint_u64(items.len() as u64, &mut self.scratch)?;
+        for item in items {
+            total += item.encode(&mut self.scratch)?;
+            if self.scratch.len() >= self.chunk_len {
+                self.inner.write_all(&self.scratch).await?;
+                self.scratch.clear();
+            }
+        }
+        self.inner.write_all(&self.scratch).await?;
+        Ok(total)
+    }
+
+    /// Flushes the underlying stream.
+    pub async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await?;
+        Ok(())
+    }
+
+    /// Returns the underlying stream.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the underlying stream.
+    #[inline(always)]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads lencode values from an [`AsyncRead`]. See the [module docs](self).
+pub struct AsyncDecoder<R> {
+    inner: R,
+    /// Bytes read from `inner`; those before `start` are consumed.
+    buf: Vec<u8>,
+    start: usize,
+    eof: bool,
+    max_value_len: usize,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecoder<R> {
+    /// Creates a decoder reading from `inner`.
+    #[inline]
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            start: 0,
+            eof: false,
+            max_value_len: 16 << 20,
+        }
+    }
+
+    /// Sets the largest encoded value (or sequence element) accepted, 16 MiB by default.
+    /// Larger ones fail with [`Error::IncorrectLength`] instead of being buffered.
+    #[inline(always)]
+    pub const fn with_max_value_len(mut self, max_value_len: usize) -> Self {
+        self.max_value_len = max_value_len;
+        self
+    }
+
+    /// Reads and decodes the next value. Returns `Ok(None)` if the stream ends before the
+    /// value's first byte.
+    pub async fn decode<T: Decode>(&mut self) -> Result<Option<T>> {
+        if self.buffered().is_empty() && !self.fill(1).await? {
+            return Ok(None);
+        }
+        self.decode_buffered().await.map(Some)
+    }
+
+    /// Reads a `Vec<T>` encoding and passes each element to `f` as soon as it is decoded,
+    /// instead of collecting them. Returns the number of elements.
+    ///
+    /// An error from `f` stops decoding and is returned. `u8` sequences use the
+    /// byte‑string layout of `Vec<u8>` and are decoded whole.
+    pub async fn decode_seq<T: Decode + 'static>(
+        &mut self,
+        mut f: impl FnMut(T) -> Result<()>,
+    ) -> Result<usize> {
+        if TypeId::of::<T>() == TypeId::of::<u8>() {
+            let bytes: Vec<T> = cast_same(self.decode_buffered::<Vec<u8>>().await?)?;
+            let len = bytes.len();
+            bytes.into_iter().try_for_each(f)?;
+            return Ok(len);
+        }
+        let len = checked_usize(self.decode_buffered::<u64>().await?)?;
+        for _ in 0..len {
+            f(self.decode_buffered().await?)?;
+        }
+        Ok(len)
+    }
+
+    /// Returns the underlying stream. Bytes already read from it but not yet decoded are
+    /// lost.
+    #[inline(always)]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns the bytes read but not yet decoded.
+    #[inline(always)]
+    fn buffered(&self) -> &[u8] {
+        &self.buf[self.start..]
+    }
+
+    /// Decodes a `T` from the buffer, reading more whenever it turns out to be incomplete.
+    ///
+    /// Each retry at least doubles the buffered bytes, so a value is decoded a logarithmic
+    /// number of times in its length, however the stream splits it.
+    async fn decode_buffered<T: Decode>(&mut self) -> Result<T> {
+        loop {
+            let mut cursor = Cursor::new(self.buffered());
+            match T::decode(&mut cursor) {
+                Ok(value) => {
+                    let used = cursor.position();
+                    self.start += used;
+                    return Ok(value);
+                }
+                Err(Error::ReaderOutOfData) if !self.eof => {
+                    let buffered = self.buffered().len();
+                    if buffered >= self.max_value_len {
+                        return Err(Error::IncorrectLength);
+                    }
+                    self.fill((buffered * 2).max(MIN_READ_LEN).min(self.max_value_len))
+                        .await?;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Reads until at least `want` bytes are buffered or the stream ends, reading ahead by up
+    /// to [`MIN_READ_LEN`] bytes but never past the value size limit. Returns `false` if the
+    /// stream ended with nothing buffered.
+    async fn fill(&mut self, want: usize) -> Result<bool> {
+        if self.start > 0 {
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        let limit = want.max(self.max_value_len);
+        while self.buf.len() < want && !self.eof {
+            let filled = self.buf.len();
+            self.buf
+                .resize(want.max(filled + MIN_READ_LEN).min(limit), 0);
+            let read = self.inner.read(&mut self.buf[filled..]).await;
+            self.buf.truncate(filled + read.as_ref().map_or(0, |&n| n));
+            if read? == 0 {
+                self.eof = true;
+            }
+        }
+        Ok(!self.buf.is_empty())
+    }
+}
+
+#[test]
+fn test_async_values_and_sequences_roundtrip() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        // A small pipe forces every value across many partial reads and writes.
+        let (client, server) = tokio::io::duplex(64);
+        let records: Vec<(u64, String)> = (0..2000).map(|i| (i, format!("tx-{i}"))).collect();
+        let blob = vec![7u8; 3000];
+        let writer = async move {
+            let mut encoder = AsyncEncoder::new(client).with_chunk_len(256);
+            encoder.encode(&blob).await.unwrap();
+            let n = encoder.encode_seq(&records).await.unwrap();
+            let mut expected = Vec::new();
+            encode(&records, &mut expected).unwrap();
+            assert_eq!(n, expected.len());
+            encoder.encode_seq(&blob[..10]).await.unwrap();
+            encoder.flush().await.unwrap();
+        };
+        let reader = async move {
+            let mut decoder = AsyncDecoder::new(server);
+            assert_eq!(
+                decoder.decode::<Vec<u8>>().await.unwrap().unwrap().len(),
+                3000
+            );
+            // A `Vec<T>` written by `encode_seq` decodes like any other.
+            let records: Vec<(u64, String)> = decoder.decode().await.unwrap().unwrap();
+            assert_eq!(records.len(), 2000);
+            assert_eq!(records[1999], (1999, String::from("tx-1999")));
+            let mut bytes = Vec::new();
+            let n = decoder
+                .decode_seq(|b: u8| {
+                    bytes.push(b);
+                    Ok(())
+                })
+                .await
+                .unwrap();
+            assert_eq!((n, bytes), (10, vec![7u8; 10]));
+            assert!(decoder.decode::<u64>().await.unwrap().is_none());
+        };
+        tokio::join!(writer, reader);
+
+        // Truncated input and oversized values.
+        let mut decoder = AsyncDecoder::new(&[0x85u8, 1, 2][..]);
+        assert!(matches!(
+            decoder.decode::<u64>().await,
+            Err(Error::ReaderOutOfData)
+        ));
+        let mut buf = Vec::new();
+        encode(&vec![1u32; 100], &mut buf).unwrap();
+        let mut decoder = AsyncDecoder::new(&buf[..]).with_max_value_len(16);
+        assert!(matches!(
+            decoder.decode::<Vec<u32>>().await,
+            Err(Error::IncorrectLength)
+        ));
+        let mut decoder = AsyncDecoder::new(&buf[..]).with_max_value_len(16);
+        let mut sum = 0;
+        decoder
+            .decode_seq(|v: u32| {
+                sum += v;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(sum, 100);
+    });
+}
@@ -0,0 +1,1214 @@
+//! Bit‑level I/O for documents that mix packed bit fields with lencode values.
+//!
+//! [`BitWriter`] and [`BitReader`] wrap a byte [`Write`]/[`Read`] and add
+//! `write_bits`/`read_bits`, most significant bit first. Both also implement the byte traits
+//! themselves, so any [`Encode`]/[`Decode`] impl can run at an arbitrary bit offset; bytes
+//! written while unaligned are shifted across byte boundaries.
+//!
+//! Bits go most significant first by default ([`Msb0`]). [`Lsb0`] fills each byte from its
+//! least significant bit and writes multi‑bit values least significant bit first, as DEFLATE
+//! and many hardware registers do; pick it with [`BitWriter::with_order`] and
+//! [`BitReader::with_order`]. [`BitSlice`] reads bits out of a byte slice at arbitrary
+//! offsets in either order, for codecs that need to peek ahead.
+//!
+//! For small integers with a known skew, the writer and reader also speak two variable‑length
+//! bit codes: Golomb‑Rice ([`BitWriter::write_rice`]) for geometric distributions of a known
+//! mean, and Elias‑gamma ([`BitWriter::write_gamma`]) when the scale is unknown.
+//! [`RiceVec`] and [`GammaVec`] wrap a `Vec<u64>` field to store it with one of them instead
+//! of a varint per element.
+//!
+//! [`encode_to_bits`] and [`decode_from_bits`] pad to the next byte boundary first, so a
+//! lencode body that follows a bit‑packed header is stored byte‑aligned. Aligned writers and
+//! readers pass bytes straight through, which keeps compressed payloads, bulk copies and
+//! zero‑copy reads on their fast paths.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! let mut bits = BitWriter::new(Vec::new());
+//! bits.write_bits(0b101, 3).unwrap(); // version
+//! bits.write_bit(true).unwrap(); // flag
+//! encode_to_bits(&vec![1u32, 2, 3], &mut bits).unwrap();
+//! let buf = bits.into_inner().unwrap();
+//!
+//! let mut bits = BitReader::new(Cursor::new(&buf));
+//! assert_eq!(bits.read_bits(3).unwrap(), 0b101);
+//! assert!(bits.read_bit().unwrap());
+//! let body: Vec<u32> = decode_from_bits(&mut bits).unwrap();
+//! assert_eq!(body, [1, 2, 3]);
+//! ```
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut, Range};
+
+use crate::io::{read_exact, read_vec};
+use crate::prelude::*;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The order in which bits fill a byte and the bits of a multi‑bit value are written:
+/// [`Msb0`] or [`Lsb0`].
+///
+/// Bit I/O works on a *stream order* in which the first bit of every byte is its most
+/// significant one; an order only says how bytes and values map to it, so readers and
+/// writers share one implementation.
+pub trait BitOrder: sealed::Sealed + Copy + Default + 'static {
+    /// Converts a byte between its stored form and stream order. Its own inverse.
+    fn stream_byte(byte: u8) -> u8;
+
+    /// Converts the low `count` bits of `value` between value order and stream order (first
+    /// bit most significant). Bits above `count` are dropped. Its own inverse.
+    fn stream_value(value: u64, count: u32) -> u64;
+}
+
+/// Most significant bit first, in bytes and in values. The default order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Msb0;
+
+impl sealed::Sealed for Msb0 {}
+
+impl BitOrder for Msb0 {
+    #[inline(always)]
+    fn stream_byte(byte: u8) -> u8 {
+        byte
+    }
+
+    #[inline(always)]
+    fn stream_value(value: u64, count: u32) -> u64 {
+        value & low_mask(count)
+    }
+}
+
+/// Least significant bit first, in bytes and in values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Lsb0;
+
+impl sealed::Sealed for Lsb0 {}
+
+impl BitOrder for Lsb0 {
+    #[inline(always)]
+    fn stream_byte(byte: u8) -> u8 {
+        byte.reverse_bits()
+    }
+
+    #[inline(always)]
+    fn stream_value(value: u64, count: u32) -> u64 {
+        if count == 0 {
+            return 0;
+        }
+        value.reverse_bits() >> (64 - count)
+    }
+}
+
+/// Returns a mask of the low `count` bits, for `count <= 64`.
+#[inline(always)]
+const fn low_mask(count: u32) -> u64 {
+    if count >= 64 {
+        u64::MAX
+    } else {
+        (1u64 << count) - 1
+    }
+}
+
+/// Writes individual bits, in the order `O` ([`Msb0`] by default), to an underlying byte
+/// [`Write`].
+///
+/// Bits accumulate until a byte is complete. Call [`BitWriter::align`] or
+/// [`BitWriter::flush_partial`] (or [`BitWriter::into_inner`], which requires alignment) so
+/// a trailing partial byte is not left behind.
+///
+/// A completed byte the underlying writer rejects stays pending, so after an error the
+/// writer is not aligned and the next write, [`align`](BitWriter::align) or
+/// [`flush_partial`](BitWriter::flush_partial) retries it.
+pub struct BitWriter<W: Write, O: BitOrder = Msb0> {
+    inner: W,
+    /// Pending bits in stream order, stored in the high bits of the byte.
+    pending: u8,
+    pending_bits: u32,
+    order: PhantomData<O>,
+}
+
+impl<W: Write> BitWriter<W> {
+    /// Creates a most‑significant‑bit‑first writer positioned at a byte boundary of `inner`.
+    #[inline(always)]
+    pub const fn new(inner: W) -> Self {
+        Self::with_order(inner)
+    }
+}
+
+impl<W: Write, O: BitOrder> BitWriter<W, O> {
+    /// Creates a writer in the order `O`, positioned at a byte boundary of `inner`.
+    ///
+    /// ```
+    /// use lencode::prelude::*;
+    ///
+    /// let mut bits = BitWriter::<_, Lsb0>::with_order(Vec::new());
+    /// bits.write_bits(0b110, 3).unwrap();
+    /// bits.align().unwrap();
+    /// assert_eq!(bits.into_inner().unwrap(), [0b110]);
+    /// ```
+    #[inline(always)]
+    pub const fn with_order(inner: W) -> Self {
+        Self {
+            inner,
+            pending: 0,
+            pending_bits: 0,
+            order: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying writer. Pending bits are not included.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns `true` if no partial byte is pending.
+    #[inline(always)]
+    pub const fn is_aligned(&self) -> bool {
+        self.pending_bits == 0
+    }
+
+    /// Returns the number of bits not yet written to the underlying writer, in `0..=8`.
+    ///
+    /// This is 8 only after the underlying writer rejected a completed byte.
+    #[inline(always)]
+    pub const fn pending_bits(&self) -> u32 {
+        self.pending_bits
+    }
+
+    /// Writes a single bit.
+    #[inline(always)]
+    pub fn write_bit(&mut self, bit: bool) -> Result<()> {
+        self.write_bits(bit as u64, 1)
+    }
+
+    /// Writes the low `count` bits of `value`, most significant first for [`Msb0`] and least
+    /// significant first for [`Lsb0`].
+    ///
+    /// Returns [`Error::IncorrectLength`] if `count` exceeds 64.
+    pub fn write_bits(&mut self, value: u64, count: u32) -> Result<()> {
+        if count > 64 {
+            return Err(Error::IncorrectLength);
+        }
+        let value = O::stream_value(value, count);
+        let mut remaining = count;
+        while remaining > 0 {
+            if self.pending_bits == 8 {
+                self.write_pending()?;
+            }
+            let free = 8 - self.pending_bits;
+            let take = free.min(remaining);
+            let chunk = ((value >> (remaining - take)) & ((1u64 << take) - 1)) as u8;
+            self.pending |= chunk << (free - take);
+            self.pending_bits += take;
+            remaining -= take;
+        }
+        if self.pending_bits == 8 {
+            self.write_pending()?;
+        }
+        Ok(())
+    }
+
+    /// Writes `n` 1 bits followed by a 0 bit, the unary code read by
+    /// [`BitReader::read_ones_max`].
+    pub fn write_ones(&mut self, n: u64) -> Result<()> {
+        self.write_run(true, n)
+    }
+
+    /// Writes `n` 0 bits followed by a 1 bit, the unary code read by
+    /// [`BitReader::read_zeros_max`].
+    pub fn write_zeros(&mut self, n: u64) -> Result<()> {
+        self.write_run(false, n)
+    }
+
+    /// Writes `n` copies of `ones` and then one opposite bit, up to 64 bits at a time.
+    fn write_run(&mut self, ones: bool, mut n: u64) -> Result<()> {
+        let fill = if ones { u64::MAX } else { 0 };
+        while n > 0 {
+            let count = n.min(64) as u32;
+            self.write_bits(fill, count)?;
+            n -= count as u64;
+        }
+        self.write_bit(!ones)
+    }
+
+    /// Writes `value` as a Golomb‑Rice code with parameter `k`: the quotient `value >> k` in
+    /// unary ([`write_ones`](Self::write_ones)), then the low `k` bits.
+    ///
+    /// A value costs `(value >> k) + 1 + k` bits, so `k` should be about
+    /// `log2(mean value)`; values far above the mean get expensive quickly. Returns
+    /// [`Error::IncorrectLength`] if `k` exceeds 64, and [`Error::OutOfRange`] without
+    /// writing anything if the quotient exceeds `u32::MAX`, the most
+    /// [`BitReader::read_rice`] accepts.
+    pub fn write_rice(&mut self, value: u64, k: u32) -> Result<()> {
+        if k > 64 {
+            return Err(Error::IncorrectLength);
+        }
+        let quotient = value.checked_shr(k).unwrap_or(0);
+        if quotient > u32::MAX as u64 {
+            return Err(Error::OutOfRange {
+                value: quotient as i128,
+                max: u32::MAX as i128,
+            });
+        }
+        self.write_ones(quotient)?;
+        self.write_bits(value, k)
+    }
+
+    /// Writes `value` as an Elias‑gamma code of `value + 1`: one 0 bit per bit after the
+    /// leading 1 of `value + 1`, then `value + 1` itself.
+    ///
+    /// A value costs `2 * floor(log2(value + 1)) + 1` bits with no parameter to tune, which
+    /// suits small values of unknown scale. Zero costs a single bit.
+    pub fn write_gamma(&mut self, value: u64) -> Result<()> {
+        let shifted = value as u128 + 1;
+        let width = 127 - shifted.leading_zeros();
+        self.write_zeros(width as u64)?;
+        // The leading 1 was written by `write_zeros`; below it are `width` bits.
+        self.write_bits(shifted as u64, width)
+    }
+
+    /// Writes the completed pending byte, keeping it pending if the underlying writer fails.
+    #[inline(always)]
+    fn write_pending(&mut self) -> Result<()> {
+        write_all(&mut self.inner, &[O::stream_byte(self.pending)])?;
+        self.pending = 0;
+        self.pending_bits = 0;
+        Ok(())
+    }
+
+    /// Pads the pending byte with zero bits and writes it out. Returns the number of padding
+    /// bits written (zero if already aligned).
+    pub fn align(&mut self) -> Result<u32> {
+        if self.pending_bits == 0 {
+            return Ok(0);
+        }
+        let padding = 8 - self.pending_bits;
+        self.write_bits(0, padding)?;
+        Ok(padding)
+    }
+
+    /// Pads and writes out a pending partial byte like [`BitWriter::align`], then flushes the
+    /// underlying writer. Returns the number of padding bits written.
+    ///
+    /// [`Write::flush`] keeps a partial byte pending instead; use this at the end of a
+    /// message, when the receiver has to see every bit written so far.
+    pub fn flush_partial(&mut self) -> Result<u32> {
+        let padding = self.align()?;
+        self.inner.flush()?;
+        Ok(padding)
+    }
+
+    /// Returns the underlying writer.
+    ///
+    /// Fails with [`Error::InvalidData`] if a partial byte is pending; call
+    /// [`BitWriter::align`] first. The error hands the bit writer back, pending bits
+    /// included, so nothing written so far is lost.
+    pub fn into_inner(self) -> Result<W, IntoInnerError<W, O>> {
+        if !self.is_aligned() {
+            return Err(IntoInnerError {
+                writer: self,
+                error: Error::InvalidData,
+            });
+        }
+        Ok(self.inner)
+    }
+}
+
+/// The error returned by [`BitWriter::into_inner`], holding the bit writer it was called
+/// on.
+///
+/// Converts into [`Error`], so `?` still works in functions returning [`Result`].
+pub struct IntoInnerError<W: Write, O: BitOrder = Msb0> {
+    writer: BitWriter<W, O>,
+    error: Error,
+}
+
+impl<W: Write, O: BitOrder> IntoInnerError<W, O> {
+    /// Returns the reason `into_inner` failed.
+    #[inline(always)]
+    pub const fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Returns the bit writer, e.g. to [`align`](BitWriter::align) it and try again.
+    #[inline(always)]
+    pub fn into_bit_writer(self) -> BitWriter<W, O> {
+        self.writer
+    }
+
+    /// Returns the underlying writer, discarding the pending bits.
+    #[inline(always)]
+    pub fn into_inner(self) -> W {
+        self.writer.inner
+    }
+}
+
+impl<W: Write, O: BitOrder> From<IntoInnerError<W, O>> for Error {
+    #[inline(always)]
+    fn from(err: IntoInnerError<W, O>) -> Self {
+        err.error
+    }
+}
+
+impl<W: Write, O: BitOrder> fmt::Debug for IntoInnerError<W, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntoInnerError")
+            .field("error", &self.error)
+            .field("pending_bits", &self.writer.pending_bits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write, O: BitOrder> fmt::Display for IntoInnerError<W, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} bits pending in the bit writer)",
+            self.error, self.writer.pending_bits
+        )
+    }
+}
+
+impl<W: Write, O: BitOrder> core::error::Error for IntoInnerError<W, O> {}
+
+impl<W: Write, O: BitOrder> Write for BitWriter<W, O> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.is_aligned() {
+            return self.inner.write(buf);
+        }
+        ensure_capacity(self, buf.len())?;
+        for &byte in buf {
+            self.write_bits(byte as u64, 8)?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Flushes the underlying writer. A pending partial byte is kept, not padded.
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline(always)]
+    fn buf_mut(&mut self) -> Option<&mut [u8]> {
+        if self.is_aligned() {
+            self.inner.buf_mut()
+        } else {
+            None
+        }
+    }
+
+    #[inline(always)]
+    fn advance_mut(&mut self, n: usize) {
+        self.inner.advance_mut(n)
+    }
+
+    #[inline(always)]
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    /// The underlying writer's remaining capacity, less the byte a pending partial byte will
+    /// take once aligned.
+    #[inline(always)]
+    fn remaining_capacity(&mut self) -> Option<usize> {
+        let pending = !self.is_aligned() as usize;
+        self.inner
+            .remaining_capacity()
+            .map(|free| free.saturating_sub(pending))
+    }
+}
+
+/// Reads individual bits, in the order `O` ([`Msb0`] by default), from an underlying byte
+/// [`Read`].
+pub struct BitReader<R: Read, O: BitOrder = Msb0> {
+    inner: R,
+    /// The current byte in stream order.
+    current: u8,
+    /// Unread bits left in `current`, counted from its low end.
+    remaining_bits: u32,
+    order: PhantomData<O>,
+}
+
+impl<R: Read> BitReader<R> {
+    /// Creates a most‑significant‑bit‑first reader positioned at a byte boundary of `inner`.
+    #[inline(always)]
+    pub const fn new(inner: R) -> Self {
+        Self::with_order(inner)
+    }
+}
+
+impl<R: Read, O: BitOrder> BitReader<R, O> {
+    /// Creates a reader in the order `O`, positioned at a byte boundary of `inner`.
+    #[inline(always)]
+    pub const fn with_order(inner: R) -> Self {
+        Self {
+            inner,
+            current: 0,
+            remaining_bits: 0,
+            order: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Consumes the bit reader and returns the underlying reader. Unread bits of a partially
+    /// consumed byte are discarded.
+    #[inline(always)]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns `true` if no partially read byte is buffered.
+    #[inline(always)]
+    pub const fn is_aligned(&self) -> bool {
+        self.remaining_bits == 0
+    }
+
+    /// Reads a single bit.
+    #[inline(always)]
+    pub fn read_bit(&mut self) -> Result<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Reads a `count`‑bit value written by [`BitWriter::write_bits`] in the same order into the
+    /// low bits of the result.
+    ///
+    /// Returns [`Error::IncorrectLength`] if `count` exceeds 64.
+    pub fn read_bits(&mut self, count: u32) -> Result<u64> {
+        if count > 64 {
+            return Err(Error::IncorrectLength);
+        }
+        let mut out = 0u64;
+        let mut remaining = count;
+        while remaining > 0 {
+            if self.remaining_bits == 0 {
+                self.load_byte()?;
+            }
+            let take = self.remaining_bits.min(remaining);
+            let shift = self.remaining_bits - take;
+            let chunk = (self.current as u64 >> shift) & ((1u64 << take) - 1);
+            out = (out << take) | chunk;
+            self.remaining_bits -= take;
+            remaining -= take;
+        }
+        Ok(O::stream_value(out, count))
+    }
+
+    /// Reads the next byte of `inner` into `current`.
+    #[inline(always)]
+    fn load_byte(&mut self) -> Result<()> {
+        let mut byte = [0u8; 1];
+        read_exact(&mut self.inner, &mut byte)?;
+        self.current = O::stream_byte(byte[0]);
+        self.remaining_bits = 8;
+        Ok(())
+    }
+
+    /// Reads a run of 1 bits ended by a 0 bit (a unary code) and returns the number of 1s.
+    /// The terminating 0 is consumed.
+    ///
+    /// Fails with [`Error::OutOfRange`] once the run exceeds `max`, so hostile input cannot
+    /// keep the reader spinning over an arbitrarily long run.
+    #[inline]
+    pub fn read_ones_max(&mut self, max: u32) -> Result<u32> {
+        self.read_run(true, max)
+    }
+
+    /// Reads a run of 0 bits ended by a 1 bit and returns the number of 0s; see
+    /// [`BitReader::read_ones_max`].
+    #[inline]
+    pub fn read_zeros_max(&mut self, max: u32) -> Result<u32> {
+        self.read_run(false, max)
+    }
+
+    /// Counts the bits equal to `ones` up to the first that differs, a byte at a time.
+    fn read_run(&mut self, ones: bool, max: u32) -> Result<u32> {
+        let mut run = 0u32;
+        loop {
+            if self.remaining_bits == 0 {
+                self.load_byte()?;
+            }
+            // The unread bits, moved to the top of the byte.
+            let unread = self.current << (8 - self.remaining_bits);
+            let same = if ones {
+                unread.leading_ones()
+            } else {
+                unread.leading_zeros()
+            }
+            .min(self.remaining_bits);
+            if run as u64 + same as u64 > max as u64 {
+                return Err(Error::OutOfRange {
+                    value: max as i128 + 1,
+                    max: max as i128,
+                });
+            }
+            run += same;
+            self.remaining_bits -= same;
+            if self.remaining_bits > 0 {
+                // Consume the bit that ended the run.
+                self.remaining_bits -= 1;
+                return Ok(run);
+            }
+        }
+    }
+
+    /// Reads a Golomb‑Rice code written by [`BitWriter::write_rice`] with the same `k`.
+    ///
+    /// Fails with [`Error::OutOfRange`] if the unary quotient exceeds `max_quotient` or the
+    /// value does not fit a `u64`, and with [`Error::IncorrectLength`] if `k` exceeds 64.
+    pub fn read_rice(&mut self, k: u32, max_quotient: u32) -> Result<u64> {
+        if k > 64 {
+            return Err(Error::IncorrectLength);
+        }
+        let quotient = self.read_ones_max(max_quotient)? as u64;
+        let max = u64::MAX.checked_shr(k).unwrap_or(0);
+        if quotient > max {
+            return Err(Error::OutOfRange {
+                value: quotient as i128,
+                max: max as i128,
+            });
+        }
+        Ok(quotient.checked_shl(k).unwrap_or(0) | self.read_bits(k)?)
+    }
+
+    /// Reads an Elias‑gamma code written by [`BitWriter::write_gamma`].
+    ///
+    /// Fails with [`Error::OutOfRange`] if the prefix is longer than any `u64` needs.
+    pub fn read_gamma(&mut self) -> Result<u64> {
+        let width = self.read_zeros_max(64)?;
+        let value = ((1u128 << width) | self.read_bits(width)? as u128) - 1;
+        u64::try_from(value).map_err(|_| Error::OutOfRange {
+            value: value as i128,
+            max: u64::MAX as i128,
+        })
+    }
+
+    /// Skips to the next byte boundary and returns the number of bits skipped.
+    ///
+    /// The skipped bits are the padding written by [`BitWriter::align`] and must be zero;
+    /// anything else is rejected with [`Error::InvalidData`].
+    pub fn align(&mut self) -> Result<u32> {
+        let skipped = self.remaining_bits;
+        if skipped != 0 && self.read_bits(skipped)? != 0 {
+            return Err(Error::InvalidData);
+        }
+        Ok(skipped)
+    }
+}
+
+impl<R: Read, O: BitOrder> Read for BitReader<R, O> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.is_aligned() {
+            return self.inner.read(buf);
+        }
+        for byte in buf.iter_mut() {
+            *byte = self.read_bits(8)? as u8;
+        }
+        Ok(buf.len())
+    }
+
+    #[inline(always)]
+    fn buf(&self) -> Option<&[u8]> {
+        if self.is_aligned() {
+            self.inner.buf()
+        } else {
+            None
+        }
+    }
+
+    #[inline(always)]
+    fn advance(&mut self, n: usize) {
+        self.inner.advance(n)
+    }
+}
+
+/// A read‑only view of a run of bits in a byte slice, in the order `O` ([`Msb0`] by default).
+///
+/// Bit `i` of the view is the `i`th bit a [`BitReader`] of the same order would read, so
+/// [`BitSlice::bits`] returns what [`BitReader::read_bits`] would at that offset. Table‑driven
+/// decoders (Huffman, Golomb) can peek at upcoming bits this way before consuming them.
+///
+/// ```
+/// use lencode::prelude::*;
+///
+/// let view = BitSlice::new(&[0b1011_0010, 0b1100_0000]);
+/// assert_eq!(view.len(), 16);
+/// assert_eq!(view.get(0), Some(true));
+/// assert_eq!(view.bits(6, 4), Some(0b1011));
+/// assert_eq!(view.slice(4..8).unwrap().iter().collect::<Vec<_>>(), [false, false, true, false]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BitSlice<'a, O: BitOrder = Msb0> {
+    bytes: &'a [u8],
+    /// First bit of the view, counted in stream order from the start of `bytes`.
+    start: usize,
+    len: usize,
+    order: PhantomData<O>,
+}
+
+impl<'a> BitSlice<'a> {
+    /// Views every bit of `bytes`, most significant bit first.
+    #[inline(always)]
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        Self::with_order(bytes)
+    }
+}
+
+impl<'a, O: BitOrder> BitSlice<'a, O> {
+    /// Views every bit of `bytes` in the order `O`.
+    #[inline(always)]
+    pub const fn with_order(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            start: 0,
+            len: bytes.len() * 8,
+            order: PhantomData,
+        }
+    }
+
+    /// Returns the number of bits in the view.
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the view holds no bits.
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns bit `index`, or `None` past the end.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < self.len).then(|| self.bit(self.start + index))
+    }
+
+    /// Returns the `count`‑bit value starting at bit `index`, as [`BitReader::read_bits`]
+    /// would read it, or `None` if `count` exceeds 64 or the value runs past the end.
+    pub fn bits(&self, index: usize, count: u32) -> Option<u64> {
+        if count > 64 || index.checked_add(count as usize)? > self.len {
+            return None;
+        }
+        let mut out = 0u64;
+        for pos in self.start + index..self.start + index + count as usize {
+            out = (out << 1) | self.bit(pos) as u64;
+        }
+        Some(O::stream_value(out, count))
+    }
+
+    /// Returns the bits in `range` as a view of their own, or `None` if it is out of bounds.
+    #[inline]
+    pub const fn slice(&self, range: Range<usize>) -> Option<Self> {
+        if range.start > range.end || range.end > self.len {
+            return None;
+        }
+        Some(Self {
+            bytes: self.bytes,
+            start: self.start + range.start,
+            len: range.end - range.start,
+            order: PhantomData,
+        })
+    }
+
+    /// Iterates over the bits of the view.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = bool> + 'a {
+        let view = *self;
+        (view.start..view.start + view.len).map(move |pos| view.bit(pos))
+    }
+
+    /// Returns the bit at stream position `pos` of `bytes`.
+    #[inline(always)]
+    fn bit(&self, pos: usize) -> bool {
+        (O::stream_byte(self.bytes[pos / 8]) >> (7 - pos % 8)) & 1 == 1
+    }
+}
+
+/// The reader over a bit‑packed payload, as handed to [`read_bit_packed`] callbacks.
+pub(crate) type PayloadBits<'a> = BitReader<Cursor<&'a [u8]>>;
+
+/// Writes `count` codes as `varint(count)`, `varint(byte_len)` and the bits `write_codes`
+/// emits, zero‑padded to a byte boundary: the layout of [`RiceVec`] and [`GammaVec`].
+pub(crate) fn write_bit_packed(
+    writer: &mut impl Write,
+    count: usize,
+    write_codes: impl FnOnce(&mut BitWriter<Vec<u8>>) -> Result<()>,
+) -> Result<usize> {
+    let mut bits = BitWriter::new(Vec::new());
+    write_codes(&mut bits)?;
+    bits.align()?;
+    let payload = bits.into_inner()?;
+    let mut total = Lencode::encode_varint_u64(count as u64, writer)?;
+    total += Lencode::encode_varint_u64(payload.len() as u64, writer)?;
+    total += write_all(writer, &payload)?;
+    Ok(total)
+}
+
+/// Reads a payload written by [`write_bit_packed`], calling `read_code` once per code.
+///
+/// Every code must take at least one bit, and the payload must end with the last code's
+/// padding; anything else is rejected with [`Error::InvalidData`].
+pub(crate) fn read_bit_packed<T>(
+    reader: &mut impl Read,
+    mut read_code: impl FnMut(&mut PayloadBits<'_>) -> Result<T>,
+) -> Result<Vec<T>> {
+    let count = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    let payload = read_vec(reader, checked_usize(Lencode::decode_varint_u64(reader)?)?)?;
+    if count > payload.len().saturating_mul(8) {
+        return Err(Error::InvalidData);
+    }
+    let mut bits = BitReader::new(Cursor::new(&payload[..]));
+    let mut out = Vec::with_capacity(count);
+    for _ in 0..count {
+        out.push(read_code(&mut bits)?);
+    }
+    bits.align()?;
+    // Whole padding bytes would give one list several encodings.
+    if bits.get_ref().position() != payload.len() {
+        return Err(Error::InvalidData);
+    }
+    Ok(out)
+}
+
+/// A `Vec<u64>` encoded with a Golomb‑Rice code of parameter `K` instead of varints.
+///
+/// Wire format: `varint(count)`, `varint(byte_len)`, then `byte_len` bytes holding each
+/// value as [`BitWriter::write_rice`] writes it, most significant bit first and zero‑padded
+/// to a byte boundary. For geometrically distributed values with a mean near `2^K` — run
+/// lengths, gaps between sorted IDs — this takes about `K + 2` bits per value, where a varint
+/// needs at least a byte; values far above the mean cost one extra bit per `2^K`.
+///
+/// ```
+/// use lencode::prelude::*;
+///
+/// let gaps = RiceVec::<2>(vec![3, 0, 5, 1, 2, 4, 0, 3]);
+/// let mut buf = Vec::new();
+/// assert_eq!(encode(&gaps, &mut buf).unwrap(), 2 + 4);
+/// assert_eq!(decode::<RiceVec<2>>(&mut Cursor::new(&buf)).unwrap(), gaps);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RiceVec<const K: u32>(pub Vec<u64>);
+
+/// A `Vec<u64>` encoded with an Elias‑gamma code instead of varints.
+///
+/// Same wire format as [`RiceVec`], with each value written by [`BitWriter::write_gamma`].
+/// Gamma needs no parameter: a value costs `2 * floor(log2(value + 1)) + 1` bits, so zero
+/// and one take a single bit and three bits, and the code stays reasonable when the scale of
+/// the values is not known in advance.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GammaVec(pub Vec<u64>);
+
+macro_rules! impl_bit_coded_vec {
+    ($([$($generics:tt)*] $name:ty, $write:expr, $read:expr);* $(;)?) => {
+        $(
+            impl<$($generics)*> Deref for $name {
+                type Target = Vec<u64>;
+
+                #[inline(always)]
+                fn deref(&self) -> &Self::Target {
+                    &self.0
+                }
+            }
+
+            impl<$($generics)*> DerefMut for $name {
+                #[inline(always)]
+                fn deref_mut(&mut self) -> &mut Self::Target {
+                    &mut self.0
+                }
+            }
+
+            impl<$($generics)*> From<Vec<u64>> for $name {
+                #[inline(always)]
+                fn from(value: Vec<u64>) -> Self {
+                    Self(value)
+                }
+            }
+
+            impl<$($generics)*> FromIterator<u64> for $name {
+                #[inline(always)]
+                fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+                    Self(iter.into_iter().collect())
+                }
+            }
+
+            impl<$($generics)*> Encode for $name {
+                #[inline]
+                fn encode_ext(
+                    &self,
+                    writer: &mut impl Write,
+                    _ctx: Option<&mut EncoderContext>,
+                ) -> Result<usize> {
+                    write_bit_packed(writer, self.0.len(), |bits| {
+                        self.0.iter().try_for_each(|&value| $write(&mut *bits, value))
+                    })
+                }
+            }
+
+            impl<$($generics)*> Decode for $name {
+                #[inline]
+                fn decode_ext(
+                    reader: &mut impl Read,
+                    _ctx: Option<&mut DecoderContext>,
+                ) -> Result<Self> {
+                    read_bit_packed(reader, $read).map(Self)
+                }
+            }
+        )*
+    };
+}
+
+impl_bit_coded_vec! {
+    [const K: u32] RiceVec<K>,
+        |bits: &mut BitWriter<Vec<u8>>, value| bits.write_rice(value, K),
+        |bits: &mut PayloadBits<'_>| bits.read_rice(K, u32::MAX);
+    [] GammaVec,
+        |bits: &mut BitWriter<Vec<u8>>, value| bits.write_gamma(value),
+        |bits: &mut PayloadBits<'_>| bits.read_gamma();
+}
+
+/// Pads `writer` to a byte boundary, then encodes `value` into it.
+///
+/// Returns the number of bytes the value occupies, not counting the padding.
+#[inline]
+pub fn encode_to_bits<T: Encode + ?Sized, W: Write, O: BitOrder>(
+    value: &T,
+    writer: &mut BitWriter<W, O>,
+) -> Result<usize> {
+    writer.align()?;
+    value.encode_ext(writer, None)
+}
+
+/// Skips `reader` to the next byte boundary, then decodes a `T` written by
+/// [`encode_to_bits`].
+#[inline]
+pub fn decode_from_bits<T: Decode, R: Read, O: BitOrder>(
+    reader: &mut BitReader<R, O>,
+) -> Result<T> {
+    reader.align()?;
+    T::decode_ext(reader, None)
+}
+
+#[test]
+fn test_bits_roundtrip_across_byte_boundaries() {
+    let fields = [(0b1u64, 1), (0x2a, 7), (0x3ff, 10), (u64::MAX, 64), (0, 3)];
+    let mut writer = BitWriter::new(Vec::new());
+    for &(value, count) in &fields {
+        writer.write_bits(value, count).unwrap();
+    }
+    assert_eq!(writer.align().unwrap(), 3);
+    let buf = writer.into_inner().unwrap();
+    assert_eq!(buf.len(), 11);
+    assert_eq!(buf[0], 0b1010_1010);
+
+    let mut reader = BitReader::new(Cursor::new(&buf));
+    for &(value, count) in &fields {
+        assert_eq!(reader.read_bits(count).unwrap(), value);
+    }
+    assert_eq!(reader.align().unwrap(), 3);
+    assert!(matches!(reader.read_bit(), Err(Error::ReaderOutOfData)));
+    assert!(matches!(
+        BitWriter::new(Vec::new()).write_bits(0, 65),
+        Err(Error::IncorrectLength)
+    ));
+}
+
+#[test]
+fn test_unaligned_values_and_aligned_bodies() {
+    let text = String::from("x").repeat(400);
+    let mut writer = BitWriter::new(Vec::new());
+    writer.write_bits(0b10, 2).unwrap();
+    // Encoded at a 2-bit offset: every byte is shifted across a boundary.
+    300u64.encode(&mut writer).unwrap();
+    writer.write_bit(true).unwrap();
+    // Aligned body: the compressed string goes straight to the inner writer.
+    let body_len = encode_to_bits(&text, &mut writer).unwrap();
+    assert!(writer.is_aligned());
+    let buf = writer.into_inner().unwrap();
+
+    let mut plain = Vec::new();
+    assert_eq!(encode(&text, &mut plain).unwrap(), body_len);
+    assert_eq!(&buf[buf.len() - body_len..], &plain[..]);
+
+    let mut reader = BitReader::new(Cursor::new(&buf));
+    assert_eq!(reader.read_bits(2).unwrap(), 0b10);
+    assert_eq!(u64::decode(&mut reader).unwrap(), 300);
+    assert!(reader.read_bit().unwrap());
+    assert_eq!(decode_from_bits::<String, _, _>(&mut reader).unwrap(), text);
+}
+
+#[test]
+fn test_bit_padding_must_be_zero() {
+    let mut writer = BitWriter::new(Vec::new());
+    writer.write_bits(0b1, 1).unwrap();
+    let err = writer.into_inner().unwrap_err();
+    assert!(matches!(err.error(), Error::InvalidData));
+    assert!(matches!(Error::from(err), Error::InvalidData));
+
+    let mut reader = BitReader::new(Cursor::new(&[0b1000_0001u8, 7][..]));
+    assert!(reader.read_bit().unwrap());
+    assert!(matches!(
+        decode_from_bits::<u8, _, _>(&mut reader),
+        Err(Error::InvalidData)
+    ));
+}
+
+#[test]
+fn test_bit_writer_recovers_from_errors() {
+    let mut writer = BitWriter::new(vec![0xffu8]);
+    writer.write_bits(0b101, 3).unwrap();
+    // The failed `into_inner` hands the writer back with its pending bits.
+    let mut writer = writer.into_inner().unwrap_err().into_bit_writer();
+    assert_eq!(writer.pending_bits(), 3);
+    assert_eq!(writer.flush_partial().unwrap(), 5);
+    assert_eq!(writer.flush_partial().unwrap(), 0);
+    assert_eq!(writer.into_inner().unwrap(), [0xff, 0b1010_0000]);
+
+    // A byte the underlying writer rejects stays pending and is retried.
+    let mut storage = [0u8; 1];
+    let mut writer = BitWriter::new(Cursor::new(&mut storage[..]));
+    writer.write_bits(0xab, 8).unwrap();
+    writer.write_bits(0b11, 2).unwrap();
+    assert!(matches!(
+        writer.write_bits(0b11_1111, 6),
+        Err(Error::WriterOutOfSpace)
+    ));
+    assert_eq!(writer.pending_bits(), 8);
+    assert!(matches!(
+        writer.flush_partial(),
+        Err(Error::WriterOutOfSpace)
+    ));
+    let err = writer.into_inner().unwrap_err();
+    assert!(matches!(err.error(), Error::InvalidData));
+    assert_eq!(err.into_inner().position(), 1);
+    assert_eq!(storage, [0xab]);
+}
+
+#[test]
+fn test_bounded_unary_runs() {
+    let mut writer = BitWriter::new(Vec::new());
+    for (value, count) in [
+        (0b1110, 4),
+        (0b0001, 4),
+        (u64::MAX, 13),
+        (0b0, 1),
+        (0b011, 3),
+    ] {
+        writer.write_bits(value, count).unwrap();
+    }
+    writer.align().unwrap();
+    let buf = writer.into_inner().unwrap();
+
+    let mut reader = BitReader::new(Cursor::new(&buf));
+    assert_eq!(reader.read_ones_max(3).unwrap(), 3);
+    assert_eq!(reader.read_zeros_max(3).unwrap(), 3);
+    // A run crossing byte boundaries.
+    assert_eq!(reader.read_ones_max(13).unwrap(), 13);
+    assert_eq!(reader.read_zeros_max(1).unwrap(), 1);
+    assert_eq!(reader.read_zeros_max(0).unwrap(), 0);
+    assert_eq!(reader.align().unwrap(), 7);
+
+    // Runs longer than the cap are rejected without reading further.
+    let hostile = [0xffu8; 64];
+    let mut reader = BitReader::new(Cursor::new(&hostile[..]));
+    assert!(matches!(
+        reader.read_ones_max(20),
+        Err(Error::OutOfRange { value: 21, max: 20 })
+    ));
+    assert_eq!(reader.get_ref().position(), 3);
+    let mut reader = BitReader::new(Cursor::new(&hostile[..]));
+    assert!(matches!(
+        reader.read_ones_max(u32::MAX),
+        Err(Error::ReaderOutOfData)
+    ));
+}
+
+#[test]
+fn test_lsb0_bits_and_bytes_roundtrip() {
+    let fields = [
+        (0b1u64, 1),
+        (0x2a, 7),
+        (0x3ff, 10),
+        (u64::MAX, 64),
+        (0b011, 3),
+    ];
+    let mut writer = BitWriter::<_, Lsb0>::with_order(Vec::new());
+    for &(value, count) in &fields {
+        writer.write_bits(value, count).unwrap();
+    }
+    // Unaligned bytes are shifted across boundaries in this order too.
+    300u64.encode(&mut writer).unwrap();
+    assert_eq!(writer.flush_partial().unwrap(), 3);
+    let buf = writer.into_inner().unwrap();
+    // The first byte holds `1`, then the low 7 bits of 0x2a, filled from the low end.
+    assert_eq!(buf[0], 0b0101_0101);
+
+    let mut reader = BitReader::<_, Lsb0>::with_order(Cursor::new(&buf));
+    for &(value, count) in &fields {
+        assert_eq!(reader.read_bits(count).unwrap(), value);
+    }
+    assert_eq!(u64::decode(&mut reader).unwrap(), 300);
+    assert_eq!(reader.align().unwrap(), 3);
+
+    // Unary runs follow the stream order as well.
+    let mut reader = BitReader::<_, Lsb0>::with_order(Cursor::new(&[0b0000_0111u8][..]));
+    assert_eq!(reader.read_ones_max(8).unwrap(), 3);
+}
+
+#[test]
+fn test_bit_slice_matches_readers() {
+    let bytes = [0b1011_0010u8, 0x5c, 0xe1, 0x07];
+    let msb = BitSlice::new(&bytes);
+    let lsb = BitSlice::<Lsb0>::with_order(&bytes);
+    for (offset, count) in [(0, 1), (3, 9), (7, 17), (0, 32)] {
+        let mut reader = BitReader::new(Cursor::new(&bytes[..]));
+        reader.read_bits(offset as u32).unwrap();
+        assert_eq!(
+            msb.bits(offset, count),
+            Some(reader.read_bits(count).unwrap())
+        );
+        let mut reader = BitReader::<_, Lsb0>::with_order(Cursor::new(&bytes[..]));
+        reader.read_bits(offset as u32).unwrap();
+        assert_eq!(
+            lsb.bits(offset, count),
+            Some(reader.read_bits(count).unwrap())
+        );
+    }
+    assert_eq!(lsb.get(1), Some(true));
+    assert_eq!(lsb.get(32), None);
+    assert_eq!(msb.bits(30, 3), None);
+
+    let middle = msb.slice(6..20).unwrap();
+    assert_eq!(middle.len(), 14);
+    assert_eq!(middle.bits(0, 14), msb.bits(6, 14));
+    assert_eq!(
+        middle.slice(2..5).unwrap().iter().collect::<Vec<_>>(),
+        [false, true, false]
+    );
+    assert!(middle.slice(10..15).is_none());
+    assert!(msb.slice(4..4).unwrap().is_empty());
+}
+
+#[test]
+fn test_rice_and_gamma_codes_roundtrip() {
+    let values = [0u64, 1, 2, 3, 7, 8, 100, 1 << 20, u64::MAX >> 1, u64::MAX];
+    let mut writer = BitWriter::<_, Lsb0>::with_order(Vec::new());
+    for &value in &values {
+        writer.write_gamma(value).unwrap();
+        writer.write_rice(value.min(1 << 12), 4).unwrap();
+        writer.write_rice(value, 64).unwrap();
+    }
+    writer.align().unwrap();
+    let buf = writer.into_inner().unwrap();
+
+    let mut reader = BitReader::<_, Lsb0>::with_order(Cursor::new(&buf));
+    for &value in &values {
+        assert_eq!(reader.read_gamma().unwrap(), value);
+        assert_eq!(reader.read_rice(4, 256).unwrap(), value.min(1 << 12));
+        assert_eq!(reader.read_rice(64, 0).unwrap(), value);
+    }
+    reader.align().unwrap();
+    assert_eq!(reader.get_ref().position(), buf.len());
+
+    // Gamma: 0 → `1`, 1 → `010`, 2 → `011`, 3 → `00100`.
+    let mut writer = BitWriter::new(Vec::new());
+    for value in 0..4 {
+        writer.write_gamma(value).unwrap();
+    }
+    assert_eq!(writer.flush_partial().unwrap(), 4);
+    assert_eq!(writer.get_ref(), &[0b1010_0110, 0b0100_0000]);
+
+    // A quotient above the cap, and gamma codes for values past `u64::MAX`.
+    let written = |write: &dyn Fn(&mut BitWriter<Vec<u8>>) -> Result<()>| {
+        let mut writer = BitWriter::new(Vec::new());
+        write(&mut writer).unwrap();
+        writer.flush_partial().unwrap();
+        writer.into_inner().unwrap()
+    };
+    let buf = written(&|w| w.write_rice(40, 2));
+    assert!(matches!(
+        BitReader::new(Cursor::new(&buf)).read_rice(2, 9),
+        Err(Error::OutOfRange { value: 10, max: 9 })
+    ));
+    // Quotients past `u32::MAX` could not be read back, so nothing is written for them.
+    let mut writer = BitWriter::new(Vec::new());
+    for (value, k) in [
+        (1u64 << 40, 0),
+        (u64::MAX, 4),
+        ((u32::MAX as u64 + 1) << 8, 8),
+    ] {
+        assert!(matches!(
+            writer.write_rice(value, k),
+            Err(Error::OutOfRange { max, .. }) if max == u32::MAX as i128
+        ));
+    }
+    assert_eq!(writer.flush_partial().unwrap(), 0);
+    assert!(writer.get_ref().is_empty());
+    assert!(matches!(
+        RiceVec::<0>(vec![1 << 40]).encode(&mut Vec::new()),
+        Err(Error::OutOfRange { .. })
+    ));
+    let buf = written(&|w| w.write_zeros(65));
+    assert!(matches!(
+        BitReader::new(Cursor::new(&buf)).read_gamma(),
+        Err(Error::OutOfRange { value: 65, max: 64 })
+    ));
+    let buf = written(&|w| {
+        w.write_zeros(64)?;
+        w.write_bits(1, 64)
+    });
+    assert!(matches!(
+        BitReader::new(Cursor::new(&buf)).read_gamma(),
+        Err(Error::OutOfRange { .. })
+    ));
+}
+
+#[test]
+fn test_bit_coded_vecs_beat_varints_on_small_values() {
+    let gaps: Vec<u64> = (0..1000u64).map(|i| (i * 7919) % 13).collect();
+    let mut plain = Vec::new();
+    gaps.encode(&mut plain).unwrap();
+
+    let rice = RiceVec::<3>(gaps.clone());
+    let gamma: GammaVec = gaps.iter().copied().collect();
+    let mut rice_buf = Vec::new();
+    let mut gamma_buf = Vec::new();
+    rice.encode(&mut rice_buf).unwrap();
+    gamma.encode(&mut gamma_buf).unwrap();
+    assert!(rice_buf.len() * 3 < plain.len() * 2);
+    assert!(gamma_buf.len() < plain.len());
+    assert_eq!(
+        decode::<RiceVec<3>>(&mut Cursor::new(&rice_buf)).unwrap(),
+        rice
+    );
+    assert_eq!(
+        decode::<GammaVec>(&mut Cursor::new(&gamma_buf)).unwrap(),
+        gamma
+    );
+
+    // Trailing padding bytes and impossible counts are rejected.
+    let mut padded = Vec::new();
+    GammaVec(vec![0]).encode(&mut padded).unwrap();
+    assert_eq!(padded, [1, 1, 0b1000_0000]);
+    padded[1] = 2;
+    padded.push(0);
+    assert!(matches!(
+        decode::<GammaVec>(&mut Cursor::new(&padded)),
+        Err(Error::InvalidData)
+    ));
+    assert!(matches!(
+        decode::<GammaVec>(&mut Cursor::new(&[9u8, 1, 0xff])),
+        Err(Error::InvalidData)
+    ));
+}
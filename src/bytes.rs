@@ -3,9 +3,15 @@
 //! This module provides zstd‑based compression/decompression for contiguous `u8` collections
 //! in a `no_std`‑compatible manner using `zstd-safe`.
 //!
+//! Which payloads are compressed, and at which level, is set by the [`CompressionPolicy`] and
+//! [`compression_level`](EncoderContext::compression_level) of the [`EncoderContext`]; see
+//! [`write_flagged_compressed`]. Without the `zstd` feature nothing is compressed, so every
+//! payload is written raw with the flag bit clear, which any decoder reads. The
+//! decompression helpers fail with [`DecompressionErrorKind::Unsupported`] instead.
+//!
 //! An entropy heuristic ([`looks_incompressible`]) samples the first 32 bytes of a payload
-//! and skips compression when the data appears random, avoiding wasted CPU on high‑entropy
-//! inputs.
+//! and, under the default policy, skips compression when the data appears random, avoiding
+//! wasted CPU on high‑entropy inputs.
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
@@ -14,13 +20,6 @@ use crate::prelude::*;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-/// zstd compression level used for byte-collections.
-const ZSTD_LEVEL: i32 = 1;
-
-/// Minimum payload size to attempt compression. Below this threshold,
-/// raw bytes are always used because compression overhead outweighs savings.
-pub(crate) const MIN_COMPRESS_LEN: usize = 64;
-
 /// Quick entropy check: returns `true` if a sample of the data appears incompressible.
 ///
 /// Samples the first 32 bytes and counts distinct byte values using a 256‑bit
@@ -43,13 +42,37 @@ pub(crate) fn looks_incompressible(data: &[u8]) -> bool {
     distinct >= 28
 }
 
-/// Compresses `input` with zstd, returning the compressed bytes.
+/// Writes `data` as a flagged byte string, compressed if the [`CompressionPolicy`] of `ctx`
+/// (the default one without a context) selects it and compression makes it smaller.
+#[inline(always)]
+pub(crate) fn write_flagged_compressed(
+    writer: &mut impl Write,
+    data: &[u8],
+    ctx: Option<&EncoderContext>,
+) -> Result<usize> {
+    let (policy, level) = ctx.map_or(
+        (CompressionPolicy::default(), DEFAULT_COMPRESSION_LEVEL),
+        |ctx| (ctx.compression, ctx.compression_level),
+    );
+    if policy.should_compress(data)
+        && let Some(compressed) = zstd_compress_with(data, level, None)?
+        && compressed.len() + flagged_header_len(compressed.len(), true)
+            < data.len() + flagged_header_len(data.len(), false)
+    {
+        return write_flagged(writer, &compressed, true);
+    }
+    write_flagged(writer, data, false)
+}
+
+/// Compresses `input` with zstd at [`DEFAULT_COMPRESSION_LEVEL`], returning the compressed
+/// bytes.
+#[cfg(feature = "zstd")]
 #[inline(always)]
 pub fn zstd_compress(input: &[u8]) -> Result<Vec<u8>> {
     // Upper bound for compressed size
     let bound = zstd_safe::compress_bound(input.len());
     let mut out = vec![0u8; bound];
-    let written = match zstd_safe::compress(&mut out[..], input, ZSTD_LEVEL) {
+    let written = match zstd_safe::compress(&mut out[..], input, DEFAULT_COMPRESSION_LEVEL) {
         Ok(n) => n,
         Err(_) => return Err(Error::InvalidData),
     };
@@ -61,27 +84,154 @@ pub fn zstd_compress(input: &[u8]) -> Result<Vec<u8>> {
 #[inline(always)]
 pub fn zstd_decompress(compressed: &[u8], original_len: usize) -> Result<Vec<u8>> {
     let mut out = vec![0u8; original_len];
-    let written = match zstd_safe::decompress(&mut out[..], compressed) {
-        Ok(n) => n,
-        Err(_) => return Err(Error::InvalidData),
+    zstd_decompress_into(compressed, &mut out)?;
+    Ok(out)
+}
+
+/// Decompresses `compressed` into `out`, which must be exactly the original length.
+#[cfg(feature = "zstd")]
+#[inline(always)]
+pub fn zstd_decompress_into(compressed: &[u8], out: &mut [u8]) -> Result<()> {
+    let written = zstd_safe::decompress(out, compressed).map_err(|_| corrupt())?;
+    if written != out.len() {
+        return Err(Error::LengthMismatch {
+            expected: out.len(),
+            actual: written,
+        });
+    }
+    Ok(())
+}
+
+/// Compresses `input` at `level`, optionally against a raw‑content `dict`. Returns `None`
+/// when built without the `zstd` feature.
+#[cfg(feature = "zstd")]
+pub(crate) fn zstd_compress_with(
+    input: &[u8],
+    level: i32,
+    dict: Option<&[u8]>,
+) -> Result<Option<Vec<u8>>> {
+    let bound = zstd_safe::compress_bound(input.len());
+    let mut out = vec![0u8; bound];
+    let mut cctx = zstd_safe::CCtx::create();
+    let written = match dict {
+        Some(dict) => cctx.compress_using_dict(&mut out[..], input, dict, level),
+        None => cctx.compress(&mut out[..], input, level),
+    }
+    .map_err(|_| Error::InvalidData)?;
+    out.truncate(written);
+    Ok(Some(out))
+}
+
+/// Decompresses a frame produced by [`zstd_compress_with`] into exactly `original_len`
+/// bytes.
+#[cfg(feature = "zstd")]
+pub(crate) fn zstd_decompress_with(
+    compressed: &[u8],
+    original_len: usize,
+    dict: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    let Some(dict) = dict else {
+        return zstd_decompress(compressed, original_len);
     };
+    let mut out = vec![0u8; original_len];
+    let written = zstd_safe::DCtx::create()
+        .decompress_using_dict(&mut out[..], compressed, dict)
+        .map_err(|_| corrupt())?;
     if written != original_len {
-        return Err(Error::IncorrectLength);
+        return Err(Error::LengthMismatch {
+            expected: original_len,
+            actual: written,
+        });
     }
     Ok(out)
 }
 
+/// Largest output zstd can produce from `compressed_len` bytes. Every block has a 3‑byte
+/// header and at least one byte of content, and decompresses to at most 128 KiB.
+#[cfg(feature = "zstd")]
+#[inline(always)]
+pub(crate) const fn zstd_max_content_size(compressed_len: usize) -> usize {
+    (compressed_len / 4).saturating_mul(128 << 10)
+}
+
 /// Returns the frame's declared content size, if present.
+///
+/// A size that `compressed` could not decompress to is forged and rejected as corrupt, so
+/// callers can allocate the output before decompressing.
+#[cfg(feature = "zstd")]
 #[inline(always)]
 pub fn zstd_content_size(compressed: &[u8]) -> Result<usize> {
     match zstd_safe::get_frame_content_size(compressed) {
-        Ok(Some(n)) => Ok(n as usize),
-        _ => Err(Error::InvalidData),
+        Ok(Some(n)) => match crate::checked_usize(n)? {
+            n if n > zstd_max_content_size(compressed.len()) => Err(corrupt()),
+            n => Ok(n),
+        },
+        Ok(None) => Err(Error::DecompressionFailed {
+            kind: DecompressionErrorKind::UnknownContentSize,
+        }),
+        Err(_) => Err(corrupt()),
     }
 }
 
+#[cfg(feature = "zstd")]
 #[inline(always)]
-const fn varint_len_usize(mut val: usize) -> usize {
+const fn corrupt() -> Error {
+    Error::DecompressionFailed {
+        kind: DecompressionErrorKind::Corrupt,
+    }
+}
+
+// Stand‑ins for builds without the `zstd` feature. Encoders never reach `zstd_compress`
+// (see `CompressionPolicy::should_compress`); decoders reject compressed payloads instead of misreading them.
+
+#[cfg(not(feature = "zstd"))]
+#[inline(always)]
+pub const fn zstd_compress(_input: &[u8]) -> Result<Vec<u8>> {
+    Err(unsupported())
+}
+
+#[cfg(not(feature = "zstd"))]
+#[inline(always)]
+pub const fn zstd_decompress_into(_compressed: &[u8], _out: &mut [u8]) -> Result<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(feature = "zstd"))]
+#[inline(always)]
+pub(crate) const fn zstd_compress_with(
+    _input: &[u8],
+    _level: i32,
+    _dict: Option<&[u8]>,
+) -> Result<Option<Vec<u8>>> {
+    Ok(None)
+}
+
+#[cfg(not(feature = "zstd"))]
+#[inline(always)]
+pub(crate) const fn zstd_decompress_with(
+    _compressed: &[u8],
+    _original_len: usize,
+    _dict: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    Err(unsupported())
+}
+
+#[cfg(not(feature = "zstd"))]
+#[inline(always)]
+pub const fn zstd_content_size(_compressed: &[u8]) -> Result<usize> {
+    Err(unsupported())
+}
+
+#[cfg(not(feature = "zstd"))]
+#[inline(always)]
+const fn unsupported() -> Error {
+    Error::DecompressionFailed {
+        kind: DecompressionErrorKind::Unsupported,
+    }
+}
+
+#[inline(always)]
+pub(crate) const fn varint_len_usize(mut val: usize) -> usize {
     if val <= 127 {
         return 1;
     }
@@ -102,3 +252,21 @@ pub const fn flagged_header_len(payload_len: usize, compressed: bool) -> usize {
     let v = (payload_len << 1) | (compressed as usize);
     varint_len_usize(v)
 }
+
+/// Writes a flagged header followed by `payload`, or nothing if the writer reports too
+/// little [`Write::remaining_capacity`] for both.
+#[inline(always)]
+pub(crate) fn write_flagged(
+    writer: &mut impl Write,
+    payload: &[u8],
+    compressed: bool,
+) -> Result<usize> {
+    ensure_capacity(
+        writer,
+        flagged_header_len(payload.len(), compressed) + payload.len(),
+    )?;
+    let flagged = (payload.len() << 1) | (compressed as usize);
+    let mut total = Lencode::encode_varint_u64(flagged as u64, writer)?;
+    total += write_all(writer, payload)?;
+    Ok(total)
+}
@@ -0,0 +1,194 @@
+//! Shipping errors across a stream.
+//!
+//! [`CapturedError`] records what can be learned about any [`core::error::Error`] — its type
+//! name, its `Display` message and the messages of its [`source`](core::error::Error::source)
+//! chain — in a form that encodes like any other value, so a service can send a failure to
+//! its caller without agreeing on an error enum or a string convention first:
+//!
+//! ```text
+//! [type_name: str] [message: str] [source_count: varint] [source message: str]*
+//! ```
+//!
+//! The decoded record implements `Error` and `Display` itself, so it can be returned or
+//! wrapped on the receiving side. The original type is not reconstructed.
+//!
+//! ```
+//! use lencode::prelude::*;
+//! use core::fmt;
+//!
+//! #[derive(Debug)]
+//! struct Timeout;
+//!
+//! impl fmt::Display for Timeout {
+//!     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//!         f.write_str("upstream timed out")
+//!     }
+//! }
+//!
+//! impl core::error::Error for Timeout {}
+//!
+//! type Reply = core::result::Result<u32, CapturedError>;
+//!
+//! let mut buf = Vec::new();
+//! let reply: Reply = Err(CapturedError::capture(&Timeout));
+//! encode(&reply, &mut buf).unwrap();
+//!
+//! let decoded: Reply = decode(&mut Cursor::new(&buf)).unwrap();
+//! let err = decoded.unwrap_err();
+//! assert!(err.type_name().ends_with("Timeout"));
+//! assert_eq!(err.message(), "upstream timed out");
+//! ```
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::prelude::*;
+
+/// A structured record of an error. See the [module docs](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CapturedError {
+    type_name: String,
+    message: String,
+    sources: Vec<String>,
+}
+
+impl CapturedError {
+    /// Creates a record from its parts.
+    #[inline]
+    pub fn new(
+        type_name: impl Into<String>,
+        message: impl Into<String>,
+        sources: impl IntoIterator<Item = String>,
+    ) -> Self {
+        Self {
+            type_name: type_name.into(),
+            message: message.into(),
+            sources: sources.into_iter().collect(),
+        }
+    }
+
+    /// Captures `err`: its type name, its message, and the message of every error in its
+    /// source chain, outermost first.
+    ///
+    /// The type name comes from [`core::any::type_name`], so for a trait object such as
+    /// `Box<dyn Error>` it names the trait object, not the concrete type behind it. The source
+    /// chain is only available as trait objects, so only its messages are kept.
+    pub fn capture<E: core::error::Error + ?Sized>(err: &E) -> Self {
+        let mut sources = Vec::new();
+        let mut next = err.source();
+        while let Some(source) = next {
+            sources.push(source.to_string());
+            next = source.source();
+        }
+        Self {
+            type_name: String::from(core::any::type_name::<E>()),
+            message: err.to_string(),
+            sources,
+        }
+    }
+
+    /// Returns the name of the captured error's type.
+    #[inline(always)]
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns the captured error's `Display` message.
+    #[inline(always)]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the messages of the captured error's sources, outermost first.
+    #[inline(always)]
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+}
+
+impl<E: core::error::Error + ?Sized> From<&E> for CapturedError {
+    #[inline(always)]
+    fn from(err: &E) -> Self {
+        Self::capture(err)
+    }
+}
+
+/// Writes the message; the alternate form (`{:#}`) appends each source as `": {source}"`.
+impl fmt::Display for CapturedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if f.alternate() {
+            for source in &self.sources {
+                write!(f, ": {source}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for CapturedError {}
+
+impl Encode for CapturedError {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total = self.type_name.encode_ext(writer, ctx.as_deref_mut())?;
+        total += self.message.encode_ext(writer, ctx.as_deref_mut())?;
+        total += self.sources.encode_ext(writer, ctx)?;
+        Ok(total)
+    }
+}
+
+impl Decode for CapturedError {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            type_name: String::decode_ext(reader, ctx.as_deref_mut())?,
+            message: String::decode_ext(reader, ctx.as_deref_mut())?,
+            sources: Vec::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_captured_error_keeps_type_message_and_sources() {
+    #[derive(Debug)]
+    struct Load(Error);
+    impl fmt::Display for Load {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("failed to load snapshot")
+        }
+    }
+    impl std::error::Error for Load {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+    let io = std::io::Error::new(std::io::ErrorKind::NotFound, "ledger.db missing");
+    let load = Load(Error::StdIo(io));
+
+    let captured = CapturedError::capture(&load);
+    assert!(captured.type_name().ends_with("Load"));
+    assert_eq!(captured.sources(), ["IO error: ledger.db missing"]);
+    let mut buf = Vec::new();
+    encode(&captured, &mut buf).unwrap();
+    let decoded: CapturedError = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, captured);
+    assert_eq!(decoded.to_string(), "failed to load snapshot");
+    assert_eq!(
+        format!("{decoded:#}"),
+        "failed to load snapshot: IO error: ledger.db missing"
+    );
+
+    let boxed: Box<dyn std::error::Error + Send + Sync> = "plain".into();
+    let captured = CapturedError::from(&*boxed);
+    assert_eq!(captured.message(), "plain");
+    assert!(captured.type_name().starts_with("dyn "));
+}
@@ -0,0 +1,241 @@
+//! Per‑call encoding overrides through a [`CodecRegistry`].
+//!
+//! A [`Codec<T>`] encodes and decodes a `T` in a layout of its choosing. Registering one
+//! under a name in a [`CodecRegistry`] and passing the registry through the
+//! [`EncoderContext`]/[`DecoderContext`] lets a single call use that layout for fields
+//! marked `#[lencode(codec = "name")]`, without touching the `Encode`/`Decode` impls of the
+//! field's type. When the context carries no registry, or no codec of that name for the
+//! field's type, the field falls back to its regular layout.
+//!
+//! Encoder and decoder must register the same codecs: the choice is not recorded on the
+//! wire.
+//!
+//! ```
+//! use lencode::prelude::*;
+//! use std::sync::Arc;
+//!
+//! /// Writes a sorted `Vec<u64>` as the differences between neighbors.
+//! struct Deltas;
+//!
+//! impl Codec<Vec<u64>> for Deltas {
+//!     fn encode(
+//!         &self,
+//!         value: &Vec<u64>,
+//!         mut writer: &mut dyn Write,
+//!         _ctx: Option<&mut EncoderContext>,
+//!     ) -> Result<usize> {
+//!         let mut total = Lencode::encode_varint_u64(value.len() as u64, &mut writer)?;
+//!         let mut prev = 0;
+//!         for &v in value {
+//!             total += Lencode::encode_varint_u64(v - prev, &mut writer)?;
+//!             prev = v;
+//!         }
+//!         Ok(total)
+//!     }
+//!
+//!     fn decode(&self, mut reader: &mut dyn Read, _ctx: Option<&mut DecoderContext>) -> Result<Vec<u64>> {
+//!         let len = Lencode::decode_varint_u64(&mut reader)? as usize;
+//!         let mut prev = 0;
+//!         (0..len)
+//!             .map(|_| {
+//!                 prev += Lencode::decode_varint_u64(&mut reader)?;
+//!                 Ok(prev)
+//!             })
+//!             .collect()
+//!     }
+//! }
+//!
+//! #[derive(Encode, Decode, Debug, PartialEq)]
+//! struct Block {
+//!     #[lencode(codec = "deltas")]
+//!     slots: Vec<u64>,
+//! }
+//!
+//! let codecs = Arc::new(CodecRegistry::new().with::<Vec<u64>>("deltas", Deltas));
+//! let block = Block { slots: vec![300_000_000, 300_000_001, 300_000_003] };
+//!
+//! let mut buf = Vec::new();
+//! block.encode_ext(&mut buf, Some(&mut EncoderContext::with_codecs(codecs.clone()))).unwrap();
+//! assert_eq!(buf.len(), 8);
+//! let mut ctx = DecoderContext::with_codecs(codecs);
+//! assert_eq!(Block::decode_ext(&mut Cursor::new(&buf), Some(&mut ctx)).unwrap(), block);
+//! ```
+use core::any::{Any, TypeId};
+use core::fmt;
+use hashbrown::HashMap;
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(all(test, not(feature = "std")))]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::boxed::Box;
+#[cfg(all(test, feature = "std"))]
+use std::sync::Arc;
+
+use crate::prelude::*;
+
+/// An alternative encoding for `T`. See the [module docs](self).
+///
+/// The reader and writer are trait objects so codecs can be stored in a [`CodecRegistry`];
+/// `&mut dyn Read` and `&mut dyn Write` implement [`Read`] and [`Write`], so they can be
+/// passed on to regular `encode_ext`/`decode_ext` calls.
+pub trait Codec<T>: Send + Sync {
+    /// Writes `value` and returns the number of bytes written.
+    fn encode(
+        &self,
+        value: &T,
+        writer: &mut dyn Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize>;
+
+    /// Reads a value written by [`Codec::encode`].
+    fn decode(&self, reader: &mut dyn Read, ctx: Option<&mut DecoderContext>) -> Result<T>;
+}
+
+/// Named [`Codec`]s, looked up by name and value type.
+///
+/// Share one registry between contexts through an `Arc`
+/// ([`EncoderContext::with_codecs`], [`DecoderContext::with_codecs`]).
+#[derive(Default)]
+pub struct CodecRegistry {
+    codecs: HashMap<(TypeId, &'static str), Box<dyn Any + Send + Sync>>,
+}
+
+impl fmt::Debug for CodecRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.codecs.keys().map(|(_, name)| name))
+            .finish()
+    }
+}
+
+impl CodecRegistry {
+    /// Creates an empty registry.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `codec` for values of type `T` under `name`, replacing any codec previously
+    /// registered for the same name and type.
+    pub fn register<T: 'static>(&mut self, name: &'static str, codec: impl Codec<T> + 'static) {
+        let codec: Box<dyn Codec<T>> = Box::new(codec);
+        self.codecs
+            .insert((TypeId::of::<T>(), name), Box::new(codec));
+    }
+
+    /// Returns the registry with `codec` registered; see [`CodecRegistry::register`].
+    #[inline(always)]
+    pub fn with<T: 'static>(mut self, name: &'static str, codec: impl Codec<T> + 'static) -> Self {
+        self.register::<T>(name, codec);
+        self
+    }
+
+    /// Returns the codec registered for values of type `T` under `name`.
+    pub fn get<T: 'static>(&self, name: &'static str) -> Option<&dyn Codec<T>> {
+        self.codecs
+            .get(&(TypeId::of::<T>(), name))
+            .and_then(|codec| codec.downcast_ref::<Box<dyn Codec<T>>>())
+            .map(|codec| &**codec)
+    }
+
+    /// Returns the number of registered codecs.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.codecs.len()
+    }
+
+    /// Returns `true` if no codecs are registered.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.codecs.is_empty()
+    }
+}
+
+/// Encodes `value` with the codec registered in `ctx` under `name`, or with its own
+/// [`Encode`] impl if there is none. Used by `#[lencode(codec = "...")]` fields.
+pub fn encode_with_codec<T: Encode + 'static>(
+    name: &'static str,
+    value: &T,
+    writer: &mut impl Write,
+    ctx: Option<&mut EncoderContext>,
+) -> Result<usize> {
+    let codecs = ctx.as_ref().and_then(|ctx| ctx.codecs.clone());
+    match codecs.as_deref().and_then(|codecs| codecs.get::<T>(name)) {
+        Some(codec) => codec.encode(value, writer, ctx),
+        None => value.encode_ext(writer, ctx),
+    }
+}
+
+/// Decodes a `T` with the codec registered in `ctx` under `name`, or with its own
+/// [`Decode`] impl if there is none. Used by `#[lencode(codec = "...")]` fields.
+pub fn decode_with_codec<T: Decode + 'static>(
+    name: &'static str,
+    reader: &mut impl Read,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<T> {
+    let codecs = ctx.as_ref().and_then(|ctx| ctx.codecs.clone());
+    match codecs.as_deref().and_then(|codecs| codecs.get::<T>(name)) {
+        Some(codec) => codec.decode(reader, ctx),
+        None => T::decode_ext(reader, ctx),
+    }
+}
+
+#[cfg(test)]
+struct Fixed32;
+
+#[cfg(test)]
+impl Codec<u64> for Fixed32 {
+    fn encode(
+        &self,
+        value: &u64,
+        writer: &mut dyn Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let value = u32::try_from(*value).map_err(|_| Error::InvalidData)?;
+        writer.write(&value.to_le_bytes())
+    }
+
+    fn decode(&self, mut reader: &mut dyn Read, _ctx: Option<&mut DecoderContext>) -> Result<u64> {
+        let mut bytes = [0u8; 4];
+        read_exact(&mut reader, &mut bytes)?;
+        Ok(u32::from_le_bytes(bytes) as u64)
+    }
+}
+
+#[test]
+fn test_codec_lookup_and_fallback() {
+    let codecs = Arc::new(CodecRegistry::new().with::<u64>("fixed32", Fixed32));
+    assert_eq!(codecs.len(), 1);
+    assert!(codecs.get::<u64>("fixed32").is_some());
+    // Codecs are keyed by value type as well as name.
+    assert!(codecs.get::<u32>("fixed32").is_none());
+
+    let mut buf = Vec::new();
+    let mut ctx = EncoderContext::with_codecs(codecs.clone());
+    assert_eq!(
+        encode_with_codec("fixed32", &7u64, &mut buf, Some(&mut ctx)).unwrap(),
+        4
+    );
+    assert_eq!(
+        encode_with_codec("other", &7u64, &mut buf, Some(&mut ctx)).unwrap(),
+        1
+    );
+    assert_eq!(
+        encode_with_codec("fixed32", &7u64, &mut buf, None).unwrap(),
+        1
+    );
+    assert_eq!(&buf[..4], &[7, 0, 0, 0]);
+
+    let mut cursor = Cursor::new(&buf);
+    let mut ctx = DecoderContext::with_codecs(codecs);
+    for name in ["fixed32", "other"] {
+        let value: u64 = decode_with_codec(name, &mut cursor, Some(&mut ctx)).unwrap();
+        assert_eq!(value, 7);
+    }
+    assert_eq!(
+        decode_with_codec::<u64>("fixed32", &mut cursor, None).unwrap(),
+        7
+    );
+}
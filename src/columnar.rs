@@ -0,0 +1,247 @@
+//! Column‑split (struct‑of‑arrays) encoding for record batches.
+//!
+//! A `Vec<T>` normally encodes row by row, interleaving the fields of each element.
+//! [`Columnar<Vec<T>>`] instead writes every field of `T` as its own column: all the
+//! timestamps, then all the slots, then all the amounts. Values of the same field sit next
+//! to each other, so each column is compressed on its own through the byte‑string layout,
+//! and numeric columns with repeating or slowly changing values shrink far more than the
+//! interleaved rows would. Rows are reassembled on decode.
+//!
+//! Implement [`Columns`] with `#[derive(Columns)]`.
+//!
+//! ## Wire format
+//!
+//! ```text
+//! [rows: varint]
+//! [column 0: bytes]   // field 0 of every row, encoded as a compressible &[u8]
+//! [column 1: bytes]
+//! ...
+//! ```
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! #[derive(Encode, Decode, Columns, Clone, Debug, PartialEq)]
+//! struct Tick {
+//!     slot: u64,
+//!     price: u64,
+//!     side: bool,
+//! }
+//!
+//! let ticks: Vec<Tick> = (0..500)
+//!     .map(|i| Tick { slot: 250_000_000 + i / 4, price: 1_000_000, side: i % 2 == 0 })
+//!     .collect();
+//! let mut rows = Vec::new();
+//! encode(&ticks, &mut rows).unwrap();
+//! let mut columns = Vec::new();
+//! encode(&Columnar(ticks.clone()), &mut columns).unwrap();
+//! # #[cfg(feature = "zstd")]
+//! assert!(columns.len() < rows.len() / 4);
+//!
+//! let decoded: Columnar<Vec<Tick>> = decode(&mut Cursor::new(&columns)).unwrap();
+//! assert_eq!(decoded.0, ticks);
+//! ```
+use core::ops::{Deref, DerefMut};
+
+use crate::prelude::*;
+
+/// Record types that can be encoded column by column. Implement with
+/// `#[derive(Columns)]`; see the [module docs](self).
+pub trait Columns: Sized {
+    /// Writes every field of `rows` as its own column. Returns the number of bytes written.
+    fn encode_columns(
+        rows: &[Self],
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize>;
+
+    /// Reads the columns of `rows` records written by [`Columns::encode_columns`] and
+    /// reassembles them.
+    fn decode_columns(
+        reader: &mut impl Read,
+        rows: usize,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Vec<Self>>;
+}
+
+/// A collection encoded column by column. See the [module docs](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Columnar<T>(pub T);
+
+impl<T> Columnar<T> {
+    /// Consumes the wrapper and returns the inner collection.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Columnar<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Columnar<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Columnar<T> {
+    #[inline(always)]
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Columns> Encode for Columnar<Vec<T>> {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total = Lencode::encode_varint_u64(self.0.len() as u64, writer)?;
+        total += T::encode_columns(&self.0, writer, ctx)?;
+        Ok(total)
+    }
+}
+
+impl<T: Columns> Decode for Columnar<Vec<T>> {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        let rows = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+        Ok(Self(T::decode_columns(reader, rows, ctx)?))
+    }
+}
+
+/// Writes one column: `encode_value` for every row into a scratch buffer, then the buffer as
+/// a byte string. Used by `#[derive(Columns)]`.
+#[doc(hidden)]
+pub fn encode_column<R>(
+    rows: &[R],
+    writer: &mut impl Write,
+    mut ctx: Option<&mut EncoderContext>,
+    mut encode_value: impl FnMut(&R, &mut Vec<u8>, Option<&mut EncoderContext>) -> Result<usize>,
+) -> Result<usize> {
+    let mut column = Vec::new();
+    for row in rows {
+        encode_value(row, &mut column, ctx.as_deref_mut())?;
+    }
+    // Compressed per the context's policy, but never diff-encoded: columns are read back
+    // without a context.
+    crate::bytes::write_flagged_compressed(writer, &column, ctx.as_deref())
+}
+
+/// Reads one column of `rows` values written by [`encode_column`]. Used by
+/// `#[derive(Columns)]`.
+#[doc(hidden)]
+pub fn decode_column<V>(
+    reader: &mut impl Read,
+    rows: usize,
+    mut ctx: Option<&mut DecoderContext>,
+    mut decode_value: impl FnMut(&mut Cursor<&[u8]>, Option<&mut DecoderContext>) -> Result<V>,
+) -> Result<Vec<V>> {
+    let column = Vec::<u8>::decode_ext(reader, None)?;
+    let mut column_reader = Cursor::new(&column[..]);
+    let mut values = Vec::with_capacity(prealloc_capacity::<V>(rows));
+    for _ in 0..rows {
+        values.push(decode_value(&mut column_reader, ctx.as_deref_mut())?);
+    }
+    if column_reader.position() != column.len() {
+        return Err(Error::InvalidData);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq)]
+struct Sample {
+    slot: u64,
+    label: String,
+}
+
+#[cfg(test)]
+impl Columns for Sample {
+    fn encode_columns(
+        rows: &[Self],
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total = encode_column(rows, writer, ctx.as_deref_mut(), |row, writer, ctx| {
+            row.slot.encode_ext(writer, ctx)
+        })?;
+        total += encode_column(rows, writer, ctx, |row, writer, ctx| {
+            row.label.encode_ext(writer, ctx)
+        })?;
+        Ok(total)
+    }
+
+    fn decode_columns(
+        reader: &mut impl Read,
+        rows: usize,
+        mut ctx: Option<&mut DecoderContext>,
+    ) -> Result<Vec<Self>> {
+        let slots = decode_column(reader, rows, ctx.as_deref_mut(), |reader, ctx| {
+            u64::decode_ext(reader, ctx)
+        })?;
+        let labels = decode_column(reader, rows, ctx, |reader, ctx| {
+            String::decode_ext(reader, ctx)
+        })?;
+        Ok(slots
+            .into_iter()
+            .zip(labels)
+            .map(|(slot, label)| Self { slot, label })
+            .collect())
+    }
+}
+
+#[test]
+fn test_columnar_layout_and_roundtrip() {
+    let rows: Vec<Sample> = (0..3)
+        .map(|i| Sample {
+            slot: 100 + i,
+            label: String::from("ok"),
+        })
+        .collect();
+    let mut buf = Vec::new();
+    let n = encode(&Columnar(rows.clone()), &mut buf).unwrap();
+    assert_eq!(n, buf.len());
+    // rows, then the slot column (3 one-byte varints), then the label column.
+    assert_eq!(&buf[..5], &[3, 3 << 1, 100, 101, 102]);
+    assert_eq!(buf[5], 9 << 1);
+    let decoded: Columnar<Vec<Sample>> = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded.into_inner(), rows);
+
+    let empty: Columnar<Vec<Sample>> = decode(&mut Cursor::new(&[0u8, 0, 0][..])).unwrap();
+    assert!(empty.is_empty());
+    // A column holding more bytes than its rows use is rejected.
+    buf[0] = 2;
+    assert!(decode::<Columnar<Vec<Sample>>>(&mut Cursor::new(&buf)).is_err());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_columnar_honors_compression_policy() {
+    let rows: Vec<Sample> = (0..100)
+        .map(|_| Sample {
+            slot: 7,
+            label: String::from("ok"),
+        })
+        .collect();
+    let slot_column_flag = |ctx: Option<&mut EncoderContext>| {
+        let mut buf = Vec::new();
+        encode_ext(&Columnar(rows.clone()), &mut buf, ctx).unwrap();
+        let mut reader = Cursor::new(&buf);
+        Lencode::decode_varint_u64(&mut reader).unwrap();
+        Lencode::decode_varint_u64(&mut reader).unwrap() & 1
+    };
+    assert_eq!(slot_column_flag(None), 1);
+    let mut never = EncoderContext::with_compression(CompressionPolicy::Never);
+    assert_eq!(slot_column_flag(Some(&mut never)), 0);
+}
@@ -0,0 +1,130 @@
+//! Importer for legacy bincode archives of Solana types.
+//!
+//! Existing Solana tooling (blockstore columns, ledger archives, RPC caches) persists
+//! `VersionedTransaction` and `TransactionStatusMeta` using bincode's legacy layout:
+//! fixed‑width little‑endian integers, `u64` length prefixes and `u32` enum tags, with
+//! `short_vec` compact‑u16 prefixes inside transactions. Status metadata is stored as
+//! [`StoredTransactionStatusMeta`], which is converted to
+//! [`TransactionStatusMeta`](solana_transaction_status::TransactionStatusMeta) here.
+//!
+//! The `decode_*` functions return the decoded value together with the number of input bytes
+//! consumed, so concatenated records can be walked. The `transcode_*` functions re‑encode a
+//! record as lencode in one step.
+use solana_storage_proto::StoredTransactionStatusMeta;
+use solana_transaction::versioned::VersionedTransaction;
+use solana_transaction_status::TransactionStatusMeta;
+
+use crate::prelude::*;
+
+/// Decodes a bincode‑encoded `VersionedTransaction` from the start of `bytes`.
+///
+/// Returns the transaction and the number of bytes consumed.
+pub fn decode_versioned_transaction(bytes: &[u8]) -> Result<(VersionedTransaction, usize)> {
+    ::bincode::serde::decode_from_slice(bytes, ::bincode::config::legacy())
+        .map_err(|_| Error::InvalidData)
+}
+
+/// Decodes a bincode‑encoded `StoredTransactionStatusMeta` from the start of `bytes` and
+/// converts it to a `TransactionStatusMeta`.
+///
+/// Returns the metadata and the number of bytes consumed.
+pub fn decode_transaction_status_meta(bytes: &[u8]) -> Result<(TransactionStatusMeta, usize)> {
+    let (stored, consumed): (StoredTransactionStatusMeta, usize) =
+        ::bincode::serde::decode_from_slice(bytes, ::bincode::config::legacy())
+            .map_err(|_| Error::InvalidData)?;
+    Ok((stored.into(), consumed))
+}
+
+/// Reads one bincode‑encoded `VersionedTransaction` from the start of `bytes` and writes it
+/// to `writer` as lencode.
+///
+/// Returns `(bytes consumed, bytes written)`.
+pub fn transcode_versioned_transaction(
+    bytes: &[u8],
+    writer: &mut impl Write,
+    ctx: Option<&mut EncoderContext>,
+) -> Result<(usize, usize)> {
+    let (tx, consumed) = decode_versioned_transaction(bytes)?;
+    Ok((consumed, tx.encode_ext(writer, ctx)?))
+}
+
+/// Reads one bincode‑encoded `StoredTransactionStatusMeta` from the start of `bytes` and
+/// writes it to `writer` as a lencode `TransactionStatusMeta`.
+///
+/// Returns `(bytes consumed, bytes written)`.
+pub fn transcode_transaction_status_meta(
+    bytes: &[u8],
+    writer: &mut impl Write,
+    ctx: Option<&mut EncoderContext>,
+) -> Result<(usize, usize)> {
+    let (meta, consumed) = decode_transaction_status_meta(bytes)?;
+    Ok((consumed, meta.encode_ext(writer, ctx)?))
+}
+
+#[test]
+fn test_transcode_versioned_transaction() {
+    use solana_hash::Hash;
+    use solana_message::{Message, MessageHeader, VersionedMessage};
+    use solana_pubkey::Pubkey;
+    use solana_signature::Signature;
+
+    let tx = VersionedTransaction {
+        signatures: vec![Signature::from([7u8; 64])],
+        message: VersionedMessage::Legacy(Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![Pubkey::new_from_array([1u8; 32]), Pubkey::default()],
+            recent_blockhash: Hash::new_from_array([9u8; 32]),
+            instructions: vec![],
+        }),
+    };
+    let mut legacy = ::bincode::serde::encode_to_vec(&tx, ::bincode::config::legacy()).unwrap();
+    let record_len = legacy.len();
+    // A second record follows; only the first must be consumed.
+    legacy.extend_from_slice(&legacy.clone());
+
+    let mut buf = Vec::new();
+    let (consumed, written) = transcode_versioned_transaction(&legacy, &mut buf, None).unwrap();
+    assert_eq!(consumed, record_len);
+    assert_eq!(written, buf.len());
+    let decoded: VersionedTransaction = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, tx);
+
+    assert!(matches!(
+        decode_versioned_transaction(&legacy[..record_len - 1]),
+        Err(Error::InvalidData)
+    ));
+}
+
+#[test]
+fn test_transcode_transaction_status_meta() {
+    let meta = TransactionStatusMeta {
+        fee: 5000,
+        pre_balances: vec![10_000, 1],
+        post_balances: vec![5_000, 1],
+        log_messages: Some(vec!["Program log: hello".to_string()]),
+        compute_units_consumed: Some(1_200),
+        ..Default::default()
+    };
+    let stored = StoredTransactionStatusMeta::try_from(meta.clone()).unwrap();
+    let mut legacy = ::bincode::serde::encode_to_vec(&stored, ::bincode::config::legacy()).unwrap();
+    let record_len = legacy.len();
+    // A second record follows; only the first must be consumed.
+    legacy.extend_from_slice(&legacy.clone());
+
+    let mut buf = Vec::new();
+    let (consumed, written) = transcode_transaction_status_meta(&legacy, &mut buf, None).unwrap();
+    assert_eq!(consumed, record_len);
+    assert_eq!(written, buf.len());
+    let decoded: TransactionStatusMeta = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, meta);
+
+    // Cut inside `fee`, before any of the fields that default at end of input.
+    assert!(matches!(
+        decode_transaction_status_meta(&legacy[..6]),
+        Err(Error::InvalidData)
+    ));
+}
@@ -0,0 +1,6 @@
+//! Importers for data written by other serialization formats.
+//!
+//! Each submodule reads a foreign layout and hands back values that implement
+//! [`Encode`](crate::Encode), so existing archives can be migrated to lencode in‑process.
+#[cfg(feature = "solana-bincode")]
+pub mod bincode;
@@ -1,9 +1,79 @@
-//! Unified encoding/decoding context that bundles optional deduplication and diff state.
+//! Unified encoding/decoding context that bundles optional deduplication and diff state,
+//! along with policies such as [`CompressionPolicy`] on the encode side and
+//! [`DuplicateKeyPolicy`] and [`Utf8Policy`] on the decode side.
+//!
+//! Every `encode_ext`/`decode_ext` (and every derived impl) takes an
+//! `Option<&mut EncoderContext>`/`Option<&mut DecoderContext>`, so new settings are added as
+//! context fields rather than as extra parameters. A bare [`DedupeEncoder`], [`DiffEncoder`],
+//! [`DedupeDecoder`] or [`DiffDecoder`] converts into a context with `.into()`.
 
+#[cfg(not(feature = "std"))]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use crate::Result;
+use crate::codec::CodecRegistry;
 use crate::dedupe::{DedupeDecoder, DedupeEncoder};
 use crate::diff::{DiffDecoder, DiffEncoder};
+#[cfg(feature = "hooks")]
+use crate::hooks::{EncodeHooks, EncodeObserver};
+use crate::intern::StringInterner;
+use crate::io::Error;
+use crate::metrics::EncodeMetrics;
+
+/// Default for [`DecoderContext::max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Default for [`EncoderContext::compression_level`].
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 1;
+
+/// Payload size from which the default [`CompressionPolicy`] tries compression. Below it,
+/// zstd's frame overhead outweighs any savings.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 64;
+
+/// Which byte and string payloads (`Vec<u8>`, `&[u8]`, `String`, `&str`, …) encoders try to
+/// compress with zstd.
+///
+/// Each payload's header carries a flag saying whether it was compressed, so the policy only
+/// affects encoding: decoders read every policy's output, and a compressed payload is only
+/// written when it is smaller than the raw one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CompressionPolicy {
+    /// Never compress, for latency‑sensitive encoders that would rather spend bytes than CPU.
+    Never,
+    /// Try every payload, however small or random it looks.
+    Always,
+    /// Try payloads of at least this many bytes unless a sample of them looks random (the
+    /// default, with [`DEFAULT_COMPRESSION_THRESHOLD`]).
+    SizeThreshold(usize),
+}
+
+impl Default for CompressionPolicy {
+    fn default() -> Self {
+        Self::SizeThreshold(DEFAULT_COMPRESSION_THRESHOLD)
+    }
+}
+
+impl CompressionPolicy {
+    /// Returns `true` if `data` is worth trying to compress under this policy. Always `false`
+    /// without the `zstd` feature.
+    #[inline(always)]
+    pub fn should_compress(self, data: &[u8]) -> bool {
+        cfg!(feature = "zstd")
+            && match self {
+                Self::Never => false,
+                Self::Always => true,
+                Self::SizeThreshold(min_len) => {
+                    data.len() >= min_len && !crate::bytes::looks_incompressible(data)
+                }
+            }
+    }
+}
 
-/// Bundles optional [`DedupeEncoder`] and [`DiffEncoder`] state for encoding.
+/// Bundles optional [`DedupeEncoder`], [`DiffEncoder`] and [`EncodeMetrics`] state for
+/// encoding.
 ///
 /// Pass `Some(&mut EncoderContext)` to [`Encode::encode_ext`] when you want
 /// deduplication, diff encoding, or both. Individual components are optional:
@@ -13,6 +83,18 @@ pub struct EncoderContext {
     pub dedupe: Option<DedupeEncoder>,
     /// Optional diff encoder for byte blobs.
     pub diff: Option<DiffEncoder>,
+    /// Optional per‑field byte statistics.
+    pub metrics: Option<EncodeMetrics>,
+    /// Optional [`Codec`](crate::codec::Codec)s for `#[lencode(codec = "...")]` fields.
+    pub codecs: Option<Arc<CodecRegistry>>,
+    /// Optional per‑value [`EncodeHooks`].
+    #[cfg(feature = "hooks")]
+    pub hooks: Option<EncodeHooks>,
+    /// Which byte and string payloads are compressed.
+    pub compression: CompressionPolicy,
+    /// zstd level (1–22) for compressed payloads. Defaults to
+    /// [`DEFAULT_COMPRESSION_LEVEL`]; higher levels shrink text further at a steep CPU cost.
+    pub compression_level: i32,
 }
 
 impl Default for EncoderContext {
@@ -28,6 +110,12 @@ impl EncoderContext {
         Self {
             dedupe: None,
             diff: None,
+            metrics: None,
+            codecs: None,
+            #[cfg(feature = "hooks")]
+            hooks: None,
+            compression: CompressionPolicy::SizeThreshold(DEFAULT_COMPRESSION_THRESHOLD),
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
         }
     }
 
@@ -36,7 +124,7 @@ impl EncoderContext {
     pub fn with_dedupe() -> Self {
         Self {
             dedupe: Some(DedupeEncoder::new()),
-            diff: None,
+            ..Self::new()
         }
     }
 
@@ -44,8 +132,8 @@ impl EncoderContext {
     #[inline(always)]
     pub fn with_diff() -> Self {
         Self {
-            dedupe: None,
             diff: Some(DiffEncoder::new()),
+            ..Self::new()
         }
     }
 
@@ -55,10 +143,157 @@ impl EncoderContext {
         Self {
             dedupe: Some(DedupeEncoder::new()),
             diff: Some(DiffEncoder::new()),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a context that only records [`EncodeMetrics`].
+    #[inline(always)]
+    pub const fn with_metrics() -> Self {
+        Self {
+            dedupe: None,
+            diff: None,
+            metrics: Some(EncodeMetrics::new()),
+            codecs: None,
+            #[cfg(feature = "hooks")]
+            hooks: None,
+            compression: CompressionPolicy::SizeThreshold(DEFAULT_COMPRESSION_THRESHOLD),
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+
+    /// Creates a context that encodes `#[lencode(codec = "...")]` fields with `codecs`.
+    #[inline(always)]
+    pub fn with_codecs(codecs: Arc<CodecRegistry>) -> Self {
+        Self {
+            codecs: Some(codecs),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a context that compresses byte and string payloads according to `policy`.
+    #[inline(always)]
+    pub const fn with_compression(policy: CompressionPolicy) -> Self {
+        let mut ctx = Self::new();
+        ctx.compression = policy;
+        ctx
+    }
+
+    /// Creates a context that reports every top‑level value to `observer`.
+    #[cfg(feature = "hooks")]
+    #[inline(always)]
+    pub fn with_hooks(observer: impl EncodeObserver + 'static) -> Self {
+        Self {
+            hooks: Some(EncodeHooks::new(observer)),
+            ..Self::new()
+        }
+    }
+
+    /// Returns `true` if encoding with this context writes the same bytes as encoding
+    /// without one and records nothing along the way, so a value may be encoded once and its
+    /// bytes reused (as [`Cached`](crate::wrappers::Cached) does). Non‑default compression
+    /// settings count as state.
+    ///
+    /// New fields that affect encoding must be checked here.
+    #[inline(always)]
+    pub const fn is_stateless(&self) -> bool {
+        #[cfg(feature = "hooks")]
+        if self.hooks.is_some() {
+            return false;
+        }
+        self.dedupe.is_none()
+            && self.diff.is_none()
+            && self.codecs.is_none()
+            && self.metrics.is_none()
+            && matches!(
+                self.compression,
+                CompressionPolicy::SizeThreshold(DEFAULT_COMPRESSION_THRESHOLD)
+            )
+            && self.compression_level == DEFAULT_COMPRESSION_LEVEL
+    }
+
+    /// Reports `bytes` written for a top‑level value of `type_name` if `ctx` has hooks.
+    ///
+    /// [`encode_ext`](crate::encode_ext) calls this after every value.
+    #[cfg(feature = "hooks")]
+    #[inline(always)]
+    pub fn record_value(ctx: Option<&mut Self>, type_name: &'static str, bytes: usize) {
+        if let Some(hooks) = ctx.and_then(|ctx| ctx.hooks.as_mut()) {
+            hooks.record(type_name, bytes);
+        }
+    }
+
+    /// Records `bytes` written for `field` of `type_name` if `ctx` collects metrics.
+    ///
+    /// `#[derive(Encode)]` calls this after every field; hand‑written impls can do the same.
+    #[inline(always)]
+    pub fn record_field(
+        ctx: Option<&mut Self>,
+        type_name: &'static str,
+        field: &'static str,
+        bytes: usize,
+    ) {
+        if let Some(metrics) = ctx.and_then(|ctx| ctx.metrics.as_mut()) {
+            metrics.record(type_name, field, bytes);
         }
     }
 }
 
+/// Wraps an existing [`DedupeEncoder`], for code that used to pass one on its own.
+impl From<DedupeEncoder> for EncoderContext {
+    #[inline(always)]
+    fn from(dedupe: DedupeEncoder) -> Self {
+        Self {
+            dedupe: Some(dedupe),
+            ..Self::new()
+        }
+    }
+}
+
+/// Wraps an existing [`DiffEncoder`].
+impl From<DiffEncoder> for EncoderContext {
+    #[inline(always)]
+    fn from(diff: DiffEncoder) -> Self {
+        Self {
+            diff: Some(diff),
+            ..Self::new()
+        }
+    }
+}
+
+/// How map decoders treat a key that appears more than once in the input.
+///
+/// A canonical encoder never emits duplicate keys, so a duplicate indicates a malformed or
+/// crafted payload. Silently keeping one of the values lets two parties that disagree on
+/// which value wins read different maps out of the same (possibly signed) bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DuplicateKeyPolicy {
+    /// Keep the value of the last occurrence (the behavior when no context is supplied).
+    #[default]
+    LastWins,
+    /// Fail with [`Error::DuplicateKey`](crate::io::Error::DuplicateKey).
+    Reject,
+}
+
+/// How `String` decoders treat bytes that are not valid UTF‑8.
+///
+/// Captured data (log messages, process output) sometimes holds stray invalid bytes, and
+/// failing the whole decode over one of them loses everything else in the payload.
+/// [`Utf8Policy::Lossy`] keeps the rest readable. It applies to `String` and
+/// [`InternedString`](crate::intern::InternedString) values and fields; borrowed `&str`
+/// decodes always fail, since they cannot be repaired in place.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Utf8Policy {
+    /// Fail with [`Error::InvalidUtf8`](crate::io::Error::InvalidUtf8) (the behavior when no
+    /// context is supplied).
+    #[default]
+    Strict,
+    /// Replace each invalid sequence with U+FFFD, like [`String::from_utf8_lossy`].
+    Lossy,
+}
+
 /// Bundles optional [`DedupeDecoder`] and [`DiffDecoder`] state for decoding.
 ///
 /// Pass `Some(&mut DecoderContext)` to [`Decode::decode_ext`] when you want
@@ -68,6 +303,25 @@ pub struct DecoderContext {
     pub dedupe: Option<DedupeDecoder>,
     /// Optional diff decoder for byte blobs.
     pub diff: Option<DiffDecoder>,
+    /// Optional table that [`InternedString`](crate::intern::InternedString) decodes share
+    /// their allocations through.
+    pub interner: Option<StringInterner>,
+    /// Optional [`Codec`](crate::codec::Codec)s for `#[lencode(codec = "...")]` fields.
+    pub codecs: Option<Arc<CodecRegistry>>,
+    /// Policy for duplicate keys when decoding maps.
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// Policy for strings that are not valid UTF‑8.
+    pub utf8: Utf8Policy,
+    /// Maximum nesting depth of containers (`Vec`, `Option`, maps, …) before decoding fails
+    /// with [`Error::DepthLimitExceeded`]. Defaults to [`DEFAULT_MAX_DEPTH`].
+    ///
+    /// Decoding without a context gets a fresh one at the first container, so plain
+    /// [`decode`](crate::decode) calls are capped at [`DEFAULT_MAX_DEPTH`] as well. Supply a
+    /// context only to change the limit.
+    pub max_depth: usize,
+    /// Current nesting depth, maintained by [`DecoderContext::nested`]. Zero between
+    /// top‑level decodes.
+    pub depth: usize,
 }
 
 impl Default for DecoderContext {
@@ -83,6 +337,12 @@ impl DecoderContext {
         Self {
             dedupe: None,
             diff: None,
+            interner: None,
+            codecs: None,
+            duplicate_keys: DuplicateKeyPolicy::LastWins,
+            utf8: Utf8Policy::Strict,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
         }
     }
 
@@ -92,6 +352,12 @@ impl DecoderContext {
         Self {
             dedupe: Some(DedupeDecoder::new()),
             diff: None,
+            interner: None,
+            codecs: None,
+            duplicate_keys: DuplicateKeyPolicy::LastWins,
+            utf8: Utf8Policy::Strict,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
         }
     }
 
@@ -101,6 +367,12 @@ impl DecoderContext {
         Self {
             dedupe: None,
             diff: Some(DiffDecoder::new()),
+            interner: None,
+            codecs: None,
+            duplicate_keys: DuplicateKeyPolicy::LastWins,
+            utf8: Utf8Policy::Strict,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
         }
     }
 
@@ -110,6 +382,109 @@ impl DecoderContext {
         Self {
             dedupe: Some(DedupeDecoder::new()),
             diff: Some(DiffDecoder::new()),
+            interner: None,
+            codecs: None,
+            duplicate_keys: DuplicateKeyPolicy::LastWins,
+            utf8: Utf8Policy::Strict,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
+        }
+    }
+
+    /// Creates a context that interns decoded [`InternedString`](crate::intern::InternedString)s.
+    #[inline(always)]
+    pub fn with_interning() -> Self {
+        Self {
+            interner: Some(StringInterner::new()),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a context that decodes `#[lencode(codec = "...")]` fields with `codecs`.
+    #[inline(always)]
+    pub fn with_codecs(codecs: Arc<CodecRegistry>) -> Self {
+        Self {
+            codecs: Some(codecs),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a strict context that rejects malformed‑but‑decodable input.
+    ///
+    /// Duplicate map keys fail with [`Error::DuplicateKey`](crate::io::Error::DuplicateKey).
+    #[inline(always)]
+    pub const fn strict() -> Self {
+        Self {
+            dedupe: None,
+            diff: None,
+            interner: None,
+            codecs: None,
+            duplicate_keys: DuplicateKeyPolicy::Reject,
+            utf8: Utf8Policy::Strict,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
+        }
+    }
+
+    /// Returns `true` if decoding with this context reads the same values as decoding
+    /// without one, depth tracking aside.
+    #[inline(always)]
+    pub(crate) const fn is_plain(&self) -> bool {
+        self.dedupe.is_none()
+            && self.diff.is_none()
+            && self.interner.is_none()
+            && self.codecs.is_none()
+            && matches!(self.duplicate_keys, DuplicateKeyPolicy::LastWins)
+            && matches!(self.utf8, Utf8Policy::Strict)
+    }
+
+    /// Runs `f` one nesting level deeper than the caller.
+    ///
+    /// Container decoders wrap their element decoding in this so that deeply nested input
+    /// fails with [`Error::DepthLimitExceeded`] instead of overflowing the stack. The depth is
+    /// restored whether or not `f` succeeds. Without a context, `f` gets a fresh default one,
+    /// so plain [`decode`](crate::decode) calls are capped at [`DEFAULT_MAX_DEPTH`] too.
+    #[inline(always)]
+    pub fn nested<T>(
+        ctx: Option<&mut Self>,
+        f: impl FnOnce(Option<&mut Self>) -> Result<T>,
+    ) -> Result<T> {
+        let mut fresh;
+        let ctx = match ctx {
+            Some(ctx) => ctx,
+            None => {
+                fresh = Self::new();
+                &mut fresh
+            }
+        };
+        if ctx.depth >= ctx.max_depth {
+            return Err(Error::DepthLimitExceeded);
+        }
+        ctx.depth += 1;
+        let result = f(Some(&mut *ctx));
+        ctx.depth -= 1;
+        result
+    }
+}
+
+/// Wraps an existing [`DedupeDecoder`], for code that used to pass one on its own.
+impl From<DedupeDecoder> for DecoderContext {
+    #[inline(always)]
+    fn from(dedupe: DedupeDecoder) -> Self {
+        Self {
+            dedupe: Some(dedupe),
+            ..Self::new()
+        }
+    }
+}
+
+/// Wraps an existing [`DiffDecoder`].
+impl From<DiffDecoder> for DecoderContext {
+    #[inline(always)]
+    fn from(diff: DiffDecoder) -> Self {
+        Self {
+            diff: Some(diff),
+            ..Self::new()
         }
     }
 }
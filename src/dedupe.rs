@@ -72,6 +72,8 @@ impl<T: DedupeDecodeable> Decode for T {
         T::unpack(reader)
     }
 
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_vec(reader: &mut impl Read, count: usize) -> Result<Vec<Self>> {
         T::unpack_vec(reader, count)
@@ -184,6 +186,41 @@ impl DedupeEncoder {
             .flat_map(|m| m.keys())
     }
 
+    /// Returns the ID assigned to `val`, or `None` if it has not been encoded yet.
+    #[inline]
+    pub fn id_of<T: Hash + Eq + Send + Sync + 'static>(&self, val: &T) -> Option<usize> {
+        self.type_stores
+            .get(&TypeId::of::<T>())
+            .and_then(|store| store.downcast_ref::<HashMap<T, usize>>())
+            .and_then(|m| m.get(val).copied())
+    }
+
+    /// Returns `true` if `val` has been encoded before, so encoding it again writes only its
+    /// ID.
+    #[inline]
+    pub fn contains<T: Hash + Eq + Send + Sync + 'static>(&self, val: &T) -> bool {
+        self.id_of(val).is_some()
+    }
+
+    /// Returns the number of bytes [`encode`](Self::encode) would write for `val` right now,
+    /// without writing anything or changing the table.
+    ///
+    /// This is the ID's varint for a value already in the table, and a `0` marker plus the
+    /// packed value otherwise, which lets a caller budget a datagram exactly before committing
+    /// to it. The prediction holds until the next call that changes the table.
+    #[inline]
+    pub fn encoded_len<T: Hash + Eq + Pack + Send + Sync + 'static>(
+        &self,
+        val: &T,
+    ) -> Result<usize> {
+        if let Some(id) = self.id_of(val) {
+            return Ok(crate::bytes::varint_len_usize(id));
+        }
+        let mut counter = crate::wrappers::ByteCounter::default();
+        val.pack(&mut counter)?;
+        Ok(1 + counter.0)
+    }
+
     /// Removes all cached entries for a specific type `T`.
     ///
     /// Other types' entries and their IDs are unaffected.
@@ -449,6 +486,28 @@ mod tests {
         assert_eq!(encoder.len(), 3);
     }
 
+    #[test]
+    fn test_dedupe_lookup_predicts_encoded_len() {
+        let mut encoder = DedupeEncoder::new();
+        let mut buffer = Vec::new();
+        for i in 0..200u64 {
+            encoder.encode(&(i << 40), &mut buffer).unwrap();
+        }
+        assert!(encoder.contains(&(7u64 << 40)));
+        assert!(!encoder.contains(&(7u32 << 20)));
+        assert_eq!(encoder.id_of(&(7u64 << 40)), Some(8));
+        assert_eq!(encoder.id_of(&1u64), None);
+
+        for val in [0u64, 199 << 40, 12345] {
+            let predicted = encoder.encoded_len(&val).unwrap();
+            let (len, before) = (encoder.len(), buffer.len());
+            assert_eq!(encoder.encoded_len(&val).unwrap(), predicted);
+            assert_eq!(encoder.len(), len);
+            assert_eq!(encoder.encode(&val, &mut buffer).unwrap(), predicted);
+            assert_eq!(buffer.len() - before, predicted);
+        }
+    }
+
     #[test]
     fn test_dedupe_clear_type() {
         let mut encoder = DedupeEncoder::new();
@@ -502,4 +561,25 @@ mod tests {
         assert!(result.is_err());
         matches!(result, Err(crate::io::Error::InvalidData));
     }
+
+    #[test]
+    fn test_dedupe_state_converts_into_context() {
+        let mut enc_ctx: EncoderContext = DedupeEncoder::new().into();
+        let mut dec_ctx: DecoderContext = DedupeDecoder::new().into();
+        let mut buffer = Vec::new();
+        for _ in 0..3 {
+            Be(7u32)
+                .encode_ext(&mut buffer, Some(&mut enc_ctx))
+                .unwrap();
+        }
+        assert_eq!(
+            enc_ctx.dedupe.as_ref().unwrap().len_for_type::<Be<u32>>(),
+            1
+        );
+        let mut cursor = Cursor::new(&buffer);
+        for _ in 0..3 {
+            let value = Be::<u32>::decode_ext(&mut cursor, Some(&mut dec_ctx)).unwrap();
+            assert_eq!(value.0, 7);
+        }
+    }
 }
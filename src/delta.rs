@@ -0,0 +1,103 @@
+//! Field‑level deltas between two values of the same struct.
+//!
+//! [`encode_delta`] writes a presence bitmap with one bit per field, followed by the encoded
+//! values of only the fields that differ between `old` and `new`. [`apply_delta`] reads the
+//! bitmap and overwrites the marked fields of an existing value. Feeds where most updates
+//! touch one or two fields (an account whose lamports change, say) shrink to a few bytes.
+//!
+//! Implement [`Delta`] with `#[derive(Delta)]`; field types need `Encode`, `Decode` and
+//! `PartialEq`. Changed fields are encoded whole, using the same field encoding (including
+//! `#[lencode(len = "...")]`) as `#[derive(Encode)]`.
+//!
+//! ## Wire format
+//!
+//! ```text
+//! [bitmap: ceil(fields / 8) bytes]  // bit i (LSB first within each byte) = field i changed
+//! [changed fields, in declaration order]
+//! ```
+//!
+//! Unused high bits of the last bitmap byte must be zero.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! #[derive(Encode, Decode, Delta, Clone, PartialEq, Debug)]
+//! struct Account {
+//!     lamports: u64,
+//!     owner: [u8; 32],
+//!     data: Vec<u8>,
+//! }
+//!
+//! let old = Account { lamports: 10, owner: [7; 32], data: vec![0; 165] };
+//! let mut new = old.clone();
+//! new.lamports = 15;
+//!
+//! let mut buf = Vec::new();
+//! assert_eq!(encode_delta(&old, &new, &mut buf).unwrap(), 2);
+//!
+//! let mut replica = old.clone();
+//! apply_delta(&mut replica, &mut Cursor::new(&buf)).unwrap();
+//! assert_eq!(replica, new);
+//! ```
+use crate::io::read_exact;
+use crate::prelude::*;
+
+/// Types that can encode the fields changed since an earlier value. See the
+/// [module docs](self).
+pub trait Delta {
+    /// Encodes the fields of `self` that differ from `old`, returning the bytes written.
+    fn encode_delta_ext(
+        &self,
+        old: &Self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize>;
+
+    /// Reads a delta written by [`Delta::encode_delta_ext`] and overwrites the changed
+    /// fields of `self`.
+    ///
+    /// On error, fields decoded before the failure have already been updated.
+    fn apply_delta_ext(
+        &mut self,
+        reader: &mut impl Read,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<()>;
+}
+
+/// Encodes the fields of `new` that differ from `old` into `writer`.
+#[inline(always)]
+pub fn encode_delta<T: Delta>(old: &T, new: &T, writer: &mut impl Write) -> Result<usize> {
+    new.encode_delta_ext(old, writer, None)
+}
+
+/// Applies a delta written by [`encode_delta`] to `target`.
+#[inline(always)]
+pub fn apply_delta<T: Delta>(target: &mut T, reader: &mut impl Read) -> Result<()> {
+    target.apply_delta_ext(reader, None)
+}
+
+/// Reads a presence bitmap for `fields` fields, rejecting set bits past the last field.
+#[doc(hidden)]
+pub fn read_delta_mask<const N: usize>(reader: &mut impl Read, fields: usize) -> Result<[u8; N]> {
+    let mut mask = [0u8; N];
+    read_exact(reader, &mut mask)?;
+    let used = fields % 8;
+    if used != 0 && mask[N - 1] >> used != 0 {
+        return Err(Error::InvalidData);
+    }
+    Ok(mask)
+}
+
+#[test]
+fn test_delta_mask_rejects_unknown_fields() {
+    let mask: [u8; 2] = read_delta_mask(&mut Cursor::new(&[0xff, 0x03][..]), 10).unwrap();
+    assert_eq!(mask, [0xff, 0x03]);
+    assert!(matches!(
+        read_delta_mask::<2>(&mut Cursor::new(&[0x00, 0x04][..]), 10),
+        Err(Error::InvalidData)
+    ));
+    assert!(matches!(
+        read_delta_mask::<1>(&mut Cursor::new(&[][..]), 3),
+        Err(Error::ReaderOutOfData)
+    ));
+}
@@ -306,7 +306,7 @@ impl DiffEncoder {
                 };
 
                 if let Some(buf) = winner {
-                    let n = writer.write(buf)?;
+                    let n = write_all(writer, buf)?;
                     self.store.insert(key, data.to_vec());
                     return Ok(n);
                 }
@@ -320,7 +320,7 @@ impl DiffEncoder {
         let mut total = 0;
         total += Lencode::encode_varint_u64(0, writer)?;
         total += Lencode::encode_varint_u64(data.len() as u64, writer)?;
-        total += writer.write(data)?;
+        total += write_all(writer, data)?;
         Ok(total)
     }
 
@@ -465,7 +465,7 @@ impl DiffDecoder {
         match mode {
             0 => {
                 // Full blob
-                let len = Lencode::decode_varint_u64(reader)? as usize;
+                let len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
                 let mut data = Vec::with_capacity(len);
                 if len > 0 {
                     unsafe { data.set_len(len) };
@@ -481,8 +481,8 @@ impl DiffDecoder {
             }
             1 => {
                 // Patch diff — need old blob
-                let new_len = Lencode::decode_varint_u64(reader)? as usize;
-                let num_patches = Lencode::decode_varint_u64(reader)? as usize;
+                let new_len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+                let num_patches = checked_usize(Lencode::decode_varint_u64(reader)?)?;
 
                 let key = self.current_key.ok_or(Error::InvalidData)?;
                 let old = self.store.get(&key).ok_or(Error::InvalidData)?;
@@ -491,8 +491,8 @@ impl DiffDecoder {
                 let mut old_cursor = 0usize;
 
                 for _ in 0..num_patches {
-                    let gap = Lencode::decode_varint_u64(reader)? as usize;
-                    let patch_len = Lencode::decode_varint_u64(reader)? as usize;
+                    let gap = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+                    let patch_len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
 
                     // Copy unchanged bytes from old blob
                     let copy_end = old_cursor + gap;
@@ -537,8 +537,8 @@ impl DiffDecoder {
             }
             2 => {
                 // XOR + zstd diff
-                let new_len = Lencode::decode_varint_u64(reader)? as usize;
-                let compressed_len = Lencode::decode_varint_u64(reader)? as usize;
+                let new_len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+                let compressed_len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
 
                 let key = self.current_key.ok_or(Error::InvalidData)?;
                 let old = self.store.get(&key).ok_or(Error::InvalidData)?;
@@ -575,7 +575,7 @@ impl DiffDecoder {
                 self.store.insert(key, result.clone());
                 Ok(result)
             }
-            _ => Err(Error::InvalidData),
+            mode => Err(Error::UnknownDiscriminant(mode)),
         }
     }
 }
@@ -784,10 +784,12 @@ mod tests {
         let mut enc_ctx = EncoderContext {
             dedupe: None,
             diff: Some(DiffEncoder::new()),
+            ..EncoderContext::new()
         };
         let mut dec_ctx = DecoderContext {
             dedupe: None,
             diff: Some(DiffDecoder::new()),
+            ..DecoderContext::new()
         };
 
         // First encode: full blob through Vec<u8> Encode trait
@@ -851,6 +853,7 @@ mod tests {
         encoder.encode_blob(&data2, &mut buf).unwrap();
 
         // Verify mode byte is 2 (XOR+zstd)
+        #[cfg(feature = "zstd")]
         assert_eq!(
             buf[0], 2,
             "expected mode 2 (XOR+zstd) for scattered changes"
@@ -1107,7 +1110,10 @@ mod tests {
         let mut buf = Vec::new();
         Lencode::encode_varint_u64(3, &mut buf).unwrap();
         let mut cursor = Cursor::new(&buf[..]);
-        assert!(decoder.decode_blob(&mut cursor).is_err());
+        assert!(matches!(
+            decoder.decode_blob(&mut cursor),
+            Err(Error::UnknownDiscriminant(3))
+        ));
     }
 
     #[test]
@@ -1119,10 +1125,12 @@ mod tests {
         let mut enc_ctx = EncoderContext {
             dedupe: None,
             diff: Some(DiffEncoder::new()),
+            ..EncoderContext::new()
         };
         let mut dec_ctx = DecoderContext {
             dedupe: None,
             diff: Some(DiffDecoder::new()),
+            ..DecoderContext::new()
         };
 
         // First encode: full blob
@@ -1165,6 +1173,7 @@ mod tests {
         let mut enc_ctx = EncoderContext {
             dedupe: None,
             diff: Some(DiffEncoder::new()),
+            ..EncoderContext::new()
         };
 
         // First encode: full blob
@@ -1199,10 +1208,12 @@ mod tests {
         let mut enc_ctx = EncoderContext {
             dedupe: None,
             diff: Some(DiffEncoder::new()),
+            ..EncoderContext::new()
         };
         let mut dec_ctx = DecoderContext {
             dedupe: None,
             diff: Some(DiffDecoder::new()),
+            ..DecoderContext::new()
         };
 
         // First encode
@@ -1346,10 +1357,12 @@ mod tests {
         let mut enc_ctx = EncoderContext {
             dedupe: None,
             diff: Some(DiffEncoder::new()),
+            ..EncoderContext::new()
         };
         let mut dec_ctx = DecoderContext {
             dedupe: None,
             diff: Some(DiffDecoder::new()),
+            ..DecoderContext::new()
         };
 
         let data: Vec<u8> = vec![7u8; 100];
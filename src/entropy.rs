@@ -0,0 +1,331 @@
+//! Static Huffman coding for byte fields with a small, skewed alphabet.
+//!
+//! Instruction discriminators, account index bytes and similar fields use a handful of byte
+//! values, some far more often than others, so a byte apiece wastes most of each byte. A
+//! [`HuffmanTable`] built from a histogram of representative data gives frequent bytes short
+//! bit codes and rare ones long codes. Encoder and decoder derive the same table from the same
+//! histogram (or from the same [code lengths](HuffmanTable::code_lengths)); nothing about the
+//! table is written to the wire.
+//!
+//! The table is a [`Codec<Vec<u8>>`](Codec), so registering it in a [`CodecRegistry`] opts a
+//! `#[lencode(codec = "...")]` field into it. Lists are stored in the layout of
+//! [`RiceVec`]: `varint(count)`, `varint(byte_len)`, then the codes most significant bit
+//! first, zero‑padded to a byte boundary.
+//!
+//! ```
+//! use lencode::prelude::*;
+//! use std::sync::Arc;
+//!
+//! #[derive(Encode, Decode, Debug, PartialEq)]
+//! struct Transaction {
+//!     #[lencode(codec = "opcodes")]
+//!     opcodes: Vec<u8>,
+//! }
+//!
+//! // Opcode 2 dominates, 3 is common, 0 and 9 are rare.
+//! let mut histogram = [0u64; 256];
+//! histogram[2] = 900;
+//! histogram[3] = 80;
+//! histogram[0] = 15;
+//! histogram[9] = 5;
+//! let codecs = Arc::new(
+//!     CodecRegistry::new().with::<Vec<u8>>("opcodes", HuffmanTable::from_histogram(&histogram)),
+//! );
+//!
+//! let tx = Transaction { opcodes: vec![2, 2, 3, 2, 2, 2, 0, 2, 2, 2, 2, 3] };
+//! let mut buf = Vec::new();
+//! tx.encode_ext(&mut buf, Some(&mut EncoderContext::with_codecs(codecs.clone()))).unwrap();
+//! assert_eq!(buf.len(), 2 + 2);
+//! let mut ctx = DecoderContext::with_codecs(codecs);
+//! assert_eq!(Transaction::decode_ext(&mut Cursor::new(&buf), Some(&mut ctx)).unwrap(), tx);
+//! ```
+use core::cmp::Reverse;
+
+use crate::bits::{read_bit_packed, write_bit_packed};
+use crate::collections::BinaryHeap;
+use crate::prelude::*;
+
+/// The longest code a [`HuffmanTable`] assigns.
+///
+/// Histograms that would need longer codes are flattened until they fit, which costs a
+/// little compression on extremely skewed data and keeps every code within one `u32`.
+pub const MAX_HUFFMAN_CODE_LEN: u32 = 16;
+
+/// A canonical Huffman code over byte values. See the [module docs](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HuffmanTable {
+    /// Code length of each byte value; 0 for bytes the table cannot encode.
+    lengths: [u8; 256],
+    /// Code of each byte value, in the low `lengths[b]` bits.
+    codes: [u32; 256],
+    /// Number of codes of each length, indexed by length.
+    counts: [u16; MAX_HUFFMAN_CODE_LEN as usize + 1],
+    /// Encodable byte values ordered by (code length, value), i.e. by code.
+    symbols: Vec<u8>,
+}
+
+impl HuffmanTable {
+    /// Builds the optimal table for data whose byte values occur `histogram[b]` times.
+    ///
+    /// Bytes with a count of 0 get no code, and encoding them fails. A histogram with a
+    /// single nonzero entry gives that byte a 1‑bit code.
+    pub fn from_histogram(histogram: &[u64; 256]) -> Self {
+        let mut weights = *histogram;
+        loop {
+            let lengths = huffman_lengths(&weights);
+            if lengths
+                .iter()
+                .all(|&len| len as u32 <= MAX_HUFFMAN_CODE_LEN)
+            {
+                return Self::from_valid_lengths(lengths.map(|len| len as u8));
+            }
+            // Halving every weight evens out the tree; all‑equal weights need at most 8 bits.
+            for weight in weights.iter_mut().filter(|w| **w > 0) {
+                *weight = (*weight / 2).max(1);
+            }
+        }
+    }
+
+    /// Builds the table for the byte frequencies of `samples`.
+    pub fn from_samples(samples: &[u8]) -> Self {
+        let mut histogram = [0u64; 256];
+        for &byte in samples {
+            histogram[byte as usize] += 1;
+        }
+        Self::from_histogram(&histogram)
+    }
+
+    /// Rebuilds a table from the [code lengths](HuffmanTable::code_lengths) of another, e.g.
+    /// ones shipped alongside a protocol definition.
+    ///
+    /// Fails with [`Error::InvalidData`] if a length exceeds [`MAX_HUFFMAN_CODE_LEN`] or the
+    /// lengths do not form a prefix code.
+    pub fn from_code_lengths(lengths: &[u8; 256]) -> Result<Self> {
+        // Kraft's inequality: the codes fit in a binary tree iff sum(2^-len) <= 1.
+        let mut kraft = 0u64;
+        for &len in lengths.iter().filter(|&&len| len > 0) {
+            if len as u32 > MAX_HUFFMAN_CODE_LEN {
+                return Err(Error::InvalidData);
+            }
+            kraft += 1 << (MAX_HUFFMAN_CODE_LEN - len as u32);
+        }
+        if kraft > 1 << MAX_HUFFMAN_CODE_LEN {
+            return Err(Error::InvalidData);
+        }
+        Ok(Self::from_valid_lengths(*lengths))
+    }
+
+    /// Assigns canonical codes: shorter codes first, equal lengths in byte order.
+    fn from_valid_lengths(lengths: [u8; 256]) -> Self {
+        let mut symbols: Vec<u8> = (0..=255u8).filter(|&b| lengths[b as usize] > 0).collect();
+        symbols.sort_by_key(|&b| lengths[b as usize]);
+        let mut counts = [0u16; MAX_HUFFMAN_CODE_LEN as usize + 1];
+        let mut codes = [0u32; 256];
+        let mut code = 0u32;
+        let mut prev_len = 0u32;
+        for &b in &symbols {
+            let len = lengths[b as usize] as u32;
+            code <<= len - prev_len;
+            codes[b as usize] = code;
+            counts[len as usize] += 1;
+            code += 1;
+            prev_len = len;
+        }
+        Self {
+            lengths,
+            codes,
+            counts,
+            symbols,
+        }
+    }
+
+    /// Returns the code length of every byte value, 0 for bytes without a code. Passing
+    /// them to [`HuffmanTable::from_code_lengths`] rebuilds this table.
+    #[inline(always)]
+    pub const fn code_lengths(&self) -> &[u8; 256] {
+        &self.lengths
+    }
+
+    /// Returns the length in bits of the code for `byte`, or `None` if the table cannot
+    /// encode it.
+    #[inline(always)]
+    pub const fn code_len(&self, byte: u8) -> Option<u32> {
+        match self.lengths[byte as usize] {
+            0 => None,
+            len => Some(len as u32),
+        }
+    }
+
+    /// Writes the code for `byte`, first bit first in either bit order.
+    ///
+    /// Fails with [`Error::InvalidData`] if the table has no code for `byte`.
+    #[inline]
+    pub fn write<W: Write, O: BitOrder>(&self, byte: u8, bits: &mut BitWriter<W, O>) -> Result<()> {
+        let len = self.lengths[byte as usize] as u32;
+        if len == 0 {
+            return Err(Error::InvalidData);
+        }
+        // `write_bits` writes values in `O`'s order; codes always go first bit first.
+        bits.write_bits(O::stream_value(self.codes[byte as usize] as u64, len), len)
+    }
+
+    /// Reads one code written by [`HuffmanTable::write`] and returns its byte.
+    ///
+    /// Fails with [`Error::InvalidData`] on a bit sequence that is not a code, which only
+    /// an incomplete table (e.g. one with a single byte) has.
+    pub fn read<R: Read, O: BitOrder>(&self, bits: &mut BitReader<R, O>) -> Result<u8> {
+        // Canonical decoding: `code - first` indexes the codes of the current length.
+        let mut code = 0u32;
+        let mut first = 0u32;
+        let mut index = 0usize;
+        for len in 1..=MAX_HUFFMAN_CODE_LEN as usize {
+            code |= bits.read_bit()? as u32;
+            let count = self.counts[len] as u32;
+            if code - first < count {
+                return Ok(self.symbols[index + (code - first) as usize]);
+            }
+            index += count as usize;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(Error::InvalidData)
+    }
+}
+
+impl Codec<Vec<u8>> for HuffmanTable {
+    fn encode(
+        &self,
+        value: &Vec<u8>,
+        mut writer: &mut dyn Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        write_bit_packed(&mut writer, value.len(), |bits| {
+            value.iter().try_for_each(|&byte| self.write(byte, bits))
+        })
+    }
+
+    fn decode(
+        &self,
+        mut reader: &mut dyn Read,
+        _ctx: Option<&mut DecoderContext>,
+    ) -> Result<Vec<u8>> {
+        read_bit_packed(&mut reader, |bits| self.read(bits))
+    }
+}
+
+/// Computes unrestricted Huffman code lengths for `weights`; 0 for zero weights.
+fn huffman_lengths(weights: &[u64; 256]) -> [u32; 256] {
+    let mut lengths = [0u32; 256];
+    let leaves: Vec<u8> = (0..=255u8).filter(|&b| weights[b as usize] > 0).collect();
+    if let [only] = leaves[..] {
+        lengths[only as usize] = 1;
+        return lengths;
+    }
+    // Nodes 0..leaves.len() are the leaves; each merge appends the parent of two nodes.
+    let mut parents: Vec<usize> = vec![usize::MAX; leaves.len()];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = leaves
+        .iter()
+        .enumerate()
+        .map(|(node, &b)| Reverse((weights[b as usize], node)))
+        .collect();
+    while let (Some(Reverse((wa, a))), Some(Reverse((wb, b)))) = (heap.pop(), heap.pop()) {
+        let parent = parents.len();
+        parents.push(usize::MAX);
+        parents[a] = parent;
+        parents[b] = parent;
+        heap.push(Reverse((wa.saturating_add(wb), parent)));
+    }
+    for (node, &b) in leaves.iter().enumerate() {
+        let mut depth = 0;
+        let mut at = node;
+        while parents[at] != usize::MAX {
+            at = parents[at];
+            depth += 1;
+        }
+        lengths[b as usize] = depth;
+    }
+    lengths
+}
+
+#[test]
+fn test_huffman_table_is_optimal_and_canonical() {
+    let mut histogram = [0u64; 256];
+    histogram[b'a' as usize] = 45;
+    histogram[b'b' as usize] = 13;
+    histogram[b'c' as usize] = 12;
+    histogram[b'd' as usize] = 16;
+    histogram[b'e' as usize] = 9;
+    histogram[b'f' as usize] = 5;
+    let table = HuffmanTable::from_histogram(&histogram);
+    let lens = b"abcdef".map(|b| table.code_len(b).unwrap());
+    assert_eq!(lens, [1, 3, 3, 3, 4, 4]);
+    assert_eq!(table.code_len(b'z'), None);
+    assert_eq!(
+        HuffmanTable::from_code_lengths(table.code_lengths()).unwrap(),
+        table
+    );
+
+    // Canonical codes: a = 0, b = 100, c = 101, d = 110, e = 1110, f = 1111.
+    let mut bits = BitWriter::new(Vec::new());
+    for &b in b"fab" {
+        table.write(b, &mut bits).unwrap();
+    }
+    bits.flush_partial().unwrap();
+    assert_eq!(bits.get_ref(), &[0b1111_0100]);
+
+    let mut bits = BitWriter::<_, Lsb0>::with_order(Vec::new());
+    for &b in b"decaf" {
+        table.write(b, &mut bits).unwrap();
+    }
+    bits.align().unwrap();
+    let buf = bits.into_inner().unwrap();
+    let mut bits = BitReader::<_, Lsb0>::with_order(Cursor::new(&buf));
+    let decoded: Vec<u8> = (0..5).map(|_| table.read(&mut bits).unwrap()).collect();
+    assert_eq!(decoded, b"decaf");
+
+    assert!(matches!(
+        table.write(b'z', &mut BitWriter::new(Vec::new())),
+        Err(Error::InvalidData)
+    ));
+    let mut overfull = [0u8; 256];
+    overfull[..3].fill(1);
+    assert!(matches!(
+        HuffmanTable::from_code_lengths(&overfull),
+        Err(Error::InvalidData)
+    ));
+}
+
+#[test]
+fn test_huffman_codec_roundtrip_and_limits() {
+    // Fibonacci weights would need 40-bit codes; the table caps them.
+    let mut histogram = [0u64; 256];
+    let (mut a, mut b) = (1u64, 1u64);
+    for weight in histogram.iter_mut().take(40) {
+        *weight = a;
+        (a, b) = (b, a + b);
+    }
+    let table = HuffmanTable::from_histogram(&histogram);
+    assert!((0..40).all(|b| {
+        table
+            .code_len(b)
+            .is_some_and(|len| len <= MAX_HUFFMAN_CODE_LEN)
+    }));
+
+    let data: Vec<u8> = (0..2000u32).map(|i| (i * i % 40) as u8).collect();
+    let mut buf = Vec::new();
+    let n = Codec::encode(&table, &data, &mut buf, None).unwrap();
+    assert_eq!(n, buf.len());
+    let decoded = Codec::<Vec<u8>>::decode(&table, &mut Cursor::new(&buf), None).unwrap();
+    assert_eq!(decoded, data);
+
+    // A single-byte table spends one bit per byte and rejects the unused code.
+    let single = HuffmanTable::from_samples(&[7; 10]);
+    let mut buf = Vec::new();
+    Codec::encode(&single, &vec![7u8; 16], &mut buf, None).unwrap();
+    assert_eq!(buf, [16, 2, 0, 0]);
+    buf[2] = 0b1000_0000;
+    assert!(matches!(
+        Codec::<Vec<u8>>::decode(&single, &mut Cursor::new(&buf), None),
+        Err(Error::InvalidData)
+    ));
+}
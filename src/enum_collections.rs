@@ -0,0 +1,199 @@
+//! Compact encodings for collections keyed by C‑like enums.
+//!
+//! - `enumset` feature: [`EnumSet<T>`](enumset::EnumSet) is encoded as a bitmap of the
+//!   variants it contains. Sets whose enum fits in 128 bits are written as a single Lencode
+//!   varint; wider enums are written as a fixed number of varint `u64` words.
+//! - `enum-map` feature: [`EnumMap<K, V>`](enum_map::EnumMap) is encoded as a dense array of
+//!   its values ordered by key discriminant, with no length prefix or keys on the wire.
+//!
+//! Both are far denser than the generic set/map encodings, which write a length followed by
+//! every key.
+use crate::prelude::*;
+
+#[cfg(feature = "enumset")]
+use enumset::{EnumSet, EnumSetType};
+
+#[cfg(feature = "enum-map")]
+use enum_map::{Enum, EnumArray, EnumMap};
+
+/// Number of `u64` words used to encode an [`EnumSet`] that does not fit in a `u128`.
+#[cfg(feature = "enumset")]
+#[inline(always)]
+const fn enumset_words(bit_width: u32) -> usize {
+    bit_width.div_ceil(64) as usize
+}
+
+#[cfg(feature = "enumset")]
+impl<T: EnumSetType> Encode for EnumSet<T> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let bit_width = EnumSet::<T>::bit_width();
+        if bit_width <= 128 {
+            return Lencode::encode_varint_u128(self.as_u128(), writer);
+        }
+        let mut words = vec![0u64; enumset_words(bit_width)];
+        self.copy_into_slice(&mut words);
+        let mut total_written = 0;
+        for word in words {
+            total_written += Lencode::encode_varint_u64(word, writer)?;
+        }
+        Ok(total_written)
+    }
+}
+
+#[cfg(feature = "enumset")]
+impl<T: EnumSetType> Decode for EnumSet<T> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        let bit_width = EnumSet::<T>::bit_width();
+        if bit_width <= 128 {
+            let bits = Lencode::decode_varint_u128(reader)?;
+            return EnumSet::try_from_u128(bits).ok_or(Error::InvalidData);
+        }
+        let count = enumset_words(bit_width);
+        let mut words = Vec::with_capacity(count);
+        for _ in 0..count {
+            words.push(Lencode::decode_varint_u64(reader)?);
+        }
+        EnumSet::try_from_slice(&words).ok_or(Error::InvalidData)
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+#[cfg(feature = "enum-map")]
+impl<K: EnumArray<V>, V: Encode> Encode for EnumMap<K, V> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = 0;
+        for value in self.as_slice() {
+            total_written += value.encode_ext(writer, ctx.as_deref_mut())?;
+        }
+        Ok(total_written)
+    }
+}
+
+#[cfg(feature = "enum-map")]
+impl<K: EnumArray<V>, V: Decode> Decode for EnumMap<K, V> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        // Decode everything up front so errors surface before the map is built.
+        let mut values = Vec::with_capacity(<K as Enum>::LENGTH);
+        for _ in 0..<K as Enum>::LENGTH {
+            values.push(V::decode_ext(reader, ctx.as_deref_mut())?);
+        }
+        let mut values = values.into_iter();
+        Ok(EnumMap::from_fn(|_| {
+            values
+                .next()
+                .expect("exactly LENGTH values were decoded above")
+        }))
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+#[cfg(feature = "enumset")]
+#[test]
+fn test_enumset_roundtrip() {
+    #[derive(EnumSetType, Debug)]
+    enum Flag {
+        A,
+        B,
+        C,
+        D,
+    }
+
+    for set in [
+        EnumSet::<Flag>::empty(),
+        Flag::A | Flag::C,
+        EnumSet::<Flag>::all(),
+    ] {
+        let mut buf = Vec::new();
+        let n = set.encode(&mut buf).unwrap();
+        assert_eq!(n, 1);
+        let decoded: EnumSet<Flag> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(decoded, set);
+    }
+}
+
+#[cfg(feature = "enumset")]
+#[test]
+fn test_enumset_rejects_unknown_bits() {
+    #[derive(EnumSetType, Debug)]
+    enum Flag {
+        A,
+        B,
+    }
+
+    let mut buf = Vec::new();
+    Lencode::encode_varint_u128(0b100, &mut buf).unwrap();
+    let res: Result<EnumSet<Flag>> = Decode::decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::InvalidData)));
+}
+
+#[cfg(feature = "enumset")]
+#[test]
+fn test_enumset_wide_roundtrip() {
+    #[derive(EnumSetType, Debug)]
+    enum Wide {
+        First = 0,
+        Middle = 100,
+        Last = 200,
+    }
+
+    let set = Wide::First | Wide::Last;
+    let mut buf = Vec::new();
+    set.encode(&mut buf).unwrap();
+    let decoded: EnumSet<Wide> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, set);
+    assert!(!decoded.contains(Wide::Middle));
+}
+
+#[cfg(feature = "enum-map")]
+#[test]
+fn test_enum_map_roundtrip() {
+    #[derive(Enum, Debug, Clone, Copy, PartialEq)]
+    enum Slot {
+        Low,
+        Mid,
+        High,
+    }
+
+    let mut map = EnumMap::<Slot, u32>::default();
+    map[Slot::Low] = 1;
+    map[Slot::High] = 300;
+
+    let mut buf = Vec::new();
+    let n = map.encode(&mut buf).unwrap();
+    // Dense values only: 1 + 1 + 3 bytes, no length prefix or keys.
+    assert_eq!(n, 5);
+    let decoded: EnumMap<Slot, u32> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, map);
+}
+
+#[cfg(feature = "enum-map")]
+#[test]
+fn test_enum_map_truncated_input() {
+    #[derive(Enum, Debug, Clone, Copy, PartialEq)]
+    enum Slot {
+        Low,
+        High,
+    }
+
+    let buf = [7u8];
+    let res: Result<EnumMap<Slot, u8>> = Decode::decode(&mut Cursor::new(&buf[..]));
+    assert!(res.is_err());
+}
@@ -0,0 +1,335 @@
+//! C ABI for producing and consuming lencode streams (`ffi` feature).
+//!
+//! Non‑Rust code works with values in their [`Pack`] layout: fixed‑width little‑endian
+//! fields with no padding, i.e. a `__attribute__((packed))` C struct on little‑endian
+//! targets. A [`LencodeSchema`] handle converts between that layout and the compact lencode
+//! encoding of one Rust type:
+//!
+//! - [`lencode_encode`] reads one packed value and writes its lencode encoding;
+//! - [`lencode_decode`] reads one lencode value from the front of a stream and writes its
+//!   packed layout, reporting how many stream bytes it consumed.
+//!
+//! Both return a [`LencodeStatus`] and hand output back in a [`LencodeBuffer`] that is
+//! released with [`lencode_buffer_free`].
+//!
+//! Schemas are created on the Rust side, typically by a small wrapper crate built as a
+//! `staticlib` or `cdylib` that exports one constructor per type:
+//!
+//! ```ignore
+//! use lencode::ffi::LencodeSchema;
+//! use lencode::prelude::*;
+//!
+//! #[derive(Encode, Decode, Pack)]
+//! pub struct Transfer {
+//!     slot: u64,
+//!     lamports: u64,
+//!     source: [u8; 32],
+//! }
+//!
+//! #[unsafe(no_mangle)]
+//! pub extern "C" fn transfer_schema() -> *mut LencodeSchema {
+//!     LencodeSchema::of::<Transfer>().into_raw()
+//! }
+//! ```
+//!
+//! The C header is generated by cbindgen at build time, into `$OUT_DIR/lencode.h` or the path
+//! in the `LENCODE_FFI_HEADER` environment variable.
+use std::boxed::Box;
+use std::ptr;
+use std::slice;
+
+use crate::prelude::*;
+
+type Convert = fn(&[u8], &mut Vec<u8>) -> Result<usize>;
+
+/// Opaque handle converting one type between its [`Pack`] layout and its lencode encoding.
+/// See the [module docs](self).
+pub struct LencodeSchema {
+    encode: Convert,
+    decode: Convert,
+}
+
+impl LencodeSchema {
+    /// Creates the schema of `T`.
+    pub fn of<T: Pack + Encode + Decode>() -> Self {
+        Self {
+            encode: |packed, out| {
+                let mut reader = Cursor::new(packed);
+                let value = T::unpack(&mut reader)?;
+                if reader.position() != packed.len() {
+                    return Err(Error::IncorrectLength);
+                }
+                value.encode(out)?;
+                Ok(packed.len())
+            },
+            decode: |data, out| {
+                let mut reader = Cursor::new(data);
+                T::decode(&mut reader)?.pack(out)?;
+                Ok(reader.position())
+            },
+        }
+    }
+
+    /// Moves the schema to the heap and returns a handle for C, to be released with
+    /// [`lencode_schema_free`].
+    #[inline(always)]
+    pub fn into_raw(self) -> *mut LencodeSchema {
+        Box::into_raw(Box::new(self))
+    }
+}
+
+/// Result of an FFI call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LencodeStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer,
+    /// See [`Error::InvalidData`].
+    InvalidData,
+    /// See [`Error::IncorrectLength`]; also returned when a packed value is longer than its
+    /// type.
+    IncorrectLength,
+    /// See [`Error::WriterOutOfSpace`].
+    WriterOutOfSpace,
+    /// See [`Error::ReaderOutOfData`].
+    ReaderOutOfData,
+    /// See [`Error::DuplicateKey`].
+    DuplicateKey,
+    /// See [`Error::DepthLimitExceeded`].
+    DepthLimitExceeded,
+    /// See [`Error::OutOfRange`].
+    OutOfRange,
+    /// See [`Error::StdIo`].
+    Io,
+    /// See [`Error::InvalidUtf8`].
+    InvalidUtf8,
+    /// See [`Error::DecompressionFailed`].
+    DecompressionFailed,
+    /// See [`Error::LengthMismatch`].
+    LengthMismatch,
+    /// See [`Error::UnknownDiscriminant`].
+    UnknownDiscriminant,
+    /// See [`Error::SchemaMismatch`].
+    SchemaMismatch,
+    /// See [`Error::ShortWrite`].
+    ShortWrite,
+    /// See [`Error::NotBorrowable`].
+    NotBorrowable,
+}
+
+impl From<Error> for LencodeStatus {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::InvalidData => Self::InvalidData,
+            Error::IncorrectLength => Self::IncorrectLength,
+            Error::WriterOutOfSpace => Self::WriterOutOfSpace,
+            Error::ReaderOutOfData => Self::ReaderOutOfData,
+            Error::DuplicateKey => Self::DuplicateKey,
+            Error::DepthLimitExceeded => Self::DepthLimitExceeded,
+            Error::OutOfRange { .. } => Self::OutOfRange,
+            Error::StdIo(_) => Self::Io,
+            Error::InvalidUtf8 => Self::InvalidUtf8,
+            Error::DecompressionFailed { .. } => Self::DecompressionFailed,
+            Error::LengthMismatch { .. } => Self::LengthMismatch,
+            Error::UnknownDiscriminant(_) => Self::UnknownDiscriminant,
+            Error::SchemaMismatch { .. } => Self::SchemaMismatch,
+            Error::ShortWrite { .. } => Self::ShortWrite,
+            Error::NotBorrowable => Self::NotBorrowable,
+        }
+    }
+}
+
+/// A byte buffer owned by this library. Release it with [`lencode_buffer_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct LencodeBuffer {
+    /// Start of the bytes; null for an empty buffer.
+    pub data: *mut u8,
+    /// Number of bytes.
+    pub len: usize,
+    /// Allocated capacity, needed to release the buffer.
+    pub capacity: usize,
+}
+
+impl LencodeBuffer {
+    const EMPTY: Self = Self {
+        data: ptr::null_mut(),
+        len: 0,
+        capacity: 0,
+    };
+
+    fn from_vec(vec: Vec<u8>) -> Self {
+        if vec.capacity() == 0 {
+            return Self::EMPTY;
+        }
+        let mut vec = core::mem::ManuallyDrop::new(vec);
+        Self {
+            data: vec.as_mut_ptr(),
+            len: vec.len(),
+            capacity: vec.capacity(),
+        }
+    }
+}
+
+/// Runs `run` over `len` bytes at `input` and stores its output in `out`.
+///
+/// # Safety
+///
+/// See [`lencode_encode`].
+unsafe fn convert(
+    run: Convert,
+    input: *const u8,
+    len: usize,
+    consumed: *mut usize,
+    out: *mut LencodeBuffer,
+) -> LencodeStatus {
+    if out.is_null() || (input.is_null() && len != 0) {
+        return LencodeStatus::NullPointer;
+    }
+    let input = if len == 0 {
+        &[][..]
+    } else {
+        // SAFETY: the caller guarantees `input` points to `len` readable bytes.
+        unsafe { slice::from_raw_parts(input, len) }
+    };
+    let mut output = Vec::new();
+    let status = match run(input, &mut output) {
+        Ok(n) => {
+            if !consumed.is_null() {
+                // SAFETY: the caller guarantees a non-null `consumed` is writable.
+                unsafe { consumed.write(n) };
+            }
+            LencodeStatus::Ok
+        }
+        Err(err) => {
+            output = Vec::new();
+            err.into()
+        }
+    };
+    // SAFETY: `out` is non-null and the caller guarantees it is writable.
+    unsafe { out.write(LencodeBuffer::from_vec(output)) };
+    status
+}
+
+/// Encodes the packed value in the `len` bytes at `packed` with `schema` and stores the
+/// lencode bytes in `out`. On failure `out` is set to an empty buffer.
+///
+/// # Safety
+///
+/// `schema` must be a live handle from [`LencodeSchema::into_raw`], `packed` must point to
+/// `len` readable bytes (or be null if `len` is 0), and `out` must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lencode_encode(
+    schema: *const LencodeSchema,
+    packed: *const u8,
+    len: usize,
+    out: *mut LencodeBuffer,
+) -> LencodeStatus {
+    // SAFETY: the caller guarantees a non-null `schema` is live.
+    let Some(schema) = (unsafe { schema.as_ref() }) else {
+        return LencodeStatus::NullPointer;
+    };
+    // SAFETY: forwarded from the caller.
+    unsafe { convert(schema.encode, packed, len, ptr::null_mut(), out) }
+}
+
+/// Decodes one value from the front of the `len` bytes at `data` with `schema` and stores
+/// its packed layout in `out`. If `consumed` is not null it receives the number of bytes
+/// read, so a stream of values can be decoded by advancing `data` by that amount. On failure
+/// `out` is set to an empty buffer and `consumed` is left untouched.
+///
+/// # Safety
+///
+/// `schema` must be a live handle from [`LencodeSchema::into_raw`], `data` must point to
+/// `len` readable bytes (or be null if `len` is 0), `out` must be writable, and `consumed`
+/// must be null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lencode_decode(
+    schema: *const LencodeSchema,
+    data: *const u8,
+    len: usize,
+    consumed: *mut usize,
+    out: *mut LencodeBuffer,
+) -> LencodeStatus {
+    // SAFETY: the caller guarantees a non-null `schema` is live.
+    let Some(schema) = (unsafe { schema.as_ref() }) else {
+        return LencodeStatus::NullPointer;
+    };
+    // SAFETY: forwarded from the caller.
+    unsafe { convert(schema.decode, data, len, consumed, out) }
+}
+
+/// Releases a buffer returned by [`lencode_encode`] or [`lencode_decode`].
+///
+/// # Safety
+///
+/// `buffer` must have been returned by this library and not released before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lencode_buffer_free(buffer: LencodeBuffer) {
+    if !buffer.data.is_null() {
+        // SAFETY: the buffer was built from a `Vec<u8>` by `LencodeBuffer::from_vec`.
+        drop(unsafe { Vec::from_raw_parts(buffer.data, buffer.len, buffer.capacity) });
+    }
+}
+
+/// Releases a schema handle. Null is ignored.
+///
+/// # Safety
+///
+/// `schema` must be null or a handle from [`LencodeSchema::into_raw`] that has not been
+/// released before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lencode_schema_free(schema: *mut LencodeSchema) {
+    if !schema.is_null() {
+        // SAFETY: the handle was created by `Box::into_raw` in `LencodeSchema::into_raw`.
+        drop(unsafe { Box::from_raw(schema) });
+    }
+}
+
+#[test]
+fn test_ffi_roundtrip() {
+    let schema = LencodeSchema::of::<u32>().into_raw();
+    let packed = [1, 1, 0, 0, 0];
+    let mut encoded = LencodeBuffer::EMPTY;
+    unsafe {
+        let status = lencode_encode(schema, packed.as_ptr(), 4, &mut encoded);
+        assert_eq!(status, LencodeStatus::Ok);
+        let bytes = slice::from_raw_parts(encoded.data, encoded.len).to_vec();
+        assert_eq!(bytes, [0x82, 0x01, 0x01]);
+
+        // Decode from a stream holding the value twice.
+        let stream = [&bytes[..], &bytes[..]].concat();
+        let mut consumed = 0;
+        let mut decoded = LencodeBuffer::EMPTY;
+        let status = lencode_decode(
+            schema,
+            stream.as_ptr(),
+            stream.len(),
+            &mut consumed,
+            &mut decoded,
+        );
+        assert_eq!(status, LencodeStatus::Ok);
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(
+            slice::from_raw_parts(decoded.data, decoded.len),
+            &packed[..4]
+        );
+        lencode_buffer_free(decoded);
+
+        let mut failed = LencodeBuffer::EMPTY;
+        let status = lencode_decode(schema, stream.as_ptr(), 2, ptr::null_mut(), &mut failed);
+        assert_eq!(status, LencodeStatus::ReaderOutOfData);
+        assert!(failed.data.is_null());
+        let status = lencode_encode(schema, packed.as_ptr(), 3, &mut failed);
+        assert_eq!(status, LencodeStatus::ReaderOutOfData);
+        let status = lencode_encode(schema, packed.as_ptr(), 5, &mut failed);
+        assert_eq!(status, LencodeStatus::IncorrectLength);
+        let status = lencode_encode(ptr::null(), packed.as_ptr(), 4, &mut failed);
+        assert_eq!(status, LencodeStatus::NullPointer);
+
+        lencode_buffer_free(encoded);
+        lencode_schema_free(schema);
+    }
+}
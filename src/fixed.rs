@@ -0,0 +1,304 @@
+//! Binary fixed‑point numbers in Q notation.
+//!
+//! [`Fixed<I, FRAC>`] stores a value as the integer `I` scaled by `2^FRAC`, so
+//! `Fixed<i64, 32>` (alias [`Q32_32`]) has 32 integer and 32 fractional bits. It encodes as
+//! the scaled integer with `I`'s own varint layout: amounts stay exact, small values stay
+//! small on the wire, and no float ever touches the value.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! let price = Q32_32::from_int(12).unwrap() + Q32_32::from_bits(1 << 30); // 12.25
+//! assert_eq!(price.to_string(), "12.25");
+//!
+//! let mut buf = Vec::new();
+//! encode(&price, &mut buf).unwrap();
+//! assert_eq!(decode::<Q32_32>(&mut Cursor::new(&buf)).unwrap(), price);
+//! ```
+use core::fmt;
+use core::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use crate::prelude::*;
+
+/// A fixed‑point number: the integer `I` scaled by `2^FRAC`. See the
+/// [module docs](self).
+///
+/// Implemented for all primitive integers; `FRAC` must be smaller than `I`'s bit width,
+/// which is checked at compile time.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Fixed<I, const FRAC: u32> {
+    bits: I,
+}
+
+/// 16 integer and 16 fractional bits.
+pub type Q16_16 = Fixed<i32, 16>;
+/// 32 integer and 32 fractional bits.
+pub type Q32_32 = Fixed<i64, 32>;
+/// 64 integer and 64 fractional bits.
+pub type Q64_64 = Fixed<i128, 64>;
+
+impl<I, const FRAC: u32> Fixed<I, FRAC> {
+    /// Creates a value from its scaled representation (`value * 2^FRAC`).
+    #[inline(always)]
+    pub const fn from_bits(bits: I) -> Self {
+        Self { bits }
+    }
+
+    /// Returns the scaled representation (`value * 2^FRAC`).
+    #[inline(always)]
+    pub fn to_bits(self) -> I {
+        self.bits
+    }
+}
+
+macro_rules! impl_fixed {
+    ($($int:ty),* $(,)?) => {
+        $(
+            impl<const FRAC: u32> Fixed<$int, FRAC> {
+                const CHECK_FRAC: () = assert!(FRAC < <$int>::BITS, "FRAC must be smaller than the bit width");
+
+                /// Zero.
+                pub const ZERO: Self = Self::from_bits(0);
+                /// The smallest positive value, `2^-FRAC`.
+                pub const DELTA: Self = Self::from_bits(1);
+                /// The smallest representable value.
+                pub const MIN: Self = Self::from_bits(<$int>::MIN);
+                /// The largest representable value.
+                pub const MAX: Self = Self::from_bits(<$int>::MAX);
+
+                /// Returns `value` as a fixed‑point number, or `None` if it does not fit in
+                /// the integer bits.
+                #[inline(always)]
+                pub const fn from_int(value: $int) -> Option<Self> {
+                    let () = Self::CHECK_FRAC;
+                    if value > (<$int>::MAX >> FRAC) || value < (<$int>::MIN >> FRAC) {
+                        return None;
+                    }
+                    Some(Self::from_bits(value << FRAC))
+                }
+
+                /// Returns the integer part, rounded toward negative infinity.
+                #[inline(always)]
+                pub const fn floor(self) -> $int {
+                    let () = Self::CHECK_FRAC;
+                    self.bits >> FRAC
+                }
+
+                /// Returns the nearest `f64`. Only for display or analytics; the conversion
+                /// may round.
+                #[inline(always)]
+                pub fn to_f64(self) -> f64 {
+                    let () = Self::CHECK_FRAC;
+                    self.bits as f64 / (1u128 << FRAC) as f64
+                }
+
+                /// Adds, returning `None` on overflow.
+                #[inline(always)]
+                pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+                    match self.bits.checked_add(rhs.bits) {
+                        Some(bits) => Some(Self::from_bits(bits)),
+                        None => None,
+                    }
+                }
+
+                /// Subtracts, returning `None` on overflow.
+                #[inline(always)]
+                pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    match self.bits.checked_sub(rhs.bits) {
+                        Some(bits) => Some(Self::from_bits(bits)),
+                        None => None,
+                    }
+                }
+            }
+
+            impl<const FRAC: u32> Add for Fixed<$int, FRAC> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn add(self, rhs: Self) -> Self {
+                    Self::from_bits(self.bits + rhs.bits)
+                }
+            }
+
+            impl<const FRAC: u32> AddAssign for Fixed<$int, FRAC> {
+                #[inline(always)]
+                fn add_assign(&mut self, rhs: Self) {
+                    self.bits += rhs.bits;
+                }
+            }
+
+            impl<const FRAC: u32> Sub for Fixed<$int, FRAC> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn sub(self, rhs: Self) -> Self {
+                    Self::from_bits(self.bits - rhs.bits)
+                }
+            }
+
+            impl<const FRAC: u32> SubAssign for Fixed<$int, FRAC> {
+                #[inline(always)]
+                fn sub_assign(&mut self, rhs: Self) {
+                    self.bits -= rhs.bits;
+                }
+            }
+
+            /// Prints the exact decimal expansion (every binary fraction terminates).
+            impl<const FRAC: u32> fmt::Display for Fixed<$int, FRAC> {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    let () = Self::CHECK_FRAC;
+                    let (negative, magnitude) = self.bits.sign_magnitude();
+                    let mask = (1u128 << FRAC) - 1;
+                    if negative {
+                        f.write_str("-")?;
+                    }
+                    write!(f, "{}", magnitude >> FRAC)?;
+                    // Drop bits that would overflow the `* 10` below; they are below 2^-124.
+                    let shift = FRAC.saturating_sub(124);
+                    let frac_bits = FRAC - shift;
+                    let mut frac = (magnitude & mask) >> shift;
+                    if frac != 0 {
+                        f.write_str(".")?;
+                    }
+                    while frac != 0 {
+                        frac *= 10;
+                        write!(f, "{}", frac >> frac_bits)?;
+                        frac &= (1u128 << frac_bits) - 1;
+                    }
+                    Ok(())
+                }
+            }
+
+            impl<const FRAC: u32> fmt::Debug for Fixed<$int, FRAC> {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    fmt::Display::fmt(self, f)
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+/// Splits a primitive integer into its sign and magnitude, for formatting.
+trait SignMagnitude {
+    fn sign_magnitude(self) -> (bool, u128);
+}
+
+macro_rules! impl_sign_magnitude {
+    (signed: $($signed:ty),*; unsigned: $($unsigned:ty),*) => {
+        $(
+            impl SignMagnitude for $signed {
+                #[inline(always)]
+                fn sign_magnitude(self) -> (bool, u128) {
+                    (self < 0, self.unsigned_abs() as u128)
+                }
+            }
+        )*
+        $(
+            impl SignMagnitude for $unsigned {
+                #[inline(always)]
+                fn sign_magnitude(self) -> (bool, u128) {
+                    (false, self as u128)
+                }
+            }
+        )*
+    };
+}
+
+impl_sign_magnitude!(
+    signed: i8, i16, i32, i64, i128, isize;
+    unsigned: u8, u16, u32, u64, u128, usize
+);
+
+macro_rules! impl_fixed_neg {
+    ($($int:ty),*) => {
+        $(
+            impl<const FRAC: u32> Neg for Fixed<$int, FRAC> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn neg(self) -> Self {
+                    Self::from_bits(-self.bits)
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_neg!(i8, i16, i32, i64, i128, isize);
+
+impl<I: Encode, const FRAC: u32> Encode for Fixed<I, FRAC> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        self.bits.encode_ext(writer, ctx)
+    }
+}
+
+impl<I: Decode, const FRAC: u32> Decode for Fixed<I, FRAC> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self::from_bits(I::decode_ext(reader, ctx)?))
+    }
+}
+
+#[test]
+fn test_fixed_display_and_roundtrip() {
+    let values = [
+        Q32_32::from_int(-3).unwrap() - Q32_32::from_bits(1 << 31),
+        Q32_32::DELTA,
+        Q32_32::MAX,
+        Q32_32::MIN,
+        Q32_32::ZERO,
+    ];
+    let mut buf = Vec::new();
+    for value in values {
+        encode(&value, &mut buf).unwrap();
+    }
+    let mut cursor = Cursor::new(&buf);
+    for value in values {
+        assert_eq!(decode::<Q32_32>(&mut cursor).unwrap(), value);
+    }
+    // A small scaled value is a short varint.
+    let mut small = Vec::new();
+    assert_eq!(
+        encode(&Fixed::<u64, 4>::from_bits(24), &mut small).unwrap(),
+        1
+    );
+
+    assert_eq!(values[0].floor(), -4);
+    assert_eq!(values[0].to_f64(), -3.5);
+    assert_eq!(-values[0], Q32_32::from_bits(7 << 31));
+    assert!(Q32_32::from_int(1 << 31).is_none());
+    assert!(Q32_32::MAX.checked_add(Q32_32::DELTA).is_none());
+    assert_eq!(Fixed::<u8, 7>::from_int(2), None);
+    assert_eq!(Q32_32::MIN.checked_sub(Q32_32::DELTA), None);
+
+    let mut text = String::new();
+    use core::fmt::Write as _;
+    write!(
+        text,
+        "{} {} {} {}",
+        values[0],
+        Fixed::<u8, 4>::from_bits(0x1f),
+        Q64_64::DELTA,
+        Fixed::<u128, 127>::from_bits(1)
+    )
+    .unwrap();
+    let mut parts = text.split(' ');
+    assert_eq!(parts.next(), Some("-3.5"));
+    assert_eq!(parts.next(), Some("1.9375"));
+    assert_eq!(
+        parts.next(),
+        Some("0.0000000000000000000542101086242752217003726400434970855712890625")
+    );
+    // Bits below 2^-124 are dropped from the expansion.
+    assert_eq!(parts.next(), Some("0"));
+}
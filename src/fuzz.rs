@@ -0,0 +1,82 @@
+//! Fuzzing support behind `#[lencode(fuzz)]`.
+//!
+//! `#[derive(Decode)]` with the container attribute `#[lencode(fuzz)]` emits two items for
+//! the annotated type `Foo` (which must also implement [`Encode`]):
+//!
+//! - `Foo::lencode_fuzz(data: &[u8])` under `cfg(fuzzing)`, the cfg set by `cargo fuzz`, which
+//!   runs [`fuzz_decode`] and plugs straight into a fuzz target;
+//! - a `lencode_fuzz_Foo` test under `cfg(test)` that runs [`fuzz_random_inputs`].
+//!
+//! ```ignore
+//! // fuzz/fuzz_targets/transfer.rs
+//! #![no_main]
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| my_protocol::Transfer::lencode_fuzz(data));
+//! ```
+//!
+//! Crates that check cfgs should declare `fuzzing` in `Cargo.toml`:
+//!
+//! ```toml
+//! [lints.rust]
+//! unexpected_cfgs = { level = "warn", check-cfg = ["cfg(fuzzing)"] }
+//! ```
+use crate::prelude::*;
+
+/// Number of inputs the test emitted by `#[lencode(fuzz)]` feeds to [`fuzz_decode`].
+pub const DEFAULT_FUZZ_CASES: usize = 4096;
+
+/// Decodes `data` as a `T`; if that succeeds, checks that the value roundtrips.
+///
+/// Arbitrary input may decode from a non‑canonical encoding (e.g. a stored byte string that
+/// would have compressed), so the check is that re‑encoding is stable: the value's encoding
+/// decodes to a value with the same encoding. Decode errors are expected and ignored; a
+/// panic anywhere, or an unstable roundtrip, is a bug.
+pub fn fuzz_decode<T: Encode + Decode>(data: &[u8]) {
+    let Ok(value) = T::decode(&mut Cursor::new(data)) else {
+        return;
+    };
+    let mut first = Vec::new();
+    value
+        .encode(&mut first)
+        .expect("a decoded value must encode");
+    let again = T::decode(&mut Cursor::new(&first)).expect("a value's encoding must decode");
+    let mut second = Vec::new();
+    again
+        .encode(&mut second)
+        .expect("a decoded value must encode");
+    assert_eq!(first, second, "re-encoding a decoded value is not stable");
+}
+
+/// Runs [`fuzz_decode`] over `cases` deterministic pseudo‑random inputs.
+///
+/// Inputs are biased towards small byte values, so that lengths, flags and discriminants
+/// often read as valid and decoding gets past the first field.
+pub fn fuzz_random_inputs<T: Encode + Decode>(cases: usize) {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut input = Vec::new();
+    for _ in 0..cases {
+        input.clear();
+        let len = next() as usize % 64;
+        for _ in 0..len {
+            let r = next();
+            input.push(if r & 1 == 0 {
+                (r >> 8) as u8 & 3
+            } else {
+                (r >> 8) as u8
+            });
+        }
+        fuzz_decode::<T>(&input);
+    }
+}
+
+#[test]
+fn test_fuzz_helpers() {
+    fuzz_random_inputs::<(u32, Option<String>, Vec<bool>)>(DEFAULT_FUZZ_CASES);
+    fuzz_decode::<String>(&[0xff]);
+    fuzz_decode::<Vec<u64>>(&[2, 1, 2]);
+}
@@ -0,0 +1,240 @@
+//! Graphs (`petgraph` feature).
+//!
+//! [`Graph`] and [`StableGraph`] are written as their node weights in index order, followed
+//! by their edges as `(source, target, weight)` with the endpoint indices as varints:
+//!
+//! ```text
+//! [node_count: varint] [node weight]*
+//! [edge_count: varint] ([source: varint] [target: varint] [edge weight])*
+//! ```
+//!
+//! A decoded [`Graph`] has the same node and edge indices as the encoded one. A
+//! [`StableGraph`] is compacted on the way out, the same way `Graph::from(stable_graph)`
+//! compacts it: slots left by removed nodes and edges are dropped and later indices shift
+//! down, so its indices survive only if nothing was removed. Directedness is part of the
+//! type and is not written.
+//!
+//! Decoding fails with [`Error::InvalidData`] if an edge refers to a node that does not
+//! exist, and with [`Error::OutOfRange`] if the graph has more nodes or edges than the index
+//! type `Ix` can address.
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+
+use ::petgraph::EdgeType;
+use ::petgraph::graph::{Graph, GraphError, IndexType, NodeIndex};
+use ::petgraph::stable_graph::StableGraph;
+use ::petgraph::visit::{EdgeRef, IntoEdgeReferences, NodeIndexable};
+
+use crate::prelude::*;
+use crate::{checked_usize, prealloc_capacity};
+
+/// Writes `nodes` and `edges` (with endpoints already mapped to wire positions).
+fn encode_parts<'a, N: Encode + 'a, E: Encode + 'a>(
+    writer: &mut impl Write,
+    mut ctx: Option<&mut EncoderContext>,
+    node_count: usize,
+    nodes: impl Iterator<Item = &'a N>,
+    edge_count: usize,
+    edges: impl Iterator<Item = (usize, usize, &'a E)>,
+) -> Result<usize> {
+    let mut total = Lencode::encode_varint_u64(node_count as u64, writer)?;
+    for weight in nodes {
+        total += weight.encode_ext(writer, ctx.as_deref_mut())?;
+    }
+    total += Lencode::encode_varint_u64(edge_count as u64, writer)?;
+    for (source, target, weight) in edges {
+        total += Lencode::encode_varint_u64(source as u64, writer)?;
+        total += Lencode::encode_varint_u64(target as u64, writer)?;
+        total += weight.encode_ext(writer, ctx.as_deref_mut())?;
+    }
+    Ok(total)
+}
+
+/// Reads a node or edge count, failing if `Ix` cannot index that many.
+fn decode_count<Ix: IndexType>(reader: &mut impl Read) -> Result<usize> {
+    let count = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    let max = <Ix as IndexType>::max().index();
+    if count > max {
+        return Err(Error::OutOfRange {
+            value: count as i128,
+            max: max as i128,
+        });
+    }
+    Ok(count)
+}
+
+/// Reads an edge's endpoints.
+fn decode_endpoints<Ix: IndexType>(
+    reader: &mut impl Read,
+) -> Result<(NodeIndex<Ix>, NodeIndex<Ix>)> {
+    let source = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    let target = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    Ok((NodeIndex::new(source), NodeIndex::new(target)))
+}
+
+/// Maps a rejected insertion to a decode error. Index limits are checked up front by
+/// [`decode_count`], so what remains is an edge to a missing node.
+const fn graph_error(_: GraphError) -> Error {
+    Error::InvalidData
+}
+
+impl<N: Encode, E: Encode, Ty: EdgeType, Ix: IndexType> Encode for Graph<N, E, Ty, Ix> {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        encode_parts(
+            writer,
+            ctx,
+            self.node_count(),
+            self.raw_nodes().iter().map(|node| &node.weight),
+            self.edge_count(),
+            self.raw_edges()
+                .iter()
+                .map(|edge| (edge.source().index(), edge.target().index(), &edge.weight)),
+        )
+    }
+}
+
+impl<N: Decode, E: Decode, Ty: EdgeType, Ix: IndexType> Decode for Graph<N, E, Ty, Ix> {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let node_count = decode_count::<Ix>(reader)?;
+            let mut graph = Graph::with_capacity(prealloc_capacity::<N>(node_count), 0);
+            for _ in 0..node_count {
+                let weight = N::decode_ext(reader, ctx.as_deref_mut())?;
+                graph.try_add_node(weight).map_err(graph_error)?;
+            }
+            let edge_count = decode_count::<Ix>(reader)?;
+            graph.reserve_edges(prealloc_capacity::<E>(edge_count));
+            for _ in 0..edge_count {
+                let (source, target) = decode_endpoints(reader)?;
+                let weight = E::decode_ext(reader, ctx.as_deref_mut())?;
+                graph
+                    .try_add_edge(source, target, weight)
+                    .map_err(graph_error)?;
+            }
+            Ok(graph)
+        })
+    }
+}
+
+impl<N: Encode, E: Encode, Ty: EdgeType, Ix: IndexType> Encode for StableGraph<N, E, Ty, Ix> {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        // Wire position of every occupied node slot, skipping vacant ones.
+        let mut positions = vec![usize::MAX; self.node_bound()];
+        for (position, index) in self.node_indices().enumerate() {
+            positions[index.index()] = position;
+        }
+        encode_parts(
+            writer,
+            ctx,
+            self.node_count(),
+            self.node_indices().map(|index| &self[index]),
+            self.edge_count(),
+            self.edge_references().map(|edge| {
+                (
+                    positions[edge.source().index()],
+                    positions[edge.target().index()],
+                    edge.weight(),
+                )
+            }),
+        )
+    }
+}
+
+impl<N: Decode, E: Decode, Ty: EdgeType, Ix: IndexType> Decode for StableGraph<N, E, Ty, Ix> {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let node_count = decode_count::<Ix>(reader)?;
+            let mut graph = StableGraph::with_capacity(prealloc_capacity::<N>(node_count), 0);
+            for _ in 0..node_count {
+                let weight = N::decode_ext(reader, ctx.as_deref_mut())?;
+                graph.try_add_node(weight).map_err(graph_error)?;
+            }
+            let edge_count = decode_count::<Ix>(reader)?;
+            graph.reserve_edges(prealloc_capacity::<E>(edge_count));
+            for _ in 0..edge_count {
+                let (source, target) = decode_endpoints(reader)?;
+                let weight = E::decode_ext(reader, ctx.as_deref_mut())?;
+                graph
+                    .try_add_edge(source, target, weight)
+                    .map_err(graph_error)?;
+            }
+            Ok(graph)
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_graphs_roundtrip_with_indices() {
+    use ::petgraph::Undirected;
+
+    let mut deps: Graph<String, u32> = Graph::new();
+    let core = deps.add_node(String::from("core"));
+    let io = deps.add_node(String::from("io"));
+    let app = deps.add_node(String::from("app"));
+    deps.add_edge(app, io, 2);
+    deps.add_edge(app, core, 1);
+    deps.add_edge(io, core, 1);
+    let mut buf = Vec::new();
+    encode(&deps, &mut buf).unwrap();
+    let decoded: Graph<String, u32> = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded.node_count(), 3);
+    assert_eq!(decoded[app], "app");
+    let edges: Vec<_> = decoded
+        .raw_edges()
+        .iter()
+        .map(|e| (e.source(), e.target(), e.weight))
+        .collect();
+    assert_eq!(edges, [(app, io, 2), (app, core, 1), (io, core, 1)]);
+
+    // Removed slots are compacted away.
+    let mut stable: StableGraph<u8, (), Undirected, u8> = StableGraph::default();
+    let a = stable.add_node(1);
+    let b = stable.add_node(2);
+    let c = stable.add_node(3);
+    stable.add_edge(a, c, ());
+    stable.remove_node(b);
+    let mut buf = Vec::new();
+    encode(&stable, &mut buf).unwrap();
+    let decoded: StableGraph<u8, (), Undirected, u8> = decode(&mut Cursor::new(&buf)).unwrap();
+    let weights: Vec<u8> = decoded.node_indices().map(|i| decoded[i]).collect();
+    assert_eq!(weights, [1, 3]);
+    assert!(decoded.contains_edge(NodeIndex::new(0), NodeIndex::new(1)));
+    assert_eq!(
+        decoded.edge_endpoints(::petgraph::graph::EdgeIndex::new(0)),
+        Some((NodeIndex::new(0), NodeIndex::new(1)))
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_graph_decode_rejects_bad_edges_and_counts() {
+    // One node, one edge from node 0 to missing node 5.
+    let dangling = [1, 7, 1, 0, 5, 9];
+    assert!(matches!(
+        decode::<Graph<u8, u8>>(&mut Cursor::new(&dangling[..])),
+        Err(Error::InvalidData)
+    ));
+
+    let mut too_many = Vec::new();
+    Lencode::encode_varint_u64(256, &mut too_many).unwrap();
+    assert!(matches!(
+        decode::<Graph<(), (), ::petgraph::Directed, u8>>(&mut Cursor::new(&too_many)),
+        Err(Error::OutOfRange {
+            value: 256,
+            max: 255
+        })
+    ));
+}
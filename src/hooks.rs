@@ -0,0 +1,119 @@
+//! Per‑value encode hooks for tracing.
+//!
+//! With the `hooks` feature, an [`EncoderContext`] can carry [`EncodeHooks`]: an
+//! [`EncodeObserver`] that [`encode_ext`](crate::encode_ext) and
+//! [`encode_atomic_ext`](crate::encode_atomic_ext) call once per top‑level value, with the
+//! value's type name and the byte span it occupies in the output. That is enough to open a
+//! `tracing` span or emit an event per message without touching every call site. Nested
+//! values are not reported; [`EncodeMetrics`](crate::metrics::EncodeMetrics) covers
+//! per‑field sizes.
+//!
+//! Spans are offsets into everything encoded through the context so far, which matches the
+//! output position when each value goes to the same writer from its start. Use
+//! [`EncodeHooks::set_position`] when the writer already holds other bytes, such as a header.
+//!
+//! ```
+//! use lencode::prelude::*;
+//! use std::sync::{Arc, Mutex};
+//!
+//! let seen = Arc::new(Mutex::new(Vec::new()));
+//! let log = seen.clone();
+//! let mut ctx = EncoderContext::with_hooks(move |ty: &'static str, span: core::ops::Range<usize>| {
+//!     log.lock().unwrap().push((ty, span));
+//! });
+//!
+//! let mut buf = Vec::new();
+//! encode_ext(&7u32, &mut buf, Some(&mut ctx)).unwrap();
+//! encode_ext(&String::from("hi"), &mut buf, Some(&mut ctx)).unwrap();
+//! assert_eq!(
+//!     *seen.lock().unwrap(),
+//!     [("u32", 0..1), ("alloc::string::String", 1..4)]
+//! );
+//! ```
+use core::ops::Range;
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use std::boxed::Box;
+
+/// Receives one call per top‑level value encoded through an [`EncodeHooks`] context.
+///
+/// Implemented for closures taking `(type_name, span)`.
+pub trait EncodeObserver: Send {
+    /// Called after a value of type `type_name` was written to `span` of the output.
+    fn on_value(&mut self, type_name: &'static str, span: Range<usize>);
+}
+
+impl<F: FnMut(&'static str, Range<usize>) + Send> EncodeObserver for F {
+    #[inline(always)]
+    fn on_value(&mut self, type_name: &'static str, span: Range<usize>) {
+        self(type_name, span)
+    }
+}
+
+/// An [`EncodeObserver`] plus the running output position. See the [module docs](self).
+pub struct EncodeHooks {
+    observer: Box<dyn EncodeObserver>,
+    position: usize,
+}
+
+impl EncodeHooks {
+    /// Reports values to `observer`, with spans starting at offset zero.
+    #[inline(always)]
+    pub fn new(observer: impl EncodeObserver + 'static) -> Self {
+        Self {
+            observer: Box::new(observer),
+            position: 0,
+        }
+    }
+
+    /// Returns the offset at which the next value's span will start.
+    #[inline(always)]
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Sets the offset at which the next value's span will start.
+    #[inline(always)]
+    pub const fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// Reports `bytes` written for a value of `type_name` and advances the position.
+    #[inline]
+    pub fn record(&mut self, type_name: &'static str, bytes: usize) {
+        let start = self.position;
+        self.position += bytes;
+        self.observer.on_value(type_name, start..self.position);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+use crate::prelude::*;
+
+#[cfg(feature = "std")]
+#[test]
+fn test_hooks_report_top_level_values_only() {
+    use std::sync::{Arc, Mutex};
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let mut ctx = EncoderContext::with_hooks(move |ty: &'static str, span: Range<usize>| {
+        log.lock().unwrap().push((ty, span));
+    });
+    ctx.hooks.as_mut().unwrap().set_position(2);
+
+    let mut buf = vec![0xAA, 0xBB];
+    encode_ext(&vec![1u16, 2, 3], &mut buf, Some(&mut ctx)).unwrap();
+    encode_atomic_ext(&Some(true), &mut buf, Some(&mut ctx)).unwrap();
+    encode(&5u8, &mut buf).unwrap();
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            ("alloc::vec::Vec<u16>", 2..6),
+            ("core::option::Option<bool>", 6..8),
+        ]
+    );
+    assert_eq!(ctx.hooks.unwrap().position(), buf.len() - 1);
+}
@@ -0,0 +1,185 @@
+//! Typed index and ID newtypes.
+//!
+//! [`define_id!`](crate::define_id) generates a `#[repr(transparent)]` newtype over an
+//! unsigned integer for table indexes and IDs, so an account index cannot be passed where a
+//! program index is expected. The value is stored XOR'd with the integer's `MAX` in a
+//! `NonZero`, which rules out `MAX` itself but gives `Option<Id>` the same size as the bare
+//! integer.
+//!
+//! Generated IDs implement [`Pack`] (the integer's fixed‑width little‑endian layout) and the
+//! [`DedupeEncodeable`]/[`DedupeDecodeable`] markers, and through them [`Encode`] and
+//! [`Decode`]: repeated IDs are deduplicated when a dedupe context is active, and
+//! `Vec<Id>` takes the bulk path. Decoding `MAX` fails with [`Error::OutOfRange`].
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! lencode::define_id!(
+//!     /// Index into a transaction's account keys.
+//!     pub AccountIdx: u32
+//! );
+//!
+//! let idx = AccountIdx::new(7).unwrap();
+//! assert_eq!(idx.get(), 7);
+//! assert_eq!(size_of::<Option<AccountIdx>>(), size_of::<u32>());
+//! assert!(AccountIdx::new(u32::MAX).is_none());
+//!
+//! let mut buf = Vec::new();
+//! encode(&idx, &mut buf).unwrap();
+//! assert_eq!(decode::<AccountIdx>(&mut Cursor::new(&buf)).unwrap(), idx);
+//! ```
+//!
+//! [`NonMaxU8`] through [`NonMaxU64`] are predefined for untyped use.
+
+/// Defines a typed ID over an unsigned integer. See the [module docs](crate::id).
+///
+/// ```ignore
+/// define_id!(AccountIdx: u32);
+/// define_id!(
+///     /// Doc comments and other attributes are forwarded.
+///     pub(crate) SlotIdx: u64
+/// );
+/// ```
+#[macro_export]
+macro_rules! define_id {
+    ($(#[$meta:meta])* $vis:vis $name:ident : $repr:ty) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(transparent)]
+        $vis struct $name(::core::num::NonZero<$repr>);
+
+        impl $name {
+            /// The largest representable ID, one below the integer's `MAX`.
+            pub const MAX: Self = match Self::new(<$repr>::MAX - 1) {
+                Some(id) => id,
+                None => unreachable!(),
+            };
+
+            /// Wraps `value`, or returns `None` if it is the integer's `MAX`.
+            #[inline(always)]
+            pub const fn new(value: $repr) -> Option<Self> {
+                match ::core::num::NonZero::new(value ^ <$repr>::MAX) {
+                    Some(inner) => Some(Self(inner)),
+                    None => None,
+                }
+            }
+
+            /// Returns the wrapped value.
+            #[inline(always)]
+            pub const fn get(self) -> $repr {
+                self.0.get() ^ <$repr>::MAX
+            }
+        }
+
+        impl ::core::convert::From<$name> for $repr {
+            #[inline(always)]
+            fn from(id: $name) -> Self {
+                id.get()
+            }
+        }
+
+        impl ::core::convert::TryFrom<$repr> for $name {
+            type Error = $crate::io::Error;
+
+            #[inline(always)]
+            fn try_from(value: $repr) -> $crate::Result<Self> {
+                Self::new(value).ok_or($crate::io::Error::OutOfRange {
+                    value: value as i128,
+                    max: (<$repr>::MAX - 1) as i128,
+                })
+            }
+        }
+
+        // The stored value is XOR'd, so order by the unwrapped value.
+        impl ::core::cmp::PartialOrd for $name {
+            #[inline(always)]
+            fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
+                Some(::core::cmp::Ord::cmp(self, other))
+            }
+        }
+
+        impl ::core::cmp::Ord for $name {
+            #[inline(always)]
+            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                self.get().cmp(&other.get())
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_tuple(stringify!($name)).field(&self.get()).finish()
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::core::fmt::Display::fmt(&self.get(), f)
+            }
+        }
+
+        impl $crate::pack::Pack for $name {
+            #[inline(always)]
+            fn pack(&self, writer: &mut impl $crate::io::Write) -> $crate::Result<usize> {
+                $crate::pack::Pack::pack(&self.get(), writer)
+            }
+
+            #[inline(always)]
+            fn unpack(reader: &mut impl $crate::io::Read) -> $crate::Result<Self> {
+                let value: $repr = $crate::pack::Pack::unpack(reader)?;
+                ::core::convert::TryFrom::try_from(value)
+            }
+        }
+
+        impl $crate::dedupe::DedupeEncodeable for $name {}
+        impl $crate::dedupe::DedupeDecodeable for $name {}
+    };
+}
+
+define_id!(
+    /// A `u8` that is never `u8::MAX`, so `Option<NonMaxU8>` is one byte.
+    pub NonMaxU8: u8
+);
+define_id!(
+    /// A `u16` that is never `u16::MAX`, so `Option<NonMaxU16>` is two bytes.
+    pub NonMaxU16: u16
+);
+define_id!(
+    /// A `u32` that is never `u32::MAX`, so `Option<NonMaxU32>` is four bytes.
+    pub NonMaxU32: u32
+);
+define_id!(
+    /// A `u64` that is never `u64::MAX`, so `Option<NonMaxU64>` is eight bytes.
+    pub NonMaxU64: u64
+);
+
+#[cfg(test)]
+use crate::prelude::*;
+
+#[test]
+fn test_ids_roundtrip_order_and_reject_max() {
+    assert_eq!(core::mem::size_of::<Option<NonMaxU32>>(), 4);
+    assert_eq!(NonMaxU16::MAX.get(), u16::MAX - 1);
+    let ids = [3, 0, 200].map(|v| NonMaxU32::new(v).unwrap());
+    let mut sorted = ids;
+    sorted.sort();
+    assert_eq!(sorted.map(NonMaxU32::get), [0, 3, 200]);
+
+    let mut buf = Vec::new();
+    encode(&ids.to_vec(), &mut buf).unwrap();
+    assert_eq!(buf.len(), 1 + 3 * 4);
+    let decoded: Vec<NonMaxU32> = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, ids);
+
+    let mut ctx = EncoderContext::with_dedupe();
+    let mut deduped = Vec::new();
+    encode_ext(&[ids[0]; 4].to_vec(), &mut deduped, Some(&mut ctx)).unwrap();
+    assert!(deduped.len() < 1 + 4 * 4);
+
+    assert!(matches!(
+        decode::<NonMaxU8>(&mut Cursor::new(&[u8::MAX][..])),
+        Err(Error::OutOfRange {
+            value: 255,
+            max: 254
+        })
+    ));
+}
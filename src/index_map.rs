@@ -0,0 +1,114 @@
+//! Insertion‑ordered maps and sets (`indexmap` feature).
+//!
+//! [`IndexMap`] and [`IndexSet`] use the same layout as the other maps and sets: a length
+//! followed by each entry. Entries are written in iteration order, which for these types is
+//! insertion order, and decoding inserts them in the order they were read, so the order
+//! survives a roundtrip. `HashMap` and `HashSet` have no stable order, and `BTreeMap` and
+//! `BTreeSet` always come back sorted; use these types when a protocol depends on entry
+//! order.
+//!
+//! Any hasher implementing [`BuildHasher`] + [`Default`] can be used on the decoding side.
+use core::hash::{BuildHasher, Hash};
+
+use ::indexmap::{IndexMap, IndexSet};
+
+use crate::prelude::*;
+use crate::{duplicate_keys_rejected, prealloc_capacity};
+
+impl<K: Encode, V: Encode, S> Encode for IndexMap<K, V, S> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = 0;
+        total_written += Self::encode_len(self.len(), writer)?;
+        for (key, value) in self {
+            total_written += key.encode_ext(writer, ctx.as_deref_mut())?;
+            total_written += value.encode_ext(writer, ctx.as_deref_mut())?;
+        }
+        Ok(total_written)
+    }
+}
+
+impl<K: Decode + Eq + Hash, V: Decode, S: BuildHasher + Default> Decode for IndexMap<K, V, S> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            let reject = duplicate_keys_rejected(ctx.as_deref());
+            let mut map =
+                IndexMap::with_capacity_and_hasher(prealloc_capacity::<(K, V)>(len), S::default());
+            for _ in 0..len {
+                let key = K::decode_ext(reader, ctx.as_deref_mut())?;
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                if map.insert(key, value).is_some() && reject {
+                    return Err(Error::DuplicateKey);
+                }
+            }
+            Ok(map)
+        })
+    }
+}
+
+impl<V: Encode, S> Encode for IndexSet<V, S> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = 0;
+        total_written += Self::encode_len(self.len(), writer)?;
+        for value in self {
+            total_written += value.encode_ext(writer, ctx.as_deref_mut())?;
+        }
+        Ok(total_written)
+    }
+}
+
+impl<V: Decode + Eq + Hash, S: BuildHasher + Default> Decode for IndexSet<V, S> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            let mut set =
+                IndexSet::with_capacity_and_hasher(prealloc_capacity::<V>(len), S::default());
+            for _ in 0..len {
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                set.insert(value);
+            }
+            Ok(set)
+        })
+    }
+}
+
+#[test]
+fn test_index_map_preserves_insertion_order() {
+    type Map = IndexMap<u32, u8, hashbrown::DefaultHashBuilder>;
+    let map: Map = [(30, 3), (10, 1), (20, 2)].into_iter().collect();
+    let mut buf = Vec::new();
+    map.encode(&mut buf).unwrap();
+    assert_eq!(buf, [3, 30, 3, 10, 1, 20, 2]);
+    let decoded: Map = decode(&mut Cursor::new(&buf)).unwrap();
+    assert!(decoded.keys().eq(map.keys()));
+
+    let set: IndexSet<&str, hashbrown::DefaultHashBuilder> = ["b", "a"].into_iter().collect();
+    let mut buf = Vec::new();
+    set.encode(&mut buf).unwrap();
+    let decoded: IndexSet<String, hashbrown::DefaultHashBuilder> =
+        decode(&mut Cursor::new(&buf)).unwrap();
+    assert!(decoded.iter().eq(["b", "a"]));
+
+    // Duplicate keys keep the first position.
+    let mut ctx = DecoderContext::new();
+    ctx.duplicate_keys = DuplicateKeyPolicy::Reject;
+    let dup = [2, 1, 5, 1, 6];
+    let decoded: Map = decode(&mut Cursor::new(&dup)).unwrap();
+    assert_eq!(decoded[&1], 6);
+    assert!(matches!(
+        Map::decode_ext(&mut Cursor::new(&dup), Some(&mut ctx)),
+        Err(Error::DuplicateKey)
+    ));
+}
@@ -0,0 +1,217 @@
+//! Decode‑time string interning.
+//!
+//! [`InternedString`] has the same wire layout as `String`, but decodes into a shared
+//! `Arc<str>`. When the [`DecoderContext`] carries a [`StringInterner`]
+//! ([`DecoderContext::with_interning`]), every decoded string is looked up in it first, so
+//! repeated values (log lines, program names, mint symbols) share one heap allocation
+//! instead of one per occurrence. The encoded bytes are unaffected; pair it with the dedupe
+//! table if repeats should also shrink on the wire.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! let logs = vec![String::from("Program log: Instruction: Transfer"); 3];
+//! let mut buf = Vec::new();
+//! encode(&logs, &mut buf).unwrap();
+//!
+//! let mut ctx = DecoderContext::with_interning();
+//! let lines: Vec<InternedString> = decode_ext(&mut Cursor::new(&buf), Some(&mut ctx)).unwrap();
+//! assert_eq!(lines[0], logs[0].as_str());
+//! assert!(InternedString::ptr_eq(&lines[0], &lines[2]));
+//! assert_eq!(ctx.interner.unwrap().len(), 1);
+//! ```
+use core::borrow::Borrow;
+use core::ops::Deref;
+use hashbrown::HashSet;
+
+#[cfg(not(feature = "std"))]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use crate::prelude::*;
+
+/// Default for [`StringInterner::max_entries`].
+pub const DEFAULT_MAX_INTERNED: usize = 1 << 16;
+
+/// A decoder‑side table of shared strings, used by [`InternedString`].
+#[derive(Clone, Debug)]
+pub struct StringInterner {
+    strings: HashSet<Arc<str>>,
+    /// Once the table holds this many strings, new values are decoded unshared instead of
+    /// being added, so unique input cannot grow the table without bound.
+    pub max_entries: usize,
+}
+
+impl Default for StringInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StringInterner {
+    /// Creates an empty table holding up to [`DEFAULT_MAX_INTERNED`] strings.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::with_max_entries(DEFAULT_MAX_INTERNED)
+    }
+
+    /// Creates an empty table holding up to `max_entries` strings.
+    #[inline(always)]
+    pub fn with_max_entries(max_entries: usize) -> Self {
+        Self {
+            strings: HashSet::new(),
+            max_entries,
+        }
+    }
+
+    /// Returns the shared copy of `value`, adding it if there is room.
+    pub fn intern(&mut self, value: &str) -> Arc<str> {
+        if let Some(shared) = self.strings.get(value) {
+            return shared.clone();
+        }
+        let shared: Arc<str> = Arc::from(value);
+        if self.strings.len() < self.max_entries {
+            self.strings.insert(shared.clone());
+        }
+        shared
+    }
+
+    /// Returns the number of interned strings.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if no strings are interned.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Drops all interned strings. Values already handed out stay valid.
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.strings.clear();
+    }
+}
+
+/// A string encoded like `String` that decodes into a shared `Arc<str>`. See the
+/// [module docs](self).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InternedString(pub Arc<str>);
+
+impl InternedString {
+    /// Returns `true` if both strings point to the same allocation.
+    #[inline(always)]
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+
+    /// Consumes the wrapper and returns the inner `Arc<str>`.
+    #[inline(always)]
+    pub fn into_inner(self) -> Arc<str> {
+        self.0
+    }
+}
+
+impl Deref for InternedString {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for InternedString {
+    #[inline(always)]
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for InternedString {
+    #[inline(always)]
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for InternedString {
+    #[inline(always)]
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl From<&str> for InternedString {
+    #[inline(always)]
+    fn from(value: &str) -> Self {
+        Self(Arc::from(value))
+    }
+}
+
+impl From<String> for InternedString {
+    #[inline(always)]
+    fn from(value: String) -> Self {
+        Self(Arc::from(value))
+    }
+}
+
+impl From<Arc<str>> for InternedString {
+    #[inline(always)]
+    fn from(value: Arc<str>) -> Self {
+        Self(value)
+    }
+}
+
+impl Encode for InternedString {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        (&*self.0).encode_ext(writer, ctx)
+    }
+}
+
+impl Decode for InternedString {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        let value = String::decode_ext(reader, ctx.as_deref_mut())?;
+        Ok(match ctx.and_then(|ctx| ctx.interner.as_mut()) {
+            Some(interner) => Self(interner.intern(&value)),
+            None => Self::from(value),
+        })
+    }
+}
+
+#[test]
+fn test_interner_respects_max_entries() {
+    let mut buf = Vec::new();
+    for name in ["a", "b", "a", "c", "b", "c"] {
+        encode(&name, &mut buf).unwrap();
+    }
+    let mut ctx = DecoderContext {
+        interner: Some(StringInterner::with_max_entries(2)),
+        ..DecoderContext::new()
+    };
+    let mut cursor = Cursor::new(&buf);
+    let decoded: Vec<InternedString> = (0..6)
+        .map(|_| InternedString::decode_ext(&mut cursor, Some(&mut ctx)).unwrap())
+        .collect();
+    assert_eq!(
+        decoded,
+        ["a", "b", "a", "c", "b", "c"].map(InternedString::from)
+    );
+    assert!(InternedString::ptr_eq(&decoded[0], &decoded[2]));
+    assert!(InternedString::ptr_eq(&decoded[1], &decoded[4]));
+    // The table was full when "c" arrived, so each "c" gets its own allocation.
+    assert!(!InternedString::ptr_eq(&decoded[3], &decoded[5]));
+    assert_eq!(ctx.interner.as_ref().unwrap().len(), 2);
+
+    let plain: InternedString = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(plain, "a");
+}
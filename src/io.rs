@@ -1,21 +1,78 @@
 //! Lightweight, no-std compatible I/O traits and adapters used by the [`Encode`]/[`Decode`] APIs.
 mod cursor;
+#[cfg(feature = "embedded-io")]
+mod embedded;
+mod slice;
 
 pub use cursor::*;
+#[cfg(feature = "embedded-io")]
+pub use embedded::*;
+pub use slice::*;
 
 use crate::*;
 
 #[derive(Debug)]
 /// Error type returned by encoding/decoding and I/O adapters.
 pub enum Error {
-    /// Input data was malformed or inconsistent.
+    /// Input data was malformed or inconsistent, and no more specific variant applies.
     InvalidData,
+    /// A decoded string was not valid UTF‑8.
+    InvalidUtf8,
+    /// A compressed payload could not be decompressed.
+    DecompressionFailed {
+        /// What went wrong.
+        kind: DecompressionErrorKind,
+    },
+    /// A payload decoded to a different number of bytes than its header declared.
+    LengthMismatch {
+        /// The declared length.
+        expected: usize,
+        /// The length actually produced.
+        actual: usize,
+    },
+    /// An enum tag did not match any known variant.
+    UnknownDiscriminant(u64),
+    /// A peer's schema fingerprint differs from ours; see
+    /// [`schema_handshake`](crate::layout::schema_handshake).
+    SchemaMismatch {
+        /// Our fingerprint.
+        expected: u64,
+        /// The peer's fingerprint.
+        found: u64,
+    },
     /// A size or length field was invalid for the operation.
     IncorrectLength,
     /// The writer had insufficient capacity to accept all bytes.
     WriterOutOfSpace,
+    /// The writer stopped accepting bytes (its `write` returned `Ok(0)`) partway through a
+    /// value; see [`write_all`].
+    ShortWrite {
+        /// Bytes of the value that were written before the writer stopped.
+        written: usize,
+        /// Bytes the value needed.
+        expected: usize,
+    },
     /// The reader ran out of data before the operation completed.
     ReaderOutOfData,
+    /// A decoded map contained the same key more than once while
+    /// [`DuplicateKeyPolicy::Reject`](crate::context::DuplicateKeyPolicy::Reject) was active.
+    DuplicateKey,
+    /// Nested containers exceeded
+    /// [`DecoderContext::max_depth`](crate::context::DecoderContext::max_depth).
+    DepthLimitExceeded,
+    /// A borrowed decode ([`DecodeBorrowed`](crate::DecodeBorrowed)) met a payload that is not
+    /// stored verbatim in the input, e.g. a zstd‑compressed string decoded as `&str`. Decode
+    /// into a `Cow` instead to fall back to an owned copy.
+    NotBorrowable,
+    /// A decoded integer does not fit the platform type it decodes into, e.g. a `usize` above
+    /// `u32::MAX` on a 32‑bit target.
+    OutOfRange {
+        /// The decoded value.
+        value: i128,
+        /// The largest value the target type can hold. Signed types also reject values below
+        /// `-max - 1`.
+        max: i128,
+    },
     #[cfg(feature = "std")]
     /// Wrapped `std::io::Error` when using the `std` feature.
     StdIo(std::io::Error),
@@ -24,6 +81,18 @@ pub enum Error {
     StdIo(StdIoShim),
 }
 
+/// Why a compressed payload could not be decompressed; see [`Error::DecompressionFailed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecompressionErrorKind {
+    /// The frame header does not declare the decompressed size.
+    UnknownContentSize,
+    /// The compressed data is corrupt, or needs a dictionary that was not supplied.
+    Corrupt,
+    /// This build has no decompressor (the `zstd` feature is disabled).
+    Unsupported,
+}
+
 #[cfg(not(feature = "std"))]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 /// Empty stand‑in used as a no‑std substitute for `std::io::Error`.
@@ -36,12 +105,36 @@ impl core::fmt::Display for Error {
                 f,
                 "Invalid data was encountered (corrupted or incorrect bits/bytes in data stream)"
             ),
+            Error::InvalidUtf8 => write!(f, "Decoded string is not valid UTF-8"),
+            Error::DecompressionFailed { kind } => write!(f, "Decompression failed: {kind:?}"),
+            Error::LengthMismatch { expected, actual } => write!(
+                f,
+                "Length mismatch: expected {expected} bytes, got {actual}"
+            ),
+            Error::UnknownDiscriminant(disc) => write!(f, "Unknown enum discriminant {disc}"),
+            Error::SchemaMismatch { expected, found } => write!(
+                f,
+                "Schema mismatch: expected fingerprint {expected:#018x}, peer sent {found:#018x}"
+            ),
             Error::IncorrectLength => write!(f, "Incorrect length"),
             Error::WriterOutOfSpace => write!(f, "Tried to write past the capacity of the writer"),
+            Error::ShortWrite { written, expected } => write!(
+                f,
+                "Short write: the writer accepted {written} of {expected} bytes"
+            ),
             Error::ReaderOutOfData => write!(
                 f,
                 "Tried to read past the end of the reader's available data"
             ),
+            Error::DuplicateKey => write!(f, "Duplicate key encountered while decoding a map"),
+            Error::DepthLimitExceeded => write!(f, "Maximum nesting depth exceeded"),
+            Error::NotBorrowable => write!(
+                f,
+                "Payload is not stored verbatim in the input and cannot be borrowed"
+            ),
+            Error::OutOfRange { value, max } => {
+                write!(f, "Decoded value {value} is out of range (max {max})")
+            }
             #[cfg(feature = "std")]
             Error::StdIo(e) => write!(f, "IO error: {e}"),
             #[cfg(not(feature = "std"))]
@@ -50,8 +143,56 @@ impl core::fmt::Display for Error {
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for Error {}
+/// Compact `defmt` formatting for logging decode failures over RTT; the strings are interned
+/// by `defmt` instead of being linked in as `core::fmt` machinery.
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::InvalidData => defmt::write!(f, "Invalid data"),
+            Error::InvalidUtf8 => defmt::write!(f, "Invalid UTF-8"),
+            Error::DecompressionFailed { kind } => {
+                defmt::write!(f, "Decompression failed: {}", kind)
+            }
+            Error::LengthMismatch { expected, actual } => defmt::write!(
+                f,
+                "Length mismatch: expected {=usize}, got {=usize}",
+                expected,
+                actual
+            ),
+            Error::UnknownDiscriminant(disc) => {
+                defmt::write!(f, "Unknown discriminant {=u64}", disc)
+            }
+            Error::SchemaMismatch { expected, found } => defmt::write!(
+                f,
+                "Schema mismatch: expected {=u64:x}, found {=u64:x}",
+                expected,
+                found
+            ),
+            Error::IncorrectLength => defmt::write!(f, "Incorrect length"),
+            Error::WriterOutOfSpace => defmt::write!(f, "Writer out of space"),
+            Error::ShortWrite { written, expected } => defmt::write!(
+                f,
+                "Short write: {=usize} of {=usize} bytes",
+                written,
+                expected
+            ),
+            Error::ReaderOutOfData => defmt::write!(f, "Reader out of data"),
+            Error::DuplicateKey => defmt::write!(f, "Duplicate key"),
+            Error::DepthLimitExceeded => defmt::write!(f, "Maximum nesting depth exceeded"),
+            Error::NotBorrowable => defmt::write!(f, "Payload cannot be borrowed"),
+            Error::OutOfRange { value, max } => defmt::write!(
+                f,
+                "Decoded value {=i128} is out of range (max {=i128})",
+                value,
+                max
+            ),
+            Error::StdIo(_) => defmt::write!(f, "IO error"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
 
 #[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
@@ -68,17 +209,47 @@ impl From<Error> for std::io::Error {
             Error::WriterOutOfSpace => {
                 std::io::Error::new(std::io::ErrorKind::WriteZero, "Write short")
             }
+            Error::ShortWrite { .. } => {
+                std::io::Error::new(std::io::ErrorKind::WriteZero, "Short write")
+            }
             Error::InvalidData => {
                 std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid data")
             }
             Error::IncorrectLength => {
                 std::io::Error::new(std::io::ErrorKind::InvalidInput, "Incorrect length")
             }
+            Error::InvalidUtf8 => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid UTF-8")
+            }
+            Error::DecompressionFailed { .. } => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Decompression failed")
+            }
+            Error::LengthMismatch { .. } => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Length mismatch")
+            }
+            Error::UnknownDiscriminant(_) => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Unknown discriminant")
+            }
+            Error::SchemaMismatch { .. } => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Schema mismatch")
+            }
             #[cfg(feature = "std")]
             Error::StdIo(e) => e,
             Error::ReaderOutOfData => {
                 std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "End of data")
             }
+            Error::DuplicateKey => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Duplicate key")
+            }
+            Error::DepthLimitExceeded => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Depth limit exceeded")
+            }
+            Error::NotBorrowable => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Not borrowable")
+            }
+            Error::OutOfRange { .. } => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Value out of range")
+            }
         }
     }
 }
@@ -102,10 +273,66 @@ pub trait Read {
     fn advance(&mut self, _n: usize) {}
 }
 
+/// Forwards to the underlying reader, including its zero‑copy [`Read::buf`] access, so readers
+/// can be held as trait objects and still passed wherever `&mut impl Read` is expected.
+impl Read for &mut (dyn Read + '_) {
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        (**self).read(buf)
+    }
+
+    #[inline(always)]
+    fn buf(&self) -> Option<&[u8]> {
+        (**self).buf()
+    }
+
+    #[inline(always)]
+    fn advance(&mut self, n: usize) {
+        (**self).advance(n)
+    }
+}
+
+macro_rules! impl_read_for_boxed_dyn {
+    ($($dyn_ty:ty),+) => {
+        $(
+            impl Read for alloc::boxed::Box<$dyn_ty> {
+                #[inline(always)]
+                fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+                    (**self).read(buf)
+                }
+
+                #[inline(always)]
+                fn buf(&self) -> Option<&[u8]> {
+                    (**self).buf()
+                }
+
+                #[inline(always)]
+                fn advance(&mut self, n: usize) {
+                    (**self).advance(n)
+                }
+            }
+        )+
+    };
+}
+
+impl_read_for_boxed_dyn!(dyn Read + '_, dyn Read + Send + '_);
+
 /// Minimal write abstraction used by this crate in both std and no‑std modes.
+///
+/// # Short writes
+///
+/// `write` may accept fewer bytes than it was given, as `std::io::Write::write` may, and
+/// report how many it took. Returning `Ok(0)` for a non‑empty `buf` means the sink will take
+/// no more. A writer that runs out of room should prefer failing with
+/// [`Error::WriterOutOfSpace`], ideally before writing anything, as [`Cursor`] does.
+///
+/// The encoders in this crate never assume a single call wrote everything: they go through
+/// [`write_all`], which retries until the whole slice is written and fails with
+/// [`Error::ShortWrite`] if the writer stops early. Bytes accepted before such a failure stay
+/// in the sink, so the stream holds a torn value and should be discarded.
 pub trait Write {
-    /// Writes the entire `buf` into the underlying sink when possible and
-    /// returns the number of bytes written.
+    /// Writes bytes from `buf` into the underlying sink and returns how many were written.
+    /// See the [short‑write policy](Write#short-writes).
     fn write(&mut self, buf: &[u8]) -> Result<usize>;
     /// Flushes any internal buffers, if applicable.
     fn flush(&mut self) -> Result<()>;
@@ -130,6 +357,148 @@ pub trait Write {
     /// fixed‑capacity writers like [`Cursor`].
     #[inline(always)]
     fn reserve(&mut self, _additional: usize) {}
+
+    /// Returns how many more bytes the writer can accept, if it has a fixed capacity.
+    ///
+    /// Fixed‑size writers such as [`Cursor`] report their free space; growable and streaming
+    /// writers return `None` (the default). Encoders check it before writing a multi‑byte
+    /// value so a full buffer fails with [`Error::WriterOutOfSpace`] without a torn partial
+    /// value left behind.
+    #[inline(always)]
+    fn remaining_capacity(&mut self) -> Option<usize> {
+        None
+    }
+}
+
+/// Forwards to the underlying writer, including its zero‑copy [`Write::buf_mut`] access, so
+/// writers can be held as trait objects and still passed wherever `&mut impl Write` is
+/// expected.
+impl Write for &mut (dyn Write + '_) {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        (**self).write(buf)
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
+
+    #[inline(always)]
+    fn buf_mut(&mut self) -> Option<&mut [u8]> {
+        (**self).buf_mut()
+    }
+
+    #[inline(always)]
+    fn advance_mut(&mut self, n: usize) {
+        (**self).advance_mut(n)
+    }
+
+    #[inline(always)]
+    fn reserve(&mut self, additional: usize) {
+        (**self).reserve(additional)
+    }
+
+    #[inline(always)]
+    fn remaining_capacity(&mut self) -> Option<usize> {
+        (**self).remaining_capacity()
+    }
+}
+
+/// Reads exactly `buf.len()` bytes from `reader`.
+#[inline(always)]
+pub(crate) fn read_exact(reader: &mut impl Read, buf: &mut [u8]) -> Result<()> {
+    let mut read = 0usize;
+    while read < buf.len() {
+        let n = reader.read(&mut buf[read..])?;
+        if n == 0 {
+            return Err(Error::ReaderOutOfData);
+        }
+        read += n;
+    }
+    Ok(())
+}
+
+/// Fails with [`Error::WriterOutOfSpace`] if `writer` reports less than `len` bytes of
+/// [`Write::remaining_capacity`].
+#[inline(always)]
+pub fn ensure_capacity(writer: &mut impl Write, len: usize) -> Result<()> {
+    if writer.remaining_capacity().is_some_and(|free| free < len) {
+        return Err(Error::WriterOutOfSpace);
+    }
+    Ok(())
+}
+
+/// Writes all of `buf`, calling [`Write::write`] until every byte is accepted, and returns
+/// `buf.len()`.
+///
+/// Fails with [`Error::ShortWrite`] if the writer returns `Ok(0)` before the end; errors from
+/// the writer are passed through unchanged.
+#[inline(always)]
+pub fn write_all(writer: &mut impl Write, buf: &[u8]) -> Result<usize> {
+    let mut written = writer.write(buf)?;
+    while written < buf.len() {
+        match writer.write(&buf[written..])? {
+            0 => {
+                return Err(Error::ShortWrite {
+                    written,
+                    expected: buf.len(),
+                });
+            }
+            n => written += n,
+        }
+    }
+    Ok(buf.len())
+}
+
+/// Writes all of `buf`, or nothing if `writer` reports too little remaining capacity.
+#[inline(always)]
+pub fn write_whole(writer: &mut impl Write, buf: &[u8]) -> Result<usize> {
+    ensure_capacity(writer, buf.len())?;
+    write_all(writer, buf)
+}
+
+/// Consumes `len` bytes from `reader` without keeping them.
+pub(crate) fn skip_bytes(reader: &mut impl Read, mut len: usize) -> Result<()> {
+    if let Some(buf) = reader.buf() {
+        if buf.len() < len {
+            return Err(Error::ReaderOutOfData);
+        }
+        reader.advance(len);
+        return Ok(());
+    }
+    let mut sink = [0u8; 256];
+    while len > 0 {
+        let step = len.min(sink.len());
+        read_exact(reader, &mut sink[..step])?;
+        len -= step;
+    }
+    Ok(())
+}
+
+/// Reads exactly `len` bytes from `reader` into a new `Vec`.
+///
+/// The buffer grows in steps of at most [`MAX_PREALLOC_BYTES`](crate::MAX_PREALLOC_BYTES), so a
+/// forged `len` runs out of input before it can force a large allocation.
+#[inline]
+pub(crate) fn read_vec(reader: &mut impl Read, len: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(len.min(crate::MAX_PREALLOC_BYTES));
+    read_into_vec(reader, &mut out, len)?;
+    Ok(out)
+}
+
+/// Reads exactly `len` bytes from `reader` and appends them to `out`, growing it in the same
+/// bounded steps as [`read_vec`]. On error `out` may hold part of the bytes.
+#[inline]
+pub(crate) fn read_into_vec(reader: &mut impl Read, out: &mut Vec<u8>, len: usize) -> Result<()> {
+    let end = out.len().checked_add(len).ok_or(Error::InvalidData)?;
+    while out.len() < end {
+        let start = out.len();
+        let step = (end - start).min(crate::MAX_PREALLOC_BYTES);
+        out.resize(start + step, 0);
+        read_exact(reader, &mut out[start..])?;
+    }
+    Ok(())
 }
 
 #[cfg(feature = "std")]
@@ -249,6 +618,94 @@ impl Write for VecWriter {
     }
 }
 
+/// Appends to a `Vec<u8>` and truncates it back to its original length unless committed.
+///
+/// An encode that fails midway leaves the bytes of every field written so far behind.
+/// Writing through a `TransactionalWriter` and calling [`TransactionalWriter::commit`] only
+/// on success keeps message buffers intact: dropping the writer uncommitted rolls the
+/// buffer back. [`encode_atomic`](crate::encode_atomic) wraps this pattern.
+///
+/// Only the buffer is rolled back; dedupe or diff state in an [`EncoderContext`] keeps
+/// entries recorded before the failure, so discard the context too.
+pub struct TransactionalWriter<'a> {
+    buf: &'a mut alloc::vec::Vec<u8>,
+    start: usize,
+    committed: bool,
+}
+
+impl<'a> TransactionalWriter<'a> {
+    /// Starts a transaction appending to `buf`.
+    #[inline(always)]
+    pub const fn new(buf: &'a mut alloc::vec::Vec<u8>) -> Self {
+        let start = buf.len();
+        Self {
+            buf,
+            start,
+            committed: false,
+        }
+    }
+
+    /// Returns the number of bytes written in this transaction so far.
+    #[inline(always)]
+    pub const fn written(&self) -> usize {
+        self.buf.len() - self.start
+    }
+
+    /// Keeps the bytes written in this transaction.
+    #[inline(always)]
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+
+    /// Discards the bytes written in this transaction. Equivalent to dropping the writer.
+    #[inline(always)]
+    pub fn rollback(self) {}
+}
+
+impl Drop for TransactionalWriter<'_> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        if !self.committed {
+            self.buf.truncate(self.start);
+        }
+    }
+}
+
+impl Write for TransactionalWriter<'_> {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn buf_mut(&mut self) -> Option<&mut [u8]> {
+        if self.buf.capacity() - self.buf.len() < 17 {
+            self.buf.reserve(self.buf.capacity().max(256));
+        }
+        let spare = self.buf.spare_capacity_mut();
+        // SAFETY: callers only write to the slice and then call `advance_mut` for the bytes
+        // they initialized, as with `VecWriter`.
+        Some(unsafe { core::slice::from_raw_parts_mut(spare.as_mut_ptr().cast(), spare.len()) })
+    }
+
+    #[inline(always)]
+    fn advance_mut(&mut self, n: usize) {
+        let new_len = self.buf.len() + n;
+        unsafe { self.buf.set_len(new_len) };
+    }
+
+    #[inline(always)]
+    fn reserve(&mut self, additional: usize) {
+        self.buf.reserve(additional);
+    }
+}
+
 #[cfg(not(feature = "std"))]
 impl Write for alloc::vec::Vec<u8> {
     #[inline(always)]
@@ -300,6 +757,43 @@ impl Write for alloc::vec::Vec<u8> {
     }
 }
 
+/// A [`Write`] adapter that feeds every byte written into a [`core::hash::Hasher`].
+///
+/// Nothing is buffered or stored; the writer only forwards bytes to the hasher and counts
+/// them. See [`hash_encoded`](crate::hash_encoded) for the usual entry point.
+pub struct HashingWriter<'a, H: core::hash::Hasher> {
+    hasher: &'a mut H,
+    written: usize,
+}
+
+impl<'a, H: core::hash::Hasher> HashingWriter<'a, H> {
+    /// Creates a new [`HashingWriter`] feeding `hasher`.
+    #[inline(always)]
+    pub const fn new(hasher: &'a mut H) -> Self {
+        Self { hasher, written: 0 }
+    }
+
+    /// Returns the total number of bytes fed to the hasher so far.
+    #[inline(always)]
+    pub const fn bytes_written(&self) -> usize {
+        self.written
+    }
+}
+
+impl<H: core::hash::Hasher> Write for HashingWriter<'_, H> {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.hasher.write(buf);
+        self.written += buf.len();
+        Ok(buf.len())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
 #[test]
 fn test_write_vec() {
     let mut my_vec = alloc::vec::Vec::new();
@@ -311,3 +805,167 @@ fn test_write_vec() {
 
     assert_eq!(my_vec, b"Hello, world!".to_vec());
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn test_hashing_writer_counts_bytes() {
+    use core::hash::Hasher;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let mut writer = HashingWriter::new(&mut hasher);
+    writer.write(&[1, 2, 3]).unwrap();
+    writer.write(&[4]).unwrap();
+    assert_eq!(writer.bytes_written(), 4);
+
+    let mut expected = std::collections::hash_map::DefaultHasher::new();
+    expected.write(&[1, 2, 3]);
+    expected.write(&[4]);
+    assert_eq!(hasher.finish(), expected.finish());
+}
+
+#[test]
+fn test_decode_from_dyn_readers() {
+    use alloc::boxed::Box;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    let mut buf = Vec::new();
+    encode(&300u64, &mut buf).unwrap();
+    encode(&String::from("plugin"), &mut buf).unwrap();
+
+    let mut readers: Vec<Box<dyn Read>> = Vec::new();
+    readers.push(Box::new(Cursor::new(buf.clone())));
+    #[cfg(feature = "std")]
+    readers.push(Box::new(std::io::Cursor::new(buf.clone())));
+    for reader in &mut readers {
+        assert_eq!(decode::<u64>(reader).unwrap(), 300);
+        assert_eq!(decode_dyn::<String>(reader.as_mut()).unwrap(), "plugin");
+    }
+
+    // Zero-copy access survives the `dyn` indirection.
+    let mut cursor = Cursor::new(&buf[..]);
+    let mut reader: &mut dyn Read = &mut cursor;
+    assert_eq!(Read::buf(&reader), Some(&buf[..]));
+    assert_eq!(decode::<u64>(&mut reader).unwrap(), 300);
+    assert_eq!(cursor.position(), 3);
+}
+
+#[cfg(test)]
+struct FailingField;
+
+#[cfg(test)]
+impl Encode for FailingField {
+    fn encode_ext(
+        &self,
+        _writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        Err(Error::InvalidData)
+    }
+}
+
+#[test]
+fn test_transactional_writer_rolls_back() {
+    let mut buf = alloc::vec![1, 2, 3];
+    let mut tx = TransactionalWriter::new(&mut buf);
+    encode(&300u64, &mut tx).unwrap();
+    assert_eq!(tx.written(), 3);
+    tx.rollback();
+    assert_eq!(buf, [1, 2, 3]);
+
+    // A field failing midway leaves the buffer as it was.
+    let value = (String::from("header"), 7u32, FailingField);
+    assert!(matches!(
+        crate::encode_atomic(&value, &mut buf),
+        Err(Error::InvalidData)
+    ));
+    assert_eq!(buf, [1, 2, 3]);
+    assert_eq!(crate::encode_atomic(&(7u32, 8u8), &mut buf).unwrap(), 2);
+    assert_eq!(buf, [1, 2, 3, 7, 8]);
+}
+
+#[test]
+fn test_fixed_writers_fail_without_partial_writes() {
+    let mut storage = [0u8; 6];
+    let mut cursor = Cursor::new(&mut storage[..]);
+    assert_eq!(cursor.remaining_capacity(), Some(6));
+    encode(&1u8, &mut cursor).unwrap();
+    // The header of this byte string fits, its payload does not.
+    assert!(matches!(
+        encode(&&[7u8; 8][..], &mut cursor),
+        Err(Error::WriterOutOfSpace)
+    ));
+    assert!(matches!(
+        u64::MAX.pack(&mut cursor),
+        Err(Error::WriterOutOfSpace)
+    ));
+    assert_eq!(cursor.position(), 1);
+
+    // An unaligned bit writer has no direct buffer, so values go through `write`.
+    let mut storage = [0u8; 4];
+    let mut bits = BitWriter::new(Cursor::new(&mut storage[..]));
+    bits.write_bit(true).unwrap();
+    assert_eq!(bits.remaining_capacity(), Some(3));
+    assert!(matches!(
+        encode(&u64::MAX, &mut bits),
+        Err(Error::WriterOutOfSpace)
+    ));
+    assert_eq!(bits.get_ref().position(), 0);
+    assert_eq!(encode(&300u32, &mut bits).unwrap(), 3);
+    assert_eq!(bits.remaining_capacity(), Some(0));
+}
+
+#[cfg(feature = "defmt")]
+#[test]
+fn test_defmt_format_impls() {
+    fn is_format<T: defmt::Format>() {}
+    is_format::<Error>();
+    is_format::<crate::context::DuplicateKeyPolicy>();
+    is_format::<crate::profile::MessageCompression>();
+    is_format::<crate::snapshot::SyncEvent>();
+}
+
+#[test]
+fn test_encoders_finish_short_writes_or_report_them() {
+    /// Accepts at most one byte per call, and nothing once `limit` bytes are in.
+    struct Trickle {
+        out: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            if buf.is_empty() || self.out.len() == self.limit {
+                return Ok(0);
+            }
+            self.out.push(buf[0]);
+            Ok(1)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    let value = (u128::MAX - 1, -5i64, String::from("trickle"), [9u8; 40]);
+    let mut expected = Vec::new();
+    let len = encode(&value, &mut expected).unwrap();
+    let mut sink = Trickle {
+        out: Vec::new(),
+        limit: usize::MAX,
+    };
+    assert_eq!(encode(&value, &mut sink).unwrap(), len);
+    assert_eq!(sink.out, expected);
+
+    // The sink stalls three bytes into the string's body.
+    let mut sink = Trickle {
+        out: Vec::new(),
+        limit: 17 + 1 + 1 + 3,
+    };
+    assert!(matches!(
+        encode(&value, &mut sink),
+        Err(Error::ShortWrite {
+            written: 3,
+            expected: 7
+        })
+    ));
+}
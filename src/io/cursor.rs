@@ -121,4 +121,9 @@ impl<T: AsMut<[u8]>> Write for Cursor<T> {
     fn advance_mut(&mut self, n: usize) {
         self.position += n;
     }
+
+    #[inline(always)]
+    fn remaining_capacity(&mut self) -> Option<usize> {
+        Some(self.stream.as_mut().len().saturating_sub(self.position))
+    }
 }
@@ -0,0 +1,168 @@
+//! Adapters between this crate's [`Read`]/[`Write`] and the `embedded-io` traits
+//! (`embedded-io` feature).
+//!
+//! - [`EmbeddedIo<T>`] wraps an `embedded_io::Read`/`Write` device (a UART, an SPI bus, a
+//!   flash region) so it can be passed to `encode`/`decode` directly.
+//! - [`LencodeIo<T>`] goes the other way, exposing any lencode [`Read`]/[`Write`] (such as a
+//!   [`Cursor`]) through the `embedded-io` traits.
+//!
+//! `embedded-io` errors carry only an [`ErrorKind`](embedded_io::ErrorKind), so device
+//! errors are mapped by kind: `InvalidData`/`InvalidInput` become [`Error::InvalidData`],
+//! everything else becomes [`Error::ReaderOutOfData`] on reads and
+//! [`Error::WriterOutOfSpace`] on writes.
+use embedded_io::ErrorKind;
+
+use super::{Error, Read, Write};
+use crate::Result;
+
+/// An `embedded-io` device usable as a lencode [`Read`]/[`Write`]. See the
+/// [module docs](self).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EmbeddedIo<T>(pub T);
+
+impl<T> EmbeddedIo<T> {
+    /// Consumes the adapter and returns the device.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Maps a device error to the closest lencode [`Error`], using `fallback` for kinds without
+/// a counterpart.
+#[inline(always)]
+fn from_device_error(err: impl embedded_io::Error, fallback: Error) -> Error {
+    match err.kind() {
+        ErrorKind::InvalidData | ErrorKind::InvalidInput => Error::InvalidData,
+        _ => fallback,
+    }
+}
+
+impl<T: embedded_io::Read> Read for EmbeddedIo<T> {
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match self.0.read(buf) {
+            // `embedded-io` signals end of input with `Ok(0)`.
+            Ok(0) if !buf.is_empty() => Err(Error::ReaderOutOfData),
+            Ok(n) => Ok(n),
+            Err(err) => Err(from_device_error(err, Error::ReaderOutOfData)),
+        }
+    }
+}
+
+impl<T: embedded_io::Write> Write for EmbeddedIo<T> {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.0
+            .write_all(buf)
+            .map_err(|err| from_device_error(err, Error::WriterOutOfSpace))?;
+        Ok(buf.len())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        self.0
+            .flush()
+            .map_err(|err| from_device_error(err, Error::WriterOutOfSpace))
+    }
+}
+
+/// A lencode [`Read`]/[`Write`] usable through the `embedded-io` traits. See the
+/// [module docs](self).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LencodeIo<T>(pub T);
+
+impl<T> LencodeIo<T> {
+    /// Consumes the adapter and returns the reader or writer.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl embedded_io::Error for Error {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Error::InvalidData
+            | Error::InvalidUtf8
+            | Error::DecompressionFailed { .. }
+            | Error::LengthMismatch { .. }
+            | Error::UnknownDiscriminant(_)
+            | Error::SchemaMismatch { .. }
+            | Error::IncorrectLength
+            | Error::DuplicateKey
+            | Error::DepthLimitExceeded
+            | Error::NotBorrowable
+            | Error::OutOfRange { .. } => ErrorKind::InvalidData,
+            Error::WriterOutOfSpace | Error::ShortWrite { .. } => ErrorKind::WriteZero,
+            Error::ReaderOutOfData | Error::StdIo(_) => ErrorKind::Other,
+        }
+    }
+}
+
+impl<T> embedded_io::ErrorType for LencodeIo<T> {
+    type Error = Error;
+}
+
+impl<T: Read> embedded_io::Read for LencodeIo<T> {
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match self.0.read(buf) {
+            // Lencode readers report end of input as an error; `embedded-io` expects `Ok(0)`.
+            Err(Error::ReaderOutOfData) => Ok(0),
+            result => result,
+        }
+    }
+}
+
+impl<T: Write> embedded_io::Write for LencodeIo<T> {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.0.write(buf)
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        self.0.flush()
+    }
+}
+
+#[test]
+fn test_embedded_io_adapters_roundtrip() {
+    use crate::prelude::*;
+
+    let values = (7u64, String::from("uart"), vec![1u16, 2, 3]);
+    let mut frame = [0u8; 32];
+    let mut device: &mut [u8] = &mut frame;
+    let n = encode(&values, &mut EmbeddedIo(&mut device)).unwrap();
+    // The slice device is full once every byte has been handed over.
+    let mut tiny = [0u8; 2];
+    let mut tiny_device: &mut [u8] = &mut tiny;
+    assert!(matches!(
+        encode(&values, &mut EmbeddedIo(&mut tiny_device)),
+        Err(Error::WriterOutOfSpace)
+    ));
+
+    let mut input = EmbeddedIo(&frame[..n]);
+    let decoded: (u64, String, Vec<u16>) = decode(&mut input).unwrap();
+    assert_eq!(decoded, values);
+    assert!(matches!(
+        u8::decode(&mut input),
+        Err(Error::ReaderOutOfData)
+    ));
+
+    let mut reader = LencodeIo(Cursor::new(&frame[..n]));
+    let mut first = [0u8; 1];
+    assert_eq!(embedded_io::Read::read(&mut reader, &mut first).unwrap(), 1);
+    assert_eq!(first[0], 7);
+    let mut rest = [0u8; 64];
+    assert_eq!(
+        embedded_io::Read::read(&mut reader, &mut rest).unwrap(),
+        n - 1
+    );
+    assert_eq!(embedded_io::Read::read(&mut reader, &mut rest).unwrap(), 0);
+
+    let mut writer = LencodeIo(Cursor::new([0u8; 4]));
+    embedded_io::Write::write_all(&mut writer, &[1, 2, 3]).unwrap();
+    assert_eq!(writer.into_inner().position(), 3);
+}
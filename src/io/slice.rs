@@ -0,0 +1,71 @@
+use super::{Error, Read};
+
+/// Reader over a borrowed byte slice that can hand out sub‑slices living as long as the
+/// input itself.
+///
+/// [`Cursor`](super::Cursor) only lends its remaining bytes for the duration of a `&self`
+/// borrow; [`SliceReader::read_borrowed`] returns `&'a [u8]`, which is what lets
+/// [`DecodeBorrowed`](crate::DecodeBorrowed) decode `&'a str` and `&'a [u8]` without copying.
+#[derive(Clone, Copy, Debug)]
+pub struct SliceReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    /// Creates a new [`SliceReader`] positioned at the start of `data`.
+    #[inline(always)]
+    pub const fn new(data: &'a [u8]) -> Self {
+        SliceReader { data, position: 0 }
+    }
+
+    /// Returns the number of bytes consumed so far.
+    #[inline(always)]
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the unread part of the input, borrowed for `'a`.
+    #[inline(always)]
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.position..]
+    }
+
+    /// Consumes the next `n` bytes and returns them borrowed for `'a`.
+    ///
+    /// Fails with [`Error::ReaderOutOfData`] without consuming anything if fewer than `n`
+    /// bytes are left.
+    #[inline(always)]
+    pub fn read_borrowed(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        let rest = self.remaining();
+        if rest.len() < n {
+            return Err(Error::ReaderOutOfData);
+        }
+        self.position += n;
+        Ok(&rest[..n])
+    }
+}
+
+impl Read for SliceReader<'_> {
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let rest = self.remaining();
+        if rest.is_empty() {
+            return Err(Error::ReaderOutOfData);
+        }
+        let n = buf.len().min(rest.len());
+        buf[..n].copy_from_slice(&rest[..n]);
+        self.position += n;
+        Ok(n)
+    }
+
+    #[inline(always)]
+    fn buf(&self) -> Option<&[u8]> {
+        Some(self.remaining())
+    }
+
+    #[inline(always)]
+    fn advance(&mut self, n: usize) {
+        self.position += n;
+    }
+}
@@ -0,0 +1,674 @@
+//! Runtime descriptors of a type's wire layout.
+//!
+//! [`layout_of::<T>()`](layout_of) returns a [`Layout`] tree describing how `T` is encoded —
+//! the codec used for each part, struct field and enum variant names, and the minimum and
+//! maximum encoded size — without encoding a value. This lets documentation, FFI bindings,
+//! and format audits be generated from the Rust types themselves.
+//!
+//! Implement [`DescribeLayout`] with `#[derive(DescribeLayout)]` alongside `Encode`/`Decode`:
+//!
+//! ```rust
+//! use lencode::prelude::*;
+//!
+//! #[derive(Encode, Decode, DescribeLayout)]
+//! struct Header {
+//!     version: u8,
+//!     flags: Option<u16>,
+//! }
+//!
+//! let layout = layout_of::<Header>();
+//! assert_eq!(layout.min_size, 2);
+//! assert_eq!(layout.max_size, Some(5));
+//! let LayoutKind::Struct(fields) = &layout.kind else { unreachable!() };
+//! assert_eq!(fields[1].name, "flags");
+//! ```
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+
+use crate::prelude::*;
+
+/// How a value (or part of a value) is represented on the wire.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayoutKind {
+    /// Zero bytes.
+    Unit,
+    /// A single `0`/`1` byte.
+    Bool,
+    /// Fixed‑width little‑endian bytes (`u8`, `i8`, floats, …).
+    Raw,
+    /// Lencode unsigned varint.
+    Varint,
+    /// ZigZag‑mapped Lencode varint.
+    ZigzagVarint,
+    /// Flagged byte string: `varint((len << 1) | compressed) + payload`.
+    Bytes,
+    /// Flagged UTF‑8 string, same framing as [`LayoutKind::Bytes`].
+    Str,
+    /// Presence flag followed by the value when present.
+    Option(Box<Layout>),
+    /// Ok flag followed by the `Ok` or `Err` value.
+    Result(Box<Layout>, Box<Layout>),
+    /// Element count followed by each element.
+    Sequence(Box<Layout>),
+    /// Entry count followed by each key and value.
+    Map(Box<Layout>, Box<Layout>),
+    /// Exactly `len` elements with no length prefix.
+    Array(Box<Layout>, usize),
+    /// Each element in order with no prefix.
+    Tuple(Vec<Layout>),
+    /// Each field in declaration order with no prefix.
+    Struct(Vec<Field>),
+    /// Varint discriminant followed by the selected variant's fields.
+    Enum(Vec<Variant>),
+    /// A type‑specific layout not described further.
+    Opaque,
+}
+
+/// Describes the wire layout of a type. See [`layout_of`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// The Rust type name, as reported by [`core::any::type_name`], or the name given with
+    /// `#[lencode(rename = "...")]` on a derived type.
+    pub type_name: &'static str,
+    /// The codec used.
+    pub kind: LayoutKind,
+    /// Minimum encoded size in bytes.
+    pub min_size: usize,
+    /// Maximum encoded size in bytes, or `None` if unbounded.
+    pub max_size: Option<usize>,
+}
+
+/// A named struct field (or positional field, named `"0"`, `"1"`, …) in a [`Layout`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    /// The field name.
+    pub name: &'static str,
+    /// The field's layout.
+    pub layout: Layout,
+}
+
+/// An enum variant in a [`Layout`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variant {
+    /// The variant name.
+    pub name: &'static str,
+    /// The discriminant written on the wire for this variant.
+    pub discriminant: u64,
+    /// The variant's fields, in encoding order.
+    pub fields: Vec<Field>,
+}
+
+impl Field {
+    /// Creates a new [`Field`].
+    #[inline(always)]
+    pub const fn new(name: &'static str, layout: Layout) -> Self {
+        Self { name, layout }
+    }
+}
+
+impl Variant {
+    /// Creates a new [`Variant`].
+    #[inline(always)]
+    pub fn new(
+        name: &'static str,
+        discriminant: u64,
+        fields: impl IntoIterator<Item = Field>,
+    ) -> Self {
+        Self {
+            name,
+            discriminant,
+            fields: fields.into_iter().collect(),
+        }
+    }
+}
+
+/// Sums sizes, propagating `None` (unbounded) and overflow as unbounded.
+#[inline(always)]
+fn sum_max(sizes: impl IntoIterator<Item = Option<usize>>) -> Option<usize> {
+    sizes
+        .into_iter()
+        .try_fold(0usize, |acc, size| acc.checked_add(size?))
+}
+
+/// Returns the encoded size of `val` as a Lencode varint.
+#[inline(always)]
+pub(crate) const fn varint_size(val: u64) -> usize {
+    if val <= 127 {
+        return 1;
+    }
+    1 + (8 - (val.leading_zeros() as usize / 8))
+}
+
+impl Layout {
+    /// Creates a leaf layout with the given size bounds.
+    #[inline(always)]
+    pub const fn leaf(
+        type_name: &'static str,
+        kind: LayoutKind,
+        min_size: usize,
+        max_size: Option<usize>,
+    ) -> Self {
+        Self {
+            type_name,
+            kind,
+            min_size,
+            max_size,
+        }
+    }
+
+    /// Creates a struct layout whose size bounds are the sums of its fields'.
+    pub fn structure(type_name: &'static str, fields: impl IntoIterator<Item = Field>) -> Self {
+        let fields: Vec<Field> = fields.into_iter().collect();
+        let min_size = fields.iter().map(|f| f.layout.min_size).sum();
+        let max_size = sum_max(fields.iter().map(|f| f.layout.max_size));
+        Self::leaf(type_name, LayoutKind::Struct(fields), min_size, max_size)
+    }
+
+    /// Creates a tuple layout whose size bounds are the sums of its elements'.
+    pub fn tuple(type_name: &'static str, elements: impl IntoIterator<Item = Layout>) -> Self {
+        let elements: Vec<Layout> = elements.into_iter().collect();
+        let min_size = elements.iter().map(|l| l.min_size).sum();
+        let max_size = sum_max(elements.iter().map(|l| l.max_size));
+        Self::leaf(type_name, LayoutKind::Tuple(elements), min_size, max_size)
+    }
+
+    /// Creates an enum layout; each variant costs its discriminant varint plus its fields.
+    pub fn enumeration(
+        type_name: &'static str,
+        variants: impl IntoIterator<Item = Variant>,
+    ) -> Self {
+        let variants: Vec<Variant> = variants.into_iter().collect();
+        let variant_size = |v: &Variant| {
+            let disc = varint_size(v.discriminant);
+            let min = disc + v.fields.iter().map(|f| f.layout.min_size).sum::<usize>();
+            let max = sum_max(
+                core::iter::once(Some(disc)).chain(v.fields.iter().map(|f| f.layout.max_size)),
+            );
+            (min, max)
+        };
+        let min_size = variants
+            .iter()
+            .map(|v| variant_size(v).0)
+            .min()
+            .unwrap_or(0);
+        let max_size = variants
+            .iter()
+            .map(|v| variant_size(v).1)
+            .try_fold(0usize, |acc, max| max.map(|m| acc.max(m)));
+        Self::leaf(type_name, LayoutKind::Enum(variants), min_size, max_size)
+    }
+}
+
+/// 64‑bit FNV‑1a, the hash behind [`Layout::fingerprint`].
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    #[inline(always)]
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    #[inline(always)]
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    #[inline(always)]
+    fn str(&mut self, value: &str) {
+        self.u64(value.len() as u64);
+        self.bytes(value.as_bytes());
+    }
+}
+
+impl Layout {
+    /// Returns a hash of the wire layout, for peers to check they agree on a schema before
+    /// exchanging data (see [`schema_handshake`]).
+    ///
+    /// The hash covers the codec of every part, struct field and enum variant names,
+    /// discriminants, array lengths and encoded size bounds. It leaves out `type_name`,
+    /// which the compiler does not keep stable, so it is the same across compilations,
+    /// targets and compiler versions. Renaming a field or variant changes it unless the old
+    /// name is kept with `#[lencode(rename = "...")]`.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        self.hash_into(&mut hasher);
+        hasher.0
+    }
+
+    fn hash_into(&self, hasher: &mut Fnv1a) {
+        hasher.u64(self.min_size as u64);
+        hasher.u64(self.max_size.map_or(u64::MAX, |max| max as u64));
+        let fields = |hasher: &mut Fnv1a, fields: &[Field]| {
+            hasher.u64(fields.len() as u64);
+            for field in fields {
+                hasher.str(field.name);
+                field.layout.hash_into(hasher);
+            }
+        };
+        match &self.kind {
+            LayoutKind::Unit => hasher.bytes(&[0]),
+            LayoutKind::Bool => hasher.bytes(&[1]),
+            LayoutKind::Raw => hasher.bytes(&[2]),
+            LayoutKind::Varint => hasher.bytes(&[3]),
+            LayoutKind::ZigzagVarint => hasher.bytes(&[4]),
+            LayoutKind::Bytes => hasher.bytes(&[5]),
+            LayoutKind::Str => hasher.bytes(&[6]),
+            LayoutKind::Option(inner) => {
+                hasher.bytes(&[7]);
+                inner.hash_into(hasher);
+            }
+            LayoutKind::Result(ok, err) => {
+                hasher.bytes(&[8]);
+                ok.hash_into(hasher);
+                err.hash_into(hasher);
+            }
+            LayoutKind::Sequence(element) => {
+                hasher.bytes(&[9]);
+                element.hash_into(hasher);
+            }
+            LayoutKind::Map(key, value) => {
+                hasher.bytes(&[10]);
+                key.hash_into(hasher);
+                value.hash_into(hasher);
+            }
+            LayoutKind::Array(element, len) => {
+                hasher.bytes(&[11]);
+                hasher.u64(*len as u64);
+                element.hash_into(hasher);
+            }
+            LayoutKind::Tuple(elements) => {
+                hasher.bytes(&[12]);
+                hasher.u64(elements.len() as u64);
+                for element in elements {
+                    element.hash_into(hasher);
+                }
+            }
+            LayoutKind::Struct(struct_fields) => {
+                hasher.bytes(&[13]);
+                fields(hasher, struct_fields);
+            }
+            LayoutKind::Enum(variants) => {
+                hasher.bytes(&[14]);
+                hasher.u64(variants.len() as u64);
+                for variant in variants {
+                    hasher.str(variant.name);
+                    hasher.u64(variant.discriminant);
+                    fields(hasher, &variant.fields);
+                }
+            }
+            LayoutKind::Opaque => hasher.bytes(&[15]),
+        }
+    }
+}
+
+/// Returns the [`Layout::fingerprint`] of `T`.
+#[inline(always)]
+pub fn fingerprint_of<T: DescribeLayout + ?Sized>() -> u64 {
+    T::layout().fingerprint()
+}
+
+/// Exchanges schema fingerprints with a peer before streaming: writes `fingerprint` as 8
+/// little‑endian bytes, flushes, then reads the peer's. Both sides call this with the
+/// fingerprint of the type they stream (see [`fingerprint_of`]), and fail fast with
+/// [`Error::SchemaMismatch`] instead of misreading data mid‑stream.
+pub fn schema_handshake(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    fingerprint: u64,
+) -> Result<()> {
+    write_whole(writer, &fingerprint.to_le_bytes())?;
+    writer.flush()?;
+    let mut peer = [0u8; 8];
+    read_exact(reader, &mut peer)?;
+    let found = u64::from_le_bytes(peer);
+    if found != fingerprint {
+        return Err(Error::SchemaMismatch {
+            expected: fingerprint,
+            found,
+        });
+    }
+    Ok(())
+}
+
+/// Trait for types that can describe their wire layout. See [`layout_of`].
+///
+/// Derive it with `#[derive(DescribeLayout)]`; the generated descriptor matches the layout
+/// produced by `#[derive(Encode)]`.
+pub trait DescribeLayout {
+    /// Returns the layout of `Self`.
+    fn layout() -> Layout;
+}
+
+/// Returns the wire layout descriptor of `T`.
+#[inline(always)]
+pub fn layout_of<T: DescribeLayout + ?Sized>() -> Layout {
+    T::layout()
+}
+
+macro_rules! impl_describe_layout_leaf {
+    ($($t:ty => $kind:expr, $min:expr, $max:expr);* $(;)?) => {
+        $(
+            impl DescribeLayout for $t {
+                #[inline(always)]
+                fn layout() -> Layout {
+                    Layout::leaf(core::any::type_name::<$t>(), $kind, $min, $max)
+                }
+            }
+        )*
+    };
+}
+
+impl_describe_layout_leaf! {
+    () => LayoutKind::Unit, 0, Some(0);
+    bool => LayoutKind::Bool, 1, Some(1);
+    u8 => LayoutKind::Raw, 1, Some(1);
+    i8 => LayoutKind::Raw, 1, Some(1);
+    f32 => LayoutKind::Raw, 4, Some(4);
+    f64 => LayoutKind::Raw, 8, Some(8);
+    u16 => LayoutKind::Varint, 1, Some(3);
+    u32 => LayoutKind::Varint, 1, Some(5);
+    u64 => LayoutKind::Varint, 1, Some(9);
+    u128 => LayoutKind::Varint, 1, Some(17);
+    usize => LayoutKind::Varint, 1, Some(9);
+    U256 => LayoutKind::Varint, 1, Some(33);
+    i16 => LayoutKind::ZigzagVarint, 1, Some(3);
+    i32 => LayoutKind::ZigzagVarint, 1, Some(5);
+    i64 => LayoutKind::ZigzagVarint, 1, Some(9);
+    i128 => LayoutKind::ZigzagVarint, 1, Some(17);
+    isize => LayoutKind::ZigzagVarint, 1, Some(9);
+    Timestamp => LayoutKind::ZigzagVarint, 1, Some(17);
+    String => LayoutKind::Str, 1, None;
+    ByteString => LayoutKind::Bytes, 1, None;
+    str => LayoutKind::Str, 1, None;
+}
+
+impl<T> DescribeLayout for OptVec<T> {
+    #[inline(always)]
+    fn layout() -> Layout {
+        Layout::leaf(core::any::type_name::<Self>(), LayoutKind::Opaque, 1, None)
+    }
+}
+
+impl<T> DescribeLayout for Rle<T> {
+    #[inline(always)]
+    fn layout() -> Layout {
+        Layout::leaf(core::any::type_name::<Self>(), LayoutKind::Opaque, 1, None)
+    }
+}
+
+impl<const K: u32> DescribeLayout for RiceVec<K> {
+    #[inline(always)]
+    fn layout() -> Layout {
+        Layout::leaf(core::any::type_name::<Self>(), LayoutKind::Opaque, 2, None)
+    }
+}
+
+impl DescribeLayout for GammaVec {
+    #[inline(always)]
+    fn layout() -> Layout {
+        Layout::leaf(core::any::type_name::<Self>(), LayoutKind::Opaque, 2, None)
+    }
+}
+
+impl<const N: usize> DescribeLayout for RawArray<N> {
+    #[inline(always)]
+    fn layout() -> Layout {
+        Layout::leaf(
+            core::any::type_name::<Self>(),
+            LayoutKind::Array(Box::new(u8::layout()), N),
+            N,
+            Some(N),
+        )
+    }
+}
+
+impl<T: ?Sized> DescribeLayout for core::marker::PhantomData<T> {
+    #[inline(always)]
+    fn layout() -> Layout {
+        Layout::leaf(core::any::type_name::<Self>(), LayoutKind::Unit, 0, Some(0))
+    }
+}
+
+impl<T: DescribeLayout> DescribeLayout for Option<T> {
+    fn layout() -> Layout {
+        let inner = T::layout();
+        let max_size = inner.max_size.and_then(|m| m.checked_add(1));
+        Layout::leaf(
+            core::any::type_name::<Self>(),
+            LayoutKind::Option(Box::new(inner)),
+            1,
+            max_size,
+        )
+    }
+}
+
+impl<T: DescribeLayout, E: DescribeLayout> DescribeLayout for core::result::Result<T, E> {
+    fn layout() -> Layout {
+        let ok = T::layout();
+        let err = E::layout();
+        let min_size = 1 + ok.min_size.min(err.min_size);
+        let max_size = ok
+            .max_size
+            .zip(err.max_size)
+            .and_then(|(a, b)| a.max(b).checked_add(1));
+        Layout::leaf(
+            core::any::type_name::<Self>(),
+            LayoutKind::Result(Box::new(ok), Box::new(err)),
+            min_size,
+            max_size,
+        )
+    }
+}
+
+impl<T: DescribeLayout + 'static, const N: usize> DescribeLayout for [T; N] {
+    fn layout() -> Layout {
+        let inner = T::layout();
+        let min_size = inner.min_size.saturating_mul(N);
+        let max_size = inner.max_size.and_then(|m| m.checked_mul(N));
+        Layout::leaf(
+            core::any::type_name::<Self>(),
+            LayoutKind::Array(Box::new(inner), N),
+            min_size,
+            max_size,
+        )
+    }
+}
+
+/// Layout of a length‑prefixed sequence of `T`, using the byte‑string layout for `u8`.
+fn sequence_layout<T: DescribeLayout + 'static>(type_name: &'static str) -> Layout {
+    if core::any::TypeId::of::<T>() == core::any::TypeId::of::<u8>() {
+        return Layout::leaf(type_name, LayoutKind::Bytes, 1, None);
+    }
+    Layout::leaf(
+        type_name,
+        LayoutKind::Sequence(Box::new(T::layout())),
+        1,
+        None,
+    )
+}
+
+impl<T: DescribeLayout + 'static> DescribeLayout for Vec<T> {
+    #[inline(always)]
+    fn layout() -> Layout {
+        sequence_layout::<T>(core::any::type_name::<Self>())
+    }
+}
+
+impl<T: DescribeLayout + 'static> DescribeLayout for [T] {
+    #[inline(always)]
+    fn layout() -> Layout {
+        sequence_layout::<T>(core::any::type_name::<Self>())
+    }
+}
+
+impl<T: DescribeLayout + 'static> DescribeLayout for collections::VecDeque<T> {
+    #[inline(always)]
+    fn layout() -> Layout {
+        sequence_layout::<T>(core::any::type_name::<Self>())
+    }
+}
+
+macro_rules! impl_describe_layout_sequence {
+    ($($t:ident),*) => {
+        $(
+            impl<T: DescribeLayout> DescribeLayout for $t<T> {
+                #[inline(always)]
+                fn layout() -> Layout {
+                    Layout::leaf(
+                        core::any::type_name::<Self>(),
+                        LayoutKind::Sequence(Box::new(T::layout())),
+                        1,
+                        None,
+                    )
+                }
+            }
+        )*
+    };
+}
+
+use collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList};
+impl_describe_layout_sequence!(BTreeSet, LinkedList, BinaryHeap, PlainVec);
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+impl_describe_layout_sequence!(HashSet);
+
+macro_rules! impl_describe_layout_map {
+    ($($t:ident),*) => {
+        $(
+            impl<K: DescribeLayout, V: DescribeLayout> DescribeLayout for $t<K, V> {
+                #[inline(always)]
+                fn layout() -> Layout {
+                    Layout::leaf(
+                        core::any::type_name::<Self>(),
+                        LayoutKind::Map(Box::new(K::layout()), Box::new(V::layout())),
+                        1,
+                        None,
+                    )
+                }
+            }
+        )*
+    };
+}
+
+impl_describe_layout_map!(BTreeMap);
+#[cfg(feature = "std")]
+impl_describe_layout_map!(HashMap);
+
+macro_rules! impl_describe_layout_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: DescribeLayout),+> DescribeLayout for ($($name,)+) {
+            #[inline(always)]
+            fn layout() -> Layout {
+                Layout::tuple(core::any::type_name::<Self>(), [$($name::layout()),+])
+            }
+        }
+    };
+}
+
+impl_describe_layout_tuple!(A);
+impl_describe_layout_tuple!(A, B);
+impl_describe_layout_tuple!(A, B, C);
+impl_describe_layout_tuple!(A, B, C, D);
+impl_describe_layout_tuple!(A, B, C, D, E);
+impl_describe_layout_tuple!(A, B, C, D, E, F);
+impl_describe_layout_tuple!(A, B, C, D, E, F, G);
+impl_describe_layout_tuple!(A, B, C, D, E, F, G, H);
+impl_describe_layout_tuple!(A, B, C, D, E, F, G, H, I);
+impl_describe_layout_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_describe_layout_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+
+#[test]
+fn test_layout_primitives_match_encoding() {
+    fn max_encoded_len(value: impl Encode) -> usize {
+        let mut buf = Vec::new();
+        value.encode(&mut buf).unwrap()
+    }
+    assert_eq!(layout_of::<u32>().max_size, Some(max_encoded_len(u32::MAX)));
+    assert_eq!(layout_of::<i64>().max_size, Some(max_encoded_len(i64::MIN)));
+    assert_eq!(
+        layout_of::<u128>().max_size,
+        Some(max_encoded_len(u128::MAX))
+    );
+    assert_eq!(layout_of::<u16>().min_size, max_encoded_len(0u16));
+    assert_eq!(
+        layout_of::<String>().min_size,
+        max_encoded_len(String::new())
+    );
+}
+
+#[test]
+fn test_layout_composites() {
+    let layout = layout_of::<(u8, Option<u16>, [bool; 4])>();
+    assert_eq!(layout.min_size, 1 + 1 + 4);
+    assert_eq!(layout.max_size, Some(1 + 4 + 4));
+
+    let bytes = layout_of::<Vec<u8>>();
+    assert_eq!(bytes.kind, LayoutKind::Bytes);
+    assert_eq!(bytes.max_size, None);
+
+    let seq = layout_of::<Vec<u32>>();
+    assert_eq!(seq.kind, LayoutKind::Sequence(Box::new(layout_of::<u32>())));
+
+    let res = layout_of::<core::result::Result<u8, String>>();
+    assert_eq!(res.min_size, 2);
+    assert_eq!(res.max_size, None);
+}
+
+#[test]
+fn test_layout_enumeration_sizes() {
+    let variants = (0..200).map(|i| Variant::new("V", i, [Field::new("0", layout_of::<u8>())]));
+    let layout = Layout::enumeration("E", variants);
+    // Discriminants 0..=127 take one byte; 128..200 take two.
+    assert_eq!(layout.min_size, 2);
+    assert_eq!(layout.max_size, Some(3));
+}
+
+#[test]
+fn test_layout_fingerprint_and_handshake() {
+    let base = Layout::structure(
+        "A",
+        [
+            Field::new("id", layout_of::<u64>()),
+            Field::new("tags", layout_of::<Vec<String>>()),
+        ],
+    );
+    // Type names do not matter; field names, codecs and order do.
+    let renamed_type = Layout {
+        type_name: "B",
+        ..base.clone()
+    };
+    assert_eq!(base.fingerprint(), renamed_type.fingerprint());
+    let retyped = Layout::structure(
+        "A",
+        [
+            Field::new("id", layout_of::<i64>()),
+            Field::new("tags", layout_of::<Vec<String>>()),
+        ],
+    );
+    assert_ne!(base.fingerprint(), retyped.fingerprint());
+    assert_ne!(fingerprint_of::<u8>(), fingerprint_of::<f32>());
+    assert_ne!(fingerprint_of::<[u8; 2]>(), fingerprint_of::<[u8; 3]>());
+    // Pinned so that an accidental change to the hash shows up here.
+    assert_eq!(fingerprint_of::<(u32, bool)>(), 0x95f7_4881_11f1_f815);
+
+    let fingerprint = base.fingerprint();
+    let mut sent = Vec::new();
+    let peer = fingerprint.to_le_bytes();
+    schema_handshake(&mut Cursor::new(&peer[..]), &mut sent, fingerprint).unwrap();
+    assert_eq!(sent, peer);
+
+    let other = retyped.fingerprint().to_le_bytes();
+    assert!(matches!(
+        schema_handshake(&mut Cursor::new(&other[..]), &mut Vec::new(), fingerprint),
+        Err(Error::SchemaMismatch { expected, found }) if expected == fingerprint && found != expected
+    ));
+}
@@ -0,0 +1,611 @@
+//! Pluggable length‑prefix strategies for collections.
+//!
+//! By default every collection writes its element count as a Lencode varint (see
+//! [`Encode::encode_len`]). Legacy formats often use a fixed‑width count instead; a
+//! [`LenStrategy`] describes how a count is written, and [`EncodeWithLen`]/[`DecodeWithLen`]
+//! encode a collection using a chosen strategy.
+//!
+//! With the derive macros, pick a strategy per field:
+//!
+//! ```rust
+//! use lencode::prelude::*;
+//!
+//! #[derive(Encode, Decode, Debug, PartialEq)]
+//! struct LegacyRecord {
+//!     #[lencode(len = "u16_fixed")]
+//!     name: String,
+//!     #[lencode(len = "u8_fixed")]
+//!     tags: Vec<u32>,
+//! }
+//!
+//! let record = LegacyRecord { name: "abc".into(), tags: vec![1, 2] };
+//! let mut buf = Vec::new();
+//! encode(&record, &mut buf).unwrap();
+//! assert_eq!(&buf[..5], &[3, 0, b'a', b'b', b'c']);
+//! assert_eq!(decode::<LegacyRecord>(&mut Cursor::new(&buf)).unwrap(), record);
+//! ```
+//!
+//! Built‑in strategy names are `varint`, `u8_fixed`, `u16_fixed`, `u32_fixed`, `u64_fixed`
+//! and `short_vec` (Solana's compact‑u16); any other value is taken as the path of a type implementing [`LenStrategy`].
+//! Fixed‑width counts are little‑endian. With a non‑default strategy, `String` and `Vec<u8>`
+//! are written as the count followed by the raw bytes (never compressed).
+//!
+//! ## Size‑prefixed values
+//!
+//! [`LengthPrefixed<T>`] prefixes a value with the byte size of its encoding,
+//! `varint(size) + T`, so a decoder can step over a section it does not need (or does not
+//! understand) without parsing it, and a reader can hand the section's bytes to another
+//! process. On a derived field, `#[lencode(length_prefixed)]` does the same without changing
+//! the field's type:
+//!
+//! ```rust
+//! use lencode::prelude::*;
+//!
+//! #[derive(Encode, Decode, Debug, PartialEq)]
+//! struct Envelope {
+//!     kind: u8,
+//!     #[lencode(length_prefixed)]
+//!     body: Vec<u64>,
+//! }
+//!
+//! let envelope = Envelope { kind: 1, body: vec![300, 7] };
+//! let mut buf = Vec::new();
+//! encode(&envelope, &mut buf).unwrap();
+//! assert_eq!(&buf[..3], &[1, 5, 2]); // kind, body size, body element count
+//!
+//! let mut reader = Cursor::new(&buf[1..]);
+//! LengthPrefixed::<Vec<u64>>::skip_ext(&mut reader, None).unwrap();
+//! assert_eq!(reader.position(), buf.len() - 1);
+//! ```
+//!
+//! Sections are skipped without touching the context, so do not skip size‑prefixed values in
+//! a deduplicated stream.
+use core::ops::{Deref, DerefMut};
+
+use crate::bytes;
+use crate::io::{read_exact, read_vec, skip_bytes};
+use crate::prelude::*;
+
+/// Describes how a collection's element count is written and read.
+pub trait LenStrategy {
+    /// Writes `len` to `writer`, returning the number of bytes written.
+    ///
+    /// Returns [`Error::IncorrectLength`] if `len` cannot be represented.
+    fn encode_len(len: usize, writer: &mut impl Write) -> Result<usize>;
+
+    /// Reads a count written by [`LenStrategy::encode_len`].
+    fn decode_len(reader: &mut impl Read) -> Result<usize>;
+}
+
+/// The default Lencode varint count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VarintLen;
+
+impl LenStrategy for VarintLen {
+    #[inline(always)]
+    fn encode_len(len: usize, writer: &mut impl Write) -> Result<usize> {
+        Lencode::encode_varint_u64(len as u64, writer)
+    }
+
+    #[inline(always)]
+    fn decode_len(reader: &mut impl Read) -> Result<usize> {
+        checked_usize(Lencode::decode_varint_u64(reader)?)
+    }
+}
+
+macro_rules! impl_fixed_len_strategy {
+    ($($name:ident => $t:ty, $doc:literal);* $(;)?) => {
+        $(
+            #[doc = $doc]
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+            pub struct $name;
+
+            impl LenStrategy for $name {
+                #[inline(always)]
+                fn encode_len(len: usize, writer: &mut impl Write) -> Result<usize> {
+                    let len = <$t>::try_from(len).map_err(|_| Error::IncorrectLength)?;
+                    write_all(writer, &len.to_le_bytes())
+                }
+
+                #[inline(always)]
+                fn decode_len(reader: &mut impl Read) -> Result<usize> {
+                    let mut buf = [0u8; core::mem::size_of::<$t>()];
+                    read_exact(reader, &mut buf)?;
+                    usize::try_from(<$t>::from_le_bytes(buf)).map_err(|_| Error::IncorrectLength)
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_len_strategy! {
+    U8FixedLen => u8, "A single‑byte count (`u8_fixed`).";
+    U16FixedLen => u16, "A little‑endian `u16` count (`u16_fixed`).";
+    U32FixedLen => u32, "A little‑endian `u32` count (`u32_fixed`).";
+    U64FixedLen => u64, "A little‑endian `u64` count (`u64_fixed`).";
+}
+
+/// Solana's `short_vec` compact‑u16 count (`short_vec`).
+///
+/// Seven bits per byte, least significant group first, at most three bytes. Counts above
+/// `u16::MAX` are rejected, as are non‑canonical encodings (a trailing zero group), matching
+/// the on‑chain decoder.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShortVecLen;
+
+impl LenStrategy for ShortVecLen {
+    #[inline(always)]
+    fn encode_len(len: usize, writer: &mut impl Write) -> Result<usize> {
+        let mut rem = u16::try_from(len).map_err(|_| Error::IncorrectLength)?;
+        let mut buf = [0u8; 3];
+        let mut n = 0;
+        loop {
+            let byte = (rem & 0x7f) as u8;
+            rem >>= 7;
+            if rem == 0 {
+                buf[n] = byte;
+                n += 1;
+                break;
+            }
+            buf[n] = byte | 0x80;
+            n += 1;
+        }
+        write_all(writer, &buf[..n])
+    }
+
+    #[inline(always)]
+    fn decode_len(reader: &mut impl Read) -> Result<usize> {
+        let mut len = 0usize;
+        for i in 0..3 {
+            let mut byte = [0u8; 1];
+            read_exact(reader, &mut byte)?;
+            let byte = byte[0];
+            // A zero continuation group is non‑canonical.
+            if i > 0 && byte == 0 {
+                return Err(Error::InvalidData);
+            }
+            len |= ((byte & 0x7f) as usize) << (i * 7);
+            if byte & 0x80 == 0 {
+                return if len > u16::MAX as usize {
+                    Err(Error::InvalidData)
+                } else {
+                    Ok(len)
+                };
+            }
+            // The third byte carries the top two bits and must terminate.
+            if i == 2 {
+                return Err(Error::InvalidData);
+            }
+        }
+        unreachable!()
+    }
+}
+
+/// Collections that can be encoded with an arbitrary [`LenStrategy`].
+pub trait EncodeWithLen: Encode {
+    /// Encodes `self`, writing its element count with `L`.
+    fn encode_with_len<L: LenStrategy>(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize>;
+}
+
+/// Collections that can be decoded with an arbitrary [`LenStrategy`].
+pub trait DecodeWithLen: Decode + Sized {
+    /// Decodes `Self`, reading its element count with `L`.
+    fn decode_with_len<L: LenStrategy>(
+        reader: &mut impl Read,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self>;
+}
+
+impl EncodeWithLen for String {
+    #[inline(always)]
+    fn encode_with_len<L: LenStrategy>(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = L::encode_len(self.len(), writer)?;
+        total_written += write_all(writer, self.as_bytes())?;
+        Ok(total_written)
+    }
+}
+
+impl DecodeWithLen for String {
+    #[inline(always)]
+    fn decode_with_len<L: LenStrategy>(
+        reader: &mut impl Read,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        let len = L::decode_len(reader)?;
+        let buf = read_vec(reader, len)?;
+        crate::string_from_utf8(buf, ctx.as_deref())
+    }
+}
+
+macro_rules! impl_with_len_sequence {
+    ($($t:ident => $push:ident),* $(,)?) => {
+        $(
+            impl<T: Encode + 'static> EncodeWithLen for $t<T> {
+                #[inline(always)]
+                fn encode_with_len<L: LenStrategy>(
+                    &self,
+                    writer: &mut impl Write,
+                    mut ctx: Option<&mut EncoderContext>,
+                ) -> Result<usize> {
+                    let mut total_written = L::encode_len(self.len(), writer)?;
+                    for item in self {
+                        total_written += item.encode_ext(writer, ctx.as_deref_mut())?;
+                    }
+                    Ok(total_written)
+                }
+            }
+
+            impl<T: Decode + 'static> DecodeWithLen for $t<T> {
+                #[inline(always)]
+                fn decode_with_len<L: LenStrategy>(
+                    reader: &mut impl Read,
+                    ctx: Option<&mut DecoderContext>,
+                ) -> Result<Self> {
+                    DecoderContext::nested(ctx, |mut ctx| {
+                        let len = L::decode_len(reader)?;
+                        let mut out = $t::new();
+                        for _ in 0..len {
+                            out.$push(T::decode_ext(reader, ctx.as_deref_mut())?);
+                        }
+                        Ok(out)
+                    })
+                }
+            }
+        )*
+    };
+}
+
+use collections::{LinkedList, VecDeque};
+impl_with_len_sequence!(Vec => push, VecDeque => push_back, LinkedList => push_back);
+
+impl<T: Encode> EncodeWithLen for collections::BTreeSet<T> {
+    #[inline(always)]
+    fn encode_with_len<L: LenStrategy>(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = L::encode_len(self.len(), writer)?;
+        for item in self {
+            total_written += item.encode_ext(writer, ctx.as_deref_mut())?;
+        }
+        Ok(total_written)
+    }
+}
+
+impl<T: Decode + Ord> DecodeWithLen for collections::BTreeSet<T> {
+    #[inline(always)]
+    fn decode_with_len<L: LenStrategy>(
+        reader: &mut impl Read,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = L::decode_len(reader)?;
+            let mut out = collections::BTreeSet::new();
+            for _ in 0..len {
+                out.insert(T::decode_ext(reader, ctx.as_deref_mut())?);
+            }
+            Ok(out)
+        })
+    }
+}
+
+impl<K: Encode, V: Encode> EncodeWithLen for collections::BTreeMap<K, V> {
+    #[inline(always)]
+    fn encode_with_len<L: LenStrategy>(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = L::encode_len(self.len(), writer)?;
+        for (key, value) in self {
+            total_written += key.encode_ext(writer, ctx.as_deref_mut())?;
+            total_written += value.encode_ext(writer, ctx.as_deref_mut())?;
+        }
+        Ok(total_written)
+    }
+}
+
+impl<K: Decode + Ord, V: Decode> DecodeWithLen for collections::BTreeMap<K, V> {
+    #[inline(always)]
+    fn decode_with_len<L: LenStrategy>(
+        reader: &mut impl Read,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = L::decode_len(reader)?;
+            let reject = crate::duplicate_keys_rejected(ctx.as_deref());
+            let mut map = collections::BTreeMap::new();
+            for _ in 0..len {
+                let key = K::decode_ext(reader, ctx.as_deref_mut())?;
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                if map.insert(key, value).is_some() && reject {
+                    return Err(Error::DuplicateKey);
+                }
+            }
+            Ok(map)
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Encode, V: Encode> EncodeWithLen for std::collections::HashMap<K, V> {
+    #[inline(always)]
+    fn encode_with_len<L: LenStrategy>(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = L::encode_len(self.len(), writer)?;
+        for (key, value) in self {
+            total_written += key.encode_ext(writer, ctx.as_deref_mut())?;
+            total_written += value.encode_ext(writer, ctx.as_deref_mut())?;
+        }
+        Ok(total_written)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Decode + Eq + std::hash::Hash, V: Decode> DecodeWithLen
+    for std::collections::HashMap<K, V>
+{
+    #[inline(always)]
+    fn decode_with_len<L: LenStrategy>(
+        reader: &mut impl Read,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = L::decode_len(reader)?;
+            let reject = crate::duplicate_keys_rejected(ctx.as_deref());
+            let mut map = std::collections::HashMap::new();
+            for _ in 0..len {
+                let key = K::decode_ext(reader, ctx.as_deref_mut())?;
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                if map.insert(key, value).is_some() && reject {
+                    return Err(Error::DuplicateKey);
+                }
+            }
+            Ok(map)
+        })
+    }
+}
+
+/// A value prefixed with the byte size of its encoding. See the [module docs](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LengthPrefixed<T>(pub T);
+
+impl<T> LengthPrefixed<T> {
+    /// Consumes the wrapper and returns the inner value.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for LengthPrefixed<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for LengthPrefixed<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for LengthPrefixed<T> {
+    #[inline(always)]
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Encode> Encode for LengthPrefixed<T> {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        encode_length_prefixed(writer, ctx, |body, ctx| self.0.encode_ext(body, ctx))
+    }
+}
+
+impl<T: Decode> Decode for LengthPrefixed<T> {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        decode_length_prefixed(reader, ctx, |body, ctx| T::decode_ext(body, ctx)).map(Self)
+    }
+
+    #[inline(always)]
+    fn skip_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<()> {
+        skip_length_prefixed(reader)
+    }
+}
+
+/// Writes `varint(size)` followed by the bytes `encode` produces. Used by
+/// [`LengthPrefixed`] and `#[lencode(length_prefixed)]` fields.
+pub fn encode_length_prefixed(
+    writer: &mut impl Write,
+    ctx: Option<&mut EncoderContext>,
+    encode: impl FnOnce(&mut Vec<u8>, Option<&mut EncoderContext>) -> Result<usize>,
+) -> Result<usize> {
+    let mut body = Vec::new();
+    encode(&mut body, ctx)?;
+    ensure_capacity(writer, bytes::varint_len_usize(body.len()) + body.len())?;
+    let mut total = Lencode::encode_varint_u64(body.len() as u64, writer)?;
+    total += write_all(writer, &body)?;
+    Ok(total)
+}
+
+/// Reads a section written by [`encode_length_prefixed`] and runs `decode` over exactly its
+/// bytes, failing with [`Error::LengthMismatch`] if `decode` leaves any unread.
+pub fn decode_length_prefixed<V>(
+    reader: &mut impl Read,
+    ctx: Option<&mut DecoderContext>,
+    decode: impl FnOnce(&mut Cursor<&[u8]>, Option<&mut DecoderContext>) -> Result<V>,
+) -> Result<V> {
+    let len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    let decode_body = |body: &[u8]| {
+        let mut body_reader = Cursor::new(body);
+        let value = decode(&mut body_reader, ctx)?;
+        if body_reader.position() != body.len() {
+            return Err(Error::LengthMismatch {
+                expected: body.len(),
+                actual: body_reader.position(),
+            });
+        }
+        Ok(value)
+    };
+    if let Some(buf) = reader.buf() {
+        if buf.len() < len {
+            return Err(Error::ReaderOutOfData);
+        }
+        let value = decode_body(&buf[..len])?;
+        reader.advance(len);
+        return Ok(value);
+    }
+    decode_body(&read_vec(reader, len)?)
+}
+
+/// Steps over a section written by [`encode_length_prefixed`] without decoding it.
+#[inline(always)]
+pub fn skip_length_prefixed(reader: &mut impl Read) -> Result<()> {
+    let len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    skip_bytes(reader, len)
+}
+
+#[test]
+fn test_length_prefixed_roundtrip_and_skip() {
+    let names = vec![String::from("alpha"), String::from("beta")];
+    let mut buf = Vec::new();
+    let n = encode(&LengthPrefixed(names.clone()), &mut buf).unwrap();
+    assert_eq!(n, buf.len());
+    assert_eq!(buf[0] as usize, buf.len() - 1);
+    encode(&9u8, &mut buf).unwrap();
+
+    let mut reader = Cursor::new(&buf);
+    let decoded: LengthPrefixed<Vec<String>> = decode(&mut reader).unwrap();
+    assert_eq!(decoded.into_inner(), names);
+    assert_eq!(u8::decode(&mut reader).unwrap(), 9);
+
+    let mut reader = Cursor::new(&buf);
+    LengthPrefixed::<Vec<String>>::skip_ext(&mut reader, None).unwrap();
+    assert_eq!(u8::decode(&mut reader).unwrap(), 9);
+
+    // The section must hold exactly one value.
+    assert!(matches!(
+        decode::<LengthPrefixed<Vec<String>>>(&mut Cursor::new(&[3u8, 0, 0, 0][..])),
+        Err(Error::LengthMismatch {
+            expected: 3,
+            actual: 1
+        })
+    ));
+    assert!(matches!(
+        decode::<LengthPrefixed<u8>>(&mut Cursor::new(&[3u8, 0][..])),
+        Err(Error::ReaderOutOfData)
+    ));
+    let body = vec![0u8; 300];
+    let mut big = Vec::new();
+    encode(&LengthPrefixed(PlainVec(body.clone())), &mut big).unwrap();
+    assert_eq!(&big[..3], &[0x82, 0x2f, 0x01]);
+    #[cfg(feature = "std")]
+    {
+        // Readers without direct buffer access take the copying path.
+        let mut reader = std::io::Cursor::new(&big);
+        let decoded: LengthPrefixed<PlainVec<u8>> = decode(&mut reader).unwrap();
+        assert_eq!(decoded.0.0, body);
+    }
+}
+
+#[test]
+fn test_fixed_len_strategies() {
+    let value: Vec<u16> = vec![1, 300, 7];
+
+    let mut buf = Vec::new();
+    value.encode_with_len::<U8FixedLen>(&mut buf, None).unwrap();
+    assert_eq!(buf[0], 3);
+    let decoded = Vec::<u16>::decode_with_len::<U8FixedLen>(&mut Cursor::new(&buf), None).unwrap();
+    assert_eq!(decoded, value);
+
+    let mut buf = Vec::new();
+    value
+        .encode_with_len::<U32FixedLen>(&mut buf, None)
+        .unwrap();
+    assert_eq!(&buf[..4], &[3, 0, 0, 0]);
+    let decoded = Vec::<u16>::decode_with_len::<U32FixedLen>(&mut Cursor::new(&buf), None).unwrap();
+    assert_eq!(decoded, value);
+
+    // The varint strategy matches the default collection encoding.
+    let mut default_buf = Vec::new();
+    value.encode(&mut default_buf).unwrap();
+    let mut varint_buf = Vec::new();
+    value
+        .encode_with_len::<VarintLen>(&mut varint_buf, None)
+        .unwrap();
+    assert_eq!(default_buf, varint_buf);
+}
+
+#[test]
+fn test_fixed_len_overflow_and_truncation() {
+    let too_long = vec![0u8; 256];
+    let mut buf = Vec::new();
+    assert!(matches!(
+        too_long.encode_with_len::<U8FixedLen>(&mut buf, None),
+        Err(Error::IncorrectLength)
+    ));
+
+    let res = String::decode_with_len::<U16FixedLen>(&mut Cursor::new(&[5u8, 0, b'a'][..]), None);
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+}
+
+#[test]
+fn test_short_vec_len() {
+    for (len, expected) in [
+        (0usize, &[0x00u8][..]),
+        (0x7f, &[0x7f]),
+        (0x80, &[0x80, 0x01]),
+        (0x3fff, &[0xff, 0x7f]),
+        (0x4000, &[0x80, 0x80, 0x01]),
+        (0xffff, &[0xff, 0xff, 0x03]),
+    ] {
+        let mut buf = Vec::new();
+        assert_eq!(
+            ShortVecLen::encode_len(len, &mut buf).unwrap(),
+            expected.len()
+        );
+        assert_eq!(buf, expected);
+        assert_eq!(
+            ShortVecLen::decode_len(&mut Cursor::new(&buf)).unwrap(),
+            len
+        );
+    }
+    let mut buf = Vec::new();
+    assert!(matches!(
+        ShortVecLen::encode_len(0x10000, &mut buf),
+        Err(Error::IncorrectLength)
+    ));
+    for bad in [
+        &[0x80u8, 0x00][..],
+        &[0x80, 0x80, 0x04],
+        &[0x80, 0x80, 0x80],
+    ] {
+        assert!(matches!(
+            ShortVecLen::decode_len(&mut Cursor::new(bad)),
+            Err(Error::InvalidData)
+        ));
+    }
+}
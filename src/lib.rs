@@ -28,6 +28,11 @@
 //! This keeps headers minimal while improving size significantly for repetitive content, and
 //! is `no_std` compatible via `zstd-safe`.
 //!
+//! Compression comes from the default `zstd` feature. Building with `default-features = false`
+//! drops the C dependency for footprint‑sensitive targets (SGX, SBF, embedded): the flag bit
+//! is then always 0, so the output still decodes anywhere, but compressed input fails with
+//! [`DecompressionErrorKind::Unsupported`](crate::io::DecompressionErrorKind::Unsupported).
+//!
 //! ## Incremental diff encoding
 //!
 //! [`DiffEncoder`]/[`DiffDecoder`] provide stateful delta encoding for keyed byte blobs.
@@ -120,6 +125,8 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 #[cfg(not(feature = "std"))]
+use alloc::borrow;
+#[cfg(not(feature = "std"))]
 use alloc::collections;
 #[cfg(not(feature = "std"))]
 use alloc::string::String;
@@ -128,34 +135,143 @@ use alloc::vec;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 #[cfg(feature = "std")]
+use std::borrow;
+#[cfg(feature = "std")]
 use std::collections;
 
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+pub mod armor;
+#[cfg(feature = "async")]
+pub mod async_io;
+pub mod bits;
 mod bytes;
+pub mod captured;
+pub mod codec;
+pub mod columnar;
+#[cfg(feature = "solana-bincode")]
+pub mod compat;
 pub mod context;
 pub mod dedupe;
+pub mod delta;
 pub mod diff;
+pub mod entropy;
+#[cfg(any(feature = "enumset", feature = "enum-map"))]
+pub mod enum_collections;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixed;
+pub mod fuzz;
+#[cfg(feature = "petgraph")]
+pub mod graph;
+#[cfg(feature = "hooks")]
+pub mod hooks;
+pub mod id;
+#[cfg(feature = "indexmap")]
+pub mod index_map;
+pub mod intern;
 pub mod io;
+pub mod layout;
+pub mod length;
+pub mod metrics;
+pub mod mux;
 pub mod pack;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod portable;
+pub mod profile;
+pub mod progress;
+pub mod projection;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "quinn")]
+pub mod quic;
+pub mod recording;
+pub mod remote;
+pub mod rpc;
+pub mod snapshot;
+pub mod timestamp;
+#[cfg(any(feature = "postcard", feature = "cbor"))]
+pub mod transcode;
 pub mod tuples;
 pub mod u256;
 pub mod varint;
+#[cfg(any(feature = "web", feature = "actix"))]
+pub mod web;
+pub mod wrappers;
 
 #[cfg(feature = "solana")]
 pub mod solana;
+#[cfg(feature = "spl-token")]
+pub mod spl_token;
 
 /// Convenience re‑exports for common traits, modules and derive macros.
 pub mod prelude {
     pub use super::*;
+    #[cfg(feature = "bumpalo")]
+    pub use crate::arena::*;
+    pub use crate::bits::*;
+    pub use crate::captured::*;
+    pub use crate::codec::*;
+    pub use crate::columnar::*;
     pub use crate::context::*;
     pub use crate::dedupe::*;
+    pub use crate::delta::*;
     pub use crate::diff::*;
+    pub use crate::entropy::*;
+    pub use crate::fixed::*;
+    #[cfg(feature = "hooks")]
+    pub use crate::hooks::*;
+    pub use crate::id::*;
+    pub use crate::intern::*;
     pub use crate::io::*;
+    pub use crate::layout::*;
+    pub use crate::length::*;
+    pub use crate::metrics::*;
+    pub use crate::mux::*;
     pub use crate::pack::*;
+    #[cfg(feature = "rayon")]
+    pub use crate::parallel::*;
+    pub use crate::portable::*;
+    pub use crate::profile::*;
+    pub use crate::progress::*;
+    pub use crate::projection::*;
+    pub use crate::recording::*;
+    pub use crate::rpc::*;
+    pub use crate::snapshot::*;
+    pub use crate::timestamp::*;
     pub use crate::u256::*;
     pub use crate::varint::*;
+    pub use crate::wrappers::*;
     pub use lencode_macros::*;
 }
 
+/// Applies the inlining attributes for derived `encode_ext`/`decode_ext` bodies.
+///
+/// Elsewhere they are inlined like the built‑in impls. On SBF (Solana programs), where each
+/// stack frame is capped at 4 KB, every derived type keeps a frame of its own so the locals
+/// of nested types do not pile up in the caller's frame. The target is checked here rather
+/// than in the derive output so downstream crates need no `check-cfg` for it.
+#[doc(hidden)]
+#[cfg(not(target_os = "solana"))]
+#[macro_export]
+macro_rules! __derived_codec_fn {
+    ($($item:tt)*) => {
+        #[inline(always)]
+        $($item)*
+    };
+}
+
+#[doc(hidden)]
+#[cfg(target_os = "solana")]
+#[macro_export]
+macro_rules! __derived_codec_fn {
+    ($($item:tt)*) => {
+        #[inline(never)]
+        $($item)*
+    };
+}
+
 use core::mem::MaybeUninit;
 use core::num::{
     NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize, NonZeroU8,
@@ -165,6 +281,89 @@ use core::ptr;
 
 use prelude::*;
 
+/// Maximum number of bytes a decoder reserves up front based on a length prefix.
+///
+/// Length prefixes come from the input, so they cannot be trusted for allocation. Collections
+/// and byte strings longer than this still decode; their buffers just grow as elements are
+/// read, so a forged length fails with [`Error::ReaderOutOfData`] once the input runs out
+/// instead of triggering a multi‑gigabyte reservation first.
+///
+/// 1 MiB, or 4 KiB on SBF, where a Solana program's heap is 32 KB by default.
+#[cfg(not(target_os = "solana"))]
+pub const MAX_PREALLOC_BYTES: usize = 1 << 20;
+
+/// Maximum number of bytes a decoder reserves up front based on a length prefix.
+///
+/// 4 KiB on SBF, where a Solana program's heap is 32 KB by default, so a forged length
+/// cannot exhaust it before any data is read.
+#[cfg(target_os = "solana")]
+pub const MAX_PREALLOC_BYTES: usize = 1 << 12;
+
+/// Returns the initial capacity to reserve for `len` elements of `T`, bounded by
+/// [`MAX_PREALLOC_BYTES`].
+#[inline(always)]
+pub(crate) const fn prealloc_capacity<T>(len: usize) -> usize {
+    let size = core::mem::size_of::<T>();
+    if size == 0 {
+        return len;
+    }
+    let max = MAX_PREALLOC_BYTES / size;
+    if len < max { len } else { max }
+}
+
+// Lengths and counts are written as `len as u64`, which is lossless only while `usize` is at
+// most 64 bits wide.
+const _: () = assert!(
+    usize::BITS <= 64,
+    "lencode requires usize of at most 64 bits"
+);
+
+/// Narrows a decoded `u64` to `usize`, failing with [`Error::OutOfRange`] instead of
+/// truncating on targets where `usize` is narrower than 64 bits.
+#[inline(always)]
+pub(crate) fn checked_usize(value: u64) -> Result<usize> {
+    narrow_unsigned(value, usize::MAX as u64).map(|value| value as usize)
+}
+
+/// Narrows a decoded `i64` to `isize`; see [`checked_usize`].
+#[inline(always)]
+pub(crate) fn checked_isize(value: i64) -> Result<isize> {
+    narrow_signed(value, isize::MAX as i64).map(|value| value as isize)
+}
+
+/// Checks `value <= max`. Split out from [`checked_usize`] so the 32‑bit path can be tested
+/// on any host.
+#[inline(always)]
+pub(crate) const fn narrow_unsigned(value: u64, max: u64) -> Result<u64> {
+    if value > max {
+        return Err(Error::OutOfRange {
+            value: value as i128,
+            max: max as i128,
+        });
+    }
+    Ok(value)
+}
+
+/// Checks `-max - 1 <= value <= max`.
+#[inline(always)]
+pub(crate) const fn narrow_signed(value: i64, max: i64) -> Result<i64> {
+    if value > max || value < -max - 1 {
+        return Err(Error::OutOfRange {
+            value: value as i128,
+            max: max as i128,
+        });
+    }
+    Ok(value)
+}
+
+/// Steps over a flagged byte‑string (`varint((len << 1) | flag) + payload`) without
+/// decompressing or validating the payload.
+#[inline(always)]
+pub(crate) fn skip_flagged_bytes(reader: &mut impl Read) -> Result<()> {
+    let flagged = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    skip_bytes(reader, flagged >> 1)
+}
+
 /// Encodes `value` into `writer` using the type’s [`Encode`] implementation.
 ///
 /// Returns the number of bytes written on success.
@@ -181,15 +380,86 @@ pub fn decode<T: Decode>(reader: &mut impl Read) -> Result<T> {
 
 /// Encodes `value` with an optional [`EncoderContext`] for deduplication and/or
 /// diff encoding.
+///
+/// With the `hooks` feature, the value is also reported to the context's
+/// [`EncodeHooks`](crate::hooks::EncodeHooks).
 #[inline(always)]
-pub fn encode_ext(
-    value: &impl Encode,
+pub fn encode_ext<T: Encode + ?Sized>(
+    value: &T,
     writer: &mut impl Write,
     ctx: Option<&mut EncoderContext>,
 ) -> Result<usize> {
+    #[cfg(feature = "hooks")]
+    {
+        let mut ctx = ctx;
+        let written = value.encode_ext(writer, ctx.as_deref_mut())?;
+        EncoderContext::record_value(ctx, core::any::type_name::<T>(), written);
+        Ok(written)
+    }
+    #[cfg(not(feature = "hooks"))]
     value.encode_ext(writer, ctx)
 }
 
+/// Appends the encoding of `value` to `buf`, leaving `buf` unchanged if encoding fails.
+///
+/// See [`TransactionalWriter`].
+#[inline(always)]
+pub fn encode_atomic<T: Encode + ?Sized>(value: &T, buf: &mut Vec<u8>) -> Result<usize> {
+    encode_atomic_ext(value, buf, None)
+}
+
+/// Like [`encode_atomic`], with an optional [`EncoderContext`]. Context state is not rolled
+/// back on failure.
+#[inline(always)]
+pub fn encode_atomic_ext<T: Encode + ?Sized>(
+    value: &T,
+    buf: &mut Vec<u8>,
+    ctx: Option<&mut EncoderContext>,
+) -> Result<usize> {
+    let mut writer = TransactionalWriter::new(buf);
+    let written = encode_ext(value, &mut writer, ctx)?;
+    writer.commit();
+    Ok(written)
+}
+
+/// Encodes the items of `iter` as a list, without collecting them first: a length prefix
+/// taken from [`ExactSizeIterator::len`], then each item in turn.
+///
+/// The output decodes as a `Vec<T>` (or any other list collection of `T`), except for `u8`
+/// items: `Vec<u8>` uses the byte‑string layout instead. Fails with
+/// [`Error::IncorrectLength`] if the iterator yields a different number of items than it
+/// reported, in which case the bytes already written are not a valid list.
+#[inline(always)]
+pub fn encode_from_iter<T: Encode>(
+    writer: &mut impl Write,
+    iter: impl IntoIterator<Item = T, IntoIter: ExactSizeIterator>,
+) -> Result<usize> {
+    encode_from_iter_ext(writer, iter, None)
+}
+
+/// Like [`encode_from_iter`], with an optional [`EncoderContext`].
+pub fn encode_from_iter_ext<T: Encode>(
+    writer: &mut impl Write,
+    iter: impl IntoIterator<Item = T, IntoIter: ExactSizeIterator>,
+    mut ctx: Option<&mut EncoderContext>,
+) -> Result<usize> {
+    let iter = iter.into_iter();
+    let len = iter.len();
+    let mut total = Lencode::encode_varint_u64(len as u64, writer)?;
+    let mut count = 0;
+    for item in iter {
+        count += 1;
+        if count > len {
+            return Err(Error::IncorrectLength);
+        }
+        total += item.encode_ext(writer, ctx.as_deref_mut())?;
+    }
+    if count != len {
+        return Err(Error::IncorrectLength);
+    }
+    Ok(total)
+}
+
 /// Decodes a value with an optional [`DecoderContext`] for deduplication and/or
 /// diff decoding.
 #[inline(always)]
@@ -200,6 +470,270 @@ pub fn decode_ext<T: Decode>(
     T::decode_ext(reader, ctx)
 }
 
+/// Decodes a value of type `T` from a reader held as a trait object.
+///
+/// Use this when readers are stored heterogeneously, e.g. as `Box<dyn Read>`; zero‑copy
+/// readers keep their fast paths through the `dyn` forwarding impls.
+#[inline(always)]
+pub fn decode_dyn<T: Decode>(reader: &mut dyn Read) -> Result<T> {
+    T::decode_ext(&mut { reader }, None)
+}
+
+/// Like [`decode_dyn`], with an optional [`DecoderContext`].
+#[inline(always)]
+pub fn decode_dyn_ext<T: Decode>(
+    reader: &mut dyn Read,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<T> {
+    T::decode_ext(&mut { reader }, ctx)
+}
+
+/// Decodes a value of type `T` that may borrow from `bytes`; see [`DecodeBorrowed`].
+///
+/// ```rust
+/// use lencode::prelude::*;
+///
+/// let mut buf = Vec::new();
+/// encode(&(String::from("hello"), vec![1u8, 2, 3]), &mut buf).unwrap();
+/// let name: &str = decode_borrowed(&buf).unwrap();
+/// assert_eq!(name, "hello");
+/// ```
+#[inline(always)]
+pub fn decode_borrowed<'a, T: DecodeBorrowed<'a>>(bytes: &'a [u8]) -> Result<T> {
+    T::decode_borrowed(&mut SliceReader::new(bytes), None)
+}
+
+/// Like [`decode_borrowed`], reading from a [`SliceReader`] with an optional
+/// [`DecoderContext`].
+#[inline(always)]
+pub fn decode_borrowed_ext<'a, T: DecodeBorrowed<'a>>(
+    reader: &mut SliceReader<'a>,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<T> {
+    T::decode_borrowed(reader, ctx)
+}
+
+/// Encodes `value`, then compresses the whole message as one byte string.
+///
+/// The output uses the flagged header of `Vec<u8>` (`varint((len << 1) | compressed)` +
+/// payload), so zstd sees across field boundaries: structured payloads made of many small
+/// varints typically shrink several times further than per‑field compression allows.
+/// Messages that do not benefit are stored raw behind the same one‑varint header.
+#[inline(always)]
+pub fn encode_compressed<T: Encode + ?Sized>(value: &T, writer: &mut impl Write) -> Result<usize> {
+    encode_compressed_ext(value, writer, None)
+}
+
+/// Like [`encode_compressed`], encoding the message with an optional [`EncoderContext`].
+pub fn encode_compressed_ext<T: Encode + ?Sized>(
+    value: &T,
+    writer: &mut impl Write,
+    ctx: Option<&mut EncoderContext>,
+) -> Result<usize> {
+    let mut message = Vec::new();
+    value.encode_ext(&mut message, ctx)?;
+    message.as_slice().encode_ext(writer, None)
+}
+
+/// Decodes a `T` written by [`encode_compressed`].
+///
+/// Fails with [`Error::InvalidData`] if the decompressed message holds more than one `T`.
+#[inline(always)]
+pub fn decode_compressed<T: Decode>(reader: &mut impl Read) -> Result<T> {
+    decode_compressed_ext(reader, None)
+}
+
+/// Like [`decode_compressed`], decoding the message with an optional [`DecoderContext`].
+pub fn decode_compressed_ext<T: Decode>(
+    reader: &mut impl Read,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<T> {
+    let message = Vec::<u8>::decode_ext(reader, None)?;
+    let mut cursor = Cursor::new(&message[..]);
+    let value = T::decode_ext(&mut cursor, ctx)?;
+    if cursor.position() != message.len() {
+        return Err(Error::InvalidData);
+    }
+    Ok(value)
+}
+
+/// Encodes `value` through the full size pipeline: dedupe → encode → whole‑message zstd.
+///
+/// Repeated values are first replaced by table IDs through a fresh [`DedupeEncoder`], and
+/// the resulting message is then compressed as one byte string, as [`encode_compressed`]
+/// does. The layers only compose in this order: compressing first would hide repeats from
+/// the dedupe table, and the table must be rebuilt from the decompressed bytes. Each call
+/// starts a new table, so every message decodes on its own with [`decode_pipeline`].
+///
+/// [`Profile::archival`] applies the same pipeline with a configurable zstd level and
+/// dictionary.
+///
+/// ```
+/// use lencode::prelude::*;
+/// use std::borrow::Cow;
+///
+/// let events: Vec<(u32, Cow<'static, str>)> =
+///     (0..100).map(|i| (i, Cow::Borrowed(["deposit", "withdraw"][i as usize % 2]))).collect();
+///
+/// let mut buf = Vec::new();
+/// encode_pipeline(&events, &mut buf).unwrap();
+/// let decoded: Vec<(u32, Cow<'static, str>)> = decode_pipeline(&mut Cursor::new(&buf)).unwrap();
+/// assert_eq!(decoded, events);
+/// ```
+#[inline(always)]
+pub fn encode_pipeline<T: Encode + ?Sized>(value: &T, writer: &mut impl Write) -> Result<usize> {
+    encode_compressed_ext(value, writer, Some(&mut EncoderContext::with_dedupe()))
+}
+
+/// Decodes a `T` written by [`encode_pipeline`]: decompresses the message, then resolves
+/// dedupe IDs against a fresh [`DedupeDecoder`].
+///
+/// Fails with [`Error::InvalidData`] if the decompressed message holds more than one `T`.
+#[inline(always)]
+pub fn decode_pipeline<T: Decode>(reader: &mut impl Read) -> Result<T> {
+    decode_compressed_ext(reader, Some(&mut DecoderContext::with_dedupe()))
+}
+
+/// Feeds the canonical encoding of `value` into `hasher`, returning the number of bytes
+/// hashed.
+///
+/// The bytes hashed are exactly the bytes [`encode`] would write, streamed through a
+/// [`HashingWriter`] without materializing a buffer, so the resulting digest is a content
+/// hash of the wire form. The encoder issues the same sequence of writes for equal values,
+/// so this is deterministic even for hashers that are sensitive to how input is chunked.
+#[inline(always)]
+pub fn hash_encoded<T: Encode + ?Sized, H: core::hash::Hasher>(
+    value: &T,
+    hasher: &mut H,
+) -> Result<usize> {
+    value.encode_ext(&mut HashingWriter::new(hasher), None)
+}
+
+/// Decodes a `Vec<T>` from `reader` and appends its elements to `out`.
+///
+/// The wire format is identical to `Vec<T>`'s, but the caller owns the allocation, so a
+/// long‑running decode loop can `clear()` and reuse one buffer instead of allocating a
+/// fresh `Vec` per message. Returns the number of elements appended. On error `out` is
+/// truncated back to its original length.
+#[inline(always)]
+pub fn decode_extend<T: Decode + 'static>(
+    reader: &mut impl Read,
+    out: &mut Vec<T>,
+) -> Result<usize> {
+    decode_extend_ext(reader, out, None)
+}
+
+/// Like [`decode_extend`], with an optional [`DecoderContext`].
+#[inline(always)]
+pub fn decode_extend_ext<T: Decode + 'static>(
+    reader: &mut impl Read,
+    out: &mut Vec<T>,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<usize> {
+    let start = out.len();
+    let result = DecoderContext::nested(ctx, |mut ctx| {
+        // Byte vectors use the flagged byte-string layout (and may be diff-encoded); read
+        // them straight into `out`.
+        if let Some(bytes) = (&mut *out as &mut dyn core::any::Any).downcast_mut::<Vec<u8>>() {
+            return decode_bytes_into(reader, bytes, ctx);
+        }
+        let len = Vec::<T>::decode_len(reader)?;
+        // `len` comes from the input: reserve a bounded amount and let `push` grow the rest.
+        out.reserve(prealloc_capacity::<T>(len));
+        for _ in 0..len {
+            out.push(T::decode_ext(reader, ctx.as_deref_mut())?);
+        }
+        Ok(len)
+    });
+    if result.is_err() {
+        out.truncate(start);
+    }
+    result
+}
+
+/// Builds an array from `N` values produced by `f`, in order.
+///
+/// If `f` fails, the elements decoded so far are dropped and the error is returned. This is
+/// the element loop behind the `[T; N]` impl, and `#[derive(Decode)]` calls it directly for
+/// array fields so their elements need no `'static` bound.
+#[inline(always)]
+pub fn decode_array_with<T, const N: usize>(mut f: impl FnMut() -> Result<T>) -> Result<[T; N]> {
+    let mut arr = MaybeUninit::<[T; N]>::uninit();
+    let arr_ptr = arr.as_mut_ptr() as *mut T;
+    let mut idx = 0;
+    while idx < N {
+        match f() {
+            Ok(value) => unsafe {
+                ptr::write(arr_ptr.add(idx), value);
+                idx += 1;
+            },
+            Err(err) => {
+                for initialized in 0..idx {
+                    unsafe {
+                        ptr::drop_in_place(arr_ptr.add(initialized));
+                    }
+                }
+                return Err(err);
+            }
+        }
+    }
+
+    // SAFETY: every element was written above, so the array is fully initialized.
+    Ok(unsafe { arr.assume_init() })
+}
+
+/// Decodes a `HashMap<K, V>` from `reader` and inserts its entries into `out`.
+///
+/// Existing entries are kept; decoded entries overwrite any with an equal key. Returns the
+/// number of entries decoded. Under a [`DuplicateKeyPolicy::Reject`] context (see
+/// [`decode_into_map_ext`]) a decoded key already in `out`, whether repeated in the input or
+/// present beforehand, fails with [`Error::DuplicateKey`] instead.
+///
+/// Entries are inserted as they are decoded, so on error `out` keeps those read before the
+/// failure, including any values they replaced. Clear the map before reusing it after an
+/// error.
+#[cfg(feature = "std")]
+#[inline(always)]
+pub fn decode_into_map<K, V, S>(
+    reader: &mut impl Read,
+    out: &mut std::collections::HashMap<K, V, S>,
+) -> Result<usize>
+where
+    K: Decode + Eq + std::hash::Hash,
+    V: Decode,
+    S: std::hash::BuildHasher,
+{
+    decode_into_map_ext(reader, out, None)
+}
+
+/// Like [`decode_into_map`], with an optional [`DecoderContext`].
+#[cfg(feature = "std")]
+#[inline(always)]
+pub fn decode_into_map_ext<K, V, S>(
+    reader: &mut impl Read,
+    out: &mut std::collections::HashMap<K, V, S>,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<usize>
+where
+    K: Decode + Eq + std::hash::Hash,
+    V: Decode,
+    S: std::hash::BuildHasher,
+{
+    DecoderContext::nested(ctx, |mut ctx| {
+        let len = std::collections::HashMap::<K, V>::decode_len(reader)?;
+        let reject = duplicate_keys_rejected(ctx.as_deref());
+        out.reserve(prealloc_capacity::<(K, V)>(len));
+        for _ in 0..len {
+            let key = K::decode_ext(reader, ctx.as_deref_mut())?;
+            let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+            if out.insert(key, value).is_some() && reject {
+                return Err(Error::DuplicateKey);
+            }
+        }
+        Ok(len)
+    })
+}
+
 // Provide a Result alias that defaults to this crate's [`Error`] type while still allowing
 // callers (and macros) to specify a different error type when needed. This avoids clashing
 // with macros that expect the standard `Result` alias to accept two generic parameters.
@@ -277,7 +811,7 @@ pub trait Decode {
     /// Decodes a collection length previously encoded with [`Encode::encode_len`].
     #[inline(always)]
     fn decode_len(reader: &mut impl Read) -> Result<usize> {
-        Lencode::decode_varint_u64(reader).map(|v| v as usize)
+        checked_usize(Lencode::decode_varint_u64(reader)?)
     }
 
     /// Decodes an enum discriminant previously encoded with [`Encode::encode_discriminant`].
@@ -285,7 +819,21 @@ pub trait Decode {
     /// The default reads an unsigned varint.
     #[inline(always)]
     fn decode_discriminant(reader: &mut impl Read) -> Result<usize> {
-        Lencode::decode_varint_u64(reader).map(|v| v as usize)
+        checked_usize(Lencode::decode_varint_u64(reader)?)
+    }
+
+    /// Consumes an encoded `Self` from `reader` without keeping it.
+    ///
+    /// The default decodes the value and drops it. Types that can step over their encoding
+    /// more cheaply override this; byte strings and `String`, for example, skip their payload
+    /// without decompressing it. A context is passed through so deduplicated values are still
+    /// registered.
+    #[inline(always)]
+    fn skip_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Self::decode_ext(reader, ctx).map(drop)
     }
 
     /// Convenience wrapper around [`Decode::decode_ext`] without deduplication.
@@ -297,20 +845,28 @@ pub trait Decode {
         Self::decode_ext(reader, None)
     }
 
+    /// Whether [`Decode::decode_vec`] decodes no nested containers.
+    ///
+    /// `Vec<Self>` only calls `decode_vec` when this is `true`, since elements decoded there
+    /// get no [`DecoderContext`] and so no depth limit. Fixed‑width primitives set it, as
+    /// should types that override `decode_vec` with a bulk read.
+    const FLAT_DECODE_VEC: bool = false;
+
     /// Decodes `count` items into a `Vec` without deduplication.
     ///
     /// The default iterates per‑element. Types whose wire representation is a
     /// fixed‑size byte sequence (e.g. `[u8; N]`) override this to perform a
     /// single bulk read, which is significantly faster for large collections.
     ///
-    /// Called automatically by `Vec<T>::decode_ext` when no dedupe context is
-    /// active.
+    /// Called automatically by `Vec<T>::decode_ext` when [`Decode::FLAT_DECODE_VEC`] is set
+    /// and the context carries no state (no dedupe, diff, interner, codecs or non‑default
+    /// policies).
     #[inline(always)]
     fn decode_vec(reader: &mut impl Read, count: usize) -> Result<Vec<Self>>
     where
         Self: Sized,
     {
-        let mut vec = Vec::with_capacity(count);
+        let mut vec = Vec::with_capacity(prealloc_capacity::<Self>(count));
         for _ in 0..count {
             vec.push(Self::decode_ext(reader, None)?);
         }
@@ -329,6 +885,8 @@ macro_rules! impl_encode_decode_unsigned_primitive {
             }
 
             impl Decode for $t {
+                const FLAT_DECODE_VEC: bool = true;
+
                 #[inline(always)]
                 fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
                     Lencode::decode_varint(reader)
@@ -357,6 +915,8 @@ impl Encode for u16 {
 }
 
 impl Decode for u16 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Lencode::decode_varint_u16(reader)
@@ -380,6 +940,8 @@ impl Encode for u32 {
 }
 
 impl Decode for u32 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Lencode::decode_varint_u32(reader)
@@ -403,6 +965,8 @@ impl Encode for u64 {
 }
 
 impl Decode for u64 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Lencode::decode_varint_u64(reader)
@@ -426,6 +990,8 @@ impl Encode for u128 {
 }
 
 impl Decode for u128 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Lencode::decode_varint_u128(reader)
@@ -449,9 +1015,11 @@ impl Encode for usize {
 }
 
 impl Decode for usize {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        Lencode::decode_varint_u64(reader).map(|v| v as usize)
+        checked_usize(Lencode::decode_varint_u64(reader)?)
     }
 
     #[inline(always)]
@@ -471,6 +1039,8 @@ macro_rules! impl_encode_decode_signed_primitive {
             }
 
             impl Decode for $t {
+                const FLAT_DECODE_VEC: bool = true;
+
                 #[inline(always)]
                 fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
                     Lencode::decode_varint_signed(reader)
@@ -499,6 +1069,8 @@ impl Encode for i16 {
 }
 
 impl Decode for i16 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Ok(zigzag_decode(Lencode::decode_varint_u16(reader)?))
@@ -522,6 +1094,8 @@ impl Encode for i32 {
 }
 
 impl Decode for i32 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Ok(zigzag_decode(Lencode::decode_varint_u32(reader)?))
@@ -545,6 +1119,8 @@ impl Encode for i64 {
 }
 
 impl Decode for i64 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Ok(zigzag_decode(Lencode::decode_varint_u64(reader)?))
@@ -568,6 +1144,8 @@ impl Encode for i128 {
 }
 
 impl Decode for i128 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Ok(zigzag_decode(Lencode::decode_varint_u128(reader)?))
@@ -591,9 +1169,11 @@ impl Encode for isize {
 }
 
 impl Decode for isize {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        Ok(zigzag_decode(Lencode::decode_varint_u64(reader)?) as isize)
+        checked_isize(zigzag_decode(Lencode::decode_varint_u64(reader)?))
     }
 
     #[inline(always)]
@@ -618,6 +1198,8 @@ macro_rules! impl_encode_decode_nonzero {
             }
 
             impl Decode for $nonzero {
+                const FLAT_DECODE_VEC: bool = true;
+
                 #[inline(always)]
                 fn decode_ext(
                     reader: &mut impl Read,
@@ -663,6 +1245,8 @@ impl Encode for bool {
 }
 
 impl Decode for bool {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Lencode::decode_bool(reader)
@@ -692,11 +1276,13 @@ impl Encode for f32 {
             return Ok(4);
         }
         let bytes = self.to_le_bytes();
-        writer.write(&bytes)
+        write_whole(writer, &bytes)
     }
 }
 
 impl Decode for f32 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         if let Some(slice) = reader.buf() {
@@ -737,11 +1323,13 @@ impl Encode for f64 {
             return Ok(8);
         }
         let bytes = self.to_le_bytes();
-        writer.write(&bytes)
+        write_whole(writer, &bytes)
     }
 }
 
 impl Decode for f64 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         if let Some(slice) = reader.buf() {
@@ -764,6 +1352,9 @@ impl Decode for f64 {
     }
 }
 
+/// Borrowed bytes encode exactly like `Vec<u8>`, so maps and sets keyed by `&[u8]` (e.g.
+/// `BTreeMap<&[u8], V>`) are encoded without allocating owned keys and decode as their
+/// owned counterparts.
 impl Encode for &[u8] {
     #[inline(always)]
     fn encode_ext(
@@ -781,54 +1372,20 @@ impl Encode for &[u8] {
 
         // Encode as either raw or compressed with a 1-bit flag in the header:
         // header = varint((payload_len << 1) | (is_compressed as usize))
-        let raw_len = self.len();
-        // Skip compression for small payloads where overhead outweighs savings
-        if raw_len >= bytes::MIN_COMPRESS_LEN && !bytes::looks_incompressible(self) {
-            let compressed = bytes::zstd_compress(self)?;
-            let comp_len = compressed.len();
-            let raw_hdr = bytes::flagged_header_len(raw_len, false);
-            let comp_hdr = bytes::flagged_header_len(comp_len, true);
-            if comp_len + comp_hdr < raw_len + raw_hdr {
-                let mut total = 0;
-                total += Self::encode_len((comp_len << 1) | 1, writer)?;
-                total += writer.write(&compressed)?;
-                return Ok(total);
-            }
-        }
-        let mut total = 0;
-        total += Self::encode_len(raw_len << 1, writer)?;
-        total += writer.write(self)?;
-        Ok(total)
+        bytes::write_flagged_compressed(writer, self, ctx.as_deref())
     }
 }
 
+/// Borrowed strings encode exactly like `String`; see the note on `&[u8]`.
 impl Encode for &str {
     #[inline(always)]
     fn encode_ext(
         &self,
         writer: &mut impl Write,
-        _ctx: Option<&mut EncoderContext>,
+        ctx: Option<&mut EncoderContext>,
     ) -> Result<usize> {
         // Encode as either raw UTF-8 bytes or compressed with a 1-bit flag in header
-        let bytes = self.as_bytes();
-        let raw_len = bytes.len();
-        // Skip compression for small payloads where overhead outweighs savings
-        if raw_len >= bytes::MIN_COMPRESS_LEN && !bytes::looks_incompressible(bytes) {
-            let compressed = bytes::zstd_compress(bytes)?;
-            let comp_len = compressed.len();
-            let raw_hdr = bytes::flagged_header_len(raw_len, false);
-            let comp_hdr = bytes::flagged_header_len(comp_len, true);
-            if comp_len + comp_hdr < raw_len + raw_hdr {
-                let mut total = 0;
-                total += Self::encode_len((comp_len << 1) | 1, writer)?;
-                total += writer.write(&compressed)?;
-                return Ok(total);
-            }
-        }
-        let mut total = 0;
-        total += Self::encode_len(raw_len << 1, writer)?;
-        total += writer.write(bytes)?;
-        Ok(total)
+        bytes::write_flagged_compressed(writer, self.as_bytes(), ctx.as_deref())
     }
 }
 
@@ -837,15 +1394,15 @@ impl Encode for String {
     fn encode_ext(
         &self,
         writer: &mut impl Write,
-        _ctx: Option<&mut EncoderContext>,
+        ctx: Option<&mut EncoderContext>,
     ) -> Result<usize> {
-        self.as_str().encode_ext(writer, None)
+        self.as_str().encode_ext(writer, ctx)
     }
 }
 
 impl Decode for String {
     #[inline(always)]
-    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
         let flagged = Self::decode_len(reader)?;
         let is_compressed = (flagged & 1) == 1;
         let payload_len = flagged >> 1;
@@ -858,16 +1415,12 @@ impl Decode for String {
                 let orig_len = bytes::zstd_content_size(comp)?;
                 let out = bytes::zstd_decompress(comp, orig_len)?;
                 reader.advance(payload_len);
-                return String::from_utf8(out).map_err(|_| Error::InvalidData);
-            }
-            let mut comp = vec![0u8; payload_len];
-            let mut read = 0usize;
-            while read < payload_len {
-                read += reader.read(&mut comp[read..])?;
+                return string_from_utf8(out, ctx.as_deref());
             }
+            let comp = read_vec(reader, payload_len)?;
             let orig_len = bytes::zstd_content_size(&comp)?;
             let out = bytes::zstd_decompress(&comp, orig_len)?;
-            String::from_utf8(out).map_err(|_| Error::InvalidData)
+            string_from_utf8(out, ctx.as_deref())
         } else {
             // Zero-copy fast path
             if let Some(slice) = reader.buf()
@@ -878,16 +1431,17 @@ impl Decode for String {
                     core::ptr::copy_nonoverlapping(slice.as_ptr(), buf.as_mut_ptr(), payload_len);
                 }
                 reader.advance(payload_len);
-                return String::from_utf8(buf).map_err(|_| Error::InvalidData);
-            }
-            let mut buf = vec![0u8; payload_len];
-            let mut read = 0usize;
-            while read < payload_len {
-                read += reader.read(&mut buf[read..])?;
+                return string_from_utf8(buf, ctx.as_deref());
             }
-            String::from_utf8(buf).map_err(|_| Error::InvalidData)
+            let buf = read_vec(reader, payload_len)?;
+            string_from_utf8(buf, ctx.as_deref())
         }
     }
+
+    #[inline(always)]
+    fn skip_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<()> {
+        skip_flagged_bytes(reader)
+    }
 }
 
 impl<T: Encode> Encode for Option<T> {
@@ -912,11 +1466,13 @@ impl<T: Encode> Encode for Option<T> {
 impl<T: Decode> Decode for Option<T> {
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        if Lencode::decode_bool(reader)? {
-            Ok(Some(T::decode_ext(reader, ctx)?))
-        } else {
-            Ok(None)
-        }
+        DecoderContext::nested(ctx, |ctx| {
+            if Lencode::decode_bool(reader)? {
+                Ok(Some(T::decode_ext(reader, ctx)?))
+            } else {
+                Ok(None)
+            }
+        })
     }
 
     fn decode_len(_reader: &mut impl Read) -> Result<usize> {
@@ -951,11 +1507,13 @@ impl<T: Encode, E: Encode> Encode for core::result::Result<T, E> {
 impl<T: Decode, E: Decode> Decode for core::result::Result<T, E> {
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        if Lencode::decode_bool(reader)? {
-            Ok(Ok(T::decode_ext(reader, ctx)?))
-        } else {
-            Ok(Err(E::decode_ext(reader, ctx)?))
-        }
+        DecoderContext::nested(ctx, |ctx| {
+            if Lencode::decode_bool(reader)? {
+                Ok(Ok(T::decode_ext(reader, ctx)?))
+            } else {
+                Ok(Err(E::decode_ext(reader, ctx)?))
+            }
+        })
     }
 
     fn decode_len(_reader: &mut impl Read) -> Result<usize> {
@@ -992,7 +1550,7 @@ impl<const N: usize, T: Encode + 'static> Encode for [T; N] {
                 writer.advance_mut(N);
                 return Ok(N);
             }
-            return writer.write(bytes);
+            return write_whole(writer, bytes);
         }
         let mut total_written = 0;
         for item in self {
@@ -1007,7 +1565,7 @@ impl<const N: usize, T: Encode + 'static> Encode for [T; N] {
             let total = N * items.len();
             let bytes: &[u8] =
                 unsafe { core::slice::from_raw_parts(items.as_ptr() as *const u8, total) };
-            return writer.write(bytes);
+            return write_whole(writer, bytes);
         }
         let mut total = 0;
         for item in items {
@@ -1038,7 +1596,9 @@ impl<const N: usize, T: Decode + 'static> Decode for [T; N] {
                 return Ok(unsafe { arr.assume_init() });
             }
 
-            let mut arr = MaybeUninit::<[T; N]>::uninit();
+            // Zeroed rather than uninitialized: `T` is `u8`, so zero is a valid value, and the
+            // fallback below hands the buffer to `Read::read`, which may inspect it.
+            let mut arr = MaybeUninit::<[T; N]>::zeroed();
             if let Some(buf) = reader.buf() {
                 if buf.len() >= N {
                     unsafe {
@@ -1055,45 +1615,23 @@ impl<const N: usize, T: Decode + 'static> Decode for [T; N] {
             }
             // Fallback: read through the trait
             let dst = unsafe { core::slice::from_raw_parts_mut(arr.as_mut_ptr() as *mut u8, N) };
-            let mut read = 0;
-            while read < N {
-                read += reader.read(&mut dst[read..])?;
-            }
+            crate::io::read_exact(reader, dst)?;
             return Ok(unsafe { arr.assume_init() });
         }
 
-        let mut arr = MaybeUninit::<[T; N]>::uninit();
-        let arr_ptr = arr.as_mut_ptr() as *mut T;
-        let mut idx = 0;
-        while idx < N {
-            match T::decode_ext(reader, ctx.as_deref_mut()) {
-                Ok(value) => unsafe {
-                    ptr::write(arr_ptr.add(idx), value);
-                    idx += 1;
-                },
-                Err(err) => {
-                    for initialized in 0..idx {
-                        unsafe {
-                            ptr::drop_in_place(arr_ptr.add(initialized));
-                        }
-                    }
-                    return Err(err);
-                }
-            }
-        }
-
-        // SAFETY: every element was written above, so the array is fully initialized.
-        Ok(unsafe { arr.assume_init() })
+        decode_array_with(|| T::decode_ext(reader, ctx.as_deref_mut()))
     }
 
     fn decode_len(_reader: &mut impl Read) -> Result<usize> {
         unimplemented!()
     }
 
+    const FLAT_DECODE_VEC: bool = T::FLAT_DECODE_VEC;
+
     #[inline(always)]
     fn decode_vec(reader: &mut impl Read, count: usize) -> Result<Vec<Self>> {
         if core::any::TypeId::of::<T>() == core::any::TypeId::of::<u8>() {
-            let total = N * count;
+            let total = N.checked_mul(count).ok_or(Error::InvalidData)?;
             if let Some(buf) = reader.buf() {
                 if buf.len() >= total {
                     let mut vec: Vec<Self> = Vec::with_capacity(count);
@@ -1110,18 +1648,20 @@ impl<const N: usize, T: Decode + 'static> Decode for [T; N] {
                 }
                 return Err(Error::ReaderOutOfData);
             }
-            // Fallback: read through trait
-            let mut vec: Vec<Self> = Vec::with_capacity(count);
-            let dst =
-                unsafe { core::slice::from_raw_parts_mut(vec.as_mut_ptr() as *mut u8, total) };
-            let mut read = 0;
-            while read < total {
-                read += reader.read(&mut dst[read..])?;
+            // Fallback: read through trait. Counts beyond the preallocation limit take the
+            // per-element path below so the buffer grows only as data arrives.
+            if total <= MAX_PREALLOC_BYTES {
+                let mut vec: Vec<Self> = Vec::with_capacity(count);
+                let dst = unsafe {
+                    core::ptr::write_bytes(vec.as_mut_ptr() as *mut u8, 0, total);
+                    core::slice::from_raw_parts_mut(vec.as_mut_ptr() as *mut u8, total)
+                };
+                crate::io::read_exact(reader, dst)?;
+                unsafe { vec.set_len(count) };
+                return Ok(vec);
             }
-            unsafe { vec.set_len(count) };
-            return Ok(vec);
         }
-        let mut vec = Vec::with_capacity(count);
+        let mut vec = Vec::with_capacity(prealloc_capacity::<Self>(count));
         for _ in 0..count {
             vec.push(Self::decode_ext(reader, None)?);
         }
@@ -1129,82 +1669,122 @@ impl<const N: usize, T: Decode + 'static> Decode for [T; N] {
     }
 }
 
-impl<T: Decode + 'static> Decode for Vec<T> {
-    #[inline(always)]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        // If T is u8, decode flagged header + payload without a leading element count.
-        if core::any::TypeId::of::<T>() == core::any::TypeId::of::<u8>() {
-            // Diff decoding path: when a diff decoder with an active key is present
-            if let Some(ref mut c) = ctx
-                && let Some(ref mut diff) = c.diff
-                && diff.current_key.is_some()
-            {
-                let out = diff.decode_blob(reader)?;
-                let vec_t: Vec<T> = unsafe { core::mem::transmute::<Vec<u8>, Vec<T>>(out) };
-                return Ok(vec_t);
-            }
+/// Converts `value` into `U` when `T` and `U` are the same type, failing with
+/// [`Error::InvalidData`] otherwise.
+///
+/// The byte specializations of the collection codecs pick their path by comparing `TypeId`s
+/// and then need to hand back the concrete `Vec<u8>`/`VecDeque<u8>` as the generic type. Going
+/// through [`Any`](core::any::Any) makes that conversion checked and safe instead of relying
+/// on layout assumptions via `mem::transmute`; it compiles down to a move.
+#[inline(always)]
+pub(crate) fn cast_same<T: 'static, U: 'static>(value: T) -> Result<U> {
+    let mut slot = Some(value);
+    (&mut slot as &mut dyn core::any::Any)
+        .downcast_mut::<Option<U>>()
+        .and_then(Option::take)
+        .ok_or(Error::InvalidData)
+}
 
-            let flagged = Self::decode_len(reader)?;
-            let is_compressed = (flagged & 1) == 1;
-            let payload_len = flagged >> 1;
-            if is_compressed {
-                // Zero-copy fast path for compressed data
-                if let Some(slice) = reader.buf()
-                    && slice.len() >= payload_len
-                {
-                    let comp = &slice[..payload_len];
-                    let orig_len = bytes::zstd_content_size(comp)?;
-                    let out = bytes::zstd_decompress(comp, orig_len)?;
-                    reader.advance(payload_len);
-                    let vec_t: Vec<T> = unsafe { core::mem::transmute::<Vec<u8>, Vec<T>>(out) };
-                    return Ok(vec_t);
-                }
-                let mut comp = vec![0u8; payload_len];
-                let mut read = 0usize;
-                while read < payload_len {
-                    read += reader.read(&mut comp[read..])?;
-                }
-                let orig_len = bytes::zstd_content_size(&comp)?;
-                let out = bytes::zstd_decompress(&comp, orig_len)?;
-                let vec_t: Vec<T> = unsafe { core::mem::transmute::<Vec<u8>, Vec<T>>(out) };
-                return Ok(vec_t);
-            } else {
-                // Zero-copy fast path for raw data
-                if let Some(slice) = reader.buf()
-                    && slice.len() >= payload_len
-                {
-                    let mut out = Vec::<u8>::with_capacity(payload_len);
-                    unsafe {
-                        core::ptr::copy_nonoverlapping(
-                            slice.as_ptr(),
-                            out.as_mut_ptr(),
-                            payload_len,
-                        );
-                        out.set_len(payload_len);
-                    }
-                    reader.advance(payload_len);
-                    let vec_t: Vec<T> = unsafe { core::mem::transmute::<Vec<u8>, Vec<T>>(out) };
-                    return Ok(vec_t);
-                }
-                let mut out = vec![0u8; payload_len];
-                let mut read = 0usize;
-                while read < payload_len {
-                    read += reader.read(&mut out[read..])?;
-                }
-                let vec_t: Vec<T> = unsafe { core::mem::transmute::<Vec<u8>, Vec<T>>(out) };
-                return Ok(vec_t);
-            }
-        }
+/// Reads one `Vec<u8>` encoding (the flagged byte string, or a diff blob while the context's
+/// diff decoder has a key set) and appends the bytes to `out`, returning how many were
+/// appended. On error `out` is truncated back to its original length.
+#[inline(always)]
+fn decode_bytes_into(
+    reader: &mut impl Read,
+    out: &mut Vec<u8>,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<usize> {
+    let start = out.len();
+    let result = append_bytes(reader, out, ctx);
+    if result.is_err() {
+        out.truncate(start);
+    }
+    result.map(|()| out.len() - start)
+}
 
-        let len = Self::decode_len(reader)?;
-        if ctx.is_none() {
-            return T::decode_vec(reader, len);
+#[inline(always)]
+fn append_bytes(
+    reader: &mut impl Read,
+    out: &mut Vec<u8>,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<()> {
+    if let Some(c) = ctx
+        && let Some(ref mut diff) = c.diff
+        && diff.current_key.is_some()
+    {
+        let blob = diff.decode_blob(reader)?;
+        if out.is_empty() {
+            *out = blob;
+        } else {
+            out.extend_from_slice(&blob);
         }
-        let mut vec = Vec::with_capacity(len);
-        for _ in 0..len {
-            vec.push(T::decode_ext(reader, ctx.as_deref_mut())?);
+        return Ok(());
+    }
+    let flagged = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    let is_compressed = (flagged & 1) == 1;
+    let payload_len = flagged >> 1;
+    if is_compressed {
+        let start = out.len();
+        let mut decompress = |comp: &[u8]| -> Result<()> {
+            let orig_len = bytes::zstd_content_size(comp)?;
+            out.resize(start + orig_len, 0);
+            bytes::zstd_decompress_into(comp, &mut out[start..])
+        };
+        // Zero-copy fast path for compressed data
+        if let Some(slice) = reader.buf()
+            && slice.len() >= payload_len
+        {
+            decompress(&slice[..payload_len])?;
+            reader.advance(payload_len);
+            return Ok(());
         }
-        Ok(vec)
+        return decompress(&read_vec(reader, payload_len)?);
+    }
+    // Zero-copy fast path for raw data
+    if let Some(slice) = reader.buf()
+        && slice.len() >= payload_len
+    {
+        out.extend_from_slice(&slice[..payload_len]);
+        reader.advance(payload_len);
+        return Ok(());
+    }
+    read_into_vec(reader, out, payload_len)
+}
+
+impl<T: Decode + 'static> Decode for Vec<T> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            // If T is u8, decode flagged header + payload without a leading element count.
+            if core::any::TypeId::of::<T>() == core::any::TypeId::of::<u8>() {
+                let mut out = Vec::new();
+                decode_bytes_into(reader, &mut out, ctx)?;
+                return cast_same(out);
+            }
+
+            let len = Self::decode_len(reader)?;
+            if T::FLAT_DECODE_VEC && ctx.as_deref().is_none_or(DecoderContext::is_plain) {
+                return T::decode_vec(reader, len);
+            }
+            let mut vec = Vec::with_capacity(prealloc_capacity::<T>(len));
+            for _ in 0..len {
+                vec.push(T::decode_ext(reader, ctx.as_deref_mut())?);
+            }
+            Ok(vec)
+        })
+    }
+
+    #[inline(always)]
+    fn skip_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<()> {
+        if core::any::TypeId::of::<T>() == core::any::TypeId::of::<u8>()
+            && ctx
+                .as_ref()
+                .and_then(|c| c.diff.as_ref())
+                .is_none_or(|diff| diff.current_key.is_none())
+        {
+            return skip_flagged_bytes(reader);
+        }
+        Self::decode_ext(reader, ctx).map(drop)
     }
 }
 
@@ -1216,10 +1796,8 @@ impl<T: Encode + 'static> Encode for Vec<T> {
         mut ctx: Option<&mut EncoderContext>,
     ) -> Result<usize> {
         // If element type is u8, write as raw-or-compressed with flagged header, no element count:
-        if core::any::TypeId::of::<T>() == core::any::TypeId::of::<u8>() {
-            // SAFETY: when T == u8, we can view the slice as &[u8]
-            let bytes: &[u8] =
-                unsafe { core::slice::from_raw_parts(self.as_ptr() as *const u8, self.len()) };
+        if let Some(bytes) = (self as &dyn core::any::Any).downcast_ref::<Vec<u8>>() {
+            let bytes: &[u8] = bytes;
 
             // Diff encoding path: when a diff encoder with an active key is present
             if let Some(ref mut c) = ctx
@@ -1229,24 +1807,7 @@ impl<T: Encode + 'static> Encode for Vec<T> {
                 return diff.encode_blob(bytes, writer);
             }
 
-            let raw_len = bytes.len();
-            // Skip compression for small payloads where overhead outweighs savings
-            if raw_len >= bytes::MIN_COMPRESS_LEN && !bytes::looks_incompressible(bytes) {
-                let compressed = bytes::zstd_compress(bytes)?;
-                let comp_len = compressed.len();
-                let raw_hdr = bytes::flagged_header_len(raw_len, false);
-                let comp_hdr = bytes::flagged_header_len(comp_len, true);
-                if comp_len + comp_hdr < raw_len + raw_hdr {
-                    let mut total = 0;
-                    total += Self::encode_len((comp_len << 1) | 1, writer)?;
-                    total += writer.write(&compressed)?;
-                    return Ok(total);
-                }
-            }
-            let mut total = 0;
-            total += Self::encode_len(raw_len << 1, writer)?;
-            total += writer.write(bytes)?;
-            return Ok(total);
+            return bytes::write_flagged_compressed(writer, bytes, ctx.as_deref());
         }
 
         let mut total_written = 0;
@@ -1281,17 +1842,40 @@ impl<K: Encode, V: Encode> Encode for collections::BTreeMap<K, V> {
     }
 }
 
+/// Converts decoded string bytes, applying the [`Utf8Policy`] of `ctx` to invalid UTF‑8.
+#[inline(always)]
+pub(crate) fn string_from_utf8(bytes: Vec<u8>, ctx: Option<&DecoderContext>) -> Result<String> {
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(text),
+        Err(err) if ctx.is_some_and(|c| c.utf8 == Utf8Policy::Lossy) => {
+            Ok(String::from_utf8_lossy(err.as_bytes()).into_owned())
+        }
+        Err(_) => Err(Error::InvalidUtf8),
+    }
+}
+
+/// Returns whether `ctx` asks map decoders to reject duplicate keys.
+#[inline(always)]
+pub(crate) fn duplicate_keys_rejected(ctx: Option<&DecoderContext>) -> bool {
+    ctx.is_some_and(|c| c.duplicate_keys == DuplicateKeyPolicy::Reject)
+}
+
 impl<K: Decode + Ord, V: Decode> Decode for collections::BTreeMap<K, V> {
     #[inline(always)]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        let len = Self::decode_len(reader)?;
-        let mut map = collections::BTreeMap::new();
-        for _ in 0..len {
-            let key = K::decode_ext(reader, ctx.as_deref_mut())?;
-            let value = V::decode_ext(reader, ctx.as_deref_mut())?;
-            map.insert(key, value);
-        }
-        Ok(map)
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            let reject = duplicate_keys_rejected(ctx.as_deref());
+            let mut map = collections::BTreeMap::new();
+            for _ in 0..len {
+                let key = K::decode_ext(reader, ctx.as_deref_mut())?;
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                if map.insert(key, value).is_some() && reject {
+                    return Err(Error::DuplicateKey);
+                }
+            }
+            Ok(map)
+        })
     }
 }
 
@@ -1313,14 +1897,16 @@ impl<V: Encode> Encode for collections::BTreeSet<V> {
 
 impl<V: Decode + Ord> Decode for collections::BTreeSet<V> {
     #[inline(always)]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        let len = Self::decode_len(reader)?;
-        let mut set = collections::BTreeSet::new();
-        for _ in 0..len {
-            let value = V::decode_ext(reader, ctx.as_deref_mut())?;
-            set.insert(value);
-        }
-        Ok(set)
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            let mut set = collections::BTreeSet::new();
+            for _ in 0..len {
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                set.insert(value);
+            }
+            Ok(set)
+        })
     }
 }
 
@@ -1331,13 +1917,11 @@ impl<V: Encode + 'static> Encode for collections::VecDeque<V> {
         writer: &mut impl Write,
         mut ctx: Option<&mut EncoderContext>,
     ) -> Result<usize> {
-        if core::any::TypeId::of::<V>() == core::any::TypeId::of::<u8>() {
+        if let Some(deque) =
+            (self as &dyn core::any::Any).downcast_ref::<collections::VecDeque<u8>>()
+        {
             // Flatten to contiguous bytes first
-            let (a, b) = self.as_slices();
-            let a_u8: &[u8] =
-                unsafe { core::slice::from_raw_parts(a.as_ptr() as *const u8, a.len()) };
-            let b_u8: &[u8] =
-                unsafe { core::slice::from_raw_parts(b.as_ptr() as *const u8, b.len()) };
+            let (a_u8, b_u8) = deque.as_slices();
 
             // Diff encoding path
             if let Some(ref mut c) = ctx
@@ -1352,26 +1936,7 @@ impl<V: Encode + 'static> Encode for collections::VecDeque<V> {
             let mut tmp = Vec::with_capacity(a_u8.len() + b_u8.len());
             tmp.extend_from_slice(a_u8);
             tmp.extend_from_slice(b_u8);
-            let raw_len = tmp.len();
-            // Skip compression for small payloads where overhead outweighs savings
-            if raw_len >= bytes::MIN_COMPRESS_LEN && !bytes::looks_incompressible(&tmp) {
-                let compressed = bytes::zstd_compress(&tmp)?;
-                let comp_len = compressed.len();
-                let raw_hdr = bytes::flagged_header_len(raw_len, false);
-                let comp_hdr = bytes::flagged_header_len(comp_len, true);
-                if comp_len + comp_hdr < raw_len + raw_hdr {
-                    let mut total_written = 0;
-                    total_written += Self::encode_len((comp_len << 1) | 1, writer)?;
-                    total_written += writer.write(&compressed)?;
-                    return Ok(total_written);
-                }
-            }
-            {
-                let mut total_written = 0;
-                total_written += Self::encode_len(raw_len << 1, writer)?;
-                total_written += writer.write(&tmp)?;
-                return Ok(total_written);
-            }
+            return bytes::write_flagged_compressed(writer, &tmp, ctx.as_deref());
         }
 
         let mut total_written = 0;
@@ -1385,60 +1950,44 @@ impl<V: Encode + 'static> Encode for collections::VecDeque<V> {
 
 impl<V: Decode + 'static> Decode for collections::VecDeque<V> {
     #[inline(always)]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        if core::any::TypeId::of::<V>() == core::any::TypeId::of::<u8>() {
-            // Diff decoding path
-            if let Some(ref mut c) = ctx
-                && let Some(ref mut diff) = c.diff
-                && diff.current_key.is_some()
-            {
-                let out = diff.decode_blob(reader)?;
-                let out_v: Vec<V> = unsafe { core::mem::transmute::<Vec<u8>, Vec<V>>(out) };
-                let mut deque = collections::VecDeque::with_capacity(out_v.len());
-                deque.extend(out_v);
-                return Ok(deque);
-            }
-
-            let flagged = Self::decode_len(reader)?;
-            let is_compressed = (flagged & 1) == 1;
-            let payload_len = flagged >> 1;
-            if is_compressed {
-                let mut comp = vec![0u8; payload_len];
-                let mut read = 0usize;
-                while read < payload_len {
-                    read += reader.read(&mut comp[read..])?;
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            if core::any::TypeId::of::<V>() == core::any::TypeId::of::<u8>() {
+                // Diff decoding path
+                if let Some(ref mut c) = ctx
+                    && let Some(ref mut diff) = c.diff
+                    && diff.current_key.is_some()
+                {
+                    let out = diff.decode_blob(reader)?;
+                    return cast_same(collections::VecDeque::from(out));
                 }
-                let orig_len = bytes::zstd_content_size(&comp)?;
-                let out = bytes::zstd_decompress(&comp, orig_len)?;
-                // SAFETY: V == u8, so reinterpretation is sound
-                let out_v: Vec<V> = unsafe { core::mem::transmute::<Vec<u8>, Vec<V>>(out) };
-                let mut deque = collections::VecDeque::with_capacity(orig_len);
-                deque.extend(out_v);
-                return Ok(deque);
-            } else {
-                let mut out = vec![0u8; payload_len];
-                let mut read = 0usize;
-                while read < payload_len {
-                    read += reader.read(&mut out[read..])?;
+
+                let flagged = Self::decode_len(reader)?;
+                let is_compressed = (flagged & 1) == 1;
+                let payload_len = flagged >> 1;
+                if is_compressed {
+                    let comp = read_vec(reader, payload_len)?;
+                    let orig_len = bytes::zstd_content_size(&comp)?;
+                    let out = bytes::zstd_decompress(&comp, orig_len)?;
+                    return cast_same(collections::VecDeque::from(out));
+                } else {
+                    let out = read_vec(reader, payload_len)?;
+                    return cast_same(collections::VecDeque::from(out));
                 }
-                let out_v: Vec<V> = unsafe { core::mem::transmute::<Vec<u8>, Vec<V>>(out) };
-                let mut deque = collections::VecDeque::with_capacity(payload_len);
-                deque.extend(out_v);
-                return Ok(deque);
             }
-        }
 
-        let len = Self::decode_len(reader)?;
-        let mut deque = collections::VecDeque::with_capacity(len);
-        for _ in 0..len {
-            let value = V::decode_ext(reader, ctx.as_deref_mut())?;
-            deque.push_back(value);
-        }
-        Ok(deque)
+            let len = Self::decode_len(reader)?;
+            let mut deque = collections::VecDeque::with_capacity(prealloc_capacity::<V>(len));
+            for _ in 0..len {
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                deque.push_back(value);
+            }
+            Ok(deque)
+        })
     }
 }
 
-impl<V: Encode> Encode for collections::LinkedList<V> {
+impl<V: Encode + 'static> Encode for collections::LinkedList<V> {
     #[inline(always)]
     fn encode_ext(
         &self,
@@ -1447,6 +1996,14 @@ impl<V: Encode> Encode for collections::LinkedList<V> {
     ) -> Result<usize> {
         let mut total_written = 0;
         total_written += Self::encode_len(self.len(), writer)?;
+        if let Some(list) =
+            (self as &dyn core::any::Any).downcast_ref::<collections::LinkedList<u8>>()
+        {
+            // Same bytes as the element loop, written in one call
+            let bytes: Vec<u8> = list.iter().copied().collect();
+            total_written += write_whole(writer, &bytes)?;
+            return Ok(total_written);
+        }
         for value in self {
             total_written += value.encode_ext(writer, ctx.as_deref_mut())?;
         }
@@ -1454,20 +2011,27 @@ impl<V: Encode> Encode for collections::LinkedList<V> {
     }
 }
 
-impl<V: Decode> Decode for collections::LinkedList<V> {
+impl<V: Decode + 'static> Decode for collections::LinkedList<V> {
     #[inline(always)]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        let len = Self::decode_len(reader)?;
-        let mut list = collections::LinkedList::new();
-        for _ in 0..len {
-            let value = V::decode_ext(reader, ctx.as_deref_mut())?;
-            list.push_back(value);
-        }
-        Ok(list)
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            if core::any::TypeId::of::<V>() == core::any::TypeId::of::<u8>() {
+                let bytes: Vec<V> = cast_same(read_vec(reader, len)?)?;
+                return Ok(bytes.into_iter().collect());
+            }
+            // Nodes are allocated one at a time, so there is no capacity to reserve.
+            let mut list = collections::LinkedList::new();
+            for _ in 0..len {
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                list.push_back(value);
+            }
+            Ok(list)
+        })
     }
 }
 
-impl<T: Encode> Encode for collections::BinaryHeap<T> {
+impl<T: Encode + 'static> Encode for collections::BinaryHeap<T> {
     #[inline(always)]
     fn encode_ext(
         &self,
@@ -1476,22 +2040,35 @@ impl<T: Encode> Encode for collections::BinaryHeap<T> {
     ) -> Result<usize> {
         let mut total_written = 0;
         total_written += Self::encode_len(self.len(), writer)?;
+        if let Some(heap) =
+            (self as &dyn core::any::Any).downcast_ref::<collections::BinaryHeap<u8>>()
+        {
+            // Same bytes as the element loop, which visits the heap in storage order
+            total_written += write_whole(writer, heap.as_slice())?;
+            return Ok(total_written);
+        }
         for value in self {
             total_written += value.encode_ext(writer, ctx.as_deref_mut())?;
         }
         Ok(total_written)
     }
 }
-impl<T: Decode + Ord> Decode for collections::BinaryHeap<T> {
+impl<T: Decode + Ord + 'static> Decode for collections::BinaryHeap<T> {
     #[inline(always)]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        let len = Self::decode_len(reader)?;
-        let mut heap = collections::BinaryHeap::with_capacity(len);
-        for _ in 0..len {
-            let value = T::decode_ext(reader, ctx.as_deref_mut())?;
-            heap.push(value);
-        }
-        Ok(heap)
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            if core::any::TypeId::of::<T>() == core::any::TypeId::of::<u8>() {
+                let bytes: Vec<T> = cast_same(read_vec(reader, len)?)?;
+                return Ok(collections::BinaryHeap::from(bytes));
+            }
+            let mut values = Vec::with_capacity(prealloc_capacity::<T>(len));
+            for _ in 0..len {
+                values.push(T::decode_ext(reader, ctx.as_deref_mut())?);
+            }
+            // Heapify once instead of sifting on every push.
+            Ok(collections::BinaryHeap::from(values))
+        })
     }
 }
 
@@ -1516,15 +2093,21 @@ impl<K: Encode, V: Encode> Encode for std::collections::HashMap<K, V> {
 #[cfg(feature = "std")]
 impl<K: Decode + Eq + std::hash::Hash, V: Decode> Decode for std::collections::HashMap<K, V> {
     #[inline(always)]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        let len = Self::decode_len(reader)?;
-        let mut map = std::collections::HashMap::with_capacity(len);
-        for _ in 0..len {
-            let key = K::decode_ext(reader, ctx.as_deref_mut())?;
-            let value = V::decode_ext(reader, ctx.as_deref_mut())?;
-            map.insert(key, value);
-        }
-        Ok(map)
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            let reject = duplicate_keys_rejected(ctx.as_deref());
+            let mut map =
+                std::collections::HashMap::with_capacity(prealloc_capacity::<(K, V)>(len));
+            for _ in 0..len {
+                let key = K::decode_ext(reader, ctx.as_deref_mut())?;
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                if map.insert(key, value).is_some() && reject {
+                    return Err(Error::DuplicateKey);
+                }
+            }
+            Ok(map)
+        })
     }
 }
 
@@ -1548,14 +2131,16 @@ impl<V: Encode> Encode for std::collections::HashSet<V> {
 #[cfg(feature = "std")]
 impl<V: Decode + Eq + std::hash::Hash> Decode for std::collections::HashSet<V> {
     #[inline(always)]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        let len = Self::decode_len(reader)?;
-        let mut set = std::collections::HashSet::with_capacity(len);
-        for _ in 0..len {
-            let value = V::decode_ext(reader, ctx.as_deref_mut())?;
-            set.insert(value);
-        }
-        Ok(set)
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            let mut set = std::collections::HashSet::with_capacity(prealloc_capacity::<V>(len));
+            for _ in 0..len {
+                let value = V::decode_ext(reader, ctx.as_deref_mut())?;
+                set.insert(value);
+            }
+            Ok(set)
+        })
     }
 }
 
@@ -1748,8 +2333,7 @@ impl<T: Decode> Decode for core::marker::PhantomData<T> {
     }
 }
 
-#[cfg(feature = "std")]
-impl<T: Encode + Clone> Encode for std::borrow::Cow<'_, T> {
+impl<T: Encode + Clone> Encode for borrow::Cow<'_, T> {
     #[inline(always)]
     fn encode_ext(
         &self,
@@ -1760,11 +2344,10 @@ impl<T: Encode + Clone> Encode for std::borrow::Cow<'_, T> {
     }
 }
 
-#[cfg(feature = "std")]
-impl<T: Decode + Clone> Decode for std::borrow::Cow<'_, T> {
+impl<T: Decode + Clone> Decode for borrow::Cow<'_, T> {
     #[inline(always)]
     fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        Ok(std::borrow::Cow::Owned(T::decode_ext(reader, ctx)?))
+        Ok(borrow::Cow::Owned(T::decode_ext(reader, ctx)?))
     }
 
     fn decode_len(_reader: &mut impl Read) -> Result<usize> {
@@ -1772,6 +2355,236 @@ impl<T: Decode + Clone> Decode for std::borrow::Cow<'_, T> {
     }
 }
 
+/// `Cow<'static, str>` uses the same layout as `String`, but is interned through the dedupe
+/// table when a context with [`DedupeEncoder`]/[`DedupeDecoder`] is supplied: the first
+/// occurrence of each string is written in full and repeats become a small ID. This suits
+/// config‑style maps keyed by a small, fixed set of names.
+impl Pack for borrow::Cow<'static, str> {
+    #[inline(always)]
+    fn pack(&self, writer: &mut impl Write) -> Result<usize> {
+        self.as_ref().encode_ext(writer, None)
+    }
+
+    #[inline(always)]
+    fn unpack(reader: &mut impl Read) -> Result<Self> {
+        Ok(borrow::Cow::Owned(String::decode_ext(reader, None)?))
+    }
+}
+
+impl DedupeEncodeable for borrow::Cow<'static, str> {}
+impl DedupeDecodeable for borrow::Cow<'static, str> {}
+
+/// Trait for types that can be decoded as borrows out of an in‑memory buffer.
+///
+/// [`Decode`] must copy every string and byte string out of its reader. A `DecodeBorrowed`
+/// impl reads from a [`SliceReader`] instead, so `&'a str` and `&'a [u8]` point straight into
+/// the input and decoding them allocates nothing. The wire format is the one [`Encode`]
+/// writes: a `&str` decodes anything encoded from a `String`, and a `&[u8]` anything encoded
+/// from a `Vec<u8>`.
+///
+/// Payloads that are not stored verbatim — zstd‑compressed byte strings and, with a
+/// [`DiffDecoder`] key set, diff‑encoded blobs — cannot be borrowed: `&str` and `&[u8]` fail
+/// with [`Error::NotBorrowable`], while `Cow<'a, str>` and `Cow<'a, [u8]>` fall back to an
+/// owned copy for them.
+pub trait DecodeBorrowed<'a>: Sized {
+    /// Decodes `Self` from `reader`, optionally using a [`DecoderContext`].
+    fn decode_borrowed(
+        reader: &mut SliceReader<'a>,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self>;
+
+    /// Decodes a `Vec<Self>` encoding.
+    ///
+    /// The default reads an element count followed by each element. `u8` overrides this to
+    /// read the flagged byte‑string layout used by `Vec<u8>`.
+    #[inline(always)]
+    fn decode_vec_borrowed(
+        reader: &mut SliceReader<'a>,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Vec<Self>> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+            let mut out = Vec::with_capacity(prealloc_capacity::<Self>(len));
+            for _ in 0..len {
+                out.push(Self::decode_borrowed(reader, ctx.as_deref_mut())?);
+            }
+            Ok(out)
+        })
+    }
+}
+
+/// Reads a flagged byte‑string header and borrows its payload, returning whether the payload
+/// is compressed.
+#[inline(always)]
+fn read_flagged_borrowed<'a>(reader: &mut SliceReader<'a>) -> Result<(&'a [u8], bool)> {
+    let flagged = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    let payload = reader.read_borrowed(flagged >> 1)?;
+    Ok((payload, (flagged & 1) == 1))
+}
+
+/// Returns whether `ctx` has a [`DiffDecoder`] with a key set, so byte strings are diff blobs.
+#[inline(always)]
+fn diff_key_active(ctx: Option<&DecoderContext>) -> bool {
+    ctx.and_then(|c| c.diff.as_ref())
+        .is_some_and(|diff| diff.current_key.is_some())
+}
+
+/// Decompresses a flagged payload read by [`read_flagged_borrowed`].
+#[inline(always)]
+fn decompress_borrowed(comp: &[u8]) -> Result<Vec<u8>> {
+    let orig_len = bytes::zstd_content_size(comp)?;
+    bytes::zstd_decompress(comp, orig_len)
+}
+
+impl<'a> DecodeBorrowed<'a> for &'a [u8] {
+    #[inline(always)]
+    fn decode_borrowed(
+        reader: &mut SliceReader<'a>,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        if diff_key_active(ctx.as_deref()) {
+            return Err(Error::NotBorrowable);
+        }
+        match read_flagged_borrowed(reader)? {
+            (payload, false) => Ok(payload),
+            (_, true) => Err(Error::NotBorrowable),
+        }
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for &'a str {
+    #[inline(always)]
+    fn decode_borrowed(
+        reader: &mut SliceReader<'a>,
+        _ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        match read_flagged_borrowed(reader)? {
+            (payload, false) => core::str::from_utf8(payload).map_err(|_| Error::InvalidUtf8),
+            (_, true) => Err(Error::NotBorrowable),
+        }
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for borrow::Cow<'a, [u8]> {
+    #[inline(always)]
+    fn decode_borrowed(
+        reader: &mut SliceReader<'a>,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        if let Some(c) = ctx
+            && let Some(ref mut diff) = c.diff
+            && diff.current_key.is_some()
+        {
+            return Ok(borrow::Cow::Owned(diff.decode_blob(reader)?));
+        }
+        match read_flagged_borrowed(reader)? {
+            (payload, false) => Ok(borrow::Cow::Borrowed(payload)),
+            (comp, true) => Ok(borrow::Cow::Owned(decompress_borrowed(comp)?)),
+        }
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for borrow::Cow<'a, str> {
+    #[inline(always)]
+    fn decode_borrowed(
+        reader: &mut SliceReader<'a>,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        match read_flagged_borrowed(reader)? {
+            (payload, false) => match core::str::from_utf8(payload) {
+                Ok(text) => Ok(borrow::Cow::Borrowed(text)),
+                Err(_) if ctx.as_deref().is_some_and(|c| c.utf8 == Utf8Policy::Lossy) => {
+                    Ok(String::from_utf8_lossy(payload))
+                }
+                Err(_) => Err(Error::InvalidUtf8),
+            },
+            (comp, true) => Ok(borrow::Cow::Owned(string_from_utf8(
+                decompress_borrowed(comp)?,
+                ctx.as_deref(),
+            )?)),
+        }
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for u8 {
+    #[inline(always)]
+    fn decode_borrowed(
+        reader: &mut SliceReader<'a>,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        u8::decode_ext(reader, ctx)
+    }
+
+    #[inline(always)]
+    fn decode_vec_borrowed(
+        reader: &mut SliceReader<'a>,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Vec<Self>> {
+        Vec::<u8>::decode_ext(reader, ctx)
+    }
+}
+
+macro_rules! impl_decode_borrowed_via_decode {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> DecodeBorrowed<'a> for $t {
+                #[inline(always)]
+                fn decode_borrowed(
+                    reader: &mut SliceReader<'a>,
+                    ctx: Option<&mut DecoderContext>,
+                ) -> Result<Self> {
+                    <$t>::decode_ext(reader, ctx)
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_borrowed_via_decode!(
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    bool,
+    f32,
+    f64,
+    (),
+    String
+);
+
+impl<'a, T: DecodeBorrowed<'a>> DecodeBorrowed<'a> for Vec<T> {
+    #[inline(always)]
+    fn decode_borrowed(
+        reader: &mut SliceReader<'a>,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        T::decode_vec_borrowed(reader, ctx)
+    }
+}
+
+impl<'a, T: DecodeBorrowed<'a>> DecodeBorrowed<'a> for Option<T> {
+    #[inline(always)]
+    fn decode_borrowed(
+        reader: &mut SliceReader<'a>,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self> {
+        DecoderContext::nested(ctx, |ctx| {
+            if Lencode::decode_bool(reader)? {
+                Ok(Some(T::decode_borrowed(reader, ctx)?))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+}
+
 #[test]
 fn test_encode_decode_unit_type() {
     let val = ();
@@ -1854,6 +2667,47 @@ fn test_encode_decode_arrays() {
     assert_eq!(decoded, values);
 }
 
+#[test]
+fn test_byte_arrays_are_raw_and_fail_cleanly_at_end_of_stream() {
+    /// A reader without a borrowed buffer that returns `Ok(0)` once drained.
+    struct Stream<'a>(&'a [u8]);
+
+    impl Read for Stream<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = buf.len().min(self.0.len()).min(7);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    let hash = [0xFFu8; 32];
+    let hashes = vec![[0x80u8; 32], hash];
+    let mut buf = Vec::new();
+    assert_eq!(encode(&hash, &mut buf).unwrap(), 32);
+    assert_eq!(encode(&hashes, &mut buf).unwrap(), 1 + 64);
+    assert_eq!(buf.len(), 97);
+
+    let mut stream = Stream(&buf);
+    assert_eq!(decode::<[u8; 32]>(&mut stream).unwrap(), hash);
+    assert_eq!(decode::<Vec<[u8; 32]>>(&mut stream).unwrap(), hashes);
+    assert_eq!(<[u8; 32]>::unpack(&mut Stream(&buf)).unwrap(), hash);
+
+    // Truncated input is an error rather than a reader polled forever.
+    assert!(matches!(
+        decode::<[u8; 32]>(&mut Stream(&buf[..31])),
+        Err(Error::ReaderOutOfData)
+    ));
+    assert!(matches!(
+        decode::<Vec<[u8; 32]>>(&mut Stream(&buf[32..90])),
+        Err(Error::ReaderOutOfData)
+    ));
+    assert!(matches!(
+        <[u8; 32]>::unpack(&mut Stream(&buf[..10])),
+        Err(Error::ReaderOutOfData)
+    ));
+}
+
 #[cfg(test)]
 #[derive(PartialEq, Debug)]
 struct NoDefault(u64);
@@ -1892,6 +2746,77 @@ fn test_nonzero_roundtrip() {
     assert_eq!(decoded_signed, signed);
 }
 
+#[test]
+fn test_decode_error_kinds() {
+    let mut buf = Vec::new();
+    (&[0xffu8, 0xfe][..]).encode(&mut buf).unwrap();
+    assert!(matches!(
+        String::decode(&mut Cursor::new(&buf)),
+        Err(Error::InvalidUtf8)
+    ));
+
+    // A compressed payload that is not a zstd frame.
+    let garbage = [(4 << 1) | 1, 1, 2, 3, 4];
+    let expected = if cfg!(feature = "zstd") {
+        DecompressionErrorKind::Corrupt
+    } else {
+        DecompressionErrorKind::Unsupported
+    };
+    assert!(matches!(
+        String::decode(&mut Cursor::new(&garbage[..])),
+        Err(Error::DecompressionFailed { kind }) if kind == expected
+    ));
+
+    #[cfg(feature = "zstd")]
+    {
+        let text = "lencode ".repeat(32);
+        let frame = bytes::zstd_compress(text.as_bytes()).unwrap();
+        let mut short = vec![0u8; text.len() - 1];
+        assert!(matches!(
+            bytes::zstd_decompress_into(&frame, &mut short),
+            Err(Error::DecompressionFailed { .. })
+        ));
+        let mut long = vec![0u8; text.len() + 1];
+        assert!(matches!(
+            bytes::zstd_decompress_into(&frame, &mut long),
+            Err(Error::LengthMismatch { expected, actual }) if expected == text.len() + 1 && actual == text.len()
+        ));
+    }
+}
+
+#[test]
+fn test_lossy_utf8_policy_keeps_the_rest_of_the_payload() {
+    let mut buf = Vec::new();
+    (&b"disk \xff full"[..]).encode(&mut buf).unwrap();
+    7u32.encode(&mut buf).unwrap();
+    assert!(matches!(
+        decode_ext::<(String, u32)>(&mut Cursor::new(&buf), Some(&mut DecoderContext::new())),
+        Err(Error::InvalidUtf8)
+    ));
+
+    let mut ctx = DecoderContext::new();
+    ctx.utf8 = Utf8Policy::Lossy;
+    let decoded: (String, u32) = decode_ext(&mut Cursor::new(&buf), Some(&mut ctx)).unwrap();
+    assert_eq!(decoded, (String::from("disk \u{fffd} full"), 7));
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn test_without_zstd_everything_is_written_raw() {
+    let text = "lencode ".repeat(32);
+    let mut buf = Vec::new();
+    let n = text.encode(&mut buf).unwrap();
+    assert_eq!(n, bytes::flagged_header_len(text.len(), false) + text.len());
+    assert_eq!(String::decode(&mut Cursor::new(&buf)).unwrap(), text);
+
+    let mut buf = Vec::new();
+    encode_with_profile(&text, &mut buf, &Profile::archival()).unwrap();
+    assert_eq!(
+        decode_with_profile::<String>(&mut Cursor::new(&buf), &Profile::archival()).unwrap(),
+        text
+    );
+}
+
 #[test]
 fn test_nonzero_decode_zero_fails() {
     let err: Result<NonZeroU16> = Decode::decode(&mut Cursor::new(&[0u8]));
@@ -1962,6 +2887,37 @@ fn test_hash_set_encode_decode() {
     assert_eq!(decoded, set);
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn test_borrowed_key_maps_encode_like_owned() {
+    let names = [String::from("alpha"), String::from("beta")];
+    let borrowed: std::collections::HashMap<&str, u32> = names
+        .iter()
+        .map(|name| (name.as_str(), name.len() as u32))
+        .collect();
+    let mut buf = Vec::new();
+    borrowed.encode(&mut buf).unwrap();
+    let owned: std::collections::HashMap<String, u32> =
+        Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(owned.len(), 2);
+    assert_eq!(owned["beta"], 4);
+
+    let keys: [&[u8]; 2] = [b"b", b"a"];
+    let borrowed: collections::BTreeMap<&[u8], u8> = keys.iter().map(|k| (*k, k[0])).collect();
+    let owned: collections::BTreeMap<Vec<u8>, u8> =
+        keys.iter().map(|k| (k.to_vec(), k[0])).collect();
+    let (mut a, mut b) = (Vec::new(), Vec::new());
+    borrowed.encode(&mut a).unwrap();
+    owned.encode(&mut b).unwrap();
+    assert_eq!(a, b);
+
+    let set: collections::BTreeSet<&str> = ["x", "y"].into_iter().collect();
+    let mut buf = Vec::new();
+    set.encode(&mut buf).unwrap();
+    let decoded: collections::BTreeSet<String> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert!(decoded.contains("y"));
+}
+
 #[test]
 fn test_btree_set_encode_decode() {
     let mut set = collections::BTreeSet::new();
@@ -2007,6 +2963,47 @@ fn test_linked_list_encode_decode() {
     assert_eq!(decoded, list);
 }
 
+#[cfg(test)]
+struct Overreporting(core::ops::Range<u32>);
+
+#[cfg(test)]
+impl Iterator for Overreporting {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        self.0.next()
+    }
+}
+
+#[cfg(test)]
+impl ExactSizeIterator for Overreporting {
+    fn len(&self) -> usize {
+        self.0.len() + 1
+    }
+}
+
+#[test]
+fn test_encode_from_iter() {
+    let mut map = collections::BTreeMap::new();
+    map.insert(1u32, 300u64);
+    map.insert(2, 5);
+
+    let mut buf = Vec::new();
+    let n = encode_from_iter(&mut buf, map.values().copied()).unwrap();
+    assert_eq!(n, buf.len());
+    let mut expected = Vec::new();
+    encode(&map.values().copied().collect::<Vec<_>>(), &mut expected).unwrap();
+    assert_eq!(buf, expected);
+    let decoded: Vec<u64> = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, [300, 5]);
+
+    let mut buf = Vec::new();
+    assert!(matches!(
+        encode_from_iter(&mut buf, Overreporting(0..2)),
+        Err(Error::IncorrectLength)
+    ));
+}
+
 #[test]
 fn test_binary_heap_encode_decode() {
     let mut heap = collections::BinaryHeap::new();
@@ -2088,6 +3085,7 @@ fn test_string_flag_raw_small_ascii() {
     assert_eq!(rt, s);
 }
 
+#[cfg(feature = "zstd")]
 #[test]
 fn test_string_flag_compressed_repetitive_ascii() {
     use crate::prelude::*;
@@ -2120,6 +3118,7 @@ fn test_string_flag_compressed_repetitive_ascii() {
     assert_eq!(rt, s);
 }
 
+#[cfg(feature = "zstd")]
 #[test]
 fn test_string_flag_compressed_unicode() {
     use crate::prelude::*;
@@ -2138,6 +3137,7 @@ fn test_string_flag_compressed_unicode() {
     assert_eq!(rt, s);
 }
 
+#[cfg(feature = "zstd")]
 #[test]
 fn test_string_flag_corrupted_compressed_payload_errors() {
     use crate::prelude::*;
@@ -2187,6 +3187,49 @@ fn test_bytes_flag_raw_for_small_incompressible_slice() {
     assert_eq!(rt, data);
 }
 
+#[cfg(feature = "zstd")]
+#[test]
+fn test_compression_policy_controls_flag() {
+    use crate::prelude::*;
+    fn encode_flag(value: &impl Encode, ctx: Option<&mut EncoderContext>) -> (u64, usize) {
+        let mut buf = Vec::new();
+        value.encode_ext(&mut buf, ctx).unwrap();
+        let flagged = Lencode::decode_varint_u64(&mut Cursor::new(&buf)).unwrap();
+        (flagged & 1, buf.len())
+    }
+    let text = "the quick brown fox ".repeat(200);
+    let short = "a".repeat(48);
+    let raw_len = text.len() + bytes::flagged_header_len(text.len(), false);
+
+    assert_eq!(encode_flag(&text, None).0, 1);
+    assert_eq!(encode_flag(&short, None).0, 0);
+
+    let mut never = EncoderContext::with_compression(CompressionPolicy::Never);
+    assert_eq!(encode_flag(&text, Some(&mut never)), (0, raw_len));
+    let mut always = EncoderContext::with_compression(CompressionPolicy::Always);
+    assert_eq!(encode_flag(&short, Some(&mut always)).0, 1);
+    let mut threshold = EncoderContext::with_compression(CompressionPolicy::SizeThreshold(8 << 10));
+    assert_eq!(encode_flag(&text.as_bytes(), Some(&mut threshold)).0, 0);
+
+    // The policy reaches payloads nested in containers, and the level is honored.
+    let nested = vec![text.clone().into_bytes(); 2];
+    let mut buf = Vec::new();
+    nested.encode_ext(&mut buf, Some(&mut never)).unwrap();
+    assert_eq!(buf.len(), 1 + 2 * raw_len);
+    assert_eq!(
+        decode::<Vec<Vec<u8>>>(&mut Cursor::new(&buf)).unwrap(),
+        nested
+    );
+    let mut archival = EncoderContext::new();
+    archival.compression_level = 19;
+    let (flag, len) = encode_flag(&text, Some(&mut archival));
+    assert!(flag == 1 && len <= encode_flag(&text, None).1);
+    let mut buf = Vec::new();
+    short.encode_ext(&mut buf, Some(&mut always)).unwrap();
+    assert_eq!(decode::<String>(&mut Cursor::new(&buf)).unwrap(), short);
+}
+
+#[cfg(feature = "zstd")]
 #[test]
 fn test_bytes_flag_compressed_for_repetitive_slice() {
     use crate::prelude::*;
@@ -2218,6 +3261,7 @@ fn test_bytes_flag_compressed_for_repetitive_slice() {
     assert_eq!(rt, data);
 }
 
+#[cfg(feature = "zstd")]
 #[test]
 fn test_vec_u8_flag_paths() {
     use crate::prelude::*;
@@ -2256,6 +3300,7 @@ fn test_vec_u8_flag_paths() {
     assert_eq!(rt2, comp);
 }
 
+#[cfg(feature = "zstd")]
 #[test]
 fn test_vecdeque_u8_flag_paths_roundtrip() {
     use crate::prelude::*;
@@ -2297,6 +3342,7 @@ fn test_vecdeque_u8_flag_paths_roundtrip() {
     assert_eq!(rt2, comp);
 }
 
+#[cfg(feature = "zstd")]
 #[test]
 fn test_bytes_flag_corrupted_compressed_payload_errors() {
     use crate::prelude::*;
@@ -2320,3 +3366,553 @@ fn test_bytes_flag_corrupted_compressed_payload_errors() {
         assert!(res.is_err());
     }
 }
+
+#[test]
+fn test_decode_extend_appends() {
+    let mut buf = Vec::new();
+    encode(&vec![1u32, 2, 3], &mut buf).unwrap();
+    encode(&vec![4u32, 5], &mut buf).unwrap();
+    let mut cursor = Cursor::new(&buf);
+    let mut out: Vec<u32> = Vec::with_capacity(8);
+    assert_eq!(decode_extend(&mut cursor, &mut out).unwrap(), 3);
+    assert_eq!(decode_extend(&mut cursor, &mut out).unwrap(), 2);
+    assert_eq!(out, vec![1, 2, 3, 4, 5]);
+
+    // A truncated message leaves `out` as it was.
+    buf.clear();
+    encode(&vec![6u32, 7, 8], &mut buf).unwrap();
+    buf.pop();
+    assert!(decode_extend(&mut Cursor::new(&buf), &mut out).is_err());
+    assert_eq!(out, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn test_decode_extend_depth_limit() {
+    let mut buf = Vec::new();
+    encode(&vec![vec![1u32]], &mut buf).unwrap();
+    let mut out: Vec<Vec<u32>> = Vec::new();
+    let mut ctx = DecoderContext::new();
+    ctx.max_depth = 1;
+    let res = decode_extend_ext(&mut Cursor::new(&buf), &mut out, Some(&mut ctx));
+    assert!(matches!(res, Err(Error::DepthLimitExceeded)));
+    assert!(out.is_empty());
+    assert_eq!(ctx.depth, 0);
+    assert_eq!(decode_extend(&mut Cursor::new(&buf), &mut out).unwrap(), 1);
+    assert_eq!(out, vec![vec![1]]);
+}
+
+#[test]
+fn test_decode_extend_forged_length_reserves_bounded() {
+    let mut buf = Vec::new();
+    Lencode::encode_varint_u64(1 << 40, &mut buf).unwrap();
+    let mut out: Vec<u64> = Vec::new();
+    assert!(decode_extend(&mut Cursor::new(&buf), &mut out).is_err());
+    assert!(out.capacity() <= prealloc_capacity::<u64>(usize::MAX));
+}
+
+#[test]
+fn test_decode_extend_bytes() {
+    /// A reader without a borrowed buffer.
+    struct Stream<'a>(&'a [u8]);
+
+    impl Read for Stream<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = buf.len().min(self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    let mut buf = Vec::new();
+    encode(&vec![7u8; 300], &mut buf).unwrap();
+    let mut out = vec![1u8];
+    assert_eq!(
+        decode_extend(&mut Cursor::new(&buf), &mut out).unwrap(),
+        300
+    );
+    assert_eq!(out.len(), 301);
+    assert_eq!(out[0], 1);
+    assert!(out[1..].iter().all(|&b| b == 7));
+
+    // Bytes land in the reserved capacity of `out` without reallocating it, whether read
+    // raw, compressed or through a reader without a buffer.
+    let repetitive = "ab".repeat(500).into_bytes();
+    buf.clear();
+    encode(&repetitive, &mut buf).unwrap();
+    let mut out = Vec::with_capacity(2048);
+    out.push(1u8);
+    let ptr = out.as_ptr();
+    decode_extend(&mut Cursor::new(&buf), &mut out).unwrap();
+    decode_extend(&mut Stream(&buf), &mut out).unwrap();
+    assert_eq!(out.as_ptr(), ptr);
+    assert_eq!(out.len(), 1 + 2 * repetitive.len());
+    assert_eq!(&out[1..1001], &repetitive[..]);
+    assert_eq!(&out[1001..], &repetitive[..]);
+
+    // A failed read truncates back to the original length.
+    buf.pop();
+    assert!(decode_extend(&mut Stream(&buf), &mut out).is_err());
+    assert_eq!(out.len(), 1 + 2 * repetitive.len());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_decode_into_map_merges() {
+    let mut src = std::collections::HashMap::new();
+    src.insert(1u32, 10u64);
+    src.insert(2, 20);
+    let mut buf = Vec::new();
+    encode(&src, &mut buf).unwrap();
+
+    let mut out = std::collections::HashMap::new();
+    out.insert(2u32, 0u64);
+    out.insert(3, 30);
+    assert_eq!(
+        decode_into_map(&mut Cursor::new(&buf), &mut out).unwrap(),
+        2
+    );
+    assert_eq!(out.len(), 3);
+    assert_eq!(out[&1], 10);
+    assert_eq!(out[&2], 20);
+    assert_eq!(out[&3], 30);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_hash_encoded_matches_wire_bytes() {
+    use core::hash::Hasher;
+    let value = (42u64, String::from("hash me"), vec![1u16, 2, 3]);
+    let mut buf = Vec::new();
+    let n = encode(&value, &mut buf).unwrap();
+
+    let mut streamed = std::collections::hash_map::DefaultHasher::new();
+    assert_eq!(hash_encoded(&value, &mut streamed).unwrap(), n);
+    let mut buffered = std::collections::hash_map::DefaultHasher::new();
+    buffered.write(&buf);
+    assert_eq!(streamed.finish(), buffered.finish());
+
+    let mut other = std::collections::hash_map::DefaultHasher::new();
+    hash_encoded(
+        &(43u64, String::from("hash me"), vec![1u16, 2, 3]),
+        &mut other,
+    )
+    .unwrap();
+    assert_ne!(other.finish(), streamed.finish());
+}
+
+#[test]
+fn test_btree_map_duplicate_key_policy() {
+    // Two entries with the same key: {1: 10, 1: 20}
+    let buf = [2u8, 1, 10, 1, 20];
+    let map: collections::BTreeMap<u8, u8> = decode(&mut Cursor::new(&buf[..])).unwrap();
+    assert_eq!(map.len(), 1);
+    assert_eq!(map[&1], 20);
+
+    let mut ctx = DecoderContext::strict();
+    let res: Result<collections::BTreeMap<u8, u8>> =
+        decode_ext(&mut Cursor::new(&buf[..]), Some(&mut ctx));
+    assert!(matches!(res, Err(Error::DuplicateKey)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_hash_map_duplicate_key_policy() {
+    let buf = [3u8, 1, 10, 2, 20, 1, 30];
+    let mut ctx = DecoderContext::new();
+    let map: std::collections::HashMap<u8, u8> =
+        decode_ext(&mut Cursor::new(&buf[..]), Some(&mut ctx)).unwrap();
+    assert_eq!(map[&1], 30);
+
+    ctx.duplicate_keys = DuplicateKeyPolicy::Reject;
+    let res: Result<std::collections::HashMap<u8, u8>> =
+        decode_ext(&mut Cursor::new(&buf[..]), Some(&mut ctx));
+    assert!(matches!(res, Err(Error::DuplicateKey)));
+
+    // `decode_into_map_ext` applies the same policy.
+    let mut out = std::collections::HashMap::<u8, u8>::new();
+    let res = decode_into_map_ext(&mut Cursor::new(&buf[..]), &mut out, Some(&mut ctx));
+    assert!(matches!(res, Err(Error::DuplicateKey)));
+    let mut out = std::collections::HashMap::new();
+    assert_eq!(
+        decode_into_map_ext(&mut Cursor::new(&buf[..]), &mut out, None).unwrap(),
+        3
+    );
+    assert_eq!(out[&1], 30);
+}
+
+#[test]
+fn test_decode_depth_limit() {
+    type Nested = Vec<Vec<Vec<Vec<u8>>>>;
+    let value: Nested = vec![vec![vec![vec![1, 2, 3]]]];
+    let mut buf = Vec::new();
+    encode(&value, &mut buf).unwrap();
+
+    let mut ctx = DecoderContext::new();
+    let decoded: Nested = decode_ext(&mut Cursor::new(&buf), Some(&mut ctx)).unwrap();
+    assert_eq!(decoded, value);
+    assert_eq!(ctx.depth, 0);
+
+    ctx.max_depth = 3;
+    let res: Result<Nested> = decode_ext(&mut Cursor::new(&buf), Some(&mut ctx));
+    assert!(matches!(res, Err(Error::DepthLimitExceeded)));
+    // Depth is unwound on failure so the context can be reused.
+    assert_eq!(ctx.depth, 0);
+}
+
+#[test]
+fn test_fixed_width_arrays_keep_bulk_decode_vec() {
+    assert!(<u8 as Decode>::FLAT_DECODE_VEC);
+    assert!(<[u8; 32] as Decode>::FLAT_DECODE_VEC);
+    assert!(<[u64; 4] as Decode>::FLAT_DECODE_VEC);
+    assert!(!<Vec<u8> as Decode>::FLAT_DECODE_VEC);
+    assert!(!<[Vec<u8>; 2] as Decode>::FLAT_DECODE_VEC);
+}
+
+#[test]
+fn test_decode_depth_limit_recursive_type() {
+    #[derive(Debug, PartialEq)]
+    struct Node(Vec<Node>);
+
+    impl Encode for Node {
+        fn encode_ext(
+            &self,
+            writer: &mut impl Write,
+            ctx: Option<&mut EncoderContext>,
+        ) -> Result<usize> {
+            self.0.encode_ext(writer, ctx)
+        }
+    }
+
+    impl Decode for Node {
+        fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+            Ok(Node(Vec::decode_ext(reader, ctx)?))
+        }
+    }
+
+    // Each level is a one-element vec: 10k nested levels would overflow the stack unguarded.
+    let buf = vec![1u8; 10_000];
+    let mut ctx = DecoderContext::new();
+    let res: Result<Node> = decode_ext(&mut Cursor::new(&buf), Some(&mut ctx));
+    assert!(matches!(res, Err(Error::DepthLimitExceeded)));
+    // Plain `decode` applies the default cap too.
+    let res: Result<Node> = decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::DepthLimitExceeded)));
+
+    let mut shallow = Node(Vec::new());
+    for _ in 0..DEFAULT_MAX_DEPTH - 1 {
+        shallow = Node(vec![shallow]);
+    }
+    let mut buf = Vec::new();
+    encode(&shallow, &mut buf).unwrap();
+    assert_eq!(decode::<Node>(&mut Cursor::new(&buf)).unwrap(), shallow);
+}
+
+#[test]
+fn test_cow_str_map_keys_dedupe() {
+    use borrow::Cow;
+    use collections::BTreeMap;
+    let maps: Vec<BTreeMap<Cow<'static, str>, u32>> = (0..16)
+        .map(|i| {
+            BTreeMap::from([
+                (Cow::Borrowed("max_connections"), i),
+                (Cow::Borrowed("timeout_ms"), i * 10),
+                (Cow::Owned(String::from("retry_limit")), 3),
+            ])
+        })
+        .collect();
+
+    let mut plain = Vec::new();
+    maps.encode(&mut plain).unwrap();
+    let decoded: Vec<BTreeMap<Cow<'static, str>, u32>> = decode(&mut Cursor::new(&plain)).unwrap();
+    assert_eq!(decoded, maps);
+
+    // Without a context the layout matches `String` keys.
+    let as_strings: Vec<BTreeMap<String, u32>> = decode(&mut Cursor::new(&plain)).unwrap();
+    assert_eq!(as_strings[0]["timeout_ms"], 0);
+
+    let mut enc = EncoderContext::with_dedupe();
+    let mut deduped = Vec::new();
+    encode_ext(&maps, &mut deduped, Some(&mut enc)).unwrap();
+    assert!(deduped.len() < plain.len() / 2);
+
+    let mut dec = DecoderContext::with_dedupe();
+    let decoded: Vec<BTreeMap<Cow<'static, str>, u32>> =
+        decode_ext(&mut Cursor::new(&deduped), Some(&mut dec)).unwrap();
+    assert_eq!(decoded, maps);
+}
+
+#[test]
+fn test_cast_same_is_checked() {
+    let bytes = vec![1u8, 2, 3];
+    let same: Vec<u8> = cast_same(bytes.clone()).unwrap();
+    assert_eq!(same, bytes);
+    assert!(matches!(
+        cast_same::<Vec<u8>, Vec<u16>>(bytes),
+        Err(Error::InvalidData)
+    ));
+}
+
+#[test]
+fn test_usize_narrowing_is_checked() {
+    // On this host every u64 fits; emulate a 32-bit target through the shared check.
+    let wide = u32::MAX as u64 + 1;
+    assert!(matches!(
+        narrow_unsigned(wide, u32::MAX as u64),
+        Err(Error::OutOfRange { value, max }) if value == wide as i128 && max == u32::MAX as i128
+    ));
+    assert_eq!(
+        narrow_unsigned(u32::MAX as u64, u32::MAX as u64).unwrap(),
+        u32::MAX as u64
+    );
+    assert!(matches!(
+        narrow_signed(i32::MIN as i64 - 1, i32::MAX as i64),
+        Err(Error::OutOfRange { .. })
+    ));
+    assert!(matches!(
+        narrow_signed(i32::MAX as i64 + 1, i32::MAX as i64),
+        Err(Error::OutOfRange { .. })
+    ));
+    assert_eq!(
+        narrow_signed(i32::MIN as i64, i32::MAX as i64).unwrap(),
+        i32::MIN as i64
+    );
+
+    let mut buf = Vec::new();
+    encode(&wide, &mut buf).unwrap();
+    let res: Result<usize> = decode(&mut Cursor::new(&buf));
+    if usize::BITS < 64 {
+        assert!(matches!(res, Err(Error::OutOfRange { .. })));
+    } else {
+        assert_eq!(res.unwrap() as u64, wide);
+    }
+    let mut buf = Vec::new();
+    encode(&i64::MIN, &mut buf).unwrap();
+    let res: Result<isize> = decode(&mut Cursor::new(&buf));
+    if isize::BITS < 64 {
+        assert!(matches!(res, Err(Error::OutOfRange { .. })));
+    } else {
+        assert_eq!(res.unwrap() as i64, i64::MIN);
+    }
+}
+
+#[test]
+fn test_forged_lengths_fail_without_huge_preallocation() {
+    // A claimed 2^40 elements followed by a single element's worth of input.
+    let mut buf = Vec::new();
+    Vec::<u64>::encode_len(1 << 40, &mut buf).unwrap();
+    7u64.encode(&mut buf).unwrap();
+    let mut ctx = DecoderContext::new();
+    let res: Result<Vec<u64>> = decode_ext(&mut Cursor::new(&buf), Some(&mut ctx));
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+    let res: Result<Vec<u64>> = decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+    let res: Result<collections::VecDeque<u64>> = decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+    let res: Result<collections::BinaryHeap<u64>> = decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+    let res: Result<Vec<[u8; 32]>> = decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+    assert_eq!(prealloc_capacity::<u64>(1 << 40), MAX_PREALLOC_BYTES / 8);
+    assert_eq!(prealloc_capacity::<u64>(10), 10);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_forged_byte_lengths_fail_on_streaming_readers() {
+    // `std::io::Read` sources have no borrowed buffer, so payloads are read incrementally.
+    let mut buf = Vec::new();
+    Vec::<u8>::encode_len(1 << 41, &mut buf).unwrap();
+    buf.extend_from_slice(b"short");
+    let res: Result<Vec<u8>> = decode(&mut std::io::Cursor::new(buf.clone()));
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+    let res: Result<String> = decode(&mut std::io::Cursor::new(buf.clone()));
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+    let res: Result<collections::VecDeque<u8>> = decode(&mut std::io::Cursor::new(buf));
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+}
+
+#[test]
+fn test_heap_and_list_byte_fast_path_matches_element_layout() {
+    let bytes: Vec<u8> = (0..=255u8).rev().collect();
+    let list: collections::LinkedList<u8> = bytes.iter().copied().collect();
+    let heap: collections::BinaryHeap<u8> = bytes.iter().copied().collect();
+
+    // Length prefix followed by one raw byte per element, exactly like the element loop.
+    let mut expected = Vec::new();
+    Vec::<u8>::encode_len(bytes.len(), &mut expected).unwrap();
+    expected.extend(list.iter().copied());
+    let mut buf = Vec::new();
+    assert_eq!(encode(&list, &mut buf).unwrap(), expected.len());
+    assert_eq!(buf, expected);
+    let decoded: collections::LinkedList<u8> = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, list);
+
+    let mut buf = Vec::new();
+    encode(&heap, &mut buf).unwrap();
+    assert_eq!(&buf[expected.len() - bytes.len()..], heap.as_slice());
+    let decoded: collections::BinaryHeap<u8> = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded.into_sorted_vec(), heap.into_sorted_vec());
+}
+
+#[test]
+fn test_fuzz_heap_and_list_decoders() {
+    // Mutated encodings and forged lengths must fail cleanly, never panic or over-allocate.
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let words: collections::BinaryHeap<u64> = (0..64).map(|i| i * 0x1_0001).collect();
+    let names: collections::LinkedList<String> = (1..16).map(|i| "name".repeat(i)).collect();
+    let mut seeds = [Vec::new(), Vec::new()];
+    encode(&words, &mut seeds[0]).unwrap();
+    encode(&names, &mut seeds[1]).unwrap();
+
+    for round in 0..2000 {
+        let mut input = seeds[round % seeds.len()].clone();
+        match next() % 3 {
+            0 => {
+                let at = next() as usize % input.len();
+                input[at] ^= (next() as u8) | 1;
+            }
+            1 => input.truncate(next() as usize % input.len()),
+            _ => {
+                // Replace the length prefix with a large forged one.
+                let mut forged = Vec::new();
+                Vec::<u8>::encode_len((next() as usize) >> (next() % 40), &mut forged).unwrap();
+                forged.extend_from_slice(&input[1..]);
+                input = forged;
+            }
+        }
+        let _ = decode::<collections::BinaryHeap<u64>>(&mut Cursor::new(&input));
+        let _ = decode::<collections::BinaryHeap<u8>>(&mut Cursor::new(&input));
+        let _ = decode::<collections::LinkedList<String>>(&mut Cursor::new(&input));
+        let _ = decode::<collections::LinkedList<u8>>(&mut Cursor::new(&input));
+    }
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_encode_compressed_whole_message() {
+    let ticks: Vec<(u32, u16, bool)> = (0..500).map(|i| (1_000 + i % 7, 3, i % 2 == 0)).collect();
+    let mut plain = Vec::new();
+    encode(&ticks, &mut plain).unwrap();
+    let mut packed = Vec::new();
+    let n = encode_compressed(&ticks, &mut packed).unwrap();
+    assert_eq!(n, packed.len());
+    assert!(packed.len() * 3 < plain.len());
+    assert_eq!(
+        decode_compressed::<Vec<(u32, u16, bool)>>(&mut Cursor::new(&packed)).unwrap(),
+        ticks
+    );
+
+    // Small messages stay raw: one header byte in front of the plain encoding.
+    let mut small = Vec::new();
+    encode_compressed(&7u8, &mut small).unwrap();
+    assert_eq!(small, [2, 7]);
+    assert!(matches!(
+        decode_compressed::<u8>(&mut Cursor::new(&[4u8, 7, 7][..])),
+        Err(Error::InvalidData)
+    ));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_pipeline_dedupes_before_compressing() {
+    use borrow::Cow;
+
+    let names = ["Tokenkeg", "ComputeBudget", "Vote111", "Stake111"];
+    let events: Vec<(u64, Cow<'static, str>)> = (0..400)
+        .map(|i| (i * 7919, Cow::Borrowed(names[i as usize % 4])))
+        .collect();
+    let mut deduped = Vec::new();
+    encode_ext(
+        &events,
+        &mut deduped,
+        Some(&mut EncoderContext::with_dedupe()),
+    )
+    .unwrap();
+    let mut piped = Vec::new();
+    let n = encode_pipeline(&events, &mut piped).unwrap();
+    assert_eq!(n, piped.len());
+    assert!(piped.len() < deduped.len());
+    assert_eq!(
+        decode_pipeline::<Vec<(u64, Cow<'static, str>)>>(&mut Cursor::new(&piped)).unwrap(),
+        events
+    );
+
+    // Every message carries its own table, so messages decode independently.
+    let mut stream = Vec::new();
+    encode_pipeline(&events[..10].to_vec(), &mut stream).unwrap();
+    encode_pipeline(&events[10..20].to_vec(), &mut stream).unwrap();
+    let mut reader = Cursor::new(&stream);
+    let first: Vec<(u64, Cow<'static, str>)> = decode_pipeline(&mut reader).unwrap();
+    let second: Vec<(u64, Cow<'static, str>)> = decode_pipeline(&mut reader).unwrap();
+    assert_eq!((&first[..], &second[..]), (&events[..10], &events[10..20]));
+
+    // Without the dedupe layer the IDs are misread as fresh values.
+    assert!(!matches!(
+        decode_compressed::<Vec<(u64, Cow<'static, str>)>>(&mut Cursor::new(&piped)),
+        Ok(decoded) if decoded == events
+    ));
+}
+
+#[test]
+fn test_decode_borrowed_points_into_input() {
+    use borrow::Cow;
+
+    let repetitive = "ab".repeat(500);
+    let value = (
+        String::from("alice"),
+        vec![1u8, 2, 3],
+        Some(vec![String::from("x"), String::from("yz")]),
+        repetitive.clone(),
+    );
+    let mut buf = Vec::new();
+    encode(&value, &mut buf).unwrap();
+    let range = buf.as_ptr_range();
+
+    let mut reader = SliceReader::new(&buf);
+    let name: &str = decode_borrowed_ext(&mut reader, None).unwrap();
+    let blob: &[u8] = decode_borrowed_ext(&mut reader, None).unwrap();
+    let tags: Option<Vec<&str>> = decode_borrowed_ext(&mut reader, None).unwrap();
+    assert_eq!((name, blob), ("alice", &[1u8, 2, 3][..]));
+    assert_eq!(tags, Some(vec!["x", "yz"]));
+    assert!(range.contains(&name.as_ptr()) && range.contains(&blob.as_ptr()));
+
+    // The long string was compressed: `&str` refuses it, `Cow` falls back to a copy.
+    let at = reader.position();
+    let err = decode_borrowed::<&str>(&buf[at..]);
+    assert!(matches!(err, Err(Error::NotBorrowable)));
+    let text: Cow<str> = decode_borrowed_ext(&mut reader, None).unwrap();
+    assert!(matches!(text, Cow::Owned(ref s) if *s == repetitive));
+    assert!(reader.remaining().is_empty());
+
+    let short: Cow<str> = decode_borrowed(&buf).unwrap();
+    assert!(matches!(short, Cow::Borrowed("alice")));
+}
+
+#[test]
+fn test_decode_borrowed_matches_decode() {
+    let value: Vec<Option<Vec<u8>>> = vec![Some(vec![7; 3]), None, Some(vec![])];
+    let mut buf = Vec::new();
+    encode(&value, &mut buf).unwrap();
+    let borrowed: Vec<Option<&[u8]>> = decode_borrowed(&buf).unwrap();
+    assert_eq!(borrowed, [Some(&[7u8; 3][..]), None, Some(&[][..])]);
+    let owned: Vec<Option<Vec<u8>>> = decode_borrowed(&buf).unwrap();
+    assert_eq!(owned, value);
+
+    let mut bad = Vec::new();
+    vec![0xffu8, 0xfe].encode(&mut bad).unwrap();
+    assert!(matches!(
+        decode_borrowed::<&str>(&bad),
+        Err(Error::InvalidUtf8)
+    ));
+    assert!(matches!(
+        decode_borrowed::<&str>(&bad[..2]),
+        Err(Error::ReaderOutOfData)
+    ));
+}
@@ -0,0 +1,120 @@
+//! Per‑field encode statistics.
+//!
+//! [`EncodeMetrics`] records how many bytes each field of each type contributed to the
+//! output. Enable it by setting [`EncoderContext::metrics`] (or using
+//! [`EncoderContext::with_metrics`]) and encoding with `encode_ext`. `#[derive(Encode)]`
+//! records every field automatically; hand‑written impls can call
+//! [`EncoderContext::record_field`]. Without a context, or with `metrics: None`, nothing is
+//! recorded.
+//!
+//! Counts are inclusive: a field holding a struct is charged for all of that struct's bytes,
+//! and the struct's own fields are recorded as well.
+use crate::prelude::*;
+
+/// Totals recorded for one field of one type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FieldStats {
+    /// Number of times the field was encoded.
+    pub count: u64,
+    /// Total bytes written for the field.
+    pub bytes: u64,
+}
+
+/// Bytes written per `(type, field)` pair. See the [module docs](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncodeMetrics {
+    fields: collections::BTreeMap<(&'static str, &'static str), FieldStats>,
+}
+
+impl EncodeMetrics {
+    /// Creates an empty collector.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            fields: collections::BTreeMap::new(),
+        }
+    }
+
+    /// Records `bytes` written for `field` of `type_name`.
+    #[inline]
+    pub fn record(&mut self, type_name: &'static str, field: &'static str, bytes: usize) {
+        let stats = self.fields.entry((type_name, field)).or_default();
+        stats.count += 1;
+        stats.bytes += bytes as u64;
+    }
+
+    /// Returns the totals for `field` of `type_name`, if it was recorded.
+    #[inline]
+    pub fn get(&self, type_name: &str, field: &str) -> Option<FieldStats> {
+        self.iter()
+            .find(|(ty, name, _)| *ty == type_name && *name == field)
+            .map(|(_, _, stats)| stats)
+    }
+
+    /// Iterates over `(type, field, stats)` ordered by type name, then field name.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'static str, FieldStats)> + '_ {
+        self.fields
+            .iter()
+            .map(|(&(ty, field), &stats)| (ty, field, stats))
+    }
+
+    /// Returns all recorded fields, largest byte total first.
+    pub fn by_bytes(&self) -> Vec<(&'static str, &'static str, FieldStats)> {
+        let mut out: Vec<_> = self.iter().collect();
+        out.sort_by_key(|&(_, _, stats)| core::cmp::Reverse(stats.bytes));
+        out
+    }
+
+    /// Returns the bytes written by all fields of `type_name`. Enum discriminants are not
+    /// attributed to any field and are therefore not included.
+    pub fn type_bytes(&self, type_name: &str) -> u64 {
+        self.iter()
+            .filter(|(ty, _, _)| *ty == type_name)
+            .map(|(_, _, stats)| stats.bytes)
+            .sum()
+    }
+
+    /// Returns `true` if nothing has been recorded.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Discards all recorded totals.
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.fields.clear();
+    }
+}
+
+#[test]
+fn test_encode_metrics_totals() {
+    let mut metrics = EncodeMetrics::new();
+    assert!(metrics.is_empty());
+    metrics.record("Meta", "fee", 2);
+    metrics.record("Meta", "logs", 120);
+    metrics.record("Meta", "fee", 3);
+    metrics.record("Balance", "amount", 9);
+    assert_eq!(
+        metrics.get("Meta", "fee"),
+        Some(FieldStats { count: 2, bytes: 5 })
+    );
+    assert_eq!(metrics.get("Meta", "missing"), None);
+    assert_eq!(metrics.type_bytes("Meta"), 125);
+    let ranked: Vec<_> = metrics.by_bytes().into_iter().map(|(_, f, _)| f).collect();
+    assert_eq!(ranked, ["logs", "amount", "fee"]);
+    metrics.clear();
+    assert!(metrics.is_empty());
+}
+
+#[test]
+fn test_record_field_without_metrics_is_noop() {
+    EncoderContext::record_field(None, "Meta", "fee", 1);
+    let mut ctx = EncoderContext::new();
+    EncoderContext::record_field(Some(&mut ctx), "Meta", "fee", 1);
+    assert!(ctx.metrics.is_none());
+    let mut ctx = EncoderContext::with_metrics();
+    EncoderContext::record_field(Some(&mut ctx), "Meta", "fee", 1);
+    assert_eq!(ctx.metrics.unwrap().type_bytes("Meta"), 1);
+}
@@ -0,0 +1,567 @@
+//! Channel‑tagged frames for carrying several lencode streams over one connection.
+//!
+//! Each frame is `varint channel | varint length | payload`. A [`MuxEncoder`] hands out
+//! [`Channel`] handles that encode one value per frame; a [`Demuxer`] reads frames back and
+//! dispatches each payload to the callback registered for its channel. Frames are
+//! length‑delimited, so channels with no callback are skipped without decoding them, and
+//! producers can add channels without breaking older consumers.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! const ACCOUNTS: usize = 0;
+//! const CONTROL: usize = 1;
+//!
+//! let mut mux = MuxEncoder::new(Vec::new());
+//! mux.channel(ACCOUNTS).send(&(7u64, 1_000u64)).unwrap();
+//! mux.channel(CONTROL).send(&String::from("ping")).unwrap();
+//! mux.channel(ACCOUNTS).send(&(8u64, 250u64)).unwrap();
+//! let buf = mux.into_inner();
+//!
+//! let mut balances = Vec::new();
+//! let mut control = Vec::new();
+//! let frames = Demuxer::new()
+//!     .on_decode(ACCOUNTS, |update: (u64, u64)| {
+//!         balances.push(update);
+//!         Ok(())
+//!     })
+//!     .on_decode(CONTROL, |msg: String| {
+//!         control.push(msg);
+//!         Ok(())
+//!     })
+//!     .run(&mut Cursor::new(&buf))
+//!     .unwrap();
+//! assert_eq!(frames, 3);
+//! assert_eq!(balances, [(7, 1_000), (8, 250)]);
+//! assert_eq!(control, ["ping"]);
+//! ```
+//!
+//! # Detecting lost frames
+//!
+//! A relay that drops frames (a full queue, a reconnect that lost its backlog) leaves the
+//! consumer looking at a stream that is merely quiet. With
+//! [`MuxEncoder::with_sequence_numbers`] every frame's payload starts with a varint sequence
+//! number, counting from zero across all channels, and [`MuxEncoder::heartbeat`] writes a
+//! frame on [`HEARTBEAT_CHANNEL`] carrying the next sequence number, so loss at the end of a
+//! burst shows up at the next heartbeat instead of at the next data frame. A
+//! [`Demuxer::with_sequence_numbers`] strips the numbers before dispatching and reports every
+//! discontinuity to the [`Demuxer::on_gap`] callback. Both ends must agree on the mode; a
+//! demuxer without it skips heartbeats like any unhandled channel.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! let mut mux = MuxEncoder::new(Vec::new()).with_sequence_numbers();
+//! for slot in 0..5u64 {
+//!     mux.channel(0).send(&slot).unwrap();
+//! }
+//! mux.heartbeat().unwrap();
+//! let buf = mux.into_inner();
+//!
+//! // Drop the frame for slot 2 (`0 | len | seq | value`, 4 bytes each) in transit.
+//! let relayed = [&buf[..8], &buf[12..]].concat();
+//!
+//! let mut gaps = Vec::new();
+//! let mut slots = Vec::new();
+//! Demuxer::new()
+//!     .with_sequence_numbers()
+//!     .on_decode(0, |slot: u64| {
+//!         slots.push(slot);
+//!         Ok(())
+//!     })
+//!     .on_gap(|gap| {
+//!         gaps.push(gap);
+//!         Ok(())
+//!     })
+//!     .run(&mut Cursor::new(&relayed))
+//!     .unwrap();
+//! assert_eq!(slots, [0, 1, 3, 4]);
+//! assert_eq!(gaps, [Gap { expected: 2, received: 3 }]);
+//! ```
+use hashbrown::HashMap;
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use std::boxed::Box;
+
+use crate::io::read_exact;
+use crate::layout::varint_size;
+use crate::prelude::*;
+
+/// The channel [`MuxEncoder::heartbeat`] frames are sent on. Its payload is the varint
+/// sequence number of the next data frame.
+pub const HEARTBEAT_CHANNEL: usize = u32::MAX as usize;
+
+/// Writes channel‑tagged frames to an underlying [`Write`].
+pub struct MuxEncoder<W: Write> {
+    inner: W,
+    /// Reused buffer for the value being framed.
+    scratch: Vec<u8>,
+    /// Whether data frames carry sequence numbers.
+    sequenced: bool,
+    /// The sequence number of the next data frame.
+    next_seq: u64,
+}
+
+impl<W: Write> MuxEncoder<W> {
+    /// Creates a multiplexer writing frames to `inner`.
+    #[inline(always)]
+    pub const fn new(inner: W) -> Self {
+        Self {
+            inner,
+            scratch: Vec::new(),
+            sequenced: false,
+            next_seq: 0,
+        }
+    }
+
+    /// Returns the multiplexer with sequence numbers on every data frame; see
+    /// [Detecting lost frames](self#detecting-lost-frames).
+    #[inline(always)]
+    pub const fn with_sequence_numbers(mut self) -> Self {
+        self.sequenced = true;
+        self
+    }
+
+    /// Returns the sequence number the next data frame will carry.
+    #[inline(always)]
+    pub const fn next_sequence(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Returns a handle that sends values on channel `id`.
+    #[inline(always)]
+    pub const fn channel(&mut self, id: usize) -> Channel<'_, W> {
+        Channel { mux: self, id }
+    }
+
+    /// Writes `payload` as a single frame on `channel`. Returns the number of bytes written,
+    /// including the frame header and, with sequence numbers on, the frame's number.
+    pub fn write_frame(&mut self, channel: usize, payload: &[u8]) -> Result<usize> {
+        if !self.sequenced {
+            return self.write_raw_frame(channel, payload, None);
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.write_raw_frame(channel, payload, Some(seq))
+    }
+
+    /// Writes a heartbeat frame on [`HEARTBEAT_CHANNEL`] carrying
+    /// [`next_sequence`](Self::next_sequence), so a consumer can tell a quiet stream from one
+    /// that lost its latest frames. Heartbeats do not consume a sequence number.
+    pub fn heartbeat(&mut self) -> Result<usize> {
+        let mut payload = core::mem::take(&mut self.scratch);
+        payload.clear();
+        let result = Lencode::encode_varint_u64(self.next_seq, &mut payload)
+            .and_then(|_| self.write_raw_frame(HEARTBEAT_CHANNEL, &payload, None));
+        self.scratch = payload;
+        result
+    }
+
+    fn write_raw_frame(
+        &mut self,
+        channel: usize,
+        payload: &[u8],
+        seq: Option<u64>,
+    ) -> Result<usize> {
+        let seq_len = seq.map_or(0, varint_size);
+        let mut total = Lencode::encode_varint_u64(channel as u64, &mut self.inner)?;
+        total += Lencode::encode_varint_u64((seq_len + payload.len()) as u64, &mut self.inner)?;
+        if let Some(seq) = seq {
+            total += Lencode::encode_varint_u64(seq, &mut self.inner)?;
+        }
+        total += write_all(&mut self.inner, payload)?;
+        Ok(total)
+    }
+
+    /// Returns a reference to the underlying writer.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Flushes the underlying writer.
+    #[inline(always)]
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying writer.
+    #[inline(always)]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// A handle to one channel of a [`MuxEncoder`], returned by [`MuxEncoder::channel`].
+pub struct Channel<'a, W: Write> {
+    mux: &'a mut MuxEncoder<W>,
+    id: usize,
+}
+
+impl<W: Write> Channel<'_, W> {
+    /// Returns the channel id.
+    #[inline(always)]
+    pub const fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Encodes `value` into a frame on this channel. Returns the number of bytes written,
+    /// including the frame header.
+    #[inline(always)]
+    pub fn send<T: Encode + ?Sized>(&mut self, value: &T) -> Result<usize> {
+        self.send_ext(value, None)
+    }
+
+    /// Like [`Channel::send`], encoding with an optional [`EncoderContext`].
+    ///
+    /// Frames are decoded independently, so a context shared across frames must be mirrored
+    /// by the receiving callback.
+    pub fn send_ext<T: Encode + ?Sized>(
+        &mut self,
+        value: &T,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut scratch = core::mem::take(&mut self.mux.scratch);
+        scratch.clear();
+        let result = value
+            .encode_ext(&mut scratch, ctx)
+            .and_then(|_| self.mux.write_frame(self.id, &scratch));
+        self.mux.scratch = scratch;
+        result
+    }
+}
+
+/// Reads a frame's channel and payload length, or returns `Ok(None)` if `reader` is exhausted
+/// at a frame boundary.
+pub(crate) fn read_frame_header(reader: &mut impl Read) -> Result<Option<(usize, usize)>> {
+    let mut first = 0u8;
+    match reader.read(core::slice::from_mut(&mut first)) {
+        Ok(0) | Err(Error::ReaderOutOfData) => return Ok(None),
+        Ok(_) => {}
+        Err(err) => return Err(err),
+    }
+    let channel = checked_usize(Lencode::decode_varint_u64(&mut Prefixed {
+        first: Some(first),
+        inner: reader,
+    })?)?;
+    let len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    Ok(Some((channel, len)))
+}
+
+/// Reads a `len`‑byte frame payload into `buf`, growing it in bounded steps so a corrupt
+/// length cannot allocate more than the stream holds.
+pub(crate) fn read_frame_payload(
+    reader: &mut impl Read,
+    len: usize,
+    buf: &mut Vec<u8>,
+) -> Result<()> {
+    buf.clear();
+    let mut filled = 0;
+    while filled < len {
+        let step = (len - filled).min(MAX_PREALLOC_BYTES);
+        buf.resize(filled + step, 0);
+        read_exact(reader, &mut buf[filled..])?;
+        filled += step;
+    }
+    Ok(())
+}
+
+type Handler<'a> = Box<dyn FnMut(&[u8]) -> Result<()> + 'a>;
+
+/// A discontinuity in frame sequence numbers, reported to [`Demuxer::on_gap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Gap {
+    /// The sequence number that should have come next.
+    pub expected: u64,
+    /// The sequence number that arrived (or that a heartbeat announced as next). Below
+    /// `expected` when the producer restarted or frames were replayed.
+    pub received: u64,
+}
+
+impl Gap {
+    /// Returns how many frames were lost, or 0 if the sequence went backwards.
+    #[inline(always)]
+    pub const fn lost(&self) -> u64 {
+        self.received.saturating_sub(self.expected)
+    }
+}
+
+/// Reads channel‑tagged frames and dispatches each payload to its channel's callback.
+///
+/// Frames on channels without a callback are skipped.
+#[derive(Default)]
+pub struct Demuxer<'a> {
+    handlers: HashMap<usize, Handler<'a>>,
+    /// Reused buffer for the payload being dispatched.
+    scratch: Vec<u8>,
+    /// Whether data frames carry sequence numbers.
+    sequenced: bool,
+    /// The sequence number of the next data frame, once one has been seen.
+    expected: Option<u64>,
+    on_gap: Option<Box<dyn FnMut(Gap) -> Result<()> + 'a>>,
+    on_heartbeat: Option<Box<dyn FnMut(u64) -> Result<()> + 'a>>,
+    lost: u64,
+}
+
+impl<'a> Demuxer<'a> {
+    /// Creates a demuxer with no callbacks.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expects sequence numbers on data frames, as written by
+    /// [`MuxEncoder::with_sequence_numbers`]. Numbering is checked from the first frame
+    /// read, so a consumer can join a stream midway.
+    #[inline(always)]
+    pub const fn with_sequence_numbers(&mut self) -> &mut Self {
+        self.sequenced = true;
+        self
+    }
+
+    /// Registers `handler` for discontinuities in the frame sequence. An error it returns is
+    /// passed through by [`dispatch`](Self::dispatch).
+    pub fn on_gap(&mut self, handler: impl FnMut(Gap) -> Result<()> + 'a) -> &mut Self {
+        self.on_gap = Some(Box::new(handler));
+        self
+    }
+
+    /// Registers `handler` for heartbeats; it receives the sequence number the producer will
+    /// use next. Useful for tracking when the producer was last heard from.
+    pub fn on_heartbeat(&mut self, handler: impl FnMut(u64) -> Result<()> + 'a) -> &mut Self {
+        self.on_heartbeat = Some(Box::new(handler));
+        self
+    }
+
+    /// Returns the number of frames reported lost so far.
+    #[inline(always)]
+    pub const fn lost_frames(&self) -> u64 {
+        self.lost
+    }
+
+    /// Registers `handler` for the raw payloads of `channel`, replacing any previous one.
+    pub fn on(
+        &mut self,
+        channel: usize,
+        handler: impl FnMut(&[u8]) -> Result<()> + 'a,
+    ) -> &mut Self {
+        self.handlers.insert(channel, Box::new(handler));
+        self
+    }
+
+    /// Registers `handler` for values sent on `channel` with [`Channel::send`].
+    ///
+    /// A payload that does not decode to exactly one `T` fails dispatch with
+    /// [`Error::InvalidData`].
+    pub fn on_decode<T: Decode>(
+        &mut self,
+        channel: usize,
+        mut handler: impl FnMut(T) -> Result<()> + 'a,
+    ) -> &mut Self {
+        self.on(channel, move |payload| {
+            let mut cursor = Cursor::new(payload);
+            let value = T::decode(&mut cursor)?;
+            if cursor.position() != payload.len() {
+                return Err(Error::InvalidData);
+            }
+            handler(value)
+        })
+    }
+
+    /// Reads one frame and dispatches it. Returns the frame's channel, or `Ok(None)` if
+    /// `reader` is exhausted at a frame boundary.
+    ///
+    /// Errors returned by the callback are passed through.
+    pub fn dispatch(&mut self, reader: &mut impl Read) -> Result<Option<usize>> {
+        let Some((channel, mut len)) = read_frame_header(reader)? else {
+            return Ok(None);
+        };
+        if self.sequenced {
+            if channel == HEARTBEAT_CHANNEL {
+                read_frame_payload(reader, len, &mut self.scratch)?;
+                let mut payload = Cursor::new(&self.scratch[..]);
+                let next = Lencode::decode_varint_u64(&mut payload)?;
+                if payload.position() != len {
+                    return Err(Error::InvalidData);
+                }
+                self.check_sequence(next)?;
+                if let Some(handler) = self.on_heartbeat.as_mut() {
+                    handler(next)?;
+                }
+                return Ok(Some(channel));
+            }
+            let seq = Lencode::decode_varint_u64(reader)?;
+            len = len
+                .checked_sub(varint_size(seq))
+                .ok_or(Error::InvalidData)?;
+            self.check_sequence(seq)?;
+            self.expected = Some(seq.wrapping_add(1));
+        }
+        let Some(handler) = self.handlers.get_mut(&channel) else {
+            skip_bytes(reader, len)?;
+            return Ok(Some(channel));
+        };
+        read_frame_payload(reader, len, &mut self.scratch)?;
+        handler(&self.scratch)?;
+        Ok(Some(channel))
+    }
+
+    /// Compares `seq` with the expected next sequence number, reporting any gap, and makes it
+    /// the expected one.
+    fn check_sequence(&mut self, seq: u64) -> Result<()> {
+        let expected = self.expected.replace(seq);
+        match expected {
+            Some(expected) if expected != seq => {
+                let gap = Gap {
+                    expected,
+                    received: seq,
+                };
+                self.lost += gap.lost();
+                match self.on_gap.as_mut() {
+                    Some(handler) => handler(gap),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Dispatches frames until `reader` is exhausted. Returns the number of frames read.
+    pub fn run(&mut self, reader: &mut impl Read) -> Result<usize> {
+        let mut frames = 0;
+        while self.dispatch(reader)?.is_some() {
+            frames += 1;
+        }
+        Ok(frames)
+    }
+}
+
+#[test]
+fn test_mux_skips_unhandled_channels() {
+    let mut mux = MuxEncoder::new(Vec::new());
+    let bytes = mux.channel(3).send(&[9u8; 300]).unwrap();
+    assert_eq!(bytes, mux.get_ref().len());
+    mux.channel(300).send(&42u32).unwrap();
+    mux.write_frame(1, &[]).unwrap();
+    let buf = mux.into_inner();
+
+    let mut seen = Vec::new();
+    let mut empty = 0;
+    let mut demux = Demuxer::new();
+    demux
+        .on_decode(300, |value: u32| {
+            seen.push(value);
+            Ok(())
+        })
+        .on(1, |payload| {
+            assert!(payload.is_empty());
+            empty += 1;
+            Ok(())
+        });
+    let mut cursor = Cursor::new(&buf);
+    assert_eq!(demux.dispatch(&mut cursor).unwrap(), Some(3));
+    assert_eq!(demux.dispatch(&mut cursor).unwrap(), Some(300));
+    assert_eq!(demux.dispatch(&mut cursor).unwrap(), Some(1));
+    assert_eq!(demux.dispatch(&mut cursor).unwrap(), None);
+    drop(demux);
+    assert_eq!(seen, [42]);
+    assert_eq!(empty, 1);
+}
+
+#[test]
+fn test_demux_rejects_truncated_and_trailing_frames() {
+    let mut mux = MuxEncoder::new(Vec::new());
+    mux.channel(0).send(&1_000_000u64).unwrap();
+    let buf = mux.into_inner();
+    let mut demux = Demuxer::new();
+    demux.on_decode(0, |_: u64| Ok(()));
+    assert!(matches!(
+        demux.run(&mut Cursor::new(&buf[..buf.len() - 1])),
+        Err(Error::ReaderOutOfData)
+    ));
+
+    let mut mux = MuxEncoder::new(Vec::new());
+    mux.write_frame(0, &[1, 2]).unwrap();
+    let buf = mux.into_inner();
+    let mut demux = Demuxer::new();
+    demux.on_decode(0, |_: u8| Ok(()));
+    assert!(matches!(
+        demux.run(&mut Cursor::new(&buf)),
+        Err(Error::InvalidData)
+    ));
+}
+
+#[test]
+fn test_sequence_gaps_and_heartbeats() {
+    let mut mux = MuxEncoder::new(Vec::new()).with_sequence_numbers();
+    let mut frames = Vec::new();
+    for i in 0..6u64 {
+        let start = mux.get_ref().len();
+        mux.channel(i as usize % 2).send(&(i * 1_000)).unwrap();
+        frames.push(mux.get_ref()[start..].to_vec());
+    }
+    assert_eq!(mux.next_sequence(), 6);
+    let start = mux.get_ref().len();
+    assert_eq!(mux.heartbeat().unwrap(), 5 + 1 + 1);
+    frames.push(mux.get_ref()[start..].to_vec());
+
+    // Frames 1 (unhandled channel) and 4, 5 (lost before the heartbeat) go missing, and frame
+    // 3 arrives twice.
+    let relayed = [0, 2, 3, 3, 6].map(|i| &frames[i][..]).concat();
+    let mut values = Vec::new();
+    let mut gaps = Vec::new();
+    let mut heartbeats = Vec::new();
+    let mut demux = Demuxer::new();
+    demux
+        .with_sequence_numbers()
+        .on_decode(0, |v: u64| {
+            values.push(v);
+            Ok(())
+        })
+        .on_gap(|gap| {
+            gaps.push(gap);
+            Ok(())
+        })
+        .on_heartbeat(|next| {
+            heartbeats.push(next);
+            Ok(())
+        });
+    assert_eq!(demux.run(&mut Cursor::new(&relayed)).unwrap(), 5);
+    assert_eq!(demux.lost_frames(), 1 + 2);
+    drop(demux);
+    assert_eq!(values, [0, 2000]);
+    assert_eq!(
+        gaps,
+        [
+            Gap {
+                expected: 1,
+                received: 2
+            },
+            Gap {
+                expected: 4,
+                received: 3
+            },
+            Gap {
+                expected: 4,
+                received: 6
+            },
+        ]
+    );
+    assert_eq!(gaps[1].lost(), 0);
+    assert_eq!(heartbeats, [6]);
+
+    // A gap handler can stop the stream.
+    let mut demux = Demuxer::new();
+    demux
+        .with_sequence_numbers()
+        .on_gap(|_| Err(Error::InvalidData));
+    let mut reader = Cursor::new(&relayed);
+    assert_eq!(demux.dispatch(&mut reader).unwrap(), Some(0));
+    assert!(matches!(
+        demux.dispatch(&mut reader),
+        Err(Error::InvalidData)
+    ));
+}
@@ -1,3 +1,5 @@
+use core::hash::Hash;
+
 use crate::prelude::*;
 
 /// Implemented on types that can be packed into a platform‑independent byte‑stream.
@@ -13,6 +15,8 @@ use crate::prelude::*;
 /// Use `#[derive(Pack)]` to auto‑implement this trait. For `#[repr(transparent)]`
 /// single‑field structs, the derive macro generates optimized `pack_slice`/`unpack_vec`
 /// overrides automatically.
+///
+/// Built‑in impls are little‑endian; see [`PackBe`] and [`Be`] for big‑endian layouts.
 pub trait Pack: Sized {
     /// Writes `self` to `writer` using a stable, platform‑independent layout.
     ///
@@ -54,7 +58,7 @@ pub trait Pack: Sized {
     /// impl, so overriding this automatically speeds up `Vec<Self>` decoding.
     #[inline(always)]
     fn unpack_vec(reader: &mut impl Read, count: usize) -> Result<Vec<Self>> {
-        let mut vec = Vec::with_capacity(count);
+        let mut vec = Vec::with_capacity(prealloc_capacity::<Self>(count));
         for _ in 0..count {
             vec.push(Self::unpack(reader)?);
         }
@@ -78,7 +82,7 @@ impl<const N: usize, T: Pack + 'static> Pack for [T; N] {
                 writer.advance_mut(N);
                 return Ok(N);
             }
-            return writer.write(bytes);
+            return write_whole(writer, bytes);
         }
         let mut total_bytes = 0;
         for item in self.iter() {
@@ -91,7 +95,9 @@ impl<const N: usize, T: Pack + 'static> Pack for [T; N] {
     fn unpack(reader: &mut impl Read) -> Result<Self> {
         // Fast path: bulk copy for u8 arrays
         if core::any::TypeId::of::<T>() == core::any::TypeId::of::<u8>() {
-            let mut arr: core::mem::MaybeUninit<[T; N]> = core::mem::MaybeUninit::uninit();
+            // Zeroed rather than uninitialized: `T` is `u8`, so zero is a valid value, and the
+            // fallback below hands the buffer to `Read::read`, which may inspect it.
+            let mut arr = core::mem::MaybeUninit::<[T; N]>::zeroed();
             if let Some(buf) = reader.buf() {
                 if buf.len() >= N {
                     unsafe {
@@ -108,10 +114,7 @@ impl<const N: usize, T: Pack + 'static> Pack for [T; N] {
             }
             // Fallback: read through the trait
             let dst = unsafe { core::slice::from_raw_parts_mut(arr.as_mut_ptr() as *mut u8, N) };
-            let mut read = 0;
-            while read < N {
-                read += reader.read(&mut dst[read..])?;
-            }
+            crate::io::read_exact(reader, dst)?;
             return Ok(unsafe { arr.assume_init() });
         }
 
@@ -131,7 +134,7 @@ impl<const N: usize, T: Pack + 'static> Pack for [T; N] {
             let total = N * items.len();
             let bytes: &[u8] =
                 unsafe { core::slice::from_raw_parts(items.as_ptr() as *const u8, total) };
-            return writer.write(bytes);
+            return write_whole(writer, bytes);
         }
         let mut total = 0;
         for item in items {
@@ -143,7 +146,7 @@ impl<const N: usize, T: Pack + 'static> Pack for [T; N] {
     #[inline(always)]
     fn unpack_vec(reader: &mut impl Read, count: usize) -> Result<Vec<Self>> {
         if core::any::TypeId::of::<T>() == core::any::TypeId::of::<u8>() {
-            let total = N * count;
+            let total = N.checked_mul(count).ok_or(Error::InvalidData)?;
             if let Some(buf) = reader.buf() {
                 if buf.len() >= total {
                     let mut vec: Vec<Self> = Vec::with_capacity(count);
@@ -160,18 +163,20 @@ impl<const N: usize, T: Pack + 'static> Pack for [T; N] {
                 }
                 return Err(Error::ReaderOutOfData);
             }
-            // Fallback: read through trait
-            let mut vec: Vec<Self> = Vec::with_capacity(count);
-            let dst =
-                unsafe { core::slice::from_raw_parts_mut(vec.as_mut_ptr() as *mut u8, total) };
-            let mut read = 0;
-            while read < total {
-                read += reader.read(&mut dst[read..])?;
+            // Fallback: read through trait. Counts beyond the preallocation limit take the
+            // per-element path below so the buffer grows only as data arrives.
+            if total <= MAX_PREALLOC_BYTES {
+                let mut vec: Vec<Self> = Vec::with_capacity(count);
+                let dst = unsafe {
+                    core::ptr::write_bytes(vec.as_mut_ptr() as *mut u8, 0, total);
+                    core::slice::from_raw_parts_mut(vec.as_mut_ptr() as *mut u8, total)
+                };
+                crate::io::read_exact(reader, dst)?;
+                unsafe { vec.set_len(count) };
+                return Ok(vec);
             }
-            unsafe { vec.set_len(count) };
-            return Ok(vec);
         }
-        let mut vec = Vec::with_capacity(count);
+        let mut vec = Vec::with_capacity(prealloc_capacity::<Self>(count));
         for _ in 0..count {
             vec.push(Self::unpack(reader)?);
         }
@@ -205,7 +210,7 @@ macro_rules! impl_pack_for_endianness_types {
             impl $crate::pack::Pack for $t {
                 #[inline(always)]
                 fn pack(&self, writer: &mut impl $crate::io::Write) -> $crate::Result<usize> {
-                    writer.write(&endian_cast::Endianness::le_bytes(self))
+                    $crate::io::write_whole(writer, &endian_cast::Endianness::le_bytes(self))
                 }
 
                 fn unpack(reader: &mut impl $crate::io::Read) -> $crate::Result<Self> {
@@ -239,6 +244,97 @@ impl_pack_for_endianness_types!(
     u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
 );
 
+/// Big‑endian counterpart of [`Pack`], for exchanging fixed layouts with network formats.
+///
+/// [`Pack`] always writes little‑endian. `PackBe` gives the same types a big‑endian layout
+/// that hand‑written [`Pack`] impls can call field by field, and [`Be<T>`] wraps a value so
+/// it packs big‑endian wherever a [`Pack`] type is expected (including `#[derive(Pack)]`
+/// fields and the dedupe table).
+pub trait PackBe: Sized {
+    /// Writes `self` most significant byte first.
+    fn pack_be(&self, writer: &mut impl Write) -> Result<usize>;
+    /// Reads a value written by [`PackBe::pack_be`].
+    fn unpack_be(reader: &mut impl Read) -> Result<Self>;
+}
+
+macro_rules! impl_pack_be {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl PackBe for $t {
+                #[inline(always)]
+                fn pack_be(&self, writer: &mut impl Write) -> Result<usize> {
+                    write_whole(writer, &self.to_be_bytes())
+                }
+
+                #[inline(always)]
+                fn unpack_be(reader: &mut impl Read) -> Result<Self> {
+                    let mut bytes = [0u8; core::mem::size_of::<$t>()];
+                    crate::io::read_exact(reader, &mut bytes)?;
+                    Ok(<$t>::from_be_bytes(bytes))
+                }
+            }
+        )+
+    };
+}
+
+impl_pack_be!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+
+impl<const N: usize, T: PackBe> PackBe for [T; N] {
+    #[inline(always)]
+    fn pack_be(&self, writer: &mut impl Write) -> Result<usize> {
+        let mut total_bytes = 0;
+        for item in self.iter() {
+            total_bytes += item.pack_be(writer)?;
+        }
+        Ok(total_bytes)
+    }
+
+    #[inline(always)]
+    fn unpack_be(reader: &mut impl Read) -> Result<Self> {
+        crate::decode_array_with(|| T::unpack_be(reader))
+    }
+}
+
+/// Wraps a [`PackBe`] value so its [`Pack`] impl writes big‑endian.
+///
+/// `Be<T>` is also [`DedupeEncodeable`]/[`DedupeDecodeable`], so it encodes as its fixed
+/// big‑endian bytes and participates in deduplication like any other packed type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Be<T>(pub T);
+
+impl<T> Be<T> {
+    /// Consumes the wrapper and returns the inner value.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Be<T> {
+    #[inline(always)]
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: PackBe> Pack for Be<T> {
+    #[inline(always)]
+    fn pack(&self, writer: &mut impl Write) -> Result<usize> {
+        self.0.pack_be(writer)
+    }
+
+    #[inline(always)]
+    fn unpack(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self(T::unpack_be(reader)?))
+    }
+}
+
+impl<T: PackBe + Hash + Eq + Clone + Send + Sync + 'static> DedupeEncodeable for Be<T> {}
+impl<T: PackBe + Hash + Eq + Clone + Send + Sync + 'static> DedupeDecodeable for Be<T> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1036,3 +1132,37 @@ fn test_nested_array_concepts() {
     let unpacked: [u64; 8] = <[u64; 8]>::unpack(&mut read_cursor).unwrap();
     assert_eq!(unpacked, original);
 }
+
+#[test]
+fn test_pack_be_layout_and_wrapper() {
+    let mut buf = Vec::new();
+    0x0102_0304u32.pack_be(&mut buf).unwrap();
+    (-2i16).pack_be(&mut buf).unwrap();
+    [0x0a0bu16, 0x0c0d].pack_be(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3, 4, 0xff, 0xfe, 0x0a, 0x0b, 0x0c, 0x0d]);
+
+    let mut cursor = Cursor::new(&buf);
+    assert_eq!(u32::unpack_be(&mut cursor).unwrap(), 0x0102_0304);
+    assert_eq!(i16::unpack_be(&mut cursor).unwrap(), -2);
+    assert_eq!(
+        <[u16; 2]>::unpack_be(&mut cursor).unwrap(),
+        [0x0a0b, 0x0c0d]
+    );
+    assert!(matches!(
+        u8::unpack_be(&mut cursor),
+        Err(Error::ReaderOutOfData)
+    ));
+
+    // `Be<T>` goes through the dedupe machinery with its big-endian bytes.
+    let ports = vec![Be(8080u16), Be(443), Be(8080), Be(8080)];
+    let mut enc = EncoderContext::with_dedupe();
+    let mut encoded = Vec::new();
+    encode_ext(&ports, &mut encoded, Some(&mut enc)).unwrap();
+    let mut dec = DecoderContext::with_dedupe();
+    let decoded: Vec<Be<u16>> = decode_ext(&mut Cursor::new(&encoded), Some(&mut dec)).unwrap();
+    assert_eq!(decoded, ports);
+
+    let mut packed = Vec::new();
+    Be(1.5f64).pack(&mut packed).unwrap();
+    assert_eq!(packed, 1.5f64.to_be_bytes());
+}
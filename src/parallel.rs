@@ -0,0 +1,162 @@
+//! Parallel decoding of independent frames (`rayon` feature).
+//!
+//! An archive written as a sequence of [`LengthPrefixed`] frames can be decoded on every
+//! core: [`split_frames`] walks the size headers once, sequentially, to find where each
+//! frame starts, and [`decode_frames_par`] then decodes the frames on the rayon thread pool
+//! and returns the values in their original order.
+//!
+//! Frames must not depend on each other. A [`DedupeEncoder`] normally carries its table from
+//! one value to the next, so either encode without a context, or use
+//! [`encode_frame_batches`], which resets the table every `batch_len` frames, and decode with
+//! [`decode_frame_batches_par`]: each batch gets a fresh [`DecoderContext`] and is decoded on
+//! one thread, and the batches run in parallel.
+//!
+//! ```
+//! use lencode::prelude::*;
+//! use std::borrow::Cow;
+//!
+//! let records: Vec<(u64, Cow<'static, str>)> =
+//!     (0..1000).map(|i| (i, Cow::Borrowed(["ok", "retry"][i as usize % 2]))).collect();
+//!
+//! let mut archive = Vec::new();
+//! let mut ctx = EncoderContext::with_dedupe();
+//! encode_frame_batches(&records, 64, &mut archive, &mut ctx).unwrap();
+//!
+//! let decoded: Vec<(u64, Cow<'static, str>)> =
+//!     decode_frame_batches_par(&archive, 64, DecoderContext::with_dedupe).unwrap();
+//! assert_eq!(decoded, records);
+//! ```
+use rayon::prelude::*;
+
+use crate::prelude::*;
+
+/// Splits `buf` into its [`LengthPrefixed`] frames, each slice including its size header.
+///
+/// Fails with [`Error::ReaderOutOfData`] if the last frame is cut short.
+pub fn split_frames(buf: &[u8]) -> Result<Vec<&[u8]>> {
+    let mut frames = Vec::new();
+    let mut cursor = Cursor::new(buf);
+    while cursor.position() < buf.len() {
+        let start = cursor.position();
+        skip_length_prefixed(&mut cursor)?;
+        frames.push(&buf[start..cursor.position()]);
+    }
+    Ok(frames)
+}
+
+/// Decodes one frame produced by [`split_frames`].
+#[inline(always)]
+fn decode_frame<T: Decode>(frame: &[u8], ctx: Option<&mut DecoderContext>) -> Result<T> {
+    decode_length_prefixed(&mut Cursor::new(frame), ctx, |body, ctx| {
+        T::decode_ext(body, ctx)
+    })
+}
+
+/// Writes each of `values` as a [`LengthPrefixed`] frame.
+///
+/// Every `batch_len` frames (every frame if zero) the dedupe and diff state of `ctx` is
+/// cleared, so each batch can be decoded on its own by [`decode_frame_batches_par`].
+pub fn encode_frame_batches<'a, T: Encode + 'a>(
+    values: impl IntoIterator<Item = &'a T>,
+    batch_len: usize,
+    writer: &mut impl Write,
+    ctx: &mut EncoderContext,
+) -> Result<usize> {
+    let batch_len = batch_len.max(1);
+    let mut total = 0;
+    for (i, value) in values.into_iter().enumerate() {
+        if i % batch_len == 0 {
+            if let Some(dedupe) = ctx.dedupe.as_mut() {
+                dedupe.clear();
+            }
+            if let Some(diff) = ctx.diff.as_mut() {
+                diff.clear();
+            }
+        }
+        total +=
+            encode_length_prefixed(writer, Some(ctx), |body, ctx| value.encode_ext(body, ctx))?;
+    }
+    Ok(total)
+}
+
+/// Decodes every [`LengthPrefixed`] frame in `buf` in parallel, without a context, and
+/// returns the values in order.
+///
+/// If several frames fail to decode, which of their errors is returned is unspecified.
+pub fn decode_frames_par<T: Decode + Send>(buf: &[u8]) -> Result<Vec<T>> {
+    split_frames(buf)?
+        .into_par_iter()
+        .map(|frame| decode_frame(frame, None))
+        .collect()
+}
+
+/// Decodes frames written by [`encode_frame_batches`] with the same `batch_len`, and
+/// returns the values in order.
+///
+/// Each batch is decoded sequentially with a context from `new_ctx`, and the batches run in
+/// parallel. If several frames fail to decode, which of their errors is returned is
+/// unspecified.
+pub fn decode_frame_batches_par<T: Decode + Send>(
+    buf: &[u8],
+    batch_len: usize,
+    new_ctx: impl Fn() -> DecoderContext + Sync,
+) -> Result<Vec<T>> {
+    let frames = split_frames(buf)?;
+    let batches: Vec<Vec<T>> = frames
+        .par_chunks(batch_len.max(1))
+        .map(|batch| {
+            let mut ctx = new_ctx();
+            batch
+                .iter()
+                .map(|frame| decode_frame(frame, Some(&mut ctx)))
+                .collect()
+        })
+        .collect::<Result<_>>()?;
+    Ok(batches.into_iter().flatten().collect())
+}
+
+#[test]
+fn test_frames_decode_in_parallel_in_order() {
+    let values: Vec<(u32, String)> = (0..2000).map(|i| (i, "x".repeat(i as usize % 7))).collect();
+    let mut buf = Vec::new();
+    for value in &values {
+        encode(&LengthPrefixed(value.clone()), &mut buf).unwrap();
+    }
+    assert_eq!(split_frames(&buf).unwrap().len(), values.len());
+    assert_eq!(decode_frames_par::<(u32, String)>(&buf).unwrap(), values);
+
+    assert!(matches!(
+        decode_frames_par::<(u32, String)>(&buf[..buf.len() - 1]),
+        Err(Error::ReaderOutOfData)
+    ));
+    // A frame holding more than one value.
+    let mut loose = Vec::new();
+    encode(&LengthPrefixed((1u8, 2u8)), &mut loose).unwrap();
+    assert!(matches!(
+        decode_frames_par::<u8>(&loose),
+        Err(Error::LengthMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_frame_batches_share_dedupe_tables_within_a_batch() {
+    use std::borrow::Cow;
+
+    let names = ["alpha-validator", "beta-validator", "gamma-validator"];
+    let values: Vec<Cow<'static, str>> = (0..300).map(|i| Cow::Borrowed(names[i % 3])).collect();
+    let mut plain = Vec::new();
+    encode_frame_batches(&values, 50, &mut plain, &mut EncoderContext::new()).unwrap();
+    let mut deduped = Vec::new();
+    let mut ctx = EncoderContext::with_dedupe();
+    encode_frame_batches(&values, 50, &mut deduped, &mut ctx).unwrap();
+    assert!(deduped.len() < plain.len() / 2);
+
+    let decoded: Vec<Cow<'static, str>> =
+        decode_frame_batches_par(&deduped, 50, DecoderContext::with_dedupe).unwrap();
+    assert_eq!(decoded, values);
+    // Batches that do not line up with the encoder's resets refer to unknown IDs.
+    assert!(
+        decode_frame_batches_par::<Cow<'static, str>>(&deduped, 40, DecoderContext::with_dedupe)
+            .is_err()
+    );
+}
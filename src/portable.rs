@@ -0,0 +1,295 @@
+//! Platform‑independent representations of OS‑level metadata.
+//!
+//! Types like `std::fs::Metadata` and `std::process::ExitStatus` are opaque and differ per
+//! platform, so they cannot be encoded directly. [`FileMetadata`] and [`PortableExitStatus`]
+//! capture the commonly needed parts in a fixed, portable shape with [`Encode`]/[`Decode`]
+//! impls, so tools built on this crate (file sync, remote execution) agree on one layout.
+//! Conversions from the `std` types are available with the `std` feature.
+use crate::prelude::*;
+
+/// The kind of filesystem entry described by a [`FileMetadata`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum FileKind {
+    /// A regular file.
+    #[default]
+    File = 0,
+    /// A directory.
+    Dir = 1,
+    /// A symbolic link.
+    Symlink = 2,
+    /// Any other entry (device, socket, FIFO, …).
+    Other = 3,
+}
+
+impl Encode for FileKind {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        (*self as u8).encode_ext(writer, None)
+    }
+}
+
+impl Decode for FileKind {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        match u8::decode_ext(reader, None)? {
+            0 => Ok(FileKind::File),
+            1 => Ok(FileKind::Dir),
+            2 => Ok(FileKind::Symlink),
+            3 => Ok(FileKind::Other),
+            disc => Err(Error::UnknownDiscriminant(disc as u64)),
+        }
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+/// Portable file metadata: kind, size, modification time, and permission bits.
+///
+/// Wire format: `kind (u8) + varint(size) + Option<zigzag varint(mtime_ns)> + varint(mode)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FileMetadata {
+    /// The kind of entry.
+    pub kind: FileKind,
+    /// Size in bytes.
+    pub size: u64,
+    /// Last modification time in nanoseconds relative to the Unix epoch (negative for times
+    /// before 1970), or `None` if the platform does not report it.
+    pub mtime_ns: Option<i128>,
+    /// Unix permission bits (e.g. `0o644`). On platforms without Unix permissions this is
+    /// synthesized from the read‑only flag as `0o444` or `0o644`.
+    pub mode: u32,
+}
+
+impl FileMetadata {
+    /// Returns `true` if no write permission bit is set.
+    #[inline(always)]
+    pub const fn is_readonly(&self) -> bool {
+        self.mode & 0o222 == 0
+    }
+}
+
+impl Encode for FileMetadata {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = 0;
+        total_written += self.kind.encode_ext(writer, ctx.as_deref_mut())?;
+        total_written += self.size.encode_ext(writer, ctx.as_deref_mut())?;
+        total_written += self.mtime_ns.encode_ext(writer, ctx.as_deref_mut())?;
+        total_written += self.mode.encode_ext(writer, ctx)?;
+        Ok(total_written)
+    }
+}
+
+impl Decode for FileMetadata {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(FileMetadata {
+            kind: FileKind::decode_ext(reader, ctx.as_deref_mut())?,
+            size: u64::decode_ext(reader, ctx.as_deref_mut())?,
+            mtime_ns: Option::<i128>::decode_ext(reader, ctx.as_deref_mut())?,
+            mode: u32::decode_ext(reader, ctx)?,
+        })
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&std::fs::Metadata> for FileMetadata {
+    fn from(meta: &std::fs::Metadata) -> Self {
+        let file_type = meta.file_type();
+        let kind = if file_type.is_file() {
+            FileKind::File
+        } else if file_type.is_dir() {
+            FileKind::Dir
+        } else if file_type.is_symlink() {
+            FileKind::Symlink
+        } else {
+            FileKind::Other
+        };
+        let mtime_ns = meta
+            .modified()
+            .ok()
+            .map(|t| Timestamp::from(t).unix_nanos());
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            meta.permissions().mode() & 0o7777
+        };
+        #[cfg(not(unix))]
+        let mode = if meta.permissions().readonly() {
+            0o444
+        } else {
+            0o644
+        };
+        FileMetadata {
+            kind,
+            size: meta.len(),
+            mtime_ns,
+            mode,
+        }
+    }
+}
+
+/// Portable process exit status.
+///
+/// `std::process::ExitStatus` is platform specific; this captures the exit code and, on
+/// Unix, the terminating signal. At most one of the two is `Some` for a finished process.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PortableExitStatus {
+    /// The exit code, if the process exited normally.
+    pub code: Option<i32>,
+    /// The signal that terminated the process, if any (Unix only).
+    pub signal: Option<i32>,
+}
+
+impl PortableExitStatus {
+    /// Returns `true` if the process exited with code `0`.
+    #[inline(always)]
+    pub const fn success(&self) -> bool {
+        matches!(self.code, Some(0))
+    }
+}
+
+impl Encode for PortableExitStatus {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = 0;
+        total_written += self.code.encode_ext(writer, ctx.as_deref_mut())?;
+        total_written += self.signal.encode_ext(writer, ctx)?;
+        Ok(total_written)
+    }
+}
+
+impl Decode for PortableExitStatus {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(PortableExitStatus {
+            code: Option::<i32>::decode_ext(reader, ctx.as_deref_mut())?,
+            signal: Option::<i32>::decode_ext(reader, ctx)?,
+        })
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::process::ExitStatus> for PortableExitStatus {
+    fn from(status: std::process::ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = {
+            use std::os::unix::process::ExitStatusExt;
+            status.signal()
+        };
+        #[cfg(not(unix))]
+        let signal = None;
+        PortableExitStatus {
+            code: status.code(),
+            signal,
+        }
+    }
+}
+
+#[test]
+fn test_file_metadata_roundtrip() {
+    let values = [
+        FileMetadata::default(),
+        FileMetadata {
+            kind: FileKind::Dir,
+            size: 4096,
+            mtime_ns: Some(1_700_000_000_123_456_789),
+            mode: 0o755,
+        },
+        FileMetadata {
+            kind: FileKind::Symlink,
+            size: 12,
+            mtime_ns: Some(-1),
+            mode: 0o777,
+        },
+    ];
+    for meta in values {
+        let mut buf = Vec::new();
+        meta.encode(&mut buf).unwrap();
+        let decoded: FileMetadata = decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(decoded, meta);
+    }
+    assert!(
+        FileMetadata {
+            mode: 0o444,
+            ..Default::default()
+        }
+        .is_readonly()
+    );
+}
+
+#[test]
+fn test_file_kind_rejects_unknown() {
+    let buf = [4u8];
+    let res: Result<FileKind> = decode(&mut Cursor::new(&buf[..]));
+    assert!(matches!(res, Err(Error::UnknownDiscriminant(4))));
+}
+
+#[test]
+fn test_portable_exit_status_roundtrip() {
+    for status in [
+        PortableExitStatus {
+            code: Some(0),
+            signal: None,
+        },
+        PortableExitStatus {
+            code: Some(-3),
+            signal: None,
+        },
+        PortableExitStatus {
+            code: None,
+            signal: Some(9),
+        },
+    ] {
+        let mut buf = Vec::new();
+        status.encode(&mut buf).unwrap();
+        let decoded: PortableExitStatus = decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(decoded, status);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_file_metadata_from_std() {
+    let path = std::env::temp_dir().join("lencode_portable_metadata_test");
+    std::fs::write(&path, b"hello").unwrap();
+    let meta = FileMetadata::from(&std::fs::metadata(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(meta.kind, FileKind::File);
+    assert_eq!(meta.size, 5);
+    assert!(meta.mtime_ns.is_some());
+}
+
+#[cfg(all(feature = "std", unix))]
+#[test]
+fn test_portable_exit_status_from_std() {
+    use std::os::unix::process::ExitStatusExt;
+    let ok = PortableExitStatus::from(std::process::ExitStatus::from_raw(0));
+    assert!(ok.success());
+    let killed = PortableExitStatus::from(std::process::ExitStatus::from_raw(9));
+    assert_eq!(killed.code, None);
+    assert_eq!(killed.signal, Some(9));
+}
@@ -0,0 +1,309 @@
+//! Bundled encode/decode settings.
+//!
+//! A [`Profile`] collects the knobs that otherwise get wired at every call site: whether the
+//! whole message is compressed (and at which zstd level, against which dictionary), whether
+//! values are deduplicated, and the limits applied when encoding and decoding.
+//! [`Profile::archival`], [`Profile::realtime`] and [`Profile::datagram`] are opinionated
+//! starting points; adjust individual fields as needed. Encoder and decoder must use the same
+//! profile.
+//!
+//! Every message encoded with a profile is self‑contained: the dedupe table starts empty for
+//! each message, so a repeated value costs its full encoding once per message and an ID after
+//! that, and no message refers to state carried over from an earlier one. Messages can
+//! therefore be lost, duplicated or reordered in transit, which is what
+//! [`Profile::datagram`] relies on.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! let profile = Profile::archival();
+//! let rows: Vec<(u64, u32)> = (0..1_000).map(|i| (1_700_000_000 + i, 42)).collect();
+//!
+//! let mut buf = Vec::new();
+//! encode_with_profile(&rows, &mut buf, &profile).unwrap();
+//! let decoded: Vec<(u64, u32)> = decode_with_profile(&mut Cursor::new(&buf), &profile).unwrap();
+//! assert_eq!(decoded, rows);
+//! ```
+use crate::bytes;
+use crate::prelude::*;
+
+/// How a [`Profile`] compresses messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MessageCompression {
+    /// Only byte and string fields are compressed, each on its own (the default encoding).
+    Fields,
+    /// The encoded message is compressed as a whole at the given zstd level, using the same
+    /// flagged header as [`encode_compressed`].
+    WholeMessage {
+        /// zstd compression level (1–22).
+        level: i32,
+    },
+}
+
+/// Encode/decode settings applied by [`encode_with_profile`] and [`decode_with_profile`].
+/// See the [module docs](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    /// How messages are compressed.
+    pub compression: MessageCompression,
+    /// Optional raw‑content zstd dictionary for whole‑message compression. Small messages
+    /// with a shared shape (same field names, same program IDs) compress far better against
+    /// a dictionary of typical content.
+    pub dictionary: Option<Vec<u8>>,
+    /// Whether values are deduplicated through a fresh [`DedupeEncoder`]/[`DedupeDecoder`]
+    /// per message.
+    pub dedupe: bool,
+    /// Maximum nesting depth when decoding; see [`DecoderContext::max_depth`].
+    pub max_depth: usize,
+    /// Largest (decompressed) whole message accepted by [`decode_with_profile`]; larger
+    /// messages fail with [`Error::IncorrectLength`] before any buffer is allocated for them.
+    pub max_message_len: usize,
+    /// Largest output [`encode_with_profile`] may write, if limited. A message that would not
+    /// fit fails with [`Error::WriterOutOfSpace`] and nothing is written.
+    pub max_encoded_len: Option<usize>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::realtime()
+    }
+}
+
+impl Profile {
+    /// Favors size over speed, for data written once and stored: whole‑message compression at
+    /// zstd level 19 with deduplication.
+    pub const fn archival() -> Self {
+        Self {
+            compression: MessageCompression::WholeMessage { level: 19 },
+            dictionary: None,
+            dedupe: true,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_message_len: 256 << 20,
+            max_encoded_len: None,
+        }
+    }
+
+    /// Favors latency, for live feeds: the default per‑field encoding without deduplication.
+    pub const fn realtime() -> Self {
+        Self {
+            compression: MessageCompression::Fields,
+            dictionary: None,
+            dedupe: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_message_len: 16 << 20,
+            max_encoded_len: None,
+        }
+    }
+
+    /// For lossy, unordered transports such as UDP: every message is self‑contained (see the
+    /// [module docs](self)), deduplicated within itself, compressed as a whole at zstd level 3
+    /// when that helps, and at most `max_len` bytes once encoded.
+    ///
+    /// 1200 bytes fits a single unfragmented packet on practically every path (it is QUIC's
+    /// minimum). Decoding accepts messages that decompress to at most 64 KiB.
+    ///
+    /// ```
+    /// use lencode::prelude::*;
+    ///
+    /// let profile = Profile::datagram(1200);
+    /// let mut packet = Vec::new();
+    /// encode_with_profile(&(7u64, "heartbeat"), &mut packet, &profile).unwrap();
+    /// assert!(packet.len() <= 1200);
+    ///
+    /// let noise: Vec<u64> = (0..400u64).map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15)).collect();
+    /// let mut oversized = Vec::new();
+    /// let result = encode_with_profile(&noise, &mut oversized, &profile);
+    /// assert!(matches!(result, Err(Error::WriterOutOfSpace)));
+    /// assert!(oversized.is_empty());
+    /// ```
+    pub const fn datagram(max_len: usize) -> Self {
+        Self {
+            compression: MessageCompression::WholeMessage { level: 3 },
+            dictionary: None,
+            dedupe: true,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_message_len: 64 << 10,
+            max_encoded_len: Some(max_len),
+        }
+    }
+
+    /// Returns the profile with `dictionary` used for whole‑message compression.
+    pub fn with_dictionary(mut self, dictionary: impl Into<Vec<u8>>) -> Self {
+        self.dictionary = Some(dictionary.into());
+        self
+    }
+
+    /// Returns a fresh [`EncoderContext`] configured by this profile.
+    pub fn encoder_context(&self) -> EncoderContext {
+        if self.dedupe {
+            EncoderContext::with_dedupe()
+        } else {
+            EncoderContext::new()
+        }
+    }
+
+    /// Returns a fresh [`DecoderContext`] configured by this profile.
+    pub fn decoder_context(&self) -> DecoderContext {
+        let mut ctx = if self.dedupe {
+            DecoderContext::with_dedupe()
+        } else {
+            DecoderContext::new()
+        };
+        ctx.max_depth = self.max_depth;
+        ctx
+    }
+}
+
+/// Encodes `value` into `writer` with the settings of `profile`. Returns the number of bytes
+/// written.
+///
+/// Fails with [`Error::WriterOutOfSpace`], before writing anything, if the output would
+/// exceed [`Profile::max_encoded_len`].
+pub fn encode_with_profile<T: Encode + ?Sized>(
+    value: &T,
+    writer: &mut impl Write,
+    profile: &Profile,
+) -> Result<usize> {
+    let mut ctx = profile.encoder_context();
+    let MessageCompression::WholeMessage { level } = profile.compression else {
+        let Some(max_len) = profile.max_encoded_len else {
+            return value.encode_ext(writer, Some(&mut ctx));
+        };
+        let mut message = Vec::new();
+        value.encode_ext(&mut message, Some(&mut ctx))?;
+        if message.len() > max_len {
+            return Err(Error::WriterOutOfSpace);
+        }
+        return write_all(writer, &message);
+    };
+    let mut message = Vec::new();
+    value.encode_ext(&mut message, Some(&mut ctx))?;
+    let compressed = bytes::zstd_compress_with(&message, level, profile.dictionary.as_deref())?
+        .filter(|compressed| compressed.len() < message.len());
+    let (flagged, payload) = match &compressed {
+        Some(compressed) => ((compressed.len() << 1) | 1, &compressed[..]),
+        None => (message.len() << 1, &message[..]),
+    };
+    if let Some(max_len) = profile.max_encoded_len
+        && bytes::varint_len_usize(flagged) + payload.len() > max_len
+    {
+        return Err(Error::WriterOutOfSpace);
+    }
+    let mut total = Lencode::encode_varint_u64(flagged as u64, writer)?;
+    total += write_all(writer, payload)?;
+    Ok(total)
+}
+
+/// Decodes a `T` written by [`encode_with_profile`] with the same `profile`.
+pub fn decode_with_profile<T: Decode>(reader: &mut impl Read, profile: &Profile) -> Result<T> {
+    let mut ctx = profile.decoder_context();
+    if profile.compression == MessageCompression::Fields {
+        return T::decode_ext(reader, Some(&mut ctx));
+    }
+    let flagged = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+    let payload_len = flagged >> 1;
+    if payload_len > profile.max_message_len {
+        return Err(Error::IncorrectLength);
+    }
+    let payload = read_vec(reader, payload_len)?;
+    let message = if flagged & 1 == 1 {
+        let message_len = bytes::zstd_content_size(&payload)?;
+        if message_len > profile.max_message_len {
+            return Err(Error::IncorrectLength);
+        }
+        bytes::zstd_decompress_with(&payload, message_len, profile.dictionary.as_deref())?
+    } else {
+        payload
+    };
+    let mut cursor = Cursor::new(&message[..]);
+    let value = T::decode_ext(&mut cursor, Some(&mut ctx))?;
+    if cursor.position() != message.len() {
+        return Err(Error::InvalidData);
+    }
+    Ok(value)
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_profiles_roundtrip_and_limits() {
+    let names: Vec<String> = (0..200)
+        .map(|i| String::from(["transfer", "mint_to", "burn"][i % 3]))
+        .collect();
+    let dictionary = b"transfer mint_to burn transfer mint_to burn".repeat(4);
+    let profiles = [
+        Profile::realtime(),
+        Profile::archival(),
+        Profile::archival().with_dictionary(dictionary.clone()),
+    ];
+    let mut sizes = Vec::new();
+    for profile in &profiles {
+        let mut buf = Vec::new();
+        let n = encode_with_profile(&names, &mut buf, profile).unwrap();
+        assert_eq!(n, buf.len());
+        let decoded: Vec<String> = decode_with_profile(&mut Cursor::new(&buf), profile).unwrap();
+        assert_eq!(decoded, names);
+        sizes.push(n);
+    }
+    assert!(sizes[1] < sizes[0]);
+
+    // A dictionary frame does not decode without the dictionary.
+    let mut buf = Vec::new();
+    encode_with_profile(&names, &mut buf, &profiles[2]).unwrap();
+    assert!(decode_with_profile::<Vec<String>>(&mut Cursor::new(&buf), &profiles[1]).is_err());
+
+    let tight = Profile {
+        max_message_len: 16,
+        ..Profile::archival()
+    };
+    let mut buf = Vec::new();
+    encode_with_profile(&names, &mut buf, &Profile::archival()).unwrap();
+    assert!(matches!(
+        decode_with_profile::<Vec<String>>(&mut Cursor::new(&buf), &tight),
+        Err(Error::IncorrectLength)
+    ));
+}
+
+#[cfg(all(feature = "std", feature = "zstd"))]
+#[test]
+fn test_datagram_profile_is_self_contained_and_bounded() {
+    use std::borrow::Cow;
+
+    let profile = Profile::datagram(512);
+    let packets: Vec<Vec<u8>> = (0..4u64)
+        .map(|i| {
+            let event: (u64, Vec<Cow<'static, str>>) = (i, vec![Cow::Borrowed("vote"); 20]);
+            let mut packet = Vec::new();
+            encode_with_profile(&event, &mut packet, &profile).unwrap();
+            assert!(packet.len() <= 512);
+            packet
+        })
+        .collect();
+    // Lost and reordered packets do not affect the ones that arrive.
+    for i in [3, 1] {
+        let (seq, names): (u64, Vec<Cow<'static, str>>) =
+            decode_with_profile(&mut Cursor::new(&packets[i]), &profile).unwrap();
+        assert_eq!((seq, names.len()), (i as u64, 20));
+        assert!(names.iter().all(|name| name == "vote"));
+    }
+
+    // The limit covers the output after compression, and applies to field-only profiles too.
+    let noisy: Vec<u64> = (0..200u64)
+        .map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .collect();
+    let mut out = vec![0xEE];
+    assert!(matches!(
+        encode_with_profile(&noisy, &mut out, &profile),
+        Err(Error::WriterOutOfSpace)
+    ));
+    let fields = Profile {
+        compression: MessageCompression::Fields,
+        ..Profile::datagram(8)
+    };
+    assert!(matches!(
+        encode_with_profile(&noisy, &mut out, &fields),
+        Err(Error::WriterOutOfSpace)
+    ));
+    assert_eq!(out, [0xEE]);
+    assert_eq!(encode_with_profile(&7u32, &mut out, &fields).unwrap(), 1);
+}
@@ -0,0 +1,220 @@
+//! Read‑side progress reporting for long decodes.
+//!
+//! [`ProgressReader`] wraps a [`Read`], counts the bytes pulled through it and calls a
+//! callback whenever another `every` bytes have been read, so decoding a multi‑gigabyte
+//! archive can drive a progress bar. Bytes consumed through [`Read::buf`]/[`Read::advance`]
+//! (the zero‑copy paths of in‑memory readers) are counted too.
+//!
+//! The callback learns the total size when there is one: set it with
+//! [`ProgressReader::with_total`] (e.g. from the file size), or write the payload as a
+//! [`LengthPrefixed`] value and read it back with [`ProgressReader::decode_length_prefixed`],
+//! which takes the total from the size header and then decodes the body as it streams in.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! let ledger: Vec<u64> = (0..10_000).collect();
+//! let mut archive = Vec::new();
+//! encode(&LengthPrefixed(ledger.clone()), &mut archive).unwrap();
+//!
+//! let mut reports = Vec::new();
+//! let mut reader = ProgressReader::new(Cursor::new(&archive), 4096, |p: Progress| {
+//!     reports.push(p)
+//! });
+//! let decoded: Vec<u64> = reader.decode_length_prefixed().unwrap();
+//! assert_eq!(decoded, ledger);
+//! let last = reports.last().unwrap();
+//! assert_eq!((last.read, last.total), (archive.len() as u64, Some(archive.len() as u64)));
+//! ```
+use crate::prelude::*;
+
+/// A progress update passed to the [`ProgressReader`] callback.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes read so far.
+    pub read: u64,
+    /// Total bytes expected, if known.
+    pub total: Option<u64>,
+}
+
+impl Progress {
+    /// Returns the completed fraction in `0.0..=1.0`, if the total is known.
+    #[inline]
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.read as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+}
+
+/// A [`Read`] adapter that reports how many bytes have been read. See the
+/// [module docs](self).
+pub struct ProgressReader<R: Read, F: FnMut(Progress)> {
+    inner: R,
+    read: u64,
+    total: Option<u64>,
+    every: u64,
+    next_report: u64,
+    on_progress: F,
+}
+
+impl<R: Read, F: FnMut(Progress)> ProgressReader<R, F> {
+    /// Wraps `inner`, calling `on_progress` each time the byte count crosses a multiple of
+    /// `every` (at most once per read) and when it reaches the total.
+    #[inline]
+    pub fn new(inner: R, every: u64, on_progress: F) -> Self {
+        let every = every.max(1);
+        Self {
+            inner,
+            read: 0,
+            total: None,
+            every,
+            next_report: every,
+            on_progress,
+        }
+    }
+
+    /// Sets the total number of bytes expected.
+    #[inline(always)]
+    pub const fn with_total(mut self, total: u64) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// Returns the number of bytes read so far.
+    #[inline(always)]
+    pub const fn bytes_read(&self) -> u64 {
+        self.read
+    }
+
+    /// Returns the total number of bytes expected, if known.
+    #[inline(always)]
+    pub const fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Returns a reference to the underlying reader.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the underlying reader.
+    #[inline(always)]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decodes a `T` written as [`LengthPrefixed<T>`](LengthPrefixed), first setting the
+    /// total to the end of its section.
+    #[inline(always)]
+    pub fn decode_length_prefixed<T: Decode>(&mut self) -> Result<T> {
+        self.decode_length_prefixed_ext(None)
+    }
+
+    /// Like [`decode_length_prefixed`](Self::decode_length_prefixed), with an optional
+    /// [`DecoderContext`].
+    ///
+    /// Unlike decoding a [`LengthPrefixed`] directly, the body is not buffered first, so the
+    /// callback sees it arrive. Fails with [`Error::LengthMismatch`] if `T` does not occupy
+    /// exactly the declared size.
+    pub fn decode_length_prefixed_ext<T: Decode>(
+        &mut self,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<T> {
+        let len = Lencode::decode_varint_u64(self)?;
+        let start = self.read;
+        self.total = Some(start.saturating_add(len));
+        self.report();
+        let value = T::decode_ext(self, ctx)?;
+        let actual = self.read - start;
+        if actual != len {
+            return Err(Error::LengthMismatch {
+                expected: checked_usize(len)?,
+                actual: checked_usize(actual)?,
+            });
+        }
+        Ok(value)
+    }
+
+    #[inline(always)]
+    fn report(&mut self) {
+        (self.on_progress)(Progress {
+            read: self.read,
+            total: self.total,
+        });
+    }
+
+    #[inline]
+    fn record(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.read += n as u64;
+        if self.read >= self.next_report || self.total == Some(self.read) {
+            self.next_report = (self.read / self.every + 1) * self.every;
+            self.report();
+        }
+    }
+}
+
+impl<R: Read, F: FnMut(Progress)> Read for ProgressReader<R, F> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.record(n);
+        Ok(n)
+    }
+
+    #[inline(always)]
+    fn buf(&self) -> Option<&[u8]> {
+        self.inner.buf()
+    }
+
+    #[inline]
+    fn advance(&mut self, n: usize) {
+        self.inner.advance(n);
+        self.record(n);
+    }
+}
+
+#[test]
+fn test_progress_reports_every_n_bytes_and_at_total() {
+    let values: Vec<u64> = (0..1000).map(|i| i * 1_000_003).collect();
+    let mut archive = Vec::new();
+    encode(&LengthPrefixed(values.clone()), &mut archive).unwrap();
+    encode(&7u8, &mut archive).unwrap();
+    let end = archive.len() as u64 - 1;
+
+    let mut reports = Vec::new();
+    let mut reader = ProgressReader::new(Cursor::new(&archive), 512, |p| reports.push(p));
+    let decoded: Vec<u64> = reader.decode_length_prefixed().unwrap();
+    assert_eq!(decoded, values);
+    assert_eq!(reader.bytes_read(), end);
+    assert_eq!(u8::decode(&mut reader).unwrap(), 7);
+
+    // The first report announces the total, then each 512-byte mark is reported once, and
+    // the last one lands on the total.
+    assert_eq!(reports[0].total, Some(end));
+    let last = reports.last().unwrap();
+    assert_eq!((last.read, last.fraction()), (end, Some(1.0)));
+    let marks: Vec<u64> = reports[1..].iter().map(|p| p.read / 512).collect();
+    assert_eq!(
+        marks,
+        (1..=end / 512).chain([end / 512]).collect::<Vec<_>>()
+    );
+
+    // A section that holds more than one value.
+    let mut short = Vec::new();
+    encode(&LengthPrefixed((1u8, 2u8)), &mut short).unwrap();
+    let mut reader = ProgressReader::new(Cursor::new(&short), 1, |_| {});
+    assert!(matches!(
+        reader.decode_length_prefixed::<u8>(),
+        Err(Error::LengthMismatch {
+            expected: 2,
+            actual: 1
+        })
+    ));
+}
@@ -0,0 +1,143 @@
+//! Decoding a subset of a struct's fields.
+//!
+//! `#[derive(Project)]` on a struct `Foo` generates `FooProjection`, a struct with the same
+//! fields wrapped in `Option`, and implements [`Project`]. [`decode_projection`] decodes the
+//! fields selected by a [`FieldMask`] and steps over the rest with [`Decode::skip_ext`], so an
+//! indexer that needs two fields of a large record pays for neither the others' allocations
+//! nor their decompression.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! #[derive(Encode, Decode, Project)]
+//! struct Meta {
+//!     fee: u64,
+//!     logs: Vec<String>,
+//!     status: bool,
+//! }
+//!
+//! let meta = Meta { fee: 5_000, logs: vec![String::from("Program log: hi"); 50], status: true };
+//! let mut buf = Vec::new();
+//! encode(&meta, &mut buf).unwrap();
+//!
+//! let mask = FieldMask::of::<Meta>(&["fee", "status"]).unwrap();
+//! let partial = decode_projection::<Meta>(&mut Cursor::new(&buf), &mask).unwrap();
+//! assert_eq!(partial.fee, Some(5_000));
+//! assert_eq!(partial.logs, None);
+//! assert_eq!(partial.status, Some(true));
+//! ```
+use crate::prelude::*;
+
+/// Structs that can decode a selection of their fields. Implement with
+/// `#[derive(Project)]`; see the [module docs](self).
+pub trait Project: Decode + Sized {
+    /// The struct's fields wrapped in `Option`; unselected fields are `None`.
+    type Projection;
+
+    /// Field names in declaration (and wire) order. Tuple struct fields are named `"0"`,
+    /// `"1"`, …
+    const FIELDS: &'static [&'static str];
+
+    /// Decodes the fields selected by `mask` and skips the others.
+    fn decode_projection_ext(
+        reader: &mut impl Read,
+        mask: &FieldMask,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Self::Projection>;
+}
+
+/// The set of fields a projection decodes, by position.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FieldMask {
+    words: Vec<u64>,
+}
+
+impl FieldMask {
+    /// Creates a mask selecting no fields.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self { words: Vec::new() }
+    }
+
+    /// Creates a mask selecting the fields of `T` named in `names`.
+    ///
+    /// Fails with [`Error::InvalidData`] if a name is not a field of `T`.
+    pub fn of<T: Project>(names: &[&str]) -> Result<Self> {
+        let mut mask = Self::new();
+        for name in names {
+            let index = T::FIELDS
+                .iter()
+                .position(|field| field == name)
+                .ok_or(Error::InvalidData)?;
+            mask.insert(index);
+        }
+        Ok(mask)
+    }
+
+    /// Creates a mask selecting every field of `T`.
+    pub fn all<T: Project>() -> Self {
+        let mut mask = Self::new();
+        for index in 0..T::FIELDS.len() {
+            mask.insert(index);
+        }
+        mask
+    }
+
+    /// Selects the field at `index`.
+    pub fn insert(&mut self, index: usize) {
+        let word = index / 64;
+        if self.words.len() <= word {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (index % 64);
+    }
+
+    /// Returns `true` if the field at `index` is selected.
+    #[inline(always)]
+    pub fn contains(&self, index: usize) -> bool {
+        self.words
+            .get(index / 64)
+            .is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+}
+
+/// Decodes the fields of a `T` selected by `mask`, skipping the rest.
+#[inline(always)]
+pub fn decode_projection<T: Project>(
+    reader: &mut impl Read,
+    mask: &FieldMask,
+) -> Result<T::Projection> {
+    T::decode_projection_ext(reader, mask, None)
+}
+
+#[test]
+fn test_field_mask_bits() {
+    let mut mask = FieldMask::new();
+    mask.insert(0);
+    mask.insert(70);
+    assert!(mask.contains(0) && mask.contains(70));
+    assert!(!mask.contains(1) && !mask.contains(64) && !mask.contains(500));
+}
+
+#[test]
+fn test_skip_matches_decode() {
+    let text = String::from("abc").repeat(100);
+    let bytes: Vec<u8> = (0..300).map(|i| (i % 5) as u8).collect();
+    let mut buf = Vec::new();
+    encode(&text, &mut buf).unwrap();
+    encode(&bytes, &mut buf).unwrap();
+    encode(&vec![1u32, 2, 3], &mut buf).unwrap();
+    encode(&9u8, &mut buf).unwrap();
+
+    let mut cursor = Cursor::new(&buf);
+    String::skip_ext(&mut cursor, None).unwrap();
+    Vec::<u8>::skip_ext(&mut cursor, None).unwrap();
+    Vec::<u32>::skip_ext(&mut cursor, None).unwrap();
+    assert_eq!(u8::decode(&mut cursor).unwrap(), 9);
+
+    // Skipping does not validate the payload it steps over.
+    let mut bad = Vec::new();
+    encode(&vec![0xffu8, 0xfe], &mut bad).unwrap();
+    assert!(String::decode(&mut Cursor::new(&bad)).is_err());
+    String::skip_ext(&mut Cursor::new(&bad), None).unwrap();
+}
@@ -0,0 +1,350 @@
+//! Python bindings for schema‑driven decoding (`python` feature).
+//!
+//! A [`Schema`] wraps the [`Layout`] of a Rust type (see [`layout_of`]) and decodes lencode
+//! bytes into plain Python objects, so captured archives can be read from Python without
+//! reimplementing the format:
+//!
+//! - structs become `dict`s keyed by field name (tuple structs and tuples become `tuple`s);
+//! - sequences become `list`s, maps `dict`s, byte strings and `[u8; N]` `bytes`;
+//! - integers become `int`, floats `float`, `Option`s `None` or the value;
+//! - unit enum variants become their name as a `str`, other variants a one‑entry
+//!   `{name: fields}` dict; `Result`s become `{"Ok": value}` or `{"Err": value}`.
+//!
+//! Crates embedding Python register the schemas of their types in their own `#[pymodule]`
+//! with [`add_schemas`]:
+//!
+//! ```ignore
+//! use lencode::prelude::*;
+//! use pyo3::prelude::*;
+//!
+//! #[pymodule]
+//! fn geyser_archive(m: &Bound<'_, PyModule>) -> PyResult<()> {
+//!     add_schemas(m, [("TransactionMeta", Schema::of::<TransactionMeta>())])
+//! }
+//! ```
+//!
+//! ```python
+//! from geyser_archive import TransactionMeta
+//! for meta in TransactionMeta.decode_all(open("metas.bin", "rb").read()):
+//!     print(meta["fee"], len(meta["log_messages"]))
+//! ```
+//!
+//! Values are decoded without a [`DecoderContext`], so deduplicated or diff‑encoded streams
+//! are not supported; [`LayoutKind::Opaque`] parts of a layout raise `ValueError`.
+use pyo3::IntoPyObjectExt;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList, PyTuple};
+
+use crate::prelude::*;
+
+/// A decodable type, exposed to Python. See the [module docs](self).
+#[pyclass(frozen, module = "lencode")]
+#[derive(Clone, Debug)]
+pub struct Schema {
+    layout: Layout,
+}
+
+impl Schema {
+    /// Creates a schema from a layout descriptor.
+    #[inline(always)]
+    pub const fn new(layout: Layout) -> Self {
+        Self { layout }
+    }
+
+    /// Creates the schema of `T`.
+    #[inline(always)]
+    pub fn of<T: DescribeLayout + ?Sized>() -> Self {
+        Self::new(layout_of::<T>())
+    }
+
+    /// Returns the layout this schema decodes.
+    #[inline(always)]
+    pub const fn layout(&self) -> &Layout {
+        &self.layout
+    }
+}
+
+#[pymethods]
+impl Schema {
+    /// The Rust type name of the schema.
+    #[getter]
+    const fn type_name(&self) -> &'static str {
+        self.layout.type_name
+    }
+
+    /// The schema's [`Layout::fingerprint`], for checking that a peer or archive uses the
+    /// same schema.
+    #[getter]
+    fn fingerprint(&self) -> u64 {
+        self.layout.fingerprint()
+    }
+
+    /// Decodes one value that spans all of `data`.
+    fn decode<'py>(&self, py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyAny>> {
+        let mut reader = Cursor::new(data);
+        let value = decode_to_py(py, &self.layout, &mut reader)?;
+        if reader.position() != data.len() {
+            return Err(PyValueError::new_err("trailing bytes after value"));
+        }
+        Ok(value)
+    }
+
+    /// Decodes consecutive values until `data` is exhausted.
+    fn decode_all<'py>(&self, py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyList>> {
+        let mut reader = Cursor::new(data);
+        let values = PyList::empty(py);
+        while reader.position() < data.len() {
+            values.append(decode_to_py(py, &self.layout, &mut reader)?)?;
+        }
+        Ok(values)
+    }
+}
+
+/// Adds each `(name, schema)` pair to `module` as a Python attribute.
+pub fn add_schemas<'a>(
+    module: &Bound<'_, PyModule>,
+    schemas: impl IntoIterator<Item = (&'a str, Schema)>,
+) -> PyResult<()> {
+    for (name, schema) in schemas {
+        module.add(name, schema)?;
+    }
+    Ok(())
+}
+
+#[inline(always)]
+fn value_error(err: Error) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Decodes one value described by `layout` from `reader` into a Python object. See the
+/// [module docs](self) for the mapping.
+pub fn decode_to_py<'py>(
+    py: Python<'py>,
+    layout: &Layout,
+    reader: &mut impl Read,
+) -> PyResult<Bound<'py, PyAny>> {
+    match &layout.kind {
+        LayoutKind::Unit => Ok(py.None().into_bound(py)),
+        LayoutKind::Bool => bool::decode(reader)
+            .map_err(value_error)?
+            .into_bound_py_any(py),
+        LayoutKind::Raw => match (layout.type_name, layout.min_size) {
+            ("i8", _) => i8::decode(reader)
+                .map_err(value_error)?
+                .into_bound_py_any(py),
+            (_, 1) => u8::decode(reader)
+                .map_err(value_error)?
+                .into_bound_py_any(py),
+            (_, 4) => f32::decode(reader)
+                .map_err(value_error)?
+                .into_bound_py_any(py),
+            (_, 8) => f64::decode(reader)
+                .map_err(value_error)?
+                .into_bound_py_any(py),
+            _ => Err(opaque(layout)),
+        },
+        // Varints are width‑independent on the wire, so the widest type decodes them all.
+        LayoutKind::Varint if layout.type_name.ends_with("U256") => {
+            let value = U256::decode(reader).map_err(value_error)?;
+            py.get_type::<pyo3::types::PyInt>()
+                .call1((value.to_string(),))
+        }
+        LayoutKind::Varint => u128::decode(reader)
+            .map_err(value_error)?
+            .into_bound_py_any(py),
+        LayoutKind::ZigzagVarint => i128::decode(reader)
+            .map_err(value_error)?
+            .into_bound_py_any(py),
+        LayoutKind::Bytes => {
+            let bytes = Vec::<u8>::decode(reader).map_err(value_error)?;
+            Ok(PyBytes::new(py, &bytes).into_any())
+        }
+        LayoutKind::Str => String::decode(reader)
+            .map_err(value_error)?
+            .into_bound_py_any(py),
+        LayoutKind::Option(inner) => {
+            if bool::decode(reader).map_err(value_error)? {
+                decode_to_py(py, inner, reader)
+            } else {
+                Ok(py.None().into_bound(py))
+            }
+        }
+        LayoutKind::Result(ok, err) => {
+            let (name, inner) = if bool::decode(reader).map_err(value_error)? {
+                ("Ok", ok)
+            } else {
+                ("Err", err)
+            };
+            let dict = PyDict::new(py);
+            dict.set_item(name, decode_to_py(py, inner, reader)?)?;
+            Ok(dict.into_any())
+        }
+        LayoutKind::Sequence(element) => {
+            let len = decode_count(reader)?;
+            let list = PyList::empty(py);
+            for _ in 0..len {
+                list.append(decode_to_py(py, element, reader)?)?;
+            }
+            Ok(list.into_any())
+        }
+        LayoutKind::Array(element, len) if element.type_name == "u8" => {
+            let mut bytes = vec![0u8; *len];
+            read_exact(reader, &mut bytes).map_err(value_error)?;
+            Ok(PyBytes::new(py, &bytes).into_any())
+        }
+        LayoutKind::Array(element, len) => {
+            let list = PyList::empty(py);
+            for _ in 0..*len {
+                list.append(decode_to_py(py, element, reader)?)?;
+            }
+            Ok(list.into_any())
+        }
+        LayoutKind::Map(key, value) => {
+            let len = decode_count(reader)?;
+            let dict = PyDict::new(py);
+            for _ in 0..len {
+                let k = decode_to_py(py, key, reader)?;
+                dict.set_item(k, decode_to_py(py, value, reader)?)?;
+            }
+            Ok(dict.into_any())
+        }
+        LayoutKind::Tuple(elements) => {
+            let items = elements
+                .iter()
+                .map(|element| decode_to_py(py, element, reader))
+                .collect::<PyResult<Vec<_>>>()?;
+            Ok(PyTuple::new(py, items)?.into_any())
+        }
+        LayoutKind::Struct(fields) => decode_fields(py, fields, reader),
+        LayoutKind::Enum(variants) => {
+            let discriminant = Lencode::decode_varint_u64(reader).map_err(value_error)?;
+            let variant = variants
+                .iter()
+                .find(|variant| variant.discriminant == discriminant)
+                .ok_or_else(|| value_error(Error::UnknownDiscriminant(discriminant)))?;
+            if variant.fields.is_empty() {
+                return variant.name.into_bound_py_any(py);
+            }
+            let dict = PyDict::new(py);
+            dict.set_item(variant.name, decode_fields(py, &variant.fields, reader)?)?;
+            Ok(dict.into_any())
+        }
+        LayoutKind::Opaque => Err(opaque(layout)),
+    }
+}
+
+/// Decodes struct or variant fields: a `dict` for named fields, a `tuple` for positional
+/// ones.
+fn decode_fields<'py>(
+    py: Python<'py>,
+    fields: &[Field],
+    reader: &mut impl Read,
+) -> PyResult<Bound<'py, PyAny>> {
+    let positional = fields
+        .first()
+        .is_some_and(|field| field.name.starts_with(|c: char| c.is_ascii_digit()));
+    if positional {
+        let items = fields
+            .iter()
+            .map(|field| decode_to_py(py, &field.layout, reader))
+            .collect::<PyResult<Vec<_>>>()?;
+        return Ok(PyTuple::new(py, items)?.into_any());
+    }
+    let dict = PyDict::new(py);
+    for field in fields {
+        dict.set_item(field.name, decode_to_py(py, &field.layout, reader)?)?;
+    }
+    Ok(dict.into_any())
+}
+
+#[inline(always)]
+fn decode_count(reader: &mut impl Read) -> PyResult<usize> {
+    Lencode::decode_varint_u64(reader)
+        .and_then(checked_usize)
+        .map_err(value_error)
+}
+
+#[inline(always)]
+fn opaque(layout: &Layout) -> PyErr {
+    PyValueError::new_err(format!(
+        "{} has no schema-decodable layout",
+        layout.type_name
+    ))
+}
+
+#[test]
+fn test_schema_decodes_to_python_objects() {
+    let status = Layout::enumeration(
+        "Status",
+        [
+            Variant::new("Ok", 0, []),
+            Variant::new("Failed", 1, [Field::new("0", layout_of::<String>())]),
+        ],
+    );
+    let meta = Layout::structure(
+        "Meta",
+        [
+            Field::new("fee", layout_of::<u64>()),
+            Field::new("logs", layout_of::<Vec<String>>()),
+            Field::new("key", layout_of::<[u8; 4]>()),
+            Field::new("delta", layout_of::<Option<i64>>()),
+            Field::new("status", status),
+        ],
+    );
+    let schema = Schema::new(meta);
+    let mut buf = Vec::new();
+    for (fee, delta, status) in [(5_000u64, Some(-7i64), None), (1, None, Some("boom"))] {
+        encode(&fee, &mut buf).unwrap();
+        encode(&vec![String::from("log")], &mut buf).unwrap();
+        encode(&[1u8, 2, 3, 4], &mut buf).unwrap();
+        encode(&delta, &mut buf).unwrap();
+        match status {
+            None => encode(&0usize, &mut buf).unwrap(),
+            Some(err) => encode(&(1usize, String::from(err)), &mut buf).unwrap(),
+        };
+    }
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let values = schema.decode_all(py, &buf).unwrap();
+        assert_eq!(values.len(), 2);
+        let first = values.get_item(0).unwrap();
+        assert_eq!(
+            first.get_item("fee").unwrap().extract::<u64>().unwrap(),
+            5_000
+        );
+        let logs: Vec<String> = first.get_item("logs").unwrap().extract().unwrap();
+        assert_eq!(logs, ["log"]);
+        let key: Vec<u8> = first.get_item("key").unwrap().extract().unwrap();
+        assert_eq!(key, [1, 2, 3, 4]);
+        assert_eq!(
+            first.get_item("delta").unwrap().extract::<i64>().unwrap(),
+            -7
+        );
+        assert_eq!(
+            first
+                .get_item("status")
+                .unwrap()
+                .extract::<String>()
+                .unwrap(),
+            "Ok"
+        );
+
+        let second = values.get_item(1).unwrap();
+        assert!(second.get_item("delta").unwrap().is_none());
+        let failed = second
+            .get_item("status")
+            .unwrap()
+            .get_item("Failed")
+            .unwrap();
+        assert_eq!(
+            failed.get_item(0).unwrap().extract::<String>().unwrap(),
+            "boom"
+        );
+
+        // A single value must span the whole input.
+        assert!(schema.decode(py, &buf).is_err());
+        assert!(schema.decode(py, &buf[..3]).is_err());
+    });
+}
@@ -0,0 +1,325 @@
+//! Mux channels mapped onto QUIC streams (`quinn` feature).
+//!
+//! Over a single byte stream every [`mux`](crate::mux) channel shares one ordered pipe, so a
+//! burst on a busy channel delays every frame queued behind it. QUIC offers independent
+//! streams instead: [`QuicMux`] opens one unidirectional stream per channel the first time
+//! the channel is used, and gives each stream the priority configured for its channel. quinn
+//! sends pending data from higher‑priority streams first, so control messages overtake an
+//! account‑update firehose instead of waiting behind it, and a lost packet only stalls the
+//! channel it belongs to.
+//!
+//! A stream starts with its channel ID as a varint, followed by `varint length | payload`
+//! frames, one encoded value each. On the receiving side, [`accept_channel`] yields a
+//! [`QuicChannel`] for every stream the peer opens.
+//!
+//! ```ignore
+//! use lencode::prelude::*;
+//! use lencode::quic::{QuicMux, accept_channel};
+//!
+//! const ACCOUNTS: usize = 0;
+//! const CONTROL: usize = 1;
+//!
+//! // Sender
+//! let mut mux = QuicMux::new(connection).with_priority(CONTROL, 10);
+//! mux.send(ACCOUNTS, &update).await?;
+//! mux.send(CONTROL, &Heartbeat { slot }).await?;
+//!
+//! // Receiver
+//! while let Some(mut channel) = accept_channel(&connection).await? {
+//!     tokio::spawn(async move {
+//!         while let Some(frame) = channel.recv_frame().await? {
+//!             route(channel.id(), frame);
+//!         }
+//!         Ok::<_, Error>(())
+//!     });
+//! }
+//! ```
+//!
+//! Frames on different channels are not ordered relative to each other, so a
+//! [`DedupeEncoder`] or [`DiffEncoder`] must not be shared between channels.
+use hashbrown::HashMap;
+use quinn::{Connection, ConnectionError, ReadExactError, RecvStream, SendStream};
+
+use crate::prelude::*;
+
+/// Sends values on QUIC streams, one stream per channel. See the [module docs](self).
+pub struct QuicMux {
+    connection: Connection,
+    streams: HashMap<usize, SendStream>,
+    priorities: HashMap<usize, i32>,
+    /// Reused buffer for the frame being sent.
+    scratch: Vec<u8>,
+}
+
+impl QuicMux {
+    /// Creates a multiplexer opening its streams on `connection`. Every channel starts at
+    /// priority 0.
+    #[inline]
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            connection,
+            streams: HashMap::new(),
+            priorities: HashMap::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Returns the multiplexer with `channel` sent at `priority`; see
+    /// [`set_priority`](Self::set_priority).
+    #[inline]
+    pub fn with_priority(mut self, channel: usize, priority: i32) -> Self {
+        self.priorities.insert(channel, priority);
+        self
+    }
+
+    /// Sets the priority of `channel`. When several streams have data waiting, quinn sends
+    /// from those with the highest priority first; equal priorities share the connection.
+    ///
+    /// Applies immediately if the channel's stream is already open.
+    pub fn set_priority(&mut self, channel: usize, priority: i32) -> Result<()> {
+        self.priorities.insert(channel, priority);
+        if let Some(stream) = self.streams.get(&channel) {
+            stream
+                .set_priority(priority)
+                .map_err(std::io::Error::from)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the priority of `channel`.
+    #[inline]
+    pub fn priority(&self, channel: usize) -> i32 {
+        self.priorities.get(&channel).copied().unwrap_or(0)
+    }
+
+    /// Encodes `value` as one frame on `channel`, opening the channel's stream first if
+    /// needed. Returns the number of bytes written, including the frame header (and the
+    /// stream header, for the first frame).
+    pub async fn send<T: Encode + ?Sized>(&mut self, channel: usize, value: &T) -> Result<usize> {
+        let mut header = Vec::new();
+        if !self.streams.contains_key(&channel) {
+            let stream = self
+                .connection
+                .open_uni()
+                .await
+                .map_err(std::io::Error::from)?;
+            stream
+                .set_priority(self.priority(channel))
+                .map_err(std::io::Error::from)?;
+            self.streams.insert(channel, stream);
+            Lencode::encode_varint_u64(channel as u64, &mut header)?;
+        }
+        self.scratch.clear();
+        value.encode(&mut self.scratch)?;
+        Lencode::encode_varint_u64(self.scratch.len() as u64, &mut header)?;
+        let stream = self.streams.get_mut(&channel).ok_or(Error::InvalidData)?;
+        stream
+            .write_all(&header)
+            .await
+            .map_err(std::io::Error::from)?;
+        stream
+            .write_all(&self.scratch)
+            .await
+            .map_err(std::io::Error::from)?;
+        Ok(header.len() + self.scratch.len())
+    }
+
+    /// Finishes the stream of `channel`, telling the peer no more frames follow. A later
+    /// [`send`](Self::send) on the channel opens a new stream.
+    pub fn finish(&mut self, channel: usize) -> Result<()> {
+        if let Some(mut stream) = self.streams.remove(&channel) {
+            stream.finish().map_err(std::io::Error::from)?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the streams of every channel.
+    pub fn finish_all(&mut self) -> Result<()> {
+        for (_, mut stream) in self.streams.drain() {
+            stream.finish().map_err(std::io::Error::from)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the underlying connection.
+    #[inline(always)]
+    pub const fn connection(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+/// Accepts the next channel stream the peer opens on `connection`.
+///
+/// Returns `Ok(None)` once the connection has been closed by either side.
+pub async fn accept_channel(connection: &Connection) -> Result<Option<QuicChannel>> {
+    let mut stream = match connection.accept_uni().await {
+        Ok(stream) => stream,
+        Err(ConnectionError::ApplicationClosed(_) | ConnectionError::LocallyClosed) => {
+            return Ok(None);
+        }
+        Err(err) => return Err(std::io::Error::from(err).into()),
+    };
+    let id = read_varint(&mut stream)
+        .await?
+        .ok_or(Error::ReaderOutOfData)?;
+    Ok(Some(QuicChannel {
+        id: checked_usize(id)?,
+        stream,
+        frame: Vec::new(),
+        max_frame_len: 16 << 20,
+    }))
+}
+
+/// The receiving end of one channel's stream, from [`accept_channel`].
+pub struct QuicChannel {
+    id: usize,
+    stream: RecvStream,
+    frame: Vec<u8>,
+    max_frame_len: usize,
+}
+
+impl QuicChannel {
+    /// Returns the channel ID the sender used.
+    #[inline(always)]
+    pub const fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Sets the largest frame accepted, 16 MiB by default. Larger frames fail with
+    /// [`Error::IncorrectLength`] before anything is allocated for them.
+    #[inline(always)]
+    pub const fn with_max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    /// Reads the next frame's payload. Returns `Ok(None)` when the sender finished the stream
+    /// between frames.
+    pub async fn recv_frame(&mut self) -> Result<Option<&[u8]>> {
+        let Some(len) = read_varint(&mut self.stream).await? else {
+            return Ok(None);
+        };
+        let len = checked_usize(len)?;
+        if len > self.max_frame_len {
+            return Err(Error::IncorrectLength);
+        }
+        self.frame.clear();
+        self.frame.resize(len, 0);
+        read_exact(&mut self.stream, &mut self.frame).await?;
+        Ok(Some(&self.frame))
+    }
+
+    /// Reads and decodes the next frame. Returns `Ok(None)` when the sender finished the
+    /// stream between frames.
+    ///
+    /// Fails with [`Error::LengthMismatch`] if the frame holds more than one `T`.
+    pub async fn recv<T: Decode>(&mut self) -> Result<Option<T>> {
+        let Some(frame) = self.recv_frame().await? else {
+            return Ok(None);
+        };
+        let mut cursor = Cursor::new(frame);
+        let value = T::decode(&mut cursor)?;
+        if cursor.position() != frame.len() {
+            return Err(Error::LengthMismatch {
+                expected: frame.len(),
+                actual: cursor.position(),
+            });
+        }
+        Ok(Some(value))
+    }
+
+    /// Returns the underlying stream.
+    #[inline(always)]
+    pub fn into_inner(self) -> RecvStream {
+        self.stream
+    }
+}
+
+/// Fills `buf` from `stream`, failing with [`Error::ReaderOutOfData`] if the stream ends first.
+async fn read_exact(stream: &mut RecvStream, buf: &mut [u8]) -> Result<()> {
+    match stream.read_exact(buf).await {
+        Ok(()) => Ok(()),
+        Err(ReadExactError::FinishedEarly(_)) => Err(Error::ReaderOutOfData),
+        Err(ReadExactError::ReadError(err)) => Err(std::io::Error::from(err).into()),
+    }
+}
+
+/// Reads one varint from `stream`, or `None` if the stream ends before its first byte.
+async fn read_varint(stream: &mut RecvStream) -> Result<Option<u64>> {
+    let mut bytes = [0u8; 9];
+    match stream.read_exact(&mut bytes[..1]).await {
+        Ok(()) => {}
+        Err(ReadExactError::FinishedEarly(_)) => return Ok(None),
+        Err(ReadExactError::ReadError(err)) => return Err(std::io::Error::from(err).into()),
+    }
+    let extra = if bytes[0] & 0x80 == 0 {
+        0
+    } else {
+        (bytes[0] & 0x7F) as usize
+    };
+    if extra > 8 {
+        return Err(Error::InvalidData);
+    }
+    read_exact(stream, &mut bytes[1..1 + extra]).await?;
+    Lencode::decode_varint_u64(&mut Cursor::new(&bytes[..1 + extra])).map(Some)
+}
+
+#[test]
+fn test_channels_travel_on_prioritized_streams() {
+    use std::sync::Arc;
+
+    use quinn::rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer};
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let cert = rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+        let cert_der = CertificateDer::from(cert.cert);
+        let key = PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
+        let server_config =
+            quinn::ServerConfig::with_single_cert(vec![cert_der.clone()], key.into()).unwrap();
+        let server =
+            quinn::Endpoint::server(server_config, "127.0.0.1:0".parse().unwrap()).unwrap();
+        let mut roots = quinn::rustls::RootCertStore::empty();
+        roots.add(cert_der).unwrap();
+        let mut client = quinn::Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        client.set_default_client_config(
+            quinn::ClientConfig::with_root_certificates(Arc::new(roots)).unwrap(),
+        );
+
+        let address = server.local_addr().unwrap();
+        let (sender, receiver) = tokio::join!(
+            async { client.connect(address, "localhost").unwrap().await.unwrap() },
+            async { server.accept().await.unwrap().await.unwrap() },
+        );
+
+        let mut mux = QuicMux::new(sender.clone()).with_priority(1, 10);
+        for i in 0..100u64 {
+            mux.send(0, &(i, vec![i as u8; 64])).await.unwrap();
+        }
+        let n = mux.send(1, &String::from("ping")).await.unwrap();
+        assert_eq!(n, 1 + 1 + 5);
+        assert_eq!((mux.priority(0), mux.priority(1)), (0, 10));
+        mux.finish_all().unwrap();
+
+        let mut channels = Vec::new();
+        for _ in 0..2 {
+            channels.push(accept_channel(&receiver).await.unwrap().unwrap());
+        }
+        channels.sort_by_key(QuicChannel::id);
+        let mut control = channels.pop().unwrap();
+        let mut accounts = channels.pop().unwrap();
+        assert_eq!(control.recv::<String>().await.unwrap().unwrap(), "ping");
+        assert!(control.recv::<String>().await.unwrap().is_none());
+        for i in 0..100u64 {
+            let (seq, data) = accounts.recv::<(u64, Vec<u8>)>().await.unwrap().unwrap();
+            assert_eq!((seq, data.len()), (i, 64));
+        }
+        assert!(accounts.recv_frame().await.unwrap().is_none());
+
+        sender.close(0u32.into(), b"done");
+        assert!(accept_channel(&receiver).await.unwrap().is_none());
+    });
+}
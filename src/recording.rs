@@ -0,0 +1,303 @@
+//! Capturing the bytes behind each decoded value.
+//!
+//! When a long‑running consumer hits a value it cannot decode, the stream that produced it is
+//! usually gone, and shipping whole captures to reproduce the bug is impractical.
+//! [`RecordingReader`] wraps a [`Read`] and remembers, for every top‑level value decoded
+//! through [`RecordingReader::decode`], the exact bytes that value consumed and where it
+//! started. Failed decodes are kept, with the error and the type being decoded; the most
+//! recent successful spans are kept too (see [`RecordingReader::with_history`]), since the
+//! value before a failure often explains it. With the `std` feature,
+//! [`RecordingReader::dump_failures`] writes each failing span to its own file.
+//!
+//! Bytes consumed through [`Read::buf`]/[`Read::advance`] (the zero‑copy paths of in‑memory
+//! readers) are recorded as well.
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! let mut stream = Vec::new();
+//! encode(&(1u32, String::from("ok")), &mut stream).unwrap();
+//! stream.extend_from_slice(&[0x04, 0xFF, 0xFE]); // a string of invalid UTF-8
+//!
+//! let mut reader = RecordingReader::new(Cursor::new(&stream));
+//! reader.decode::<(u32, String)>().unwrap();
+//! assert!(reader.decode::<(u32, String)>().is_err());
+//!
+//! let failure = &reader.failures()[0];
+//! assert_eq!(failure.offset, 4);
+//! assert!(failure.type_name.ends_with("(u32, alloc::string::String)"));
+//! assert_eq!(reader.history()[0].bytes, stream[..4]);
+//! ```
+#[cfg(not(feature = "std"))]
+use alloc::{
+    collections::VecDeque,
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+use crate::prelude::*;
+
+/// The bytes one top‑level decode consumed, recorded by [`RecordingReader`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedSpan {
+    /// Position of the first byte in the stream, counted from where the reader was wrapped.
+    pub offset: u64,
+    /// The bytes consumed, in order. For a failed decode, everything read up to the failure.
+    pub bytes: Vec<u8>,
+    /// The type being decoded, as reported by [`core::any::type_name`].
+    pub type_name: &'static str,
+    /// The decode error's message, if the decode failed.
+    pub error: Option<String>,
+    /// For a failed decode on a zero‑copy reader, up to [`TRAILING_BYTES`] bytes that
+    /// followed the span and were still unread. A decode can fail on data it inspected through
+    /// [`Read::buf`] without consuming it, so these are often the bytes at fault.
+    pub trailing: Vec<u8>,
+}
+
+/// How many unread bytes [`RecordingReader`] keeps after a failed span; see
+/// [`RecordedSpan::trailing`].
+pub const TRAILING_BYTES: usize = 64;
+
+/// A [`Read`] adapter that records the bytes each decoded value consumed. See the
+/// [module docs](self).
+pub struct RecordingReader<R: Read> {
+    inner: R,
+    position: u64,
+    current: Vec<u8>,
+    history: VecDeque<RecordedSpan>,
+    history_len: usize,
+    failures: Vec<RecordedSpan>,
+}
+
+impl<R: Read> RecordingReader<R> {
+    /// Wraps `inner`, keeping the spans of the last 8 successful decodes.
+    #[inline]
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            position: 0,
+            current: Vec::new(),
+            history: VecDeque::new(),
+            history_len: 8,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Sets how many successful spans are kept, oldest dropped first. `0` keeps failures only.
+    #[inline]
+    pub fn with_history(mut self, history_len: usize) -> Self {
+        self.history_len = history_len;
+        self.history.truncate(history_len);
+        self
+    }
+
+    /// Decodes one `T`, recording the bytes it consumes.
+    #[inline(always)]
+    pub fn decode<T: Decode>(&mut self) -> Result<T> {
+        self.decode_ext(None)
+    }
+
+    /// Like [`decode`](Self::decode), with an optional [`DecoderContext`].
+    pub fn decode_ext<T: Decode>(&mut self, ctx: Option<&mut DecoderContext>) -> Result<T> {
+        self.current.clear();
+        let offset = self.position;
+        let result = T::decode_ext(self, ctx);
+        let error = result.as_ref().err().map(ToString::to_string);
+        let trailing = match (&error, self.inner.buf()) {
+            (Some(_), Some(buf)) => buf[..buf.len().min(TRAILING_BYTES)].to_vec(),
+            _ => Vec::new(),
+        };
+        let span = RecordedSpan {
+            offset,
+            bytes: core::mem::take(&mut self.current),
+            type_name: core::any::type_name::<T>(),
+            error,
+            trailing,
+        };
+        if span.error.is_some() {
+            self.failures.push(span);
+        } else if self.history_len > 0 {
+            if self.history.len() == self.history_len {
+                self.history.pop_front();
+            }
+            self.history.push_back(span);
+        }
+        result
+    }
+
+    /// Returns the spans of failed decodes, in the order they happened.
+    #[inline(always)]
+    pub fn failures(&self) -> &[RecordedSpan] {
+        &self.failures
+    }
+
+    /// Returns the spans of the most recent successful decodes, oldest first.
+    #[inline(always)]
+    pub const fn history(&self) -> &VecDeque<RecordedSpan> {
+        &self.history
+    }
+
+    /// Removes and returns the recorded failures.
+    #[inline(always)]
+    pub fn take_failures(&mut self) -> Vec<RecordedSpan> {
+        core::mem::take(&mut self.failures)
+    }
+
+    /// Returns the number of bytes read so far.
+    #[inline(always)]
+    pub const fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Returns a reference to the underlying reader.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the underlying reader.
+    #[inline(always)]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Writes each recorded failure to `dir` as `failure-<offset>.bin`, holding the span's
+    /// bytes followed by its [trailing](RecordedSpan::trailing) bytes, and
+    /// `failure-<offset>.txt`, naming the type and the error. The `.bin` file can be fed
+    /// straight back to `decode::<T>` to reproduce the failure. Creates `dir` if needed and
+    /// returns the paths of the `.bin` files.
+    #[cfg(feature = "std")]
+    pub fn dump_failures(
+        &self,
+        dir: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<Vec<std::path::PathBuf>> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let mut paths = Vec::with_capacity(self.failures.len());
+        for span in &self.failures {
+            let path = dir.join(format!("failure-{}.bin", span.offset));
+            std::fs::write(&path, [&span.bytes[..], &span.trailing[..]].concat())?;
+            std::fs::write(
+                path.with_extension("txt"),
+                format!(
+                    "type: {}\noffset: {}\nlength: {}\ntrailing: {}\nerror: {}\n",
+                    span.type_name,
+                    span.offset,
+                    span.bytes.len(),
+                    span.trailing.len(),
+                    span.error.as_deref().unwrap_or_default()
+                ),
+            )?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+impl<R: Read> Read for RecordingReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.current.extend_from_slice(&buf[..n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+
+    #[inline(always)]
+    fn buf(&self) -> Option<&[u8]> {
+        self.inner.buf()
+    }
+
+    #[inline]
+    fn advance(&mut self, n: usize) {
+        if let Some(buf) = self.inner.buf() {
+            self.current.extend_from_slice(&buf[..n.min(buf.len())]);
+        }
+        self.inner.advance(n);
+        self.position += n as u64;
+    }
+}
+
+#[test]
+fn test_recording_reader_keeps_history_and_failures() {
+    /// A reader without a borrowed buffer, so decodes go through `read`.
+    struct Stream<'a>(&'a [u8]);
+
+    impl Read for Stream<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            if self.0.is_empty() && !buf.is_empty() {
+                return Err(Error::ReaderOutOfData);
+            }
+            let n = buf.len().min(self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    let mut stream = Vec::new();
+    let mut starts = Vec::new();
+    for i in 0..5u64 {
+        starts.push(stream.len());
+        encode(&(i << 20, vec![i as u8; i as usize]), &mut stream).unwrap();
+    }
+    // A vector that claims three elements but ends after one.
+    stream.extend_from_slice(&[3, 0x01]);
+
+    let mut zero_copy = RecordingReader::new(Cursor::new(&stream)).with_history(3);
+    let mut streaming = RecordingReader::new(Stream(&stream)).with_history(3);
+    for _ in 0..5 {
+        zero_copy.decode::<(u64, Vec<u8>)>().unwrap();
+        streaming.decode::<(u64, Vec<u8>)>().unwrap();
+    }
+    assert!(zero_copy.decode::<(u64, Vec<u8>)>().is_err());
+    assert!(zero_copy.decode::<u8>().is_err());
+    assert!(streaming.decode::<Vec<u64>>().is_err());
+
+    let history: Vec<_> = zero_copy.history().iter().map(|s| s.offset).collect();
+    assert_eq!(
+        history,
+        starts[2..].iter().map(|&s| s as u64).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        zero_copy.history()[2].bytes,
+        stream[starts[4]..stream.len() - 2]
+    );
+    assert_eq!(streaming.history(), zero_copy.history());
+
+    let failures = zero_copy.failures();
+    assert_eq!(failures.len(), 2);
+    assert_eq!(failures[0].offset, (stream.len() - 2) as u64);
+    assert!(failures[1].bytes.is_empty());
+    assert!(failures[1].trailing.is_empty());
+    assert!(streaming.failures()[0].trailing.is_empty());
+    assert_eq!(streaming.failures()[0].bytes, [3, 0x01]);
+    assert!(streaming.failures()[0].type_name.contains("Vec<u64>"));
+    assert_eq!(
+        streaming.failures()[0].error.as_deref(),
+        Some(Error::ReaderOutOfData.to_string().as_str())
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_recording_reader_dumps_failing_spans() {
+    let mut stream = Vec::new();
+    encode(&7u32, &mut stream).unwrap();
+    stream.extend_from_slice(&[0x81]); // a varint missing its payload byte
+
+    let mut reader = RecordingReader::new(Cursor::new(&stream)).with_history(0);
+    assert_eq!(reader.decode::<u32>().unwrap(), 7);
+    assert!(reader.decode::<u32>().is_err());
+    assert!(reader.history().is_empty());
+
+    let dir = std::env::temp_dir().join(format!("lencode-recording-{}", std::process::id()));
+    let paths = reader.dump_failures(&dir).unwrap();
+    assert_eq!(paths, [dir.join("failure-1.bin")]);
+    assert_eq!(std::fs::read(&paths[0]).unwrap(), [0x81]);
+    let report = std::fs::read_to_string(dir.join("failure-1.txt")).unwrap();
+    assert!(report.starts_with("type: u32\noffset: 1\nlength: 0\ntrailing: 1\n"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
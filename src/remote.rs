@@ -0,0 +1,397 @@
+//! Field‑by‑field [`Encode`]/[`Decode`] impls for types defined elsewhere.
+//!
+//! [`impl_lencode_remote!`](crate::impl_lencode_remote) takes a copy of a type's definition
+//! (field names and types, or variants) and generates the same impls `#[derive(Encode,
+//! Decode)]` would, without access to the type's source. The layout matches the derive:
+//! fields in the order listed, enums prefixed by the variant's position as a discriminant.
+//!
+//! ```
+//! # mod external {
+//! #     pub struct Point { pub x: u32, pub y: u32 }
+//! # }
+//! use lencode::prelude::*;
+//!
+//! // The orphan rule forbids implementing lencode's traits for another crate's type, so
+//! // `as` generates a local `#[repr(transparent)]` proxy that converts to and from it.
+//! lencode::impl_lencode_remote!(struct external::Point { x: u32, y: u32 } as PointDef);
+//!
+//! let point = PointDef(external::Point { x: 3, y: 4 });
+//! let mut buf = Vec::new();
+//! encode(&point, &mut buf).unwrap();
+//! let decoded: external::Point = decode::<PointDef>(&mut Cursor::new(&buf)).unwrap().into();
+//! assert_eq!((decoded.x, decoded.y), (3, 4));
+//! ```
+//!
+//! Without `as`, the impls are for the named type itself, which is what this crate uses for
+//! third‑party types behind its feature flags. Every field type must itself implement
+//! `Encode`/`Decode` (a proxy cannot stand in for a nested remote type), all fields must be
+//! listed and public, and generic types are not supported. Tuple structs and tuple variants
+//! take up to 16 fields.
+
+/// Returns the position of `name` in `names`, for the discriminants written by
+/// [`impl_lencode_remote!`](crate::impl_lencode_remote).
+#[doc(hidden)]
+pub const fn variant_index(names: &[&str], name: &str) -> usize {
+    let mut idx = 0;
+    while idx < names.len() {
+        let candidate = names[idx].as_bytes();
+        let name = name.as_bytes();
+        if candidate.len() == name.len() {
+            let mut i = 0;
+            while i < name.len() && candidate[i] == name[i] {
+                i += 1;
+            }
+            if i == name.len() {
+                return idx;
+            }
+        }
+        idx += 1;
+    }
+    panic!("variant not listed")
+}
+
+/// Implements [`Encode`](crate::Encode) and [`Decode`](crate::Decode) for a type from a
+/// restatement of its definition. See the [module docs](crate::remote).
+///
+/// ```ignore
+/// impl_lencode_remote!(struct path::Named { a: u32, b: String });
+/// impl_lencode_remote!(struct path::Tuple(u64, Vec<u8>));
+/// impl_lencode_remote!(struct path::Unit);
+/// impl_lencode_remote!(enum path::Kind { A, B(u8), C { x: u16 } });
+/// // Local proxy for foreign types:
+/// impl_lencode_remote!(struct other::Named { a: u32, b: String } as NamedDef);
+/// ```
+#[macro_export]
+macro_rules! impl_lencode_remote {
+    (struct $($seg:ident)::+ { $($field:ident : $ty:ty),* $(,)? } $(as $proxy:ident)?) => {
+        $crate::impl_lencode_remote!(
+            @proxy [$($seg)::+] [$($proxy)?] struct { $(($field $field $ty))* }
+        );
+    };
+    (struct $($seg:ident)::+ ( $($ty:ty),* $(,)? ) $(as $proxy:ident)?) => {
+        $crate::impl_lencode_remote!(@proxy [$($seg)::+] [$($proxy)?] tuple ($($ty,)*));
+    };
+    (struct $($seg:ident)::+ $(as $proxy:ident)?) => {
+        $crate::impl_lencode_remote!(@proxy [$($seg)::+] [$($proxy)?] struct {});
+    };
+    (enum $($seg:ident)::+ { $($variants:tt)* } $(as $proxy:ident)?) => {
+        $crate::impl_lencode_remote!(@proxy [$($seg)::+] [$($proxy)?] enum { $($variants)* });
+    };
+
+    (@proxy [$($seg:ident)::+] [] $($rest:tt)*) => {
+        $crate::impl_lencode_remote!(@shape [[$($seg)::+] [$($seg)::+]] $($rest)*);
+    };
+    (@proxy [$($seg:ident)::+] [$proxy:ident] $($rest:tt)*) => {
+        #[doc = concat!("Encoding proxy for `", stringify!($($seg)::+), "`.")]
+        #[repr(transparent)]
+        pub struct $proxy(pub $($seg)::+);
+
+        impl ::core::convert::From<$($seg)::+> for $proxy {
+            #[inline(always)]
+            fn from(value: $($seg)::+) -> Self {
+                Self(value)
+            }
+        }
+
+        impl ::core::convert::From<$proxy> for $($seg)::+ {
+            #[inline(always)]
+            fn from(proxy: $proxy) -> Self {
+                proxy.0
+            }
+        }
+
+        impl ::core::borrow::Borrow<$($seg)::+> for $proxy {
+            #[inline(always)]
+            fn borrow(&self) -> &$($seg)::+ {
+                &self.0
+            }
+        }
+
+        $crate::impl_lencode_remote!(@shape [[$($seg)::+] [$proxy]] $($rest)*);
+    };
+
+    (@shape $hdr:tt struct { $($fields:tt)* }) => {
+        $crate::impl_lencode_remote!(@struct $hdr { $($fields)* });
+    };
+    (@shape $hdr:tt tuple ($($ty:ty,)*)) => {
+        $crate::impl_lencode_remote!(
+            @tuple $hdr []
+            [(f0 0) (f1 1) (f2 2) (f3 3) (f4 4) (f5 5) (f6 6) (f7 7)
+             (f8 8) (f9 9) (f10 10) (f11 11) (f12 12) (f13 13) (f14 14) (f15 15)]
+            [$($ty,)*]
+        );
+    };
+    (@shape $hdr:tt enum { $($variants:tt)* }) => {
+        $crate::impl_lencode_remote!(@variants $hdr [] $($variants)*);
+    };
+
+    // Pairs each tuple field type with a binding name and its index.
+    (@tuple $hdr:tt [$($done:tt)*] [($bind:ident $key:tt) $($pool:tt)*] [$ty:ty, $($rest:ty,)*]) => {
+        $crate::impl_lencode_remote!(
+            @tuple $hdr [$($done)* ($key $bind $ty)] [$($pool)*] [$($rest,)*]
+        );
+    };
+    (@tuple $hdr:tt [$($done:tt)*] [$($pool:tt)*] []) => {
+        $crate::impl_lencode_remote!(@struct $hdr { $($done)* });
+    };
+
+    // Normalizes each variant to `Name { (key binding type)* }`.
+    (@variants $hdr:tt [$($done:tt)*] $variant:ident { $($field:ident : $ty:ty),* $(,)? } $(, $($rest:tt)*)?) => {
+        $crate::impl_lencode_remote!(
+            @variants $hdr [$($done)* $variant { $(($field $field $ty))* }] $($($rest)*)?
+        );
+    };
+    (@variants $hdr:tt [$($done:tt)*] $variant:ident ( $($ty:ty),* $(,)? ) $(, $($rest:tt)*)?) => {
+        $crate::impl_lencode_remote!(
+            @variant_tuple $hdr [$($done)*] $variant []
+            [(f0 0) (f1 1) (f2 2) (f3 3) (f4 4) (f5 5) (f6 6) (f7 7)
+             (f8 8) (f9 9) (f10 10) (f11 11) (f12 12) (f13 13) (f14 14) (f15 15)]
+            [$($ty,)*] [$($($rest)*)?]
+        );
+    };
+    (@variants $hdr:tt [$($done:tt)*] $variant:ident $(, $($rest:tt)*)?) => {
+        $crate::impl_lencode_remote!(@variants $hdr [$($done)* $variant {}] $($($rest)*)?);
+    };
+    (@variants $hdr:tt [$($done:tt)*]) => {
+        $crate::impl_lencode_remote!(@enum $hdr { $($done)* });
+    };
+    (@variant_tuple $hdr:tt [$($done:tt)*] $variant:ident [$($fields:tt)*]
+        [($bind:ident $key:tt) $($pool:tt)*] [$ty:ty, $($rest:ty,)*] $tail:tt) => {
+        $crate::impl_lencode_remote!(
+            @variant_tuple $hdr [$($done)*] $variant [$($fields)* ($key $bind $ty)]
+            [$($pool)*] [$($rest,)*] $tail
+        );
+    };
+    (@variant_tuple $hdr:tt [$($done:tt)*] $variant:ident [$($fields:tt)*]
+        [$($pool:tt)*] [] [$($tail:tt)*]) => {
+        $crate::impl_lencode_remote!(
+            @variants $hdr [$($done)* $variant { $($fields)* }] $($tail)*
+        );
+    };
+
+    (@struct [[$remote:ty] [$target:ty]] { $(($key:tt $bind:ident $ty:ty))* }) => {
+        impl $crate::prelude::Encode for $target {
+            #[inline(always)]
+            #[allow(unused_mut, unused_variables)]
+            fn encode_ext(
+                &self,
+                writer: &mut impl $crate::io::Write,
+                mut ctx: Option<&mut $crate::context::EncoderContext>,
+            ) -> $crate::Result<usize> {
+                type Remote = $remote;
+                let Remote { $($key: $bind),* } =
+                    ::core::borrow::Borrow::<Remote>::borrow(self);
+                let mut total_bytes = 0;
+                $(
+                    total_bytes += $crate::impl_lencode_remote!(
+                        @field Remote, writer, ctx, stringify!($key), $bind, $ty
+                    );
+                )*
+                Ok(total_bytes)
+            }
+        }
+
+        impl $crate::prelude::Decode for $target {
+            #[inline(always)]
+            #[allow(unused_mut, unused_variables)]
+            fn decode_ext(
+                reader: &mut impl $crate::io::Read,
+                mut ctx: Option<&mut $crate::context::DecoderContext>,
+            ) -> $crate::Result<Self> {
+                type Remote = $remote;
+                let value = Remote {
+                    $($key: <$ty as $crate::prelude::Decode>::decode_ext(
+                        reader,
+                        ctx.as_deref_mut(),
+                    )?,)*
+                };
+                Ok(::core::convert::From::from(value))
+            }
+        }
+    };
+
+    (@enum [[$remote:ty] [$target:ty]] {
+        $($variant:ident { $(($key:tt $bind:ident $ty:ty))* })*
+    }) => {
+        impl $crate::prelude::Encode for $target {
+            #[inline(always)]
+            #[allow(unused_mut, unused_variables)]
+            fn encode_ext(
+                &self,
+                writer: &mut impl $crate::io::Write,
+                mut ctx: Option<&mut $crate::context::EncoderContext>,
+            ) -> $crate::Result<usize> {
+                type Remote = $remote;
+                const VARIANTS: &[&str] = &[$(stringify!($variant)),*];
+                match ::core::borrow::Borrow::<Remote>::borrow(self) {
+                    $(
+                        Remote::$variant { $($key: $bind),* } => {
+                            let disc = const {
+                                $crate::remote::variant_index(VARIANTS, stringify!($variant))
+                            };
+                            let mut total_bytes =
+                                <usize as $crate::prelude::Encode>::encode_discriminant(
+                                    disc, writer,
+                                )?;
+                            $(
+                                total_bytes += $crate::impl_lencode_remote!(
+                                    @field Remote, writer, ctx,
+                                    concat!(stringify!($variant), ".", stringify!($key)),
+                                    $bind, $ty
+                                );
+                            )*
+                            Ok(total_bytes)
+                        }
+                    )*
+                }
+            }
+        }
+
+        impl $crate::prelude::Decode for $target {
+            #[inline(always)]
+            #[allow(unused_mut, unused_variables)]
+            fn decode_ext(
+                reader: &mut impl $crate::io::Read,
+                mut ctx: Option<&mut $crate::context::DecoderContext>,
+            ) -> $crate::Result<Self> {
+                type Remote = $remote;
+                const VARIANTS: &[&str] = &[$(stringify!($variant)),*];
+                let disc = <usize as $crate::prelude::Decode>::decode_discriminant(reader)?;
+                let value = match disc {
+                    $(
+                        disc if disc == const {
+                            $crate::remote::variant_index(VARIANTS, stringify!($variant))
+                        } => Remote::$variant {
+                            $($key: <$ty as $crate::prelude::Decode>::decode_ext(
+                                reader,
+                                ctx.as_deref_mut(),
+                            )?,)*
+                        },
+                    )*
+                    disc => return Err($crate::io::Error::UnknownDiscriminant(disc as u64)),
+                };
+                Ok(::core::convert::From::from(value))
+            }
+        }
+    };
+
+    (@field $remote:ty, $writer:ident, $ctx:ident, $label:expr, $bind:ident, $ty:ty) => {{
+        let field_bytes =
+            <$ty as $crate::prelude::Encode>::encode_ext($bind, $writer, $ctx.as_deref_mut())?;
+        $crate::context::EncoderContext::record_field(
+            $ctx.as_deref_mut(),
+            ::core::any::type_name::<$remote>(),
+            $label,
+            field_bytes,
+        );
+        field_bytes
+    }};
+}
+
+#[cfg(test)]
+mod external {
+    #[derive(Debug, PartialEq)]
+    pub struct Header {
+        pub id: u64,
+        pub tag: Option<u32>,
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Span(pub u32, pub u32);
+
+    #[derive(Debug, PartialEq)]
+    pub struct Marker;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Event {
+        Start,
+        Data(Span, [u16; 3]),
+        End { header: Header, ok: bool },
+    }
+}
+
+#[cfg(test)]
+impl_lencode_remote!(struct external::Header { id: u64, tag: Option<u32> });
+#[cfg(test)]
+impl_lencode_remote!(struct external::Span(u32, u32));
+#[cfg(test)]
+impl_lencode_remote!(struct external::Marker);
+#[cfg(test)]
+impl_lencode_remote!(enum external::Event {
+    Start,
+    Data(external::Span, [u16; 3]),
+    End { header: external::Header, ok: bool },
+});
+#[cfg(test)]
+impl_lencode_remote!(struct external::Span(u32, u32) as SpanDef);
+
+#[test]
+fn test_remote_impls_roundtrip_with_derive_layout() {
+    use crate::prelude::*;
+    use external::*;
+
+    let events = [
+        Event::Start,
+        Event::Data(Span(1, 300), [9, 8, 700]),
+        Event::End {
+            header: Header {
+                id: 42,
+                tag: Some(7),
+            },
+            ok: true,
+        },
+    ];
+    for (disc, event) in events.iter().enumerate() {
+        let mut buf = Vec::new();
+        encode(event, &mut buf).unwrap();
+        assert_eq!(buf[0] as usize, disc);
+        let decoded: Event = decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(&decoded, event);
+    }
+
+    // Fields are written in order with no framing, exactly like `#[derive(Encode)]`.
+    let mut expected = Vec::new();
+    encode(&42u64, &mut expected).unwrap();
+    encode(&Some(7u32), &mut expected).unwrap();
+    let mut buf = Vec::new();
+    let header = Header {
+        id: 42,
+        tag: Some(7),
+    };
+    assert_eq!(encode(&header, &mut buf).unwrap(), expected.len());
+    assert_eq!(buf, expected);
+
+    let mut buf = Vec::new();
+    assert_eq!(encode(&Marker, &mut buf).unwrap(), 0);
+    assert_eq!(decode::<Marker>(&mut Cursor::new(&buf)).unwrap(), Marker);
+
+    let mut buf = Vec::new();
+    encode(&SpanDef(Span(5, 6)), &mut buf).unwrap();
+    let mut direct = Vec::new();
+    encode(&Span(5, 6), &mut direct).unwrap();
+    assert_eq!(buf, direct);
+    let span: Span = decode::<SpanDef>(&mut Cursor::new(&buf)).unwrap().into();
+    assert_eq!(span, Span(5, 6));
+
+    assert!(decode::<Event>(&mut Cursor::new(&[3u8][..])).is_err());
+}
+
+#[test]
+fn test_remote_impls_record_field_metrics() {
+    use crate::prelude::*;
+    use external::*;
+
+    let mut ctx = EncoderContext::with_metrics();
+    let event = Event::Data(Span(1, 2), [0; 3]);
+    event.encode_ext(&mut Vec::new(), Some(&mut ctx)).unwrap();
+    let metrics = ctx.metrics.unwrap();
+    let event_ty = core::any::type_name::<Event>();
+    assert_eq!(metrics.get(event_ty, "Data.1").unwrap().bytes, 3);
+    assert_eq!(
+        metrics
+            .get(core::any::type_name::<Span>(), "0")
+            .unwrap()
+            .count,
+        1
+    );
+}
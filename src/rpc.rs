@@ -0,0 +1,424 @@
+//! Request/response services over any [`Read`]/[`Write`] transport.
+//!
+//! [`service!`](crate::service) declares a set of methods, each taking one request type and
+//! returning one response type, and generates a module holding a `Service` trait to
+//! implement on the server, a typed `Client`, and a `server` constructor. Calls travel as
+//! [`mux`](crate::mux) frames whose channel is the method's position in the declaration:
+//!
+//! ```text
+//! request:  [method: varint] [len: varint] [call_id: varint] [request]
+//! response: [method: varint] [len: varint] [call_id: varint] [known: bool] [response]?
+//! ```
+//!
+//! The client sends one call at a time and waits for its response. A server that does not
+//! know the method (an older build) answers `known = false`, which the client reports as
+//! [`Error::UnknownDiscriminant`] with the method's position; adding methods at the end is
+//! therefore backward compatible. Application errors belong in the response type, e.g.
+//! `Result<Receipt, CapturedError>`.
+//!
+//! ```
+//! # #[cfg(all(feature = "std", unix))]
+//! # {
+//! use lencode::prelude::*;
+//! use std::os::unix::net::UnixStream;
+//!
+//! lencode::service! {
+//!     /// A toy account ledger.
+//!     pub mod ledger {
+//!         /// Returns the balance of an account.
+//!         fn balance(u64) -> u64;
+//!         /// Moves `amount` between two accounts and returns the new source balance.
+//!         fn transfer((u64, u64, u64)) -> Result<u64, String>;
+//!     }
+//! }
+//!
+//! struct Ledger(Vec<u64>);
+//!
+//! impl ledger::Service for Ledger {
+//!     fn balance(&mut self, account: u64) -> u64 {
+//!         self.0[account as usize]
+//!     }
+//!
+//!     fn transfer(&mut self, (from, to, amount): (u64, u64, u64)) -> Result<u64, String> {
+//!         let from = from as usize;
+//!         if self.0[from] < amount {
+//!             return Err(String::from("insufficient funds"));
+//!         }
+//!         self.0[from] -= amount;
+//!         self.0[to as usize] += amount;
+//!         Ok(self.0[from])
+//!     }
+//! }
+//!
+//! let (client_end, server_end) = UnixStream::pair().unwrap();
+//! let server = std::thread::spawn(move || {
+//!     let reader = server_end.try_clone().unwrap();
+//!     ledger::server(Ledger(vec![100, 0]), reader, server_end).run()
+//! });
+//!
+//! let mut client = ledger::Client::new(client_end.try_clone().unwrap(), client_end);
+//! assert_eq!(client.transfer(&(0, 1, 30)).unwrap(), Ok(70));
+//! assert_eq!(client.balance(&1).unwrap(), 30);
+//! assert!(client.transfer(&(1, 0, 31)).unwrap().is_err());
+//! drop(client);
+//! assert_eq!(server.join().unwrap().unwrap(), 3);
+//! # }
+//! ```
+use crate::mux::{read_frame_header, read_frame_payload};
+use crate::prelude::*;
+
+/// The server half of a service: decodes a request, calls the method and encodes its
+/// response. Implemented by the `Dispatcher` that [`service!`](crate::service) generates.
+pub trait RpcDispatch {
+    /// The method names, in declaration order.
+    const METHODS: &'static [&'static str];
+
+    /// Handles a call to the method at position `method`, decoding the request from
+    /// `request` and encoding the response to `response`. Returns `Ok(false)` without
+    /// touching either if there is no such method.
+    fn dispatch(
+        &mut self,
+        method: usize,
+        request: &mut Cursor<&[u8]>,
+        response: &mut impl Write,
+    ) -> Result<bool>;
+}
+
+/// The client half of a service: sends calls and waits for their responses. Wrapped by the
+/// `Client` that [`service!`](crate::service) generates.
+pub struct RpcClient<R: Read, W: Write> {
+    reader: R,
+    writer: MuxEncoder<W>,
+    next_call: u64,
+    /// Reused buffer for the frame being sent or received.
+    scratch: Vec<u8>,
+}
+
+impl<R: Read, W: Write> RpcClient<R, W> {
+    /// Creates a client that sends calls to `writer` and reads responses from `reader`.
+    #[inline(always)]
+    pub const fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader,
+            writer: MuxEncoder::new(writer),
+            next_call: 0,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Calls the method at position `method` and waits for its response.
+    ///
+    /// Fails with [`Error::UnknownDiscriminant`] if the server has no such method, and with
+    /// [`Error::InvalidData`] if the response does not answer this call or is not exactly
+    /// one `Resp`.
+    pub fn call<Req: Encode + ?Sized, Resp: Decode>(
+        &mut self,
+        method: usize,
+        request: &Req,
+    ) -> Result<Resp> {
+        let call = self.next_call;
+        self.next_call += 1;
+        self.scratch.clear();
+        Lencode::encode_varint_u64(call, &mut self.scratch)?;
+        request.encode(&mut self.scratch)?;
+        self.writer.write_frame(method, &self.scratch)?;
+        self.writer.flush()?;
+
+        let Some((channel, len)) = read_frame_header(&mut self.reader)? else {
+            return Err(Error::ReaderOutOfData);
+        };
+        read_frame_payload(&mut self.reader, len, &mut self.scratch)?;
+        let mut cursor = Cursor::new(&self.scratch[..]);
+        if channel != method || Lencode::decode_varint_u64(&mut cursor)? != call {
+            return Err(Error::InvalidData);
+        }
+        if !bool::decode(&mut cursor)? {
+            return Err(Error::UnknownDiscriminant(method as u64));
+        }
+        let response = Resp::decode(&mut cursor)?;
+        if cursor.position() != len {
+            return Err(Error::InvalidData);
+        }
+        Ok(response)
+    }
+
+    /// Returns the reader and writer.
+    #[inline(always)]
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer.into_inner())
+    }
+}
+
+/// Answers calls read from a transport with an [`RpcDispatch`]. Created by the `server`
+/// function that [`service!`](crate::service) generates.
+pub struct RpcServer<D: RpcDispatch, R: Read, W: Write> {
+    dispatcher: D,
+    reader: R,
+    writer: MuxEncoder<W>,
+    /// Reused buffers for the request and response being handled.
+    request: Vec<u8>,
+    response: Vec<u8>,
+}
+
+impl<D: RpcDispatch, R: Read, W: Write> RpcServer<D, R, W> {
+    /// Creates a server reading calls from `reader` and writing responses to `writer`.
+    #[inline(always)]
+    pub const fn new(dispatcher: D, reader: R, writer: W) -> Self {
+        Self {
+            dispatcher,
+            reader,
+            writer: MuxEncoder::new(writer),
+            request: Vec::new(),
+            response: Vec::new(),
+        }
+    }
+
+    /// Answers one call. Returns `Ok(false)` if `reader` is exhausted at a call boundary.
+    ///
+    /// Fails with [`Error::InvalidData`] if a request is not exactly one value of the
+    /// method's request type.
+    pub fn handle_one(&mut self) -> Result<bool> {
+        let Some((method, len)) = read_frame_header(&mut self.reader)? else {
+            return Ok(false);
+        };
+        read_frame_payload(&mut self.reader, len, &mut self.request)?;
+        let mut request = Cursor::new(&self.request[..]);
+        let call = Lencode::decode_varint_u64(&mut request)?;
+        self.response.clear();
+        Lencode::encode_varint_u64(call, &mut self.response)?;
+        let header = self.response.len();
+        true.encode(&mut self.response)?;
+        if self
+            .dispatcher
+            .dispatch(method, &mut request, &mut self.response)?
+        {
+            if request.position() != len {
+                return Err(Error::InvalidData);
+            }
+        } else {
+            self.response.truncate(header);
+            false.encode(&mut self.response)?;
+        }
+        self.writer.write_frame(method, &self.response)?;
+        self.writer.flush()?;
+        Ok(true)
+    }
+
+    /// Answers calls until `reader` is exhausted. Returns the number of calls answered.
+    pub fn run(&mut self) -> Result<usize> {
+        let mut calls = 0;
+        while self.handle_one()? {
+            calls += 1;
+        }
+        Ok(calls)
+    }
+
+    /// Returns a reference to the dispatcher.
+    #[inline(always)]
+    pub const fn dispatcher(&self) -> &D {
+        &self.dispatcher
+    }
+
+    /// Returns the dispatcher, reader and writer.
+    #[inline(always)]
+    pub fn into_inner(self) -> (D, R, W) {
+        (self.dispatcher, self.reader, self.writer.into_inner())
+    }
+}
+
+/// Declares a request/response service. See the [module docs](crate::rpc).
+///
+/// ```ignore
+/// lencode::service! {
+///     pub mod name {
+///         fn method(Request) -> Response;
+///     }
+/// }
+/// ```
+///
+/// The module contains:
+/// - `METHODS`, the method names in declaration order;
+/// - `trait Service`, with `fn method(&mut self, request: Request) -> Response`;
+/// - `struct Client<R, W>`, with `fn method(&mut self, request: &Request) -> Result<Response>`;
+/// - `struct Dispatcher<S>`, the [`RpcDispatch`](crate::rpc::RpcDispatch) for a `Service`;
+/// - `fn server(service, reader, writer)`, returning an [`RpcServer`](crate::rpc::RpcServer).
+///
+/// Names in the module body resolve as in the enclosing module.
+#[macro_export]
+macro_rules! service {
+    (
+        $(#[$attr:meta])*
+        $vis:vis mod $name:ident {
+            $(
+                $(#[$method_attr:meta])*
+                fn $method:ident($request:ty) -> $response:ty;
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        #[allow(dead_code)]
+        $vis mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            /// The method names, in declaration order.
+            pub const METHODS: &[&str] = &[$(stringify!($method)),*];
+
+            /// The methods a server implements.
+            pub trait Service {
+                $(
+                    $(#[$method_attr])*
+                    fn $method(&mut self, request: $request) -> $response;
+                )*
+            }
+
+            /// Calls the service over a transport.
+            pub struct Client<R: $crate::io::Read, W: $crate::io::Write>(
+                pub $crate::rpc::RpcClient<R, W>,
+            );
+
+            impl<R: $crate::io::Read, W: $crate::io::Write> Client<R, W> {
+                /// Creates a client that sends calls to `writer` and reads responses from
+                /// `reader`.
+                #[inline(always)]
+                pub const fn new(reader: R, writer: W) -> Self {
+                    Self($crate::rpc::RpcClient::new(reader, writer))
+                }
+
+                $(
+                    $(#[$method_attr])*
+                    #[inline]
+                    pub fn $method(
+                        &mut self,
+                        request: &$request,
+                    ) -> $crate::Result<$response> {
+                        const METHOD: usize =
+                            $crate::remote::variant_index(METHODS, stringify!($method));
+                        self.0.call(METHOD, request)
+                    }
+                )*
+            }
+
+            /// Dispatches calls to a [`Service`].
+            pub struct Dispatcher<S: Service>(pub S);
+
+            impl<S: Service> $crate::rpc::RpcDispatch for Dispatcher<S> {
+                const METHODS: &'static [&'static str] = METHODS;
+
+                fn dispatch(
+                    &mut self,
+                    method: usize,
+                    request: &mut $crate::io::Cursor<&[u8]>,
+                    response: &mut impl $crate::io::Write,
+                ) -> $crate::Result<bool> {
+                    $(
+                        if method == $crate::remote::variant_index(METHODS, stringify!($method)) {
+                            let request =
+                                <$request as $crate::Decode>::decode(request)?;
+                            $crate::Encode::encode(&self.0.$method(request), response)?;
+                            return Ok(true);
+                        }
+                    )*
+                    let _ = (request, response);
+                    Ok(false)
+                }
+            }
+
+            /// Creates a server answering calls read from `reader` with `service`.
+            #[inline(always)]
+            pub const fn server<S: Service, R: $crate::io::Read, W: $crate::io::Write>(
+                service: S,
+                reader: R,
+                writer: W,
+            ) -> $crate::rpc::RpcServer<Dispatcher<S>, R, W> {
+                $crate::rpc::RpcServer::new(Dispatcher(service), reader, writer)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+crate::service! {
+    mod counter {
+        fn add(u64) -> u64;
+        fn name(()) -> String;
+    }
+}
+
+#[cfg(test)]
+crate::service! {
+    mod counter_v1 {
+        fn add(u64) -> u64;
+    }
+}
+
+#[cfg(test)]
+struct Counter(u64);
+
+#[cfg(test)]
+impl counter::Service for Counter {
+    fn add(&mut self, n: u64) -> u64 {
+        self.0 += n;
+        self.0
+    }
+
+    fn name(&mut self, (): ()) -> String {
+        String::from("counter")
+    }
+}
+
+#[cfg(test)]
+impl counter_v1::Service for Counter {
+    fn add(&mut self, n: u64) -> u64 {
+        <Self as counter::Service>::add(self, n)
+    }
+}
+
+#[test]
+fn test_service_answers_recorded_calls() {
+    assert_eq!(counter::METHODS, ["add", "name"]);
+
+    // Record calls with a client whose responses are not read, then replay them.
+    let mut client = counter::Client::new(Cursor::new(&[][..]), Vec::new());
+    assert!(matches!(client.add(&5), Err(Error::ReaderOutOfData)));
+    assert!(client.name(&()).is_err());
+    assert!(client.add(&7).is_err());
+    let (_, calls) = client.0.into_inner();
+
+    let mut server = counter::server(Counter(0), Cursor::new(&calls[..]), Vec::new());
+    assert_eq!(server.run().unwrap(), 3);
+    assert_eq!(server.dispatcher().0.0, 12);
+    let (_, _, responses) = server.into_inner();
+
+    // Read the responses back through a client making the same calls.
+    let mut client = counter::Client::new(Cursor::new(&responses[..]), Vec::new());
+    assert_eq!(client.add(&5).unwrap(), 5);
+    assert_eq!(client.name(&()).unwrap(), "counter");
+    assert_eq!(client.add(&7).unwrap(), 12);
+    let (_, resent) = client.0.into_inner();
+    assert_eq!(resent, calls);
+}
+
+#[test]
+fn test_service_reports_unknown_methods() {
+    let mut client = counter::Client::new(Cursor::new(&[][..]), Vec::new());
+    client.name(&()).unwrap_err();
+    client.add(&1).unwrap_err();
+    let (_, calls) = client.0.into_inner();
+
+    // An older server without `name` answers it as unknown and keeps going.
+    let mut server = counter_v1::server(Counter(0), Cursor::new(&calls[..]), Vec::new());
+    assert_eq!(server.run().unwrap(), 2);
+    let (_, _, responses) = server.into_inner();
+
+    let mut client = counter::Client::new(Cursor::new(&responses[..]), Vec::new());
+    assert!(matches!(
+        client.name(&()),
+        Err(Error::UnknownDiscriminant(1))
+    ));
+    assert_eq!(client.add(&1).unwrap(), 1);
+
+    // A response to a different call is rejected.
+    let mut client = counter::Client::new(Cursor::new(&responses[..]), Vec::new());
+    assert!(matches!(client.add(&1), Err(Error::InvalidData)));
+}
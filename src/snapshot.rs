@@ -0,0 +1,310 @@
+//! Snapshot + delta update streams with sequence numbers.
+//!
+//! [`SyncEncoder`] turns successive versions of a value into a message stream: a full
+//! snapshot every `N` messages (or on demand) and [`Delta`]s against the previous version
+//! in between. [`SyncDecoder`] rebuilds the value on the consuming side. Every message
+//! carries a sequence number, so a consumer that misses a message notices the gap, ignores
+//! deltas until the next snapshot, and can ask the producer for one early
+//! ([`SyncEncoder::force_snapshot`]).
+//!
+//! ## Wire format
+//!
+//! ```text
+//! [kind: u8]          // 0 = snapshot, 1 = delta against message seq - 1
+//! [seq: varint]
+//! [body_len: varint]  // so deltas can be skipped while awaiting a snapshot
+//! [body]              // the encoded value, or its delta
+//! ```
+//!
+//! ```
+//! use lencode::prelude::*;
+//!
+//! #[derive(Encode, Decode, Delta, Clone, PartialEq, Debug)]
+//! struct Account {
+//!     lamports: u64,
+//!     data: Vec<u8>,
+//! }
+//!
+//! let mut producer = SyncEncoder::new(100);
+//! let mut account = Account { lamports: 10, data: vec![1; 64] };
+//! let mut stream = Vec::new();
+//! producer.encode(&account, &mut stream).unwrap();
+//! account.lamports = 11;
+//! producer.encode(&account, &mut stream).unwrap();
+//!
+//! let mut consumer = SyncDecoder::new();
+//! let mut reader = Cursor::new(&stream);
+//! assert_eq!(consumer.decode(&mut reader).unwrap(), SyncEvent::Snapshot { seq: 0 });
+//! assert_eq!(consumer.decode(&mut reader).unwrap(), SyncEvent::Delta { seq: 1 });
+//! assert_eq!(consumer.state(), Some(&account));
+//! ```
+use crate::prelude::*;
+
+const KIND_SNAPSHOT: u8 = 0;
+const KIND_DELTA: u8 = 1;
+
+/// Produces a snapshot/delta message stream for successive versions of a `T`.
+///
+/// Not to be confused with [`core::marker::Sync`].
+pub struct SyncEncoder<T> {
+    snapshot_every: u64,
+    since_snapshot: u64,
+    seq: u64,
+    last: Option<T>,
+    /// Reused buffer for the message body.
+    scratch: Vec<u8>,
+}
+
+impl<T: Encode + Delta + Clone> SyncEncoder<T> {
+    /// Creates an encoder that writes a full snapshot every `snapshot_every` messages
+    /// (at least every message if zero).
+    #[inline(always)]
+    pub const fn new(snapshot_every: u64) -> Self {
+        Self {
+            snapshot_every: if snapshot_every == 0 {
+                1
+            } else {
+                snapshot_every
+            },
+            since_snapshot: 0,
+            seq: 0,
+            last: None,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Returns the sequence number the next message will carry.
+    #[inline(always)]
+    pub const fn next_seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Makes the next message a full snapshot, e.g. after a consumer reported a gap.
+    #[inline(always)]
+    pub fn force_snapshot(&mut self) {
+        self.last = None;
+    }
+
+    /// Writes `value` as the next message, choosing a snapshot or a delta against the
+    /// previously written value. Returns the number of bytes written.
+    pub fn encode(&mut self, value: &T, writer: &mut impl Write) -> Result<usize> {
+        self.scratch.clear();
+        let kind = match &self.last {
+            Some(last) if self.since_snapshot < self.snapshot_every => {
+                value.encode_delta_ext(last, &mut self.scratch, None)?;
+                KIND_DELTA
+            }
+            _ => {
+                value.encode_ext(&mut self.scratch, None)?;
+                KIND_SNAPSHOT
+            }
+        };
+        let mut total = write_all(writer, &[kind])?;
+        total += Lencode::encode_varint_u64(self.seq, writer)?;
+        total += Lencode::encode_varint_u64(self.scratch.len() as u64, writer)?;
+        total += write_all(writer, &self.scratch)?;
+        self.seq += 1;
+        self.since_snapshot = if kind == KIND_SNAPSHOT {
+            1
+        } else {
+            self.since_snapshot + 1
+        };
+        match &mut self.last {
+            Some(last) => last.clone_from(value),
+            None => self.last = Some(value.clone()),
+        }
+        Ok(total)
+    }
+}
+
+/// What [`SyncDecoder::decode`] did with a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SyncEvent {
+    /// A snapshot replaced the state.
+    Snapshot { seq: u64 },
+    /// A delta was applied to the state.
+    Delta { seq: u64 },
+    /// A delta arrived out of sequence and was dropped; the state is stale until the next
+    /// snapshot. A good moment to ask the producer for one.
+    Gap { expected: u64, found: u64 },
+    /// A delta was dropped because no snapshot has been received since the last gap (or
+    /// since the start of the stream).
+    AwaitingSnapshot { seq: u64 },
+}
+
+/// Rebuilds a `T` from a [`SyncEncoder`] message stream.
+pub struct SyncDecoder<T> {
+    state: Option<T>,
+    next_seq: u64,
+    awaiting_snapshot: bool,
+}
+
+impl<T> Default for SyncDecoder<T> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SyncDecoder<T> {
+    /// Creates a decoder that waits for its first snapshot.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            state: None,
+            next_seq: 0,
+            awaiting_snapshot: true,
+        }
+    }
+
+    /// Returns the current state, or `None` before the first snapshot.
+    ///
+    /// After a [`SyncEvent::Gap`] this is the last state known to be consistent.
+    #[inline(always)]
+    pub const fn state(&self) -> Option<&T> {
+        self.state.as_ref()
+    }
+
+    /// Returns `true` if deltas are being dropped until the next snapshot.
+    #[inline(always)]
+    pub const fn is_awaiting_snapshot(&self) -> bool {
+        self.awaiting_snapshot
+    }
+
+    /// Consumes the decoder and returns the current state.
+    #[inline(always)]
+    pub fn into_state(self) -> Option<T> {
+        self.state
+    }
+}
+
+impl<T: Decode + Delta> SyncDecoder<T> {
+    /// Reads one message and updates the state.
+    ///
+    /// A message whose body does not match its declared length fails with
+    /// [`Error::InvalidData`]; the decoder then awaits a snapshot.
+    pub fn decode(&mut self, reader: &mut impl Read) -> Result<SyncEvent> {
+        let kind = u8::decode(reader)?;
+        let seq = Lencode::decode_varint_u64(reader)?;
+        let body_len = checked_usize(Lencode::decode_varint_u64(reader)?)?;
+        let body = read_vec(reader, body_len)?;
+        let mut body_reader = Cursor::new(&body[..]);
+        let event = match kind {
+            KIND_SNAPSHOT => {
+                let decoded = T::decode(&mut body_reader);
+                self.awaiting_snapshot = true;
+                self.state = Some(decoded?);
+                SyncEvent::Snapshot { seq }
+            }
+            KIND_DELTA if self.awaiting_snapshot => return Ok(SyncEvent::AwaitingSnapshot { seq }),
+            KIND_DELTA if seq != self.next_seq => {
+                self.awaiting_snapshot = true;
+                return Ok(SyncEvent::Gap {
+                    expected: self.next_seq,
+                    found: seq,
+                });
+            }
+            KIND_DELTA => {
+                let Some(state) = self.state.as_mut() else {
+                    return Err(Error::InvalidData);
+                };
+                self.awaiting_snapshot = true;
+                state.apply_delta_ext(&mut body_reader, None)?;
+                SyncEvent::Delta { seq }
+            }
+            kind => return Err(Error::UnknownDiscriminant(kind as u64)),
+        };
+        if body_reader.position() != body.len() {
+            return Err(Error::InvalidData);
+        }
+        self.awaiting_snapshot = false;
+        self.next_seq = seq + 1;
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq)]
+struct Counter(u64);
+
+#[cfg(test)]
+impl Encode for Counter {
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        self.0.encode_ext(writer, ctx)
+    }
+}
+
+#[cfg(test)]
+impl Decode for Counter {
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self(u64::decode_ext(reader, ctx)?))
+    }
+}
+
+#[cfg(test)]
+impl Delta for Counter {
+    fn encode_delta_ext(
+        &self,
+        old: &Self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        (self.0 - old.0).encode_ext(writer, ctx)
+    }
+
+    fn apply_delta_ext(
+        &mut self,
+        reader: &mut impl Read,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<()> {
+        self.0 += u64::decode_ext(reader, ctx)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_sync_snapshots_deltas_and_gaps() {
+    let mut producer = SyncEncoder::new(3);
+    let mut messages = Vec::new();
+    for value in 0..7 {
+        let mut buf = Vec::new();
+        producer.encode(&Counter(value * 10), &mut buf).unwrap();
+        messages.push(buf);
+    }
+    assert_eq!(producer.next_seq(), 7);
+    let kinds: Vec<u8> = messages.iter().map(|m| m[0]).collect();
+    assert_eq!(kinds, [0, 1, 1, 0, 1, 1, 0]);
+
+    let mut consumer = SyncDecoder::new();
+    let mut decode = |i: usize| consumer.decode(&mut Cursor::new(&messages[i]));
+    assert_eq!(decode(1).unwrap(), SyncEvent::AwaitingSnapshot { seq: 1 });
+    assert_eq!(decode(0).unwrap(), SyncEvent::Snapshot { seq: 0 });
+    assert_eq!(decode(1).unwrap(), SyncEvent::Delta { seq: 1 });
+    // Message 2 is lost.
+    assert_eq!(
+        decode(4).unwrap(),
+        SyncEvent::Gap {
+            expected: 2,
+            found: 4
+        }
+    );
+    assert_eq!(decode(5).unwrap(), SyncEvent::AwaitingSnapshot { seq: 5 });
+    assert_eq!(decode(6).unwrap(), SyncEvent::Snapshot { seq: 6 });
+    assert_eq!(consumer.state(), Some(&Counter(60)));
+    assert!(!consumer.is_awaiting_snapshot());
+
+    producer.force_snapshot();
+    let mut buf = Vec::new();
+    producer.encode(&Counter(70), &mut buf).unwrap();
+    assert_eq!(buf[0], KIND_SNAPSHOT);
+    buf.truncate(buf.len() - 1);
+    assert!(consumer.decode(&mut Cursor::new(&buf)).is_err());
+    // Unknown message kinds are reported by kind byte.
+    let res = consumer.decode(&mut Cursor::new(&[2u8, 0, 0][..]));
+    assert!(matches!(res, Err(Error::UnknownDiscriminant(2))));
+}
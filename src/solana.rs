@@ -1,16 +1,23 @@
 use agave_geyser_plugin_interface::geyser_plugin_interface as ifc;
+use bv::{Bits, BitsMut};
 use solana_account_decoder_client_types as acct_dec_client;
+use solana_address_lookup_table_interface::state as alt3;
+use solana_clock as clock3;
+use solana_epoch_info as epoch_info3;
 use solana_hash as hash3;
 use solana_instruction::error as ixerr;
 use solana_message as msg3;
 use solana_pubkey as pubkey3;
 use solana_reward_info as reward_info;
 use solana_signature as sig3;
+use solana_slot_history as slot_history3;
 use solana_transaction as tx3;
 use solana_transaction_context as txctx3;
 use solana_transaction_error as txerr3;
 use solana_transaction_status as txstatus3;
+use solana_transaction_status_client_types as ui3;
 
+use crate::prealloc_capacity;
 use crate::prelude::*;
 
 #[cfg(test)]
@@ -30,7 +37,42 @@ use tx3::versioned::VersionedTransaction;
 
 // Implementations for Agave (v3) Geyser interface and its dependencies (inline)
 
-// No serde/bincode usage in this module; all types implement Encode/Decode directly.
+// No serde/bincode usage in this module; all types implement Encode/Decode directly (only the
+// free-form `jsonParsed` payloads of RPC types are kept as JSON text).
+
+/// Decodes `count` fixed-size values stored back to back, taking all `count * N` bytes in one
+/// read instead of one value at a time.
+fn decode_fixed_stride<T, const N: usize>(
+    reader: &mut impl Read,
+    count: usize,
+    from_bytes: impl Fn([u8; N]) -> T,
+) -> Result<Vec<T>> {
+    let total = N.checked_mul(count).ok_or(Error::InvalidData)?;
+    if let Some(buf) = reader.buf()
+        && buf.len() >= total
+    {
+        let (chunks, _) = buf[..total].as_chunks::<N>();
+        let values = chunks.iter().copied().map(&from_bytes).collect();
+        reader.advance(total);
+        return Ok(values);
+    }
+    let bytes = crate::io::read_vec(reader, total)?;
+    let (chunks, _) = bytes.as_chunks::<N>();
+    Ok(chunks.iter().copied().map(from_bytes).collect())
+}
+
+/// Decodes a `Vec<Signature>` through the fixed-stride path even under a context:
+/// signatures are never deduplicated, so the context only tracks nesting depth here.
+#[inline(always)]
+fn decode_signatures(
+    reader: &mut impl Read,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<Vec<sig3::Signature>> {
+    DecoderContext::nested(ctx, |_| {
+        let len = Vec::<sig3::Signature>::decode_len(reader)?;
+        sig3::Signature::decode_vec(reader, len)
+    })
+}
 
 // Pubkey/Hash/Signature for v3 crates
 impl Pack for pubkey3::Pubkey {
@@ -46,6 +88,10 @@ impl Pack for pubkey3::Pubkey {
         }
         Ok(Self::new_from_array(buf))
     }
+    #[inline(always)]
+    fn unpack_vec(reader: &mut impl Read, count: usize) -> Result<Vec<Self>> {
+        decode_fixed_stride(reader, count, Self::new_from_array)
+    }
 }
 impl DedupeEncodeable for pubkey3::Pubkey {}
 impl DedupeDecodeable for pubkey3::Pubkey {}
@@ -83,6 +129,11 @@ impl Decode for sig3::Signature {
         let sig: [u8; sig3::SIGNATURE_BYTES] = decode(reader)?;
         Ok(Self::from(sig))
     }
+    const FLAT_DECODE_VEC: bool = true;
+    #[inline(always)]
+    fn decode_vec(reader: &mut impl Read, count: usize) -> Result<Vec<Self>> {
+        decode_fixed_stride(reader, count, Self::from)
+    }
 }
 
 // Message components (v3)
@@ -301,7 +352,7 @@ impl Decode for msg3::SanitizedMessage {
                 ctx.as_deref_mut(),
             )?)),
             1 => Ok(Self::V0(Decode::decode_ext(reader, ctx)?)),
-            _ => Err(Error::InvalidData),
+            disc => Err(Error::UnknownDiscriminant(disc as u64)),
         }
     }
 }
@@ -392,7 +443,7 @@ impl Decode for msg3::VersionedMessage {
                 ctx.as_deref_mut(),
             )?)),
             1 => Ok(Self::V0(Decode::decode_ext(reader, ctx)?)),
-            _ => Err(Error::InvalidData),
+            disc => Err(Error::UnknownDiscriminant(disc as u64)),
         }
     }
 }
@@ -412,7 +463,7 @@ impl Encode for tx3::versioned::VersionedTransaction {
 impl Decode for tx3::versioned::VersionedTransaction {
     #[inline]
     fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        let signatures = Vec::<sig3::Signature>::decode_ext(reader, ctx.as_deref_mut())?;
+        let signatures = decode_signatures(reader, ctx.as_deref_mut())?;
         let message = msg3::VersionedMessage::decode_ext(reader, ctx)?;
         Ok(Self {
             signatures,
@@ -444,7 +495,7 @@ impl Decode for tx3::sanitized::SanitizedTransaction {
         let message = msg3::SanitizedMessage::decode_ext(reader, ctx.as_deref_mut())?;
         let message_hash = hash3::Hash::decode_ext(reader, ctx.as_deref_mut())?;
         let is_simple_vote_tx = bool::decode_ext(reader, ctx.as_deref_mut())?;
-        let signatures = Vec::<sig3::Signature>::decode_ext(reader, ctx)?;
+        let signatures = decode_signatures(reader, ctx)?;
         tx3::sanitized::SanitizedTransaction::try_new_from_fields(
             message,
             message_hash,
@@ -456,80 +507,20 @@ impl Decode for tx3::sanitized::SanitizedTransaction {
 }
 
 // TransactionStatusMeta and friends
-impl Encode for txstatus3::InnerInstruction {
-    #[inline]
-    fn encode_ext(
-        &self,
-        writer: &mut impl Write,
-        mut ctx: Option<&mut EncoderContext>,
-    ) -> Result<usize> {
-        let mut n = 0;
-        n += self.instruction.encode_ext(writer, ctx.as_deref_mut())?;
-        n += self.stack_height.encode_ext(writer, ctx)?;
-        Ok(n)
-    }
-}
-impl Decode for txstatus3::InnerInstruction {
-    #[inline]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        let instruction = Decode::decode_ext(reader, ctx.as_deref_mut())?;
-        let stack_height = Decode::decode_ext(reader, ctx)?;
-        Ok(Self {
-            instruction,
-            stack_height,
-        })
-    }
-}
-impl Encode for txstatus3::InnerInstructions {
-    #[inline]
-    fn encode_ext(
-        &self,
-        writer: &mut impl Write,
-        mut ctx: Option<&mut EncoderContext>,
-    ) -> Result<usize> {
-        let mut n = 0;
-        n += self.index.encode_ext(writer, ctx.as_deref_mut())?;
-        n += self.instructions.encode_ext(writer, ctx)?;
-        Ok(n)
-    }
-}
-impl Decode for txstatus3::InnerInstructions {
-    #[inline]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        let index = Decode::decode_ext(reader, ctx.as_deref_mut())?;
-        let instructions = Decode::decode_ext(reader, ctx)?;
-        Ok(Self {
-            index,
-            instructions,
-        })
-    }
-}
-impl Encode for acct_dec_client::token::UiTokenAmount {
-    #[inline]
-    fn encode_ext(
-        &self,
-        writer: &mut impl Write,
-        mut ctx: Option<&mut EncoderContext>,
-    ) -> Result<usize> {
-        let mut n = 0;
-        n += self.ui_amount.encode_ext(writer, ctx.as_deref_mut())?;
-        n += self.decimals.encode_ext(writer, ctx.as_deref_mut())?;
-        n += self.amount.encode_ext(writer, ctx.as_deref_mut())?;
-        n += self.ui_amount_string.encode_ext(writer, ctx)?;
-        Ok(n)
-    }
-}
-impl Decode for acct_dec_client::token::UiTokenAmount {
-    #[inline]
-    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        Ok(Self {
-            ui_amount: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            decimals: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            amount: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            ui_amount_string: Decode::decode_ext(reader, ctx)?,
-        })
-    }
-}
+crate::impl_lencode_remote!(struct txstatus3::InnerInstruction {
+    instruction: msg3::compiled_instruction::CompiledInstruction,
+    stack_height: Option<u32>,
+});
+crate::impl_lencode_remote!(struct txstatus3::InnerInstructions {
+    index: u8,
+    instructions: Vec<txstatus3::InnerInstruction>,
+});
+crate::impl_lencode_remote!(struct acct_dec_client::token::UiTokenAmount {
+    ui_amount: Option<f64>,
+    decimals: u8,
+    amount: String,
+    ui_amount_string: String,
+});
 
 impl Encode for txstatus3::TransactionTokenBalance {
     #[inline]
@@ -586,7 +577,7 @@ impl Decode for reward_info::RewardType {
             1 => reward_info::RewardType::Rent,
             2 => reward_info::RewardType::Staking,
             3 => reward_info::RewardType::Voting,
-            _ => return Err(Error::InvalidData),
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
         })
     }
 }
@@ -662,280 +653,1663 @@ impl Decode for txctx3::TransactionReturnData {
         })
     }
 }
-// InstructionError encoding (direct, no serde)
-impl Encode for ixerr::InstructionError {
+/// Builds both the [`Encode`] and [`Decode`] impls for a Solana error enum from one
+/// discriminant table.
+///
+/// Each arm is `disc => Variant`, `disc => Variant(a, b)` or `disc => Variant { field }`;
+/// payload fields are encoded in order after the varint discriminant. The generated encode
+/// `match` has no wildcard arm, so when upstream adds a variant the build fails until it is
+/// given a discriminant here. The named test checks that the table is dense from `0`, that
+/// every entry decodes and re‑encodes to itself, and that the next discriminant is rejected.
+/// It also implements [`ForwardCompatibleEnum`] with the size of the table.
+macro_rules! solana_error_codec {
+    (
+        $ty:ty, $test:ident {
+            $($disc:literal => $variant:ident
+                $(( $($field:ident),+ ))?
+                $({ $($named:ident),+ })?
+            ),+ $(,)?
+        }
+    ) => {
+        impl Encode for $ty {
+            #[inline]
+            #[allow(deprecated)]
+            fn encode_ext(
+                &self,
+                writer: &mut impl Write,
+                _ctx: Option<&mut EncoderContext>,
+            ) -> Result<usize> {
+                type E = $ty;
+                match self {
+                    $(
+                        E::$variant $(( $($field),+ ))? $({ $($named),+ })? => {
+                            #[allow(unused_mut)]
+                            let mut n = <usize as Encode>::encode_discriminant($disc, writer)?;
+                            $($(n += $field.encode_ext(writer, None)?;)+)?
+                            $($(n += $named.encode_ext(writer, None)?;)+)?
+                            Ok(n)
+                        }
+                    )+
+                }
+            }
+        }
+
+        impl Decode for $ty {
+            #[inline]
+            #[allow(deprecated)]
+            fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+                type E = $ty;
+                Ok(match <usize as Decode>::decode_discriminant(reader)? {
+                    $(
+                        $disc => E::$variant
+                            $(( $({
+                                let $field = Decode::decode_ext(reader, None)?;
+                                $field
+                            }),+ ))?
+                            $({ $($named: Decode::decode_ext(reader, None)?),+ })?,
+                    )+
+                    disc => return Err(Error::UnknownDiscriminant(disc as u64)),
+                })
+            }
+        }
+
+        impl ForwardCompatibleEnum for $ty {
+            const KNOWN_DISCRIMINANTS: usize = [$($disc),+].len();
+        }
+
+        #[test]
+        fn $test() {
+            let discs: Vec<usize> = vec![$($disc),+];
+            assert_eq!(discs, (0..discs.len()).collect::<Vec<_>>());
+            for disc in 0..=discs.len() {
+                // Zero payload bytes decode as the first variant / zero index of every field.
+                let mut buf = Vec::new();
+                <usize as Encode>::encode_discriminant(disc, &mut buf).unwrap();
+                buf.extend_from_slice(&[0u8; 8]);
+                let res: Result<$ty> = decode(&mut Cursor::new(&buf));
+                if disc == discs.len() {
+                    assert!(matches!(res, Err(Error::UnknownDiscriminant(d)) if d == disc as u64));
+                    continue;
+                }
+                let value = res.unwrap();
+                let mut out = Vec::new();
+                value.encode(&mut out).unwrap();
+                assert_eq!(<usize as Decode>::decode_discriminant(&mut Cursor::new(&out)).unwrap(), disc);
+                let again: $ty = decode(&mut Cursor::new(&out)).unwrap();
+                assert_eq!(again, value);
+            }
+        }
+    };
+}
+
+solana_error_codec!(ixerr::InstructionError, test_instruction_error_discriminant_table {
+    0 => GenericError,
+    1 => InvalidArgument,
+    2 => InvalidInstructionData,
+    3 => InvalidAccountData,
+    4 => AccountDataTooSmall,
+    5 => InsufficientFunds,
+    6 => IncorrectProgramId,
+    7 => MissingRequiredSignature,
+    8 => AccountAlreadyInitialized,
+    9 => UninitializedAccount,
+    10 => UnbalancedInstruction,
+    11 => ModifiedProgramId,
+    12 => ExternalAccountLamportSpend,
+    13 => ExternalAccountDataModified,
+    14 => ReadonlyLamportChange,
+    15 => ReadonlyDataModified,
+    16 => DuplicateAccountIndex,
+    17 => ExecutableModified,
+    18 => RentEpochModified,
+    19 => NotEnoughAccountKeys,
+    20 => AccountDataSizeChanged,
+    21 => AccountNotExecutable,
+    22 => AccountBorrowFailed,
+    23 => AccountBorrowOutstanding,
+    24 => DuplicateAccountOutOfSync,
+    25 => Custom(code),
+    26 => InvalidError,
+    27 => ExecutableDataModified,
+    28 => ExecutableLamportChange,
+    29 => ExecutableAccountNotRentExempt,
+    30 => UnsupportedProgramId,
+    31 => CallDepth,
+    32 => MissingAccount,
+    33 => ReentrancyNotAllowed,
+    34 => MaxSeedLengthExceeded,
+    35 => InvalidSeeds,
+    36 => InvalidRealloc,
+    37 => ComputationalBudgetExceeded,
+    38 => PrivilegeEscalation,
+    39 => ProgramEnvironmentSetupFailure,
+    40 => ProgramFailedToComplete,
+    41 => ProgramFailedToCompile,
+    42 => Immutable,
+    43 => IncorrectAuthority,
+    44 => BorshIoError,
+    45 => AccountNotRentExempt,
+    46 => InvalidAccountOwner,
+    47 => ArithmeticOverflow,
+    48 => UnsupportedSysvar,
+    49 => IllegalOwner,
+    50 => MaxAccountsDataAllocationsExceeded,
+    51 => MaxAccountsExceeded,
+    52 => MaxInstructionTraceLengthExceeded,
+    53 => BuiltinProgramsMustConsumeComputeUnits,
+});
+
+solana_error_codec!(txerr3::TransactionError, test_transaction_error_discriminant_table {
+    0 => AccountInUse,
+    1 => AccountLoadedTwice,
+    2 => AccountNotFound,
+    3 => ProgramAccountNotFound,
+    4 => InsufficientFundsForFee,
+    5 => InvalidAccountForFee,
+    6 => AlreadyProcessed,
+    7 => BlockhashNotFound,
+    8 => InstructionError(index, error),
+    9 => CallChainTooDeep,
+    10 => MissingSignatureForFee,
+    11 => InvalidAccountIndex,
+    12 => SignatureFailure,
+    13 => InvalidProgramForExecution,
+    14 => SanitizeFailure,
+    15 => ClusterMaintenance,
+    16 => AccountBorrowOutstanding,
+    17 => WouldExceedMaxBlockCostLimit,
+    18 => UnsupportedVersion,
+    19 => InvalidWritableAccount,
+    20 => WouldExceedMaxAccountCostLimit,
+    21 => WouldExceedAccountDataBlockLimit,
+    22 => TooManyAccountLocks,
+    23 => AddressLookupTableNotFound,
+    24 => InvalidAddressLookupTableOwner,
+    25 => InvalidAddressLookupTableData,
+    26 => InvalidAddressLookupTableIndex,
+    27 => InvalidRentPayingAccount,
+    28 => WouldExceedMaxVoteCostLimit,
+    29 => WouldExceedAccountDataTotalLimit,
+    30 => DuplicateInstruction(index),
+    31 => InsufficientFundsForRent { account_index },
+    32 => MaxLoadedAccountsDataSizeExceeded,
+    33 => InvalidLoadedAccountsDataSizeLimit,
+    34 => ResanitizationNeeded,
+    35 => ProgramExecutionTemporarilyRestricted { account_index },
+    36 => UnbalancedTransaction,
+    37 => ProgramCacheHitMaxLimit,
+    38 => CommitCancelled,
+});
+
+/// Solana enums whose discriminants are assigned densely from `0`, so any discriminant at or
+/// above [`KNOWN_DISCRIMINANTS`](Self::KNOWN_DISCRIMINANTS) comes from a newer upstream
+/// version.
+pub trait ForwardCompatibleEnum: Encode + Decode + Sized {
+    /// The number of variants this crate knows how to decode.
+    const KNOWN_DISCRIMINANTS: usize;
+}
+
+/// A forward‑compatible wrapper for [`ForwardCompatibleEnum`]s such as `TransactionError` and
+/// `InstructionError`.
+///
+/// Variant payloads are length‑prefixed, so a stream written by a newer Agave version stays
+/// decodable: variants this crate doesn't know become [`Unknown`](Self::Unknown) and are
+/// re‑encoded byte for byte. A known variant whose payload fails to decode (e.g. a
+/// `TransactionError::InstructionError` carrying a newer `InstructionError`) is kept as
+/// `Unknown` too.
+///
+/// Wire format: `varint(disc) + bytes(payload)`, where `payload` is the bare encoding of the
+/// variant's fields. This differs from the bare enum encoding, so both sides must use the
+/// wrapper.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ForwardCompatible<E> {
+    /// A variant known to this crate.
+    Known(E),
+    /// A variant from a newer upstream version, kept as its raw discriminant and payload.
+    Unknown {
+        /// The variant's discriminant.
+        disc: usize,
+        /// The encoded variant fields.
+        payload: Vec<u8>,
+    },
+}
+
+/// Forward‑compatible `TransactionError`.
+pub type ForwardTransactionError = ForwardCompatible<txerr3::TransactionError>;
+/// Forward‑compatible `InstructionError`.
+pub type ForwardInstructionError = ForwardCompatible<ixerr::InstructionError>;
+
+impl<E> ForwardCompatible<E> {
+    /// Returns the known value, if any.
+    #[inline(always)]
+    pub const fn known(&self) -> Option<&E> {
+        match self {
+            Self::Known(value) => Some(value),
+            Self::Unknown { .. } => None,
+        }
+    }
+
+    /// Converts into the known value, if any.
+    #[inline(always)]
+    pub fn into_known(self) -> Option<E> {
+        match self {
+            Self::Known(value) => Some(value),
+            Self::Unknown { .. } => None,
+        }
+    }
+}
+
+impl<E> From<E> for ForwardCompatible<E> {
+    #[inline(always)]
+    fn from(value: E) -> Self {
+        Self::Known(value)
+    }
+}
+
+impl<E: ForwardCompatibleEnum> Encode for ForwardCompatible<E> {
     #[inline]
     fn encode_ext(
         &self,
         writer: &mut impl Write,
         _ctx: Option<&mut EncoderContext>,
     ) -> Result<usize> {
-        use ixerr::InstructionError as E;
-        let disc: usize = match self {
-            E::GenericError => 0,
-            E::InvalidArgument => 1,
-            E::InvalidInstructionData => 2,
-            E::InvalidAccountData => 3,
-            E::AccountDataTooSmall => 4,
-            E::InsufficientFunds => 5,
-            E::IncorrectProgramId => 6,
-            E::MissingRequiredSignature => 7,
-            E::AccountAlreadyInitialized => 8,
-            E::UninitializedAccount => 9,
-            E::UnbalancedInstruction => 10,
-            E::ModifiedProgramId => 11,
-            E::ExternalAccountLamportSpend => 12,
-            E::ExternalAccountDataModified => 13,
-            E::ReadonlyLamportChange => 14,
-            E::ReadonlyDataModified => 15,
-            E::DuplicateAccountIndex => 16,
-            E::ExecutableModified => 17,
-            E::RentEpochModified => 18,
-            #[allow(deprecated)]
-            E::NotEnoughAccountKeys => 19,
-            E::AccountDataSizeChanged => 20,
-            E::AccountNotExecutable => 21,
-            E::AccountBorrowFailed => 22,
-            E::AccountBorrowOutstanding => 23,
-            E::DuplicateAccountOutOfSync => 24,
-            E::Custom(_) => 25,
-            E::InvalidError => 26,
-            E::ExecutableDataModified => 27,
-            E::ExecutableLamportChange => 28,
-            E::ExecutableAccountNotRentExempt => 29,
-            E::UnsupportedProgramId => 30,
-            E::CallDepth => 31,
-            E::MissingAccount => 32,
-            E::ReentrancyNotAllowed => 33,
-            E::MaxSeedLengthExceeded => 34,
-            E::InvalidSeeds => 35,
-            E::InvalidRealloc => 36,
-            E::ComputationalBudgetExceeded => 37,
-            E::PrivilegeEscalation => 38,
-            E::ProgramEnvironmentSetupFailure => 39,
-            E::ProgramFailedToComplete => 40,
-            E::ProgramFailedToCompile => 41,
-            E::Immutable => 42,
-            E::IncorrectAuthority => 43,
-            E::BorshIoError => 44,
-            E::AccountNotRentExempt => 45,
-            E::InvalidAccountOwner => 46,
-            E::ArithmeticOverflow => 47,
-            E::UnsupportedSysvar => 48,
-            E::IllegalOwner => 49,
-            E::MaxAccountsDataAllocationsExceeded => 50,
-            E::MaxAccountsExceeded => 51,
-            E::MaxInstructionTraceLengthExceeded => 52,
-            E::BuiltinProgramsMustConsumeComputeUnits => 53,
-        };
-        let mut n = <usize as Encode>::encode_discriminant(disc, writer)?;
-        if let E::Custom(code) = self {
-            n += code.encode_ext(writer, None)?;
+        match self {
+            Self::Known(value) => {
+                let mut bare = Vec::new();
+                value.encode_ext(&mut bare, None)?;
+                let mut cursor = Cursor::new(&bare[..]);
+                let disc = <usize as Decode>::decode_discriminant(&mut cursor)?;
+                let mut n = <usize as Encode>::encode_discriminant(disc, writer)?;
+                n += (&bare[cursor.position()..]).encode_ext(writer, None)?;
+                Ok(n)
+            }
+            Self::Unknown { disc, payload } => {
+                let mut n = <usize as Encode>::encode_discriminant(*disc, writer)?;
+                n += payload.encode_ext(writer, None)?;
+                Ok(n)
+            }
         }
-        Ok(n)
     }
 }
 
-impl Decode for ixerr::InstructionError {
+impl<E: ForwardCompatibleEnum> Decode for ForwardCompatible<E> {
     #[inline]
     fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        use ixerr::InstructionError as E;
+        let disc = <usize as Decode>::decode_discriminant(reader)?;
+        let payload = Vec::<u8>::decode_ext(reader, None)?;
+        if disc < E::KNOWN_DISCRIMINANTS {
+            let mut bare = Vec::with_capacity(payload.len() + 9);
+            <usize as Encode>::encode_discriminant(disc, &mut bare)?;
+            bare.extend_from_slice(&payload);
+            if let Ok(value) = E::decode_ext(&mut Cursor::new(&bare[..]), None) {
+                return Ok(Self::Known(value));
+            }
+        }
+        Ok(Self::Unknown { disc, payload })
+    }
+}
+/// Each field is reported to [`EncoderContext::record_field`], so [`EncodeMetrics`] show which
+/// parts of the metadata dominate storage.
+impl Encode for txstatus3::TransactionStatusMeta {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let type_name = core::any::type_name::<Self>();
+        macro_rules! field {
+            ($name:ident) => {{
+                let bytes = self.$name.encode_ext(writer, ctx.as_deref_mut())?;
+                EncoderContext::record_field(
+                    ctx.as_deref_mut(),
+                    type_name,
+                    stringify!($name),
+                    bytes,
+                );
+                bytes
+            }};
+        }
+        let mut n = 0;
+        n += field!(status);
+        n += field!(fee);
+        n += field!(pre_balances);
+        n += field!(post_balances);
+        n += field!(inner_instructions);
+        n += field!(log_messages);
+        n += field!(pre_token_balances);
+        n += field!(post_token_balances);
+        n += field!(rewards);
+        n += field!(loaded_addresses);
+        n += field!(return_data);
+        n += field!(compute_units_consumed);
+        n += field!(cost_units);
+        Ok(n)
+    }
+}
+impl Decode for txstatus3::TransactionStatusMeta {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            status: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            fee: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            pre_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            post_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            inner_instructions: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            log_messages: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            pre_token_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            post_token_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            rewards: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            loaded_addresses: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            return_data: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            compute_units_consumed: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            cost_units: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+// Geyser interface types
+// Note: We intentionally do not implement Encode/Decode for agave-geyser
+// interface wrappers that carry reference fields, to avoid requiring leaked
+// allocations for decoding. These values can be reconstructed from their
+// underlying owned types when needed.
+
+// SlotStatus and GeyserPluginError
+impl Encode for ifc::SlotStatus {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut _dedupe: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        match self {
+            ifc::SlotStatus::Processed => <usize as Encode>::encode_discriminant(0, writer),
+            ifc::SlotStatus::Rooted => <usize as Encode>::encode_discriminant(1, writer),
+            ifc::SlotStatus::Confirmed => <usize as Encode>::encode_discriminant(2, writer),
+            ifc::SlotStatus::FirstShredReceived => {
+                <usize as Encode>::encode_discriminant(3, writer)
+            }
+            ifc::SlotStatus::Completed => <usize as Encode>::encode_discriminant(4, writer),
+            ifc::SlotStatus::CreatedBank => <usize as Encode>::encode_discriminant(5, writer),
+            ifc::SlotStatus::Dead(msg) => {
+                let mut n = <usize as Encode>::encode_discriminant(6, writer)?;
+                n += msg.encode_ext(writer, None)?;
+                Ok(n)
+            }
+        }
+    }
+}
+impl Decode for ifc::SlotStatus {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, _dedupe: Option<&mut DecoderContext>) -> Result<Self> {
         Ok(match <usize as Decode>::decode_discriminant(reader)? {
-            0 => E::GenericError,
-            1 => E::InvalidArgument,
-            2 => E::InvalidInstructionData,
-            3 => E::InvalidAccountData,
-            4 => E::AccountDataTooSmall,
-            5 => E::InsufficientFunds,
-            6 => E::IncorrectProgramId,
-            7 => E::MissingRequiredSignature,
-            8 => E::AccountAlreadyInitialized,
-            9 => E::UninitializedAccount,
-            10 => E::UnbalancedInstruction,
-            11 => E::ModifiedProgramId,
-            12 => E::ExternalAccountLamportSpend,
-            13 => E::ExternalAccountDataModified,
-            14 => E::ReadonlyLamportChange,
-            15 => E::ReadonlyDataModified,
-            16 => E::DuplicateAccountIndex,
-            17 => E::ExecutableModified,
-            18 => E::RentEpochModified,
-            #[allow(deprecated)]
-            19 => E::NotEnoughAccountKeys,
-            20 => E::AccountDataSizeChanged,
-            21 => E::AccountNotExecutable,
-            22 => E::AccountBorrowFailed,
-            23 => E::AccountBorrowOutstanding,
-            24 => E::DuplicateAccountOutOfSync,
-            25 => E::Custom(Decode::decode_ext(reader, None)?),
-            26 => E::InvalidError,
-            27 => E::ExecutableDataModified,
-            28 => E::ExecutableLamportChange,
-            29 => E::ExecutableAccountNotRentExempt,
-            30 => E::UnsupportedProgramId,
-            31 => E::CallDepth,
-            32 => E::MissingAccount,
-            33 => E::ReentrancyNotAllowed,
-            34 => E::MaxSeedLengthExceeded,
-            35 => E::InvalidSeeds,
-            36 => E::InvalidRealloc,
-            37 => E::ComputationalBudgetExceeded,
-            38 => E::PrivilegeEscalation,
-            39 => E::ProgramEnvironmentSetupFailure,
-            40 => E::ProgramFailedToComplete,
-            41 => E::ProgramFailedToCompile,
-            42 => E::Immutable,
-            43 => E::IncorrectAuthority,
-            44 => E::BorshIoError,
-            45 => E::AccountNotRentExempt,
-            46 => E::InvalidAccountOwner,
-            47 => E::ArithmeticOverflow,
-            48 => E::UnsupportedSysvar,
-            49 => E::IllegalOwner,
-            50 => E::MaxAccountsDataAllocationsExceeded,
-            51 => E::MaxAccountsExceeded,
-            52 => E::MaxInstructionTraceLengthExceeded,
-            53 => E::BuiltinProgramsMustConsumeComputeUnits,
-            _ => return Err(Error::InvalidData),
+            0 => ifc::SlotStatus::Processed,
+            1 => ifc::SlotStatus::Rooted,
+            2 => ifc::SlotStatus::Confirmed,
+            3 => ifc::SlotStatus::FirstShredReceived,
+            4 => ifc::SlotStatus::Completed,
+            5 => ifc::SlotStatus::CreatedBank,
+            6 => ifc::SlotStatus::Dead(Decode::decode_ext(reader, None)?),
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
         })
     }
 }
 
-// TransactionError encoding (direct, no serde)
-impl Encode for txerr3::TransactionError {
+#[derive(Debug)]
+struct SimpleError(String);
+impl core::fmt::Display for SimpleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for SimpleError {}
+
+impl Encode for ifc::GeyserPluginError {
     #[inline]
     fn encode_ext(
         &self,
         writer: &mut impl Write,
-        _ctx: Option<&mut EncoderContext>,
+        _dedupe: Option<&mut EncoderContext>,
     ) -> Result<usize> {
-        use txerr3::TransactionError as E;
-        let disc: usize = match self {
-            E::AccountInUse => 0,
-            E::AccountLoadedTwice => 1,
-            E::AccountNotFound => 2,
-            E::ProgramAccountNotFound => 3,
-            E::InsufficientFundsForFee => 4,
-            E::InvalidAccountForFee => 5,
-            E::AlreadyProcessed => 6,
-            E::BlockhashNotFound => 7,
-            E::InstructionError(_, _) => 8,
-            E::CallChainTooDeep => 9,
-            E::MissingSignatureForFee => 10,
-            E::InvalidAccountIndex => 11,
-            E::SignatureFailure => 12,
-            E::InvalidProgramForExecution => 13,
-            E::SanitizeFailure => 14,
-            E::ClusterMaintenance => 15,
-            E::AccountBorrowOutstanding => 16,
-            E::WouldExceedMaxBlockCostLimit => 17,
-            E::UnsupportedVersion => 18,
-            E::InvalidWritableAccount => 19,
-            E::WouldExceedMaxAccountCostLimit => 20,
-            E::WouldExceedAccountDataBlockLimit => 21,
-            E::TooManyAccountLocks => 22,
-            E::AddressLookupTableNotFound => 23,
-            E::InvalidAddressLookupTableOwner => 24,
-            E::InvalidAddressLookupTableData => 25,
-            E::InvalidAddressLookupTableIndex => 26,
-            E::InvalidRentPayingAccount => 27,
-            E::WouldExceedMaxVoteCostLimit => 28,
-            E::WouldExceedAccountDataTotalLimit => 29,
-            E::DuplicateInstruction(_) => 30,
-            E::InsufficientFundsForRent { .. } => 31,
-            E::MaxLoadedAccountsDataSizeExceeded => 32,
-            E::InvalidLoadedAccountsDataSizeLimit => 33,
-            E::ResanitizationNeeded => 34,
-            E::ProgramExecutionTemporarilyRestricted { .. } => 35,
-            E::UnbalancedTransaction => 36,
-            E::ProgramCacheHitMaxLimit => 37,
-            E::CommitCancelled => 38,
-        };
-        let mut n = <usize as Encode>::encode_discriminant(disc, writer)?;
         match self {
-            E::InstructionError(idx, err) => {
-                n += idx.encode_ext(writer, None)?;
-                n += err.encode_ext(writer, None)?;
+            ifc::GeyserPluginError::ConfigFileOpenError(e) => {
+                let mut n = <usize as Encode>::encode_discriminant(0, writer)?;
+                n += e.to_string().encode_ext(writer, None)?;
+                Ok(n)
+            }
+            ifc::GeyserPluginError::ConfigFileReadError { msg } => {
+                let mut n = <usize as Encode>::encode_discriminant(1, writer)?;
+                n += msg.encode_ext(writer, None)?;
+                Ok(n)
+            }
+            ifc::GeyserPluginError::AccountsUpdateError { msg } => {
+                let mut n = <usize as Encode>::encode_discriminant(2, writer)?;
+                n += msg.encode_ext(writer, None)?;
+                Ok(n)
             }
-            E::DuplicateInstruction(idx) => {
-                n += idx.encode_ext(writer, None)?;
+            ifc::GeyserPluginError::SlotStatusUpdateError { msg } => {
+                let mut n = <usize as Encode>::encode_discriminant(3, writer)?;
+                n += msg.encode_ext(writer, None)?;
+                Ok(n)
             }
-            E::InsufficientFundsForRent { account_index } => {
-                n += account_index.encode_ext(writer, None)?;
+            ifc::GeyserPluginError::Custom(err) => {
+                let mut n = <usize as Encode>::encode_discriminant(4, writer)?;
+                n += err.to_string().encode_ext(writer, None)?;
+                Ok(n)
             }
-            E::ProgramExecutionTemporarilyRestricted { account_index } => {
-                n += account_index.encode_ext(writer, None)?;
+            ifc::GeyserPluginError::TransactionUpdateError { msg } => {
+                let mut n = <usize as Encode>::encode_discriminant(5, writer)?;
+                n += msg.encode_ext(writer, None)?;
+                Ok(n)
             }
-            _ => {}
         }
-        Ok(n)
     }
 }
-
-impl Decode for txerr3::TransactionError {
+impl Decode for ifc::GeyserPluginError {
     #[inline]
-    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
-        use txerr3::TransactionError as E;
+    fn decode_ext(reader: &mut impl Read, _dedupe: Option<&mut DecoderContext>) -> Result<Self> {
         Ok(match <usize as Decode>::decode_discriminant(reader)? {
-            0 => E::AccountInUse,
-            1 => E::AccountLoadedTwice,
-            2 => E::AccountNotFound,
-            3 => E::ProgramAccountNotFound,
-            4 => E::InsufficientFundsForFee,
-            5 => E::InvalidAccountForFee,
-            6 => E::AlreadyProcessed,
-            7 => E::BlockhashNotFound,
-            8 => E::InstructionError(
-                Decode::decode_ext(reader, None)?,
-                Decode::decode_ext(reader, None)?,
-            ),
-            9 => E::CallChainTooDeep,
-            10 => E::MissingSignatureForFee,
-            11 => E::InvalidAccountIndex,
-            12 => E::SignatureFailure,
-            13 => E::InvalidProgramForExecution,
-            14 => E::SanitizeFailure,
-            15 => E::ClusterMaintenance,
-            16 => E::AccountBorrowOutstanding,
-            17 => E::WouldExceedMaxBlockCostLimit,
-            18 => E::UnsupportedVersion,
-            19 => E::InvalidWritableAccount,
-            20 => E::WouldExceedMaxAccountCostLimit,
-            21 => E::WouldExceedAccountDataBlockLimit,
-            22 => E::TooManyAccountLocks,
-            23 => E::AddressLookupTableNotFound,
-            24 => E::InvalidAddressLookupTableOwner,
-            25 => E::InvalidAddressLookupTableData,
-            26 => E::InvalidAddressLookupTableIndex,
-            27 => E::InvalidRentPayingAccount,
-            28 => E::WouldExceedMaxVoteCostLimit,
-            29 => E::WouldExceedAccountDataTotalLimit,
-            30 => E::DuplicateInstruction(Decode::decode_ext(reader, None)?),
-            31 => E::InsufficientFundsForRent {
-                account_index: Decode::decode_ext(reader, None)?,
+            0 => ifc::GeyserPluginError::ConfigFileOpenError(std::io::Error::other(
+                String::decode_ext(reader, None)?,
+            )),
+            1 => ifc::GeyserPluginError::ConfigFileReadError {
+                msg: Decode::decode_ext(reader, None)?,
+            },
+            2 => ifc::GeyserPluginError::AccountsUpdateError {
+                msg: Decode::decode_ext(reader, None)?,
             },
-            32 => E::MaxLoadedAccountsDataSizeExceeded,
-            33 => E::InvalidLoadedAccountsDataSizeLimit,
-            34 => E::ResanitizationNeeded,
-            35 => E::ProgramExecutionTemporarilyRestricted {
-                account_index: Decode::decode_ext(reader, None)?,
+            3 => ifc::GeyserPluginError::SlotStatusUpdateError {
+                msg: Decode::decode_ext(reader, None)?,
+            },
+            4 => ifc::GeyserPluginError::Custom(Box::new(SimpleError(Decode::decode_ext(
+                reader, None,
+            )?))),
+            5 => ifc::GeyserPluginError::TransactionUpdateError {
+                msg: Decode::decode_ext(reader, None)?,
             },
-            36 => E::UnbalancedTransaction,
-            37 => E::ProgramCacheHitMaxLimit,
-            38 => E::CommitCancelled,
-            _ => return Err(Error::InvalidData),
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
         })
     }
 }
-impl Encode for txstatus3::TransactionStatusMeta {
-    #[inline]
+
+// Solana wire format (bincode + short_vec)
+
+/// Types with a Solana on‑chain wire representation: fixed‑width fields and `short_vec`
+/// compact‑u16 length prefixes, byte‑for‑byte identical to what validators and RPC nodes
+/// exchange. Use through [`SolanaWire`].
+pub trait SolanaWireFormat: Sized {
+    /// Writes `self` in Solana's wire layout, returning the number of bytes written.
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize>;
+    /// Reads `Self` from Solana's wire layout.
+    fn wire_decode(reader: &mut impl Read) -> Result<Self>;
+}
+
+/// Encodes and decodes `T` in Solana's exact wire layout instead of lencode's.
+///
+/// Lets the same [`Encode`]/[`Decode`] pipeline emit and parse on‑chain message and
+/// transaction bytes, e.g. `SolanaWire(tx).encode(&mut buf)` produces the bytes accepted by
+/// `sendTransaction`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolanaWire<T>(pub T);
+
+impl<T: SolanaWireFormat> Encode for SolanaWire<T> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        self.0.wire_encode(writer)
+    }
+}
+
+impl<T: SolanaWireFormat> Decode for SolanaWire<T> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(SolanaWire(T::wire_decode(reader)?))
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+#[inline(always)]
+fn wire_encode_bytes(bytes: &[u8], writer: &mut impl Write) -> Result<usize> {
+    let n = ShortVecLen::encode_len(bytes.len(), writer)?;
+    Ok(n + writer.write(bytes)?)
+}
+
+#[inline(always)]
+fn wire_decode_bytes(reader: &mut impl Read) -> Result<Vec<u8>> {
+    let len = ShortVecLen::decode_len(reader)?;
+    let mut out = vec![0u8; len];
+    crate::io::read_exact(reader, &mut out)?;
+    Ok(out)
+}
+
+#[inline(always)]
+fn wire_encode_seq<T: SolanaWireFormat>(items: &[T], writer: &mut impl Write) -> Result<usize> {
+    let mut n = ShortVecLen::encode_len(items.len(), writer)?;
+    for item in items {
+        n += item.wire_encode(writer)?;
+    }
+    Ok(n)
+}
+
+#[inline(always)]
+fn wire_decode_seq<T: SolanaWireFormat>(reader: &mut impl Read) -> Result<Vec<T>> {
+    let len = ShortVecLen::decode_len(reader)?;
+    let mut out = Vec::with_capacity(len);
+    for _ in 0..len {
+        out.push(T::wire_decode(reader)?);
+    }
+    Ok(out)
+}
+
+impl SolanaWireFormat for pubkey3::Pubkey {
+    #[inline(always)]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        writer.write(&self.to_bytes())
+    }
+    #[inline(always)]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        let mut buf = [0u8; 32];
+        crate::io::read_exact(reader, &mut buf)?;
+        Ok(Self::new_from_array(buf))
+    }
+}
+
+impl SolanaWireFormat for hash3::Hash {
+    #[inline(always)]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        writer.write(self.as_bytes())
+    }
+    #[inline(always)]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        let mut buf = [0u8; hash3::HASH_BYTES];
+        crate::io::read_exact(reader, &mut buf)?;
+        Ok(Self::new_from_array(buf))
+    }
+}
+
+impl SolanaWireFormat for sig3::Signature {
+    #[inline(always)]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        writer.write(self.as_array())
+    }
+    #[inline(always)]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        let mut buf = [0u8; sig3::SIGNATURE_BYTES];
+        crate::io::read_exact(reader, &mut buf)?;
+        Ok(Self::from(buf))
+    }
+}
+
+impl SolanaWireFormat for msg3::MessageHeader {
+    #[inline(always)]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        writer.write(&[
+            self.num_required_signatures,
+            self.num_readonly_signed_accounts,
+            self.num_readonly_unsigned_accounts,
+        ])
+    }
+    #[inline(always)]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        let mut b = [0u8; 3];
+        crate::io::read_exact(reader, &mut b)?;
+        Ok(Self {
+            num_required_signatures: b[0],
+            num_readonly_signed_accounts: b[1],
+            num_readonly_unsigned_accounts: b[2],
+        })
+    }
+}
+
+impl SolanaWireFormat for msg3::compiled_instruction::CompiledInstruction {
+    #[inline(always)]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        let mut n = writer.write(&[self.program_id_index])?;
+        n += wire_encode_bytes(&self.accounts, writer)?;
+        n += wire_encode_bytes(&self.data, writer)?;
+        Ok(n)
+    }
+    #[inline(always)]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        let mut program_id_index = [0u8; 1];
+        crate::io::read_exact(reader, &mut program_id_index)?;
+        Ok(Self {
+            program_id_index: program_id_index[0],
+            accounts: wire_decode_bytes(reader)?,
+            data: wire_decode_bytes(reader)?,
+        })
+    }
+}
+
+impl SolanaWireFormat for msg3::legacy::Message {
+    #[inline]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        let mut n = self.header.wire_encode(writer)?;
+        n += wire_encode_seq(&self.account_keys, writer)?;
+        n += self.recent_blockhash.wire_encode(writer)?;
+        n += wire_encode_seq(&self.instructions, writer)?;
+        Ok(n)
+    }
+    #[inline]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            header: SolanaWireFormat::wire_decode(reader)?,
+            account_keys: wire_decode_seq(reader)?,
+            recent_blockhash: SolanaWireFormat::wire_decode(reader)?,
+            instructions: wire_decode_seq(reader)?,
+        })
+    }
+}
+
+impl SolanaWireFormat for msg3::v0::MessageAddressTableLookup {
+    #[inline(always)]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        let mut n = self.account_key.wire_encode(writer)?;
+        n += wire_encode_bytes(&self.writable_indexes, writer)?;
+        n += wire_encode_bytes(&self.readonly_indexes, writer)?;
+        Ok(n)
+    }
+    #[inline(always)]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            account_key: SolanaWireFormat::wire_decode(reader)?,
+            writable_indexes: wire_decode_bytes(reader)?,
+            readonly_indexes: wire_decode_bytes(reader)?,
+        })
+    }
+}
+
+impl SolanaWireFormat for msg3::v0::Message {
+    #[inline]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        let mut n = self.header.wire_encode(writer)?;
+        n += wire_encode_seq(&self.account_keys, writer)?;
+        n += self.recent_blockhash.wire_encode(writer)?;
+        n += wire_encode_seq(&self.instructions, writer)?;
+        n += wire_encode_seq(&self.address_table_lookups, writer)?;
+        Ok(n)
+    }
+    #[inline]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            header: SolanaWireFormat::wire_decode(reader)?,
+            account_keys: wire_decode_seq(reader)?,
+            recent_blockhash: SolanaWireFormat::wire_decode(reader)?,
+            instructions: wire_decode_seq(reader)?,
+            address_table_lookups: wire_decode_seq(reader)?,
+        })
+    }
+}
+
+/// High bit of the first message byte; set for versioned (non‑legacy) messages.
+const MESSAGE_VERSION_PREFIX: u8 = 0x80;
+
+impl SolanaWireFormat for msg3::VersionedMessage {
+    #[inline]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        match self {
+            msg3::VersionedMessage::Legacy(m) => m.wire_encode(writer),
+            msg3::VersionedMessage::V0(m) => {
+                let n = writer.write(&[MESSAGE_VERSION_PREFIX])?;
+                Ok(n + m.wire_encode(writer)?)
+            }
+        }
+    }
+    #[inline]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        let mut first = [0u8; 1];
+        crate::io::read_exact(reader, &mut first)?;
+        if first[0] & MESSAGE_VERSION_PREFIX == 0 {
+            // Legacy: the byte already read is `num_required_signatures`.
+            let mut rest = [0u8; 2];
+            crate::io::read_exact(reader, &mut rest)?;
+            let header = msg3::MessageHeader {
+                num_required_signatures: first[0],
+                num_readonly_signed_accounts: rest[0],
+                num_readonly_unsigned_accounts: rest[1],
+            };
+            return Ok(Self::Legacy(msg3::legacy::Message {
+                header,
+                account_keys: wire_decode_seq(reader)?,
+                recent_blockhash: SolanaWireFormat::wire_decode(reader)?,
+                instructions: wire_decode_seq(reader)?,
+            }));
+        }
+        match first[0] & !MESSAGE_VERSION_PREFIX {
+            0 => Ok(Self::V0(SolanaWireFormat::wire_decode(reader)?)),
+            version => Err(Error::UnknownDiscriminant(version as u64)),
+        }
+    }
+}
+
+impl SolanaWireFormat for tx3::versioned::VersionedTransaction {
+    #[inline]
+    fn wire_encode(&self, writer: &mut impl Write) -> Result<usize> {
+        let mut n = wire_encode_seq(&self.signatures, writer)?;
+        n += self.message.wire_encode(writer)?;
+        Ok(n)
+    }
+    #[inline]
+    fn wire_decode(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            signatures: wire_decode_seq(reader)?,
+            message: SolanaWireFormat::wire_decode(reader)?,
+        })
+    }
+}
+
+// Pubkey tables and base58 helpers
+
+/// Parses a base58 pubkey string and encodes it as a `Pubkey` (32 bytes, or a dedupe ID when
+/// `ctx` carries a [`DedupeEncoder`]).
+///
+/// Returns [`Error::InvalidData`] if `s` is not a valid base58 pubkey.
+#[inline]
+pub fn encode_pubkey_base58(
+    s: &str,
+    writer: &mut impl Write,
+    ctx: Option<&mut EncoderContext>,
+) -> Result<usize> {
+    let key: pubkey3::Pubkey = s.parse().map_err(|_| Error::InvalidData)?;
+    key.encode_ext(writer, ctx)
+}
+
+/// Decodes a `Pubkey` and returns it as a base58 string.
+#[inline]
+pub fn decode_pubkey_base58(
+    reader: &mut impl Read,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<String> {
+    Ok(pubkey3::Pubkey::decode_ext(reader, ctx)?.to_string())
+}
+
+/// An ordered list of pubkeys that encodes as a deduplicated key table plus one index per
+/// entry, the same shape v0 messages use for account keys.
+///
+/// Use it in structs that reference the same accounts many times: each distinct key is
+/// written once (and goes through the [`DedupeEncoder`] when one is active), every entry
+/// after that costs a varint index.
+///
+/// Wire format: `Vec<Pubkey>` table, then `varint(count)` and a varint table index per entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PubkeySet {
+    table: Vec<pubkey3::Pubkey>,
+    indexes: Vec<usize>,
+    lookup: std::collections::HashMap<pubkey3::Pubkey, usize>,
+}
+
+impl PubkeySet {
+    /// Creates an empty set.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `key`, returning its index in the deduplicated table.
+    #[inline]
+    pub fn push(&mut self, key: pubkey3::Pubkey) -> usize {
+        let index = *self.lookup.entry(key).or_insert_with(|| {
+            self.table.push(key);
+            self.table.len() - 1
+        });
+        self.indexes.push(index);
+        index
+    }
+
+    /// Returns the number of entries, counting repeats.
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.indexes.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.indexes.is_empty()
+    }
+
+    /// Returns the entry at `position`.
+    #[inline(always)]
+    pub fn get(&self, position: usize) -> Option<&pubkey3::Pubkey> {
+        self.indexes.get(position).map(|&i| &self.table[i])
+    }
+
+    /// Returns the deduplicated key table, in first‑seen order.
+    #[inline(always)]
+    pub fn table(&self) -> &[pubkey3::Pubkey] {
+        &self.table
+    }
+
+    /// Returns the table index of each entry.
+    #[inline(always)]
+    pub fn indexes(&self) -> &[usize] {
+        &self.indexes
+    }
+
+    /// Returns the table index of `key`, if present.
+    #[inline(always)]
+    pub fn index_of(&self, key: &pubkey3::Pubkey) -> Option<usize> {
+        self.lookup.get(key).copied()
+    }
+
+    /// Iterates over the entries in insertion order, including repeats.
+    #[inline(always)]
+    pub fn iter(&self) -> impl Iterator<Item = &pubkey3::Pubkey> + '_ {
+        self.indexes.iter().map(|&i| &self.table[i])
+    }
+}
+
+impl FromIterator<pubkey3::Pubkey> for PubkeySet {
+    fn from_iter<I: IntoIterator<Item = pubkey3::Pubkey>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for key in iter {
+            set.push(key);
+        }
+        set
+    }
+}
+
+impl From<Vec<pubkey3::Pubkey>> for PubkeySet {
+    #[inline(always)]
+    fn from(keys: Vec<pubkey3::Pubkey>) -> Self {
+        keys.into_iter().collect()
+    }
+}
+
+impl From<PubkeySet> for Vec<pubkey3::Pubkey> {
+    #[inline(always)]
+    fn from(set: PubkeySet) -> Self {
+        set.iter().copied().collect()
+    }
+}
+
+impl Encode for PubkeySet {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.table.encode_ext(writer, ctx)?;
+        n += Self::encode_len(self.indexes.len(), writer)?;
+        for &index in &self.indexes {
+            n += index.encode_ext(writer, None)?;
+        }
+        Ok(n)
+    }
+}
+
+impl Decode for PubkeySet {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        let table = Vec::<pubkey3::Pubkey>::decode_ext(reader, ctx)?;
+        let mut lookup = std::collections::HashMap::with_capacity(table.len());
+        for (i, key) in table.iter().enumerate() {
+            if lookup.insert(*key, i).is_some() {
+                return Err(Error::InvalidData);
+            }
+        }
+        let count = Self::decode_len(reader)?;
+        let mut indexes = Vec::with_capacity(prealloc_capacity::<usize>(count));
+        for _ in 0..count {
+            let index = usize::decode_ext(reader, None)?;
+            if index >= table.len() {
+                return Err(Error::InvalidData);
+            }
+            indexes.push(index);
+        }
+        Ok(Self {
+            table,
+            indexes,
+            lookup,
+        })
+    }
+}
+
+// Owned Geyser notification types
+//
+// The `Replica*InfoVersions` wrappers passed to Geyser plugins borrow from the validator, so
+// they cannot be decoded. The owned types below capture every field of the newest version
+// (fields introduced by later versions are `Option`s), and [`GeyserEvent`] wraps them so a
+// whole notification feed can be written with one type and decoded downstream.
+
+/// Owned equivalent of `ReplicaEntryInfoVersions`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OwnedReplicaEntryInfo {
+    /// Slot the entry belongs to.
+    pub slot: u64,
+    /// Index of the entry within the slot.
+    pub index: usize,
+    /// Number of hashes since the previous entry.
+    pub num_hashes: u64,
+    /// The entry's hash.
+    pub hash: Vec<u8>,
+    /// Number of executed transactions in the entry.
+    pub executed_transaction_count: u64,
+    /// Index of the entry's first transaction within the block (`V0_0_2` and later).
+    pub starting_transaction_index: Option<usize>,
+}
+
+impl From<&ifc::ReplicaEntryInfoVersions<'_>> for OwnedReplicaEntryInfo {
+    fn from(info: &ifc::ReplicaEntryInfoVersions<'_>) -> Self {
+        match info {
+            ifc::ReplicaEntryInfoVersions::V0_0_1(e) => Self {
+                slot: e.slot,
+                index: e.index,
+                num_hashes: e.num_hashes,
+                hash: e.hash.to_vec(),
+                executed_transaction_count: e.executed_transaction_count,
+                starting_transaction_index: None,
+            },
+            ifc::ReplicaEntryInfoVersions::V0_0_2(e) => Self {
+                slot: e.slot,
+                index: e.index,
+                num_hashes: e.num_hashes,
+                hash: e.hash.to_vec(),
+                executed_transaction_count: e.executed_transaction_count,
+                starting_transaction_index: Some(e.starting_transaction_index),
+            },
+        }
+    }
+}
+
+impl Encode for OwnedReplicaEntryInfo {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.slot.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.index.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.num_hashes.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.hash.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .executed_transaction_count
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.starting_transaction_index.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for OwnedReplicaEntryInfo {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            slot: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            index: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            num_hashes: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            hash: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            executed_transaction_count: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            starting_transaction_index: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+/// Owned equivalent of `ReplicaBlockInfoVersions`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OwnedReplicaBlockInfo {
+    /// Parent slot (`V0_0_2` and later).
+    pub parent_slot: Option<u64>,
+    /// Parent blockhash, base58 (`V0_0_2` and later).
+    pub parent_blockhash: Option<String>,
+    /// The block's slot.
+    pub slot: u64,
+    /// The blockhash, base58.
+    pub blockhash: String,
+    /// Rewards credited in this block.
+    pub rewards: Vec<txstatus3::Reward>,
+    /// Number of reward partitions (`V0_0_4` and later, when partitioned).
+    pub num_partitions: Option<u64>,
+    /// Estimated production time, as Unix seconds.
+    pub block_time: Option<i64>,
+    /// Block height.
+    pub block_height: Option<u64>,
+    /// Number of executed transactions (`V0_0_2` and later).
+    pub executed_transaction_count: Option<u64>,
+    /// Number of entries (`V0_0_3` and later).
+    pub entry_count: Option<u64>,
+}
+
+impl From<&ifc::ReplicaBlockInfoVersions<'_>> for OwnedReplicaBlockInfo {
+    fn from(info: &ifc::ReplicaBlockInfoVersions<'_>) -> Self {
+        match info {
+            ifc::ReplicaBlockInfoVersions::V0_0_1(b) => Self {
+                slot: b.slot,
+                blockhash: b.blockhash.to_string(),
+                rewards: b.rewards.to_vec(),
+                block_time: b.block_time,
+                block_height: b.block_height,
+                ..Default::default()
+            },
+            ifc::ReplicaBlockInfoVersions::V0_0_2(b) => Self {
+                parent_slot: Some(b.parent_slot),
+                parent_blockhash: Some(b.parent_blockhash.to_string()),
+                slot: b.slot,
+                blockhash: b.blockhash.to_string(),
+                rewards: b.rewards.to_vec(),
+                block_time: b.block_time,
+                block_height: b.block_height,
+                executed_transaction_count: Some(b.executed_transaction_count),
+                ..Default::default()
+            },
+            ifc::ReplicaBlockInfoVersions::V0_0_3(b) => Self {
+                parent_slot: Some(b.parent_slot),
+                parent_blockhash: Some(b.parent_blockhash.to_string()),
+                slot: b.slot,
+                blockhash: b.blockhash.to_string(),
+                rewards: b.rewards.to_vec(),
+                block_time: b.block_time,
+                block_height: b.block_height,
+                executed_transaction_count: Some(b.executed_transaction_count),
+                entry_count: Some(b.entry_count),
+                ..Default::default()
+            },
+            ifc::ReplicaBlockInfoVersions::V0_0_4(b) => Self {
+                parent_slot: Some(b.parent_slot),
+                parent_blockhash: Some(b.parent_blockhash.to_string()),
+                slot: b.slot,
+                blockhash: b.blockhash.to_string(),
+                rewards: b.rewards.rewards.clone(),
+                num_partitions: b.rewards.num_partitions,
+                block_time: b.block_time,
+                block_height: b.block_height,
+                executed_transaction_count: Some(b.executed_transaction_count),
+                entry_count: Some(b.entry_count),
+            },
+        }
+    }
+}
+
+impl Encode for OwnedReplicaBlockInfo {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.parent_slot.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .parent_blockhash
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.slot.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.blockhash.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.rewards.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.num_partitions.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.block_time.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.block_height.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .executed_transaction_count
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.entry_count.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for OwnedReplicaBlockInfo {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            parent_slot: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            parent_blockhash: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            slot: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            blockhash: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            rewards: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            num_partitions: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            block_time: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            block_height: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            executed_transaction_count: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            entry_count: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+/// A single Geyser plugin notification in owned, encodable form.
+///
+/// Wire format: varint discriminant (`0` slot status, `1` entry, `2` block metadata,
+/// `3` end of startup) followed by the variant's fields.
+#[derive(Debug)]
+pub enum GeyserEvent {
+    /// `update_slot_status(slot, parent, status)`.
+    SlotStatus {
+        /// The slot whose status changed.
+        slot: u64,
+        /// The parent slot, if known.
+        parent: Option<u64>,
+        /// The new status.
+        status: ifc::SlotStatus,
+    },
+    /// `notify_entry(entry)`.
+    Entry(OwnedReplicaEntryInfo),
+    /// `notify_block_metadata(blockinfo)`.
+    BlockMetadata(OwnedReplicaBlockInfo),
+    /// `notify_end_of_startup()`.
+    EndOfStartup,
+}
+
+impl Encode for GeyserEvent {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        match self {
+            GeyserEvent::SlotStatus {
+                slot,
+                parent,
+                status,
+            } => {
+                n += <usize as Encode>::encode_discriminant(0, writer)?;
+                n += slot.encode_ext(writer, ctx.as_deref_mut())?;
+                n += parent.encode_ext(writer, ctx.as_deref_mut())?;
+                n += status.encode_ext(writer, ctx)?;
+            }
+            GeyserEvent::Entry(entry) => {
+                n += <usize as Encode>::encode_discriminant(1, writer)?;
+                n += entry.encode_ext(writer, ctx)?;
+            }
+            GeyserEvent::BlockMetadata(block) => {
+                n += <usize as Encode>::encode_discriminant(2, writer)?;
+                n += block.encode_ext(writer, ctx)?;
+            }
+            GeyserEvent::EndOfStartup => {
+                n += <usize as Encode>::encode_discriminant(3, writer)?;
+            }
+        }
+        Ok(n)
+    }
+}
+impl Decode for GeyserEvent {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(match <usize as Decode>::decode_discriminant(reader)? {
+            0 => GeyserEvent::SlotStatus {
+                slot: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+                parent: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+                status: Decode::decode_ext(reader, ctx)?,
+            },
+            1 => GeyserEvent::Entry(Decode::decode_ext(reader, ctx)?),
+            2 => GeyserEvent::BlockMetadata(Decode::decode_ext(reader, ctx)?),
+            3 => GeyserEvent::EndOfStartup,
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
+        })
+    }
+}
+
+// Sysvar state snapshots
+
+crate::impl_lencode_remote!(struct clock3::Clock {
+    slot: u64,
+    epoch_start_timestamp: i64,
+    epoch: u64,
+    leader_schedule_epoch: u64,
+    unix_timestamp: i64,
+});
+crate::impl_lencode_remote!(struct epoch_info3::EpochInfo {
+    epoch: u64,
+    slot_index: u64,
+    slots_in_epoch: u64,
+    absolute_slot: u64,
+    block_height: u64,
+    transaction_count: Option<u64>,
+});
+
+/// `SlotHistory` is a 1M‑bit bitmap that is almost entirely runs of set or cleared slots,
+/// so its `u64` blocks are written through [`Rle`]: `varint(bit_len) + Rle<u64> blocks +
+/// varint(next_slot)`. A typical snapshot shrinks from 128 KiB to a few dozen bytes.
+impl Encode for slot_history3::SlotHistory {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let blocks: Rle<u64> = (0..self.bits.block_len())
+            .map(|i| self.bits.get_block(i))
+            .collect();
+        let mut n = 0;
+        n += self.bits.bit_len().encode_ext(writer, ctx.as_deref_mut())?;
+        n += blocks.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.next_slot.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for slot_history3::SlotHistory {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        let bit_len = u64::decode_ext(reader, ctx.as_deref_mut())?;
+        let blocks = Rle::<u64>::decode_ext(reader, ctx.as_deref_mut())?;
+        if blocks.len() as u64 != bit_len.div_ceil(64) {
+            return Err(Error::InvalidData);
+        }
+        let mut bits = bv::BitVec::<u64>::new_fill(false, bit_len);
+        for (i, block) in blocks.iter().enumerate() {
+            bits.set_block(i, *block);
+        }
+        Ok(Self {
+            bits,
+            next_slot: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+// Address lookup tables
+
+impl Encode for alt3::LookupTableMeta {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self
+            .deactivation_slot
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .last_extended_slot
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .last_extended_slot_start_index
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.authority.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for alt3::LookupTableMeta {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            deactivation_slot: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            last_extended_slot: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            last_extended_slot_start_index: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            authority: Decode::decode_ext(reader, ctx)?,
+            // Only exists for the on-chain account layout; not encoded.
+            _padding: 0,
+        })
+    }
+}
+
+/// Addresses go through the [`DedupeEncoder`] when one is active, so snapshots of many tables
+/// sharing accounts store each address once.
+impl Encode for alt3::AddressLookupTable<'_> {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.meta.encode_ext(writer, ctx.as_deref_mut())?;
+        let addresses: &[pubkey3::Pubkey] = &self.addresses;
+        n += Vec::<pubkey3::Pubkey>::encode_len(addresses.len(), writer)?;
+        if ctx.is_none() {
+            n += pubkey3::Pubkey::encode_slice(addresses, writer)?;
+            return Ok(n);
+        }
+        for address in addresses {
+            n += address.encode_ext(writer, ctx.as_deref_mut())?;
+        }
+        Ok(n)
+    }
+}
+impl Decode for alt3::AddressLookupTable<'_> {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        let meta = Decode::decode_ext(reader, ctx.as_deref_mut())?;
+        let addresses = Vec::<pubkey3::Pubkey>::decode_ext(reader, ctx)?;
+        Ok(Self {
+            meta,
+            addresses: std::borrow::Cow::Owned(addresses),
+        })
+    }
+}
+
+// RPC (`Ui*`) response types
+//
+// These are the JSON-facing shapes returned by `getTransaction`/`getBlock`. Encoding them lets
+// RPC caching layers persist responses without keeping the JSON around. Fields the RPC omits
+// (`OptionSerializer::Skip`) are preserved so the re-serialized JSON is identical.
+
+impl<T: Encode> Encode for ui3::option_serializer::OptionSerializer<T> {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        use ui3::option_serializer::OptionSerializer;
+        match self {
+            OptionSerializer::None => <usize as Encode>::encode_discriminant(0, writer),
+            OptionSerializer::Some(v) => {
+                let mut n = <usize as Encode>::encode_discriminant(1, writer)?;
+                n += v.encode_ext(writer, ctx)?;
+                Ok(n)
+            }
+            OptionSerializer::Skip => <usize as Encode>::encode_discriminant(2, writer),
+        }
+    }
+}
+impl<T: Decode + Sized> Decode for ui3::option_serializer::OptionSerializer<T> {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        use ui3::option_serializer::OptionSerializer;
+        Ok(match <usize as Decode>::decode_discriminant(reader)? {
+            0 => OptionSerializer::None,
+            1 => OptionSerializer::Some(T::decode_ext(reader, ctx)?),
+            2 => OptionSerializer::Skip,
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
+        })
+    }
+}
+
+/// `jsonParsed` payloads are free-form, so they are stored as compact JSON text.
+#[inline]
+fn encode_json_value(
+    value: &serde_json::Value,
+    writer: &mut impl Write,
+    ctx: Option<&mut EncoderContext>,
+) -> Result<usize> {
+    let text = serde_json::to_string(value).map_err(|_| Error::InvalidData)?;
+    text.encode_ext(writer, ctx)
+}
+#[inline]
+fn decode_json_value(
+    reader: &mut impl Read,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<serde_json::Value> {
+    let text = String::decode_ext(reader, ctx)?;
+    serde_json::from_str(&text).map_err(|_| Error::InvalidData)
+}
+
+impl Encode for ui3::UiCompiledInstruction {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self
+            .program_id_index
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.accounts.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.data.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.stack_height.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiCompiledInstruction {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            program_id_index: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            accounts: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            data: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            stack_height: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::ParsedInstruction {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.program.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.program_id.encode_ext(writer, ctx.as_deref_mut())?;
+        n += encode_json_value(&self.parsed, writer, ctx.as_deref_mut())?;
+        n += self.stack_height.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::ParsedInstruction {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            program: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            program_id: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            parsed: decode_json_value(reader, ctx.as_deref_mut())?,
+            stack_height: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::UiPartiallyDecodedInstruction {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.program_id.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.accounts.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.data.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.stack_height.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiPartiallyDecodedInstruction {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            program_id: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            accounts: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            data: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            stack_height: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::UiParsedInstruction {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        match self {
+            ui3::UiParsedInstruction::Parsed(ix) => {
+                let mut n = <usize as Encode>::encode_discriminant(0, writer)?;
+                n += ix.encode_ext(writer, ctx)?;
+                Ok(n)
+            }
+            ui3::UiParsedInstruction::PartiallyDecoded(ix) => {
+                let mut n = <usize as Encode>::encode_discriminant(1, writer)?;
+                n += ix.encode_ext(writer, ctx)?;
+                Ok(n)
+            }
+        }
+    }
+}
+impl Decode for ui3::UiParsedInstruction {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(match <usize as Decode>::decode_discriminant(reader)? {
+            0 => Self::Parsed(Decode::decode_ext(reader, ctx)?),
+            1 => Self::PartiallyDecoded(Decode::decode_ext(reader, ctx)?),
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
+        })
+    }
+}
+
+impl Encode for ui3::UiInstruction {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        match self {
+            ui3::UiInstruction::Compiled(ix) => {
+                let mut n = <usize as Encode>::encode_discriminant(0, writer)?;
+                n += ix.encode_ext(writer, ctx)?;
+                Ok(n)
+            }
+            ui3::UiInstruction::Parsed(ix) => {
+                let mut n = <usize as Encode>::encode_discriminant(1, writer)?;
+                n += ix.encode_ext(writer, ctx)?;
+                Ok(n)
+            }
+        }
+    }
+}
+impl Decode for ui3::UiInstruction {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(match <usize as Decode>::decode_discriminant(reader)? {
+            0 => Self::Compiled(Decode::decode_ext(reader, ctx)?),
+            1 => Self::Parsed(Decode::decode_ext(reader, ctx)?),
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
+        })
+    }
+}
+
+impl Encode for ui3::UiInnerInstructions {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.index.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.instructions.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiInnerInstructions {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            index: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            instructions: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::UiTransactionTokenBalance {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.account_index.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.mint.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .ui_token_amount
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.owner.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.program_id.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiTransactionTokenBalance {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            account_index: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            mint: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            ui_token_amount: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            owner: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            program_id: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::UiLoadedAddresses {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.writable.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.readonly.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiLoadedAddresses {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            writable: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            readonly: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::UiReturnDataEncoding {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        match self {
+            ui3::UiReturnDataEncoding::Base64 => <usize as Encode>::encode_discriminant(0, writer),
+        }
+    }
+}
+impl Decode for ui3::UiReturnDataEncoding {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        match <usize as Decode>::decode_discriminant(reader)? {
+            0 => Ok(Self::Base64),
+            disc => Err(Error::UnknownDiscriminant(disc as u64)),
+        }
+    }
+}
+
+impl Encode for ui3::UiTransactionReturnData {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.program_id.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.data.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiTransactionReturnData {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            program_id: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            data: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+/// `err`/`status` are stored through [`txerr3::TransactionError`], which the RPC error wrapper
+/// converts to and from losslessly.
+impl Encode for ui3::UiTransactionStatusMeta {
+    #[inline]
     fn encode_ext(
         &self,
         writer: &mut impl Write,
         mut ctx: Option<&mut EncoderContext>,
     ) -> Result<usize> {
+        let err: Option<txerr3::TransactionError> = self.err.clone().map(Into::into);
+        let status: core::result::Result<(), txerr3::TransactionError> =
+            self.status.clone().map_err(Into::into);
         let mut n = 0;
-        n += self.status.encode_ext(writer, ctx.as_deref_mut())?;
+        n += err.encode_ext(writer, ctx.as_deref_mut())?;
+        n += status.encode_ext(writer, ctx.as_deref_mut())?;
         n += self.fee.encode_ext(writer, ctx.as_deref_mut())?;
         n += self.pre_balances.encode_ext(writer, ctx.as_deref_mut())?;
         n += self.post_balances.encode_ext(writer, ctx.as_deref_mut())?;
@@ -957,155 +2331,410 @@ impl Encode for txstatus3::TransactionStatusMeta {
         n += self
             .compute_units_consumed
             .encode_ext(writer, ctx.as_deref_mut())?;
-        n += self.cost_units.encode_ext(writer, ctx)?;
+        n += self.cost_units.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiTransactionStatusMeta {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        let err: Option<txerr3::TransactionError> = Decode::decode_ext(reader, ctx.as_deref_mut())?;
+        let status: core::result::Result<(), txerr3::TransactionError> =
+            Decode::decode_ext(reader, ctx.as_deref_mut())?;
+        Ok(Self {
+            err: err.map(Into::into),
+            status: status.map_err(Into::into),
+            fee: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            pre_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            post_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            inner_instructions: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            log_messages: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            pre_token_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            post_token_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            rewards: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            loaded_addresses: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            return_data: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            compute_units_consumed: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            cost_units: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::TransactionBinaryEncoding {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        match self {
+            ui3::TransactionBinaryEncoding::Base58 => {
+                <usize as Encode>::encode_discriminant(0, writer)
+            }
+            ui3::TransactionBinaryEncoding::Base64 => {
+                <usize as Encode>::encode_discriminant(1, writer)
+            }
+        }
+    }
+}
+impl Decode for ui3::TransactionBinaryEncoding {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        match <usize as Decode>::decode_discriminant(reader)? {
+            0 => Ok(Self::Base58),
+            1 => Ok(Self::Base64),
+            disc => Err(Error::UnknownDiscriminant(disc as u64)),
+        }
+    }
+}
+
+impl Encode for ui3::UiAddressTableLookup {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.account_key.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .writable_indexes
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.readonly_indexes.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiAddressTableLookup {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            account_key: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            writable_indexes: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            readonly_indexes: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::UiRawMessage {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.header.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.account_keys.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .recent_blockhash
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.instructions.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.address_table_lookups.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiRawMessage {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            header: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            account_keys: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            recent_blockhash: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            instructions: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            address_table_lookups: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::ParsedAccountSource {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        match self {
+            ui3::ParsedAccountSource::Transaction => {
+                <usize as Encode>::encode_discriminant(0, writer)
+            }
+            ui3::ParsedAccountSource::LookupTable => {
+                <usize as Encode>::encode_discriminant(1, writer)
+            }
+        }
+    }
+}
+impl Decode for ui3::ParsedAccountSource {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        match <usize as Decode>::decode_discriminant(reader)? {
+            0 => Ok(Self::Transaction),
+            1 => Ok(Self::LookupTable),
+            disc => Err(Error::UnknownDiscriminant(disc as u64)),
+        }
+    }
+}
+
+impl Encode for ui3::ParsedAccount {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.pubkey.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.writable.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.signer.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.source.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::ParsedAccount {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            pubkey: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            writable: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            signer: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            source: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::UiParsedMessage {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.account_keys.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .recent_blockhash
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.instructions.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.address_table_lookups.encode_ext(writer, ctx)?;
         Ok(n)
     }
 }
-impl Decode for txstatus3::TransactionStatusMeta {
+impl Decode for ui3::UiParsedMessage {
     #[inline]
     fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Ok(Self {
-            status: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            fee: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            pre_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            post_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            inner_instructions: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            log_messages: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            pre_token_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            post_token_balances: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            rewards: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            loaded_addresses: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            return_data: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            compute_units_consumed: Decode::decode_ext(reader, ctx.as_deref_mut())?,
-            cost_units: Decode::decode_ext(reader, ctx)?,
+            account_keys: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            recent_blockhash: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            instructions: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            address_table_lookups: Decode::decode_ext(reader, ctx)?,
         })
     }
 }
 
-// Geyser interface types
-// Note: We intentionally do not implement Encode/Decode for agave-geyser
-// interface wrappers that carry reference fields, to avoid requiring leaked
-// allocations for decoding. These values can be reconstructed from their
-// underlying owned types when needed.
-
-// SlotStatus and GeyserPluginError
-impl Encode for ifc::SlotStatus {
+impl Encode for ui3::UiMessage {
     #[inline]
     fn encode_ext(
         &self,
         writer: &mut impl Write,
-        mut _dedupe: Option<&mut EncoderContext>,
+        ctx: Option<&mut EncoderContext>,
     ) -> Result<usize> {
         match self {
-            ifc::SlotStatus::Processed => <usize as Encode>::encode_discriminant(0, writer),
-            ifc::SlotStatus::Rooted => <usize as Encode>::encode_discriminant(1, writer),
-            ifc::SlotStatus::Confirmed => <usize as Encode>::encode_discriminant(2, writer),
-            ifc::SlotStatus::FirstShredReceived => {
-                <usize as Encode>::encode_discriminant(3, writer)
+            ui3::UiMessage::Parsed(m) => {
+                let mut n = <usize as Encode>::encode_discriminant(0, writer)?;
+                n += m.encode_ext(writer, ctx)?;
+                Ok(n)
             }
-            ifc::SlotStatus::Completed => <usize as Encode>::encode_discriminant(4, writer),
-            ifc::SlotStatus::CreatedBank => <usize as Encode>::encode_discriminant(5, writer),
-            ifc::SlotStatus::Dead(msg) => {
-                let mut n = <usize as Encode>::encode_discriminant(6, writer)?;
-                n += msg.encode_ext(writer, None)?;
+            ui3::UiMessage::Raw(m) => {
+                let mut n = <usize as Encode>::encode_discriminant(1, writer)?;
+                n += m.encode_ext(writer, ctx)?;
                 Ok(n)
             }
         }
     }
 }
-impl Decode for ifc::SlotStatus {
+impl Decode for ui3::UiMessage {
     #[inline]
-    fn decode_ext(reader: &mut impl Read, _dedupe: Option<&mut DecoderContext>) -> Result<Self> {
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Ok(match <usize as Decode>::decode_discriminant(reader)? {
-            0 => ifc::SlotStatus::Processed,
-            1 => ifc::SlotStatus::Rooted,
-            2 => ifc::SlotStatus::Confirmed,
-            3 => ifc::SlotStatus::FirstShredReceived,
-            4 => ifc::SlotStatus::Completed,
-            5 => ifc::SlotStatus::CreatedBank,
-            6 => ifc::SlotStatus::Dead(Decode::decode_ext(reader, None)?),
-            _ => return Err(Error::InvalidData),
+            0 => Self::Parsed(Decode::decode_ext(reader, ctx)?),
+            1 => Self::Raw(Decode::decode_ext(reader, ctx)?),
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
         })
     }
 }
 
-#[derive(Debug)]
-struct SimpleError(String);
-impl core::fmt::Display for SimpleError {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "{}", self.0)
+impl Encode for ui3::UiTransaction {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.signatures.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.message.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiTransaction {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            signatures: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            message: Decode::decode_ext(reader, ctx)?,
+        })
     }
 }
-impl std::error::Error for SimpleError {}
 
-impl Encode for ifc::GeyserPluginError {
+impl Encode for ui3::UiAccountsList {
     #[inline]
     fn encode_ext(
         &self,
         writer: &mut impl Write,
-        _dedupe: Option<&mut EncoderContext>,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.signatures.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.account_keys.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::UiAccountsList {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            signatures: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            account_keys: Decode::decode_ext(reader, ctx)?,
+        })
+    }
+}
+
+impl Encode for ui3::EncodedTransaction {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
     ) -> Result<usize> {
         match self {
-            ifc::GeyserPluginError::ConfigFileOpenError(e) => {
+            ui3::EncodedTransaction::LegacyBinary(data) => {
                 let mut n = <usize as Encode>::encode_discriminant(0, writer)?;
-                n += e.to_string().encode_ext(writer, None)?;
+                n += data.encode_ext(writer, ctx)?;
                 Ok(n)
             }
-            ifc::GeyserPluginError::ConfigFileReadError { msg } => {
+            ui3::EncodedTransaction::Binary(data, encoding) => {
                 let mut n = <usize as Encode>::encode_discriminant(1, writer)?;
-                n += msg.encode_ext(writer, None)?;
+                n += data.encode_ext(writer, ctx.as_deref_mut())?;
+                n += encoding.encode_ext(writer, ctx)?;
                 Ok(n)
             }
-            ifc::GeyserPluginError::AccountsUpdateError { msg } => {
+            ui3::EncodedTransaction::Json(tx) => {
                 let mut n = <usize as Encode>::encode_discriminant(2, writer)?;
-                n += msg.encode_ext(writer, None)?;
+                n += tx.encode_ext(writer, ctx)?;
                 Ok(n)
             }
-            ifc::GeyserPluginError::SlotStatusUpdateError { msg } => {
+            ui3::EncodedTransaction::Accounts(list) => {
                 let mut n = <usize as Encode>::encode_discriminant(3, writer)?;
-                n += msg.encode_ext(writer, None)?;
+                n += list.encode_ext(writer, ctx)?;
                 Ok(n)
             }
-            ifc::GeyserPluginError::Custom(err) => {
-                let mut n = <usize as Encode>::encode_discriminant(4, writer)?;
-                n += err.to_string().encode_ext(writer, None)?;
-                Ok(n)
+        }
+    }
+}
+impl Decode for ui3::EncodedTransaction {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(match <usize as Decode>::decode_discriminant(reader)? {
+            0 => Self::LegacyBinary(Decode::decode_ext(reader, ctx)?),
+            1 => Self::Binary(
+                Decode::decode_ext(reader, ctx.as_deref_mut())?,
+                Decode::decode_ext(reader, ctx)?,
+            ),
+            2 => Self::Json(Decode::decode_ext(reader, ctx)?),
+            3 => Self::Accounts(Decode::decode_ext(reader, ctx)?),
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
+        })
+    }
+}
+
+impl Encode for tx3::versioned::TransactionVersion {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        match self {
+            tx3::versioned::TransactionVersion::Legacy(_) => {
+                <usize as Encode>::encode_discriminant(0, writer)
             }
-            ifc::GeyserPluginError::TransactionUpdateError { msg } => {
-                let mut n = <usize as Encode>::encode_discriminant(5, writer)?;
-                n += msg.encode_ext(writer, None)?;
+            tx3::versioned::TransactionVersion::Number(v) => {
+                let mut n = <usize as Encode>::encode_discriminant(1, writer)?;
+                n += v.encode_ext(writer, None)?;
                 Ok(n)
             }
         }
     }
 }
-impl Decode for ifc::GeyserPluginError {
+impl Decode for tx3::versioned::TransactionVersion {
     #[inline]
-    fn decode_ext(reader: &mut impl Read, _dedupe: Option<&mut DecoderContext>) -> Result<Self> {
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
         Ok(match <usize as Decode>::decode_discriminant(reader)? {
-            0 => ifc::GeyserPluginError::ConfigFileOpenError(std::io::Error::other(
-                String::decode_ext(reader, None)?,
-            )),
-            1 => ifc::GeyserPluginError::ConfigFileReadError {
-                msg: Decode::decode_ext(reader, None)?,
-            },
-            2 => ifc::GeyserPluginError::AccountsUpdateError {
-                msg: Decode::decode_ext(reader, None)?,
-            },
-            3 => ifc::GeyserPluginError::SlotStatusUpdateError {
-                msg: Decode::decode_ext(reader, None)?,
-            },
-            4 => ifc::GeyserPluginError::Custom(Box::new(SimpleError(Decode::decode_ext(
-                reader, None,
-            )?))),
-            5 => ifc::GeyserPluginError::TransactionUpdateError {
-                msg: Decode::decode_ext(reader, None)?,
-            },
-            _ => return Err(Error::InvalidData),
+            0 => Self::Legacy(tx3::versioned::Legacy::Legacy),
+            1 => Self::Number(Decode::decode_ext(reader, None)?),
+            disc => return Err(Error::UnknownDiscriminant(disc as u64)),
+        })
+    }
+}
+
+impl Encode for ui3::EncodedTransactionWithStatusMeta {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.transaction.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.meta.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.version.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+impl Decode for ui3::EncodedTransactionWithStatusMeta {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self {
+            transaction: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            meta: Decode::decode_ext(reader, ctx.as_deref_mut())?,
+            version: Decode::decode_ext(reader, ctx)?,
         })
     }
 }
 
+#[test]
+fn test_fixed_stride_vectors_decode_from_any_reader() {
+    let keys: Vec<Pubkey> = (0..100).map(|_| Pubkey::new_unique()).collect();
+    let sigs: Vec<Signature> = (0..100u8).map(|i| Signature::from([i; 64])).collect();
+    let mut buf = Vec::new();
+    encode(&keys, &mut buf).unwrap();
+    encode(&sigs, &mut buf).unwrap();
+
+    // Zero-copy readers and plain `std::io` readers take different paths.
+    let mut cursor = Cursor::new(&buf);
+    assert_eq!(Vec::<Pubkey>::decode(&mut cursor).unwrap(), keys);
+    assert_eq!(decode_signatures(&mut cursor, None).unwrap(), sigs);
+    let mut stream = std::io::Cursor::new(&buf);
+    assert_eq!(Vec::<Pubkey>::decode(&mut stream).unwrap(), keys);
+    let mut ctx = DecoderContext::new();
+    assert_eq!(
+        decode_signatures(&mut stream, Some(&mut ctx)).unwrap(),
+        sigs
+    );
+
+    assert!(matches!(
+        Vec::<Pubkey>::decode(&mut Cursor::new(&buf[..buf.len() / 2])),
+        Err(Error::ReaderOutOfData)
+    ));
+}
+
 #[test]
 fn test_agave_slot_status_roundtrip() {
     use crate::prelude::*;
@@ -2236,3 +3865,341 @@ fn test_pubkey_deduplication_without_duplicates() {
     assert_eq!(decoded_pubkeys, pubkeys);
     assert_eq!(ctx_dec.dedupe.as_ref().unwrap().len(), 5);
 }
+
+#[test]
+fn test_solana_wire_versioned_transaction_layout() {
+    let legacy = VersionedTransaction {
+        signatures: vec![Signature::from([3u8; 64])],
+        message: msg3::VersionedMessage::Legacy(Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![Pubkey::new_from_array([1u8; 32]), Pubkey::default()],
+            recent_blockhash: Hash::new_from_array([9u8; 32]),
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                accounts: vec![0],
+                data: vec![0xaa; 200],
+            }],
+        }),
+    };
+    let mut buf = Vec::new();
+    let n = SolanaWire(legacy.clone()).encode(&mut buf).unwrap();
+    assert_eq!(n, buf.len());
+    // short_vec(1) + signature + header + short_vec(2) keys + blockhash + short_vec(1) ix
+    assert_eq!(buf[0], 1);
+    assert_eq!(&buf[65..68], &[1, 0, 1]);
+    assert_eq!(buf[68], 2);
+    assert_eq!(buf[68 + 1 + 64 + 32], 1);
+    // The 200-byte instruction data uses a two-byte compact-u16 prefix.
+    assert_eq!(&buf[buf.len() - 202..buf.len() - 200], &[0xc8, 0x01]);
+    let decoded: SolanaWire<VersionedTransaction> = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded.0, legacy);
+
+    let v0_tx = VersionedTransaction {
+        signatures: vec![Signature::from([4u8; 64])],
+        message: msg3::VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 0,
+            },
+            account_keys: vec![Pubkey::new_from_array([5u8; 32])],
+            recent_blockhash: Hash::new_from_array([6u8; 32]),
+            instructions: vec![],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_from_array([7u8; 32]),
+                writable_indexes: vec![1, 2],
+                readonly_indexes: vec![3],
+            }],
+        }),
+    };
+    let mut buf = Vec::new();
+    SolanaWire(v0_tx.clone()).encode(&mut buf).unwrap();
+    assert_eq!(buf[65], 0x80);
+    let decoded: SolanaWire<VersionedTransaction> = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded.0, v0_tx);
+
+    // Unknown message versions are rejected.
+    buf[65] = 0x81;
+    let res: Result<SolanaWire<VersionedTransaction>> = decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::UnknownDiscriminant(1))));
+}
+
+#[test]
+fn test_pubkey_set_roundtrip_and_size() {
+    let a = Pubkey::new_from_array([1u8; 32]);
+    let b = Pubkey::new_from_array([2u8; 32]);
+    let keys = vec![a, b, a, a, b, a];
+    let set = PubkeySet::from(keys.clone());
+    assert_eq!(set.table(), &[a, b]);
+    assert_eq!(set.indexes(), &[0, 1, 0, 0, 1, 0]);
+    assert_eq!(set.index_of(&b), Some(1));
+
+    let mut buf = Vec::new();
+    set.encode(&mut buf).unwrap();
+    assert_eq!(buf.len(), 1 + 2 * 32 + 1 + keys.len());
+    let decoded: PubkeySet = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, set);
+    assert_eq!(Vec::from(decoded), keys);
+
+    // Out-of-range index.
+    let last = buf.len() - 1;
+    buf[last] = 2;
+    let res: Result<PubkeySet> = decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::InvalidData)));
+
+    // A forged index count runs out of input instead of reserving for it.
+    let mut buf = Vec::new();
+    vec![a].encode(&mut buf).unwrap();
+    Lencode::encode_varint_u64(1 << 60, &mut buf).unwrap();
+    let res: Result<PubkeySet> = decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::ReaderOutOfData)));
+}
+
+#[test]
+fn test_pubkey_base58_helpers() {
+    let key = Pubkey::new_from_array([7u8; 32]);
+    let text = key.to_string();
+    let mut buf = Vec::new();
+    assert_eq!(encode_pubkey_base58(&text, &mut buf, None).unwrap(), 32);
+    assert_eq!(
+        decode_pubkey_base58(&mut Cursor::new(&buf), None).unwrap(),
+        text
+    );
+    assert!(matches!(
+        encode_pubkey_base58("not-base58!", &mut Vec::new(), None),
+        Err(Error::InvalidData)
+    ));
+}
+
+#[test]
+fn test_geyser_event_feed_roundtrip() {
+    let hash = [9u8; 32];
+    let entry = ifc::ReplicaEntryInfoV2 {
+        slot: 10,
+        index: 3,
+        num_hashes: 12_500,
+        hash: &hash,
+        executed_transaction_count: 7,
+        starting_transaction_index: 40,
+    };
+    let owned_entry = OwnedReplicaEntryInfo::from(&ifc::ReplicaEntryInfoVersions::V0_0_2(&entry));
+    assert_eq!(owned_entry.starting_transaction_index, Some(40));
+
+    let rewards = txstatus3::RewardsAndNumPartitions {
+        rewards: vec![],
+        num_partitions: Some(4),
+    };
+    let block = ifc::ReplicaBlockInfoV4 {
+        parent_slot: 9,
+        parent_blockhash: "parent",
+        slot: 10,
+        blockhash: "hash",
+        rewards: &rewards,
+        block_time: Some(1_700_000_000),
+        block_height: Some(8),
+        executed_transaction_count: 100,
+        entry_count: 64,
+    };
+    let owned_block = OwnedReplicaBlockInfo::from(&ifc::ReplicaBlockInfoVersions::V0_0_4(&block));
+    assert_eq!(owned_block.num_partitions, Some(4));
+    assert_eq!(owned_block.entry_count, Some(64));
+
+    let events = vec![
+        GeyserEvent::SlotStatus {
+            slot: 10,
+            parent: Some(9),
+            status: ifc::SlotStatus::Confirmed,
+        },
+        GeyserEvent::Entry(owned_entry.clone()),
+        GeyserEvent::BlockMetadata(owned_block.clone()),
+        GeyserEvent::EndOfStartup,
+    ];
+    let mut buf = Vec::new();
+    for event in &events {
+        event.encode(&mut buf).unwrap();
+    }
+    let mut cursor = Cursor::new(&buf);
+    match GeyserEvent::decode_ext(&mut cursor, None).unwrap() {
+        GeyserEvent::SlotStatus {
+            slot,
+            parent,
+            status,
+        } => {
+            assert_eq!((slot, parent), (10, Some(9)));
+            assert_eq!(status.as_str(), ifc::SlotStatus::Confirmed.as_str());
+        }
+        other => panic!("unexpected event {other:?}"),
+    }
+    assert!(matches!(
+        GeyserEvent::decode_ext(&mut cursor, None).unwrap(),
+        GeyserEvent::Entry(e) if e == owned_entry
+    ));
+    assert!(matches!(
+        GeyserEvent::decode_ext(&mut cursor, None).unwrap(),
+        GeyserEvent::BlockMetadata(b) if b == owned_block
+    ));
+    assert!(matches!(
+        GeyserEvent::decode_ext(&mut cursor, None).unwrap(),
+        GeyserEvent::EndOfStartup
+    ));
+}
+
+#[test]
+fn test_sysvar_snapshots_roundtrip() {
+    let clock = clock3::Clock {
+        slot: 250_000_000,
+        epoch_start_timestamp: 1_700_000_000,
+        epoch: 580,
+        leader_schedule_epoch: 581,
+        unix_timestamp: 1_700_100_000,
+    };
+    let mut buf = Vec::new();
+    clock.encode(&mut buf).unwrap();
+    let decoded: clock3::Clock = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, clock);
+
+    let info = epoch_info3::EpochInfo {
+        epoch: 580,
+        slot_index: 1234,
+        slots_in_epoch: 432_000,
+        absolute_slot: 250_000_000,
+        block_height: 230_000_000,
+        transaction_count: Some(300_000_000_000),
+    };
+    let mut buf = Vec::new();
+    info.encode(&mut buf).unwrap();
+    let decoded: epoch_info3::EpochInfo = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, info);
+}
+
+#[test]
+fn test_slot_history_rle_roundtrip() {
+    let mut history = slot_history3::SlotHistory::default();
+    for slot in 1..5_000 {
+        history.add(slot);
+    }
+    history.add(10_000);
+    let mut buf = Vec::new();
+    history.encode(&mut buf).unwrap();
+    assert!(buf.len() < 64, "encoded {} bytes", buf.len());
+    let decoded: slot_history3::SlotHistory = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, history);
+}
+
+#[test]
+fn test_address_lookup_table_roundtrip_and_dedupe() {
+    let shared: Vec<Pubkey> = (0..8u8).map(|i| Pubkey::new_from_array([i; 32])).collect();
+    let mut meta = alt3::LookupTableMeta::new(Pubkey::new_from_array([42u8; 32]));
+    meta.deactivation_slot = 300_000_000;
+    meta.last_extended_slot = 299_999_000;
+    meta.last_extended_slot_start_index = 4;
+    let tables: Vec<alt3::AddressLookupTable> = (0..4)
+        .map(|_| alt3::AddressLookupTable {
+            meta: meta.clone(),
+            addresses: std::borrow::Cow::Borrowed(&shared[..]),
+        })
+        .collect();
+
+    let mut plain = Vec::new();
+    for table in &tables {
+        table.encode(&mut plain).unwrap();
+    }
+    let mut cursor = Cursor::new(&plain);
+    for table in &tables {
+        let decoded: alt3::AddressLookupTable = decode(&mut cursor).unwrap();
+        assert_eq!(&decoded, table);
+    }
+
+    let mut enc = EncoderContext::with_dedupe();
+    let mut deduped = Vec::new();
+    for table in &tables {
+        table.encode_ext(&mut deduped, Some(&mut enc)).unwrap();
+    }
+    assert!(deduped.len() < plain.len() / 2);
+    let mut dec = DecoderContext::with_dedupe();
+    let mut cursor = Cursor::new(&deduped);
+    for table in &tables {
+        let decoded = alt3::AddressLookupTable::decode_ext(&mut cursor, Some(&mut dec)).unwrap();
+        assert_eq!(&decoded, table);
+    }
+}
+
+#[test]
+fn test_ui_rpc_transactions_roundtrip_fixture() {
+    let fixture: Vec<serde_json::Value> =
+        serde_json::from_str(include_str!("../tests/fixtures/rpc_transactions.json")).unwrap();
+    for json in fixture {
+        let tx: ui3::EncodedTransactionWithStatusMeta =
+            serde_json::from_value(json.clone()).unwrap();
+        let mut buf = Vec::new();
+        tx.encode(&mut buf).unwrap();
+        let decoded: ui3::EncodedTransactionWithStatusMeta =
+            decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(decoded, tx);
+        // Omitted RPC fields stay omitted when the decoded value is served back as JSON.
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&tx).unwrap()
+        );
+        assert!(buf.len() < serde_json::to_string(&json).unwrap().len());
+    }
+}
+
+#[test]
+fn test_forward_compatible_errors_survive_unknown_variants() {
+    let known: ForwardTransactionError =
+        txerr3::TransactionError::InstructionError(3, ixerr::InstructionError::Custom(7)).into();
+    // A variant from a newer validator, followed by a value that must still decode.
+    let mut buf = Vec::new();
+    <usize as Encode>::encode_discriminant(200, &mut buf).unwrap();
+    [1u8, 2, 3].as_slice().encode(&mut buf).unwrap();
+    known.encode(&mut buf).unwrap();
+
+    let mut cursor = Cursor::new(&buf);
+    let unknown: ForwardTransactionError = decode(&mut cursor).unwrap();
+    assert_eq!(
+        unknown,
+        ForwardCompatible::Unknown {
+            disc: 200,
+            payload: vec![1, 2, 3],
+        }
+    );
+    let decoded: ForwardTransactionError = decode(&mut cursor).unwrap();
+    assert_eq!(decoded, known);
+    assert!(decoded.known().is_some());
+
+    // Unknown variants re-encode byte for byte.
+    let mut out = Vec::new();
+    unknown.encode(&mut out).unwrap();
+    known.encode(&mut out).unwrap();
+    assert_eq!(out, buf);
+
+    // A known variant wrapping a newer `InstructionError` stays decodable as well.
+    let mut buf = Vec::new();
+    <usize as Encode>::encode_discriminant(8, &mut buf).unwrap();
+    let mut payload = vec![0u8];
+    <usize as Encode>::encode_discriminant(99, &mut payload).unwrap();
+    payload.as_slice().encode(&mut buf).unwrap();
+    let nested: ForwardTransactionError = decode(&mut Cursor::new(&buf)).unwrap();
+    assert!(matches!(nested, ForwardCompatible::Unknown { disc: 8, .. }));
+}
+
+#[test]
+fn test_txstatus_meta_encode_metrics() {
+    let meta = txstatus3::TransactionStatusMeta {
+        fee: 5000,
+        log_messages: Some(vec!["Program log: hello".to_string(); 4]),
+        ..Default::default()
+    };
+    let mut ctx = EncoderContext::with_metrics();
+    let mut buf = Vec::new();
+    let n = meta.encode_ext(&mut buf, Some(&mut ctx)).unwrap();
+    let metrics = ctx.metrics.unwrap();
+    let type_name = core::any::type_name::<txstatus3::TransactionStatusMeta>();
+    assert_eq!(metrics.type_bytes(type_name), n as u64);
+    assert_eq!(metrics.by_bytes()[0].1, "log_messages");
+}
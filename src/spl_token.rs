@@ -0,0 +1,454 @@
+//! SPL Token account state.
+//!
+//! Token accounts and mints are the account payloads indexers store most often. [`Account`]
+//! and [`Mint`] encode their fields directly (pubkeys go through the dedupe table when one is
+//! active, amounts as varints), which is far smaller than the fixed 165/82‑byte on‑chain
+//! layout.
+//!
+//! Token‑2022 accounts append a type byte and a TLV list of extensions to the base layout.
+//! [`Token2022Account`] and [`Token2022Mint`] split raw account data into the base state plus
+//! a tagged list of [`TokenExtension`]s, and can rebuild the on‑chain bytes.
+use solana_program_option::COption;
+use solana_program_pack::Pack as SplPack;
+use solana_pubkey::Pubkey;
+use spl_token_interface::state::{Account, AccountState, Mint};
+
+use crate::prelude::*;
+
+/// Offset of the account type byte in Token‑2022 account data (the size of a base account).
+const ACCOUNT_TYPE_OFFSET: usize = Account::LEN;
+/// Token‑2022 account type tag for mints.
+const ACCOUNT_TYPE_MINT: u8 = 1;
+/// Token‑2022 account type tag for token accounts.
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+#[inline(always)]
+fn coption_to_option<T: Copy>(value: &COption<T>) -> Option<T> {
+    (*value).into()
+}
+
+impl Encode for AccountState {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        (*self as u8).encode_ext(writer, None)
+    }
+}
+
+impl Decode for AccountState {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        match u8::decode_ext(reader, None)? {
+            0 => Ok(AccountState::Uninitialized),
+            1 => Ok(AccountState::Initialized),
+            2 => Ok(AccountState::Frozen),
+            state => Err(Error::UnknownDiscriminant(state as u64)),
+        }
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+impl Encode for Account {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += self.mint.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.owner.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.amount.encode_ext(writer, ctx.as_deref_mut())?;
+        n += coption_to_option(&self.delegate).encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.state.encode_ext(writer, ctx.as_deref_mut())?;
+        n += coption_to_option(&self.is_native).encode_ext(writer, ctx.as_deref_mut())?;
+        n += self
+            .delegated_amount
+            .encode_ext(writer, ctx.as_deref_mut())?;
+        n += coption_to_option(&self.close_authority).encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+
+impl Decode for Account {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Account {
+            mint: Pubkey::decode_ext(reader, ctx.as_deref_mut())?,
+            owner: Pubkey::decode_ext(reader, ctx.as_deref_mut())?,
+            amount: u64::decode_ext(reader, ctx.as_deref_mut())?,
+            delegate: Option::<Pubkey>::decode_ext(reader, ctx.as_deref_mut())?.into(),
+            state: AccountState::decode_ext(reader, ctx.as_deref_mut())?,
+            is_native: Option::<u64>::decode_ext(reader, ctx.as_deref_mut())?.into(),
+            delegated_amount: u64::decode_ext(reader, ctx.as_deref_mut())?,
+            close_authority: Option::<Pubkey>::decode_ext(reader, ctx)?.into(),
+        })
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+impl Encode for Mint {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = 0;
+        n += coption_to_option(&self.mint_authority).encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.supply.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.decimals.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.is_initialized.encode_ext(writer, ctx.as_deref_mut())?;
+        n += coption_to_option(&self.freeze_authority).encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+
+impl Decode for Mint {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Mint {
+            mint_authority: Option::<Pubkey>::decode_ext(reader, ctx.as_deref_mut())?.into(),
+            supply: u64::decode_ext(reader, ctx.as_deref_mut())?,
+            decimals: u8::decode_ext(reader, ctx.as_deref_mut())?,
+            is_initialized: bool::decode_ext(reader, ctx.as_deref_mut())?,
+            freeze_authority: Option::<Pubkey>::decode_ext(reader, ctx)?.into(),
+        })
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+/// A single Token‑2022 extension: its `ExtensionType` tag and raw value bytes.
+///
+/// Wire format: `varint(extension_type) + bytes(data)`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TokenExtension {
+    /// The Token‑2022 `ExtensionType` discriminant.
+    pub extension_type: u16,
+    /// The extension value exactly as stored on chain.
+    pub data: Vec<u8>,
+}
+
+impl Encode for TokenExtension {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.extension_type.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.data.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+
+impl Decode for TokenExtension {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(TokenExtension {
+            extension_type: u16::decode_ext(reader, ctx.as_deref_mut())?,
+            data: Vec::<u8>::decode_ext(reader, ctx)?,
+        })
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+/// Splits the extension area of Token‑2022 account data into its TLV entries.
+///
+/// `data` must be longer than a base account; the type byte must equal `account_type`.
+/// Parsing stops at the first uninitialized (zero) tag, which marks trailing padding.
+fn parse_extensions(data: &[u8], account_type: u8) -> Result<Vec<TokenExtension>> {
+    if data[ACCOUNT_TYPE_OFFSET] != account_type {
+        return Err(Error::InvalidData);
+    }
+    let mut extensions = Vec::new();
+    let mut rest = &data[ACCOUNT_TYPE_OFFSET + 1..];
+    while rest.len() >= 4 {
+        let extension_type = u16::from_le_bytes([rest[0], rest[1]]);
+        if extension_type == 0 {
+            break;
+        }
+        let len = u16::from_le_bytes([rest[2], rest[3]]) as usize;
+        let value = rest.get(4..4 + len).ok_or(Error::InvalidData)?;
+        extensions.push(TokenExtension {
+            extension_type,
+            data: value.to_vec(),
+        });
+        rest = &rest[4 + len..];
+    }
+    Ok(extensions)
+}
+
+/// Appends the account type byte and TLV entries to `out`, which holds the base state padded
+/// to the size of a base account.
+fn write_extensions(out: &mut Vec<u8>, account_type: u8, extensions: &[TokenExtension]) {
+    out.push(account_type);
+    for ext in extensions {
+        out.extend_from_slice(&ext.extension_type.to_le_bytes());
+        out.extend_from_slice(&(ext.data.len() as u16).to_le_bytes());
+        out.extend_from_slice(&ext.data);
+    }
+}
+
+/// A Token‑2022 token account: the base [`Account`] plus its extensions.
+///
+/// Plain SPL Token accounts (no extensions) are represented with an empty extension list.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Token2022Account {
+    /// The base token account state.
+    pub account: Account,
+    /// Extensions in on‑chain order.
+    pub extensions: Vec<TokenExtension>,
+}
+
+impl Token2022Account {
+    /// Parses raw on‑chain account data owned by the Token or Token‑2022 program.
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < Account::LEN {
+            return Err(Error::InvalidData);
+        }
+        let account =
+            Account::unpack_from_slice(&data[..Account::LEN]).map_err(|_| Error::InvalidData)?;
+        let extensions = if data.len() > Account::LEN {
+            parse_extensions(data, ACCOUNT_TYPE_ACCOUNT)?
+        } else {
+            Vec::new()
+        };
+        Ok(Token2022Account {
+            account,
+            extensions,
+        })
+    }
+
+    /// Rebuilds the on‑chain account data. Trailing zero padding after the last extension is
+    /// not reproduced.
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let mut out = vec![0u8; Account::LEN];
+        self.account.pack_into_slice(&mut out);
+        if !self.extensions.is_empty() {
+            write_extensions(&mut out, ACCOUNT_TYPE_ACCOUNT, &self.extensions);
+        }
+        out
+    }
+}
+
+impl Encode for Token2022Account {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.account.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.extensions.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+
+impl Decode for Token2022Account {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Token2022Account {
+            account: Account::decode_ext(reader, ctx.as_deref_mut())?,
+            extensions: Vec::<TokenExtension>::decode_ext(reader, ctx)?,
+        })
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+/// A Token‑2022 mint: the base [`Mint`] plus its extensions.
+///
+/// On chain, a mint with extensions is zero‑padded to the size of a base token account before
+/// the account type byte, so both kinds share the same extension offset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Token2022Mint {
+    /// The base mint state.
+    pub mint: Mint,
+    /// Extensions in on‑chain order.
+    pub extensions: Vec<TokenExtension>,
+}
+
+impl Token2022Mint {
+    /// Parses raw on‑chain mint data owned by the Token or Token‑2022 program.
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < Mint::LEN {
+            return Err(Error::InvalidData);
+        }
+        let mint = Mint::unpack_from_slice(&data[..Mint::LEN]).map_err(|_| Error::InvalidData)?;
+        let extensions = if data.len() > Mint::LEN {
+            if data.len() <= ACCOUNT_TYPE_OFFSET
+                || data[Mint::LEN..ACCOUNT_TYPE_OFFSET].iter().any(|&b| b != 0)
+            {
+                return Err(Error::InvalidData);
+            }
+            parse_extensions(data, ACCOUNT_TYPE_MINT)?
+        } else {
+            Vec::new()
+        };
+        Ok(Token2022Mint { mint, extensions })
+    }
+
+    /// Rebuilds the on‑chain mint data. Trailing zero padding after the last extension is not
+    /// reproduced.
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let mut out = vec![0u8; Mint::LEN];
+        self.mint.pack_into_slice(&mut out);
+        if !self.extensions.is_empty() {
+            out.resize(ACCOUNT_TYPE_OFFSET, 0);
+            write_extensions(&mut out, ACCOUNT_TYPE_MINT, &self.extensions);
+        }
+        out
+    }
+}
+
+impl Encode for Token2022Mint {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut n = self.mint.encode_ext(writer, ctx.as_deref_mut())?;
+        n += self.extensions.encode_ext(writer, ctx)?;
+        Ok(n)
+    }
+}
+
+impl Decode for Token2022Mint {
+    #[inline]
+    fn decode_ext(reader: &mut impl Read, mut ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Token2022Mint {
+            mint: Mint::decode_ext(reader, ctx.as_deref_mut())?,
+            extensions: Vec::<TokenExtension>::decode_ext(reader, ctx)?,
+        })
+    }
+
+    fn decode_len(_reader: &mut impl Read) -> Result<usize> {
+        unimplemented!()
+    }
+}
+
+#[cfg(test)]
+fn sample_account() -> Account {
+    Account {
+        mint: Pubkey::new_from_array([1; 32]),
+        owner: Pubkey::new_from_array([2; 32]),
+        amount: 1_250_000,
+        delegate: COption::Some(Pubkey::new_from_array([3; 32])),
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 500,
+        close_authority: COption::None,
+    }
+}
+
+#[test]
+fn test_spl_account_and_mint_roundtrip() {
+    let account = sample_account();
+    let mut buf = Vec::new();
+    account.encode(&mut buf).unwrap();
+    assert!(buf.len() < Account::LEN);
+    let decoded: Account = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, account);
+
+    let mint = Mint {
+        mint_authority: COption::Some(Pubkey::new_from_array([4; 32])),
+        supply: 1_000_000_000,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    buf.clear();
+    mint.encode(&mut buf).unwrap();
+    let decoded: Mint = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, mint);
+
+    let res: Result<AccountState> = decode(&mut Cursor::new(&[3u8][..]));
+    assert!(matches!(res, Err(Error::UnknownDiscriminant(3))));
+}
+
+#[test]
+fn test_spl_accounts_share_pubkeys_with_dedupe() {
+    let accounts: Vec<Account> = (0..8u64)
+        .map(|i| Account {
+            amount: i,
+            ..sample_account()
+        })
+        .collect();
+    let mut plain = Vec::new();
+    accounts.encode(&mut plain).unwrap();
+    let mut enc = EncoderContext::with_dedupe();
+    let mut deduped = Vec::new();
+    accounts.encode_ext(&mut deduped, Some(&mut enc)).unwrap();
+    assert!(deduped.len() < plain.len() / 2);
+    let mut dec = DecoderContext::with_dedupe();
+    let decoded: Vec<Account> = decode_ext(&mut Cursor::new(&deduped), Some(&mut dec)).unwrap();
+    assert_eq!(decoded, accounts);
+}
+
+#[test]
+fn test_token_2022_extensions_roundtrip() {
+    let account = Token2022Account {
+        account: sample_account(),
+        extensions: vec![
+            // ImmutableOwner carries no data; TransferFeeAmount stores a u64.
+            TokenExtension {
+                extension_type: 7,
+                data: Vec::new(),
+            },
+            TokenExtension {
+                extension_type: 2,
+                data: 42u64.to_le_bytes().to_vec(),
+            },
+        ],
+    };
+    let raw = account.to_account_data();
+    assert_eq!(raw[ACCOUNT_TYPE_OFFSET], ACCOUNT_TYPE_ACCOUNT);
+    assert_eq!(Token2022Account::from_account_data(&raw).unwrap(), account);
+
+    let mut buf = Vec::new();
+    account.encode(&mut buf).unwrap();
+    let decoded: Token2022Account = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, account);
+
+    let mint = Token2022Mint {
+        mint: Mint {
+            supply: 7,
+            decimals: 9,
+            is_initialized: true,
+            ..Default::default()
+        },
+        extensions: vec![TokenExtension {
+            extension_type: 18,
+            data: vec![5; 64],
+        }],
+    };
+    let raw = mint.to_account_data();
+    assert_eq!(raw.len(), ACCOUNT_TYPE_OFFSET + 1 + 4 + 64);
+    assert_eq!(Token2022Mint::from_account_data(&raw).unwrap(), mint);
+
+    // Plain SPL Token layouts parse with no extensions, and a wrong type byte is rejected.
+    let plain = Token2022Mint::from_account_data(&raw[..Mint::LEN]).unwrap();
+    assert!(plain.extensions.is_empty());
+    let mut bad = account.to_account_data();
+    bad[ACCOUNT_TYPE_OFFSET] = ACCOUNT_TYPE_MINT;
+    assert!(Token2022Account::from_account_data(&bad).is_err());
+}
@@ -0,0 +1,241 @@
+//! A canonical wire type for points in time.
+//!
+//! [`Timestamp`] is a count of nanoseconds since the Unix epoch, negative for times before
+//! 1970, written as a ZigZag varint: the same encoding as an `i128`. Converting every
+//! timestamp to it before encoding means payloads agree on one representation no matter
+//! which clock or date library produced them. Conversions are provided for
+//! `std::time::SystemTime` (`std` feature), `time::OffsetDateTime` (`time` feature) and
+//! `chrono::DateTime` (`chrono` feature).
+//!
+//! Conversions into a [`Timestamp`] are lossless. Conversions out of one fail with
+//! [`Error::OutOfRange`] when the target type cannot represent the instant.
+//!
+//! `std::time::Instant` is deliberately not supported: it is only meaningful within the
+//! process that created it, so it has no portable encoding. Record [`Timestamp::now`]
+//! alongside it, or send a `Duration` measured from it instead.
+//!
+//! ```
+//! # #[cfg(feature = "std")]
+//! # {
+//! use lencode::prelude::*;
+//! use std::time::{Duration, SystemTime, UNIX_EPOCH};
+//!
+//! let sent = UNIX_EPOCH + Duration::from_nanos(1_700_000_000_123_456_789);
+//! let mut buf = Vec::new();
+//! encode(&Timestamp::from(sent), &mut buf).unwrap();
+//! let ts: Timestamp = decode(&mut Cursor::new(&buf)).unwrap();
+//! assert_eq!(ts.unix_nanos(), 1_700_000_000_123_456_789);
+//! assert_eq!(SystemTime::try_from(ts).unwrap(), sent);
+//! # }
+//! ```
+use crate::prelude::*;
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// Nanoseconds since the Unix epoch. See the [module docs](self).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    nanos: i128,
+}
+
+impl Timestamp {
+    /// 1970‑01‑01T00:00:00Z.
+    pub const UNIX_EPOCH: Self = Self { nanos: 0 };
+
+    /// Creates a timestamp `nanos` nanoseconds after (or, if negative, before) the Unix epoch.
+    #[inline(always)]
+    pub const fn from_unix_nanos(nanos: i128) -> Self {
+        Self { nanos }
+    }
+
+    /// Returns the nanoseconds since the Unix epoch.
+    #[inline(always)]
+    pub const fn unix_nanos(self) -> i128 {
+        self.nanos
+    }
+
+    /// Returns the whole seconds since the Unix epoch, rounded towards negative infinity.
+    #[inline(always)]
+    pub const fn unix_secs(self) -> i128 {
+        self.nanos.div_euclid(NANOS_PER_SEC)
+    }
+
+    /// Returns the nanoseconds past [`unix_secs`](Self::unix_secs), in `0..1_000_000_000`.
+    #[inline(always)]
+    pub const fn subsec_nanos(self) -> u32 {
+        self.nanos.rem_euclid(NANOS_PER_SEC) as u32
+    }
+
+    /// Returns the current system time.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn now() -> Self {
+        std::time::SystemTime::now().into()
+    }
+
+    /// Returns the error for a timestamp that does not fit in a type whose latest
+    /// representable instant is `max`.
+    #[cfg(any(feature = "std", feature = "time", feature = "chrono"))]
+    #[inline(always)]
+    const fn out_of_range(self, max: Self) -> Error {
+        Error::OutOfRange {
+            value: self.nanos,
+            max: max.nanos,
+        }
+    }
+}
+
+impl Encode for Timestamp {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        self.nanos.encode_ext(writer, ctx)
+    }
+}
+
+impl Decode for Timestamp {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Ok(Self::from_unix_nanos(i128::decode_ext(reader, ctx)?))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::time::SystemTime> for Timestamp {
+    #[inline]
+    fn from(time: std::time::SystemTime) -> Self {
+        match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(after) => Self::from_unix_nanos(after.as_nanos() as i128),
+            Err(before) => Self::from_unix_nanos(-(before.duration().as_nanos() as i128)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<Timestamp> for std::time::SystemTime {
+    type Error = Error;
+
+    /// Fails if the platform's `SystemTime` cannot hold `ts`. The reported `max` is the
+    /// latest instant of a 64‑bit seconds clock, which some platforms do not reach.
+    fn try_from(ts: Timestamp) -> Result<Self> {
+        let max = Timestamp::from_unix_nanos(i64::MAX as i128 * NANOS_PER_SEC + 999_999_999);
+        let magnitude = ts.nanos.unsigned_abs();
+        let offset = std::time::Duration::new(
+            u64::try_from(magnitude / NANOS_PER_SEC as u128).map_err(|_| ts.out_of_range(max))?,
+            (magnitude % NANOS_PER_SEC as u128) as u32,
+        );
+        if ts.nanos >= 0 {
+            std::time::UNIX_EPOCH.checked_add(offset)
+        } else {
+            std::time::UNIX_EPOCH.checked_sub(offset)
+        }
+        .ok_or_else(|| ts.out_of_range(max))
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<::time::OffsetDateTime> for Timestamp {
+    #[inline]
+    fn from(time: ::time::OffsetDateTime) -> Self {
+        Self::from_unix_nanos(time.unix_timestamp_nanos())
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<Timestamp> for ::time::OffsetDateTime {
+    type Error = Error;
+
+    /// Fails outside the years `time` supports (±9999 without its `large-dates` feature).
+    /// The result is in UTC.
+    fn try_from(ts: Timestamp) -> Result<Self> {
+        ::time::OffsetDateTime::from_unix_timestamp_nanos(ts.nanos)
+            .map_err(|_| ts.out_of_range(::time::PrimitiveDateTime::MAX.assume_utc().into()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: ::chrono::TimeZone> From<::chrono::DateTime<Tz>> for Timestamp {
+    #[inline]
+    fn from(time: ::chrono::DateTime<Tz>) -> Self {
+        Self::from_unix_nanos(
+            time.timestamp() as i128 * NANOS_PER_SEC + time.timestamp_subsec_nanos() as i128,
+        )
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<Timestamp> for ::chrono::DateTime<::chrono::Utc> {
+    type Error = Error;
+
+    /// Fails outside the roughly ±262,000 years `chrono` supports.
+    fn try_from(ts: Timestamp) -> Result<Self> {
+        i64::try_from(ts.unix_secs())
+            .ok()
+            .and_then(|secs| Self::from_timestamp(secs, ts.subsec_nanos()))
+            .ok_or_else(|| ts.out_of_range(Self::MAX_UTC.into()))
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_timestamp_roundtrips_system_time_before_and_after_epoch() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    for (time, nanos, len) in [
+        (UNIX_EPOCH, 0, 1),
+        (
+            UNIX_EPOCH + Duration::new(1_700_000_000, 5),
+            1_700_000_000_000_000_005,
+            9,
+        ),
+        (UNIX_EPOCH - Duration::new(1, 500), -1_000_000_500, 5),
+    ] {
+        let ts = Timestamp::from(time);
+        assert_eq!(ts.unix_nanos(), nanos);
+        let mut buf = Vec::new();
+        assert_eq!(encode(&ts, &mut buf).unwrap(), len);
+        let mut i128_buf = Vec::new();
+        encode(&nanos, &mut i128_buf).unwrap();
+        assert_eq!(buf, i128_buf);
+        let decoded: Timestamp = decode(&mut Cursor::new(&buf)).unwrap();
+        assert_eq!(SystemTime::try_from(decoded).unwrap(), time);
+    }
+
+    let before = Timestamp::from_unix_nanos(-1_000_000_500);
+    assert_eq!(
+        (before.unix_secs(), before.subsec_nanos()),
+        (-2, 999_999_500)
+    );
+    assert!(matches!(
+        SystemTime::try_from(Timestamp::from_unix_nanos(i128::MAX)),
+        Err(Error::OutOfRange { .. })
+    ));
+}
+
+#[cfg(all(feature = "time", feature = "chrono"))]
+#[test]
+fn test_timestamp_agrees_across_time_and_chrono() {
+    let nanos = -86_400_000_000_001;
+    let odt = ::time::OffsetDateTime::from_unix_timestamp_nanos(nanos).unwrap();
+    let ts = Timestamp::from(odt);
+    let chrono: ::chrono::DateTime<::chrono::Utc> = ts.try_into().unwrap();
+    assert_eq!(
+        (chrono.timestamp(), chrono.timestamp_subsec_nanos()),
+        (-86_401, 999_999_999)
+    );
+    assert_eq!(Timestamp::from(chrono), ts);
+    assert_eq!(::time::OffsetDateTime::try_from(ts).unwrap(), odt);
+
+    let far = Timestamp::from_unix_nanos(i128::MAX);
+    assert!(matches!(
+        ::time::OffsetDateTime::try_from(far),
+        Err(Error::OutOfRange { .. })
+    ));
+    assert!(matches!(
+        ::chrono::DateTime::<::chrono::Utc>::try_from(far),
+        Err(Error::OutOfRange { .. })
+    ));
+}
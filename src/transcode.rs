@@ -0,0 +1,124 @@
+//! Transcoding between lencode and other serde formats, for gradual migrations.
+//!
+//! While some services still speak an older format, these helpers convert single values at
+//! the boundary: `from_*` reads one foreign‑format value into `T` and writes it as lencode,
+//! `to_*` reads one lencode value and returns it in the foreign format. `T` needs both the
+//! serde and the lencode traits, which a type deriving `Serialize, Deserialize, Encode,
+//! Decode` has.
+//!
+//! - `postcard` feature: [`from_postcard`], [`to_postcard`].
+//! - `cbor` feature (implies `std`): [`from_cbor`], [`to_cbor`], using `ciborium`.
+//!
+//! The `from_*` functions return `(bytes consumed, bytes written)`, so concatenated records
+//! can be walked. Malformed foreign input fails with [`Error::InvalidData`].
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::prelude::*;
+
+/// Reads one postcard‑encoded `T` from the start of `bytes` and writes it to `writer` as
+/// lencode.
+///
+/// Returns `(bytes consumed, bytes written)`.
+#[cfg(feature = "postcard")]
+pub fn from_postcard<T: DeserializeOwned + Encode>(
+    bytes: &[u8],
+    writer: &mut impl Write,
+    ctx: Option<&mut EncoderContext>,
+) -> Result<(usize, usize)> {
+    let (value, rest): (T, &[u8]) =
+        postcard::take_from_bytes(bytes).map_err(|_| Error::InvalidData)?;
+    Ok((bytes.len() - rest.len(), value.encode_ext(writer, ctx)?))
+}
+
+/// Decodes one lencode `T` from `reader` and returns it encoded with postcard.
+#[cfg(feature = "postcard")]
+pub fn to_postcard<T: Decode + Serialize>(
+    reader: &mut impl Read,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<Vec<u8>> {
+    let value = T::decode_ext(reader, ctx)?;
+    postcard::to_allocvec(&value).map_err(|_| Error::InvalidData)
+}
+
+/// Reads one CBOR‑encoded `T` from the start of `bytes` and writes it to `writer` as
+/// lencode.
+///
+/// Returns `(bytes consumed, bytes written)`.
+#[cfg(feature = "cbor")]
+pub fn from_cbor<T: DeserializeOwned + Encode>(
+    bytes: &[u8],
+    writer: &mut impl Write,
+    ctx: Option<&mut EncoderContext>,
+) -> Result<(usize, usize)> {
+    let mut rest = bytes;
+    let value: T = ciborium::from_reader(&mut rest).map_err(|_| Error::InvalidData)?;
+    Ok((bytes.len() - rest.len(), value.encode_ext(writer, ctx)?))
+}
+
+/// Decodes one lencode `T` from `reader` and returns it encoded as CBOR.
+#[cfg(feature = "cbor")]
+pub fn to_cbor<T: Decode + Serialize>(
+    reader: &mut impl Read,
+    ctx: Option<&mut DecoderContext>,
+) -> Result<Vec<u8>> {
+    let value = T::decode_ext(reader, ctx)?;
+    let mut out = Vec::new();
+    ciborium::into_writer(&value, &mut out).map_err(|_| Error::InvalidData)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+type Record = (u64, String, Vec<u32>, Option<bool>);
+
+#[cfg(feature = "postcard")]
+#[test]
+fn test_postcard_transcoding() {
+    let records: [Record; 2] = [
+        (1, String::from("alpha"), vec![1, 2, 3], Some(true)),
+        (u64::MAX, String::new(), vec![], None),
+    ];
+    let mut postcard_bytes = Vec::new();
+    for record in &records {
+        postcard_bytes.extend(postcard::to_allocvec(record).unwrap());
+    }
+
+    let mut lencode_bytes = Vec::new();
+    let mut offset = 0;
+    while offset < postcard_bytes.len() {
+        let (consumed, _) =
+            from_postcard::<Record>(&postcard_bytes[offset..], &mut lencode_bytes, None).unwrap();
+        offset += consumed;
+    }
+    let mut cursor = Cursor::new(&lencode_bytes);
+    for record in &records {
+        assert_eq!(decode::<Record>(&mut cursor).unwrap(), *record);
+    }
+
+    let mut cursor = Cursor::new(&lencode_bytes);
+    let mut back = Vec::new();
+    for _ in &records {
+        back.extend(to_postcard::<Record>(&mut cursor, None).unwrap());
+    }
+    assert_eq!(back, postcard_bytes);
+    assert!(from_postcard::<Record>(&[0xff], &mut Vec::new(), None).is_err());
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_transcoding() {
+    let record: Record = (300, String::from("beta"), vec![9; 4], Some(false));
+    let mut cbor_bytes = Vec::new();
+    ciborium::into_writer(&record, &mut cbor_bytes).unwrap();
+    cbor_bytes.push(0xaa);
+
+    let mut lencode_bytes = Vec::new();
+    let (consumed, written) = from_cbor::<Record>(&cbor_bytes, &mut lencode_bytes, None).unwrap();
+    assert_eq!(consumed, cbor_bytes.len() - 1);
+    assert_eq!(written, lencode_bytes.len());
+    let back = to_cbor::<Record>(&mut Cursor::new(&lencode_bytes), None).unwrap();
+    assert_eq!(back, cbor_bytes[..consumed]);
+    assert!(from_cbor::<Record>(&[0xff], &mut Vec::new(), None).is_err());
+}
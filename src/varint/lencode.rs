@@ -1,3 +1,4 @@
+use crate::io::read_exact;
 use crate::prelude::*;
 #[cfg(test)]
 use core::mem;
@@ -49,8 +50,9 @@ fn from_le_bytes<I: UnsignedInteger>(le: &[u8]) -> I {
 pub enum Lencode {}
 
 impl Lencode {
+    /// Encodes a `u16` varint. Equivalent to [`Lencode::encode_varint`] specialized for `u16`.
     #[inline(always)]
-    pub(crate) fn encode_varint_u16(val: u16, writer: &mut impl Write) -> Result<usize> {
+    pub fn encode_varint_u16(val: u16, writer: &mut impl Write) -> Result<usize> {
         // Zero-copy fast path — single upfront length check covers all cases
         if let Some(dst) = writer.buf_mut() {
             if dst.len() >= 3 {
@@ -92,7 +94,7 @@ impl Lencode {
         // Fallback
         if val <= 0x7F {
             let byte = val as u8;
-            writer.write(core::slice::from_ref(&byte))?;
+            write_all(writer, core::slice::from_ref(&byte))?;
             return Ok(1);
         }
         let n = ((16 - val.leading_zeros() + 7) >> 3) as usize;
@@ -102,12 +104,13 @@ impl Lencode {
         unsafe {
             (out.as_mut_ptr().add(1) as *mut [u8; 2]).write_unaligned(le);
         }
-        writer.write(&out[..(1 + n)])?;
+        write_whole(writer, &out[..(1 + n)])?;
         Ok(1 + n)
     }
 
+    /// Encodes a `u32` varint. Equivalent to [`Lencode::encode_varint`] specialized for `u32`.
     #[inline(always)]
-    pub(crate) fn encode_varint_u32(val: u32, writer: &mut impl Write) -> Result<usize> {
+    pub fn encode_varint_u32(val: u32, writer: &mut impl Write) -> Result<usize> {
         // Zero-copy fast path — single upfront length check covers all cases
         if let Some(dst) = writer.buf_mut() {
             if dst.len() >= 5 {
@@ -149,7 +152,7 @@ impl Lencode {
         // Fallback
         if val <= 0x7F {
             let byte = val as u8;
-            writer.write(core::slice::from_ref(&byte))?;
+            write_all(writer, core::slice::from_ref(&byte))?;
             return Ok(1);
         }
         let n = ((32 - val.leading_zeros() + 7) >> 3) as usize;
@@ -159,12 +162,13 @@ impl Lencode {
         unsafe {
             (out.as_mut_ptr().add(1) as *mut [u8; 4]).write_unaligned(le);
         }
-        writer.write(&out[..(1 + n)])?;
+        write_whole(writer, &out[..(1 + n)])?;
         Ok(1 + n)
     }
 
+    /// Encodes a `u64` varint. Equivalent to [`Lencode::encode_varint`] specialized for `u64`.
     #[inline(always)]
-    pub(crate) fn encode_varint_u64(val: u64, writer: &mut impl Write) -> Result<usize> {
+    pub fn encode_varint_u64(val: u64, writer: &mut impl Write) -> Result<usize> {
         // Zero-copy fast path — single upfront length check covers all cases
         if let Some(dst) = writer.buf_mut() {
             if dst.len() >= 9 {
@@ -206,7 +210,7 @@ impl Lencode {
         // Fallback
         if val <= 0x7F {
             let byte = val as u8;
-            writer.write(core::slice::from_ref(&byte))?;
+            write_all(writer, core::slice::from_ref(&byte))?;
             return Ok(1);
         }
         let n = ((64 - val.leading_zeros() + 7) >> 3) as usize;
@@ -216,12 +220,13 @@ impl Lencode {
         unsafe {
             (out.as_mut_ptr().add(1) as *mut [u8; 8]).write_unaligned(le);
         }
-        writer.write(&out[..(1 + n)])?;
+        write_whole(writer, &out[..(1 + n)])?;
         Ok(1 + n)
     }
 
+    /// Encodes a `u128` varint. Equivalent to [`Lencode::encode_varint`] specialized for `u128`.
     #[inline(always)]
-    pub(crate) fn encode_varint_u128(val: u128, writer: &mut impl Write) -> Result<usize> {
+    pub fn encode_varint_u128(val: u128, writer: &mut impl Write) -> Result<usize> {
         // Zero-copy fast path — single upfront length check covers all cases
         if let Some(dst) = writer.buf_mut() {
             if dst.len() >= 17 {
@@ -266,7 +271,7 @@ impl Lencode {
         // Fallback
         if val <= 0x7F {
             let byte = val as u8;
-            writer.write(core::slice::from_ref(&byte))?;
+            write_all(writer, core::slice::from_ref(&byte))?;
             return Ok(1);
         }
         let n = ((128 - val.leading_zeros() + 7) >> 3) as usize;
@@ -276,32 +281,37 @@ impl Lencode {
         unsafe {
             (out.as_mut_ptr().add(1) as *mut [u8; 16]).write_unaligned(le);
         }
-        writer.write(&out[..(1 + n)])?;
+        write_whole(writer, &out[..(1 + n)])?;
         Ok(1 + n)
     }
 
+    /// Encodes an `i16` as a zigzag varint.
     #[inline(always)]
-    pub(crate) fn encode_varint_i16(val: i16, writer: &mut impl Write) -> Result<usize> {
+    pub fn encode_varint_i16(val: i16, writer: &mut impl Write) -> Result<usize> {
         Self::encode_varint_u16(zigzag_encode(val), writer)
     }
 
+    /// Encodes an `i32` as a zigzag varint.
     #[inline(always)]
-    pub(crate) fn encode_varint_i32(val: i32, writer: &mut impl Write) -> Result<usize> {
+    pub fn encode_varint_i32(val: i32, writer: &mut impl Write) -> Result<usize> {
         Self::encode_varint_u32(zigzag_encode(val), writer)
     }
 
+    /// Encodes an `i64` as a zigzag varint.
     #[inline(always)]
-    pub(crate) fn encode_varint_i64(val: i64, writer: &mut impl Write) -> Result<usize> {
+    pub fn encode_varint_i64(val: i64, writer: &mut impl Write) -> Result<usize> {
         Self::encode_varint_u64(zigzag_encode(val), writer)
     }
 
+    /// Encodes an `i128` as a zigzag varint.
     #[inline(always)]
-    pub(crate) fn encode_varint_i128(val: i128, writer: &mut impl Write) -> Result<usize> {
+    pub fn encode_varint_i128(val: i128, writer: &mut impl Write) -> Result<usize> {
         Self::encode_varint_u128(zigzag_encode(val), writer)
     }
 
+    /// Decodes a `u16` varint. Equivalent to [`Lencode::decode_varint`] specialized for `u16`.
     #[inline(always)]
-    pub(crate) fn decode_varint_u16(reader: &mut impl Read) -> Result<u16> {
+    pub fn decode_varint_u16(reader: &mut impl Read) -> Result<u16> {
         // Zero-copy fast path — single upfront length check
         if let Some(slice) = reader.buf() {
             if slice.len() >= 3 {
@@ -311,6 +321,9 @@ impl Lencode {
                     return Ok(first as u16);
                 }
                 let n = (first & 0x7F) as usize;
+                if n > 2 {
+                    return Err(Error::InvalidData);
+                }
                 let raw =
                     u16::from_le(unsafe { (slice.as_ptr().add(1) as *const u16).read_unaligned() });
                 let val = if n < 2 {
@@ -331,6 +344,9 @@ impl Lencode {
                 return Ok(first as u16);
             }
             let n = (first & 0x7F) as usize;
+            if n > 2 {
+                return Err(Error::InvalidData);
+            }
             if 1 + n > slice.len() {
                 return Err(Error::ReaderOutOfData);
             }
@@ -348,13 +364,17 @@ impl Lencode {
             return Ok(first as u16);
         }
         let n = (first & 0x7F) as usize;
+        if n > 2 {
+            return Err(Error::InvalidData);
+        }
         let mut bytes = [0u8; 2];
         reader.read(&mut bytes[..n])?;
         Ok(u16::from_le_bytes(bytes))
     }
 
+    /// Decodes a `u32` varint. Equivalent to [`Lencode::decode_varint`] specialized for `u32`.
     #[inline(always)]
-    pub(crate) fn decode_varint_u32(reader: &mut impl Read) -> Result<u32> {
+    pub fn decode_varint_u32(reader: &mut impl Read) -> Result<u32> {
         // Zero-copy fast path — single upfront length check covers all cases
         if let Some(slice) = reader.buf() {
             if slice.len() >= 5 {
@@ -364,6 +384,9 @@ impl Lencode {
                     return Ok(first as u32);
                 }
                 let n = (first & 0x7F) as usize;
+                if n > 4 {
+                    return Err(Error::InvalidData);
+                }
                 let raw =
                     u32::from_le(unsafe { (slice.as_ptr().add(1) as *const u32).read_unaligned() });
                 let val = if n < 4 {
@@ -384,6 +407,9 @@ impl Lencode {
                 return Ok(first as u32);
             }
             let n = (first & 0x7F) as usize;
+            if n > 4 {
+                return Err(Error::InvalidData);
+            }
             if 1 + n > slice.len() {
                 return Err(Error::ReaderOutOfData);
             }
@@ -401,13 +427,17 @@ impl Lencode {
             return Ok(first as u32);
         }
         let n = (first & 0x7F) as usize;
+        if n > 4 {
+            return Err(Error::InvalidData);
+        }
         let mut bytes = [0u8; 4];
         reader.read(&mut bytes[..n])?;
         Ok(u32::from_le_bytes(bytes))
     }
 
+    /// Decodes a `u64` varint. Equivalent to [`Lencode::decode_varint`] specialized for `u64`.
     #[inline(always)]
-    pub(crate) fn decode_varint_u64(reader: &mut impl Read) -> Result<u64> {
+    pub fn decode_varint_u64(reader: &mut impl Read) -> Result<u64> {
         // Zero-copy fast path — single upfront length check covers all cases
         if let Some(slice) = reader.buf() {
             if slice.len() >= 9 {
@@ -417,6 +447,9 @@ impl Lencode {
                     return Ok(first as u64);
                 }
                 let n = (first & 0x7F) as usize;
+                if n > 8 {
+                    return Err(Error::InvalidData);
+                }
                 let raw =
                     u64::from_le(unsafe { (slice.as_ptr().add(1) as *const u64).read_unaligned() });
                 let val = if n < 8 {
@@ -437,6 +470,9 @@ impl Lencode {
                 return Ok(first as u64);
             }
             let n = (first & 0x7F) as usize;
+            if n > 8 {
+                return Err(Error::InvalidData);
+            }
             if 1 + n > slice.len() {
                 return Err(Error::ReaderOutOfData);
             }
@@ -454,13 +490,17 @@ impl Lencode {
             return Ok(first as u64);
         }
         let n = (first & 0x7F) as usize;
+        if n > 8 {
+            return Err(Error::InvalidData);
+        }
         let mut bytes = [0u8; 8];
         reader.read(&mut bytes[..n])?;
         Ok(u64::from_le_bytes(bytes))
     }
 
+    /// Decodes a `u128` varint. Equivalent to [`Lencode::decode_varint`] specialized for `u128`.
     #[inline(always)]
-    pub(crate) fn decode_varint_u128(reader: &mut impl Read) -> Result<u128> {
+    pub fn decode_varint_u128(reader: &mut impl Read) -> Result<u128> {
         // Zero-copy fast path — single upfront length check
         if let Some(slice) = reader.buf() {
             if slice.len() >= 17 {
@@ -470,6 +510,9 @@ impl Lencode {
                     return Ok(first as u128);
                 }
                 let n = (first & 0x7F) as usize;
+                if n > 16 {
+                    return Err(Error::InvalidData);
+                }
                 // Load as two u64s — avoids slow u128 read_unaligned on aarch64
                 let ptr = unsafe { slice.as_ptr().add(1) };
                 let lo = unsafe { u64::from_le((ptr as *const u64).read_unaligned()) } as u128;
@@ -494,6 +537,9 @@ impl Lencode {
                 return Ok(first as u128);
             }
             let n = (first & 0x7F) as usize;
+            if n > 16 {
+                return Err(Error::InvalidData);
+            }
             if 1 + n > slice.len() {
                 return Err(Error::ReaderOutOfData);
             }
@@ -511,12 +557,122 @@ impl Lencode {
             return Ok(first as u128);
         }
         let n = (first & 0x7F) as usize;
+        if n > 16 {
+            return Err(Error::InvalidData);
+        }
         let mut bytes = [0u8; 16];
         reader.read(&mut bytes[..n])?;
         Ok(u128::from_le_bytes(bytes))
     }
 }
 
+/// A reader that yields an already consumed header byte before the rest of `inner`.
+pub(crate) struct Prefixed<'a, R: Read> {
+    pub(crate) first: Option<u8>,
+    pub(crate) inner: &'a mut R,
+}
+
+impl<R: Read> Read for Prefixed<'_, R> {
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match (self.first.take(), buf.split_first_mut()) {
+            (Some(first), Some((head, rest))) => {
+                *head = first;
+                if rest.is_empty() {
+                    return Ok(1);
+                }
+                Ok(1 + self.inner.read(rest)?)
+            }
+            (first, _) => {
+                self.first = first;
+                self.inner.read(buf)
+            }
+        }
+    }
+}
+
+/// Converts `value` to `i128` for [`Error::OutOfRange`], saturating at `i128::MAX`.
+fn saturating_i128<I: UnsignedInteger>(value: I) -> i128 {
+    let mut out = 0u128;
+    for (i, &byte) in value.le_bytes().iter().enumerate() {
+        if i >= 16 {
+            if byte != 0 {
+                return i128::MAX;
+            }
+        } else {
+            out |= (byte as u128) << (i * 8);
+        }
+    }
+    i128::try_from(out).unwrap_or(i128::MAX)
+}
+
+impl Lencode {
+    /// Decodes an unsigned varint, failing with [`Error::OutOfRange`] if it exceeds `max`.
+    ///
+    /// The header byte states how many payload bytes follow, so a value needing more bytes
+    /// than `max` is rejected before its payload is read; in that case the error reports the
+    /// smallest value with that many bytes. This relies on the minimal encoding Lencode
+    /// writes: a payload padded with leading zero bytes is rejected even if its value fits.
+    pub fn decode_varint_bounded<I: UnsignedInteger + PartialOrd>(
+        reader: &mut impl Read,
+        max: I,
+    ) -> Result<I> {
+        let max_bytes = max
+            .le_bytes()
+            .iter()
+            .rposition(|&byte| byte != 0)
+            .map_or(0, |i| i + 1);
+        let header = match reader.buf() {
+            Some(buf) => *buf.first().ok_or(Error::ReaderOutOfData)?,
+            None => {
+                let mut first = 0u8;
+                read_exact(reader, core::slice::from_mut(&mut first))?;
+                first
+            }
+        };
+        let payload_bytes = (header & 0x7F) as usize;
+        if header & 0x80 != 0 && payload_bytes > max_bytes {
+            let lower_bound = if payload_bytes > 16 {
+                i128::MAX
+            } else {
+                i128::try_from(1u128 << ((payload_bytes - 1) * 8)).unwrap_or(i128::MAX)
+            };
+            return Err(Error::OutOfRange {
+                value: lower_bound,
+                max: saturating_i128(max),
+            });
+        }
+        let value: I = if reader.buf().is_some() {
+            Self::decode_varint(reader)?
+        } else {
+            Self::decode_varint(&mut Prefixed {
+                first: Some(header),
+                inner: reader,
+            })?
+        };
+        if value > max {
+            return Err(Error::OutOfRange {
+                value: saturating_i128(value),
+                max: saturating_i128(max),
+            });
+        }
+        Ok(value)
+    }
+
+    /// Decodes the varint at the front of a zero‑copy reader without consuming it.
+    ///
+    /// Returns the value and its encoded length in bytes, or `Ok(None)` if `reader` does not
+    /// expose its buffer through [`Read::buf`].
+    pub fn peek_varint<I: UnsignedInteger>(reader: &impl Read) -> Result<Option<(I, usize)>> {
+        let Some(buf) = reader.buf() else {
+            return Ok(None);
+        };
+        let mut cursor = Cursor::new(buf);
+        let value = Self::decode_varint(&mut cursor)?;
+        Ok(Some((value, cursor.position())))
+    }
+}
+
 impl VarintEncodingScheme for Lencode {
     #[inline(always)]
     fn encode_varint<I: UnsignedInteger>(val: I, writer: &mut impl Write) -> Result<usize> {
@@ -553,7 +709,7 @@ impl VarintEncodingScheme for Lencode {
         // Fallback: write through trait
         if (val >> 7) == I::ZERO {
             let byte = val.le_bytes()[0];
-            writer.write(core::slice::from_ref(&byte))?;
+            write_all(writer, core::slice::from_ref(&byte))?;
             return Ok(1);
         }
 
@@ -572,11 +728,12 @@ impl VarintEncodingScheme for Lencode {
             unsafe {
                 core::ptr::copy_nonoverlapping(bytes.as_ptr(), out.as_mut_ptr().add(1), n);
             }
-            writer.write(&out[..(1 + n)])?;
+            write_whole(writer, &out[..(1 + n)])?;
             Ok(1 + n)
         } else {
-            writer.write(core::slice::from_ref(&first_byte))?;
-            writer.write(&bytes[..n])?;
+            ensure_capacity(writer, 1 + n)?;
+            write_all(writer, core::slice::from_ref(&first_byte))?;
+            write_all(writer, &bytes[..n])?;
             Ok(1 + n)
         }
     }
@@ -603,6 +760,9 @@ impl VarintEncodingScheme for Lencode {
                 }
             }
             let n = (first & 0x7F) as usize;
+            if n > core::mem::size_of::<I>() {
+                return Err(Error::InvalidData);
+            }
             if 1 + n > slice.len() {
                 return Err(Error::ReaderOutOfData);
             }
@@ -646,6 +806,9 @@ impl VarintEncodingScheme for Lencode {
                 return Ok(val);
             }
             let n = (first & 0x7F) as usize;
+            if n > core::mem::size_of::<I>() {
+                return Err(Error::InvalidData);
+            }
             reader.read(&mut val_bytes[..n])?;
             Ok(val)
         }
@@ -658,6 +821,9 @@ impl VarintEncodingScheme for Lencode {
                 return Ok(from_le_bytes::<I>(&[first]));
             }
             let n = (first & 0x7F) as usize;
+            if n > core::mem::size_of::<I>() {
+                return Err(Error::InvalidData);
+            }
             let mut buf = [0u8; 32];
             reader.read(&mut buf[..n])?;
             return Ok(from_le_bytes::<I>(&buf[..n]));
@@ -675,7 +841,7 @@ impl VarintEncodingScheme for Lencode {
             writer.advance_mut(1);
             return Ok(1);
         }
-        writer.write(core::slice::from_ref(&byte))
+        write_all(writer, core::slice::from_ref(&byte))
     }
 
     #[inline(always)]
@@ -716,11 +882,13 @@ impl Encode for u8 {
             writer.advance_mut(1);
             return Ok(1);
         }
-        writer.write(core::slice::from_ref(self))
+        write_all(writer, core::slice::from_ref(self))
     }
 }
 
 impl Decode for u8 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(
         reader: &mut impl Read,
@@ -756,11 +924,13 @@ impl Encode for i8 {
             writer.advance_mut(1);
             return Ok(1);
         }
-        writer.write(&[*self as u8])
+        write_all(writer, &[*self as u8])
     }
 }
 
 impl Decode for i8 {
+    const FLAT_DECODE_VEC: bool = true;
+
     #[inline(always)]
     fn decode_ext(
         reader: &mut impl Read,
@@ -963,3 +1133,88 @@ fn test_encode_decode_u256() {
         assert_eq!(decoded, val, "Failed for iteration {}", i);
     }
 }
+
+#[test]
+fn test_decode_varint_rejects_oversized_length_byte() {
+    use crate::u256::U256;
+    // A header claiming more payload bytes than the type holds is malformed, whether the
+    // reader has enough input left for the fast path or not.
+    for header in [0x83u8, 0x85, 0x89, 0x91, 0xFF] {
+        let mut buf = [0u8; 32];
+        buf[0] = header;
+        for input in [&buf[..], &buf[..2]] {
+            let n = (header & 0x7F) as usize;
+            if n > 2 {
+                assert!(Lencode::decode_varint_u16(&mut Cursor::new(input)).is_err());
+            }
+            if n > 4 {
+                assert!(Lencode::decode_varint_u32(&mut Cursor::new(input)).is_err());
+            }
+            if n > 8 {
+                assert!(Lencode::decode_varint_u64(&mut Cursor::new(input)).is_err());
+            }
+            if n > 16 {
+                assert!(Lencode::decode_varint_u128(&mut Cursor::new(input)).is_err());
+            }
+            if n > 32 {
+                assert!(Lencode::decode_varint::<U256>(&mut Cursor::new(input)).is_err());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_decode_varint_bounded() {
+    let mut buf = Vec::new();
+    Lencode::encode_varint(70_000u64, &mut buf).unwrap();
+    Lencode::encode_varint(90u64, &mut buf).unwrap();
+
+    let mut cursor = Cursor::new(&buf[..]);
+    assert_eq!(
+        Lencode::peek_varint::<u64>(&cursor).unwrap(),
+        Some((70_000, 4))
+    );
+    assert_eq!(cursor.position(), 0);
+    // Two payload bytes cannot hold 70_000: rejected from the header, nothing consumed.
+    assert!(matches!(
+        Lencode::decode_varint_bounded(&mut cursor, 1000u64),
+        Err(Error::OutOfRange {
+            value: 65_536,
+            max: 1000
+        })
+    ));
+    assert!(matches!(
+        Lencode::decode_varint_bounded(&mut cursor, 69_999u64),
+        Err(Error::OutOfRange {
+            value: 70_000,
+            max: 69_999
+        })
+    ));
+
+    #[cfg(feature = "std")]
+    {
+        // Readers without a borrowed buffer take the streaming path.
+        let mut reader = std::io::Cursor::new(buf.clone());
+        assert_eq!(Lencode::peek_varint::<u64>(&reader).unwrap(), None);
+        assert_eq!(
+            Lencode::decode_varint_bounded(&mut reader, u32::MAX as u64).unwrap(),
+            70_000
+        );
+        assert_eq!(
+            Lencode::decode_varint_bounded(&mut reader, 90u64).unwrap(),
+            90
+        );
+        let mut reader = std::io::Cursor::new(buf.clone());
+        assert!(matches!(
+            Lencode::decode_varint_bounded(&mut reader, 255u64),
+            Err(Error::OutOfRange { .. })
+        ));
+    }
+
+    let mut cursor = Cursor::new(&buf[4..]);
+    assert_eq!(
+        Lencode::decode_varint_bounded(&mut cursor, 90u8).unwrap(),
+        90
+    );
+    assert!(Lencode::decode_varint_bounded(&mut Cursor::new(&buf[4..]), 89u8).is_err());
+}
@@ -0,0 +1,341 @@
+//! Lencode request and response bodies for HTTP services (`web` and `actix` features).
+//!
+//! [`Lencode<T>`] plays the role `Json<T>` plays in axum and actix-web: as an extractor it
+//! checks that the request's `Content-Type` is [`CONTENT_TYPE`] and decodes the body into a
+//! `T`; as a response it encodes the value and sets that content type. Switching an internal
+//! endpoint from JSON to lencode is a matter of swapping the wrapper on both sides.
+//!
+//! The `web` feature implements axum's `FromRequest`/`IntoResponse` (through `axum-core`);
+//! the `actix` feature implements actix-web's `FromRequest`/`Responder`. Either enables this
+//! module.
+//!
+//! A request is rejected with a [`LencodeRejection`]: `415 Unsupported Media Type` for a
+//! missing or different content type, `400 Bad Request` if the body is not exactly one
+//! encoded `T`, and the framework's own status if the body could not be read (for example
+//! when it exceeds the configured size limit). A value that fails to encode is answered with
+//! `500 Internal Server Error`.
+//!
+//! ```ignore
+//! use axum::{Router, routing::post};
+//! use lencode::web::Lencode;
+//!
+//! async fn submit(Lencode(order): Lencode<Order>) -> Lencode<Receipt> {
+//!     Lencode(place(order))
+//! }
+//!
+//! let app: Router = Router::new().route("/orders", post(submit));
+//! ```
+use core::fmt;
+
+use crate::prelude::*;
+
+/// The media type of a lencode body.
+pub const CONTENT_TYPE: &str = "application/x-lencode";
+
+/// A lencode‑encoded HTTP body. See the [module docs](self).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Lencode<T>(pub T);
+
+impl<T> Lencode<T> {
+    /// Returns the wrapped value.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Lencode<T> {
+    #[inline(always)]
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> core::ops::Deref for Lencode<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> core::ops::DerefMut for Lencode<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Why a request body was not extracted as a [`Lencode<T>`].
+#[derive(Debug)]
+pub enum LencodeRejection {
+    /// The request's `Content-Type` is missing or is not [`CONTENT_TYPE`].
+    UnsupportedContentType,
+    /// The framework could not read the body.
+    Body {
+        /// The status the framework chose for the failure.
+        status: u16,
+        /// The framework's description of the failure.
+        message: String,
+    },
+    /// The body is not exactly one encoded value.
+    Decode(Error),
+}
+
+impl LencodeRejection {
+    /// Returns the HTTP status code the rejection is answered with.
+    #[inline]
+    pub const fn status(&self) -> u16 {
+        match self {
+            Self::UnsupportedContentType => 415,
+            Self::Body { status, .. } => *status,
+            Self::Decode(_) => 400,
+        }
+    }
+}
+
+impl fmt::Display for LencodeRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedContentType => {
+                write!(f, "Expected request with `Content-Type: {CONTENT_TYPE}`")
+            }
+            Self::Body { message, .. } => f.write_str(message),
+            Self::Decode(err) => write!(f, "Failed to decode the request body: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for LencodeRejection {}
+
+/// Returns whether a `Content-Type` header value names [`CONTENT_TYPE`], ignoring parameters
+/// and case.
+fn is_lencode(content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|value| {
+        value
+            .split(';')
+            .next()
+            .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(CONTENT_TYPE))
+    })
+}
+
+/// Decodes a whole request body, rejecting bytes left over after the value.
+fn decode_body<T: Decode>(body: &[u8]) -> core::result::Result<T, LencodeRejection> {
+    let mut cursor = Cursor::new(body);
+    let value = T::decode(&mut cursor).map_err(LencodeRejection::Decode)?;
+    if cursor.position() != body.len() {
+        return Err(LencodeRejection::Decode(Error::LengthMismatch {
+            expected: body.len(),
+            actual: cursor.position(),
+        }));
+    }
+    Ok(value)
+}
+
+/// Encodes a response body.
+fn encode_body<T: Encode>(value: &T) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    value.encode(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(feature = "web")]
+mod axum {
+    use ::axum_core::extract::{FromRequest, Request};
+    use ::axum_core::response::{IntoResponse, Response};
+    use ::http::{StatusCode, header};
+
+    use super::*;
+
+    impl<T: Decode + Send, S: Send + Sync> FromRequest<S> for Lencode<T> {
+        type Rejection = LencodeRejection;
+
+        async fn from_request(
+            req: Request,
+            state: &S,
+        ) -> core::result::Result<Self, Self::Rejection> {
+            let content_type = req.headers().get(header::CONTENT_TYPE);
+            if !is_lencode(content_type.and_then(|value| value.to_str().ok())) {
+                return Err(LencodeRejection::UnsupportedContentType);
+            }
+            let body = ::bytes::Bytes::from_request(req, state)
+                .await
+                .map_err(|rejection| LencodeRejection::Body {
+                    status: rejection.status().as_u16(),
+                    message: rejection.body_text(),
+                })?;
+            decode_body(&body).map(Lencode)
+        }
+    }
+
+    impl IntoResponse for LencodeRejection {
+        fn into_response(self) -> Response {
+            let status = StatusCode::from_u16(self.status()).unwrap_or(StatusCode::BAD_REQUEST);
+            (status, self.to_string()).into_response()
+        }
+    }
+
+    impl<T: Encode> IntoResponse for Lencode<T> {
+        fn into_response(self) -> Response {
+            match encode_body(&self.0) {
+                Ok(body) => ([(header::CONTENT_TYPE, CONTENT_TYPE)], body).into_response(),
+                Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix {
+    use core::future::Future;
+    use core::pin::Pin;
+
+    use ::actix_web::body::BoxBody;
+    use ::actix_web::http::{StatusCode, header};
+    use ::actix_web::web::Bytes;
+    use ::actix_web::{
+        FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder, ResponseError, dev,
+    };
+
+    use super::*;
+
+    impl ResponseError for LencodeRejection {
+        fn status_code(&self) -> StatusCode {
+            StatusCode::from_u16(self.status()).unwrap_or(StatusCode::BAD_REQUEST)
+        }
+    }
+
+    impl<T: Decode + 'static> FromRequest for Lencode<T> {
+        type Error = ::actix_web::Error;
+        type Future = Pin<Box<dyn Future<Output = core::result::Result<Self, Self::Error>>>>;
+
+        fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+            let accepted = is_lencode(Some(req.content_type()));
+            let body = Bytes::from_request(req, payload);
+            Box::pin(async move {
+                if !accepted {
+                    return Err(LencodeRejection::UnsupportedContentType.into());
+                }
+                let body = body.await.map_err(|err| LencodeRejection::Body {
+                    status: err.as_response_error().status_code().as_u16(),
+                    message: err.to_string(),
+                })?;
+                Ok(decode_body(&body).map(Lencode)?)
+            })
+        }
+    }
+
+    impl<T: Encode> Responder for Lencode<T> {
+        type Body = BoxBody;
+
+        fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+            match encode_body(&self.0) {
+                Ok(body) => HttpResponse::Ok()
+                    .insert_header((header::CONTENT_TYPE, CONTENT_TYPE))
+                    .body(body),
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        }
+    }
+}
+
+/// Polls a future that completes without waiting on I/O.
+#[cfg(test)]
+fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+    loop {
+        if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn test_content_type_matching() {
+    assert!(is_lencode(Some("application/x-lencode")));
+    assert!(is_lencode(Some("Application/X-Lencode; version=1")));
+    assert!(!is_lencode(Some("application/json")));
+    assert!(!is_lencode(None));
+    assert!(matches!(
+        decode_body::<u8>(&[1, 2]),
+        Err(LencodeRejection::Decode(Error::LengthMismatch {
+            expected: 2,
+            actual: 1
+        }))
+    ));
+}
+
+#[cfg(feature = "web")]
+#[test]
+fn test_axum_extracts_and_responds() {
+    use ::axum_core::body::Body;
+    use ::axum_core::extract::FromRequest;
+    use ::axum_core::response::IntoResponse;
+
+    let order = (42u64, String::from("SOL-PERP"));
+    let request = |content_type: &str, body: Vec<u8>| {
+        ::http::Request::builder()
+            .header(::http::header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let body = encode_body(&order).unwrap();
+    let Lencode(decoded) = block_on(Lencode::<(u64, String)>::from_request(
+        request(CONTENT_TYPE, body.clone()),
+        &(),
+    ))
+    .unwrap();
+    assert_eq!(decoded, order);
+
+    let rejection = block_on(Lencode::<(u64, String)>::from_request(
+        request("application/json", body.clone()),
+        &(),
+    ))
+    .unwrap_err();
+    assert_eq!(rejection.into_response().status(), 415);
+    let rejection = block_on(Lencode::<(u64, String)>::from_request(
+        request(CONTENT_TYPE, body[..3].to_vec()),
+        &(),
+    ))
+    .unwrap_err();
+    assert_eq!(rejection.into_response().status(), 400);
+
+    let response = Lencode(order).into_response();
+    assert_eq!(
+        response.headers()[::http::header::CONTENT_TYPE],
+        CONTENT_TYPE
+    );
+}
+
+#[cfg(feature = "actix")]
+#[test]
+fn test_actix_extracts_and_responds() {
+    use ::actix_web::test::TestRequest;
+    use ::actix_web::{FromRequest, Responder, ResponseError};
+
+    let order = (42u64, String::from("SOL-PERP"));
+    let body = encode_body(&order).unwrap();
+    let (req, mut payload) = TestRequest::default()
+        .insert_header(("content-type", CONTENT_TYPE))
+        .set_payload(body.clone())
+        .to_http_parts();
+    let Lencode(decoded) =
+        block_on(Lencode::<(u64, String)>::from_request(&req, &mut payload)).unwrap();
+    assert_eq!(decoded, order);
+
+    let (req, mut payload) = TestRequest::default()
+        .insert_header(("content-type", "application/json"))
+        .set_payload(body)
+        .to_http_parts();
+    let err = block_on(Lencode::<(u64, String)>::from_request(&req, &mut payload)).unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), 415);
+    assert_eq!(LencodeRejection::UnsupportedContentType.status_code(), 415);
+
+    let response = Lencode(order).respond_to(&req);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        CONTENT_TYPE
+    );
+}
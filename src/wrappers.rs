@@ -0,0 +1,1299 @@
+//! Wrapper types that select an alternative wire layout for a collection.
+//!
+//! The default [`Encode`]/[`Decode`] impls pick a general‑purpose layout for each type.
+//! The wrappers in this module trade that generality for a layout that is denser (or more
+//! predictable) for a particular data shape, while still dereferencing to the underlying
+//! collection.
+//!
+//! - [`OptVec<T>`]: a `Vec<Option<T>>` encoded as a presence bitmap followed by only the
+//!   present values, instead of one flag byte per element.
+//! - [`Rle<T>`]: a `Vec<T>` encoded as `(value, run_length)` pairs, falling back to the plain
+//!   element layout whenever that would be smaller.
+//! - [`PlainVec<T>`]: a `Vec<T>` that always uses the `varint(count) + elements` layout, even
+//!   for `T = u8` where `Vec<T>` switches to the compressed byte‑string layout.
+//! - [`RawArray<N>`]: exactly `N` raw bytes on the wire in every context, for signatures,
+//!   hashes and keys whose encoded size must be known in advance.
+//! - [`ByteString`]: text that may not be valid UTF‑8, in the `String` layout (so it can be
+//!   compressed) but decoded without validation.
+//! - [`Cached<T>`]: a value that keeps its last encoding and writes it again until the value
+//!   is mutated, for large, rarely changing parts of messages that are sent often.
+//! - [`StringEnum<E>`]: a fieldless enum encoded by variant name instead of index, so stored
+//!   data survives reordering; repeated names shrink to IDs through the dedupe table.
+use core::cell::OnceCell;
+use core::hash::Hash;
+use core::ops::{Deref, DerefMut};
+
+use crate::io::read_vec;
+use crate::prelude::*;
+
+/// A [`Write`] sink that discards its input and counts the bytes written.
+///
+/// Used to size alternative layouts before committing to one.
+#[derive(Default)]
+pub(crate) struct ByteCounter(pub(crate) usize);
+
+impl Write for ByteCounter {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A `Vec<Option<T>>` encoded with a leading presence bitmap.
+///
+/// Wire format: `varint(len)`, then `ceil(len / 8)` bitmap bytes (bit `i % 8` of byte
+/// `i / 8` is set when element `i` is `Some`), then the present values in order. Sparse
+/// vectors pay one bit per element instead of one byte.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct OptVec<T>(pub Vec<Option<T>>);
+
+impl<T> OptVec<T> {
+    /// Creates a new empty [`OptVec`].
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Consumes the wrapper and returns the inner `Vec<Option<T>>`.
+    #[inline(always)]
+    pub fn into_inner(self) -> Vec<Option<T>> {
+        self.0
+    }
+}
+
+impl<T> Deref for OptVec<T> {
+    type Target = Vec<Option<T>>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for OptVec<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<Vec<Option<T>>> for OptVec<T> {
+    #[inline(always)]
+    fn from(value: Vec<Option<T>>) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> From<OptVec<T>> for Vec<Option<T>> {
+    #[inline(always)]
+    fn from(value: OptVec<T>) -> Self {
+        value.0
+    }
+}
+
+impl<T> FromIterator<Option<T>> for OptVec<T> {
+    #[inline(always)]
+    fn from_iter<I: IntoIterator<Item = Option<T>>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<T: Encode> Encode for OptVec<T> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = 0;
+        total_written += Self::encode_len(self.0.len(), writer)?;
+        let mut bitmap = vec![0u8; self.0.len().div_ceil(8)];
+        for (i, item) in self.0.iter().enumerate() {
+            if item.is_some() {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+        }
+        total_written += write_all(writer, &bitmap)?;
+        for value in self.0.iter().flatten() {
+            total_written += value.encode_ext(writer, ctx.as_deref_mut())?;
+        }
+        Ok(total_written)
+    }
+}
+
+impl<T: Decode> Decode for OptVec<T> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            let bitmap = read_vec(reader, len.div_ceil(8))?;
+            // Padding bits past `len` must be clear so every value has a single encoding.
+            if len % 8 != 0 && bitmap[len / 8] >> (len % 8) != 0 {
+                return Err(Error::InvalidData);
+            }
+            let mut out = Vec::with_capacity(prealloc_capacity::<Option<T>>(len));
+            for i in 0..len {
+                if bitmap[i / 8] & (1 << (i % 8)) != 0 {
+                    out.push(Some(T::decode_ext(reader, ctx.as_deref_mut())?));
+                } else {
+                    out.push(None);
+                }
+            }
+            Ok(Self(out))
+        })
+    }
+}
+
+/// Largest decoded size, in bytes of `T`, an [`Rle`] may expand to. A run length is a
+/// single varint, so without a cap a few input bytes could demand any allocation; longer
+/// inputs fail with [`Error::IncorrectLength`].
+pub const MAX_RLE_DECODED_BYTES: usize = 64 << 20;
+
+/// A `Vec<T>` encoded as runs of identical values.
+///
+/// Wire format: `varint((n << 1) | flag)`. With `flag = 1`, `n` `(value, varint(run_len))`
+/// pairs follow; with `flag = 0`, `n` plain elements follow. The encoder measures both
+/// layouts and emits whichever is smaller, so long runs of repeated values (e.g. account
+/// flag vectors) shrink dramatically while run‑free data costs nothing extra.
+///
+/// Runs are formed from elements whose encodings are identical rather than from `==`, so
+/// values that compare equal but encode differently (`0.0` and `-0.0`) survive a round trip.
+/// Decoding expands to at most [`MAX_RLE_DECODED_BYTES`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rle<T>(pub Vec<T>);
+
+impl<T> Rle<T> {
+    /// Creates a new empty [`Rle`].
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Consumes the wrapper and returns the inner `Vec<T>`.
+    #[inline(always)]
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T> Deref for Rle<T> {
+    type Target = Vec<T>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Rle<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<Vec<T>> for Rle<T> {
+    #[inline(always)]
+    fn from(value: Vec<T>) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> From<Rle<T>> for Vec<T> {
+    #[inline(always)]
+    fn from(value: Rle<T>) -> Self {
+        value.0
+    }
+}
+
+impl<T> FromIterator<T> for Rle<T> {
+    #[inline(always)]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<T: Encode> Encode for Rle<T> {
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        // Size both layouts from the runs alone: each run value is encoded once.
+        let runs = encoded_runs(&self.0)?;
+        let mut rle = ByteCounter::default();
+        let mut plain = 0usize;
+        for &(_, run_len, value_len) in &runs {
+            rle.0 += value_len;
+            Self::encode_len(run_len, &mut rle)?;
+            plain += value_len * run_len;
+        }
+        Self::encode_len(runs.len() << 1 | 1, &mut rle)?;
+        let mut plain_header = ByteCounter::default();
+        Self::encode_len(self.0.len() << 1, &mut plain_header)?;
+        plain += plain_header.0;
+
+        let mut total_written = 0;
+        if rle.0 < plain {
+            total_written += Self::encode_len(runs.len() << 1 | 1, writer)?;
+            for &(start, run_len, _) in &runs {
+                total_written += self.0[start].encode_ext(writer, ctx.as_deref_mut())?;
+                total_written += Self::encode_len(run_len, writer)?;
+            }
+        } else {
+            total_written += Self::encode_len(self.0.len() << 1, writer)?;
+            for item in &self.0 {
+                total_written += item.encode_ext(writer, ctx.as_deref_mut())?;
+            }
+        }
+        Ok(total_written)
+    }
+}
+
+/// Splits `items` into runs of consecutive elements with identical encodings, as
+/// `(start, run_len, encoded_len)`.
+fn encoded_runs<T: Encode>(items: &[T]) -> Result<Vec<(usize, usize, usize)>> {
+    let mut runs: Vec<(usize, usize, usize)> = Vec::new();
+    let mut prev = Vec::new();
+    let mut cur = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        cur.clear();
+        item.encode_ext(&mut cur, None)?;
+        match runs.last_mut() {
+            Some((_, run_len, _)) if cur == prev => *run_len += 1,
+            _ => runs.push((i, 1, cur.len())),
+        }
+        core::mem::swap(&mut prev, &mut cur);
+    }
+    Ok(runs)
+}
+
+impl<T: Decode + Clone> Decode for Rle<T> {
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let flagged = Self::decode_len(reader)?;
+            let count = flagged >> 1;
+            let mut out = Vec::new();
+            if flagged & 1 == 1 {
+                for _ in 0..count {
+                    let value = T::decode_ext(reader, ctx.as_deref_mut())?;
+                    let run_len = Self::decode_len(reader)?;
+                    if run_len == 0 {
+                        return Err(Error::InvalidData);
+                    }
+                    let len = out
+                        .len()
+                        .checked_add(run_len)
+                        .filter(|&len| {
+                            len.saturating_mul(core::mem::size_of::<T>().max(1))
+                                <= MAX_RLE_DECODED_BYTES
+                        })
+                        .ok_or(Error::IncorrectLength)?;
+                    out.resize(len, value);
+                }
+            } else {
+                for _ in 0..count {
+                    out.push(T::decode_ext(reader, ctx.as_deref_mut())?);
+                }
+            }
+            Ok(Self(out))
+        })
+    }
+}
+
+/// A `Vec<T>` with a layout that does not depend on `T`.
+///
+/// `Vec<u8>` is encoded as a (possibly zstd‑compressed) byte string rather than a
+/// length‑prefixed element sequence, so generic code over `Vec<T>` sees a different layout
+/// when `T = u8`. `PlainVec<T>` always writes `varint(count)` followed by each element's
+/// own encoding, for every `T`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PlainVec<T>(pub Vec<T>);
+
+impl<T> PlainVec<T> {
+    /// Creates a new empty [`PlainVec`].
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Consumes the wrapper and returns the inner `Vec<T>`.
+    #[inline(always)]
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T> Deref for PlainVec<T> {
+    type Target = Vec<T>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for PlainVec<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<Vec<T>> for PlainVec<T> {
+    #[inline(always)]
+    fn from(value: Vec<T>) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> From<PlainVec<T>> for Vec<T> {
+    #[inline(always)]
+    fn from(value: PlainVec<T>) -> Self {
+        value.0
+    }
+}
+
+impl<T> FromIterator<T> for PlainVec<T> {
+    #[inline(always)]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<T: Encode> Encode for PlainVec<T> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        mut ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let mut total_written = 0;
+        total_written += Self::encode_len(self.0.len(), writer)?;
+        if ctx.is_none() {
+            total_written += T::encode_slice(&self.0, writer)?;
+            return Ok(total_written);
+        }
+        for item in &self.0 {
+            total_written += item.encode_ext(writer, ctx.as_deref_mut())?;
+        }
+        Ok(total_written)
+    }
+}
+
+impl<T: Decode> Decode for PlainVec<T> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        DecoderContext::nested(ctx, |mut ctx| {
+            let len = Self::decode_len(reader)?;
+            if T::FLAT_DECODE_VEC && ctx.as_deref().is_none_or(DecoderContext::is_plain) {
+                return T::decode_vec(reader, len).map(Self);
+            }
+            let mut out = Vec::with_capacity(prealloc_capacity::<T>(len));
+            for _ in 0..len {
+                out.push(T::decode_ext(reader, ctx.as_deref_mut())?);
+            }
+            Ok(Self(out))
+        })
+    }
+}
+
+/// `N` bytes that are always written as exactly `N` raw bytes.
+///
+/// A `[u8; N]` field is normally raw too, but an encoder context can change that: with a
+/// [`DiffEncoder`] key set it is written as a delta against the previous value. Signatures,
+/// hashes and public keys gain nothing from that and are often embedded in structs whose
+/// encoded size has to be predictable (fixed‑size records, length checks on the receiving
+/// side), so `RawArray<N>` ignores the context and never varies in size.
+///
+/// ```
+/// use lencode::prelude::*;
+///
+/// #[derive(Encode, Decode, PartialEq, Debug)]
+/// struct Vote {
+///     slot: u64,
+///     signature: RawArray<64>,
+/// }
+///
+/// let vote = Vote { slot: 7, signature: RawArray([0xAB; 64]) };
+/// let mut buf = Vec::new();
+/// assert_eq!(encode(&vote, &mut buf).unwrap(), 1 + 64);
+/// assert_eq!(decode::<Vote>(&mut Cursor::new(&buf)).unwrap(), vote);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct RawArray<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> RawArray<N> {
+    /// The encoded size of every `RawArray<N>`.
+    pub const SIZE: usize = N;
+
+    /// Consumes the wrapper and returns the bytes.
+    #[inline(always)]
+    pub const fn into_inner(self) -> [u8; N] {
+        self.0
+    }
+
+    /// Returns the bytes.
+    #[inline(always)]
+    pub const fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> Default for RawArray<N> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self([0; N])
+    }
+}
+
+impl<const N: usize> Deref for RawArray<N> {
+    type Target = [u8; N];
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const N: usize> DerefMut for RawArray<N> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for RawArray<N> {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for RawArray<N> {
+    #[inline(always)]
+    fn from(value: [u8; N]) -> Self {
+        Self(value)
+    }
+}
+
+impl<const N: usize> From<RawArray<N>> for [u8; N] {
+    #[inline(always)]
+    fn from(value: RawArray<N>) -> Self {
+        value.0
+    }
+}
+
+impl<const N: usize> TryFrom<&[u8]> for RawArray<N> {
+    type Error = Error;
+
+    /// Fails with [`Error::IncorrectLength`] unless `value` is exactly `N` bytes long.
+    #[inline(always)]
+    fn try_from(value: &[u8]) -> Result<Self> {
+        value
+            .try_into()
+            .map(Self)
+            .map_err(|_| Error::IncorrectLength)
+    }
+}
+
+/// Views a slice of wrappers as the byte arrays they hold.
+#[inline(always)]
+const fn as_arrays<const N: usize>(items: &[RawArray<N>]) -> &[[u8; N]] {
+    // SAFETY: `RawArray<N>` is `#[repr(transparent)]` over `[u8; N]`.
+    unsafe { core::slice::from_raw_parts(items.as_ptr() as *const [u8; N], items.len()) }
+}
+
+impl<const N: usize> Pack for RawArray<N> {
+    #[inline(always)]
+    fn pack(&self, writer: &mut impl Write) -> Result<usize> {
+        self.0.pack(writer)
+    }
+
+    #[inline(always)]
+    fn unpack(reader: &mut impl Read) -> Result<Self> {
+        <[u8; N]>::unpack(reader).map(Self)
+    }
+
+    #[inline(always)]
+    fn pack_slice(items: &[Self], writer: &mut impl Write) -> Result<usize> {
+        <[u8; N]>::pack_slice(as_arrays(items), writer)
+    }
+
+    #[inline(always)]
+    fn unpack_vec(reader: &mut impl Read, count: usize) -> Result<Vec<Self>> {
+        Ok(<[u8; N]>::unpack_vec(reader, count)?
+            .into_iter()
+            .map(Self)
+            .collect())
+    }
+}
+
+impl<const N: usize> Encode for RawArray<N> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        _ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        self.pack(writer)
+    }
+
+    #[inline(always)]
+    fn encode_slice(items: &[Self], writer: &mut impl Write) -> Result<usize> {
+        Self::pack_slice(items, writer)
+    }
+}
+
+impl<const N: usize> Decode for RawArray<N> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Self::unpack(reader)
+    }
+
+    const FLAT_DECODE_VEC: bool = true;
+
+    #[inline(always)]
+    fn decode_vec(reader: &mut impl Read, count: usize) -> Result<Vec<Self>> {
+        Self::unpack_vec(reader, count)
+    }
+}
+
+/// Fieldless enums with a stable name per variant, for [`StringEnum`].
+///
+/// Implement with `#[derive(NamedVariants)]`, which uses each variant's identifier or its
+/// `#[lencode(rename = "...")]`.
+pub trait NamedVariants: Sized {
+    /// Returns the name of this variant.
+    fn variant_name(&self) -> &'static str;
+
+    /// Returns the variant called `name`, if any.
+    fn from_variant_name(name: &str) -> Option<Self>;
+}
+
+/// Bytes that are usually, but not always, UTF‑8 text: program logs, memo fields, captured
+/// process output.
+///
+/// Written in the same flagged, possibly zstd‑compressed layout as `String` and `Vec<u8>`,
+/// so a `String` field can become a `ByteString` without changing the wire format. Decoding
+/// never fails on invalid UTF‑8. [`Display`](core::fmt::Display) replaces invalid sequences
+/// with U+FFFD, and [`Debug`](core::fmt::Debug) shows an escaped byte string.
+///
+/// ```
+/// use lencode::prelude::*;
+///
+/// let mut buf = Vec::new();
+/// encode(&"Program log: ok", &mut buf).unwrap();
+/// encode(&ByteString::from(&b"memo \xf0\x28"[..]), &mut buf).unwrap();
+///
+/// let mut reader = Cursor::new(&buf);
+/// let log: ByteString = decode(&mut reader).unwrap();
+/// let memo: ByteString = decode(&mut reader).unwrap();
+/// assert_eq!(log.to_str(), Ok("Program log: ok"));
+/// assert!(memo.to_str().is_err());
+/// assert_eq!(memo.to_string(), "memo \u{fffd}(");
+/// assert_eq!(format!("{memo:?}"), r#"b"memo \xf0(""#);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ByteString(pub Vec<u8>);
+
+impl ByteString {
+    /// Creates an empty byte string.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Returns the bytes.
+    #[inline(always)]
+    pub const fn as_bytes(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    /// Returns the bytes as `&str` if they are valid UTF‑8.
+    #[inline(always)]
+    pub const fn to_str(&self) -> core::result::Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.0.as_slice())
+    }
+
+    /// Consumes the wrapper and returns the bytes.
+    #[inline(always)]
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for ByteString {
+    type Target = Vec<u8>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ByteString {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl AsRef<[u8]> for ByteString {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for ByteString {
+    #[inline(always)]
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for ByteString {
+    #[inline(always)]
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl From<String> for ByteString {
+    #[inline(always)]
+    fn from(text: String) -> Self {
+        Self(text.into_bytes())
+    }
+}
+
+impl From<&str> for ByteString {
+    #[inline(always)]
+    fn from(text: &str) -> Self {
+        Self(text.as_bytes().to_vec())
+    }
+}
+
+impl From<ByteString> for Vec<u8> {
+    #[inline(always)]
+    fn from(bytes: ByteString) -> Self {
+        bytes.0
+    }
+}
+
+impl core::fmt::Display for ByteString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for chunk in self.0.utf8_chunks() {
+            f.write_str(chunk.valid())?;
+            if !chunk.invalid().is_empty() {
+                f.write_str("\u{fffd}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for ByteString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "b\"{}\"", self.0.escape_ascii())
+    }
+}
+
+impl Encode for ByteString {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        crate::bytes::write_flagged_compressed(writer, self.as_bytes(), ctx.as_deref())
+    }
+}
+
+impl Decode for ByteString {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        Vec::<u8>::decode_ext(reader, None).map(Self)
+    }
+
+    #[inline(always)]
+    fn skip_ext(reader: &mut impl Read, _ctx: Option<&mut DecoderContext>) -> Result<()> {
+        String::skip_ext(reader, None)
+    }
+}
+
+/// A value that remembers its encoded bytes.
+///
+/// The first encode stores the bytes it produced, and later encodes copy them instead of
+/// encoding `T` again. Any mutable access (through [`DerefMut`] or
+/// [`get_mut`](Self::get_mut)) discards the stored bytes, so they are never stale. Use it
+/// as the field type for a large sub‑structure that is re‑sent far more often than it
+/// changes, such as a config or account set embedded in every message. The wire format is
+/// exactly `T`'s, so switching a field to `Cached<T>` is invisible to decoders.
+///
+/// The stored bytes are only used when encoding without context state that changes the
+/// output or observes it: unless [`EncoderContext::is_stateless`] holds (no
+/// [`DedupeEncoder`], diff encoder, codecs, metrics or hooks, and the default compression
+/// settings), `T` is encoded normally each time and nothing is stored. Because the cache is
+/// filled through `&self`, `Cached<T>` is not [`Sync`].
+///
+/// ```
+/// use lencode::prelude::*;
+///
+/// let mut validators = Cached::new(vec![[7u8; 32]; 1000]);
+/// let mut first = Vec::new();
+/// encode(&validators, &mut first).unwrap();
+/// assert!(validators.is_cached());
+///
+/// validators.push([8; 32]);
+/// assert!(!validators.is_cached());
+/// let mut second = Vec::new();
+/// encode(&validators, &mut second).unwrap();
+/// let mut plain = Vec::new();
+/// encode(&*validators, &mut plain).unwrap();
+/// assert_eq!(second, plain);
+/// ```
+#[derive(Default)]
+pub struct Cached<T> {
+    value: T,
+    encoded: OnceCell<Vec<u8>>,
+}
+
+impl<T> Cached<T> {
+    /// Wraps `value` with nothing cached yet.
+    #[inline(always)]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            encoded: OnceCell::new(),
+        }
+    }
+
+    /// Returns the value.
+    #[inline(always)]
+    pub const fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the value mutably, discarding the cached bytes.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.encoded.take();
+        &mut self.value
+    }
+
+    /// Returns `true` if the next context‑free encode will reuse stored bytes.
+    #[inline(always)]
+    pub fn is_cached(&self) -> bool {
+        self.encoded.get().is_some()
+    }
+
+    /// Discards the cached bytes, e.g. after changing the value through interior mutability.
+    #[inline(always)]
+    pub fn invalidate(&mut self) {
+        self.encoded.take();
+    }
+
+    /// Consumes the wrapper and returns the value.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Cached<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Cached<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+impl<T> From<T> for Cached<T> {
+    #[inline(always)]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Clone> Clone for Cached<T> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            encoded: self.encoded.clone(),
+        }
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for Cached<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for Cached<T> {
+    #[inline(always)]
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Cached<T> {}
+
+impl<T: Hash> Hash for Cached<T> {
+    #[inline(always)]
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
+    }
+}
+
+impl<T: Encode> Encode for Cached<T> {
+    #[inline]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        if !ctx.as_deref().is_none_or(EncoderContext::is_stateless) {
+            return self.value.encode_ext(writer, ctx);
+        }
+        if let Some(bytes) = self.encoded.get() {
+            return write_whole(writer, bytes);
+        }
+        let mut bytes = Vec::new();
+        self.value.encode_ext(&mut bytes, None)?;
+        let written = write_whole(writer, &bytes)?;
+        let _ = self.encoded.set(bytes);
+        Ok(written)
+    }
+}
+
+impl<T: Decode> Decode for Cached<T> {
+    #[inline(always)]
+    fn decode_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<Self> {
+        T::decode_ext(reader, ctx).map(Self::new)
+    }
+
+    #[inline(always)]
+    fn skip_ext(reader: &mut impl Read, ctx: Option<&mut DecoderContext>) -> Result<()> {
+        T::skip_ext(reader, ctx)
+    }
+}
+
+/// An enum encoded by variant name rather than by index.
+///
+/// Derived `Encode` writes enum variants by position, so inserting or reordering variants
+/// changes what old data decodes to. `StringEnum<E>` writes [`NamedVariants::variant_name`]
+/// with the `String` layout instead, which stays valid as long as the names do: a good fit
+/// for config files and long‑lived stored data, where robustness matters more than size.
+/// Decoding a name `E` does not know fails with [`Error::InvalidData`].
+///
+/// Like `Cow<'static, str>`, it goes through the dedupe table when the context has a
+/// [`DedupeEncoder`]/[`DedupeDecoder`], so each name is written in full once and repeats
+/// become a small ID.
+///
+/// ```
+/// use lencode::prelude::*;
+///
+/// #[derive(NamedVariants, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// enum Network {
+///     Mainnet,
+///     #[lencode(rename = "devnet")]
+///     Devnet,
+/// }
+///
+/// let mut buf = Vec::new();
+/// encode(&StringEnum(Network::Devnet), &mut buf).unwrap();
+/// assert_eq!(buf, b"\x0cdevnet");
+/// let decoded: StringEnum<Network> = decode(&mut Cursor::new(&buf)).unwrap();
+/// assert_eq!(*decoded, Network::Devnet);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StringEnum<E>(pub E);
+
+impl<E> StringEnum<E> {
+    /// Consumes the wrapper and returns the inner enum.
+    #[inline(always)]
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+impl<E> Deref for StringEnum<E> {
+    type Target = E;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for StringEnum<E> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> From<E> for StringEnum<E> {
+    #[inline(always)]
+    fn from(value: E) -> Self {
+        Self(value)
+    }
+}
+
+impl<E: NamedVariants> Pack for StringEnum<E> {
+    #[inline(always)]
+    fn pack(&self, writer: &mut impl Write) -> Result<usize> {
+        self.0.variant_name().encode_ext(writer, None)
+    }
+
+    #[inline(always)]
+    fn unpack(reader: &mut impl Read) -> Result<Self> {
+        let name = String::decode_ext(reader, None)?;
+        E::from_variant_name(&name)
+            .map(Self)
+            .ok_or(Error::InvalidData)
+    }
+}
+
+/// Written through the dedupe table when the context has one, like a [`DedupeEncodeable`]
+/// type, and otherwise as a string under the context's [`CompressionPolicy`].
+impl<E: NamedVariants + Clone + Hash + Eq + Send + Sync + 'static> Encode for StringEnum<E> {
+    #[inline(always)]
+    fn encode_ext(
+        &self,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        if let Some(ctx) = ctx {
+            if let Some(encoder) = ctx.dedupe.as_mut() {
+                return encoder.encode(self, writer);
+            }
+            return self.0.variant_name().encode_ext(writer, Some(ctx));
+        }
+        self.pack(writer)
+    }
+}
+
+impl<E: NamedVariants + Clone + Hash + Eq + Send + Sync + 'static> DedupeDecodeable
+    for StringEnum<E>
+{
+}
+
+#[test]
+fn test_opt_vec_roundtrip() {
+    let values: OptVec<u32> = [
+        Some(1),
+        None,
+        None,
+        Some(300),
+        None,
+        None,
+        None,
+        None,
+        Some(7),
+    ]
+    .into_iter()
+    .collect();
+    let mut buf = Vec::new();
+    let n = values.encode(&mut buf).unwrap();
+    // len + 2 bitmap bytes + 1 + 3 + 1 value bytes
+    assert_eq!(n, 1 + 2 + 5);
+    assert_eq!(buf.len(), n);
+    let decoded: OptVec<u32> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, values);
+}
+
+#[test]
+fn test_opt_vec_smaller_than_vec_option() {
+    let values: Vec<Option<u8>> = (0..64).map(|i| (i % 16 == 0).then_some(i)).collect();
+    let mut plain = Vec::new();
+    values.encode(&mut plain).unwrap();
+    let mut packed = Vec::new();
+    OptVec(values.clone()).encode(&mut packed).unwrap();
+    assert!(packed.len() < plain.len());
+    let decoded: OptVec<u8> = Decode::decode(&mut Cursor::new(&packed)).unwrap();
+    assert_eq!(decoded.into_inner(), values);
+}
+
+#[test]
+fn test_opt_vec_empty() {
+    let values = OptVec::<u64>::new();
+    let mut buf = Vec::new();
+    assert_eq!(values.encode(&mut buf).unwrap(), 1);
+    let decoded: OptVec<u64> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert!(decoded.is_empty());
+}
+
+#[test]
+fn test_opt_vec_rejects_padding_bits() {
+    // len = 3, bitmap has bit 5 set
+    let buf = [3u8, 0b0010_0000];
+    let res: Result<OptVec<u8>> = Decode::decode(&mut Cursor::new(&buf[..]));
+    assert!(matches!(res, Err(Error::InvalidData)));
+}
+
+#[test]
+fn test_rle_long_runs() {
+    let mut flags = vec![true; 200];
+    flags.extend(vec![false; 300]);
+    flags.push(true);
+    let values = Rle(flags);
+    let mut buf = Vec::new();
+    let n = values.encode(&mut buf).unwrap();
+    // header + 3 * (bool + run varint)
+    assert_eq!(n, 1 + (1 + 2) + (1 + 3) + (1 + 1));
+    let decoded: Rle<bool> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, values);
+}
+
+#[test]
+fn test_rle_falls_back_to_plain() {
+    let values: Rle<u32> = (0..50).collect();
+    let mut buf = Vec::new();
+    values.encode(&mut buf).unwrap();
+    let mut plain = Vec::new();
+    for v in values.iter() {
+        v.encode(&mut plain).unwrap();
+    }
+    // Plain layout: header flag clear, elements follow with no run lengths.
+    assert_eq!(buf[0] & 1, 0);
+    assert_eq!(&buf[1..], &plain[..]);
+    let decoded: Rle<u32> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, values);
+}
+
+#[test]
+fn test_rle_empty_and_zero_run() {
+    let values = Rle::<u8>::new();
+    let mut buf = Vec::new();
+    values.encode(&mut buf).unwrap();
+    let decoded: Rle<u8> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert!(decoded.is_empty());
+
+    // One RLE run with a zero length is rejected.
+    let buf = [0b11u8, 5, 0];
+    let res: Result<Rle<u8>> = Decode::decode(&mut Cursor::new(&buf[..]));
+    assert!(matches!(res, Err(Error::InvalidData)));
+}
+
+#[test]
+fn test_rle_rejects_forged_run_length() {
+    // One run just past the cap, then a second run whose length overflows the total.
+    let mut buf = vec![0b11u8, 7];
+    Lencode::encode_varint_u64(MAX_RLE_DECODED_BYTES as u64 + 1, &mut buf).unwrap();
+    let res: Result<Rle<u8>> = Decode::decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::IncorrectLength)));
+
+    let mut buf = vec![0b101u8, 7, 1, 7];
+    Lencode::encode_varint_u64(usize::MAX as u64, &mut buf).unwrap();
+    let res: Result<Rle<u8>> = Decode::decode(&mut Cursor::new(&buf));
+    assert!(matches!(res, Err(Error::IncorrectLength)));
+}
+
+#[test]
+fn test_rle_keeps_values_that_compare_equal_but_encode_differently() {
+    let values = Rle(vec![0.0f64, -0.0, -0.0, 0.0, f64::NAN, f64::NAN]);
+    let mut buf = Vec::new();
+    values.encode(&mut buf).unwrap();
+    let decoded: Rle<f64> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    let bits = |v: &Rle<f64>| v.iter().map(|f| f.to_bits()).collect::<Vec<_>>();
+    assert_eq!(bits(&decoded), bits(&values));
+}
+
+#[test]
+fn test_plain_vec_u8_uses_element_layout() {
+    let values: PlainVec<u8> = vec![0u8; 100].into();
+    let mut buf = Vec::new();
+    let n = values.encode(&mut buf).unwrap();
+    // Element count (not a flagged byte length) followed by raw elements; no compression.
+    assert_eq!(n, 1 + 100);
+    assert_eq!(buf[0], 100);
+    let decoded: PlainVec<u8> = Decode::decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, values);
+
+    let mut specialized = Vec::new();
+    values.0.encode(&mut specialized).unwrap();
+    assert_ne!(specialized, buf);
+}
+
+#[test]
+fn test_plain_vec_matches_vec_for_other_types() {
+    let values: Vec<u32> = (0..20).map(|i| i * 1000).collect();
+    let mut plain = Vec::new();
+    PlainVec(values.clone()).encode(&mut plain).unwrap();
+    let mut vec = Vec::new();
+    values.encode(&mut vec).unwrap();
+    assert_eq!(plain, vec);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_raw_array_size_ignores_the_context() {
+    let hashes: Vec<RawArray<32>> = (0..4u8).map(|i| RawArray([i; 32])).collect();
+    let mut buf = Vec::new();
+    assert_eq!(encode(&hashes, &mut buf).unwrap(), 1 + 4 * 32);
+    assert_eq!(
+        decode::<Vec<RawArray<32>>>(&mut Cursor::new(&buf)).unwrap(),
+        hashes
+    );
+
+    // Under an active diff key a plain array shrinks to a delta; the wrapper does not.
+    let mut ctx = EncoderContext::with_diff();
+    let mut plain = Vec::new();
+    let mut raw = Vec::new();
+    for _ in 0..2 {
+        ctx.diff.as_mut().unwrap().set_key(1);
+        plain.clear();
+        encode_ext(&hashes[0].0, &mut plain, Some(&mut ctx)).unwrap();
+        raw.clear();
+        encode_ext(&hashes[0], &mut raw, Some(&mut ctx)).unwrap();
+    }
+    assert_ne!(plain.len(), 32);
+    assert_eq!(raw, hashes[0].0);
+
+    assert!(matches!(
+        decode::<RawArray<32>>(&mut Cursor::new(&buf[1..20])),
+        Err(Error::ReaderOutOfData)
+    ));
+    assert!(matches!(
+        RawArray::<4>::try_from(&[1u8, 2, 3][..]),
+        Err(Error::IncorrectLength)
+    ));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_byte_string_shares_the_string_layout() {
+    let text = "Program log: ".repeat(20);
+    let mut from_string = Vec::new();
+    encode(&text, &mut from_string).unwrap();
+    let mut from_bytes = Vec::new();
+    encode(&ByteString::from(text.as_str()), &mut from_bytes).unwrap();
+    assert_eq!(from_bytes, from_string);
+
+    let raw = ByteString::from(vec![b'o', b'k', 0xff, b'!']);
+    let mut buf = Vec::new();
+    encode(&raw, &mut buf).unwrap();
+    assert!(matches!(
+        String::decode(&mut Cursor::new(&buf)),
+        Err(Error::InvalidUtf8)
+    ));
+    let decoded: ByteString = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, raw);
+    assert_eq!(decoded.to_string(), "ok\u{fffd}!");
+    assert_eq!(format!("{decoded:?}"), r#"b"ok\xff!""#);
+}
+
+#[test]
+fn test_cached_reuses_bytes_until_mutated() {
+    use core::cell::Cell;
+
+    /// Counts how often it is encoded.
+    #[derive(Default)]
+    struct Expensive {
+        items: Vec<u64>,
+        encodes: Cell<usize>,
+    }
+
+    impl Encode for Expensive {
+        fn encode_ext(
+            &self,
+            writer: &mut impl Write,
+            ctx: Option<&mut EncoderContext>,
+        ) -> Result<usize> {
+            self.encodes.set(self.encodes.get() + 1);
+            self.items.encode_ext(writer, ctx)
+        }
+    }
+
+    let mut cached = Cached::new(Expensive {
+        items: (0..100).collect(),
+        ..Default::default()
+    });
+    let mut buf = Vec::new();
+    for _ in 0..3 {
+        encode(&cached, &mut buf).unwrap();
+    }
+    assert_eq!(cached.encodes.get(), 1);
+    let decoded: [Vec<u64>; 3] = decode(&mut Cursor::new(&buf)).unwrap();
+    assert!(decoded.iter().all(|items| *items == cached.items));
+
+    cached.items.push(100);
+    let mut buf = Vec::new();
+    encode(&cached, &mut buf).unwrap();
+    assert_eq!(cached.encodes.get(), 2);
+    assert_eq!(
+        decode::<Vec<u64>>(&mut Cursor::new(&buf)).unwrap().len(),
+        101
+    );
+
+    // Context state that changes the output bypasses the cache.
+    encode_ext(&cached, &mut buf, Some(&mut EncoderContext::with_dedupe())).unwrap();
+    encode_ext(&cached, &mut buf, Some(&mut EncoderContext::new())).unwrap();
+    assert_eq!(cached.encodes.get(), 3);
+    #[cfg(feature = "hooks")]
+    {
+        let mut ctx = EncoderContext::with_hooks(|_: &'static str, _: core::ops::Range<usize>| {});
+        encode_ext(&cached, &mut buf, Some(&mut ctx)).unwrap();
+        assert_eq!(cached.encodes.get(), 4);
+    }
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_compression_policy_reaches_cached_and_string_enum() {
+    const NAME: &str = "variant-variant-variant-variant-variant-variant-variant-variant-";
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    struct Long;
+
+    impl NamedVariants for Long {
+        fn variant_name(&self) -> &'static str {
+            NAME
+        }
+
+        fn from_variant_name(name: &str) -> Option<Self> {
+            (name == NAME).then_some(Long)
+        }
+    }
+
+    fn flag(value: &impl Encode, ctx: Option<&mut EncoderContext>) -> u64 {
+        let mut buf = Vec::new();
+        value.encode_ext(&mut buf, ctx).unwrap();
+        Lencode::decode_varint_u64(&mut Cursor::new(&buf)).unwrap() & 1
+    }
+
+    let cached = Cached::new(String::from(NAME));
+    let named = StringEnum(Long);
+    let mut never = EncoderContext::with_compression(CompressionPolicy::Never);
+    assert_eq!(flag(&cached, None), 1);
+    assert_eq!(flag(&cached, Some(&mut never)), 0);
+    assert_eq!(flag(&cached, None), 1);
+    assert_eq!(flag(&named, None), 1);
+    assert_eq!(flag(&named, Some(&mut never)), 0);
+
+    let mut buf = Vec::new();
+    named.encode_ext(&mut buf, Some(&mut never)).unwrap();
+    assert_eq!(
+        decode::<StringEnum<Long>>(&mut Cursor::new(&buf)).unwrap(),
+        named
+    );
+}
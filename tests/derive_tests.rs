@@ -7,7 +7,7 @@ pub struct Foo {
     pub c: [u64; 18],
 }
 
-#[derive(Encode, Decode, Debug, PartialEq)]
+#[derive(Encode, Decode, DescribeLayout, Debug, PartialEq)]
 pub enum Bar {
     A(u32),
     B { x: String, y: Vec<u8> },
@@ -171,3 +171,690 @@ fn test_derive_pack_transparent_dedupe_roundtrip() {
     let decoded: Vec<MyKey> = decode_ext(&mut Cursor::new(buf.as_slice()), Some(&mut dec)).unwrap();
     assert_eq!(keys, decoded);
 }
+
+#[test]
+fn test_derive_describe_layout_enum() {
+    let layout = layout_of::<Bar>();
+    let LayoutKind::Enum(variants) = &layout.kind else {
+        panic!("expected enum layout");
+    };
+    let names: Vec<_> = variants.iter().map(|v| (v.name, v.discriminant)).collect();
+    assert_eq!(names, [("A", 0), ("B", 1), ("C", 2)]);
+    assert_eq!(variants[1].fields[0].name, "x");
+    assert_eq!(variants[1].fields[1].layout.kind, LayoutKind::Bytes);
+    // `C` is a lone discriminant byte; `B` holds unbounded strings.
+    assert_eq!(layout.min_size, 1);
+    assert_eq!(layout.max_size, None);
+
+    let mut buf = Vec::new();
+    encode(&Bar::C, &mut buf).unwrap();
+    assert_eq!(buf.len(), layout.min_size);
+}
+
+#[derive(Encode, Decode, DescribeLayout, Debug, PartialEq)]
+#[lencode(rename = "Transfer")]
+pub enum TransferV2 {
+    #[lencode(rename = "Native")]
+    Lamports {
+        #[lencode(rename = "amount")]
+        lamports: u64,
+    },
+    Token(u64),
+}
+
+#[test]
+fn test_derive_describe_layout_rename() {
+    let layout = layout_of::<TransferV2>();
+    assert_eq!(layout.type_name, "Transfer");
+    let LayoutKind::Enum(variants) = &layout.kind else {
+        panic!("expected enum layout");
+    };
+    assert_eq!(variants[0].name, "Native");
+    assert_eq!(variants[0].fields[0].name, "amount");
+    assert_eq!(variants[1].name, "Token");
+
+    // Renames only touch the layout: the wire format is the derived one.
+    let value = TransferV2::Lamports { lamports: 5 };
+    let mut buf = Vec::new();
+    encode(&value, &mut buf).unwrap();
+    assert_eq!(buf, [0, 5]);
+    assert_eq!(decode::<TransferV2>(&mut Cursor::new(&buf)).unwrap(), value);
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub struct LegacyHeader {
+    pub version: u8,
+    #[lencode(len = "u16_fixed")]
+    pub name: String,
+    #[lencode(len = "u32_fixed")]
+    pub payload: Vec<u8>,
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub enum LegacyMessage {
+    Ping,
+    Data(#[lencode(len = "u8_fixed")] Vec<u16>),
+}
+
+#[test]
+fn test_derive_len_strategy_roundtrip() {
+    let header = LegacyHeader {
+        version: 2,
+        name: "node".into(),
+        payload: vec![9, 8, 7],
+    };
+    let mut buf = Vec::new();
+    encode(&header, &mut buf).unwrap();
+    assert_eq!(buf, [2, 4, 0, b'n', b'o', b'd', b'e', 3, 0, 0, 0, 9, 8, 7]);
+    let decoded: LegacyHeader = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, header);
+
+    let msg = LegacyMessage::Data(vec![1, 2]);
+    let mut buf = Vec::new();
+    encode(&msg, &mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 1, 2]);
+    let decoded: LegacyMessage = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, msg);
+}
+
+#[derive(Encode)]
+pub struct MetricsInner {
+    pub id: u64,
+    pub tags: Vec<String>,
+}
+
+#[derive(Encode)]
+pub enum MetricsOuter {
+    Empty,
+    Full {
+        inner: MetricsInner,
+        payload: Vec<u8>,
+    },
+}
+
+#[test]
+fn test_derive_records_encode_metrics() {
+    let value = MetricsOuter::Full {
+        inner: MetricsInner {
+            id: 300,
+            tags: vec!["a".into(), "bc".into()],
+        },
+        payload: (0..40u8).map(|i| i.wrapping_mul(97)).collect(),
+    };
+    let mut ctx = EncoderContext::with_metrics();
+    let mut buf = Vec::new();
+    let written = value.encode_ext(&mut buf, Some(&mut ctx)).unwrap();
+    value.encode_ext(&mut buf, Some(&mut ctx)).unwrap();
+
+    let metrics = ctx.metrics.as_ref().unwrap();
+    let outer = core::any::type_name::<MetricsOuter>();
+    let inner = core::any::type_name::<MetricsInner>();
+    // Everything but the one-byte variant discriminant is attributed to a field.
+    assert_eq!(metrics.type_bytes(outer), 2 * (written as u64 - 1));
+    assert_eq!(metrics.get(inner, "id").unwrap().count, 2);
+    assert_eq!(
+        metrics.get(outer, "Full.inner").unwrap().bytes,
+        metrics.type_bytes(inner)
+    );
+    assert_eq!(metrics.by_bytes()[0].1, "Full.payload");
+
+    // Recording metrics does not change the output.
+    let mut plain = Vec::new();
+    encode(&value, &mut plain).unwrap();
+    assert_eq!(&buf[..written], &plain[..]);
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub struct Names {
+    pub names: [String; 4],
+    pub grid: [[String; 2]; 2],
+    pub bytes: [u8; 3],
+}
+
+#[cfg(feature = "std")]
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub struct BorrowedArray<'a> {
+    pub values: [std::borrow::Cow<'a, u64>; 2],
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub enum Labels {
+    Pair([String; 2]),
+    Tagged { tags: [Option<String>; 3] },
+}
+
+#[test]
+fn test_derive_non_copy_arrays_roundtrip() {
+    let value = Names {
+        names: ["a".into(), "bb".into(), String::new(), "dddd".into()],
+        grid: [["x".into(), "y".into()], ["z".into(), "w".into()]],
+        bytes: [1, 2, 3],
+    };
+    let mut buf = Vec::new();
+    let written = encode(&value, &mut buf).unwrap();
+    assert_eq!(written, buf.len());
+    let decoded: Names = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, value);
+
+    // The inline expansion writes exactly what the `[T; N]` impl does.
+    let mut expected = Vec::new();
+    encode(&value.names, &mut expected).unwrap();
+    encode(&value.grid, &mut expected).unwrap();
+    encode(&value.bytes, &mut expected).unwrap();
+    assert_eq!(buf, expected);
+
+    for value in [
+        Labels::Pair(["p".into(), "q".into()]),
+        Labels::Tagged {
+            tags: [Some("t".into()), None, Some("u".into())],
+        },
+    ] {
+        let mut buf = Vec::new();
+        encode(&value, &mut buf).unwrap();
+        assert_eq!(decode::<Labels>(&mut Cursor::new(&buf)).unwrap(), value);
+    }
+
+    // A truncated array drops the elements decoded so far and reports the error.
+    let mut buf = Vec::new();
+    encode(&Labels::Pair(["p".into(), "q".into()]), &mut buf).unwrap();
+    buf.pop();
+    assert!(decode::<Labels>(&mut Cursor::new(&buf)).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_derive_borrowed_array_roundtrip() {
+    let shared = 300u64;
+    let value = BorrowedArray {
+        values: [
+            std::borrow::Cow::Owned(7),
+            std::borrow::Cow::Borrowed(&shared),
+        ],
+    };
+    let mut buf = Vec::new();
+    encode(&value, &mut buf).unwrap();
+    let mut expected = Vec::new();
+    encode(&[7u64, 300], &mut expected).unwrap();
+    assert_eq!(buf, expected);
+    assert_eq!(
+        decode::<BorrowedArray>(&mut Cursor::new(&buf)).unwrap(),
+        value
+    );
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+#[lencode(index_stable = "Pending, Active, Closed")]
+pub enum AccountState {
+    Pending,
+    Active,
+    Closed,
+    // Appended after the frozen list, so existing indices are unchanged.
+    Archived,
+}
+
+#[test]
+fn test_unit_enum_without_repr_uses_variant_index() {
+    let states = [
+        AccountState::Pending,
+        AccountState::Active,
+        AccountState::Closed,
+        AccountState::Archived,
+    ];
+    for (idx, state) in states.into_iter().enumerate() {
+        let mut buf = Vec::new();
+        encode(&state, &mut buf).unwrap();
+        let mut expected = Vec::new();
+        usize::encode_discriminant(idx, &mut expected).unwrap();
+        assert_eq!(buf, expected);
+        assert_eq!(
+            decode::<AccountState>(&mut Cursor::new(&buf)).unwrap(),
+            state
+        );
+    }
+}
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq)]
+#[repr(i64)]
+pub enum Marker {
+    Low = -1,
+    High = 1 << 40,
+}
+
+#[test]
+fn test_unit_enum_with_64_bit_repr_keeps_full_discriminant() {
+    for (marker, disc) in [(Marker::Low, u64::MAX), (Marker::High, 1 << 40)] {
+        let mut buf = Vec::new();
+        encode(&marker, &mut buf).unwrap();
+        let mut expected = Vec::new();
+        Lencode::encode_varint_u64(disc, &mut expected).unwrap();
+        assert_eq!(buf, expected);
+        assert_eq!(decode::<Marker>(&mut Cursor::new(&buf)).unwrap(), marker);
+    }
+    assert!(matches!(
+        decode::<Marker>(&mut Cursor::new(&[7u8][..])),
+        Err(lencode::io::Error::UnknownDiscriminant(7))
+    ));
+    assert!(matches!(
+        decode::<AccountState>(&mut Cursor::new(&[4u8][..])),
+        Err(lencode::io::Error::UnknownDiscriminant(4))
+    ));
+}
+
+#[derive(Encode, Decode, Delta, Clone, Debug, PartialEq)]
+pub struct AccountUpdate {
+    pub lamports: u64,
+    pub owner: [u8; 32],
+    pub data: Vec<u8>,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub tags: [String; 2],
+    pub slot: u64,
+    pub write_version: u64,
+    #[lencode(len = "u16_fixed")]
+    pub memo: Vec<u8>,
+}
+
+#[derive(Delta, Clone, Debug, PartialEq)]
+pub struct Pair<T>(pub T, pub u8);
+
+#[test]
+fn test_derive_delta_encodes_changed_fields_only() {
+    let old = AccountUpdate {
+        lamports: 1_000,
+        owner: [3; 32],
+        data: vec![9; 200],
+        executable: false,
+        rent_epoch: 361,
+        tags: [String::from("a"), String::from("b")],
+        slot: 7,
+        write_version: 1,
+        memo: vec![1, 2],
+    };
+
+    let mut buf = Vec::new();
+    assert_eq!(encode_delta(&old, &old, &mut buf).unwrap(), 2);
+    assert_eq!(buf, [0, 0]);
+
+    let mut new = old.clone();
+    new.lamports = 2_000;
+    buf.clear();
+    let delta_len = encode_delta(&old, &new, &mut buf).unwrap();
+    // Bitmap (2 bytes) plus the varint lamports.
+    assert_eq!(delta_len, 2 + 3);
+    assert_eq!(buf.len(), delta_len);
+
+    let mut replica = old.clone();
+    apply_delta(&mut replica, &mut Cursor::new(&buf)).unwrap();
+    assert_eq!(replica, new);
+
+    // The ninth field lands in the second bitmap byte and keeps its length strategy.
+    new.tags[1].push('c');
+    new.memo = vec![4; 3];
+    buf.clear();
+    encode_delta(&old, &new, &mut buf).unwrap();
+    assert_eq!(&buf[..2], &[0b0010_0001, 0b0000_0001]);
+    assert_eq!(&buf[buf.len() - 5..], &[3, 0, 4, 4, 4]);
+    let mut replica = old.clone();
+    apply_delta(&mut replica, &mut Cursor::new(&buf)).unwrap();
+    assert_eq!(replica, new);
+
+    let mut pair = Pair(5u32, 1);
+    buf.clear();
+    encode_delta(&pair, &Pair(5, 2), &mut buf).unwrap();
+    apply_delta(&mut pair, &mut Cursor::new(&buf)).unwrap();
+    assert_eq!(pair, Pair(5, 2));
+}
+
+#[derive(Encode, Decode, Project, Debug, PartialEq)]
+pub struct StatusMeta {
+    pub err: Option<String>,
+    pub fee: u64,
+    pub pre_balances: Vec<u64>,
+    pub log_messages: Vec<String>,
+    #[lencode(len = "u16_fixed")]
+    pub return_data: Vec<u8>,
+    pub signers: [String; 2],
+    pub compute_units: u64,
+}
+
+#[derive(Encode, Decode, Project)]
+pub struct Wrapped<T>(pub T, pub u8);
+
+#[test]
+fn test_derive_projection_skips_unselected_fields() {
+    let meta = StatusMeta {
+        err: None,
+        fee: 5_000,
+        pre_balances: vec![10, 20, 30],
+        log_messages: vec![String::from("Program log: Instruction: Transfer"); 40],
+        return_data: vec![1, 2, 3],
+        signers: [String::from("a"), String::from("b")],
+        compute_units: 1_400,
+    };
+    let mut buf = Vec::new();
+    encode(&meta, &mut buf).unwrap();
+
+    let mask = FieldMask::of::<StatusMeta>(&["fee", "compute_units"]).unwrap();
+    let partial = decode_projection::<StatusMeta>(&mut Cursor::new(&buf), &mask).unwrap();
+    assert_eq!(partial.err, None);
+    assert_eq!(partial.fee, Some(5_000));
+    assert_eq!(partial.log_messages, None);
+    assert_eq!(partial.return_data, None);
+    assert_eq!(partial.signers, None);
+    assert_eq!(partial.compute_units, Some(1_400));
+
+    let all =
+        decode_projection::<StatusMeta>(&mut Cursor::new(&buf), &FieldMask::all::<StatusMeta>())
+            .unwrap();
+    assert_eq!(all.err, Some(None));
+    assert_eq!(all.return_data, Some(vec![1, 2, 3]));
+    assert_eq!(all.signers, Some(meta.signers.clone()));
+    assert!(FieldMask::of::<StatusMeta>(&["status"]).is_err());
+
+    let mut buf = Vec::new();
+    encode(&Wrapped(String::from("skip me"), 4), &mut buf).unwrap();
+    let mask = FieldMask::of::<Wrapped<String>>(&["1"]).unwrap();
+    let partial = decode_projection::<Wrapped<String>>(&mut Cursor::new(&buf), &mask).unwrap();
+    assert_eq!((partial.0, partial.1), (None, Some(4)));
+}
+
+#[derive(Encode, Decode, Columns, Clone, Debug, PartialEq)]
+pub struct Trade {
+    pub slot: u64,
+    pub price: i64,
+    pub venue: String,
+    #[lencode(len = "u16_fixed")]
+    pub memo: Vec<u8>,
+    pub mint: [u8; 4],
+}
+
+#[derive(Columns, Clone, Debug, PartialEq)]
+pub struct Sample<T>(pub T, pub bool);
+
+#[test]
+fn test_derive_columns_roundtrip_and_size() {
+    let trades: Vec<Trade> = (0..400)
+        .map(|i| Trade {
+            slot: 250_000_000 + i / 8,
+            price: 1_000_000 - (i as i64 % 3),
+            venue: String::from(["raydium", "orca"][i as usize % 2]),
+            memo: vec![],
+            mint: [i as u8 % 4; 4],
+        })
+        .collect();
+    let mut rows = Vec::new();
+    encode(&trades, &mut rows).unwrap();
+    let mut columns = Vec::new();
+    let n = encode(&Columnar(trades.clone()), &mut columns).unwrap();
+    assert_eq!(n, columns.len());
+    // Columns only shrink this much once each one is compressed.
+    #[cfg(feature = "zstd")]
+    assert!(columns.len() * 4 < rows.len());
+    let decoded: Columnar<Vec<Trade>> = decode(&mut Cursor::new(&columns)).unwrap();
+    assert_eq!(decoded.0, trades);
+
+    let samples = Columnar(vec![
+        Sample(String::from("a"), true),
+        Sample(String::new(), false),
+    ]);
+    let mut buf = Vec::new();
+    encode(&samples, &mut buf).unwrap();
+    assert_eq!(
+        decode::<Columnar<Vec<Sample<String>>>>(&mut Cursor::new(&buf)).unwrap(),
+        samples
+    );
+}
+
+struct Reversed;
+
+impl Codec<String> for Reversed {
+    fn encode(
+        &self,
+        value: &String,
+        mut writer: &mut dyn Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        let reversed: String = value.chars().rev().collect();
+        reversed.encode_ext(&mut writer, ctx)
+    }
+
+    fn decode(
+        &self,
+        mut reader: &mut dyn Read,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<String> {
+        Ok(String::decode_ext(&mut reader, ctx)?
+            .chars()
+            .rev()
+            .collect())
+    }
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub struct Labeled {
+    #[lencode(codec = "reversed")]
+    pub label: String,
+    pub plain: String,
+}
+
+#[test]
+fn test_derive_codec_field_uses_registry() {
+    let codecs = std::sync::Arc::new(CodecRegistry::new().with::<String>("reversed", Reversed));
+    let value = Labeled {
+        label: String::from("abc"),
+        plain: String::from("xyz"),
+    };
+    let mut buf = Vec::new();
+    let mut ctx = EncoderContext::with_codecs(codecs.clone());
+    value.encode_ext(&mut buf, Some(&mut ctx)).unwrap();
+    assert_eq!(&buf[1..4], b"cba");
+    assert_eq!(&buf[5..], b"xyz");
+    let mut ctx = DecoderContext::with_codecs(codecs);
+    assert_eq!(
+        Labeled::decode_ext(&mut Cursor::new(&buf), Some(&mut ctx)).unwrap(),
+        value
+    );
+
+    // Without a registry the field keeps its regular layout.
+    let mut plain = Vec::new();
+    encode(&value, &mut plain).unwrap();
+    assert_eq!(&plain[1..4], b"abc");
+    assert_eq!(decode::<Labeled>(&mut Cursor::new(&plain)).unwrap(), value);
+}
+
+/// Encodes a foreign `Ipv4Addr` as its `u32` form.
+mod ipv4 {
+    use lencode::prelude::*;
+    use std::net::Ipv4Addr;
+
+    pub fn encode_ext(
+        value: &Ipv4Addr,
+        writer: &mut impl Write,
+        ctx: Option<&mut EncoderContext>,
+    ) -> Result<usize> {
+        u32::from(*value).encode_ext(writer, ctx)
+    }
+
+    pub fn decode_ext(
+        reader: &mut impl Read,
+        ctx: Option<&mut DecoderContext>,
+    ) -> Result<Ipv4Addr> {
+        Ok(Ipv4Addr::from(u32::decode_ext(reader, ctx)?))
+    }
+}
+
+#[derive(Encode, Decode, Project, Debug, PartialEq)]
+pub struct Peer {
+    #[lencode(with = "ipv4")]
+    pub addr: std::net::Ipv4Addr,
+    #[lencode(with = "ipv4", length_prefixed)]
+    pub gateway: std::net::Ipv4Addr,
+    pub port: u16,
+}
+
+#[test]
+fn test_derive_with_module_encodes_foreign_fields() {
+    let peer = Peer {
+        addr: std::net::Ipv4Addr::new(0, 0, 1, 44),
+        gateway: std::net::Ipv4Addr::new(0, 0, 0, 1),
+        port: 8080,
+    };
+    let mut buf = Vec::new();
+    encode(&peer, &mut buf).unwrap();
+    let mut expected = Vec::new();
+    encode(&300u32, &mut expected).unwrap();
+    expected.extend([1, 1]);
+    encode(&8080u16, &mut expected).unwrap();
+    assert_eq!(buf, expected);
+    assert_eq!(decode::<Peer>(&mut Cursor::new(&buf)).unwrap(), peer);
+
+    let mask = FieldMask::of::<Peer>(&["port"]).unwrap();
+    let partial = decode_projection::<Peer>(&mut Cursor::new(&buf), &mask).unwrap();
+    assert_eq!(partial.addr, None);
+    assert_eq!(partial.port, Some(8080));
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+#[lencode(fuzz)]
+pub enum FuzzedMessage {
+    Ping,
+    Transfer { slot: u64, memo: Option<String> },
+    Batch(Vec<u32>),
+}
+
+#[test]
+fn test_fuzz_attribute_roundtrips_decoded_values() {
+    // `#[lencode(fuzz)]` also generates the `lencode_fuzz_FuzzedMessage` test.
+    let data = [1, 5, 1, 4, b'h', b'i'];
+    assert_eq!(
+        decode::<FuzzedMessage>(&mut Cursor::new(&data)).unwrap(),
+        FuzzedMessage::Transfer {
+            slot: 5,
+            memo: Some(String::from("hi"))
+        }
+    );
+    lencode::fuzz::fuzz_decode::<FuzzedMessage>(&data);
+}
+
+#[derive(Encode, Decode, Project, Debug, PartialEq)]
+pub struct Envelope {
+    pub kind: u8,
+    #[lencode(length_prefixed)]
+    pub body: Vec<String>,
+    #[lencode(length_prefixed, len = "u16_fixed")]
+    pub tags: Vec<u16>,
+    pub trailer: u32,
+}
+
+#[test]
+fn test_derive_length_prefixed_fields() {
+    let envelope = Envelope {
+        kind: 2,
+        body: vec![String::from("a"), String::from("bc")],
+        tags: vec![1, 2],
+        trailer: 99,
+    };
+    let mut buf = Vec::new();
+    encode(&envelope, &mut buf).unwrap();
+    // kind, body size, body; tags size, fixed u16 count, tags; trailer.
+    assert_eq!(&buf[..3], &[2, 6, 2]);
+    assert_eq!(&buf[8..12], &[4, 2, 0, 1]);
+    assert_eq!(
+        decode::<Envelope>(&mut Cursor::new(&buf)).unwrap(),
+        envelope
+    );
+
+    let mask = FieldMask::of::<Envelope>(&["trailer"]).unwrap();
+    let partial = decode_projection::<Envelope>(&mut Cursor::new(&buf), &mask).unwrap();
+    assert_eq!(partial.body, None);
+    assert_eq!(partial.trailer, Some(99));
+
+    // Sections are checked to hold exactly the field's bytes.
+    buf[1] = 7;
+    assert!(decode::<Envelope>(&mut Cursor::new(&buf)).is_err());
+}
+
+#[derive(NamedVariants, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Level {
+    Low,
+    High,
+    #[lencode(rename = "mid")]
+    Medium,
+}
+
+#[test]
+fn test_string_enum_encodes_names_and_dedupes() {
+    let levels: Vec<StringEnum<Level>> = [Level::High, Level::Medium, Level::High, Level::High]
+        .map(StringEnum)
+        .to_vec();
+    let mut buf = Vec::new();
+    encode(&levels, &mut buf).unwrap();
+    assert_eq!(&buf[..6], b"\x04\x08High");
+    let decoded: Vec<StringEnum<Level>> = decode(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(decoded, levels);
+
+    let mut ctx = EncoderContext::with_dedupe();
+    let mut deduped = Vec::new();
+    encode_ext(&levels, &mut deduped, Some(&mut ctx)).unwrap();
+    assert!(deduped.len() < buf.len());
+    let mut ctx = DecoderContext::with_dedupe();
+    let decoded: Vec<StringEnum<Level>> =
+        decode_ext(&mut Cursor::new(&deduped), Some(&mut ctx)).unwrap();
+    assert_eq!(decoded, levels);
+
+    let mut unknown = Vec::new();
+    "Extreme".encode(&mut unknown).unwrap();
+    assert!(matches!(
+        decode::<StringEnum<Level>>(&mut Cursor::new(&unknown)),
+        Err(Error::InvalidData)
+    ));
+    assert_eq!(Level::from_variant_name("mid"), Some(Level::Medium));
+    assert_eq!(Level::from_variant_name("Medium"), None);
+}
+
+/// Version 2 of a message: `Resize` was inserted and `Close` moved, without changing the
+/// discriminants already on the wire.
+#[derive(Encode, Decode, DescribeLayout, Debug, PartialEq)]
+pub enum Command {
+    #[lencode(discriminant = 0)]
+    Open {
+        path: String,
+    },
+    #[lencode(discriminant = 7)]
+    Resize(u32, u32),
+    #[lencode(discriminant = 2)]
+    Close,
+    Write(Vec<u8>),
+}
+
+#[test]
+fn test_explicit_variant_discriminants_survive_reordering() {
+    let mut buf = Vec::new();
+    encode(&Command::Close, &mut buf).unwrap();
+    encode(&Command::Resize(80, 24), &mut buf).unwrap();
+    encode(&Command::Write(vec![1]), &mut buf).unwrap();
+    assert_eq!(&buf[..3], &[2, 7, 80]);
+    assert_eq!(buf[4], 3);
+
+    let mut reader = Cursor::new(&buf);
+    assert_eq!(decode::<Command>(&mut reader).unwrap(), Command::Close);
+    assert_eq!(
+        decode::<Command>(&mut reader).unwrap(),
+        Command::Resize(80, 24)
+    );
+    assert_eq!(
+        decode::<Command>(&mut reader).unwrap(),
+        Command::Write(vec![1])
+    );
+    assert!(matches!(
+        decode::<Command>(&mut Cursor::new(&[1u8])),
+        Err(Error::UnknownDiscriminant(1))
+    ));
+
+    let layout = layout_of::<Command>();
+    let LayoutKind::Enum(variants) = &layout.kind else {
+        panic!("expected enum layout");
+    };
+    let discs: Vec<_> = variants.iter().map(|v| v.discriminant).collect();
+    assert_eq!(discs, [0, 7, 2, 3]);
+}
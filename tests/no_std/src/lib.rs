@@ -0,0 +1,163 @@
+//! An alloc‑only consumer of lencode, so CI can check that `default-features = false`, alone
+//! or with any one of the features that do not imply `std`, builds for a target without
+//! `std` (`cargo build --target thumbv7em-none-eabihf --features <feature>`).
+//!
+//! The `const` blocks below fail to compile if an impl the alloc‑only surface promises goes
+//! missing or moves behind `std`; the functions exercise the rest of the API.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
+use lencode::prelude::*;
+
+/// Compiles only if `T` both encodes and decodes.
+const fn codec<T: Encode + Decode>() {}
+
+/// Compiles only if `T` can go through a dedupe table.
+const fn dedupe<T: DedupeEncodeable + DedupeDecodeable>() {}
+
+const _: () = {
+    codec::<u128>();
+    codec::<U256>();
+    codec::<String>();
+    codec::<Vec<u8>>();
+    codec::<Option<Vec<String>>>();
+    codec::<VecDeque<u8>>();
+    codec::<BTreeMap<u32, String>>();
+    codec::<BTreeSet<u64>>();
+    codec::<BinaryHeap<u64>>();
+    codec::<LinkedList<u16>>();
+    codec::<Cow<'static, str>>();
+    codec::<Cow<'static, [u8; 4]>>();
+    codec::<Timestamp>();
+    dedupe::<Cow<'static, str>>();
+};
+
+// `Error` is a `core::error::Error` without `std`.
+const _: fn(&Error) -> &dyn core::error::Error = |err| err;
+
+/// A message with nesting, collections and fixed‑size keys.
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub struct Reading {
+    pub sensor: [u8; 8],
+    pub at: Timestamp,
+    pub label: Cow<'static, str>,
+    pub samples: Vec<i32>,
+    pub tags: BTreeMap<String, u16>,
+}
+
+/// Encodes `readings` with a dedupe table into `buf`.
+pub fn encode_readings(readings: &[Reading], buf: &mut Vec<u8>) -> Result<usize> {
+    let mut ctx = EncoderContext::with_dedupe();
+    encode_ext(readings, buf, Some(&mut ctx))
+}
+
+/// Decodes what [`encode_readings`] wrote, with a bounded nesting depth.
+pub fn decode_readings(buf: &[u8]) -> Result<Vec<Reading>> {
+    let mut ctx = DecoderContext::with_dedupe();
+    ctx.max_depth = 16;
+    decode_ext(&mut Cursor::new(buf), Some(&mut ctx))
+}
+
+/// Encodes into a fixed buffer, as on targets without an allocator for the output.
+pub fn encode_into_slice(value: &Reading, out: &mut [u8]) -> Result<usize> {
+    encode(value, &mut Cursor::new(out))
+}
+
+#[cfg(feature = "hooks")]
+pub fn hooked_context() -> EncoderContext {
+    EncoderContext::with_hooks(|_: &'static str, _: core::ops::Range<usize>| {})
+}
+
+#[cfg(feature = "bumpalo")]
+pub fn decode_labels_in<'a>(
+    buf: &[u8],
+    bump: &'a bumpalo::Bump,
+) -> Result<bumpalo::collections::Vec<'a, &'a str>> {
+    decode_in(&mut Cursor::new(buf), bump)
+}
+
+#[cfg(feature = "embedded-io")]
+pub fn decode_from_device<D: embedded_io::Read>(device: D) -> Result<Reading> {
+    decode(&mut EmbeddedIo(device))
+}
+
+#[cfg(feature = "defmt")]
+const _: () = {
+    const fn format<T: defmt::Format>() {}
+    format::<Error>();
+};
+
+#[cfg(feature = "postcard")]
+pub fn postcard_to_lencode(bytes: &[u8], out: &mut Vec<u8>) -> Result<(usize, usize)> {
+    lencode::transcode::from_postcard::<Vec<u32>>(bytes, out, None)
+}
+
+#[cfg(feature = "enumset")]
+#[derive(enumset::EnumSetType, Debug)]
+pub enum Flag {
+    Ready,
+    Error,
+}
+
+#[cfg(feature = "enumset")]
+const _: () = codec::<enumset::EnumSet<Flag>>();
+
+#[cfg(feature = "enum-map")]
+#[derive(enum_map::Enum, Debug)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+#[cfg(feature = "enum-map")]
+const _: () = codec::<enum_map::EnumMap<Axis, i16>>();
+
+#[cfg(feature = "indexmap")]
+const _: () = {
+    type Map = indexmap::IndexMap<u32, String, hashbrown::DefaultHashBuilder>;
+    codec::<Map>();
+};
+
+#[cfg(feature = "petgraph")]
+const _: () = {
+    codec::<petgraph::Graph<u8, u16>>();
+    codec::<petgraph::stable_graph::StableGraph<u8, u16>>();
+};
+
+#[cfg(feature = "time")]
+pub fn to_offset_date_time(at: Timestamp) -> Result<time::OffsetDateTime> {
+    at.try_into()
+}
+
+#[cfg(feature = "chrono")]
+pub fn to_chrono(at: Timestamp) -> Result<chrono::DateTime<chrono::Utc>> {
+    at.try_into()
+}
+
+#[test]
+fn test_readings_roundtrip_without_std() {
+    let reading = |i: u8| Reading {
+        sensor: [i; 8],
+        at: Timestamp::from_unix_nanos(1_700_000_000_000_000_000 + i as i128),
+        label: Cow::Borrowed("thermistor"),
+        samples: alloc::vec![-3, 0, 21, i as i32],
+        tags: BTreeMap::from([(String::from("room"), i as u16)]),
+    };
+    let readings: Vec<Reading> = (0..4).map(reading).collect();
+    let mut buf = Vec::new();
+    let n = encode_readings(&readings, &mut buf).unwrap();
+    assert_eq!(n, buf.len());
+    assert_eq!(decode_readings(&buf).unwrap(), readings);
+
+    let mut out = [0u8; 64];
+    let n = encode_into_slice(&readings[0], &mut out).unwrap();
+    assert_eq!(
+        decode::<Reading>(&mut Cursor::new(&out[..n])).unwrap(),
+        readings[0]
+    );
+}
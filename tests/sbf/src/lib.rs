@@ -0,0 +1,66 @@
+//! A Solana program that decodes a lencode instruction, so CI can check that the decode
+//! paths build for SBF without `std` or zstd and stay within its 4 KB stack frames
+//! (`cargo build-sbf` reports frames that overflow).
+use lencode::prelude::*;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+/// Instruction payload with nesting, collections and fixed‑size keys.
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub struct Transfer {
+    pub from: [u8; 32],
+    pub to: [u8; 32],
+    pub amount: u64,
+    pub memo: Option<String>,
+    pub route: Vec<Hop>,
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub enum Hop {
+    Direct,
+    Pool { pool: [u8; 32], fee_bps: u16 },
+}
+
+solana_program::entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let mut ctx = DecoderContext::new();
+    // Each nesting level costs a few call frames, and SBF allows 64 in total.
+    ctx.max_depth = 16;
+    let transfer: Transfer = decode_ext(&mut Cursor::new(data), Some(&mut ctx))
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    if transfer.amount == 0 || transfer.route.is_empty() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_process_instruction_decodes_transfer() {
+    let transfer = Transfer {
+        from: [1; 32],
+        to: [2; 32],
+        amount: 5,
+        memo: Some(String::from("rent")),
+        route: vec![
+            Hop::Direct,
+            Hop::Pool {
+                pool: [3; 32],
+                fee_bps: 30,
+            },
+        ],
+    };
+    let mut data = Vec::new();
+    encode(&transfer, &mut data).unwrap();
+    assert_eq!(process_instruction(&Pubkey::default(), &[], &data), Ok(()));
+    assert_eq!(
+        process_instruction(&Pubkey::default(), &[], &data[..data.len() - 1]),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}